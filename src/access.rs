@@ -0,0 +1,143 @@
+//! Why a folder could not be opened: walks the path from the top for the
+//! first folder the user may not enter, or the folder itself when it may
+//! not be read, and says who owns it and what its permissions keep them
+//! from, for the panel shown in place of the listing.
+
+use std::path::{Path, PathBuf};
+
+use crate::i18n::{tr, trf};
+
+/// The folder in the way.
+#[derive(Debug, Clone)]
+pub struct Blocked {
+    pub dir: PathBuf,
+    pub owner: String,
+    pub group: String,
+    pub mode: u32,
+    /// Whose permissions apply to the user.
+    pub class: Class,
+    /// What is not allowed: listing it, entering it or both.
+    pub read: bool,
+    pub enter: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Owner,
+    Group,
+    Others,
+}
+
+impl Blocked {
+    /// What keeps the user out of `target`, in a few sentences.
+    pub fn explanation(&self, target: &Path) -> String {
+        let missing = match (self.read, self.enter) {
+            (true, true) => tr("neither list nor enter"),
+            (true, false) => tr("not list"),
+            _ => tr("not enter"),
+        };
+        let whom = match self.class {
+            Class::Owner => tr("its owner"),
+            Class::Group => tr("members of its group"),
+            Class::Others => tr("everyone else"),
+        };
+        let mut explanation = match self.dir == target {
+            true => trf(
+                "Its permissions, {}, let {} {} it.",
+                &[&crate::properties::mode_string(self.mode), &whom, &missing],
+            ),
+            false => trf(
+                "It is inside {}, whose permissions, {}, let {} {} it.",
+                &[
+                    &self.dir.display(),
+                    &crate::properties::mode_string(self.mode),
+                    &whom,
+                    &missing,
+                ],
+            ),
+        };
+        explanation.push(' ');
+        explanation.push_str(&match self.class {
+            Class::Owner => trf(
+                "It belongs to you, in the group {}: change its permissions to get in.",
+                &[&self.group],
+            ),
+            _ => trf(
+                "It belongs to {}, in the group {}: ask them, or open it as administrator.",
+                &[&self.owner, &self.group],
+            ),
+        });
+        explanation
+    }
+}
+
+/// What blocks the way to `dir`, if permissions do; meant for after it
+/// failed to be listed.
+#[cfg(unix)]
+pub fn diagnose(dir: &Path) -> Option<Blocked> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    use crate::properties::{group_name, user_name};
+
+    let may = |path: &Path, mode| {
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+            return true;
+        };
+        // SAFETY: `path` is NUL-terminated and outlives the call.
+        unsafe { libc::access(path.as_ptr(), mode) == 0 }
+    };
+
+    let mut chain: Vec<&Path> = dir.ancestors().collect();
+    chain.reverse();
+    chain.into_iter().find_map(|path| {
+        let enter = !may(path, libc::X_OK);
+        let read = path == dir && !may(path, libc::R_OK);
+        if !enter && !read {
+            return None;
+        }
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Blocked {
+            dir: path.to_path_buf(),
+            owner: user_name(meta.uid()).unwrap_or_else(|| meta.uid().to_string()),
+            group: group_name(meta.gid()).unwrap_or_else(|| meta.gid().to_string()),
+            mode: meta.mode() & 0o7777,
+            class: class(meta.uid(), meta.gid()),
+            read,
+            enter,
+        })
+    })
+}
+
+/// Only Unix permissions are walked.
+#[cfg(not(unix))]
+pub fn diagnose(_dir: &Path) -> Option<Blocked> {
+    None
+}
+
+/// Which of the permission bits of an entry owned by `uid` and `gid`
+/// apply to this process.
+#[cfg(unix)]
+fn class(uid: u32, gid: u32) -> Class {
+    // SAFETY: no preconditions.
+    if unsafe { libc::geteuid() } == uid {
+        return Class::Owner;
+    }
+
+    // SAFETY: asked for their number first, the groups fill a buffer that
+    // holds that many.
+    let groups = unsafe {
+        let count = libc::getgroups(0, std::ptr::null_mut());
+        let mut groups = vec![0; count.max(0) as usize];
+        let count = libc::getgroups(count, groups.as_mut_ptr());
+        groups.truncate(count.max(0) as usize);
+        groups
+    };
+    // SAFETY: no preconditions.
+    if unsafe { libc::getegid() } == gid || groups.contains(&gid) {
+        Class::Group
+    } else {
+        Class::Others
+    }
+}
@@ -0,0 +1,175 @@
+//! The WebDAV servers folders are signed in to, such as a Nextcloud or
+//! ownCloud, each by the address of its top folder. They show in the
+//! sidebar's Remote section.
+
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::tr;
+use crate::style::Style;
+use crate::webdav::Account;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Url(String),
+    User(String),
+    Password(String),
+    Add,
+    Remove(usize),
+    Close,
+}
+
+pub enum Event {
+    /// The accounts as they now are, to keep in the settings.
+    Changed(Vec<Account>),
+    Closed,
+}
+
+pub struct Accounts {
+    accounts: Vec<Account>,
+    url: String,
+    user: String,
+    password: String,
+    error: Option<String>,
+}
+
+impl Accounts {
+    pub fn open(accounts: &[Account]) -> Accounts {
+        Accounts {
+            accounts: accounts.to_vec(),
+            url: String::new(),
+            user: String::new(),
+            password: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Url(url) => self.url = url,
+            Message::User(user) => self.user = user,
+            Message::Password(password) => self.password = password,
+            Message::Add => {
+                let account = Account {
+                    url: self.url.trim().to_string(),
+                    user: self.user.trim().to_string(),
+                    password: std::mem::take(&mut self.password),
+                };
+                let Some(root) = account.root() else {
+                    self.error = Some(String::from("The address starts with https:// or http://"));
+                    return None;
+                };
+                // Signing in again to the same folder replaces the account.
+                self.accounts
+                    .retain(|known| known.root().as_ref() != Some(&root));
+                self.accounts.push(account);
+                self.url.clear();
+                self.user.clear();
+                self.error = None;
+                return Some(Event::Changed(self.accounts.clone()));
+            }
+            Message::Remove(index) => {
+                if index < self.accounts.len() {
+                    self.accounts.remove(index);
+                    return Some(Event::Changed(self.accounts.clone()));
+                }
+            }
+            Message::Close => return Some(Event::Closed),
+        }
+        self.error = None;
+
+        None
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let mut col = column!(text(tr("WebDAV accounts")).size(20)).spacing(10);
+
+        if self.accounts.is_empty() {
+            col = col.push(text(tr("None yet")).size(style.small_text_size));
+        }
+        for (index, account) in self.accounts.iter().enumerate() {
+            let user = match account.user.as_str() {
+                "" => String::from("Without signing in"),
+                user => user.to_string(),
+            };
+            col = col.push(
+                row!(
+                    text(&account.url).width(Length::Fill),
+                    text(user).size(style.small_text_size),
+                    button(tr("Remove")).on_press(Message::Remove(index)),
+                )
+                .spacing(10)
+                .align_items(Alignment::Center),
+            );
+        }
+
+        let label = |label| text(label).width(Length::Fixed(100.));
+        col = col
+            .push(
+                row!(
+                    label("Address"),
+                    text_input(
+                        tr("https://cloud.example.com/remote.php/dav/files/name/"),
+                        &self.url
+                    )
+                    .on_input(Message::Url)
+                    .on_submit(Message::Add)
+                    .padding(5),
+                )
+                .spacing(10)
+                .align_items(Alignment::Center),
+            )
+            .push(
+                row!(
+                    label("User"),
+                    text_input(tr("Name"), &self.user)
+                        .on_input(Message::User)
+                        .on_submit(Message::Add)
+                        .padding(5),
+                )
+                .spacing(10)
+                .align_items(Alignment::Center),
+            )
+            .push(
+                row!(
+                    label("Password"),
+                    text_input(tr("Password or app password"), &self.password)
+                        .on_input(Message::Password)
+                        .on_submit(Message::Add)
+                        .secure(true)
+                        .padding(5),
+                )
+                .spacing(10)
+                .align_items(Alignment::Center),
+            )
+            .push(
+                text(tr(
+                    "Passwords are kept in the settings file, readable only by you.",
+                ))
+                .size(style.small_text_size),
+            );
+        if let Some(error) = &self.error {
+            col = col.push(
+                text(error)
+                    .size(style.small_text_size)
+                    .style(theme::Text::Color(style.error)),
+            );
+        }
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Close")).on_press(Message::Close),
+                button(tr("Add"))
+                    .on_press_maybe((!self.url.trim().is_empty()).then_some(Message::Add)),
+            )
+            .spacing(5),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(620)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
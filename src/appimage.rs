@@ -0,0 +1,229 @@
+//! AppImages: single-file Linux applications, an ELF runtime with the
+//! application's squashfs appended. Running one needs it marked
+//! executable; integrating it writes a desktop entry, with the AppImage's
+//! own name and icon, for the application menu; both that and extracting
+//! go through the runtime's `--appimage-extract`.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::dirs;
+use crate::jobs;
+use crate::system;
+
+/// Whether `path` is named as an AppImage, which is all that is checked
+/// before acting on one.
+pub fn is_appimage(path: &Path) -> bool {
+    cfg!(target_os = "linux")
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("appimage"))
+        && path.is_file()
+}
+
+/// Whether the file carries the AppImage magic bytes after its ELF header,
+/// type 2 being the one that can extract itself.
+fn check(path: &Path) -> io::Result<()> {
+    let mut header = [0; 11];
+    fs::File::open(path)?.read_exact(&mut header)?;
+    if &header[..4] != b"\x7FELF" || &header[8..10] != b"AI" {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an AppImage",
+        ));
+    }
+    if header[10] != 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "only type 2 AppImages can be extracted",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Marks `path` executable for its owner, as downloads are not.
+fn make_executable(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = fs::metadata(path)?.permissions();
+        if permissions.mode() & 0o100 == 0 {
+            permissions.set_mode(permissions.mode() | 0o100);
+            fs::set_permissions(path, permissions)?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+pub fn run(path: &Path) -> io::Result<()> {
+    make_executable(path)?;
+    system::launch(path, &[])
+}
+
+/// Unpacks the AppImage into a folder beside it, giving the folder.
+pub fn extract(path: &Path) -> io::Result<PathBuf> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let target = unused(&parent.join(format!("{} contents", stem)));
+    let scratch = scratch_dir(parent)?;
+    let result = extract_into(path, &scratch, None).and_then(|root| fs::rename(root, &target));
+    let _ = fs::remove_dir_all(&scratch);
+    result?;
+    tracing::info!(path = %path.display(), target = %target.display(), "extracted AppImage");
+
+    Ok(target)
+}
+
+/// Adds the AppImage to the application menu, giving the desktop entry
+/// written.
+pub fn integrate(path: &Path) -> io::Result<PathBuf> {
+    let path = fs::canonicalize(path)?;
+    let data = dirs::data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data folder"))?;
+    let applications = data.join("applications");
+    let icons = data.join("icons");
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let id: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let scratch = scratch_dir(&std::env::temp_dir())?;
+    let entry = desktop_entry(&path, &scratch, &icons, &id);
+    let _ = fs::remove_dir_all(&scratch);
+    let entry = entry?.unwrap_or_else(|| {
+        format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" %U\nTerminal=false\n",
+            stem,
+            path.display()
+        )
+    });
+
+    fs::create_dir_all(&applications)?;
+    let file = applications.join(format!("appimage-{}.desktop", id));
+    fs::write(&file, entry)?;
+    tracing::info!(path = %path.display(), entry = %file.display(), "integrated AppImage");
+
+    Ok(file)
+}
+
+/// The AppImage's own desktop entry, made to start it from where it is,
+/// with its icon copied into `icons`; `None` when it has none.
+fn desktop_entry(
+    path: &Path,
+    scratch: &Path,
+    icons: &Path,
+    id: &str,
+) -> io::Result<Option<String>> {
+    let root = extract_into(path, scratch, Some("*.desktop"))?;
+    let Some(desktop) = fs::read_dir(&root)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|file| {
+            file.extension()
+                .is_some_and(|extension| extension == "desktop")
+        })
+    else {
+        return Ok(None);
+    };
+    let original = fs::read_to_string(desktop)?;
+
+    let icon = original
+        .lines()
+        .find_map(|line| line.strip_prefix("Icon="))
+        .map(str::trim)
+        .filter(|icon| !icon.is_empty() && !icon.contains('/'));
+    let mut copied = None;
+    if let Some(icon) = icon {
+        for extension in ["svg", "png"] {
+            let name = format!("{}.{}", icon, extension);
+            let Ok(root) = extract_into(path, scratch, Some(&name)) else {
+                continue;
+            };
+            let mut found = root.join(&name);
+            // Icons at the top are often symlinks into usr/share, which are
+            // extracted on their own.
+            if let Ok(target) = fs::read_link(&found) {
+                let target = target.to_string_lossy().trim_start_matches('/').to_string();
+                if target.split('/').any(|part| part == "..") {
+                    continue;
+                }
+                found = extract_into(path, scratch, Some(&target))?.join(target);
+            }
+            if found.is_file() {
+                fs::create_dir_all(icons)?;
+                let target = icons.join(format!("appimage-{}.{}", id, extension));
+                fs::copy(found, &target)?;
+                copied = Some(target);
+                break;
+            }
+        }
+    }
+
+    let exec = |line: &str| {
+        // Started by its path, keeping the field codes.
+        let arguments = line.split_once(' ').map(|(_, rest)| rest).unwrap_or("%U");
+        format!("Exec=\"{}\" {}", path.display(), arguments)
+    };
+    let mut entry = String::new();
+    for line in original.lines() {
+        if let Some(rest) = line.strip_prefix("Exec=") {
+            entry.push_str(&exec(rest));
+        } else if line.starts_with("TryExec=") {
+            continue;
+        } else if let (true, Some(icon)) = (line.starts_with("Icon="), &copied) {
+            entry.push_str(&format!("Icon={}", icon.display()));
+        } else {
+            entry.push_str(line);
+        }
+        entry.push('\n');
+    }
+
+    Ok(Some(entry))
+}
+
+/// Runs the AppImage's extraction with `scratch` as the working directory,
+/// of everything or of what matches `pattern`, giving the folder written.
+fn extract_into(path: &Path, scratch: &Path, pattern: Option<&str>) -> io::Result<PathBuf> {
+    check(path)?;
+    make_executable(path)?;
+    let mut command = Command::new(path);
+    command
+        .arg("--appimage-extract")
+        .args(pattern)
+        .current_dir(scratch)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "extracting failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(scratch.join("squashfs-root"))
+}
+
+/// `path`, or it with a number added when taken.
+fn unused(path: &Path) -> PathBuf {
+    match path.exists() {
+        true => jobs::unique_name(path),
+        false => path.to_path_buf(),
+    }
+}
+
+/// An empty folder in `parent` to extract into.
+fn scratch_dir(parent: &Path) -> io::Result<PathBuf> {
+    let dir = unused(&parent.join(".appimage-extract"));
+    fs::create_dir(&dir)?;
+
+    Ok(dir)
+}
@@ -0,0 +1,1305 @@
+//! Zip and tar archives browsed as if they were folders: a path such as
+//! `/home/me/photos.zip/2023/beach.jpg` names an entry inside `photos.zip`.
+//! Zip and gzip are read here; xz goes through the `xz` command. New zip and
+//! tar.gz archives are written by `Writer`. What is inside archives is
+//! [`ArchiveFs`]'s, which cannot change it.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::CrcReader;
+
+use crate::vfs::{self, Metadata, Source, VirtualFs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+}
+
+impl Format {
+    /// Those `Writer` can create.
+    pub const WRITABLE: [Format; 2] = [Format::Zip, Format::TarGz];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Zip => "zip",
+            Format::Tar => "tar",
+            Format::TarGz => "tar.gz",
+            Format::TarXz => "tar.xz",
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, ".{}", self.extension())
+    }
+}
+
+/// How a new archive is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub format: Format,
+    /// From 0, storing data as is, to 9, the smallest but slowest.
+    pub level: u32,
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression {
+            format: Format::Zip,
+            level: 6,
+        }
+    }
+}
+
+/// An entry inside an archive, or one of the folders its paths imply.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// `/`-separated, relative to the archive root; just the name for those
+    /// from `children`.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+/// Going by the name, which is all that is checked before opening one.
+pub fn format(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    [
+        (".zip", Format::Zip),
+        (".tar", Format::Tar),
+        (".tar.gz", Format::TarGz),
+        (".tgz", Format::TarGz),
+        (".tar.xz", Format::TarXz),
+        (".txz", Format::TarXz),
+    ]
+    .into_iter()
+    .find_map(|(suffix, format)| name.ends_with(suffix).then_some(format))
+}
+
+/// Splits a path below an archive into the archive and the path inside it,
+/// or `None` for an ordinary path. Only paths with an archive's name in
+/// them are looked for on the disk.
+pub fn split(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let archive = path
+        .ancestors()
+        .find(|ancestor| format(ancestor).is_some() && ancestor.is_file())?;
+    let inner = path.strip_prefix(archive).ok()?.to_path_buf();
+
+    Some((archive.to_path_buf(), inner))
+}
+
+/// What is directly inside `inner`, folders that only show up in longer
+/// paths included.
+pub fn children(archive: &Path, inner: &Path) -> io::Result<Vec<Entry>> {
+    let prefix = inner_path(inner)?;
+    let mut children: BTreeMap<String, Entry> = BTreeMap::new();
+
+    for entry in entries(archive)?.iter() {
+        let rest = match prefix.as_str() {
+            "" => entry.path.as_str(),
+            prefix => match entry.path.strip_prefix(prefix) {
+                Some(rest) if rest.starts_with('/') => &rest[1..],
+                _ => continue,
+            },
+        };
+        match rest.split_once('/') {
+            Some((name, _)) => {
+                children.entry(name.to_string()).or_insert(Entry {
+                    path: name.to_string(),
+                    is_dir: true,
+                    size: 0,
+                });
+            }
+            None if !rest.is_empty() => {
+                children.insert(
+                    rest.to_string(),
+                    Entry {
+                        path: rest.to_string(),
+                        ..entry.clone()
+                    },
+                );
+            }
+            None => {}
+        }
+    }
+
+    if children.is_empty() && !prefix.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not in the archive", prefix),
+        ));
+    }
+
+    Ok(children.into_values().collect())
+}
+
+/// The part of `path` below `item`: all of it for the empty item, which
+/// stands for the whole archive.
+fn below<'a>(item: &str, path: &'a str) -> Option<&'a str> {
+    if item.is_empty() {
+        return Some(path);
+    }
+    match path.strip_prefix(item)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+/// The unpacked size of `items`, paths inside `archive` as for `extract`.
+pub fn size(archive: &Path, items: &[PathBuf]) -> io::Result<u64> {
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| inner_path(item))
+        .collect::<io::Result<_>>()?;
+    Ok(entries(archive)?
+        .iter()
+        .filter(|entry| items.iter().any(|item| below(item, &entry.path).is_some()))
+        .map(|entry| entry.size)
+        .sum())
+}
+
+/// Goes through `items` (paths inside `archive`, folders with everything
+/// below them, or an empty path for all of it), handing `write` where each
+/// entry belongs below `dest`, whether it is a folder, and its data. Items
+/// go under their own name; entries named to escape `dest`, such as
+/// `../../.bashrc` or absolute paths, are skipped, as are links.
+pub fn extract(
+    archive: &Path,
+    items: &[PathBuf],
+    dest: &Path,
+    mut write: impl FnMut(PathBuf, bool, &mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| inner_path(item))
+        .collect::<io::Result<_>>()?;
+    let target = |path: &str| -> Option<PathBuf> {
+        items.iter().find_map(|item| {
+            let rest = below(item, path)?;
+            let name = item.rsplit('/').next().unwrap_or(item);
+            // Joining an empty `rest` would leave a trailing slash.
+            Some(match rest {
+                "" => dest.join(name),
+                rest => dest.join(name).join(rest),
+            })
+        })
+    };
+    let cleaned = |name: &str| {
+        clean(name)
+            .inspect_err(|_| tracing::warn!(name, "skipping an unsafe archive entry"))
+            .ok()
+    };
+
+    match format(archive) {
+        Some(Format::Zip) => {
+            let mut file = File::open(archive)?;
+            for entry in zip_entries(&mut file)? {
+                let Some(target) = cleaned(&entry.name).and_then(|path| target(&path)) else {
+                    continue;
+                };
+                if entry.is_dir() {
+                    write(target, true, &mut io::empty())?;
+                } else if !entry.is_link() {
+                    let mut data = zip_data(&mut file, &entry)?;
+                    write(target, false, &mut data)?;
+                }
+            }
+        }
+        Some(format) => walk_tar(tar_reader(archive, format)?, |header, data| {
+            let kind = header.kind;
+            let is_dir = header.is_dir();
+            match cleaned(&header.path).and_then(|path| target(&path)) {
+                Some(target) if is_dir => write(target, true, data),
+                Some(target) if matches!(kind, b'0' | 0 | b'7') => write(target, false, data),
+                _ => Ok(()),
+            }
+        })?,
+        None => return Err(io::Error::other("not an archive")),
+    }
+
+    Ok(())
+}
+
+/// The files of a tar archive by path, each with its size and, when no
+/// larger than `max`, its data.
+pub type TarFiles = BTreeMap<String, (u64, Option<Vec<u8>>)>;
+
+pub fn tar_files(archive: &Path, max: u64) -> io::Result<TarFiles> {
+    let format = match format(archive) {
+        Some(Format::Zip) | None => return Err(io::Error::other("not a tar archive")),
+        Some(format) => format,
+    };
+    let mut files = BTreeMap::new();
+    walk_tar(tar_reader(archive, format)?, |header, data| {
+        if header.is_dir() || !matches!(header.kind, b'0' | 0 | b'7') {
+            return Ok(());
+        }
+        let contents = match header.size <= max {
+            true => {
+                let mut contents = Vec::with_capacity(header.size as usize);
+                data.read_to_end(&mut contents)?;
+                Some(contents)
+            }
+            false => None,
+        };
+        let path = header.path.trim_start_matches("./").to_string();
+        files.insert(path, (header.size, contents));
+        Ok(())
+    })?;
+
+    Ok(files)
+}
+
+/// The archive's name without its extension, as for a folder to extract
+/// it into.
+pub fn stem(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+    let extension = [".tar.gz", ".tar.xz", ".zip", ".tar", ".tgz", ".txz"]
+        .into_iter()
+        .find(|extension| name.to_lowercase().ends_with(extension))?;
+    Some(name[..name.len() - extension.len()].to_string())
+}
+
+/// The entry at `inner`, which may be a folder only its entries' paths
+/// imply.
+fn find(archive: &Path, inner: &Path) -> io::Result<Entry> {
+    let inner = inner_path(inner)?;
+    let entries = entries(archive)?;
+    if let Some(entry) = entries.iter().find(|entry| entry.path == inner) {
+        return Ok(entry.clone());
+    }
+    if entries
+        .iter()
+        .any(|entry| below(&inner, &entry.path).is_some_and(|rest| !rest.is_empty()))
+    {
+        return Ok(Entry {
+            path: inner,
+            is_dir: true,
+            size: 0,
+        });
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} is not in the archive", inner),
+    ))
+}
+
+type Listed = (PathBuf, Option<SystemTime>, Arc<Vec<Entry>>);
+
+/// The backend `vfs::for_path` hands out for what is inside archives.
+pub struct ArchiveFs {
+    /// The entries of the archive last read.
+    listed: Mutex<Option<Listed>>,
+}
+
+static FS: ArchiveFs = ArchiveFs {
+    listed: Mutex::new(None),
+};
+
+pub fn backend() -> &'static ArchiveFs {
+    &FS
+}
+
+/// The archive and the path inside it, for a path known to be in one.
+fn inside(path: &Path) -> io::Result<(PathBuf, PathBuf)> {
+    split(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not in an archive", path.display()),
+        )
+    })
+}
+
+fn read_only() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "archives cannot be changed; extract them first",
+    )
+}
+
+fn metadata(entry: &Entry) -> Metadata {
+    Metadata {
+        is_dir: entry.is_dir,
+        is_symlink: false,
+        len: entry.size,
+        modified: None,
+        mode: None,
+    }
+}
+
+impl VirtualFs for ArchiveFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<io::Result<PathBuf>>> {
+        Ok(self
+            .list(dir)?
+            .into_iter()
+            .map(|entry| entry.map(|entry| entry.path))
+            .collect())
+    }
+
+    /// The archive itself lists as its top folder.
+    fn list(&self, dir: &Path) -> io::Result<Vec<io::Result<vfs::Entry>>> {
+        let (archive, inner) = inside(dir)?;
+        Ok(children(&archive, &inner)?
+            .into_iter()
+            .map(|entry| {
+                Ok(vfs::Entry {
+                    path: dir.join(&entry.path),
+                    meta: metadata(&entry),
+                    link_target: None,
+                })
+            })
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let (archive, inner) = inside(path)?;
+        find(&archive, &inner).map(|entry| metadata(&entry))
+    }
+
+    fn copy(&self, _from: &Path, _to: &Path) -> io::Result<u64> {
+        Err(read_only())
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn delete(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn create_dir(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    /// Unpacked whole into memory, as neither format seeks inside an
+    /// entry.
+    fn open_read(&self, path: &Path) -> io::Result<Box<dyn Source>> {
+        let (archive, inner) = inside(path)?;
+        if find(&archive, &inner)?.is_dir {
+            return Err(io::Error::other(format!("{} is a folder", path.display())));
+        }
+        let mut contents = None;
+        extract(&archive, &[inner], Path::new(""), |_, is_dir, data| {
+            if !is_dir && contents.is_none() {
+                let mut read = Vec::new();
+                data.read_to_end(&mut read)?;
+                contents = Some(read);
+            }
+            Ok(())
+        })?;
+        contents
+            .map(|contents| Box::new(Cursor::new(contents)) as Box<dyn Source>)
+            .ok_or_else(|| io::Error::other(format!("{} cannot be read", path.display())))
+    }
+
+    fn open_write(&self, _path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        Err(read_only())
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+/// Every entry of `archive`, kept until it changes on disk so that moving
+/// between its folders does not read it again.
+fn entries(archive: &Path) -> io::Result<Arc<Vec<Entry>>> {
+    let modified = fs::metadata(archive)?.modified().ok();
+    let mut listed = FS
+        .listed
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((path, mtime, entries)) = listed.as_ref() {
+        if path == archive && *mtime == modified {
+            return Ok(entries.clone());
+        }
+    }
+
+    let entries = Arc::new(read_entries(archive)?);
+    tracing::debug!(archive = %archive.display(), entries = entries.len(), "listed archive");
+    *listed = Some((archive.to_path_buf(), modified, entries.clone()));
+
+    Ok(entries)
+}
+
+fn read_entries(archive: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+
+    match format(archive) {
+        Some(Format::Zip) => {
+            for entry in zip_entries(&mut File::open(archive)?)? {
+                if let Ok(path) = clean(&entry.name) {
+                    entries.push(Entry {
+                        path,
+                        is_dir: entry.is_dir(),
+                        size: entry.size,
+                    });
+                }
+            }
+        }
+        Some(format) => walk_tar(tar_reader(archive, format)?, |header, _| {
+            if let Ok(path) = clean(&header.path) {
+                entries.push(Entry {
+                    path,
+                    is_dir: header.is_dir(),
+                    size: header.size,
+                });
+            }
+            Ok(())
+        })?,
+        None => return Err(io::Error::other("not an archive")),
+    }
+
+    Ok(entries)
+}
+
+/// An entry name as a plain relative `/`-separated path, refusing anything
+/// that would climb out of the archive: `..`, a drive and absolute paths.
+fn clean(name: &str) -> io::Result<String> {
+    let parts: Vec<&str> = name
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    if parts.is_empty()
+        || name.starts_with(['/', '\\'])
+        || parts.iter().any(|part| *part == ".." || part.contains(':'))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsafe entry name {}", name),
+        ));
+    }
+    Ok(parts.join("/"))
+}
+
+fn inner_path(inner: &Path) -> io::Result<String> {
+    let mut parts = Vec::new();
+    for component in inner.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "not inside the archive",
+                ))
+            }
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+const ZIP_END: u32 = 0x0605_4b50;
+const ZIP64_END: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP_CENTRAL: u32 = 0x0201_4b50;
+const ZIP_LOCAL: u32 = 0x0403_4b50;
+
+struct ZipEntry {
+    name: String,
+    method: u16,
+    flags: u16,
+    compressed: u64,
+    size: u64,
+    offset: u64,
+    /// The Unix mode in the high half, when made on Unix.
+    external: u32,
+}
+
+impl ZipEntry {
+    fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    fn is_link(&self) -> bool {
+        (self.external >> 16) & 0o170000 == 0o120000
+    }
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap_or_default())
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap_or_default())
+}
+
+fn corrupt(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("corrupt zip: {}", what))
+}
+
+/// Reads the central directory at the end of the file.
+fn zip_entries(file: &mut File) -> io::Result<Vec<ZipEntry>> {
+    let length = file.seek(SeekFrom::End(0))?;
+    // The end record is 22 bytes plus a comment of up to 64 KiB.
+    let tail_length = length.min(22 + 65_535 + 20);
+    let mut tail = vec![0; tail_length as usize];
+    file.seek(SeekFrom::Start(length - tail_length))?;
+    file.read_exact(&mut tail)?;
+
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&at| u32_at(&tail, at) == ZIP_END)
+        .ok_or_else(|| corrupt("no end of central directory"))?;
+    let mut count = u16_at(&tail, end + 10) as u64;
+    let mut size = u32_at(&tail, end + 12) as u64;
+    let mut start = u32_at(&tail, end + 16) as u64;
+
+    if end >= 20 && u32_at(&tail, end - 20) == ZIP64_LOCATOR {
+        let mut record = [0; 56];
+        file.seek(SeekFrom::Start(u64_at(&tail, end - 12)))?;
+        file.read_exact(&mut record)?;
+        if u32_at(&record, 0) != ZIP64_END {
+            return Err(corrupt("bad zip64 end record"));
+        }
+        count = u64_at(&record, 32);
+        size = u64_at(&record, 40);
+        start = u64_at(&record, 48);
+    }
+    if start.checked_add(size).is_none_or(|end| end > length) {
+        return Err(corrupt("central directory out of bounds"));
+    }
+
+    let mut directory = vec![0; size as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut directory)?;
+
+    let mut entries = Vec::with_capacity(count.min(1 << 16) as usize);
+    let mut at = 0;
+    while at + 46 <= directory.len() && u32_at(&directory, at) == ZIP_CENTRAL {
+        let name_length = u16_at(&directory, at + 28) as usize;
+        let extra_length = u16_at(&directory, at + 30) as usize;
+        let comment_length = u16_at(&directory, at + 32) as usize;
+        let next = at + 46 + name_length + extra_length + comment_length;
+        if next > directory.len() {
+            return Err(corrupt("truncated central directory"));
+        }
+
+        let mut entry = ZipEntry {
+            name: String::from_utf8_lossy(&directory[at + 46..at + 46 + name_length]).into_owned(),
+            flags: u16_at(&directory, at + 8),
+            method: u16_at(&directory, at + 10),
+            compressed: u32_at(&directory, at + 20) as u64,
+            size: u32_at(&directory, at + 24) as u64,
+            offset: u32_at(&directory, at + 42) as u64,
+            external: u32_at(&directory, at + 38),
+        };
+
+        // Values too big for 32 bits are in the zip64 extra field, in this
+        // order, for those that are saturated.
+        let mut extra = &directory[at + 46 + name_length..at + 46 + name_length + extra_length];
+        while extra.len() >= 4 {
+            let (id, length) = (u16_at(extra, 0), u16_at(extra, 2) as usize);
+            let data = extra.get(4..4 + length).unwrap_or_default();
+            if id == 1 {
+                let mut values = data.chunks_exact(8).map(|chunk| u64_at(chunk, 0));
+                for field in [&mut entry.size, &mut entry.compressed, &mut entry.offset] {
+                    if *field == u32::MAX as u64 {
+                        *field = values.next().unwrap_or(*field);
+                    }
+                }
+            }
+            extra = extra.get(4 + length..).unwrap_or_default();
+        }
+
+        entries.push(entry);
+        at = next;
+    }
+
+    Ok(entries)
+}
+
+fn zip_data<'a>(file: &'a mut File, entry: &ZipEntry) -> io::Result<Box<dyn Read + 'a>> {
+    if entry.flags & 1 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} is encrypted", entry.name),
+        ));
+    }
+
+    let mut header = [0; 30];
+    file.seek(SeekFrom::Start(entry.offset))?;
+    file.read_exact(&mut header)?;
+    if u32_at(&header, 0) != ZIP_LOCAL {
+        return Err(corrupt("bad local header"));
+    }
+    let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+    file.seek(SeekFrom::Current(skip))?;
+
+    let data = file.take(entry.compressed);
+    match entry.method {
+        0 => Ok(Box::new(data)),
+        8 => Ok(Box::new(DeflateDecoder::new(data))),
+        method => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} uses compression method {}", entry.name, method),
+        )),
+    }
+}
+
+pub struct TarHeader {
+    pub path: String,
+    /// The type flag: `b'0'` or NUL for files, `b'5'` for folders.
+    pub kind: u8,
+    pub size: u64,
+}
+
+impl TarHeader {
+    /// Old archives mark folders only with a trailing slash.
+    pub fn is_dir(&self) -> bool {
+        self.kind == b'5' || self.path.ends_with('/')
+    }
+}
+
+/// Decompressed by a command such as `xz`, which is killed if the archive
+/// is not read to the end.
+struct Filter {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Filter {
+    /// Runs `program -dc` on `input`.
+    fn spawn(program: &str, input: Stdio) -> io::Result<Filter> {
+        let mut child = Command::new(program)
+            .arg("-dc")
+            .stdin(input)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| {
+                io::Error::new(error.kind(), format!("cannot run {}: {}", program, error))
+            })?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other(format!("no {} output", program)))?;
+
+        Ok(Filter { child, stdout })
+    }
+}
+
+impl Read for Filter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl Drop for Filter {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn tar_reader(archive: &Path, format: Format) -> io::Result<Box<dyn Read>> {
+    let file = File::open(archive)?;
+    Ok(match format {
+        Format::TarGz => Box::new(MultiGzDecoder::new(BufReader::new(file))),
+        Format::TarXz => Box::new(Filter::spawn("xz", Stdio::from(file))?),
+        _ => Box::new(BufReader::new(file)),
+    })
+}
+
+/// Undoes the compression `extension` names, such as `gz` or `zst`, of
+/// what `reader` gives. Anything but gzip goes through its command.
+pub fn decompress(reader: Box<dyn Read + Send>, extension: &str) -> io::Result<Box<dyn Read>> {
+    let program = match extension {
+        "" => return Ok(reader),
+        "gz" => return Ok(Box::new(MultiGzDecoder::new(BufReader::new(reader)))),
+        "xz" | "lzma" => "xz",
+        "zst" => "zstd",
+        "bz2" => "bzip2",
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} compression is not supported", extension),
+            ))
+        }
+    };
+    let mut filter = Filter::spawn(program, Stdio::piped())?;
+    let mut input = filter
+        .child
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::other(format!("no {} input", program)))?;
+    let mut reader = reader;
+    // Ends with a broken pipe when the output is no longer read.
+    thread::spawn(move || io::copy(&mut reader, &mut input));
+
+    Ok(Box::new(filter))
+}
+
+/// Calls `visit` with each entry and a reader over its data, following GNU
+/// long names and pax paths.
+pub fn walk_tar(
+    mut reader: Box<dyn Read>,
+    mut visit: impl FnMut(&TarHeader, &mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut block = [0u8; 512];
+    let mut long_path: Option<String> = None;
+
+    loop {
+        match reader.read_exact(&mut block) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(error),
+        }
+        if block.iter().all(|&byte| byte == 0) {
+            return Ok(());
+        }
+
+        let size = tar_number(&block[124..136])?;
+        let kind = block[156];
+        let mut path = text(&block[..100]);
+        if &block[257..262] == b"ustar" {
+            let prefix = text(&block[345..500]);
+            if !prefix.is_empty() {
+                path = format!("{}/{}", prefix, path);
+            }
+        }
+        let padding = (512 - size % 512) % 512;
+
+        match kind {
+            b'L' | b'x' => {
+                let mut data = Vec::new();
+                (&mut reader).take(size).read_to_end(&mut data)?;
+                long_path = match kind {
+                    b'L' => Some(text(&data)),
+                    _ => pax_path(&data).or(long_path),
+                };
+            }
+            _ => {
+                let header = TarHeader {
+                    path: long_path.take().unwrap_or(path),
+                    kind,
+                    size,
+                };
+                let mut data = (&mut reader).take(size);
+                visit(&header, &mut data)?;
+                io::copy(&mut data, &mut io::sink())?;
+            }
+        }
+        io::copy(&mut (&mut reader).take(padding), &mut io::sink())?;
+    }
+}
+
+fn text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Octal, or base-256 when the top bit is set, as GNU tar writes big sizes.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold(0u64, |value, &byte| (value << 8) | byte as u64));
+    }
+    let digits = text(field);
+    let digits = digits.trim_matches([' ', '\0']);
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "corrupt tar header"))
+}
+
+/// The `path` of `length key=value\n` records.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let records = String::from_utf8_lossy(data);
+    records.lines().find_map(|record| {
+        let (_, pair) = record.split_once(' ')?;
+        pair.strip_prefix("path=").map(String::from)
+    })
+}
+
+/// Builds a new archive an entry at a time, each named by its `/`-separated
+/// path inside the archive.
+pub struct Writer {
+    sink: Sink,
+    level: flate2::Compression,
+}
+
+enum Sink {
+    Zip(ZipWriter),
+    TarGz(GzEncoder<BufWriter<File>>),
+}
+
+/// Starts `path`, which must not exist yet.
+pub fn create(path: &Path, compression: Compression) -> io::Result<Writer> {
+    let level = flate2::Compression::new(compression.level.min(9));
+    let file = || File::options().write(true).create_new(true).open(path);
+    let sink = match compression.format {
+        Format::Zip => Sink::Zip(ZipWriter {
+            file: BufWriter::new(file()?),
+            central: Vec::new(),
+            count: 0,
+        }),
+        Format::TarGz => Sink::TarGz(GzEncoder::new(BufWriter::new(file()?), level)),
+        format => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot create {} archives", format),
+            ))
+        }
+    };
+
+    Ok(Writer { sink, level })
+}
+
+impl Writer {
+    pub fn add_dir(&mut self, name: &str, meta: &Metadata) -> io::Result<()> {
+        let name = format!("{}/", name);
+        match &mut self.sink {
+            Sink::Zip(zip) => zip.add(&name, meta, None, self.level),
+            Sink::TarGz(tar) => write_tar_entry(tar, &name, meta, b'5', &mut io::empty()),
+        }
+    }
+
+    pub fn add_file(&mut self, name: &str, meta: &Metadata, data: &mut dyn Read) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Zip(zip) => zip.add(name, meta, Some(data), self.level),
+            Sink::TarGz(tar) => write_tar_entry(tar, name, meta, b'0', data),
+        }
+    }
+
+    /// Stored as a link, the way `zip -y` and `tar` do, with `target` as is.
+    pub fn add_link(&mut self, name: &str, meta: &Metadata, target: &Path) -> io::Result<()> {
+        let target = target.to_string_lossy();
+        match &mut self.sink {
+            Sink::Zip(zip) => zip.add(
+                name,
+                meta,
+                Some(&mut target.as_bytes()),
+                flate2::Compression::none(),
+            ),
+            Sink::TarGz(tar) => {
+                if target.len() > 100 {
+                    write_tar_header(
+                        tar,
+                        "././@LongLink",
+                        0,
+                        target.len() as u64 + 1,
+                        0,
+                        b'K',
+                        "",
+                    )?;
+                    write_tar_data(
+                        tar,
+                        &mut target.as_bytes().chain(&[0][..]),
+                        target.len() as u64 + 1,
+                    )?;
+                }
+                write_tar_header(tar, name, unix_mode(meta), 0, mtime(meta), b'2', &target)
+            }
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self.sink {
+            Sink::Zip(zip) => zip.finish(),
+            Sink::TarGz(mut tar) => {
+                // Two empty blocks end a tar.
+                tar.write_all(&[0; 1024])?;
+                tar.finish()?
+                    .into_inner()
+                    .map_err(|error| error.into_error())?
+                    .sync_all()
+            }
+        }
+    }
+}
+
+/// The entry's own mode, or the usual one for its kind where the backend
+/// keeps none.
+fn unix_mode(meta: &Metadata) -> u32 {
+    meta.mode.unwrap_or(if meta.is_dir {
+        0o040755
+    } else if meta.is_symlink {
+        0o120777
+    } else {
+        0o100644
+    })
+}
+
+fn mtime(meta: &Metadata) -> u64 {
+    meta.modified
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs())
+}
+
+struct ZipWriter {
+    file: BufWriter<File>,
+    central: Vec<u8>,
+    count: usize,
+}
+
+fn too_large() -> io::Error {
+    io::Error::other("too large for a zip archive; try .tar.gz")
+}
+
+fn to_u32(value: u64) -> io::Result<u32> {
+    u32::try_from(value).map_err(|_| too_large())
+}
+
+impl ZipWriter {
+    /// Sizes and checksum are only known after the data, so they are filled
+    /// into the local header afterwards rather than in a trailing descriptor.
+    fn add(
+        &mut self,
+        name: &str,
+        meta: &Metadata,
+        data: Option<&mut dyn Read>,
+        level: flate2::Compression,
+    ) -> io::Result<()> {
+        if self.count == usize::from(u16::MAX) {
+            return Err(too_large());
+        }
+        let offset = to_u32(self.file.stream_position()?)?;
+        let method: u16 = match data {
+            Some(_) if level.level() > 0 => 8,
+            _ => 0,
+        };
+        let (time, date) = dos_time(mtime(meta));
+        // Bit 11: the name is UTF-8.
+        let flags: u16 = 0x0800;
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend(ZIP_LOCAL.to_le_bytes());
+        header.extend(20u16.to_le_bytes());
+        header.extend(flags.to_le_bytes());
+        header.extend(method.to_le_bytes());
+        header.extend(time.to_le_bytes());
+        header.extend(date.to_le_bytes());
+        header.extend([0; 12]);
+        header.extend((name.len() as u16).to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(name.as_bytes());
+        self.file.write_all(&header)?;
+
+        let start = self.file.stream_position()?;
+        let (crc, size) = match data {
+            Some(data) => {
+                let mut data = CrcReader::new(data);
+                if method == 8 {
+                    let mut encoder = DeflateEncoder::new(&mut self.file, level);
+                    io::copy(&mut data, &mut encoder)?;
+                    encoder.finish()?;
+                } else {
+                    io::copy(&mut data, &mut self.file)?;
+                }
+                (data.crc().sum(), data.crc().amount() as u64)
+            }
+            None => (0, 0),
+        };
+        let end = self.file.stream_position()?;
+        let sizes = [crc, to_u32(end - start)?, to_u32(size)?];
+
+        self.file.seek(SeekFrom::Start(u64::from(offset) + 14))?;
+        for value in sizes {
+            self.file.write_all(&value.to_le_bytes())?;
+        }
+        self.file.seek(SeekFrom::Start(end))?;
+
+        let external = (unix_mode(meta) << 16) | if meta.is_dir { 0x10 } else { 0 };
+        let central = &mut self.central;
+        central.extend(ZIP_CENTRAL.to_le_bytes());
+        // Made by version 3.0 on Unix, so the mode in `external` is used.
+        central.extend(0x031eu16.to_le_bytes());
+        central.extend(&header[4..14]);
+        for value in sizes {
+            central.extend(value.to_le_bytes());
+        }
+        central.extend((name.len() as u16).to_le_bytes());
+        // Extra field, comment, disk, internal attributes.
+        central.extend([0; 8]);
+        central.extend(external.to_le_bytes());
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+        self.count += 1;
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let offset = to_u32(self.file.stream_position()?)?;
+        self.file.write_all(&self.central)?;
+
+        let mut end = Vec::with_capacity(22);
+        end.extend(ZIP_END.to_le_bytes());
+        end.extend([0; 4]);
+        end.extend((self.count as u16).to_le_bytes());
+        end.extend((self.count as u16).to_le_bytes());
+        end.extend(to_u32(self.central.len() as u64)?.to_le_bytes());
+        end.extend(offset.to_le_bytes());
+        end.extend(0u16.to_le_bytes());
+        self.file.write_all(&end)?;
+
+        self.file
+            .into_inner()
+            .map_err(|error| error.into_error())?
+            .sync_all()
+    }
+}
+
+/// MS-DOS time and date fields, in UTC for lack of a time zone database;
+/// the format cannot go before 1980.
+fn dos_time(secs: u64) -> (u16, u16) {
+    let days = secs / 86400;
+    let secs = secs % 86400;
+    // Howard Hinnant's days-to-civil algorithm.
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    if year < 1980 {
+        return (0, 0x21);
+    }
+
+    let time = ((secs / 3600) << 11) | ((secs % 3600 / 60) << 5) | (secs % 60 / 2);
+    let date = ((year.min(2107) - 1980) << 9) | (month << 5) | day;
+    (time as u16, date as u16)
+}
+
+fn write_tar_entry(
+    tar: &mut impl Write,
+    name: &str,
+    meta: &Metadata,
+    kind: u8,
+    data: &mut dyn Read,
+) -> io::Result<()> {
+    let size = if kind == b'0' { meta.len } else { 0 };
+    if name.len() > 100 {
+        write_tar_header(tar, "././@LongLink", 0, name.len() as u64 + 1, 0, b'L', "")?;
+        write_tar_data(
+            tar,
+            &mut name.as_bytes().chain(&[0][..]),
+            name.len() as u64 + 1,
+        )?;
+    }
+    write_tar_header(tar, name, unix_mode(meta), size, mtime(meta), kind, "")?;
+    write_tar_data(tar, data, size)
+}
+
+/// A ustar header; names and link targets too long for it are cut short,
+/// having gone in a GNU long-name entry before.
+fn write_tar_header(
+    tar: &mut impl Write,
+    name: &str,
+    mode: u32,
+    size: u64,
+    mtime: u64,
+    kind: u8,
+    link: &str,
+) -> io::Result<()> {
+    fn put(header: &mut [u8], at: usize, len: usize, value: &[u8]) {
+        let len = value.len().min(len);
+        header[at..at + len].copy_from_slice(&value[..len]);
+    }
+    fn octal(header: &mut [u8], at: usize, len: usize, value: u64) {
+        let digits = format!("{:0width$o}", value, width = len - 1);
+        if digits.len() < len {
+            put(header, at, len, digits.as_bytes());
+        } else {
+            // Base-256, for sizes past the 8 GiB octal fits.
+            header[at] = 0x80;
+            for (i, byte) in value.to_be_bytes().iter().enumerate() {
+                header[at + len - 8 + i] = *byte;
+            }
+        }
+    }
+
+    let mut header = [0u8; 512];
+    put(&mut header, 0, 100, name.as_bytes());
+    octal(&mut header, 100, 8, u64::from(mode & 0o7777));
+    octal(&mut header, 108, 8, 0);
+    octal(&mut header, 116, 8, 0);
+    octal(&mut header, 124, 12, size);
+    octal(&mut header, 136, 12, mtime);
+    header[156] = kind;
+    put(&mut header, 157, 100, link.as_bytes());
+    put(&mut header, 257, 8, b"ustar\x0000");
+
+    header[148..156].fill(b' ');
+    let sum: u32 = header.iter().map(|byte| u32::from(*byte)).sum();
+    put(&mut header, 148, 8, format!("{:06o}\0 ", sum).as_bytes());
+
+    tar.write_all(&header)
+}
+
+/// Exactly `size` bytes, padded to a whole block; a file that shrank while
+/// being read is filled up with zeros.
+fn write_tar_data(tar: &mut impl Write, data: &mut dyn Read, size: u64) -> io::Result<()> {
+    let copied = io::copy(&mut data.take(size), tar)?;
+    let padding = (size - copied) + (512 - size % 512) % 512;
+    io::copy(&mut io::repeat(0).take(padding), tar)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_keeps_plain_relative_names() {
+        assert_eq!(clean("a/./b//c.txt").unwrap(), "a/b/c.txt");
+        assert_eq!(clean("dir\\file.txt").unwrap(), "dir/file.txt");
+        assert_eq!(clean("folder/").unwrap(), "folder");
+    }
+
+    #[test]
+    fn clean_refuses_names_that_climb_out() {
+        for name in [
+            "../../x",
+            "a/../../x",
+            "..\\x",
+            "/etc/x",
+            "\\\\server\\share\\x",
+            "C:\\x",
+            "C:x",
+            "",
+            "./",
+        ] {
+            assert!(clean(name).is_err(), "{:?} was let through", name);
+        }
+    }
+
+    /// A scratch folder of the test's own, emptied first.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("iced-fm-archive-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// What `extract` would write for the whole of `archive` into `dest`.
+    fn extracted(archive: &Path, dest: &Path) -> Vec<PathBuf> {
+        let mut written = Vec::new();
+        extract(archive, &[PathBuf::new()], dest, |target, _, _| {
+            written.push(target);
+            Ok(())
+        })
+        .unwrap();
+        written
+    }
+
+    fn with_unsafe_entries(archive: &Path, meta: &Metadata) {
+        let format = format(archive).unwrap();
+        let mut writer = create(
+            archive,
+            Compression {
+                format,
+                ..Compression::default()
+            },
+        )
+        .unwrap();
+        for name in ["good.txt", "../../x", "/etc/x", "C:\\x"] {
+            writer.add_file(name, meta, &mut &b"data"[..]).unwrap();
+        }
+        writer
+            .add_link("link", meta, Path::new("/etc/passwd"))
+            .unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn archives_are_browsed_as_read_only_folders() {
+        let dir = scratch("browse");
+        fs::write(dir.join("file"), "data").unwrap();
+        let meta = vfs::LocalFs.metadata(&dir.join("file")).unwrap();
+        let archive = dir.join("browse.zip");
+        let mut writer = create(&archive, Compression::default()).unwrap();
+        writer
+            .add_file("docs/notes.txt", &meta, &mut &b"data"[..])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let fs = backend();
+        let listed: Vec<PathBuf> = fs
+            .read_dir(&archive)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(listed, [archive.join("docs")]);
+        assert!(fs.metadata(&archive.join("docs")).unwrap().is_dir);
+        let notes = archive.join("docs").join("notes.txt");
+        assert_eq!(fs.metadata(&notes).unwrap().len, 4);
+        let mut contents = String::new();
+        fs.open_read(&notes)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "data");
+        assert!(fs.metadata(&archive.join("missing")).is_err());
+        assert!(fs.delete(&notes).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn extracting_a_tar_skips_unsafe_names_and_links() {
+        let dir = scratch("tar");
+        fs::write(dir.join("file"), "data").unwrap();
+        let meta = vfs::LocalFs.metadata(&dir.join("file")).unwrap();
+        let archive = dir.join("evil.tar.gz");
+        with_unsafe_entries(&archive, &meta);
+
+        let dest = dir.join("out");
+        assert_eq!(extracted(&archive, &dest), [dest.join("good.txt")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Zip marks links by the Unix mode, which needs a real link to copy.
+    #[cfg(unix)]
+    #[test]
+    fn extracting_a_zip_skips_unsafe_names_and_links() {
+        let dir = scratch("zip");
+        std::os::unix::fs::symlink("/etc/passwd", dir.join("link")).unwrap();
+        let meta = vfs::LocalFs.metadata(&dir.join("link")).unwrap();
+        let archive = dir.join("evil.zip");
+        with_unsafe_entries(&archive, &meta);
+
+        let dest = dir.join("out");
+        assert!(extracted(&archive, &dest).is_empty());
+
+        fs::write(dir.join("file"), "data").unwrap();
+        let meta = vfs::LocalFs.metadata(&dir.join("file")).unwrap();
+        let archive = dir.join("plain.zip");
+        with_unsafe_entries(&archive, &meta);
+        assert_eq!(
+            extracted(&archive, &dest),
+            [dest.join("good.txt"), dest.join("link")]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+/// Whether the platform's login items include us.
+pub fn is_enabled() -> bool {
+    entry_path().is_some_and(|path| path.is_file())
+}
+
+/// Adds or removes the login item, which starts the executable with
+/// `--hidden` (and `--portable` when running portable).
+pub fn set_enabled(enabled: bool) -> io::Result<()> {
+    let path = entry_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no autostart folder"))?;
+
+    if !enabled {
+        return match fs::remove_file(&path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        };
+    }
+
+    let mut args = vec![env::current_exe()?.to_string_lossy().into_owned()];
+    args.push(String::from("--hidden"));
+    if crate::dirs::is_portable() {
+        args.push(String::from("--portable"));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    tracing::info!(path = %path.display(), "installing autostart entry");
+    crate::safe_save::write(&path, entry(&args).as_bytes())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_path() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("autostart").join("iced-fm.desktop"))
+}
+
+/// An XDG autostart desktop entry, quoting `Exec` as the spec asks.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry(args: &[String]) -> String {
+    let exec: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            let mut quoted = String::from('"');
+            for c in arg.chars() {
+                if matches!(c, '"' | '`' | '$' | '\\') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted.push('"');
+            quoted
+        })
+        .collect();
+
+    format!(
+        "[Desktop Entry]\nType=Application\nName=iced-fm\nExec={}\nNoDisplay=true\nX-GNOME-Autostart-enabled=true\n",
+        exec.join(" ")
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn entry_path() -> Option<PathBuf> {
+    let home = PathBuf::from(env::var_os("HOME")?);
+    Some(home.join("Library/LaunchAgents/io.github.elevenhsoft.iced-fm.plist"))
+}
+
+/// A launch agent that runs once at login.
+#[cfg(target_os = "macos")]
+fn entry(args: &[String]) -> String {
+    let args: String = args
+        .iter()
+        .map(|arg| {
+            let arg = arg
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            format!("        <string>{}</string>\n", arg)
+        })
+        .collect();
+
+    format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+            "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+            "<plist version=\"1.0\">\n<dict>\n",
+            "    <key>Label</key>\n    <string>io.github.elevenhsoft.iced-fm</string>\n",
+            "    <key>ProgramArguments</key>\n    <array>\n{}    </array>\n",
+            "    <key>RunAtLoad</key>\n    <true/>\n",
+            "</dict>\n</plist>\n"
+        ),
+        args
+    )
+}
+
+#[cfg(windows)]
+fn entry_path() -> Option<PathBuf> {
+    let app_data = PathBuf::from(env::var_os("APPDATA")?);
+    Some(app_data.join(r"Microsoft\Windows\Start Menu\Programs\Startup\iced-fm.cmd"))
+}
+
+/// A batch file in the Startup folder, which `start` leaves without
+/// keeping its console open.
+#[cfg(windows)]
+fn entry(args: &[String]) -> String {
+    let args: Vec<String> = args.iter().map(|arg| format!("\"{}\"", arg)).collect();
+    format!("@start \"\" {}\r\n", args.join(" "))
+}
@@ -0,0 +1,276 @@
+//! The rubber band: dragging from the empty space below the rows of the
+//! listing draws a rectangle, and the rows it spans get selected as it
+//! grows. `Band` wraps the listing's scrollable and, like `Drag`, leaves
+//! every event to it; what it reports is in the listing's own coordinates,
+//! from its top, so that the rows stay the same ones as it scrolls.
+
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{self, tree, Widget};
+use iced::advanced::{overlay, renderer, Clipboard, Shell};
+use iced::{event, mouse};
+use iced::{Border, Color, Element, Event, Length, Point, Rectangle, Size, Vector};
+
+/// How far the mouse moves with the button held before a band is drawn.
+const THRESHOLD: f32 = 4.;
+
+pub struct Band<'a, Message, Theme, Renderer> {
+    content: Element<'a, Message, Theme, Renderer>,
+    /// How far the listing is scrolled down.
+    offset: f32,
+    /// How tall the rows are together; below them is empty.
+    extent: f32,
+    on_band: Option<Box<dyn Fn(f32, f32) -> Message + 'a>>,
+    on_release: Option<Message>,
+    color: Color,
+}
+
+impl<'a, Message, Theme, Renderer> Band<'a, Message, Theme, Renderer> {
+    pub fn new(
+        content: impl Into<Element<'a, Message, Theme, Renderer>>,
+        offset: f32,
+        extent: f32,
+    ) -> Self {
+        Self {
+            content: content.into(),
+            offset,
+            extent,
+            on_band: None,
+            on_release: None,
+            color: Color::from_rgb(0.3, 0.5, 0.9),
+        }
+    }
+
+    /// Given the top and bottom of the band whenever it changes.
+    pub fn on_band(self, on_band: impl Fn(f32, f32) -> Message + 'a) -> Self {
+        Self {
+            on_band: Some(Box::new(on_band)),
+            ..self
+        }
+    }
+
+    /// Produced when the button is let go after drawing a band.
+    pub fn on_release(self, on_release: Message) -> Self {
+        Self {
+            on_release: Some(on_release),
+            ..self
+        }
+    }
+
+    pub fn color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Where the button went down, `y` counted from the top of the rows.
+    start: Option<Point>,
+    /// Where the mouse is now, on the screen.
+    cursor: Option<Point>,
+    banding: bool,
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Band<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+    Message: Clone,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<widget::Tree> {
+        vec![widget::Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut widget::Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        let within = |position: Point| Point::new(position.x, position.y - bounds.y + self.offset);
+        match &event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                *state = State::default();
+                state.start = cursor
+                    .position_over(bounds)
+                    .map(within)
+                    .filter(|start| start.y > self.extent);
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(start) = state.start {
+                    let now = within(*position);
+                    state.cursor = Some(*position);
+                    if state.banding || start.distance(now) > THRESHOLD {
+                        state.banding = true;
+                        if let Some(on_band) = &self.on_band {
+                            shell.publish(on_band(start.y.min(now.y), start.y.max(now.y)));
+                        }
+                    }
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.banding {
+                    if let Some(message) = &self.on_release {
+                        shell.publish(message.clone());
+                    }
+                }
+                *state = State::default();
+            }
+            _ => {}
+        }
+
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+
+        let state = tree.state.downcast_ref::<State>();
+        let (Some(start), Some(now), true) = (state.start, state.cursor, state.banding) else {
+            return;
+        };
+        let bounds = layout.bounds();
+        let start = Point::new(start.x, start.y + bounds.y - self.offset);
+        let top_left = Point::new(start.x.min(now.x), start.y.min(now.y));
+        let band = Rectangle::new(
+            top_left,
+            Size::new((start.x - now.x).abs(), (start.y - now.y).abs()),
+        );
+        let Some(band) = band.intersection(&bounds) else {
+            return;
+        };
+        renderer.with_layer(bounds, |renderer| {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: band,
+                    border: Border {
+                        color: self.color,
+                        width: 1.,
+                        radius: 0.into(),
+                    },
+                    ..renderer::Quad::default()
+                },
+                Color {
+                    a: 0.2,
+                    ..self.color
+                },
+            );
+        });
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if tree.state.downcast_ref::<State>().banding {
+            return mouse::Interaction::Crosshair;
+        }
+
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn operate(
+        &self,
+        tree: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Band<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer + 'a,
+    Message: Clone + 'a,
+    Theme: 'a,
+{
+    fn from(band: Band<'a, Message, Theme, Renderer>) -> Self {
+        Self::new(band)
+    }
+}
@@ -0,0 +1,416 @@
+//! What an executable or library says about itself in its headers: the
+//! processor it is built for, the shared libraries it loads and whether
+//! its symbols were stripped. ELF, PE and Mach-O are read by hand, as far
+//! as that takes, with every offset checked against the file.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Load commands and section tables larger than this are taken as corrupt.
+const MAX_TABLE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binary {
+    /// Such as "ELF 64-bit" or "PE32+".
+    pub format: String,
+    /// One per slice of a universal Mach-O file.
+    pub architectures: Vec<String>,
+    pub libraries: Vec<String>,
+    pub stripped: bool,
+}
+
+/// The headers of the file at `path`, or `None` when it is no executable
+/// of a known format or they cannot be made sense of.
+pub fn inspect(path: &Path) -> Option<Binary> {
+    let mut file = Image(File::open(path).ok()?);
+    let magic = file.bytes(0, 4).ok()?;
+    match magic.as_slice() {
+        [0x7F, b'E', b'L', b'F'] => elf(&mut file).ok(),
+        [b'M', b'Z', ..] => pe(&mut file).ok(),
+        [0xCA, 0xFE, 0xBA, 0xBE] => universal(&mut file).ok().flatten(),
+        _ => macho(&mut file, 0).ok().flatten(),
+    }
+}
+
+struct Image(File);
+
+impl Image {
+    fn bytes(&mut self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        if len > MAX_TABLE {
+            return Err(corrupt());
+        }
+        self.0.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::with_capacity(len as usize);
+        (&mut self.0).take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(corrupt());
+        }
+
+        Ok(bytes)
+    }
+
+    /// The NUL-terminated string at `offset`, up to `max` bytes.
+    fn string(&mut self, offset: u64, max: u64) -> io::Result<String> {
+        self.0.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        (&mut self.0).take(max).read_to_end(&mut bytes)?;
+        let end = bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(bytes.len());
+
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// Numbers read from a header in the file's byte order.
+#[derive(Clone, Copy)]
+struct Fields<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl Fields<'_> {
+    fn u16(&self, at: usize) -> io::Result<u16> {
+        let bytes: [u8; 2] = self.take(at)?;
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, at: usize) -> io::Result<u32> {
+        let bytes: [u8; 4] = self.take(at)?;
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    fn u64(&self, at: usize) -> io::Result<u64> {
+        let bytes: [u8; 8] = self.take(at)?;
+        Ok(match self.big_endian {
+            true => u64::from_be_bytes(bytes),
+            false => u64::from_le_bytes(bytes),
+        })
+    }
+
+    /// A word of the file's class: 8 bytes in 64-bit files, 4 otherwise.
+    fn word(&self, at: usize, wide: bool) -> io::Result<u64> {
+        match wide {
+            true => self.u64(at),
+            false => self.u32(at).map(u64::from),
+        }
+    }
+
+    fn take<const N: usize>(&self, at: usize) -> io::Result<[u8; N]> {
+        self.bytes
+            .get(at..at + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(corrupt)
+    }
+}
+
+fn little_endian(bytes: &[u8]) -> Fields<'_> {
+    Fields {
+        bytes,
+        big_endian: false,
+    }
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupt executable headers")
+}
+
+fn elf(file: &mut Image) -> io::Result<Binary> {
+    let header = file.bytes(0, 64)?;
+    let wide = header[4] == 2;
+    let fields = Fields {
+        bytes: &header,
+        big_endian: header[5] == 2,
+    };
+    let machine = match fields.u16(18)? {
+        0x02 => String::from("SPARC"),
+        0x03 => String::from("x86"),
+        0x08 => String::from("MIPS"),
+        0x14 => String::from("PowerPC"),
+        0x15 => String::from("PowerPC 64"),
+        0x16 => String::from("S/390"),
+        0x28 => String::from("ARM"),
+        0x2B => String::from("SPARC V9"),
+        0x3E => String::from("x86-64"),
+        0xB7 => String::from("AArch64"),
+        0xF3 => String::from("RISC-V"),
+        0x102 => String::from("LoongArch"),
+        other => format!("machine {:#x}", other),
+    };
+
+    let (table, entry_size, count) = match wide {
+        true => (fields.u64(0x28)?, fields.u16(0x3A)?, fields.u16(0x3C)?),
+        false => (
+            fields.u32(0x20)?.into(),
+            fields.u16(0x2E)?,
+            fields.u16(0x30)?,
+        ),
+    };
+    if table == 0 || count == 0 {
+        return Ok(elf_binary(wide, machine, Vec::new(), true));
+    }
+    let sections = file.bytes(table, u64::from(entry_size) * u64::from(count))?;
+    let section = |index: usize| -> io::Result<(u32, u64, u64, u32)> {
+        let at = index * usize::from(entry_size);
+        let fields = Fields {
+            bytes: &sections,
+            ..fields
+        };
+        Ok(match wide {
+            true => (
+                fields.u32(at + 4)?,
+                fields.u64(at + 0x18)?,
+                fields.u64(at + 0x20)?,
+                fields.u32(at + 0x28)?,
+            ),
+            false => (
+                fields.u32(at + 4)?,
+                fields.u32(at + 0x10)?.into(),
+                fields.u32(at + 0x14)?.into(),
+                fields.u32(at + 0x18)?,
+            ),
+        })
+    };
+    const SYMTAB: u32 = 2;
+    const DYNAMIC: u32 = 6;
+    const NEEDED: u64 = 1;
+
+    let mut stripped = true;
+    let mut libraries = Vec::new();
+    for index in 0..usize::from(count) {
+        let (kind, offset, size, link) = section(index)?;
+        match kind {
+            SYMTAB => stripped = false,
+            DYNAMIC => {
+                let (_, strings, strings_size, _) = section(link as usize)?;
+                let entries = file.bytes(offset, size)?;
+                let entries = Fields {
+                    bytes: &entries,
+                    ..fields
+                };
+                let width = if wide { 16 } else { 8 };
+                for at in (0..size as usize / width).map(|entry| entry * width) {
+                    let tag = entries.word(at, wide)?;
+                    let value = entries.word(at + width / 2, wide)?;
+                    match tag {
+                        0 => break,
+                        NEEDED if value < strings_size => {
+                            libraries.push(file.string(strings + value, strings_size - value)?)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(elf_binary(wide, machine, libraries, stripped))
+}
+
+fn elf_binary(wide: bool, architecture: String, libraries: Vec<String>, stripped: bool) -> Binary {
+    Binary {
+        format: String::from(if wide { "ELF 64-bit" } else { "ELF 32-bit" }),
+        architectures: vec![architecture],
+        libraries,
+        stripped,
+    }
+}
+
+fn pe(file: &mut Image) -> io::Result<Binary> {
+    let dos = file.bytes(0, 64)?;
+    let header = u64::from(little_endian(&dos).u32(0x3C)?);
+    let coff = file.bytes(header, 24)?;
+    if &coff[..4] != b"PE\0\0" {
+        return Err(corrupt());
+    }
+    let coff = little_endian(&coff[4..]);
+    let machine = match coff.u16(0)? {
+        0x014C => String::from("x86"),
+        0x8664 => String::from("x86-64"),
+        0x01C0 | 0x01C4 => String::from("ARM"),
+        0xAA64 => String::from("AArch64"),
+        other => format!("machine {:#x}", other),
+    };
+    let sections = coff.u16(2)?;
+    let symbols = coff.u32(12)?;
+    let optional_size = coff.u16(16)?;
+
+    let optional = file.bytes(header + 24, optional_size.into())?;
+    let optional = little_endian(&optional);
+    let (format, directories) = match optional.u16(0)? {
+        0x20B => ("PE32+", 112),
+        _ => ("PE32", 96),
+    };
+    let imports = optional.u32(directories + 8).unwrap_or(0);
+
+    let table = file.bytes(
+        header + 24 + u64::from(optional_size),
+        40 * u64::from(sections),
+    )?;
+    let table = little_endian(&table);
+    // Where an address in the loaded image is in the file.
+    let offset = |address: u32| -> io::Result<Option<u64>> {
+        for at in (0..usize::from(sections)).map(|section| section * 40) {
+            let size = table.u32(at + 8)?.max(table.u32(at + 16)?);
+            let start = table.u32(at + 12)?;
+            if (start..start.saturating_add(size)).contains(&address) {
+                return Ok(Some(
+                    u64::from(table.u32(at + 20)?) + u64::from(address - start),
+                ));
+            }
+        }
+        Ok(None)
+    };
+
+    let mut libraries = Vec::new();
+    if let Some(mut at) = offset(imports)?.filter(|_| imports != 0) {
+        loop {
+            let descriptor = file.bytes(at, 20)?;
+            let name = little_endian(&descriptor).u32(12)?;
+            if name == 0 {
+                break;
+            }
+            if let Some(name) = offset(name)? {
+                libraries.push(file.string(name, 256)?);
+            }
+            at += 20;
+        }
+    }
+
+    Ok(Binary {
+        format: format.to_string(),
+        architectures: vec![machine],
+        libraries,
+        // Linkers other than Microsoft's keep a COFF symbol table.
+        stripped: symbols == 0,
+    })
+}
+
+/// Several Mach-O files for different processors, one after the other.
+/// Java class files share the magic number, but give a version where the
+/// slice count would be.
+fn universal(file: &mut Image) -> io::Result<Option<Binary>> {
+    let header = file.bytes(0, 8)?;
+    let slices = Fields {
+        bytes: &header,
+        big_endian: true,
+    }
+    .u32(4)?;
+    if slices == 0 || slices > 16 {
+        return Ok(None);
+    }
+    let table = file.bytes(8, 20 * u64::from(slices))?;
+    let table = Fields {
+        bytes: &table,
+        big_endian: true,
+    };
+
+    let mut universal: Option<Binary> = None;
+    for at in (0..slices as usize).map(|slice| slice * 20) {
+        let Some(slice) = macho(file, table.u32(at + 8)?.into())? else {
+            continue;
+        };
+        match &mut universal {
+            Some(universal) => universal.architectures.extend(slice.architectures),
+            None => universal = Some(slice),
+        }
+    }
+
+    Ok(universal.map(|universal| Binary {
+        format: String::from("Mach-O universal"),
+        ..universal
+    }))
+}
+
+fn macho(file: &mut Image, start: u64) -> io::Result<Option<Binary>> {
+    let header = file.bytes(start, 32)?;
+    let (wide, big_endian) = match header[..4] {
+        [0xCF, 0xFA, 0xED, 0xFE] => (true, false),
+        [0xCE, 0xFA, 0xED, 0xFE] => (false, false),
+        [0xFE, 0xED, 0xFA, 0xCF] => (true, true),
+        [0xFE, 0xED, 0xFA, 0xCE] => (false, true),
+        _ => return Ok(None),
+    };
+    let fields = Fields {
+        bytes: &header,
+        big_endian,
+    };
+    let architecture = match fields.u32(4)? {
+        7 => String::from("x86"),
+        0x0100_0007 => String::from("x86-64"),
+        12 => String::from("ARM"),
+        0x0100_000C => String::from("ARM64"),
+        18 => String::from("PowerPC"),
+        0x0100_0012 => String::from("PowerPC 64"),
+        other => format!("CPU type {:#x}", other),
+    };
+    let count = fields.u32(16)?;
+    let size = fields.u32(20)?;
+    let commands = file.bytes(start + if wide { 32 } else { 28 }, size.into())?;
+    let commands = Fields {
+        bytes: &commands,
+        big_endian,
+    };
+    const SYMTAB: u32 = 0x2;
+    const DYSYMTAB: u32 = 0xB;
+    const LOAD_DYLIB: u32 = 0xC;
+    const LAZY_LOAD_DYLIB: u32 = 0x20;
+    const LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+    const REEXPORT_DYLIB: u32 = 0x8000_001F;
+    const LOAD_UPWARD_DYLIB: u32 = 0x8000_0023;
+
+    let (mut symbols, mut locals) = (None, None);
+    let mut libraries = Vec::new();
+    let mut at = 0;
+    for _ in 0..count {
+        let command = commands.u32(at)?;
+        let command_size = commands.u32(at + 4)? as usize;
+        if command_size < 8 {
+            return Err(corrupt());
+        }
+        match command {
+            SYMTAB => symbols = Some(commands.u32(at + 12)?),
+            DYSYMTAB => locals = Some(commands.u32(at + 12)?),
+            LOAD_DYLIB | LAZY_LOAD_DYLIB | LOAD_WEAK_DYLIB | REEXPORT_DYLIB | LOAD_UPWARD_DYLIB => {
+                let name = commands.u32(at + 8)? as usize;
+                let bytes = commands
+                    .bytes
+                    .get(at + name..at + command_size)
+                    .ok_or_else(corrupt)?;
+                let end = bytes
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(bytes.len());
+                libraries.push(String::from_utf8_lossy(&bytes[..end]).into_owned());
+            }
+            _ => {}
+        }
+        at += command_size;
+    }
+
+    Ok(Some(Binary {
+        format: String::from(if wide {
+            "Mach-O 64-bit"
+        } else {
+            "Mach-O 32-bit"
+        }),
+        architectures: vec![architecture],
+        libraries,
+        // Stripping leaves the symbols other files link against, but takes
+        // the local ones.
+        stripped: match (locals, symbols) {
+            (Some(locals), _) => locals == 0,
+            (None, Some(symbols)) => symbols == 0,
+            (None, None) => true,
+        },
+    }))
+}
@@ -0,0 +1,105 @@
+//! "Keyboard shortcuts": every action with the keys bound to it, and the
+//! preset they start from. Bindings themselves are changed in the settings
+//! file's `[keys]` table.
+
+use iced::widget::{button, column, container, pick_list, row, scrollable, text};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::tr;
+use crate::keymap::{Action, Keymap, Preset};
+use crate::style::Style;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Preset(Preset),
+    Close,
+}
+
+pub enum Event {
+    Preset(Preset),
+    Closed,
+}
+
+pub struct Bindings {
+    preset: Preset,
+}
+
+impl Bindings {
+    pub fn open(preset: Preset) -> Bindings {
+        Bindings { preset }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Preset(preset) => {
+                self.preset = preset;
+                Some(Event::Preset(preset))
+            }
+            Message::Close => Some(Event::Closed),
+        }
+    }
+
+    pub fn view<'a>(&'a self, keymap: &'a Keymap, style: &Style) -> Element<'a, Message> {
+        let mut col = column!(
+            text(tr("Keyboard shortcuts")).size(20),
+            row!(
+                text(tr("Preset")).width(Length::Fill),
+                pick_list(&Preset::ALL[..], Some(self.preset), Message::Preset),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+        )
+        .spacing(10);
+
+        let mut list = column!().spacing(4);
+        for action in Action::ALL {
+            let keys: Vec<String> = keymap
+                .bindings(action)
+                .map(|binding| binding.to_string())
+                .collect();
+            let keys = match keys.is_empty() {
+                true => String::from("—"),
+                false => keys.join(", "),
+            };
+            list = list.push(
+                row!(
+                    text(action.label()).width(Length::Fill),
+                    text(action.name())
+                        .size(style.small_text_size)
+                        .style(theme::Text::Color(style.dimmed))
+                        .width(Length::Fixed(130.)),
+                    text(keys).width(Length::Fixed(150.)),
+                )
+                .spacing(10)
+                .align_items(Alignment::Center),
+            );
+        }
+        col = col.push(scrollable(list).height(Length::Fixed(360.)));
+
+        for error in keymap.errors() {
+            col = col.push(
+                text(error)
+                    .size(style.small_text_size)
+                    .style(theme::Text::Color(style.error)),
+            );
+        }
+        col = col.push(
+            text(
+                "Keys are changed under [keys] in config.toml by the names above, \
+                 as in copy = \"Ctrl+C\" or select-first = \"G G\", \"\" for none.",
+            )
+            .size(style.small_text_size),
+        );
+        col = col.push(row!(
+            text("").width(Length::Fill),
+            button(tr("Close")).on_press(Message::Close),
+        ));
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(620)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
@@ -0,0 +1,118 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, row, text};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::tr;
+use crate::paths;
+use crate::remote;
+use crate::webdav;
+
+/// The ancestors of `path` as (label, path) pairs, outermost first. Paths
+/// inside `project` start at it, and those inside the home directory at
+/// "Home"; those on another machine at its name.
+fn crumbs(path: &Path, project: Option<&Path>) -> Vec<(String, PathBuf)> {
+    let mut crumbs = Vec::new();
+    if let Some(project) = project.filter(|project| paths::starts_with(path, project)) {
+        let label = match project.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => project.display().to_string(),
+        };
+        crumbs.push((label, project.to_path_buf()));
+        let mut current = project.to_path_buf();
+        for component in path.components().skip(project.components().count()) {
+            current.push(component);
+            let label = component.as_os_str().to_string_lossy().to_string();
+            crumbs.push((label, current.clone()));
+        }
+        return crumbs;
+    }
+    if let Some(location) = remote::Location::parse(path) {
+        let mut current = location.root();
+        crumbs.push((location.host(), current.clone()));
+        for name in location.path().split('/').filter(|name| !name.is_empty()) {
+            current.push(name);
+            crumbs.push((name.to_string(), current.clone()));
+        }
+        return crumbs;
+    }
+    if let Some(location) = webdav::Location::parse(path) {
+        let mut current = location.root();
+        crumbs.push((location.authority().to_string(), current.clone()));
+        for name in location.path().split('/').filter(|name| !name.is_empty()) {
+            current.push(name);
+            crumbs.push((name.to_string(), current.clone()));
+        }
+        return crumbs;
+    }
+    if cfg!(windows) {
+        crumbs.push((String::from(paths::COMPUTER), PathBuf::new()));
+        if paths::is_computer(path) {
+            return crumbs;
+        }
+    }
+
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let start = match &home {
+        Some(home) if paths::starts_with(path, home) => {
+            crumbs.push((String::from(tr("Home")), home.clone()));
+            home.components().count()
+        }
+        _ => 0,
+    };
+
+    let mut current = PathBuf::new();
+    for (depth, component) in path.components().enumerate() {
+        current.push(component);
+        if depth < start {
+            continue;
+        }
+        // The root directory is folded into the prefix on Windows ("C:\").
+        if cfg!(windows) && depth == 1 && current.parent().is_none() {
+            if let Some(last) = crumbs.last_mut() {
+                *last = (current.display().to_string(), current.clone());
+            }
+            continue;
+        }
+
+        let label = match current.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => current.display().to_string(),
+        };
+        crumbs.push((label, current.clone()));
+    }
+
+    crumbs
+}
+
+/// Clickable crumbs for `path`, from `project` when inside it; the empty
+/// space after them switches to text editing.
+pub fn view<'a, Message: Clone + 'a>(
+    path: &Path,
+    project: Option<&Path>,
+    on_navigate: impl Fn(PathBuf) -> Message,
+    on_edit: Message,
+) -> Element<'a, Message> {
+    let mut bar = row!().spacing(2).align_items(Alignment::Center);
+
+    for (index, (label, target)) in crumbs(path, project).into_iter().enumerate() {
+        if index > 0 {
+            bar = bar.push(text("▸"));
+        }
+        bar = bar.push(
+            button(text(label))
+                .style(theme::Button::Secondary)
+                .on_press(on_navigate(target)),
+        );
+    }
+
+    bar.push(
+        button(text(""))
+            .style(theme::Button::Text)
+            .on_press(on_edit)
+            .width(Length::Fill),
+    )
+    .padding(5)
+    .into()
+}
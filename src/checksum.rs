@@ -0,0 +1,758 @@
+//! MD5, SHA-1 and SHA-256 of files, checked against a pasted checksum or the
+//! `.sha256`-style files published next to downloads.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use iced::widget::{button, column, container, row, scrollable, text, text_input};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::{tr, trf};
+use crate::style::Style;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sums {
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+    /// A checksum for this file found in a file next to it, and that file.
+    pub listed: Option<(String, PathBuf)>,
+}
+
+impl Sums {
+    fn named(&self) -> [(&'static str, &str); 3] {
+        [
+            ("MD5", &self.md5),
+            ("SHA-1", &self.sha1),
+            ("SHA-256", &self.sha256),
+        ]
+    }
+
+    /// The algorithm `expected` is the checksum of, if it matches any.
+    fn matching(&self, expected: &str) -> Option<&'static str> {
+        let expected = expected.trim();
+        self.named()
+            .into_iter()
+            .find(|(_, sum)| sum.eq_ignore_ascii_case(expected))
+            .map(|(name, _)| name)
+    }
+}
+
+/// Reads `path` once for all three sums, giving up when `stop` is set.
+pub fn compute(path: &Path, stop: &AtomicBool) -> Result<Sums, String> {
+    let mut file = File::open(path).map_err(|error| error.to_string())?;
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+    let mut sha256 = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Err(String::from("Cancelled"));
+        }
+        let read = file.read(&mut buffer).map_err(|error| error.to_string())?;
+        if read == 0 {
+            break;
+        }
+        md5.update(&buffer[..read]);
+        sha1.update(&buffer[..read]);
+        sha256.update(&buffer[..read]);
+    }
+
+    Ok(Sums {
+        md5: hex(&md5.finish()),
+        sha1: hex(&sha1.finish()),
+        sha256: hex(&sha256.finish()),
+        listed: listed(path),
+    })
+}
+
+/// SHA-256 of the first `limit` bytes of `path`, or of all of it.
+pub fn sha256(path: &Path, limit: Option<u64>, stop: &AtomicBool) -> Result<Vec<u8>, String> {
+    let file = File::open(path).map_err(|error| error.to_string())?;
+    let mut file = file.take(limit.unwrap_or(u64::MAX));
+    let mut sha256 = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Err(String::from("Cancelled"));
+        }
+        let read = file.read(&mut buffer).map_err(|error| error.to_string())?;
+        if read == 0 {
+            break;
+        }
+        sha256.update(&buffer[..read]);
+    }
+
+    Ok(sha256.finish())
+}
+
+/// Extensions of a checksum file for the file it is named after, as in
+/// `name.sha256`.
+const OWN: [&str; 6] = ["sha256", "sha256sum", "sha1", "sha1sum", "md5", "md5sum"];
+/// Checksum lists for a whole folder.
+const SHARED: [&str; 5] = [
+    "SHA256SUMS",
+    "sha256sums.txt",
+    "SHA1SUMS",
+    "MD5SUMS",
+    "md5sums.txt",
+];
+/// Checksum lists are small; anything bigger is something else.
+const MAX_LIST: u64 = 1024 * 1024;
+
+/// Looks for the file's checksum in `name.sha256` and the like, then in
+/// `SHA256SUMS`-style lists in the same folder.
+fn listed(path: &Path) -> Option<(String, PathBuf)> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_string_lossy();
+    let own = OWN
+        .into_iter()
+        .map(|extension| dir.join(format!("{}.{}", name, extension)));
+    let shared = SHARED.into_iter().map(|list| dir.join(list));
+
+    own.chain(shared).find_map(|list| {
+        let contents = read_list(&list)?;
+        find_sum(&contents, &name).map(|sum| (sum, list))
+    })
+}
+
+fn read_list(list: &Path) -> Option<String> {
+    let meta = fs::metadata(list).ok()?;
+    if meta.len() > MAX_LIST {
+        return None;
+    }
+    fs::read_to_string(list).ok()
+}
+
+/// Every file in `dir` a checksum file there gives a checksum for, with
+/// that checksum and the file it is in. A file's own `name.sha256` wins
+/// over the folder's lists.
+pub fn sidecars(dir: &Path) -> HashMap<PathBuf, (String, PathBuf)> {
+    let mut found = HashMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return found;
+    };
+    let mut own = Vec::new();
+    for entry in entries.flatten() {
+        let list = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if SHARED.contains(&name.as_str()) {
+            let Some(contents) = read_list(&list) else {
+                continue;
+            };
+            for (file, sum) in sums(&contents) {
+                let path = dir.join(file);
+                if path.parent() == Some(dir) && path.is_file() {
+                    found.entry(path).or_insert((sum, list.clone()));
+                }
+            }
+        } else if let Some((file, extension)) = name.rsplit_once('.') {
+            if OWN.contains(&extension.to_lowercase().as_str()) {
+                own.push((dir.join(file), list));
+            }
+        }
+    }
+    for (path, list) in own {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let sum = read_list(&list).and_then(|contents| find_sum(&contents, &name));
+        if let Some(sum) = sum.filter(|_| path.is_file()) {
+            found.insert(path, (sum, list));
+        }
+    }
+
+    found
+}
+
+/// Whether `path` has the checksum `expected`, an MD5, SHA-1 or SHA-256
+/// in hex going by its length, giving up when `stop` is set.
+pub fn verify(path: &Path, expected: &str, stop: &AtomicBool) -> Result<bool, String> {
+    let mut hasher = match expected.len() {
+        32 => Hasher::Md5(Md5::new()),
+        40 => Hasher::Sha1(Sha1::new()),
+        64 => Hasher::Sha256(Sha256::new()),
+        _ => return Err(String::from(tr("Not an MD5, SHA-1 or SHA-256 checksum"))),
+    };
+    let mut file = File::open(path).map_err(|error| error.to_string())?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Err(String::from("Cancelled"));
+        }
+        let read = file.read(&mut buffer).map_err(|error| error.to_string())?;
+        if read == 0 {
+            break;
+        }
+        match &mut hasher {
+            Hasher::Md5(md5) => md5.update(&buffer[..read]),
+            Hasher::Sha1(sha1) => sha1.update(&buffer[..read]),
+            Hasher::Sha256(sha256) => sha256.update(&buffer[..read]),
+        }
+    }
+    let sum = match hasher {
+        Hasher::Md5(md5) => md5.finish(),
+        Hasher::Sha1(sha1) => sha1.finish(),
+        Hasher::Sha256(sha256) => sha256.finish(),
+    };
+
+    Ok(hex(&sum).eq_ignore_ascii_case(expected))
+}
+
+enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+fn is_hash(word: &str) -> bool {
+    matches!(word.len(), 32 | 40 | 64) && word.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The names and checksums in the output of `sha256sum` and friends, as
+/// in `<hash>  name` or `<hash> *name`, or the BSD `SHA256 (name) = <hash>`.
+fn sums(contents: &str) -> impl Iterator<Item = (&str, String)> {
+    contents.lines().map(str::trim).filter_map(|line| {
+        if let Some((hash, file)) = line.split_once(char::is_whitespace) {
+            let file = file.trim_start().trim_start_matches(['*', ' ']);
+            let file = file.rsplit(['/', '\\']).next().unwrap_or(file);
+            if is_hash(hash) && !file.is_empty() {
+                return Some((file, hash.to_lowercase()));
+            }
+        }
+        let (label, hash) = line.rsplit_once(" = ")?;
+        let file = label.split_once(" (")?.1.strip_suffix(')')?;
+        is_hash(hash).then(|| (file, hash.to_lowercase()))
+    })
+}
+
+/// The checksum for `name` in a list of them, or a lone hash.
+fn find_sum(contents: &str, name: &str) -> Option<String> {
+    if let Some((_, sum)) = sums(contents).find(|(file, _)| *file == name) {
+        return Some(sum);
+    }
+    let mut lines = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    match (lines.next(), lines.next()) {
+        (Some(line), None) if is_hash(line) => Some(line.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// SHA-1 of `data` in hex, for data already in memory.
+pub fn sha1_hex(data: &[u8]) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(data);
+    hex(&sha1.finish())
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut sha256 = Sha256::new();
+    sha256.update(data);
+    hex(&sha256.finish())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Buffers input into 64-byte blocks, the size all three work on, and adds
+/// the shared padding.
+struct Blocks {
+    buffer: [u8; 64],
+    filled: usize,
+    length: u64,
+}
+
+impl Blocks {
+    fn new() -> Blocks {
+        Blocks {
+            buffer: [0; 64],
+            filled: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8], mut block: impl FnMut(&[u8; 64])) {
+        self.length += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.filled).min(data.len());
+            self.buffer[self.filled..self.filled + take].copy_from_slice(&data[..take]);
+            self.filled += take;
+            data = &data[take..];
+            if self.filled == 64 {
+                block(&self.buffer);
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// A 1 bit, zeros, then the length in bits, little- or big-endian.
+    fn finish(mut self, big_endian: bool, mut block: impl FnMut(&[u8; 64])) {
+        let bits = self.length.wrapping_mul(8);
+        let length = if big_endian {
+            bits.to_be_bytes()
+        } else {
+            bits.to_le_bytes()
+        };
+        let mut padding = vec![0x80];
+        padding.resize(1 + (119 - self.filled) % 64, 0);
+        padding.extend(length);
+        let length = self.length;
+        self.update(&padding, &mut block);
+        self.length = length;
+    }
+}
+
+struct Md5 {
+    state: [u32; 4],
+    blocks: Blocks,
+}
+
+impl Md5 {
+    fn new() -> Md5 {
+        Md5 {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            blocks: Blocks::new(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.blocks.update(data, |block| md5_block(state, block));
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks.finish(false, |block| md5_block(state, block));
+        self.state
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+}
+
+/// The integer parts of |sin(i + 1)| * 2^32.
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+fn md5_block(state: &mut [u32; 4], block: &[u8; 64]) {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+    let words: Vec<u32> = block
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    let [mut a, mut b, mut c, mut d] = *state;
+
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a
+            .wrapping_add(f)
+            .wrapping_add(MD5_K[i])
+            .wrapping_add(words[g])
+            .rotate_left(SHIFTS[i / 16 * 4 + i % 4]);
+        (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+    }
+
+    for (word, add) in state.iter_mut().zip([a, b, c, d]) {
+        *word = word.wrapping_add(add);
+    }
+}
+
+struct Sha1 {
+    state: [u32; 5],
+    blocks: Blocks,
+}
+
+impl Sha1 {
+    fn new() -> Sha1 {
+        Sha1 {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            blocks: Blocks::new(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.blocks.update(data, |block| sha1_block(state, block));
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks.finish(true, |block| sha1_block(state, block));
+        self.state
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+}
+
+fn sha1_block(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut words = [0u32; 80];
+    for (i, chunk) in block.chunks_exact(4).enumerate() {
+        words[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for i in 16..80 {
+        words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+    for (i, word) in words.iter().enumerate() {
+        let (f, k) = match i / 20 {
+            0 => ((b & c) | (!b & d), 0x5a827999),
+            1 => (b ^ c ^ d, 0x6ed9eba1),
+            2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        (a, b, c, d, e) = (temp, a, b.rotate_left(30), c, d);
+    }
+
+    for (word, add) in state.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.wrapping_add(add);
+    }
+}
+
+struct Sha256 {
+    state: [u32; 8],
+    blocks: Blocks,
+}
+
+impl Sha256 {
+    fn new() -> Sha256 {
+        Sha256 {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            blocks: Blocks::new(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.blocks.update(data, |block| sha256_block(state, block));
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks.finish(true, |block| sha256_block(state, block));
+        self.state
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn sha256_block(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut words = [0u32; 64];
+    for (i, chunk) in block.chunks_exact(4).enumerate() {
+        words[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for i in 16..64 {
+        let s0 =
+            words[i - 15].rotate_right(7) ^ words[i - 15].rotate_right(18) ^ (words[i - 15] >> 3);
+        let s1 =
+            words[i - 2].rotate_right(17) ^ words[i - 2].rotate_right(19) ^ (words[i - 2] >> 10);
+        words[i] = words[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(words[i - 7])
+            .wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (word, k) in words.iter().zip(SHA256_K) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(choice)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(majority);
+        (a, b, c, d, e, f, g, h) = (
+            temp1.wrapping_add(temp2),
+            a,
+            b,
+            c,
+            d.wrapping_add(temp1),
+            e,
+            f,
+            g,
+        );
+    }
+
+    for (word, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(add);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Calculated(usize, Result<Sums, String>),
+    Expected(String),
+    Copy(String),
+    Close,
+}
+
+pub enum Event {
+    Copy(String),
+    Closed,
+}
+
+enum State {
+    Calculating,
+    Done(Sums),
+    Failed(String),
+}
+
+/// A dialog listing the checksums of some files as they come in.
+pub struct Checksums {
+    files: Vec<(PathBuf, State)>,
+    /// Pasted by the user to compare against.
+    expected: String,
+    stop: Arc<AtomicBool>,
+}
+
+impl Checksums {
+    /// The sums are worked out by the caller with `compute` for each of
+    /// `pending`, and come back as `Message::Calculated`.
+    pub fn open(paths: Vec<PathBuf>) -> Checksums {
+        Checksums {
+            files: paths
+                .into_iter()
+                .map(|path| (path, State::Calculating))
+                .collect(),
+            expected: String::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Each file by its index, with the flag that stops its computation.
+    pub fn pending(&self) -> impl Iterator<Item = (usize, PathBuf, Arc<AtomicBool>)> + '_ {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(index, (path, _))| (index, path.clone(), self.stop.clone()))
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Calculated(index, result) => {
+                if let Some((_, state)) = self.files.get_mut(index) {
+                    *state = match result {
+                        Ok(sums) => State::Done(sums),
+                        Err(error) => State::Failed(error),
+                    };
+                }
+                None
+            }
+            Message::Expected(expected) => {
+                self.expected = expected;
+                None
+            }
+            Message::Copy(sum) => Some(Event::Copy(sum)),
+            Message::Close => Some(Event::Closed),
+        }
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let mut col = column!(text(tr("Checksums")).size(20)).spacing(10);
+
+        for (path, state) in &self.files {
+            let name = path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy();
+            let mut file = column!(text(name.to_string())).spacing(4);
+
+            match state {
+                State::Calculating => {
+                    file = file.push(text(tr("Calculating…")).size(style.small_text_size))
+                }
+                State::Failed(error) => {
+                    file = file.push(
+                        text(error)
+                            .size(style.small_text_size)
+                            .style(theme::Text::Color(style.error)),
+                    )
+                }
+                State::Done(sums) => {
+                    for (algorithm, sum) in sums.named() {
+                        file = file.push(
+                            row!(
+                                text(algorithm).width(Length::Fixed(80.)),
+                                text(sum).size(style.small_text_size).width(Length::Fill),
+                                button(tr("Copy")).on_press(Message::Copy(sum.to_string())),
+                            )
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                        );
+                    }
+                    let mut verdicts = Vec::new();
+                    if !self.expected.trim().is_empty() {
+                        verdicts.push(match sums.matching(&self.expected) {
+                            Some(algorithm) => {
+                                (true, trf("Matches the pasted {} checksum", &[&algorithm]))
+                            }
+                            None => (
+                                false,
+                                String::from(tr("Does not match the pasted checksum")),
+                            ),
+                        });
+                    }
+                    if let Some((sum, list)) = &sums.listed {
+                        let list = list.file_name().unwrap_or_default().to_string_lossy();
+                        verdicts.push(match sums.matching(sum) {
+                            Some(_) => (true, trf("Matches the checksum in {}", &[&list])),
+                            None => (false, trf("Does not match the checksum in {}", &[&list])),
+                        });
+                    }
+                    for (matches, verdict) in verdicts {
+                        let verdict = text(verdict).size(style.small_text_size);
+                        file = file.push(if matches {
+                            verdict
+                        } else {
+                            verdict.style(theme::Text::Color(style.error))
+                        });
+                    }
+                }
+            }
+
+            col = col.push(file);
+        }
+
+        container(
+            column!(
+                scrollable(col).height(Length::Shrink),
+                text_input(tr("Paste a checksum to verify"), &self.expected)
+                    .on_input(Message::Expected)
+                    .padding(5),
+                button(tr("Close")).on_press(Message::Close),
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .max_width(720)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+impl Drop for Checksums {
+    /// Closing the dialog stops whatever is still being read.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The inputs of the RFC 1321 and FIPS 180 examples: empty, one block
+    /// and two, and a million bytes fed in uneven pieces.
+    fn digests<H>(
+        new: fn() -> H,
+        update: fn(&mut H, &[u8]),
+        finish: fn(H) -> Vec<u8>,
+    ) -> [String; 4] {
+        let digest = |pieces: &[&[u8]]| {
+            let mut hasher = new();
+            for piece in pieces {
+                update(&mut hasher, piece);
+            }
+            hex(&finish(hasher))
+        };
+        let million = vec![b'a'; 1_000_000];
+        let (head, tail) = million.split_at(63);
+        let (middle, tail) = tail.split_at(65);
+        [
+            digest(&[]),
+            digest(&[b"abc"]),
+            digest(&[b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"]),
+            digest(&[head, middle, tail]),
+        ]
+    }
+
+    #[test]
+    fn md5_matches_the_reference() {
+        assert_eq!(
+            digests(Md5::new, Md5::update, Md5::finish),
+            [
+                "d41d8cd98f00b204e9800998ecf8427e",
+                "900150983cd24fb0d6963f7d28e17f72",
+                "8215ef0796a20bcaaae116d3876c664a",
+                "7707d6ae4e027c70eea2a935c2296f21",
+            ]
+        );
+    }
+
+    #[test]
+    fn sha1_matches_the_reference() {
+        assert_eq!(
+            digests(Sha1::new, Sha1::update, Sha1::finish),
+            [
+                "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+                "34aa973cd4c4daa4f61eeb2bdbad27316534016f",
+            ]
+        );
+    }
+
+    #[test]
+    fn sha256_matches_the_reference() {
+        assert_eq!(
+            digests(Sha256::new, Sha256::update, Sha256::finish),
+            [
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+                "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! How names are put in order. By default as people read them: "file2"
+//! before "file10", and capitals and accents only counting when nothing
+//! else tells two names apart, so "Ärger" sorts with "Arger", except for
+//! the letters the user's language sorts on their own, such as Swedish "ä"
+//! after "z". Otherwise, for those who want it, byte by byte.
+
+use std::env;
+use std::ffi::OsStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::config::NameOrder;
+
+static BYTES: AtomicBool = AtomicBool::new(false);
+
+/// What names are compared by; keys compare as the names they are for.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Natural {
+        primary: Vec<Part>,
+        /// The name lowercased, which has its accents.
+        accents: String,
+        name: String,
+    },
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Part {
+    /// A run of digits: the longer number is the larger once leading zeros
+    /// are gone.
+    Number {
+        length: usize,
+        digits: String,
+    },
+    Text(Vec<u32>),
+}
+
+/// The order names are put in from now on.
+pub fn set_order(order: NameOrder) {
+    BYTES.store(order == NameOrder::Bytes, Ordering::Relaxed);
+}
+
+pub fn key(name: impl AsRef<OsStr>) -> Key {
+    let name = name.as_ref();
+    if BYTES.load(Ordering::Relaxed) {
+        return Key::Bytes(name.as_encoded_bytes().to_vec());
+    }
+
+    let name = name.to_string_lossy();
+    let tailoring = tailoring();
+    let mut primary = Vec::new();
+    let mut chars = name.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+            let digits = digits.trim_start_matches('0').to_string();
+            primary.push(Part::Number {
+                length: digits.len(),
+                digits,
+            });
+            continue;
+        }
+        let mut weights = Vec::new();
+        while let Some(c) = chars.next_if(|c| !c.is_ascii_digit()) {
+            weigh(c, tailoring, &mut weights);
+        }
+        primary.push(Part::Text(weights));
+    }
+
+    Key::Natural {
+        primary,
+        accents: name.to_lowercase(),
+        name: name.into_owned(),
+    }
+}
+
+/// `c` lowercased and without its accents, as names are grouped under it.
+pub fn fold(c: char) -> char {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    if tailoring().contains(lower) {
+        return lower;
+    }
+    base(lower)
+        .and_then(|base| base.chars().next())
+        .unwrap_or(lower)
+}
+
+/// Letters after "z", in order, for the languages that have them.
+fn tailoring() -> &'static str {
+    static TAILORING: OnceLock<&str> = OnceLock::new();
+
+    TAILORING.get_or_init(|| {
+        let set = |name| env::var(name).ok().filter(|value| !value.is_empty());
+        let locale = set("LC_ALL")
+            .or_else(|| set("LC_COLLATE"))
+            .or_else(|| set("LANG"))
+            .or_else(sys_locale::get_locale)
+            .unwrap_or_default();
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        match language {
+            "sv" | "fi" => "åäö",
+            "da" | "nb" | "nn" | "no" => "æøå",
+            _ => "",
+        }
+    })
+}
+
+/// Adds what `c` weighs when only letters count: lowercased, without its
+/// accents unless the language sorts the accented letter on its own.
+fn weigh(c: char, tailoring: &str, weights: &mut Vec<u32>) {
+    // Room between the letters for those sorted after "z".
+    let weight = |c: char| u32::from(c) << 2;
+
+    for c in c.to_lowercase() {
+        if let Some(rank) = tailoring.chars().position(|letter| letter == c) {
+            weights.push(weight('z') + 1 + rank as u32);
+        } else if ('\u{300}'..='\u{36f}').contains(&c) {
+            // A combining accent.
+        } else if let Some(base) = base(c) {
+            weights.extend(base.chars().map(weight));
+        } else {
+            weights.push(weight(c));
+        }
+    }
+}
+
+/// The unaccented letters of a lowercase Latin one.
+fn base(c: char) -> Option<&'static str> {
+    const LETTERS: [(&str, &str); 24] = [
+        ("a", "àáâãäåāăą"),
+        ("c", "çćĉċč"),
+        ("d", "ďđð"),
+        ("e", "èéêëēĕėęě"),
+        ("g", "ĝğġģ"),
+        ("h", "ĥħ"),
+        ("i", "ìíîïĩīĭįı"),
+        ("j", "ĵ"),
+        ("k", "ķ"),
+        ("l", "ĺļľŀł"),
+        ("n", "ñńņňŉ"),
+        ("o", "òóôõöøōŏő"),
+        ("r", "ŕŗř"),
+        ("s", "śŝşš"),
+        ("t", "ţťŧ"),
+        ("u", "ùúûüũūŭůűų"),
+        ("w", "ŵ"),
+        ("y", "ýÿŷ"),
+        ("z", "źżž"),
+        ("ss", "ß"),
+        ("ae", "æ"),
+        ("oe", "œ"),
+        ("th", "þ"),
+        ("ij", "ĳ"),
+    ];
+
+    LETTERS
+        .iter()
+        .find(|(_, accented)| accented.contains(c))
+        .map(|&(base, _)| base)
+}
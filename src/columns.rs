@@ -0,0 +1,649 @@
+//! The columns view: the folders leading to the current one each get a
+//! column of their own, with the way taken highlighted, and a selected
+//! file gets a preview after the listing: source files highlighted, images
+//! and what has a picture drawn, small first and then sharper, and other
+//! files dumped in hex, more of it read as the preview is scrolled to its
+//! end. In low-memory mode no picture is decoded and nothing more is read.
+
+use std::any::TypeId;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::image::Handle;
+use iced::widget::scrollable::{Direction, Properties};
+use iced::widget::{button, column, container, image, row, scrollable, text, Column};
+use iced::{subscription, theme, ContentFit, Element, Font, Length, Subscription};
+
+use crate::collation;
+use crate::encoding::{self, Detected};
+use crate::highlight;
+use crate::i18n::{tr, trf};
+use crate::images;
+use crate::jobs::format_bytes;
+use crate::media::{self, Media};
+use crate::mime;
+use crate::paths;
+use crate::pictures;
+use crate::style::Style;
+use crate::vfs;
+
+/// Columns for ancestors, not counting the current folder's listing.
+const ANCESTORS: usize = 3;
+const WIDTH: f32 = 200.;
+/// How much of a text file the preview reads.
+const PREVIEW_BYTES: u64 = 4096;
+const PREVIEW_LINES: usize = 20;
+/// How much of a binary file the hex dump reads at a time, and in all.
+const HEX_CHUNK: u64 = 1024;
+const HEX_MAX: u64 = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Open(PathBuf),
+    Listed(PathBuf, Vec<Entry>),
+    Previewed(PathBuf, Box<Preview>),
+    /// The lines of a text file previewed in the colours of the theme
+    /// named, `None` when it is not source.
+    Highlighted(PathBuf, String, Option<Vec<highlight::Line>>),
+    /// The picture of a file decoded at the size given, or why not.
+    Pictured(PathBuf, u32, Result<Handle, String>),
+    /// Shows the picture across the window, or stops.
+    View(bool),
+    /// How far down the preview is scrolled, from 0 to 1.
+    PreviewScrolled(f32),
+    /// More of the hex dump of a file, from the offset given.
+    HexRead(PathBuf, u64, Vec<u8>),
+}
+
+pub enum Event {
+    Open(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct Preview {
+    size: u64,
+    /// The first lines, for text files.
+    text: Option<String>,
+    /// The bytes dumped so far, for files neither text nor images.
+    hex: Option<Vec<u8>>,
+    encoding: Option<Detected>,
+    /// Width and height, for images.
+    dimensions: Option<(u32, u32)>,
+    /// Those of the JPEG a RAW file holds.
+    embedded: Option<(u32, u32)>,
+    /// For photos and recordings.
+    media: Option<Media>,
+}
+
+#[derive(Default)]
+pub struct Columns {
+    listed: Vec<(PathBuf, Vec<Entry>)>,
+    preview: Option<(PathBuf, Preview)>,
+    /// The offset the preview's hex dump is being read on from.
+    reading_hex: Option<u64>,
+    /// The picture of the file previewed, the size it was decoded at, or
+    /// why there is none.
+    picture: Option<(PathBuf, u32, Result<Handle, String>)>,
+    /// Whether the picture is shown across the window.
+    viewing: bool,
+    /// The preview's lines in colour, and the theme they are in.
+    highlighted: Option<(PathBuf, String, Option<Vec<highlight::Line>>)>,
+}
+
+impl Columns {
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Open(path) => return Some(Event::Open(path)),
+            Message::Pictured(path, size, picture) => {
+                let previewed = self.preview.as_ref().map(|(previewed, _)| previewed);
+                if previewed == Some(&path) {
+                    self.picture = Some((path, size, picture));
+                }
+            }
+            Message::View(viewing) => self.viewing = viewing && self.handle().is_some(),
+            Message::Listed(dir, entries) => {
+                self.listed.retain(|(listed, _)| listed != &dir);
+                self.listed.push((dir, entries));
+            }
+            Message::Previewed(path, preview) => {
+                if self
+                    .picture
+                    .as_ref()
+                    .is_some_and(|(pictured, ..)| *pictured != path)
+                {
+                    self.picture = None;
+                    self.viewing = false;
+                }
+                self.preview = Some((path, *preview));
+                self.reading_hex = None;
+            }
+            Message::Highlighted(path, theme, lines) => {
+                let previewed = self.preview.as_ref().map(|(previewed, _)| previewed);
+                if previewed == Some(&path) {
+                    self.highlighted = Some((path, theme, lines));
+                }
+            }
+            Message::PreviewScrolled(y) if y > 0.9 && self.reading_hex.is_none() => {
+                if let Some((_, preview)) = &self.preview {
+                    self.reading_hex = preview
+                        .hex
+                        .as_ref()
+                        .map(|hex| hex.len() as u64)
+                        .filter(|&read| read < preview.size.min(HEX_MAX));
+                }
+            }
+            Message::PreviewScrolled(_) => {}
+            Message::HexRead(path, offset, bytes) => {
+                if let Some((previewed, preview)) = &mut self.preview {
+                    let hex = preview
+                        .hex
+                        .as_mut()
+                        .filter(|hex| hex.len() as u64 == offset);
+                    if let Some(hex) = hex.filter(|_| *previewed == path) {
+                        // Nothing more is asked for a file that stops short.
+                        if bytes.is_empty() {
+                            preview.size = hex.len() as u64;
+                        }
+                        hex.extend(bytes);
+                    }
+                }
+                self.reading_hex = None;
+            }
+        }
+
+        None
+    }
+
+    /// Forgets listings of folders that no longer lead to `dir`, so going
+    /// back up lists them afresh.
+    pub fn follow(&mut self, dir: &Path) {
+        let ancestors = ancestors(dir);
+        self.listed
+            .retain(|(listed, _)| ancestors.iter().any(|ancestor| ancestor == listed));
+    }
+
+    /// The picture of the file previewed, when it has been decoded.
+    fn handle(&self) -> Option<&Handle> {
+        match &self.picture {
+            Some((_, _, Ok(handle))) => Some(handle),
+            _ => None,
+        }
+    }
+
+    pub fn is_viewing(&self) -> bool {
+        self.viewing
+    }
+
+    /// Lists the ancestors of `dir` not listed yet, and reads the preview
+    /// of `selected`, decoding its picture unless `low_memory` and
+    /// colouring source in the syntect `theme`.
+    pub fn subscription(
+        &self,
+        dir: &Path,
+        selected: Option<&Path>,
+        low_memory: bool,
+        theme: &str,
+    ) -> Subscription<Message> {
+        struct Listing;
+        struct Previewing;
+
+        let mut subscriptions: Vec<_> = ancestors(dir)
+            .into_iter()
+            .filter(|ancestor| !self.listed.iter().any(|(listed, _)| listed == ancestor))
+            .map(|ancestor| {
+                subscription::channel(
+                    (TypeId::of::<Listing>(), ancestor.clone()),
+                    1,
+                    move |output| {
+                        blocking(output, move || {
+                            let entries = list(&ancestor);
+                            Message::Listed(ancestor, entries)
+                        })
+                    },
+                )
+            })
+            .collect();
+
+        if let Some(path) = selected.filter(|path| {
+            self.preview
+                .as_ref()
+                .is_none_or(|(previewed, _)| previewed != path)
+        }) {
+            let path = path.to_path_buf();
+            subscriptions.push(subscription::channel(
+                (TypeId::of::<Previewing>(), path.clone()),
+                1,
+                move |output| {
+                    blocking(output, move || {
+                        let preview = preview(&path, low_memory);
+                        Message::Previewed(path, Box::new(preview))
+                    })
+                },
+            ));
+        }
+
+        let sharp = self
+            .picture
+            .as_ref()
+            .is_some_and(|(_, size, picture)| *size == pictures::SCREEN || picture.is_err());
+        if let Some((path, _)) = self
+            .preview
+            .as_ref()
+            .filter(|(path, _)| !low_memory && !sharp && pictures::shows(path))
+        {
+            struct Picturing;
+
+            // Small first, for something to show soon, then sharper.
+            let path = path.clone();
+            let sizes = match &self.picture {
+                Some(_) => vec![pictures::SCREEN],
+                None => vec![pictures::PREVIEW, pictures::SCREEN],
+            };
+            subscriptions.push(subscription::channel(
+                (TypeId::of::<Picturing>(), path.clone()),
+                2,
+                move |mut output| async move {
+                    let (sender, mut receiver) = mpsc::unbounded();
+                    std::thread::spawn(move || {
+                        for size in sizes {
+                            let picture =
+                                pictures::picture(&path, size).map_err(|error| error.to_string());
+                            let failed = picture.is_err();
+                            let message = Message::Pictured(path.clone(), size, picture);
+                            if sender.unbounded_send(message).is_err() || failed {
+                                break;
+                            }
+                        }
+                    });
+                    while let Some(message) = receiver.next().await {
+                        let _ = output.send(message).await;
+                    }
+                    std::future::pending().await
+                },
+            ));
+        }
+
+        if let Some((path, text)) = self
+            .preview
+            .as_ref()
+            .and_then(|(path, preview)| Some((path, preview.text.as_ref()?)))
+            .filter(|(path, _)| {
+                self.highlighted
+                    .as_ref()
+                    .is_none_or(|(highlighted, in_theme, _)| {
+                        highlighted != *path || in_theme != theme
+                    })
+            })
+        {
+            struct Highlighting;
+
+            let (path, text, theme) = (path.clone(), text.clone(), theme.to_string());
+            subscriptions.push(subscription::channel(
+                (TypeId::of::<Highlighting>(), path.clone(), theme.clone()),
+                1,
+                move |output| {
+                    blocking(output, move || {
+                        let lines = highlight::highlight(&path, &text, &theme);
+                        Message::Highlighted(path, theme, lines)
+                    })
+                },
+            ));
+        }
+
+        if let (Some((path, _)), Some(offset), false) =
+            (&self.preview, self.reading_hex, low_memory)
+        {
+            struct ReadingHex;
+
+            let path = path.clone();
+            subscriptions.push(subscription::channel(
+                (TypeId::of::<ReadingHex>(), path.clone(), offset),
+                1,
+                move |output| {
+                    blocking(output, move || {
+                        let bytes = read_hex(&path, offset).unwrap_or_else(|error| {
+                            tracing::debug!(%error, path = %path.display(), "cannot read on");
+                            Vec::new()
+                        });
+                        Message::HexRead(path, offset, bytes)
+                    })
+                },
+            ));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    /// The ancestors' columns, outermost first.
+    pub fn ancestors<'a>(&'a self, dir: &Path, style: &Style) -> Vec<Element<'a, Message>> {
+        let ancestors = ancestors(dir);
+        ancestors
+            .iter()
+            .enumerate()
+            .map(|(i, ancestor)| {
+                let toward = ancestors.get(i + 1).map(PathBuf::as_path).unwrap_or(dir);
+                let mut col = column!().width(Length::Fixed(WIDTH));
+                match self.listed.iter().find(|(listed, _)| listed == ancestor) {
+                    Some((_, entries)) => {
+                        for entry in entries {
+                            let style = if paths::same(&entry.path, toward) {
+                                theme::Button::Primary
+                            } else {
+                                theme::Button::Text
+                            };
+                            let label = if entry.is_dir {
+                                format!("{} ›", entry.name)
+                            } else {
+                                entry.name.clone()
+                            };
+                            let mut row = button(text(label))
+                                .style(style)
+                                .width(Length::Fill)
+                                .padding([2, 5]);
+                            if entry.is_dir {
+                                row = row.on_press(Message::Open(entry.path.clone()));
+                            }
+                            col = col.push(row);
+                        }
+                    }
+                    None => col = col.push(text(tr("Listing…")).size(style.small_text_size)),
+                }
+
+                scrollable(col).height(Length::Fill).into()
+            })
+            .collect()
+    }
+
+    /// What is known of `path` so far, for the column after the listing.
+    pub fn preview<'a>(&'a self, path: &Path, style: &Style) -> Element<'a, Message> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut col = column!(
+            text(name),
+            text(mime::guess(path)).size(style.small_text_size)
+        )
+        .spacing(5)
+        .width(Length::Fixed(WIDTH + 40.));
+
+        if let Some((_, preview)) = self
+            .preview
+            .as_ref()
+            .filter(|(previewed, _)| previewed == path)
+        {
+            col = col.push(text(format_bytes(preview.size)).size(style.small_text_size));
+            if let Some((width, height)) = preview.dimensions {
+                col = col.push(
+                    text(trf("{} × {} pixels", &[&width, &height])).size(style.small_text_size),
+                );
+            }
+            if let Some((width, height)) = preview.embedded {
+                col = col.push(
+                    text(trf("Preview of {} × {} pixels", &[&width, &height]))
+                        .size(style.small_text_size),
+                );
+            }
+            let picture = self
+                .picture
+                .as_ref()
+                .filter(|(pictured, ..)| pictured == path)
+                .map(|(_, _, picture)| picture);
+            match picture {
+                Some(Ok(handle)) => {
+                    col = col.push(
+                        button(image(handle.clone()).width(Length::Fill))
+                            .style(theme::Button::Text)
+                            .padding(0)
+                            .on_press(Message::View(true)),
+                    );
+                }
+                Some(Err(error)) => {
+                    col = col.push(
+                        text(trf("No picture: {}", &[error]))
+                            .size(style.small_text_size)
+                            .style(theme::Text::Color(style.dimmed)),
+                    );
+                }
+                None => {}
+            }
+            if picture.is_none_or(Result::is_err)
+                && (images::is_pdf(path) || images::is_video(path))
+                && !style.icons.file.is_empty()
+            {
+                let mut icon = text(&style.icons.file).size(64);
+                if let Some(font) = style.icons.font {
+                    icon = icon.font(font);
+                }
+                col = col.push(icon);
+            }
+            for (label, value) in preview.media.iter().flat_map(Media::fields) {
+                col = col.push(text(format!("{}: {}", label, value)).size(style.small_text_size));
+            }
+            if let Some(encoding) = preview.encoding {
+                col = col.push(text(encoding).size(style.small_text_size));
+            }
+            let highlighted = self
+                .highlighted
+                .as_ref()
+                .filter(|(highlighted, ..)| highlighted == path)
+                .and_then(|(_, _, lines)| lines.as_ref());
+            if let Some(lines) = highlighted {
+                let lines = lines.iter().map(|line| {
+                    row(line.iter().map(|(color, piece)| {
+                        let piece = text(piece)
+                            .font(Font::MONOSPACE)
+                            .size(style.small_text_size);
+                        match *color {
+                            Some(color) => piece.style(color).into(),
+                            None => piece.into(),
+                        }
+                    }))
+                    .into()
+                });
+                col = col.push(wide(Column::with_children(lines)));
+            } else if let Some(lines) = &preview.text {
+                col = col.push(
+                    container(text(lines).size(style.small_text_size))
+                        .padding(5)
+                        .width(Length::Fill)
+                        .style(theme::Container::Box),
+                );
+            }
+            if let Some(hex) = &preview.hex {
+                col = col.push(wide(
+                    text(hex_dump(hex))
+                        .font(Font::MONOSPACE)
+                        .size(style.small_text_size),
+                ));
+            }
+        }
+
+        scrollable(col)
+            .height(Length::Fill)
+            .on_scroll(|viewport| Message::PreviewScrolled(viewport.relative_offset().y))
+            .into()
+    }
+
+    /// The picture across the window, clicked away.
+    pub fn viewer(&self) -> Option<Element<'_, Message>> {
+        let handle = self.handle().filter(|_| self.viewing)?;
+        Some(
+            button(
+                image(handle.clone())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .content_fit(ContentFit::ScaleDown),
+            )
+            .style(theme::Button::Text)
+            .padding(20)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press(Message::View(false))
+            .into(),
+        )
+    }
+}
+
+/// What is too wide for the preview, boxed and scrolled across.
+fn wide<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    container(
+        scrollable(container(content).padding(5))
+            .direction(Direction::Horizontal(Properties::default())),
+    )
+    .width(Length::Fill)
+    .style(theme::Container::Box)
+    .into()
+}
+
+/// The folders above `dir` that get a column, outermost first.
+fn ancestors(dir: &Path) -> Vec<PathBuf> {
+    let mut ancestors: Vec<PathBuf> = dir
+        .ancestors()
+        .skip(1)
+        .take(ANCESTORS)
+        .map(Path::to_path_buf)
+        .collect();
+    ancestors.reverse();
+
+    ancestors
+}
+
+/// Runs `work` on a thread and sends what it gives.
+async fn blocking(
+    mut output: mpsc::Sender<Message>,
+    work: impl FnOnce() -> Message + Send + 'static,
+) -> std::convert::Infallible {
+    use iced::futures::channel::oneshot;
+
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || sender.send(work()));
+    if let Ok(message) = receiver.await {
+        let _ = output.send(message).await;
+    }
+
+    std::future::pending().await
+}
+
+/// Folders first, then files, by name, leaving out hidden ones.
+fn list(dir: &Path) -> Vec<Entry> {
+    let entries = match vfs::for_path(dir).read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::debug!(%error, dir = %dir.display(), "cannot list folder for a column");
+            return Vec::new();
+        }
+    };
+    let mut entries: Vec<Entry> = entries
+        .into_iter()
+        .flatten()
+        .map(|path| Entry {
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            is_dir: path.is_dir(),
+            path,
+        })
+        .filter(|entry| !entry.name.starts_with('.'))
+        .collect();
+    entries.sort_by_cached_key(|entry| (!entry.is_dir, collation::key(&entry.name)));
+
+    entries
+}
+
+fn preview(path: &Path, low_memory: bool) -> Preview {
+    let fs = vfs::for_path(path);
+    let size = fs.metadata(path).map_or(0, |metadata| metadata.len);
+    let mut bytes = Vec::new();
+    let read = fs
+        .open_read(path)
+        .and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut bytes));
+    let detected = read
+        .ok()
+        .and_then(|_| encoding::detect(&bytes, size <= PREVIEW_BYTES));
+    let text = detected.and_then(|detected| {
+        let text = encoding::decode(&bytes, detected);
+        let lines: Vec<&str> = text.lines().take(PREVIEW_LINES).collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    });
+
+    let dimensions = fs
+        .open_read(path)
+        .ok()
+        .and_then(|mut file| images::dimensions(&bytes, &mut file));
+    // Reading it takes as much memory as it holds.
+    let embedded = (images::is_raw(path) && !low_memory)
+        .then(|| images::embedded_preview(path).ok().flatten())
+        .flatten()
+        .and_then(|jpeg| images::dimensions(&jpeg, &mut io::Cursor::new(&jpeg)));
+
+    // PDFs and videos get a picture or an icon instead.
+    let media = images::is_pdf(path) || images::is_video(path);
+    let hex = (detected.is_none() && dimensions.is_none() && embedded.is_none() && !media)
+        .then(|| bytes[..bytes.len().min(HEX_CHUNK as usize)].to_vec())
+        .filter(|hex| !hex.is_empty());
+
+    // The size is shown already.
+    let media = media::read(path).map(|media| Media {
+        resolution: media.resolution.filter(|_| dimensions.is_none()),
+        ..media
+    });
+
+    Preview {
+        size,
+        text,
+        hex,
+        encoding: detected,
+        dimensions,
+        embedded,
+        media,
+    }
+}
+
+/// The next `HEX_CHUNK` bytes of `path` from `offset`.
+fn read_hex(path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+    let mut file = vfs::for_path(path).open_read(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(HEX_CHUNK).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Sixteen bytes a line after their offset, in hex and then as ASCII, with
+/// dots for what is not printable.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (index, chunk) in bytes.chunks(16).enumerate() {
+        if index > 0 {
+            dump.push('\n');
+        }
+        dump.push_str(&format!("{:08x} ", index * 16));
+        for column in 0..16 {
+            if column == 8 {
+                dump.push(' ');
+            }
+            match chunk.get(column) {
+                Some(byte) => dump.push_str(&format!(" {:02x}", byte)),
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(chunk.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        dump.push('|');
+    }
+    dump
+}
@@ -0,0 +1,452 @@
+//! "Compare folders": the current folder against another one, entry by
+//! entry all the way down, by name, size and modification time. What is
+//! only on one side or newer there can then be copied across, one way or
+//! both, as ordinary copy jobs. Syncing never deletes anything.
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use iced::widget::{button, checkbox, column, container, row, scrollable, text, text_input};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::dates::format_time;
+use crate::devices;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::paths;
+use crate::style::Style;
+
+/// Modification times closer than this count as the same, since FAT
+/// drives keep them to two seconds.
+const SLACK: Duration = Duration::from_secs(2);
+/// Rows shown at once.
+const SHOWN: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    OnlyLeft,
+    OnlyRight,
+    LeftNewer,
+    RightNewer,
+    /// Same time but not the same size, or a file on one side and a
+    /// folder on the other.
+    Differs,
+    Same,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    ToRight,
+    ToLeft,
+    Both,
+}
+
+#[derive(Debug, Clone)]
+pub struct Side {
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Below both folders.
+    path: PathBuf,
+    left: Option<Side>,
+    right: Option<Side>,
+    status: Status,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Other(String),
+    Compare,
+    Compared(usize, Result<Vec<Entry>, String>),
+    ShowSame(bool),
+    Sync(Direction),
+    Close,
+}
+
+pub enum Event {
+    /// Copies to make, sources grouped by the folder they go into.
+    Sync(Vec<(Vec<PathBuf>, PathBuf)>),
+    Closed,
+}
+
+enum State {
+    Idle,
+    Comparing,
+    /// Waiting for the copies to finish, to compare again.
+    Syncing,
+    Done(Vec<Entry>),
+    Failed(String),
+}
+
+pub struct Compare {
+    left: PathBuf,
+    other: String,
+    /// The folder `other` named when comparing started.
+    right: PathBuf,
+    /// Counts comparisons so a new one ignores what an earlier one sends.
+    scan: usize,
+    stop: Arc<AtomicBool>,
+    state: State,
+    show_same: bool,
+}
+
+impl Compare {
+    pub fn open(left: PathBuf, other: Option<PathBuf>) -> Compare {
+        Compare {
+            left,
+            other: other
+                .map(|other| other.display().to_string())
+                .unwrap_or_default(),
+            right: PathBuf::new(),
+            scan: 0,
+            stop: Arc::new(AtomicBool::new(false)),
+            state: State::Idle,
+            show_same: false,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Other(other) => self.other = other,
+            Message::Compare => match paths::expand(&self.other, &self.left) {
+                Ok(right) if !right.is_dir() => {
+                    self.state = State::Failed(format!("{} is not a folder", right.display()))
+                }
+                Ok(right) if paths::same(&right, &self.left) => {
+                    self.state = State::Failed(String::from("That is this folder"))
+                }
+                Ok(right) => {
+                    self.right = right;
+                    self.start();
+                }
+                Err(error) => self.state = State::Failed(error),
+            },
+            Message::Compared(scan, result) if scan == self.scan => {
+                if let State::Comparing = self.state {
+                    self.state = match result {
+                        Ok(entries) => State::Done(entries),
+                        Err(error) => State::Failed(error),
+                    };
+                }
+            }
+            Message::Compared(..) => {}
+            Message::ShowSame(show) => self.show_same = show,
+            Message::Sync(direction) => {
+                let copies = self.copies(direction);
+                if !copies.is_empty() {
+                    self.state = State::Syncing;
+                    return Some(Event::Sync(copies));
+                }
+            }
+            Message::Close => {
+                self.stop.store(true, Ordering::Relaxed);
+                return Some(Event::Closed);
+            }
+        }
+
+        None
+    }
+
+    fn start(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.stop = Arc::new(AtomicBool::new(false));
+        self.scan += 1;
+        self.state = State::Comparing;
+    }
+
+    /// Compares again after jobs finished, as they may have changed either
+    /// side.
+    pub fn refresh(&mut self) {
+        if let State::Syncing | State::Done(_) = self.state {
+            self.start();
+        }
+    }
+
+    fn entries(&self) -> &[Entry] {
+        match &self.state {
+            State::Done(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    /// The entries copied going `direction`, each with the side it comes
+    /// from.
+    fn copied(&self, direction: Direction) -> impl Iterator<Item = (&Entry, bool)> {
+        self.entries().iter().filter_map(move |entry| {
+            let from_left = match (direction, entry.status) {
+                (_, Status::Same) => return None,
+                (Direction::ToRight, Status::OnlyLeft | Status::LeftNewer | Status::Differs) => {
+                    true
+                }
+                (Direction::ToLeft, Status::OnlyRight | Status::RightNewer | Status::Differs) => {
+                    false
+                }
+                (Direction::Both, Status::OnlyLeft | Status::LeftNewer) => true,
+                (Direction::Both, Status::OnlyRight | Status::RightNewer) => false,
+                _ => return None,
+            };
+            Some((entry, from_left))
+        })
+    }
+
+    fn copies(&self, direction: Direction) -> Vec<(Vec<PathBuf>, PathBuf)> {
+        let mut copies: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        for (entry, from_left) in self.copied(direction) {
+            let (from, to) = if from_left {
+                (&self.left, &self.right)
+            } else {
+                (&self.right, &self.left)
+            };
+            let into = match entry.path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => to.join(parent),
+                _ => to.clone(),
+            };
+            copies.entry(into).or_default().push(from.join(&entry.path));
+        }
+
+        copies
+            .into_iter()
+            .map(|(into, sources)| (sources, into))
+            .collect()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Comparing;
+
+        if !matches!(self.state, State::Comparing) {
+            return Subscription::none();
+        }
+
+        let (scan, left, right, stop) = (
+            self.scan,
+            self.left.clone(),
+            self.right.clone(),
+            self.stop.clone(),
+        );
+        subscription::channel(
+            (TypeId::of::<Comparing>(), scan),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let Some(_turn) =
+                        devices::wait_for(&[&left, &right], || stop.load(Ordering::Relaxed))
+                    else {
+                        return;
+                    };
+                    let mut entries = Vec::new();
+                    let result = compare(&left, &right, Path::new(""), &stop, &mut entries)
+                        .map(|()| entries);
+                    let _ = sender.send(result);
+                });
+                if let Ok(result) = receiver.await {
+                    let _ = output.send(Message::Compared(scan, result)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let comparing = matches!(self.state, State::Comparing);
+        let header = row!(
+            text(self.left.display().to_string()),
+            text(tr("with")),
+            text_input(tr("Folder to compare with"), &self.other)
+                .on_input(Message::Other)
+                .on_submit(Message::Compare)
+                .padding(5),
+            button(tr("Compare")).on_press_maybe((!comparing).then_some(Message::Compare)),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let body: Element<Message> = match &self.state {
+            State::Idle => text(tr("Pick a folder to compare this one with")).into(),
+            State::Comparing => text(tr("Comparing…")).into(),
+            State::Syncing => text(tr("Copying…")).into(),
+            State::Failed(error) => text(error).style(theme::Text::Color(style.error)).into(),
+            State::Done(entries) => {
+                let shown = entries
+                    .iter()
+                    .filter(|entry| self.show_same || entry.status != Status::Same);
+                let mut col = column!().spacing(2);
+                let mut count = 0;
+                for entry in shown {
+                    count += 1;
+                    if count <= SHOWN {
+                        col = col.push(self.row(entry, style));
+                    }
+                }
+                if count == 0 {
+                    col = col.push(text(tr("Both folders hold the same")));
+                } else if count > SHOWN {
+                    col = col.push(
+                        text(trf("and {} more", &[&(count - SHOWN)])).size(style.small_text_size),
+                    );
+                }
+                scrollable(col).height(Length::Fill).into()
+            }
+        };
+
+        let sync = |label: &str, direction| {
+            let count = self.copied(direction).count();
+            button(text(format!("{} ({})", label, count)))
+                .on_press_maybe((count > 0).then_some(Message::Sync(direction)))
+        };
+        let buttons = row!(
+            checkbox(tr("Show identical"), self.show_same).on_toggle(Message::ShowSame),
+            text("").width(Length::Fill),
+            sync("Copy to the right →", Direction::ToRight),
+            sync("← Copy to the left", Direction::ToLeft),
+            sync("Sync both ways", Direction::Both),
+            button(tr("Close")).on_press(Message::Close),
+        )
+        .spacing(5)
+        .align_items(Alignment::Center);
+
+        container(
+            column!(
+                text(tr("Compare folders")).size(20),
+                header,
+                container(body).height(Length::Fill),
+                text(tr("Newer files replace older ones; nothing is deleted."))
+                    .size(style.small_text_size),
+                buttons,
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .max_width(1000)
+        .max_height(700)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    fn row<'a>(&self, entry: &'a Entry, style: &Style) -> Element<'a, Message> {
+        let side = |side: &Option<Side>| -> Element<'a, Message> {
+            let label = match side {
+                None => String::from("—"),
+                Some(side) if side.is_dir => String::from("Folder"),
+                Some(side) => format!(
+                    "{}  {}",
+                    format_bytes(side.size),
+                    format_time(side.modified.ok_or_else(|| std::io::Error::other("")))
+                ),
+            };
+            text(label)
+                .size(style.small_text_size)
+                .width(Length::FillPortion(2))
+                .into()
+        };
+        let (mark, color) = match entry.status {
+            Status::OnlyLeft => ("←", Some(style.warning)),
+            Status::OnlyRight => ("→", Some(style.warning)),
+            Status::LeftNewer => ("newer ←", None),
+            Status::RightNewer => ("→ newer", None),
+            Status::Differs => ("differs", Some(style.error)),
+            Status::Same => ("=", Some(style.dimmed)),
+        };
+        let name = match (&entry.left, &entry.right) {
+            (Some(side), _) | (None, Some(side)) if side.is_dir => {
+                format!("{}/", entry.path.display())
+            }
+            _ => entry.path.display().to_string(),
+        };
+        let mut mark = text(mark)
+            .size(style.small_text_size)
+            .width(Length::Fixed(70.));
+        if let Some(color) = color {
+            mark = mark.style(theme::Text::Color(color));
+        }
+
+        row!(
+            text(name).width(Length::FillPortion(3)),
+            side(&entry.left),
+            mark,
+            side(&entry.right),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    }
+}
+
+/// Compares what is at `below` in `left` and `right`, going into folders
+/// on both sides. A folder only on one side is a single entry.
+fn compare(
+    left: &Path,
+    right: &Path,
+    below: &Path,
+    stop: &AtomicBool,
+    entries: &mut Vec<Entry>,
+) -> Result<(), String> {
+    if stop.load(Ordering::Relaxed) {
+        return Err(String::from("Cancelled"));
+    }
+
+    let mut names: BTreeMap<std::ffi::OsString, (Option<Side>, Option<Side>)> = BTreeMap::new();
+    for (dir, on_left) in [(left, true), (right, false)] {
+        let listed = fs::read_dir(dir.join(below))
+            .map_err(|error| trf("Cannot read {}: {}", &[&dir.join(below).display(), &error]))?;
+        for entry in listed.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let side = Side {
+                is_dir: metadata.is_dir(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            };
+            let sides = names.entry(entry.file_name()).or_default();
+            if on_left {
+                sides.0 = Some(side);
+            } else {
+                sides.1 = Some(side);
+            }
+        }
+    }
+
+    for (name, (left_side, right_side)) in names {
+        let path = below.join(&name);
+        let status = match (&left_side, &right_side) {
+            (Some(_), None) => Status::OnlyLeft,
+            (None, Some(_)) => Status::OnlyRight,
+            (Some(l), Some(r)) if l.is_dir && r.is_dir => {
+                compare(left, right, &path, stop, entries)?;
+                continue;
+            }
+            (Some(l), Some(r)) if l.is_dir != r.is_dir => Status::Differs,
+            (Some(l), Some(r)) => match (l.modified, r.modified) {
+                (Some(lm), Some(rm)) if lm > rm + SLACK => Status::LeftNewer,
+                (Some(lm), Some(rm)) if rm > lm + SLACK => Status::RightNewer,
+                _ if l.size == r.size => Status::Same,
+                _ => Status::Differs,
+            },
+            (None, None) => continue,
+        };
+        entries.push(Entry {
+            path,
+            left: left_side,
+            right: right_side,
+            status,
+        });
+    }
+
+    Ok(())
+}
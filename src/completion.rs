@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use iced::widget::{button, column, container, text};
+use iced::{theme, Element, Length};
+
+use crate::collation;
+use crate::paths;
+
+const MAX_SHOWN: usize = 8;
+
+/// Directory-name completions for the last segment of a typed path.
+#[derive(Default)]
+pub struct Completions {
+    /// The directory whose children `names` holds, read once per directory
+    /// rather than on every keystroke.
+    dir: Option<PathBuf>,
+    names: Vec<String>,
+    matches: Vec<String>,
+    selected: Option<usize>,
+}
+
+impl Completions {
+    /// `base` is what relative input is taken against.
+    pub fn update(&mut self, input: &str, base: &Path) {
+        let Some((dir, prefix)) = split(input) else {
+            self.clear();
+            return;
+        };
+
+        let Ok(dir) = paths::expand(dir, base) else {
+            self.clear();
+            return;
+        };
+        if self.dir.as_ref() != Some(&dir) {
+            self.names = fs::read_dir(&dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .filter(|entry| entry.path().is_dir())
+                        .map(|entry| entry.file_name().to_string_lossy().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.names.sort_by_cached_key(|name| collation::key(name));
+            self.dir = Some(dir);
+        }
+
+        let prefix = prefix.to_lowercase();
+        self.matches = self
+            .names
+            .iter()
+            .filter(|name| prefix.starts_with('.') || !name.starts_with('.'))
+            .filter(|name| name.to_lowercase().starts_with(&prefix))
+            .take(MAX_SHOWN)
+            .cloned()
+            .collect();
+        self.selected = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.dir = None;
+        self.names.clear();
+        self.matches.clear();
+        self.selected = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+
+        let last = self.matches.len() as isize - 1;
+        self.selected = Some(match self.selected {
+            Some(selected) => (selected as isize + delta).clamp(0, last) as usize,
+            None if delta < 0 => last as usize,
+            None => 0,
+        });
+    }
+
+    /// `input` with its last segment replaced by a completion (`index` if
+    /// given, else the highlighted one, else the first) and a trailing
+    /// separator so the next segment can be completed right away.
+    pub fn accept(&self, input: &str, index: Option<usize>) -> Option<String> {
+        let (dir, _) = split(input)?;
+        let name = self.matches.get(index.or(self.selected).unwrap_or(0))?;
+
+        Some(format!("{}{}{}", dir, name, MAIN_SEPARATOR))
+    }
+
+    pub fn view(&self) -> Element<'_, usize> {
+        let mut col = column!();
+        for (index, name) in self.matches.iter().enumerate() {
+            let style = if self.selected == Some(index) {
+                theme::Button::Primary
+            } else {
+                theme::Button::Text
+            };
+            col = col.push(
+                button(text(name))
+                    .style(style)
+                    .on_press(index)
+                    .width(Length::Fill),
+            );
+        }
+
+        container(col)
+            .width(Length::Fill)
+            .padding(5)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+/// Splits after the last separator: the directory part (separator kept) and
+/// the segment being typed.
+fn split(input: &str) -> Option<(&str, &str)> {
+    let separator = if cfg!(windows) {
+        input.rfind(['/', '\\'])
+    } else {
+        input.rfind('/')
+    }?;
+
+    Some(input.split_at(separator + 1))
+}
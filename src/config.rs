@@ -0,0 +1,991 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use iced::theme::Palette;
+use iced::{Color, Theme};
+use toml_edit::{table, value, Array, ArrayOfTables, Document, Item, Table};
+
+use crate::dirs;
+use crate::hot_folders::{self, Action, Rule};
+use crate::i18n::{tr, trf};
+use crate::jobs;
+use crate::keymap::Preset;
+use crate::normalize;
+use crate::paths;
+use crate::safe_save;
+use crate::search;
+use crate::webdav::Account;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// How many folders keep a view of their own, the least recently changed
+/// forgotten past it.
+const FOLDER_VIEWS: usize = 500;
+
+/// What browsing into a symlinked folder does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Symlinks {
+    /// Stays under the link's own path, `..` going back where it is.
+    #[default]
+    Keep,
+    /// Goes to the folder it points to.
+    Resolve,
+}
+
+/// How the current folder is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    #[default]
+    List,
+    /// Its ancestors as columns before it, as macOS Finder does.
+    Columns,
+}
+
+impl ViewMode {
+    pub const ALL: [ViewMode; 2] = [ViewMode::List, ViewMode::Columns];
+
+    fn name(self) -> &'static str {
+        match self {
+            ViewMode::List => "list",
+            ViewMode::Columns => "columns",
+        }
+    }
+}
+
+impl std::fmt::Display for ViewMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ViewMode::List => tr("List"),
+            ViewMode::Columns => tr("Columns"),
+        })
+    }
+}
+
+/// What the Size column shows for folders, whose own size from the
+/// filesystem says nothing about what they hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FolderSize {
+    #[default]
+    Blank,
+    Items,
+    /// Of everything inside, worked out once and kept.
+    Total,
+}
+
+impl FolderSize {
+    pub const ALL: [FolderSize; 3] = [FolderSize::Blank, FolderSize::Items, FolderSize::Total];
+}
+
+impl std::fmt::Display for FolderSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FolderSize::Blank => tr("Nothing"),
+            FolderSize::Items => tr("Number of items"),
+            FolderSize::Total => tr("Total size"),
+        })
+    }
+}
+
+/// How sizes are written: in steps of 1024 as most systems do, or of 1000
+/// as disks are sold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeUnits {
+    #[default]
+    Binary,
+    Decimal,
+}
+
+impl SizeUnits {
+    pub const ALL: [SizeUnits; 2] = [SizeUnits::Binary, SizeUnits::Decimal];
+}
+
+impl std::fmt::Display for SizeUnits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SizeUnits::Binary => tr("1 KB = 1024 bytes"),
+            SizeUnits::Decimal => tr("1 kB = 1000 bytes"),
+        })
+    }
+}
+
+/// How times are written: as ISO 8601 dates, as the user's locale does or
+/// by a pattern of their own, in `strftime`'s terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeFormat {
+    #[default]
+    Iso,
+    Locale,
+    Custom,
+}
+
+impl TimeFormat {
+    pub const ALL: [TimeFormat; 3] = [TimeFormat::Iso, TimeFormat::Locale, TimeFormat::Custom];
+}
+
+impl std::fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TimeFormat::Iso => tr("2024-01-31 14:05:00"),
+            TimeFormat::Locale => tr("As in the language"),
+            TimeFormat::Custom => tr("Custom"),
+        })
+    }
+}
+
+/// How many items sent to the trash or deleted at once are asked about
+/// first; none are when zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ManyItems(pub usize);
+
+impl ManyItems {
+    pub const ALL: [ManyItems; 6] = [
+        ManyItems(0),
+        ManyItems(10),
+        ManyItems(50),
+        ManyItems(100),
+        ManyItems(500),
+        ManyItems(1000),
+    ];
+}
+
+impl std::fmt::Display for ManyItems {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => f.write_str(tr("Never")),
+            many => f.write_str(&trf("More than {} items", &[&many])),
+        }
+    }
+}
+
+/// What draws the window: the GPU, preferring an integrated one, or the
+/// CPU, leaving the GPU idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Renderer {
+    /// Falls back to software when no adapter is found.
+    #[default]
+    Gpu,
+    LowPower,
+    Software,
+}
+
+impl Renderer {
+    pub const ALL: [Renderer; 3] = [Renderer::Gpu, Renderer::LowPower, Renderer::Software];
+
+    /// As in the file and in `ICED_FM_RENDERER`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Gpu => "gpu",
+            Renderer::LowPower => "low-power",
+            Renderer::Software => "software",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Renderer> {
+        Renderer::ALL
+            .into_iter()
+            .find(|renderer| renderer.name() == name)
+    }
+}
+
+impl std::fmt::Display for Renderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Renderer::Gpu => tr("Graphics card"),
+            Renderer::LowPower => tr("Integrated graphics"),
+            Renderer::Software => tr("Processor only"),
+        })
+    }
+}
+
+/// How many times a second background work may redraw the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxFps(pub u32);
+
+impl MaxFps {
+    pub const ALL: [MaxFps; 5] = [MaxFps(5), MaxFps(10), MaxFps(20), MaxFps(30), MaxFps(60)];
+}
+
+impl Default for MaxFps {
+    fn default() -> MaxFps {
+        MaxFps(10)
+    }
+}
+
+impl std::fmt::Display for MaxFps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&trf("{} times a second", &[&self.0]))
+    }
+}
+
+/// How names compare: as they read, in the user's language, or byte by
+/// byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameOrder {
+    #[default]
+    Natural,
+    Bytes,
+}
+
+impl NameOrder {
+    pub const ALL: [NameOrder; 2] = [NameOrder::Natural, NameOrder::Bytes];
+}
+
+impl std::fmt::Display for NameOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NameOrder::Natural => tr("Natural (file2 before file10)"),
+            NameOrder::Bytes => tr("Strict byte order"),
+        })
+    }
+}
+
+/// What the listing is ordered by, folders first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortBy {
+    #[default]
+    Name,
+    Size,
+    /// Latest first.
+    Modified,
+    /// The extension, then the name.
+    Type,
+}
+
+impl SortBy {
+    pub const ALL: [SortBy; 4] = [SortBy::Name, SortBy::Size, SortBy::Modified, SortBy::Type];
+
+    fn name(self) -> &'static str {
+        match self {
+            SortBy::Name => "name",
+            SortBy::Size => "size",
+            SortBy::Modified => "modified",
+            SortBy::Type => "type",
+        }
+    }
+}
+
+impl std::fmt::Display for SortBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SortBy::Name => tr("Name"),
+            SortBy::Size => tr("Size"),
+            SortBy::Modified => tr("Date modified"),
+            SortBy::Type => tr("Type"),
+        })
+    }
+}
+
+/// What the listing is split up under headings by, if anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupBy {
+    #[default]
+    None,
+    Letter,
+    Type,
+    Date,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 4] = [GroupBy::None, GroupBy::Letter, GroupBy::Type, GroupBy::Date];
+
+    fn name(self) -> &'static str {
+        match self {
+            GroupBy::None => "none",
+            GroupBy::Letter => "letter",
+            GroupBy::Type => "type",
+            GroupBy::Date => "date",
+        }
+    }
+}
+
+impl std::fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GroupBy::None => tr("Nothing"),
+            GroupBy::Letter => tr("First letter"),
+            GroupBy::Type => tr("Type"),
+            GroupBy::Date => tr("Date modified"),
+        })
+    }
+}
+
+/// How a folder is shown: the defaults, or its own once changed there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct View {
+    pub mode: ViewMode,
+    pub sort: SortBy,
+    pub group_by: GroupBy,
+}
+
+/// A palette of its own from a `[[color_scheme]]` table, offered with
+/// iced's themes by its name; colors are written as `"#rrggbb"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorScheme {
+    pub name: String,
+    /// `background`, `text`, `accent`, `success` and `danger`, the light
+    /// theme's for any not given.
+    pub palette: Palette,
+    /// Behind selected rows; the accent, faded, when not given.
+    pub selection: Option<Color>,
+    /// Behind every other row when rows alternate.
+    pub stripe: Option<Color>,
+}
+
+impl ColorScheme {
+    pub fn theme(&self) -> Theme {
+        Theme::custom(self.name.clone(), self.palette)
+    }
+}
+
+/// Application-wide defaults. Individual operations start from these and may
+/// override them.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub preserve_timestamps: bool,
+    /// Whether navigation goes through symlinks to where they point.
+    pub symlinks: Symlinks,
+    /// Let searches also ask the system file index: the locate database on
+    /// Linux, Spotlight on macOS.
+    pub use_system_index: bool,
+    /// Let searches use the NTFS master file table index (Windows only,
+    /// needs administrator rights).
+    pub use_ntfs_index: bool,
+    /// List the `._name` files macOS leaves on filesystems that cannot hold
+    /// resource forks.
+    pub show_apple_double: bool,
+    /// Leaves the folder tree out of the sidebar.
+    pub hide_folder_tree: bool,
+    pub hide_toolbar: bool,
+    pub hide_sidebar: bool,
+    pub hide_status_bar: bool,
+    /// Hides all three, whatever the above say.
+    pub distraction_free: bool,
+    pub view_mode: ViewMode,
+    pub folder_size: FolderSize,
+    pub theme: Theme,
+    /// Read from the file, never written back.
+    pub color_schemes: Vec<ColorScheme>,
+    /// Alternates the background of rows in the listing.
+    pub striped_rows: bool,
+    pub size_units: SizeUnits,
+    pub time_format: TimeFormat,
+    /// The `strftime` pattern of `TimeFormat::Custom`.
+    pub time_pattern: String,
+    /// Writes times of the last week as how long ago they were.
+    pub relative_times: bool,
+    /// Leaves out files and folders whose names start with a dot.
+    pub hide_hidden: bool,
+    /// Deletes for good without asking first.
+    pub delete_without_asking: bool,
+    pub ask_over: ManyItems,
+    /// Replaces what is in the way of a copy or move without asking.
+    pub overwrite_without_asking: bool,
+    /// Trashes, deletes and moves system folders without asking.
+    pub system_without_asking: bool,
+    pub sort: SortBy,
+    pub name_order: NameOrder,
+    pub group_by: GroupBy,
+    /// The folders shown otherwise than `view_mode`, `sort` and `group_by`
+    /// say, least recently changed first.
+    pub folder_views: Vec<(PathBuf, View)>,
+    /// Opens items on a double click, a single one only selecting them.
+    pub double_click: bool,
+    /// Smaller copy buffers and listing batches, and nothing cached between
+    /// listings or searches, for machines short on RAM.
+    pub low_memory: bool,
+    /// Read once at start, like `antialiasing`.
+    pub renderer: Renderer,
+    /// Smooths edges, at the cost of multisampling on the GPU.
+    pub antialiasing: bool,
+    pub max_fps: MaxFps,
+    /// Where the browser was when last closed, opened again next time.
+    pub last_dir: Option<PathBuf>,
+    /// The project open when last closed, and those opened before it,
+    /// latest first.
+    pub project: Option<PathBuf>,
+    pub recent_projects: Vec<PathBuf>,
+    /// `sftp://` and WebDAV folders shown in the sidebar.
+    pub remote_bookmarks: Vec<PathBuf>,
+    /// WebDAV servers to sign in to, passwords included, which is why the
+    /// file is kept from other users while there are any.
+    pub webdav: Vec<Account>,
+    /// The command opening a terminal, its words split at spaces; empty
+    /// for the one found on the system.
+    pub terminal: String,
+    pub keymap: Preset,
+    /// The `[keys]` table, by action name, as written: the file is only
+    /// ever edited by hand there.
+    pub keys: Vec<(String, Vec<String>)>,
+    /// The `[[hot_folder]]` rules, edited by hand.
+    pub hot_folders: Vec<hot_folders::Rule>,
+    /// What the background instance indexes; `None` for the home folder.
+    pub index_folders: Option<Vec<PathBuf>>,
+    /// The window's size in logical pixels when last closed.
+    pub window_size: Option<(f32, f32)>,
+    /// Where the window's top left was then, from that of
+    /// `window_monitor` when it was on a known display.
+    pub window_position: Option<(f32, f32)>,
+    pub window_monitor: Option<String>,
+}
+
+impl Config {
+    /// What `config.toml` in the config dir holds, or the defaults for
+    /// anything missing or unreadable.
+    pub fn load() -> Config {
+        let Some(file) = file() else {
+            return Config::default();
+        };
+        let document = match fs::read_to_string(&file) {
+            Ok(text) => text.parse::<Document>().map_err(io::Error::other),
+            Err(error) => Err(error),
+        };
+        let document = match document {
+            Ok(document) => document,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    tracing::warn!(%error, file = %file.display(), "cannot read the configuration");
+                }
+                return Config::default();
+            }
+        };
+
+        let flag = |key| document.get(key).and_then(Item::as_bool).unwrap_or(false);
+        let session = |key| document.get("session")?.get(key);
+        let pixels = |key| {
+            let item = document.get("window")?.get(key)?;
+            item.as_float()
+                .or_else(|| item.as_integer().map(|pixels| pixels as f64))
+                .filter(|&pixels| pixels > 0.)
+                .map(|pixels| pixels as f32)
+        };
+        let offset = |key| {
+            let item = document.get("window")?.get(key)?;
+            item.as_float()
+                .or_else(|| item.as_integer().map(|pixels| pixels as f64))
+                .map(|pixels| pixels as f32)
+        };
+
+        let color_schemes: Vec<ColorScheme> = document
+            .get("color_scheme")
+            .and_then(Item::as_array_of_tables)
+            .map(|schemes| {
+                schemes
+                    .iter()
+                    .filter_map(|scheme| {
+                        let color = |key| scheme.get(key).and_then(Item::as_str).and_then(color);
+                        let light = Palette::LIGHT;
+                        Some(ColorScheme {
+                            name: scheme.get("name")?.as_str()?.to_string(),
+                            palette: Palette {
+                                background: color("background").unwrap_or(light.background),
+                                text: color("text").unwrap_or(light.text),
+                                primary: color("accent").unwrap_or(light.primary),
+                                success: color("success").unwrap_or(light.success),
+                                danger: color("danger").unwrap_or(light.danger),
+                            },
+                            selection: color("selection"),
+                            stripe: color("stripe"),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let theme = document
+            .get("theme")
+            .and_then(Item::as_str)
+            .and_then(|name| {
+                color_schemes
+                    .iter()
+                    .find(|scheme| scheme.name == name)
+                    .map(ColorScheme::theme)
+                    .or_else(|| {
+                        Theme::ALL
+                            .iter()
+                            .find(|theme| theme.to_string() == name)
+                            .cloned()
+                    })
+            });
+
+        Config {
+            preserve_timestamps: flag("preserve_timestamps"),
+            // Once a flag, read while `symlinks` is not written yet.
+            symlinks: match document.get("symlinks").and_then(Item::as_str) {
+                Some("resolve") => Symlinks::Resolve,
+                Some(_) => Symlinks::Keep,
+                None if flag("resolve_symlinks") => Symlinks::Resolve,
+                None => Symlinks::Keep,
+            },
+            use_system_index: flag("use_system_index"),
+            use_ntfs_index: flag("use_ntfs_index"),
+            show_apple_double: flag("show_apple_double"),
+            hide_folder_tree: flag("hide_folder_tree"),
+            hide_toolbar: flag("hide_toolbar"),
+            hide_sidebar: flag("hide_sidebar"),
+            hide_status_bar: flag("hide_status_bar"),
+            distraction_free: flag("distraction_free"),
+            view_mode: document
+                .get("view")
+                .and_then(Item::as_str)
+                .and_then(view_mode)
+                .unwrap_or_default(),
+            folder_size: match document.get("folder_size").and_then(Item::as_str) {
+                Some("items") => FolderSize::Items,
+                Some("total") => FolderSize::Total,
+                _ => FolderSize::Blank,
+            },
+            theme: theme.unwrap_or_default(),
+            color_schemes,
+            striped_rows: flag("striped_rows"),
+            size_units: match document.get("size_units").and_then(Item::as_str) {
+                Some("decimal") => SizeUnits::Decimal,
+                _ => SizeUnits::Binary,
+            },
+            time_format: match document.get("time_format").and_then(Item::as_str) {
+                Some("locale") => TimeFormat::Locale,
+                Some("custom") => TimeFormat::Custom,
+                _ => TimeFormat::Iso,
+            },
+            time_pattern: document
+                .get("time_pattern")
+                .and_then(Item::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            relative_times: flag("relative_times"),
+            hide_hidden: flag("hide_hidden"),
+            delete_without_asking: flag("delete_without_asking"),
+            ask_over: ManyItems(
+                document
+                    .get("ask_over")
+                    .and_then(Item::as_integer)
+                    .map_or(0, |many| many.max(0) as usize),
+            ),
+            overwrite_without_asking: flag("overwrite_without_asking"),
+            system_without_asking: flag("system_without_asking"),
+            sort: document
+                .get("sort")
+                .and_then(Item::as_str)
+                .and_then(sort)
+                .unwrap_or_default(),
+            name_order: match document.get("name_order").and_then(Item::as_str) {
+                Some("bytes") => NameOrder::Bytes,
+                _ => NameOrder::Natural,
+            },
+            group_by: document
+                .get("group_by")
+                .and_then(Item::as_str)
+                .and_then(group_by)
+                .unwrap_or_default(),
+            folder_views: document
+                .get("folder_view")
+                .and_then(Item::as_array_of_tables)
+                .map(|views| {
+                    views
+                        .iter()
+                        .filter_map(|view| {
+                            let field = |key| view.get(key).and_then(Item::as_str);
+                            Some((
+                                PathBuf::from(field("path")?),
+                                View {
+                                    mode: field("view").and_then(view_mode).unwrap_or_default(),
+                                    sort: field("sort").and_then(sort).unwrap_or_default(),
+                                    group_by: field("group_by")
+                                        .and_then(group_by)
+                                        .unwrap_or_default(),
+                                },
+                            ))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            double_click: flag("double_click"),
+            low_memory: flag("low_memory"),
+            renderer: document
+                .get("renderer")
+                .and_then(Item::as_str)
+                .and_then(Renderer::from_name)
+                .unwrap_or_default(),
+            antialiasing: flag("antialiasing"),
+            max_fps: document
+                .get("max_fps")
+                .and_then(Item::as_integer)
+                .filter(|&fps| fps > 0)
+                .map_or_else(MaxFps::default, |fps| MaxFps(fps.min(1000) as u32)),
+            last_dir: session("last_dir")
+                .and_then(Item::as_str)
+                .map(PathBuf::from),
+            project: session("project").and_then(Item::as_str).map(PathBuf::from),
+            recent_projects: session("recent_projects")
+                .and_then(Item::as_array)
+                .map(|projects| {
+                    projects
+                        .iter()
+                        .filter_map(|project| project.as_str())
+                        .map(PathBuf::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            remote_bookmarks: document
+                .get("remote_bookmarks")
+                .and_then(Item::as_array)
+                .map(|bookmarks| {
+                    bookmarks
+                        .iter()
+                        .filter_map(|bookmark| bookmark.as_str())
+                        .map(|bookmark| {
+                            normalize::for_navigation(Path::new(bookmark), Symlinks::Keep)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            webdav: document
+                .get("webdav")
+                .and_then(Item::as_array_of_tables)
+                .map(|accounts| {
+                    accounts
+                        .iter()
+                        .filter_map(|account| {
+                            let field = |key| account.get(key).and_then(Item::as_str);
+                            Some(Account {
+                                url: field("url")?.to_string(),
+                                user: field("user").unwrap_or_default().to_string(),
+                                password: field("password").unwrap_or_default().to_string(),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            terminal: document
+                .get("terminal")
+                .and_then(Item::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            keymap: document
+                .get("keymap")
+                .and_then(Item::as_str)
+                .and_then(Preset::from_name)
+                .unwrap_or_default(),
+            keys: document
+                .get("keys")
+                .and_then(Item::as_table_like)
+                .map(|keys| {
+                    keys.iter()
+                        .map(|(name, item)| {
+                            let texts = match item.as_array() {
+                                Some(texts) => texts
+                                    .iter()
+                                    .filter_map(|text| text.as_str())
+                                    .map(String::from)
+                                    .collect(),
+                                None => item.as_str().map(String::from).into_iter().collect(),
+                            };
+                            (name.to_string(), texts)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            hot_folders: document
+                .get("hot_folder")
+                .and_then(Item::as_array_of_tables)
+                .map(|rules| {
+                    rules
+                        .iter()
+                        .filter_map(|rule| {
+                            let field = |key| rule.get(key).and_then(Item::as_str);
+                            Some(Rule {
+                                folder: folder(field("folder")?)?,
+                                pattern: field("pattern").unwrap_or("*").to_string(),
+                                action: Action::from_name(field("action")?)?,
+                                to: folder(field("to")?)?,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            index_folders: document
+                .get("index_folders")
+                .and_then(Item::as_array)
+                .map(|folders| {
+                    folders
+                        .iter()
+                        .filter_map(|item| folder(item.as_str()?))
+                        .collect()
+                }),
+            window_size: pixels("width").zip(pixels("height")),
+            window_position: offset("x").zip(offset("y")),
+            window_monitor: document
+                .get("window")
+                .and_then(|window| window.get("monitor"))
+                .and_then(Item::as_str)
+                .map(String::from),
+        }
+    }
+
+    /// Writes `config.toml`, keeping whatever else the file holds,
+    /// comments included.
+    pub fn save(&self) -> io::Result<()> {
+        let file = file()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no configuration directory"))?;
+        let mut document = fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| text.parse::<Document>().ok())
+            .unwrap_or_default();
+
+        document["preserve_timestamps"] = value(self.preserve_timestamps);
+        document.remove("resolve_symlinks");
+        document["symlinks"] = value(match self.symlinks {
+            Symlinks::Keep => "keep",
+            Symlinks::Resolve => "resolve",
+        });
+        document["use_system_index"] = value(self.use_system_index);
+        document["use_ntfs_index"] = value(self.use_ntfs_index);
+        document["show_apple_double"] = value(self.show_apple_double);
+        document["hide_folder_tree"] = value(self.hide_folder_tree);
+        document["hide_toolbar"] = value(self.hide_toolbar);
+        document["hide_sidebar"] = value(self.hide_sidebar);
+        document["hide_status_bar"] = value(self.hide_status_bar);
+        document["distraction_free"] = value(self.distraction_free);
+        document["view"] = value(self.view_mode.name());
+        document["folder_size"] = value(match self.folder_size {
+            FolderSize::Blank => "blank",
+            FolderSize::Items => "items",
+            FolderSize::Total => "total",
+        });
+        document["theme"] = value(self.theme.to_string());
+        document["size_units"] = value(match self.size_units {
+            SizeUnits::Binary => "binary",
+            SizeUnits::Decimal => "decimal",
+        });
+        document["time_format"] = value(match self.time_format {
+            TimeFormat::Iso => "iso",
+            TimeFormat::Locale => "locale",
+            TimeFormat::Custom => "custom",
+        });
+        document["time_pattern"] = value(self.time_pattern.as_str());
+        document["relative_times"] = value(self.relative_times);
+        document["striped_rows"] = value(self.striped_rows);
+        document["hide_hidden"] = value(self.hide_hidden);
+        document["delete_without_asking"] = value(self.delete_without_asking);
+        document["ask_over"] = value(self.ask_over.0 as i64);
+        document["overwrite_without_asking"] = value(self.overwrite_without_asking);
+        document["system_without_asking"] = value(self.system_without_asking);
+        document["sort"] = value(self.sort.name());
+        document["group_by"] = value(self.group_by.name());
+        document["name_order"] = value(match self.name_order {
+            NameOrder::Natural => "natural",
+            NameOrder::Bytes => "bytes",
+        });
+        let mut views = ArrayOfTables::new();
+        for (path, view) in &self.folder_views {
+            let mut entry = Table::new();
+            entry["path"] = value(path.to_string_lossy().as_ref());
+            entry["view"] = value(view.mode.name());
+            entry["sort"] = value(view.sort.name());
+            entry["group_by"] = value(view.group_by.name());
+            views.push(entry);
+        }
+        document["folder_view"] = Item::ArrayOfTables(views);
+        document["double_click"] = value(self.double_click);
+        document["low_memory"] = value(self.low_memory);
+        document["renderer"] = value(self.renderer.name());
+        document["antialiasing"] = value(self.antialiasing);
+        document["max_fps"] = value(i64::from(self.max_fps.0));
+        let bookmarks: Array = self
+            .remote_bookmarks
+            .iter()
+            .map(|bookmark| bookmark.to_string_lossy().to_string())
+            .collect();
+        document["remote_bookmarks"] = value(bookmarks);
+        let mut accounts = ArrayOfTables::new();
+        for account in &self.webdav {
+            let mut entry = Table::new();
+            entry["url"] = value(account.url.as_str());
+            entry["user"] = value(account.user.as_str());
+            entry["password"] = value(account.password.as_str());
+            accounts.push(entry);
+        }
+        document["webdav"] = Item::ArrayOfTables(accounts);
+        document["terminal"] = value(self.terminal.as_str());
+        document["keymap"] = value(self.keymap.name());
+
+        if !document.contains_table("session") {
+            document["session"] = table();
+        }
+        if let Some(dir) = &self.last_dir {
+            document["session"]["last_dir"] = value(dir.to_string_lossy().as_ref());
+        }
+        match &self.project {
+            Some(project) => {
+                document["session"]["project"] = value(project.to_string_lossy().as_ref())
+            }
+            None => {
+                if let Some(session) = document["session"].as_table_mut() {
+                    session.remove("project");
+                }
+            }
+        }
+        let recent: Array = self
+            .recent_projects
+            .iter()
+            .map(|project| project.to_string_lossy().to_string())
+            .collect();
+        document["session"]["recent_projects"] = value(recent);
+        if let Some((width, height)) = self.window_size {
+            if !document.contains_table("window") {
+                document["window"] = table();
+            }
+            document["window"]["width"] = value(width.round() as i64);
+            document["window"]["height"] = value(height.round() as i64);
+        }
+        if let Some((x, y)) = self.window_position {
+            if !document.contains_table("window") {
+                document["window"] = table();
+            }
+            document["window"]["x"] = value(x.round() as i64);
+            document["window"]["y"] = value(y.round() as i64);
+            match &self.window_monitor {
+                Some(monitor) => document["window"]["monitor"] = value(monitor.as_str()),
+                None => {
+                    if let Some(window) = document["window"].as_table_mut() {
+                        window.remove("monitor");
+                    }
+                }
+            }
+        }
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Account passwords are for this user's eyes only.
+        match self.webdav.is_empty() {
+            true => safe_save::write(&file, document.to_string().as_bytes())?,
+            false => safe_save::write_with_mode(&file, document.to_string().as_bytes(), 0o600)?,
+        }
+
+        Ok(())
+    }
+
+    pub fn default_view(&self) -> View {
+        View {
+            mode: self.view_mode,
+            sort: self.sort,
+            group_by: self.group_by,
+        }
+    }
+
+    /// How `dir` is shown: its own way if it has one, else the defaults.
+    pub fn view(&self, dir: &Path) -> View {
+        self.folder_views
+            .iter()
+            .find(|(path, _)| path == dir)
+            .map_or_else(|| self.default_view(), |&(_, view)| view)
+    }
+
+    /// Has `dir` shown as `view` from now on, following the defaults again
+    /// when that is what they are.
+    pub fn set_view(&mut self, dir: &Path, view: View) {
+        self.folder_views.retain(|(path, _)| path != dir);
+        if view == self.default_view() {
+            return;
+        }
+        if self.folder_views.len() == FOLDER_VIEWS {
+            self.folder_views.remove(0);
+        }
+        self.folder_views.push((dir.to_path_buf(), view));
+    }
+
+    /// iced's themes, then those of the color schemes.
+    pub fn themes(&self) -> Vec<Theme> {
+        Theme::ALL
+            .iter()
+            .cloned()
+            .chain(self.color_schemes.iter().map(ColorScheme::theme))
+            .collect()
+    }
+
+    /// The color scheme the theme in use comes from, if any.
+    pub fn color_scheme(&self) -> Option<&ColorScheme> {
+        match &self.theme {
+            Theme::Custom(_) => {
+                let name = self.theme.to_string();
+                self.color_schemes.iter().find(|scheme| scheme.name == name)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn job_options(&self) -> jobs::Options {
+        jobs::Options {
+            preserve_timestamps: self.preserve_timestamps,
+            low_memory: self.low_memory,
+            overwrite: self.overwrite_without_asking,
+            keep_both: false,
+            resume: false,
+        }
+    }
+
+    /// The folders the background instance indexes.
+    pub fn index_folders(&self) -> Vec<PathBuf> {
+        self.index_folders
+            .clone()
+            .unwrap_or_else(|| vec![paths::home()])
+    }
+
+    pub fn search_sources(&self) -> search::Sources {
+        search::Sources {
+            system_index: self.use_system_index,
+            ntfs_index: self.use_ntfs_index,
+            keep_ntfs_index: !self.low_memory,
+            own_index: true,
+        }
+    }
+}
+
+/// A folder as written by hand, with `~` and variables expanded and
+/// relative paths taken to be in the home folder.
+fn folder(text: &str) -> Option<PathBuf> {
+    paths::expand(text, &paths::home())
+        .ok()
+        .filter(|path| path.is_absolute())
+}
+
+fn view_mode(name: &str) -> Option<ViewMode> {
+    ViewMode::ALL.into_iter().find(|mode| mode.name() == name)
+}
+
+fn sort(name: &str) -> Option<SortBy> {
+    SortBy::ALL.into_iter().find(|sort| sort.name() == name)
+}
+
+fn group_by(name: &str) -> Option<GroupBy> {
+    GroupBy::ALL.into_iter().find(|by| by.name() == name)
+}
+
+/// `#rgb`, `#rrggbb` or `#rrggbbaa`.
+fn color(text: &str) -> Option<Color> {
+    let hex = text.trim().strip_prefix('#')?;
+    let digit = |at: usize, width: usize| u8::from_str_radix(hex.get(at..at + width)?, 16).ok();
+    match hex.len() {
+        3 => Some(Color::from_rgb8(
+            digit(0, 1)? * 17,
+            digit(1, 1)? * 17,
+            digit(2, 1)? * 17,
+        )),
+        6 | 8 => {
+            let alpha = match hex.len() {
+                8 => digit(6, 2)? as f32 / 255.,
+                _ => 1.,
+            };
+            Some(Color::from_rgba8(
+                digit(0, 2)?,
+                digit(2, 2)?,
+                digit(4, 2)?,
+                alpha,
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
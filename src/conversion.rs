@@ -0,0 +1,170 @@
+//! "Convert images…": the format, size and quality the selected images are
+//! written in, and the folder the new files go to. The conversion itself
+//! runs as a job.
+
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, column, container, pick_list, row, slider, text, text_input};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::{tr, trf};
+use crate::images::{Conversion, ImageFormat};
+use crate::paths;
+use crate::style::Style;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Format(ImageFormat),
+    MaxDimension(String),
+    Quality(u8),
+    Destination(String),
+    Convert,
+    Close,
+}
+
+pub enum Event {
+    Convert(Conversion, PathBuf),
+    Closed,
+}
+
+pub struct Convert {
+    /// Where the images are, which a relative destination is taken from.
+    dir: PathBuf,
+    count: usize,
+    conversion: Conversion,
+    max_dimension: String,
+    destination: String,
+    error: Option<String>,
+}
+
+impl Convert {
+    pub fn open(dir: &Path, count: usize) -> Convert {
+        Convert {
+            dir: dir.to_path_buf(),
+            count,
+            conversion: Conversion::default(),
+            max_dimension: String::new(),
+            destination: String::from("Converted"),
+            error: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Format(format) => self.conversion.format = format,
+            Message::MaxDimension(max_dimension) => self.max_dimension = max_dimension,
+            Message::Quality(quality) => self.conversion.quality = quality,
+            Message::Destination(destination) => self.destination = destination,
+            Message::Convert => {
+                let max_dimension = match self.max_dimension.trim() {
+                    "" => None,
+                    max => match max.parse::<u32>() {
+                        Ok(max) if max > 0 => Some(max),
+                        _ => {
+                            self.error = Some(String::from(
+                                "The largest size is a number of pixels, or blank",
+                            ));
+                            return None;
+                        }
+                    },
+                };
+                let destination = match paths::expand(&self.destination, &self.dir) {
+                    Ok(destination) if destination.is_file() => {
+                        self.error = Some(format!("{} is a file", destination.display()));
+                        return None;
+                    }
+                    Ok(destination) => destination,
+                    Err(error) => {
+                        self.error = Some(error);
+                        return None;
+                    }
+                };
+                let conversion = Conversion {
+                    max_dimension,
+                    ..self.conversion
+                };
+                return Some(Event::Convert(conversion, destination));
+            }
+            Message::Close => return Some(Event::Closed),
+        }
+        self.error = None;
+
+        None
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let label = |label| text(label).width(Length::Fixed(140.));
+        let lossy = self.conversion.format != ImageFormat::Png;
+        let mut quality = row!(label("Quality"))
+            .spacing(10)
+            .align_items(Alignment::Center);
+        quality = if lossy {
+            quality
+                .push(slider(1..=100, self.conversion.quality, Message::Quality))
+                .push(text(self.conversion.quality).width(Length::Fixed(30.)))
+        } else {
+            quality.push(text(tr("Lossless")).size(style.small_text_size))
+        };
+
+        let mut col = column!(
+            text(match self.count {
+                1 => String::from("Convert 1 image"),
+                count => trf("Convert {} images", &[&count]),
+            })
+            .size(20),
+            row!(
+                label("Format"),
+                pick_list(
+                    &ImageFormat::ALL[..],
+                    Some(self.conversion.format),
+                    Message::Format
+                ),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+            row!(
+                label("Largest side"),
+                text_input(tr("Keep the size"), &self.max_dimension)
+                    .on_input(Message::MaxDimension)
+                    .on_submit(Message::Convert)
+                    .padding(5),
+                text(tr("pixels")),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+            quality,
+            row!(
+                label("Into"),
+                text_input(tr("Folder"), &self.destination)
+                    .on_input(Message::Destination)
+                    .on_submit(Message::Convert)
+                    .padding(5),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+        )
+        .spacing(10);
+        if let Some(error) = &self.error {
+            col = col.push(
+                text(error)
+                    .size(style.small_text_size)
+                    .style(theme::Text::Color(style.error)),
+            );
+        }
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Cancel")).on_press(Message::Close),
+                button(tr("Convert")).on_press(Message::Convert),
+            )
+            .spacing(5),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(520)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
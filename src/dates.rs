@@ -0,0 +1,157 @@
+//! How times are written wherever the picker shows one, as the settings say:
+//! as ISO 8601 dates by default, as the user's language writes them or by a
+//! pattern of their own, and those of the last week as how long ago they
+//! were when asked to.
+
+use std::io;
+use std::sync::{PoisonError, RwLock};
+use std::time::SystemTime;
+
+use crate::config::TimeFormat;
+use crate::i18n::{tr, trf};
+
+const ISO: &str = "%Y-%m-%d %H:%M:%S";
+
+struct Settings {
+    format: TimeFormat,
+    pattern: String,
+    relative: bool,
+}
+
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    format: TimeFormat::Iso,
+    pattern: String::new(),
+    relative: false,
+});
+
+/// How times are written from now on; `pattern` is that of
+/// `TimeFormat::Custom`.
+pub fn set(format: TimeFormat, pattern: &str, relative: bool) {
+    let mut settings = SETTINGS.write().unwrap_or_else(PoisonError::into_inner);
+    settings.format = format;
+    settings.pattern = pattern.to_string();
+    settings.relative = relative;
+}
+
+pub fn format_time(time: io::Result<SystemTime>) -> String {
+    let Ok(time) = time else {
+        return String::from("Unknown");
+    };
+    let settings = SETTINGS.read().unwrap_or_else(PoisonError::into_inner);
+    if let Some(ago) = settings.relative.then(|| relative(time)).flatten() {
+        return ago;
+    }
+
+    let pattern = match settings.format {
+        TimeFormat::Iso => ISO,
+        TimeFormat::Locale => "%c",
+        TimeFormat::Custom if settings.pattern.trim().is_empty() => ISO,
+        TimeFormat::Custom => &settings.pattern,
+    };
+    strftime(time, pattern)
+        .or_else(|| strftime(time, ISO))
+        .unwrap_or_else(|| String::from("Unknown"))
+}
+
+/// How long ago `time` was, up to a week.
+fn relative(time: SystemTime) -> Option<String> {
+    let ago = SystemTime::now().duration_since(time).ok()?.as_secs();
+    Some(match ago {
+        0..=59 => tr("Just now").to_string(),
+        60..=3_599 => trf("{} min ago", &[&(ago / 60)]),
+        3_600..=86_399 => trf("{} h ago", &[&(ago / 3_600)]),
+        86_400..=172_799 => tr("A day ago").to_string(),
+        172_800..=604_799 => trf("{} days ago", &[&(ago / 86_400)]),
+        _ => return None,
+    })
+}
+
+/// `None` when `pattern` gives nothing, or more than fits a line.
+#[cfg(unix)]
+fn strftime(time: SystemTime, pattern: &str) -> Option<String> {
+    use std::ffi::CString;
+    use std::sync::Once;
+
+    static LOCALE: Once = Once::new();
+    LOCALE.call_once(|| {
+        // SAFETY: once, before any time is written through the locale.
+        unsafe { libc::setlocale(libc::LC_TIME, c"".as_ptr()) };
+    });
+
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as libc::time_t,
+        Err(error) => -(error.duration().as_secs() as libc::time_t),
+    };
+    let pattern = CString::new(pattern).ok()?;
+    let mut buffer = [0u8; 256];
+    // SAFETY: `localtime_r` only writes into the `tm` we own, and
+    // `strftime` no further than the length of `buffer` it is given.
+    let written = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&seconds, &mut tm).is_null() {
+            return None;
+        }
+        libc::strftime(
+            buffer.as_mut_ptr().cast(),
+            buffer.len(),
+            pattern.as_ptr(),
+            &tm,
+        )
+    };
+    match written {
+        0 => None,
+        written => Some(String::from_utf8_lossy(&buffer[..written]).into_owned()),
+    }
+}
+
+/// In UTC, without the time zone at hand, and only for the date and time
+/// of day: other conversions are written as they are.
+#[cfg(not(unix))]
+fn strftime(time: SystemTime, pattern: &str) -> Option<String> {
+    let since = time.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+
+    // Days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let seconds = since.as_secs();
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let pattern = match pattern {
+        "%c" => ISO,
+        pattern => pattern,
+    };
+    let mut written = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            written.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => written.push_str(&format!("{:04}", year)),
+            Some('m') => written.push_str(&format!("{:02}", month)),
+            Some('d') => written.push_str(&format!("{:02}", day)),
+            Some('H') => written.push_str(&format!("{:02}", seconds % 86_400 / 3_600)),
+            Some('M') => written.push_str(&format!("{:02}", seconds % 3_600 / 60)),
+            Some('S') => written.push_str(&format!("{:02}", seconds % 60)),
+            Some('%') => written.push('%'),
+            Some(other) => {
+                written.push('%');
+                written.push(other);
+            }
+            None => written.push('%'),
+        }
+    }
+    if pattern == ISO {
+        written.push_str(" UTC");
+    }
+    (!written.is_empty()).then_some(written)
+}
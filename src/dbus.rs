@@ -0,0 +1,894 @@
+//! Just enough of the D-Bus wire protocol to own a name on the session bus,
+//! answer method calls and make a few of our own.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+const ERROR: u8 = 3;
+const SIGNAL: u8 = 4;
+const NO_REPLY_EXPECTED: u8 = 1;
+
+const PATH: u8 = 1;
+const INTERFACE: u8 = 2;
+const MEMBER: u8 = 3;
+const ERROR_NAME: u8 = 4;
+const REPLY_SERIAL: u8 = 5;
+const DESTINATION: u8 = 6;
+const SENDER: u8 = 7;
+const SIGNATURE: u8 = 8;
+
+/// Refuse anything bigger than this rather than allocate for it.
+const MAX_MESSAGE_SIZE: usize = 128 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    Int16(i16),
+    Uint16(u16),
+    Int32(i32),
+    Uint32(u32),
+    Int64(i64),
+    Uint64(u64),
+    Double(f64),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    /// Element signature, kept so that empty arrays can be encoded.
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    DictEntry(Box<Value>, Box<Value>),
+    Variant(Box<Value>),
+}
+
+impl Value {
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => String::from("y"),
+            Value::Bool(_) => String::from("b"),
+            Value::Int16(_) => String::from("n"),
+            Value::Uint16(_) => String::from("q"),
+            Value::Int32(_) => String::from("i"),
+            Value::Uint32(_) => String::from("u"),
+            Value::Int64(_) => String::from("x"),
+            Value::Uint64(_) => String::from("t"),
+            Value::Double(_) => String::from("d"),
+            Value::String(_) => String::from("s"),
+            Value::ObjectPath(_) => String::from("o"),
+            Value::Signature(_) => String::from("g"),
+            Value::Array(element, _) => format!("a{}", element),
+            Value::Struct(fields) => {
+                let fields: String = fields.iter().map(Value::signature).collect();
+                format!("({})", fields)
+            }
+            Value::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+            Value::Variant(_) => String::from("v"),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) | Value::ObjectPath(s) | Value::Signature(s) => Some(s),
+            Value::Variant(value) => value.as_str(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            Value::Variant(value) => value.as_bool(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Value::Uint32(value) => Some(*value),
+            Value::Variant(value) => value.as_u32(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Uint64(value) => Some(*value),
+            Value::Variant(value) => value.as_u64(),
+            _ => None,
+        }
+    }
+
+    /// Array elements, or struct fields.
+    pub fn items(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(_, items) | Value::Struct(items) => Some(items),
+            Value::Variant(value) => value.items(),
+            _ => None,
+        }
+    }
+
+    /// A byte array as bytes, with the trailing NUL that portals add
+    /// to paths removed.
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        let bytes: Vec<u8> = self
+            .items()?
+            .iter()
+            .map(|item| match item {
+                Value::Byte(byte) => Some(*byte),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        Some(bytes.strip_suffix(&[0]).unwrap_or(&bytes).to_vec())
+    }
+
+    /// Looks up `key` in an `a{sv}` dictionary.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.items()?.iter().find_map(|entry| match entry {
+            Value::DictEntry(name, value) if name.as_str() == Some(key) => Some(&**value),
+            _ => None,
+        })
+    }
+
+    /// An `a{sv}` dictionary.
+    pub fn dict(entries: Vec<(&str, Value)>) -> Value {
+        Value::Array(
+            String::from("{sv}"),
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    Value::DictEntry(
+                        Box::new(Value::String(key.to_string())),
+                        Box::new(Value::Variant(Box::new(value))),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Message {
+    kind: u8,
+    serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    no_reply: bool,
+    pub body: Vec<Value>,
+}
+
+impl Message {
+    pub fn is_call(&self) -> bool {
+        self.kind == METHOD_CALL
+    }
+
+    pub fn is_signal(&self) -> bool {
+        self.kind == SIGNAL
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let signature: String = self.body.iter().map(Value::signature).collect();
+        let mut fields = Vec::new();
+        let mut field = |code: u8, value: Value| {
+            fields.push(Value::Struct(vec![
+                Value::Byte(code),
+                Value::Variant(Box::new(value)),
+            ]))
+        };
+        if let Some(path) = &self.path {
+            field(PATH, Value::ObjectPath(path.clone()));
+        }
+        if let Some(interface) = &self.interface {
+            field(INTERFACE, Value::String(interface.clone()));
+        }
+        if let Some(member) = &self.member {
+            field(MEMBER, Value::String(member.clone()));
+        }
+        if let Some(name) = &self.error_name {
+            field(ERROR_NAME, Value::String(name.clone()));
+        }
+        if let Some(serial) = self.reply_serial {
+            field(REPLY_SERIAL, Value::Uint32(serial));
+        }
+        if let Some(destination) = &self.destination {
+            field(DESTINATION, Value::String(destination.clone()));
+        }
+        if !signature.is_empty() {
+            field(SIGNATURE, Value::Signature(signature));
+        }
+
+        let mut body = Encoder::default();
+        for value in &self.body {
+            body.value(value);
+        }
+
+        let mut header = Encoder::default();
+        header.bytes.extend_from_slice(&[
+            b'l',
+            self.kind,
+            if self.no_reply { NO_REPLY_EXPECTED } else { 0 },
+            1,
+        ]);
+        header
+            .bytes
+            .extend_from_slice(&(body.bytes.len() as u32).to_le_bytes());
+        header.bytes.extend_from_slice(&self.serial.to_le_bytes());
+        header.value(&Value::Array(String::from("(yv)"), fields));
+        header.align(8);
+        header.bytes.extend_from_slice(&body.bytes);
+
+        header.bytes
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Message> {
+        if bytes.first() != Some(&b'l') {
+            return Err(invalid("only little-endian messages are supported"));
+        }
+
+        let mut decoder = Decoder {
+            bytes,
+            position: 12,
+        };
+        let fields = decoder.value("a(yv)")?;
+        decoder.align(8)?;
+
+        let mut message = Message {
+            kind: bytes[1],
+            no_reply: bytes[2] & NO_REPLY_EXPECTED != 0,
+            serial: u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            ..Message::default()
+        };
+        let mut signature = String::new();
+        for field in fields.items().unwrap_or_default() {
+            let Some([Value::Byte(code), value]) = field.items() else {
+                continue;
+            };
+            let text = value.as_str().map(String::from);
+            match *code {
+                PATH => message.path = text,
+                INTERFACE => message.interface = text,
+                MEMBER => message.member = text,
+                ERROR_NAME => message.error_name = text,
+                REPLY_SERIAL => message.reply_serial = value.as_u32(),
+                DESTINATION => message.destination = text,
+                SENDER => message.sender = text,
+                SIGNATURE => signature = text.unwrap_or_default(),
+                _ => {}
+            }
+        }
+
+        let mut rest = signature.as_str();
+        while !rest.is_empty() {
+            let (single, remaining) = split_type(rest)?;
+            message.body.push(decoder.value(single)?);
+            rest = remaining;
+        }
+
+        Ok(message)
+    }
+}
+
+/// Replies can be sent from any thread while another one waits for calls.
+#[derive(Debug, Clone)]
+pub struct Sender {
+    stream: Arc<Mutex<UnixStream>>,
+    serial: Arc<AtomicU32>,
+}
+
+impl Sender {
+    fn send(&self, mut message: Message) -> io::Result<u32> {
+        message.serial = self.serial.fetch_add(1, Ordering::Relaxed);
+        let bytes = message.encode();
+        self.stream
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write_all(&bytes)?;
+        Ok(message.serial)
+    }
+
+    pub fn reply(&self, call: &Message, body: Vec<Value>) -> io::Result<()> {
+        if call.no_reply {
+            return Ok(());
+        }
+        self.send(Message {
+            kind: METHOD_RETURN,
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body,
+            ..Message::default()
+        })
+        .map(|_| ())
+    }
+
+    pub fn reply_error(&self, call: &Message, name: &str, text: &str) -> io::Result<()> {
+        if call.no_reply {
+            return Ok(());
+        }
+        self.send(Message {
+            kind: ERROR,
+            error_name: Some(name.to_string()),
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body: vec![Value::String(text.to_string())],
+            ..Message::default()
+        })
+        .map(|_| ())
+    }
+}
+
+pub struct Connection {
+    reader: BufReader<UnixStream>,
+    sender: Sender,
+    /// What the bus calls us, such as `:1.42`.
+    unique_name: String,
+    /// Calls and signals that arrived while waiting for a reply.
+    queued: VecDeque<Message>,
+}
+
+impl Connection {
+    /// Connects and authenticates to the bus in `DBUS_SESSION_BUS_ADDRESS`.
+    pub fn session() -> io::Result<Connection> {
+        let address = std::env::var("DBUS_SESSION_BUS_ADDRESS")
+            .map_err(|_| invalid("DBUS_SESSION_BUS_ADDRESS is not set"))?;
+        Connection::open(&address)
+    }
+
+    /// Connects and authenticates to the system bus, at its usual socket
+    /// unless `DBUS_SYSTEM_BUS_ADDRESS` says otherwise.
+    pub fn system() -> io::Result<Connection> {
+        let address = std::env::var("DBUS_SYSTEM_BUS_ADDRESS")
+            .unwrap_or_else(|_| String::from("unix:path=/var/run/dbus/system_bus_socket"));
+        Connection::open(&address)
+    }
+
+    fn open(address: &str) -> io::Result<Connection> {
+        let stream = address
+            .split(';')
+            .find_map(|address| connect(address).ok())
+            .ok_or_else(|| invalid("cannot connect to the bus"))?;
+
+        let mut connection = Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            sender: Sender {
+                stream: Arc::new(Mutex::new(stream)),
+                serial: Arc::new(AtomicU32::new(1)),
+            },
+            unique_name: String::new(),
+            queued: VecDeque::new(),
+        };
+        connection.authenticate()?;
+        let hello = connection.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "Hello",
+            Vec::new(),
+        )?;
+        connection.unique_name = hello
+            .body
+            .first()
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(connection)
+    }
+
+    pub fn sender(&self) -> Sender {
+        self.sender.clone()
+    }
+
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Asks the bus to pass on the signals `rule` matches, such as
+    /// `type='signal',interface='org.freedesktop.portal.Request'`.
+    pub fn add_match(&mut self, rule: &str) -> io::Result<()> {
+        self.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "AddMatch",
+            vec![Value::String(rule.to_string())],
+        )
+        .map(|_| ())
+    }
+
+    /// Claims `name`; `false` when someone else already has it.
+    pub fn request_name(&mut self, name: &str) -> io::Result<bool> {
+        // DBUS_NAME_FLAG_DO_NOT_QUEUE, answered with 1 for primary owner.
+        let reply = self.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "RequestName",
+            vec![Value::String(name.to_string()), Value::Uint32(4)],
+        )?;
+        Ok(reply.body.first().and_then(Value::as_u32) == Some(1))
+    }
+
+    pub fn call(
+        &mut self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        body: Vec<Value>,
+    ) -> io::Result<Message> {
+        let serial = self.sender.send(Message {
+            kind: METHOD_CALL,
+            destination: Some(destination.to_string()),
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            body,
+            ..Message::default()
+        })?;
+
+        loop {
+            let message = self.read()?;
+            if message.reply_serial != Some(serial) {
+                self.queued.push_back(message);
+                continue;
+            }
+            if message.kind == ERROR {
+                let text = message.body.first().and_then(Value::as_str).unwrap_or("");
+                return Err(io::Error::other(format!(
+                    "{}: {}",
+                    message.error_name.unwrap_or_default(),
+                    text
+                )));
+            }
+            return Ok(message);
+        }
+    }
+
+    /// The next incoming method call or signal.
+    pub fn receive(&mut self) -> io::Result<Message> {
+        match self.queued.pop_front() {
+            Some(message) => Ok(message),
+            None => self.read(),
+        }
+    }
+
+    fn authenticate(&mut self) -> io::Result<()> {
+        let uid = unsafe { libc::getuid() }.to_string();
+        let hex: String = uid.bytes().map(|byte| format!("{:02x}", byte)).collect();
+        {
+            let mut stream = self
+                .sender
+                .stream
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            stream.write_all(b"\0")?;
+            stream.write_all(format!("AUTH EXTERNAL {}\r\n", hex).as_bytes())?;
+        }
+
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        if !line.starts_with("OK ") {
+            return Err(invalid("the bus refused authentication"));
+        }
+        self.sender
+            .stream
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write_all(b"BEGIN\r\n")
+    }
+
+    fn read(&mut self) -> io::Result<Message> {
+        let mut fixed = [0u8; 16];
+        self.reader.read_exact(&mut fixed)?;
+        if fixed[0] != b'l' {
+            return Err(invalid("only little-endian messages are supported"));
+        }
+        let body = u32::from_le_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]) as usize;
+        let fields = u32::from_le_bytes([fixed[12], fixed[13], fixed[14], fixed[15]]) as usize;
+        let header = (16 + fields).next_multiple_of(8);
+        if header + body > MAX_MESSAGE_SIZE {
+            return Err(invalid("message too large"));
+        }
+
+        let mut bytes = vec![0u8; header + body];
+        bytes[..16].copy_from_slice(&fixed);
+        self.reader.read_exact(&mut bytes[16..])?;
+        Message::decode(&bytes)
+    }
+}
+
+fn connect(address: &str) -> io::Result<UnixStream> {
+    let (transport, parameters) = address
+        .split_once(':')
+        .ok_or_else(|| invalid("malformed bus address"))?;
+    if transport != "unix" {
+        return Err(invalid("unsupported bus transport"));
+    }
+
+    for parameter in parameters.split(',') {
+        match parameter.split_once('=') {
+            Some(("path", path)) => return UnixStream::connect(unescape(path)),
+            Some(("abstract", name)) => {
+                use std::os::linux::net::SocketAddrExt;
+                let address = std::os::unix::net::SocketAddr::from_abstract_name(unescape(name))?;
+                return UnixStream::connect_addr(&address);
+            }
+            _ => {}
+        }
+    }
+
+    Err(invalid("no socket in bus address"))
+}
+
+/// Bus addresses percent-encode unusual bytes.
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                unescaped.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                unescaped.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+fn invalid(text: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, text.to_string())
+}
+
+fn alignment(signature: &str) -> usize {
+    match signature.as_bytes().first() {
+        Some(b'n' | b'q') => 2,
+        Some(b'b' | b'i' | b'u' | b's' | b'o' | b'a') => 4,
+        Some(b'x' | b't' | b'd' | b'(' | b'{') => 8,
+        _ => 1,
+    }
+}
+
+/// Splits the first complete type off `signature`.
+fn split_type(signature: &str) -> io::Result<(&str, &str)> {
+    let bytes = signature.as_bytes();
+    let mut depth = 0usize;
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'a' => continue,
+            b'(' | b'{' => depth += 1,
+            b')' | b'}' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| invalid("bad signature"))?
+            }
+            _ => {}
+        }
+        if depth == 0 {
+            return Ok(signature.split_at(i + 1));
+        }
+    }
+
+    Err(invalid("bad signature"))
+}
+
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn align(&mut self, alignment: usize) {
+        while !self.bytes.len().is_multiple_of(alignment) {
+            self.bytes.push(0);
+        }
+    }
+
+    fn value(&mut self, value: &Value) {
+        self.align(alignment(&value.signature()));
+        match value {
+            Value::Byte(byte) => self.bytes.push(*byte),
+            Value::Bool(value) => self.bytes.extend_from_slice(&(*value as u32).to_le_bytes()),
+            Value::Int16(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::Uint16(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::Int32(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::Uint32(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::Int64(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::Uint64(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::Double(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Value::String(text) | Value::ObjectPath(text) => {
+                self.bytes
+                    .extend_from_slice(&(text.len() as u32).to_le_bytes());
+                self.bytes.extend_from_slice(text.as_bytes());
+                self.bytes.push(0);
+            }
+            Value::Signature(signature) => {
+                self.bytes.push(signature.len() as u8);
+                self.bytes.extend_from_slice(signature.as_bytes());
+                self.bytes.push(0);
+            }
+            Value::Array(element, items) => {
+                let length_at = self.bytes.len();
+                self.bytes.extend_from_slice(&[0; 4]);
+                self.align(alignment(element));
+                let start = self.bytes.len();
+                for item in items {
+                    self.value(item);
+                }
+                let length = (self.bytes.len() - start) as u32;
+                self.bytes[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+            }
+            Value::Struct(fields) => {
+                for field in fields {
+                    self.value(field);
+                }
+            }
+            Value::DictEntry(key, value) => {
+                self.value(key);
+                self.value(value);
+            }
+            Value::Variant(value) => {
+                self.value(&Value::Signature(value.signature()));
+                self.value(value);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Decoder<'_> {
+    fn align(&mut self, alignment: usize) -> io::Result<()> {
+        self.position = self.position.next_multiple_of(alignment);
+        if self.position > self.bytes.len() {
+            return Err(invalid("truncated message"));
+        }
+        Ok(())
+    }
+
+    fn take(&mut self, count: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid("truncated message"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn fixed<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        self.align(N)?;
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn text(&mut self, length: usize) -> io::Result<String> {
+        let text = String::from_utf8_lossy(self.take(length)?).into_owned();
+        self.take(1)?;
+        Ok(text)
+    }
+
+    fn value(&mut self, signature: &str) -> io::Result<Value> {
+        let value = match signature.as_bytes().first() {
+            Some(b'y') => Value::Byte(self.take(1)?[0]),
+            Some(b'b') => Value::Bool(u32::from_le_bytes(self.fixed()?) != 0),
+            Some(b'n') => Value::Int16(i16::from_le_bytes(self.fixed()?)),
+            Some(b'q') => Value::Uint16(u16::from_le_bytes(self.fixed()?)),
+            Some(b'i') => Value::Int32(i32::from_le_bytes(self.fixed()?)),
+            Some(b'u' | b'h') => Value::Uint32(u32::from_le_bytes(self.fixed()?)),
+            Some(b'x') => Value::Int64(i64::from_le_bytes(self.fixed()?)),
+            Some(b't') => Value::Uint64(u64::from_le_bytes(self.fixed()?)),
+            Some(b'd') => Value::Double(f64::from_le_bytes(self.fixed()?)),
+            Some(b's') => {
+                let length = u32::from_le_bytes(self.fixed()?) as usize;
+                Value::String(self.text(length)?)
+            }
+            Some(b'o') => {
+                let length = u32::from_le_bytes(self.fixed()?) as usize;
+                Value::ObjectPath(self.text(length)?)
+            }
+            Some(b'g') => {
+                let length = self.take(1)?[0] as usize;
+                Value::Signature(self.text(length)?)
+            }
+            Some(b'v') => {
+                let length = self.take(1)?[0] as usize;
+                let inner = self.text(length)?;
+                let (single, _) = split_type(&inner)?;
+                Value::Variant(Box::new(self.value(single)?))
+            }
+            Some(b'a') => {
+                let length = u32::from_le_bytes(self.fixed()?) as usize;
+                let element = &signature[1..];
+                self.align(alignment(element))?;
+                let end = self.position + length;
+                if end > self.bytes.len() {
+                    return Err(invalid("truncated message"));
+                }
+                let mut items = Vec::new();
+                while self.position < end {
+                    items.push(self.value(element)?);
+                }
+                Value::Array(element.to_string(), items)
+            }
+            Some(b'(') => {
+                self.align(8)?;
+                let mut rest = &signature[1..signature.len() - 1];
+                let mut fields = Vec::new();
+                while !rest.is_empty() {
+                    let (single, remaining) = split_type(rest)?;
+                    fields.push(self.value(single)?);
+                    rest = remaining;
+                }
+                Value::Struct(fields)
+            }
+            Some(b'{') => {
+                self.align(8)?;
+                let inner = &signature[1..signature.len() - 1];
+                let (key, value) = split_type(inner)?;
+                let key = self.value(key)?;
+                let value = self.value(value)?;
+                Value::DictEntry(Box::new(key), Box::new(value))
+            }
+            _ => return Err(invalid("unsupported type in message")),
+        };
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: &Value) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.value(value);
+        encoder.bytes
+    }
+
+    fn decoded(bytes: &[u8], signature: &str) -> io::Result<Value> {
+        Decoder { bytes, position: 0 }.value(signature)
+    }
+
+    #[test]
+    fn fields_are_padded_to_their_alignment() {
+        let value = Value::Struct(vec![Value::Byte(1), Value::Int16(-2), Value::Uint64(3)]);
+        assert_eq!(
+            encoded(&value),
+            [1, 0, 0xfe, 0xff, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(decoded(&encoded(&value), "(ynt)").unwrap(), value);
+    }
+
+    #[test]
+    fn array_lengths_leave_out_the_padding_before_the_first_element() {
+        let value = Value::Array(String::from("t"), vec![Value::Uint64(1)]);
+        assert_eq!(
+            encoded(&value),
+            [8, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        let empty = Value::Array(String::from("(ss)"), Vec::new());
+        assert_eq!(encoded(&empty), [0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(decoded(&encoded(&empty), "a(ss)").unwrap(), empty);
+    }
+
+    #[test]
+    fn variants_carry_their_signature() {
+        let value = Value::Variant(Box::new(Value::String(String::from("hi"))));
+        assert_eq!(encoded(&value), [1, b's', 0, 0, 2, 0, 0, 0, b'h', b'i', 0]);
+        assert_eq!(decoded(&encoded(&value), "v").unwrap(), value);
+    }
+
+    #[test]
+    fn every_type_survives_a_round_trip() {
+        let values = vec![
+            Value::Byte(7),
+            Value::Bool(true),
+            Value::Int16(-16),
+            Value::Uint16(16),
+            Value::Int32(-32),
+            Value::Uint32(32),
+            Value::Int64(-64),
+            Value::Uint64(64),
+            Value::Double(0.5),
+            Value::String(String::from("été")),
+            Value::ObjectPath(String::from("/org/freedesktop/portal/desktop")),
+            Value::Signature(String::from("a{sv}")),
+            Value::Array(
+                String::from("ay"),
+                vec![Value::Array(String::from("y"), vec![Value::Byte(1)])],
+            ),
+            Value::dict(vec![
+                ("multiple", Value::Bool(false)),
+                (
+                    "uris",
+                    Value::Array(
+                        String::from("s"),
+                        vec![Value::String(String::from("file:///tmp/a"))],
+                    ),
+                ),
+                ("nested", Value::Variant(Box::new(Value::Uint64(9)))),
+            ]),
+            Value::Struct(vec![Value::Byte(0), Value::Struct(vec![Value::Double(1.)])]),
+        ];
+        for value in values {
+            let signature = value.signature();
+            // After a byte, so each has to pad to its own alignment.
+            let pair = Value::Struct(vec![Value::Byte(0xaa), value.clone()]);
+            let bytes = encoded(&pair);
+            assert_eq!(
+                decoded(&bytes, &format!("(y{})", signature)).unwrap(),
+                pair,
+                "{}",
+                signature
+            );
+        }
+    }
+
+    #[test]
+    fn messages_survive_a_round_trip() {
+        let message = Message {
+            kind: METHOD_RETURN,
+            serial: 5,
+            path: Some(String::from("/org/freedesktop/portal/desktop/request/1")),
+            interface: Some(String::from("org.freedesktop.portal.Request")),
+            member: Some(String::from("Response")),
+            reply_serial: Some(4),
+            destination: Some(String::from(":1.42")),
+            no_reply: true,
+            body: vec![
+                Value::Uint32(0),
+                Value::dict(vec![(
+                    "choices",
+                    Value::Array(String::from("(ss)"), Vec::new()),
+                )]),
+            ],
+            ..Message::default()
+        };
+        let decoded = Message::decode(&message.encode()).unwrap();
+        assert_eq!(decoded.kind, message.kind);
+        assert_eq!(decoded.serial, message.serial);
+        assert_eq!(decoded.path, message.path);
+        assert_eq!(decoded.interface, message.interface);
+        assert_eq!(decoded.member, message.member);
+        assert_eq!(decoded.reply_serial, message.reply_serial);
+        assert_eq!(decoded.destination, message.destination);
+        assert!(decoded.no_reply);
+        assert_eq!(decoded.body, message.body);
+    }
+
+    #[test]
+    fn truncated_and_malformed_input_is_refused() {
+        let bytes = encoded(&Value::String(String::from("hello")));
+        assert!(decoded(&bytes[..bytes.len() - 2], "s").is_err());
+        let array = encoded(&Value::Array(String::from("u"), vec![Value::Uint32(1)]));
+        assert!(decoded(&array[..6], "au").is_err());
+        assert!(split_type("(ss").is_err());
+        assert!(split_type(")s").is_err());
+        assert_eq!(split_type("a{sv}u").unwrap(), ("a{sv}", "u"));
+    }
+}
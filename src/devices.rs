@@ -0,0 +1,114 @@
+//! Turns at the disks: the long operations that read or write a lot, such
+//! as copies, checksums, searches and size scans, take the devices of the
+//! paths they work on for as long as they run, one operation per device,
+//! so that a spinning disk seeks between two of them less than it would
+//! were they interleaved. Remote folders belong to no device and never wait.
+
+use std::path::Path;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::time::Duration;
+
+/// How often one waiting for a device looks whether it gave up.
+const POLL: Duration = Duration::from_millis(100);
+
+/// The devices taken, each once.
+static HELD: Mutex<Vec<Device>> = Mutex::new(Vec::new());
+static FREED: Condvar = Condvar::new();
+
+/// A filesystem, by the number the system gives it or, on Windows, by its
+/// drive or share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Device(u64);
+
+/// The devices `paths` are on, each once. A path that does not exist yet,
+/// such as the destination of a copy, counts for the folder it goes in.
+pub fn of(paths: &[&Path]) -> Vec<Device> {
+    let mut devices = Vec::new();
+    for path in paths {
+        if let Some(device) = device(path).filter(|device| !devices.contains(device)) {
+            devices.push(device);
+        }
+    }
+    devices
+}
+
+#[cfg(unix)]
+fn device(path: &Path) -> Option<Device> {
+    use std::os::unix::fs::MetadataExt;
+
+    if !crate::vfs::is_local(path) {
+        return None;
+    }
+    path.ancestors()
+        .find_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| Device(meta.dev()))
+}
+
+#[cfg(not(unix))]
+fn device(path: &Path) -> Option<Device> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::path::Component;
+
+    if !crate::vfs::is_local(path) {
+        return None;
+    }
+    let Component::Prefix(prefix) = path.components().next()? else {
+        return None;
+    };
+    let mut hasher = DefaultHasher::new();
+    prefix.as_os_str().to_ascii_lowercase().hash(&mut hasher);
+    Some(Device(hasher.finish()))
+}
+
+/// The devices an operation holds, given back when it is dropped.
+#[must_use]
+pub struct Turn {
+    devices: Vec<Device>,
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        let mut held = HELD.lock().unwrap_or_else(PoisonError::into_inner);
+        held.retain(|device| !self.devices.contains(device));
+        FREED.notify_all();
+    }
+}
+
+/// Takes `devices` if none of them is taken.
+pub fn take(devices: &[Device]) -> Option<Turn> {
+    let mut held = HELD.lock().unwrap_or_else(PoisonError::into_inner);
+    if devices.iter().any(|device| held.contains(device)) {
+        return None;
+    }
+    held.extend_from_slice(devices);
+    Some(Turn {
+        devices: devices.to_vec(),
+    })
+}
+
+/// Takes `devices` once the others holding any of them are done, or gives
+/// up when `cancelled` says to.
+pub fn wait(devices: &[Device], cancelled: impl Fn() -> bool) -> Option<Turn> {
+    let mut held = HELD.lock().unwrap_or_else(PoisonError::into_inner);
+    loop {
+        if cancelled() {
+            return None;
+        }
+        if !devices.iter().any(|device| held.contains(device)) {
+            held.extend_from_slice(devices);
+            return Some(Turn {
+                devices: devices.to_vec(),
+            });
+        }
+        held = FREED
+            .wait_timeout(held, POLL)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+    }
+}
+
+/// `wait` for the devices of `paths`.
+pub fn wait_for(paths: &[&Path], cancelled: impl Fn() -> bool) -> Option<Turn> {
+    wait(&of(paths), cancelled)
+}
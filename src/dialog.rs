@@ -0,0 +1,320 @@
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use iced::widget::{button, pick_list, row, text, text_input};
+use iced::{Alignment, Element, Length};
+
+use crate::i18n::tr;
+use crate::{mime, paths};
+
+/// What the picker is asked to choose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogMode {
+    OpenFile,
+    OpenFiles,
+    OpenDirectory,
+    SaveFile,
+}
+
+/// How a dialog ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Chosen(Vec<Selection>),
+    Cancelled,
+}
+
+impl Outcome {
+    /// Just the paths chosen; none when cancelled.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        match self {
+            Outcome::Chosen(chosen) => chosen.iter().map(|item| item.path.clone()).collect(),
+            Outcome::Cancelled => Vec::new(),
+        }
+    }
+}
+
+/// One chosen path with what the picker knew about it at the time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub path: PathBuf,
+    /// In bytes; `None` for folders and files yet to be saved.
+    pub size: Option<u64>,
+    pub mime: String,
+    /// In save mode, nothing was there yet: saving creates the file rather
+    /// than replacing one.
+    pub new: bool,
+}
+
+impl Selection {
+    pub(crate) fn new(path: PathBuf) -> Selection {
+        let meta = fs::metadata(&path).ok();
+        Selection {
+            size: meta
+                .as_ref()
+                .filter(|meta| meta.is_file())
+                .map(fs::Metadata::len),
+            mime: mime::guess(&path).to_string(),
+            new: meta.is_none() && fs::symlink_metadata(&path).is_err(),
+            path,
+        }
+    }
+}
+
+/// A named set of patterns offered in the dialog's filter dropdown, such
+/// as "Images (*.png, *.jpg)".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFilter {
+    pub name: String,
+    /// `*` and `?` wildcards, matched ignoring case.
+    pub patterns: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn new(name: impl Into<String>, patterns: &[&str]) -> FileFilter {
+        FileFilter {
+            name: name.into(),
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+        }
+    }
+
+    fn all_files() -> FileFilter {
+        FileFilter::new("All files", &["*"])
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let name: Vec<char> = name.to_lowercase().chars().collect();
+        self.patterns.iter().any(|pattern| {
+            let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+            glob(&pattern, &name)
+        })
+    }
+
+    /// The extension save mode appends when the typed name has none: that
+    /// of the first pattern, if it is a plain `*.ext`.
+    fn extension(&self) -> Option<&str> {
+        let extension = self.patterns.first()?.strip_prefix("*.")?;
+        (!extension.contains(['*', '?'])).then_some(extension)
+    }
+}
+
+impl std::fmt::Display for FileFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.patterns.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FileName(String),
+    SelectFilter(FileFilter),
+    Confirm,
+    Cancel,
+}
+
+pub enum Event {
+    /// The listing needs redrawing with the new filter.
+    FilterChanged,
+    Confirm,
+    Cancel,
+}
+
+/// Turns the browser into an open or save dialog with a confirm/cancel bar.
+#[derive(Debug, Clone)]
+pub struct Dialog {
+    mode: DialogMode,
+    /// Offered in the order added, with "All files" last.
+    filters: Vec<FileFilter>,
+    active: usize,
+    /// The name typed in save mode.
+    file_name: String,
+    directory: Option<PathBuf>,
+}
+
+impl Dialog {
+    pub fn new(mode: DialogMode) -> Dialog {
+        Dialog {
+            mode,
+            filters: Vec::new(),
+            active: 0,
+            file_name: String::new(),
+            directory: None,
+        }
+    }
+
+    /// Adds a filter to the dropdown; the first one added starts active.
+    /// Files it does not match are shown greyed out and cannot be picked.
+    pub fn filter(mut self, filter: FileFilter) -> Dialog {
+        let all_files = FileFilter::all_files();
+        self.filters.retain(|filter| filter != &all_files);
+        self.filters.push(filter);
+        self.filters.push(all_files);
+        self
+    }
+
+    /// The name suggested in save mode.
+    pub fn file_name(mut self, name: impl Into<String>) -> Dialog {
+        self.file_name = name.into();
+        self
+    }
+
+    /// The folder to open in instead of the current directory.
+    pub fn directory(mut self, dir: impl Into<PathBuf>) -> Dialog {
+        self.directory = Some(dir.into());
+        self
+    }
+
+    pub fn mode(&self) -> DialogMode {
+        self.mode
+    }
+
+    pub(crate) fn start_dir(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    pub(crate) fn accepts(&self, name: &str) -> bool {
+        self.filters
+            .get(self.active)
+            .is_none_or(|filter| filter.matches(name))
+    }
+
+    fn accepts_path(&self, path: &Path) -> bool {
+        path.is_file()
+            && path
+                .file_name()
+                .is_some_and(|name| self.accepts(&name.to_string_lossy()))
+    }
+
+    pub(crate) fn set_file_name(&mut self, name: String) {
+        self.file_name = name;
+    }
+
+    /// The folder and name of a path typed as the name in save mode, as
+    /// GTK's save dialog takes them: the picker goes to the folder, with
+    /// the rest left as the name.
+    pub(crate) fn typed_path(&self, dir: &Path) -> Option<Result<(PathBuf, String), String>> {
+        let typed = self.file_name.trim();
+        if self.mode != DialogMode::SaveFile || !typed.contains(['/', MAIN_SEPARATOR]) {
+            return None;
+        }
+
+        Some(paths::expand(typed, dir).map(|path| {
+            if typed.ends_with(['/', MAIN_SEPARATOR]) {
+                return (path, String::new());
+            }
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match path.parent() {
+                Some(parent) => (parent.to_path_buf(), name),
+                None => (path, String::new()),
+            }
+        }))
+    }
+
+    /// What confirming would return given the folder being shown and the
+    /// selection, or `None` while that is not a valid choice.
+    pub(crate) fn choice(&self, dir: &Path, selected: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        if paths::is_computer(dir) {
+            return None;
+        }
+
+        match self.mode {
+            DialogMode::OpenFile => match selected {
+                [file] if self.accepts_path(file) => Some(vec![file.clone()]),
+                _ => None,
+            },
+            DialogMode::OpenFiles => (!selected.is_empty()
+                && selected.iter().all(|path| self.accepts_path(path)))
+            .then(|| selected.to_vec()),
+            DialogMode::OpenDirectory => match selected {
+                [] => Some(vec![dir.to_path_buf()]),
+                [path] if path.is_dir() => Some(vec![path.clone()]),
+                _ => None,
+            },
+            DialogMode::SaveFile => {
+                let name = self.file_name.trim();
+                let valid = !name.is_empty()
+                    && name != "."
+                    && name != ".."
+                    && !name.contains(['/', MAIN_SEPARATOR]);
+                let extension = self
+                    .filters
+                    .get(self.active)
+                    .and_then(FileFilter::extension)
+                    .filter(|_| Path::new(name).extension().is_none());
+                valid.then(|| match extension {
+                    Some(extension) => vec![dir.join(format!("{}.{}", name, extension))],
+                    None => vec![dir.join(name)],
+                })
+            }
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::FileName(name) => self.file_name = name,
+            Message::SelectFilter(filter) => {
+                if let Some(active) = self.filters.iter().position(|known| known == &filter) {
+                    self.active = active;
+                    return Some(Event::FilterChanged);
+                }
+            }
+            Message::Confirm => return Some(Event::Confirm),
+            Message::Cancel => return Some(Event::Cancel),
+        }
+
+        None
+    }
+
+    /// The bottom bar; `ready` is whether `choice` currently has one.
+    pub fn view(&self, ready: bool) -> Element<'_, Message> {
+        // A typed path is confirmed to go there.
+        let ready = ready || self.typed_path(Path::new("")).is_some();
+        let (confirm, prompt) = match self.mode {
+            DialogMode::OpenFile => ("Open", "Select a file"),
+            DialogMode::OpenFiles => ("Open", "Select one or more files"),
+            DialogMode::OpenDirectory => ("Choose", "Select a folder, or none for this one"),
+            DialogMode::SaveFile => ("Save", ""),
+        };
+
+        let mut bar = row!().spacing(10).align_items(Alignment::Center);
+        bar = if self.mode == DialogMode::SaveFile {
+            let mut name = text_input(tr("File name"), &self.file_name)
+                .on_input(Message::FileName)
+                .padding(5);
+            if ready {
+                name = name.on_submit(Message::Confirm);
+            }
+            bar.push(name)
+        } else {
+            bar.push(text(prompt).width(Length::Fill))
+        };
+
+        if let Some(active) = self.filters.get(self.active) {
+            bar = bar.push(pick_list(
+                self.filters.as_slice(),
+                Some(active.clone()),
+                Message::SelectFilter,
+            ));
+        }
+
+        bar.push(button(tr("Cancel")).on_press(Message::Cancel))
+            .push(button(confirm).on_press_maybe(ready.then_some(Message::Confirm)))
+            .into()
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of
+/// characters and `?` for any one.
+pub fn glob(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            glob(&pattern[1..], name) || (!name.is_empty() && glob(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => glob(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => glob(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
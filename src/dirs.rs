@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fs, io};
+
+/// Name of the folder created inside each platform directory.
+const APP_DIR: &str = "iced-fm";
+
+/// A file next to the executable that turns portable mode on, so a copy on
+/// a USB stick keeps its state on the stick without needing `--portable`.
+pub const PORTABLE_MARKER: &str = "portable.ini";
+
+/// The folder next to the executable everything goes into in portable mode.
+static PORTABLE_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Config,
+    Cache,
+    Data,
+}
+
+/// Decides once, at startup, between platform directories and portable
+/// mode, which `requested` (the `--portable` flag) or the marker file
+/// turns on.
+pub fn init(requested: bool) {
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from));
+    let root = exe_dir.filter(|dir| requested || dir.join(PORTABLE_MARKER).is_file());
+
+    if let Some(root) = &root {
+        tracing::info!(root = %root.display(), "portable mode");
+    }
+    let _ = PORTABLE_ROOT.set(root);
+}
+
+pub fn is_portable() -> bool {
+    portable_root().is_some()
+}
+
+/// Settings.
+pub fn config_dir() -> Option<PathBuf> {
+    dir(Kind::Config)
+}
+
+/// What can be rebuilt, such as thumbnails.
+pub fn cache_dir() -> Option<PathBuf> {
+    dir(Kind::Cache)
+}
+
+/// What cannot be rebuilt, such as tags and history.
+pub fn data_dir() -> Option<PathBuf> {
+    dir(Kind::Data)
+}
+
+/// A folder for sockets and secrets that need not outlive the session,
+/// short enough for the length limit on socket paths: `name` under
+/// `XDG_RUNTIME_DIR` when there is one.
+pub fn runtime_dir(name: &str) -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join(name),
+        None => env::temp_dir().join(format!("{}-{}", name, user_id())),
+    }
+}
+
+/// Creates `dir` if need be, where only this user can look.
+pub fn make_private(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    restrict(dir, 0o700)
+}
+
+/// Leaves `path` to this user alone, `mode` being what the owner keeps.
+#[cfg(unix)]
+pub fn restrict(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(windows)]
+pub fn restrict(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn user_id() -> u32 {
+    // SAFETY: getuid cannot fail.
+    unsafe { libc::getuid() }
+}
+
+#[cfg(windows)]
+fn user_id() -> String {
+    env::var("USERNAME").unwrap_or_default()
+}
+
+fn portable_root() -> Option<&'static PathBuf> {
+    PORTABLE_ROOT.get().and_then(Option::as_ref)
+}
+
+fn dir(kind: Kind) -> Option<PathBuf> {
+    if let Some(root) = portable_root() {
+        let name = match kind {
+            Kind::Config => "config",
+            Kind::Cache => "cache",
+            Kind::Data => "data",
+        };
+        return Some(root.join(APP_DIR).join(name));
+    }
+
+    platform_dir(kind).map(|dir| dir.join(APP_DIR))
+}
+
+#[cfg(windows)]
+fn platform_dir(kind: Kind) -> Option<PathBuf> {
+    let variable = match kind {
+        Kind::Config | Kind::Data => "APPDATA",
+        Kind::Cache => "LOCALAPPDATA",
+    };
+    env::var_os(variable).map(PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_dir(kind: Kind) -> Option<PathBuf> {
+    let home = PathBuf::from(env::var_os("HOME")?);
+    Some(match kind {
+        Kind::Config | Kind::Data => home.join("Library/Application Support"),
+        Kind::Cache => home.join("Library/Caches"),
+    })
+}
+
+/// The XDG base directories, with their documented fallbacks.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_dir(kind: Kind) -> Option<PathBuf> {
+    let (variable, fallback) = match kind {
+        Kind::Config => ("XDG_CONFIG_HOME", ".config"),
+        Kind::Cache => ("XDG_CACHE_HOME", ".cache"),
+        Kind::Data => ("XDG_DATA_HOME", ".local/share"),
+    };
+
+    env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+}
@@ -0,0 +1,240 @@
+//! Dragging entries within the picker. Rows and places are wrapped in
+//! `Drag`, which only watches the mouse and leaves every event to what
+//! it wraps, so buttons inside keep working. What is dragged out of the
+//! window is handed to `drag_out`; drops from other applications come as
+//! window events.
+
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{self, tree, Widget};
+use iced::advanced::{overlay, renderer, Clipboard, Shell};
+use iced::{event, mouse};
+use iced::{Border, Color, Element, Event, Length, Point, Rectangle, Size, Vector};
+
+/// How far the mouse moves with the button held before it is a drag
+/// rather than a click.
+const THRESHOLD: f32 = 8.;
+
+pub struct Drag<'a, Message, Theme, Renderer> {
+    content: Element<'a, Message, Theme, Renderer>,
+    on_drag: Option<Message>,
+    on_drop: Option<Message>,
+    highlight: Option<Color>,
+}
+
+impl<'a, Message, Theme, Renderer> Drag<'a, Message, Theme, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, Theme, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            on_drag: None,
+            on_drop: None,
+            highlight: None,
+        }
+    }
+
+    /// Message produced once when a drag starts here.
+    pub fn on_drag(self, on_drag: Option<Message>) -> Self {
+        Self { on_drag, ..self }
+    }
+
+    /// Message produced when the button is released here, unless the drag
+    /// started here too. The host ignores it when nothing was dragged.
+    pub fn on_drop(self, on_drop: Option<Message>) -> Self {
+        Self { on_drop, ..self }
+    }
+
+    /// Outlines it in `color` while the mouse is over it, for while
+    /// something is being dragged.
+    pub fn highlight(self, highlight: Option<Color>) -> Self {
+        Self { highlight, ..self }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    pressed: Option<Point>,
+    dragging: bool,
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Drag<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+    Message: Clone,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<widget::Tree> {
+        vec![widget::Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut widget::Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        match &event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                state.pressed = cursor.position_over(layout.bounds());
+                state.dragging = false;
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(pressed) = state.pressed.filter(|_| !state.dragging) {
+                    if pressed.distance(*position) > THRESHOLD {
+                        state.dragging = true;
+                        if let Some(message) = &self.on_drag {
+                            shell.publish(message.clone());
+                        }
+                    }
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if let Some(message) = self.on_drop.as_ref().filter(|_| !state.dragging) {
+                    if cursor.is_over(layout.bounds()) {
+                        shell.publish(message.clone());
+                    }
+                }
+                *state = State::default();
+            }
+            _ => {}
+        }
+
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+
+        let Some(color) = self.highlight.filter(|_| self.on_drop.is_some()) else {
+            return;
+        };
+        if cursor.is_over(layout.bounds()) {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: layout.bounds(),
+                    border: Border {
+                        color,
+                        width: 2.,
+                        radius: 2.into(),
+                    },
+                    ..renderer::Quad::default()
+                },
+                Color::TRANSPARENT,
+            );
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if tree.state.downcast_ref::<State>().dragging {
+            return mouse::Interaction::Grabbing;
+        }
+
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn operate(
+        &self,
+        tree: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Drag<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer + 'a,
+    Message: Clone + 'a,
+    Theme: 'a,
+{
+    fn from(drag: Drag<'a, Message, Theme, Renderer>) -> Self {
+        Self::new(drag)
+    }
+}
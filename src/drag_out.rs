@@ -0,0 +1,440 @@
+//! Dragging entries out of the window into other applications, offered as
+//! `text/uri-list`. winit cannot start a system drag, so one is started
+//! here once a drag leaves the window: on X11 as an XDND source on a
+//! connection of its own, following the pointer until the button goes up,
+//! and on Windows through the shell's own drag loop. Wayland only lets the
+//! surface that holds the pointer start one, and macOS the view handling
+//! the mouse event, neither of which winit gives access to, so there the
+//! entries stay within the window.
+
+use std::io;
+use std::path::PathBuf;
+
+use iced::window::raw_window_handle::{RawWindowHandle, WindowHandle};
+
+/// Starts dragging `paths` out of the window of `handle`.
+pub fn start(handle: &WindowHandle<'_>, paths: Vec<PathBuf>) -> io::Result<()> {
+    tracing::info!(count = paths.len(), "dragging out");
+    match handle.as_raw() {
+        #[cfg(target_os = "linux")]
+        RawWindowHandle::Xlib(handle) => x11::start(handle.window as u32, paths),
+        #[cfg(target_os = "linux")]
+        RawWindowHandle::Xcb(handle) => x11::start(handle.window.get(), paths),
+        #[cfg(windows)]
+        RawWindowHandle::Win32(handle) => shell::start(handle.hwnd.get(), &paths),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this windowing system does not let windows start drags",
+        )),
+    }
+}
+
+/// The `text/uri-list` of `paths`: a URI a line, each ended by CRLF.
+#[cfg(target_os = "linux")]
+fn uri_list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("{}\r\n", crate::paths::to_uri(path)))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::error::Error;
+    use std::io;
+    use std::path::PathBuf;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        AtomEnum, ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask, KeyButMask,
+        PropMode, SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass,
+        SELECTION_NOTIFY_EVENT,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+
+    use super::uri_list;
+
+    /// The newest XDND version spoken, and the oldest a target may speak.
+    const VERSION: u32 = 5;
+    const OLDEST: u32 = 3;
+    /// How often the pointer is looked at.
+    const POLL: Duration = Duration::from_millis(16);
+    /// How long a target has to take the data once dropped on.
+    const FINISH: Duration = Duration::from_secs(10);
+
+    x11rb::atom_manager! {
+        Atoms: AtomsCookie {
+            XdndAware,
+            XdndEnter,
+            XdndPosition,
+            XdndStatus,
+            XdndLeave,
+            XdndDrop,
+            XdndFinished,
+            XdndSelection,
+            XdndActionCopy,
+            XdndActionMove,
+            TARGETS,
+            URI_LIST: b"text/uri-list",
+        }
+    }
+
+    type Fallible<T> = Result<T, Box<dyn Error>>;
+
+    /// Owns the drag selection with a window of its own, then follows the
+    /// pointer in the background. `own` is the window dragged from, left
+    /// out as a target since drops on it come back as window events.
+    pub fn start(own: Window, paths: Vec<PathBuf>) -> io::Result<()> {
+        let source = Source::new(own, uri_list(&paths))
+            .map_err(|error| io::Error::other(error.to_string()))?;
+        thread::spawn(move || {
+            if let Err(error) = source.run() {
+                tracing::warn!(%error, "the drag out failed");
+            }
+        });
+        Ok(())
+    }
+
+    struct Source {
+        conn: RustConnection,
+        atoms: Atoms,
+        root: Window,
+        window: Window,
+        own: Window,
+        data: String,
+        /// The window under the pointer that takes drops, and its version.
+        target: Option<(Window, u32)>,
+        accepted: bool,
+        /// Sent a position the target has not answered yet.
+        waiting: bool,
+        finished: bool,
+    }
+
+    impl Source {
+        fn new(own: Window, data: String) -> Fallible<Source> {
+            let (conn, screen) = x11rb::connect(None)?;
+            let atoms = Atoms::new(&conn)?.reply()?;
+            let root = conn.setup().roots[screen].root;
+            let window = conn.generate_id()?;
+            conn.create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                -1,
+                -1,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                0,
+                &CreateWindowAux::new(),
+            )?;
+            conn.set_selection_owner(window, atoms.XdndSelection, CURRENT_TIME)?;
+            conn.flush()?;
+            Ok(Source {
+                conn,
+                atoms,
+                root,
+                window,
+                own,
+                data,
+                target: None,
+                accepted: false,
+                waiting: false,
+                finished: false,
+            })
+        }
+
+        fn run(mut self) -> Fallible<()> {
+            let mut last = None;
+            let mut action = self.atoms.XdndActionCopy;
+            loop {
+                self.answer()?;
+                let pointer = self.conn.query_pointer(self.root)?.reply()?;
+                if !pointer.mask.contains(KeyButMask::BUTTON1) {
+                    break;
+                }
+                // Shift asks for a move, as it does for drops on the window.
+                action = match pointer.mask.contains(KeyButMask::SHIFT) {
+                    true => self.atoms.XdndActionMove,
+                    false => self.atoms.XdndActionCopy,
+                };
+
+                let under = self.target_under()?;
+                if under.map(|(window, _)| window) != self.target.map(|(window, _)| window) {
+                    if let Some((target, _)) = self.target {
+                        self.send(target, self.atoms.XdndLeave, [self.window, 0, 0, 0, 0])?;
+                    }
+                    if let Some((target, version)) = under {
+                        let enter = [self.window, version << 24, self.atoms.URI_LIST, 0, 0];
+                        self.send(target, self.atoms.XdndEnter, enter)?;
+                    }
+                    self.target = under;
+                    self.accepted = false;
+                    self.waiting = false;
+                    last = None;
+                }
+                let position = (pointer.root_x, pointer.root_y, action);
+                if let Some((target, _)) = self.target.filter(|_| !self.waiting) {
+                    if last != Some(position) {
+                        let (x, y) = (pointer.root_x as u16 as u32, pointer.root_y as u16 as u32);
+                        let at = [self.window, 0, x << 16 | y, CURRENT_TIME, action];
+                        self.send(target, self.atoms.XdndPosition, at)?;
+                        self.waiting = true;
+                        last = Some(position);
+                    }
+                }
+                self.conn.flush()?;
+                thread::sleep(POLL);
+            }
+
+            match self.target {
+                Some((target, _)) if self.accepted => {
+                    tracing::debug!(target, action, "dropped out");
+                    self.send(
+                        target,
+                        self.atoms.XdndDrop,
+                        [self.window, 0, CURRENT_TIME, 0, 0],
+                    )?;
+                    self.conn.flush()?;
+                    let started = Instant::now();
+                    while !self.finished && started.elapsed() < FINISH {
+                        self.answer()?;
+                        thread::sleep(POLL);
+                    }
+                }
+                Some((target, _)) => {
+                    self.send(target, self.atoms.XdndLeave, [self.window, 0, 0, 0, 0])?;
+                }
+                None => {}
+            }
+            self.conn.destroy_window(self.window)?;
+            self.conn.flush()?;
+            Ok(())
+        }
+
+        /// The top-level window under the pointer taking drops, if any.
+        fn target_under(&self) -> Fallible<Option<(Window, u32)>> {
+            let mut window = self.root;
+            loop {
+                let child = self.conn.query_pointer(window)?.reply()?.child;
+                if child == NONE || child == self.own || child == self.window {
+                    return Ok(None);
+                }
+                let aware = self
+                    .conn
+                    .get_property(false, child, self.atoms.XdndAware, AtomEnum::ATOM, 0, 1)?
+                    .reply()?;
+                if let Some(version) = aware.value32().and_then(|mut values| values.next()) {
+                    return Ok((version >= OLDEST).then_some((child, version.min(VERSION))));
+                }
+                window = child;
+            }
+        }
+
+        /// Takes in what the target says, and hands it the data it asks
+        /// for.
+        fn answer(&mut self) -> Fallible<()> {
+            while let Some(event) = self.conn.poll_for_event()? {
+                match event {
+                    Event::ClientMessage(message) if message.type_ == self.atoms.XdndStatus => {
+                        let data = message.data.as_data32();
+                        if self.target.is_some_and(|(target, _)| target == data[0]) {
+                            self.accepted = data[1] & 1 != 0;
+                            self.waiting = false;
+                        }
+                    }
+                    Event::ClientMessage(message) if message.type_ == self.atoms.XdndFinished => {
+                        self.finished = true;
+                    }
+                    Event::SelectionRequest(request) => self.provide(&request)?,
+                    _ => {}
+                }
+            }
+            self.conn.flush()?;
+            Ok(())
+        }
+
+        fn provide(&self, request: &SelectionRequestEvent) -> Fallible<()> {
+            let property = match request.property {
+                NONE => request.target,
+                property => property,
+            };
+            let provided = if request.target == self.atoms.URI_LIST {
+                self.conn.change_property8(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    self.atoms.URI_LIST,
+                    self.data.as_bytes(),
+                )?;
+                true
+            } else if request.target == self.atoms.TARGETS {
+                self.conn.change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &[self.atoms.TARGETS, self.atoms.URI_LIST],
+                )?;
+                true
+            } else {
+                false
+            };
+            let notify = SelectionNotifyEvent {
+                response_type: SELECTION_NOTIFY_EVENT,
+                sequence: 0,
+                time: request.time,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                property: if provided { property } else { NONE },
+            };
+            self.conn
+                .send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+            Ok(())
+        }
+
+        fn send(&self, target: Window, kind: u32, data: [u32; 5]) -> Fallible<()> {
+            let message = ClientMessageEvent::new(32, target, kind, data);
+            self.conn
+                .send_event(false, target, EventMask::NO_EVENT, message)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(windows)]
+mod shell {
+    use std::ffi::c_void;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::ptr;
+
+    use windows_sys::core::GUID;
+    use windows_sys::Win32::System::Ole::{
+        OleInitialize, OleUninitialize, DROPEFFECT_COPY, DROPEFFECT_LINK, DROPEFFECT_MOVE,
+    };
+    use windows_sys::Win32::UI::Shell::SHDoDragDrop;
+
+    /// An item ID list, only ever handled through a pointer.
+    type IdList = c_void;
+
+    // windows-sys gates these behind a feature it does not define.
+    #[link(name = "shell32")]
+    extern "system" {
+        fn ILCreateFromPathW(path: *const u16) -> *mut IdList;
+        fn ILFindLastID(list: *const IdList) -> *mut IdList;
+        fn ILFree(list: *const IdList);
+        fn SHCreateDataObject(
+            folder: *const IdList,
+            count: u32,
+            children: *const *const IdList,
+            inner: *mut c_void,
+            riid: *const GUID,
+            object: *mut *mut c_void,
+        ) -> i32;
+    }
+
+    const IID_IDATAOBJECT: GUID = GUID::from_u128(0x0000010e_0000_0000_c000_000000000046);
+
+    /// The start of every COM interface's table, for `Release`.
+    #[repr(C)]
+    struct Unknown {
+        query_interface: usize,
+        add_ref: usize,
+        release: unsafe extern "system" fn(*mut c_void) -> u32,
+    }
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    /// Runs the shell's drag loop for `paths`, those in the folder of the
+    /// first: a shell data object holds the items of one folder. Returns
+    /// once they are dropped, the window's events waiting until then as
+    /// they do while it is moved.
+    pub fn start(window: isize, paths: &[PathBuf]) -> io::Result<()> {
+        let Some(folder) = paths.first().and_then(|path| path.parent()) else {
+            return Ok(());
+        };
+        let items: Vec<&PathBuf> = paths
+            .iter()
+            .filter(|path| path.parent() == Some(folder))
+            .collect();
+
+        // SAFETY: the paths are NUL-terminated, every ID list made is freed
+        // after the drag, and the data object is released once done with.
+        unsafe {
+            let initialized = OleInitialize(ptr::null()) >= 0;
+            let folder = ILCreateFromPathW(wide(folder).as_ptr());
+            let lists: Vec<*mut IdList> = items
+                .iter()
+                .map(|path| ILCreateFromPathW(wide(path).as_ptr()))
+                .filter(|list| !list.is_null())
+                .collect();
+            let children: Vec<*const IdList> = lists
+                .iter()
+                .map(|list| ILFindLastID(*list) as *const IdList)
+                .collect();
+
+            let mut result = Err(io::Error::other("cannot describe the files to the shell"));
+            let mut data: *mut c_void = ptr::null_mut();
+            if !folder.is_null()
+                && !children.is_empty()
+                && SHCreateDataObject(
+                    folder,
+                    children.len() as u32,
+                    children.as_ptr(),
+                    ptr::null_mut(),
+                    &IID_IDATAOBJECT,
+                    &mut data,
+                ) >= 0
+            {
+                let mut effect = 0;
+                let allowed = DROPEFFECT_COPY | DROPEFFECT_MOVE | DROPEFFECT_LINK;
+                let dragged = SHDoDragDrop(window, data, ptr::null_mut(), allowed, &mut effect);
+                result = match dragged >= 0 {
+                    true => {
+                        tracing::debug!(effect, "dropped out");
+                        Ok(())
+                    }
+                    false => Err(io::Error::from_raw_os_error(dragged)),
+                };
+                let table = *(data as *const *const Unknown);
+                ((*table).release)(data);
+            }
+
+            for list in lists {
+                ILFree(list);
+            }
+            if !folder.is_null() {
+                ILFree(folder);
+            }
+            if initialized {
+                OleUninitialize();
+            }
+            result
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_uris_a_line() {
+        let list = uri_list(&[PathBuf::from("/a b/c.txt"), PathBuf::from("/d")]);
+        assert_eq!(list, "file:///a%20b/c.txt\r\nfile:///d\r\n");
+    }
+}
@@ -0,0 +1,528 @@
+//! "Find duplicates": files below a folder are grouped by size, then by the
+//! hash of their start and then of all of them, in the background. The
+//! copies checked in each group can then be trashed or replaced with hard
+//! links to the one kept.
+
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::{button, checkbox, column, container, row, scrollable, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::checksum;
+use crate::devices;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::style::Style;
+
+/// How much of each file is hashed first, to tell most apart cheaply.
+const START: u64 = 4096;
+/// Groups shown at once, most space wasted first.
+const SHOWN: usize = 200;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Found {
+    /// Files listed so far.
+    Files(u64),
+    /// Bytes hashed of those that might have copies.
+    Hashed(u64, u64),
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Progress(usize, Found),
+    Scanned(usize, Result<Vec<Group>, String>),
+    Toggle(PathBuf, bool),
+    /// Checks all but the first file of every group.
+    CheckCopies,
+    UncheckAll,
+    Trash,
+    Link,
+    /// The copies replaced with links, and the first failure.
+    Linked(Vec<PathBuf>, Option<String>),
+    Rescan,
+    Cancel,
+    Close,
+}
+
+pub enum Event {
+    Trash(Vec<PathBuf>),
+    /// Each copy, to be replaced with a link to the file before it.
+    Link(Vec<(PathBuf, PathBuf)>),
+    Closed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Group {
+    size: u64,
+    /// By path.
+    files: Vec<PathBuf>,
+}
+
+impl Group {
+    fn wasted(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+enum State {
+    Scanning(Found),
+    Done(Vec<Group>),
+    Failed(String),
+    Cancelled,
+}
+
+pub struct Duplicates {
+    root: PathBuf,
+    /// Counts scans so a rescan ignores what an earlier one still sends.
+    scan: usize,
+    stop: Arc<AtomicBool>,
+    state: State,
+    checked: HashSet<PathBuf>,
+    linking: bool,
+    notice: Option<String>,
+}
+
+impl Duplicates {
+    pub fn open(root: PathBuf) -> Duplicates {
+        Duplicates {
+            root,
+            scan: 0,
+            stop: Arc::new(AtomicBool::new(false)),
+            state: State::Scanning(Found::Files(0)),
+            checked: HashSet::new(),
+            linking: false,
+            notice: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Progress(scan, found) if scan == self.scan => {
+                if let State::Scanning(_) = self.state {
+                    self.state = State::Scanning(found);
+                }
+            }
+            Message::Scanned(scan, result) if scan == self.scan => {
+                if let State::Scanning(_) = self.state {
+                    self.state = match result {
+                        Ok(groups) => State::Done(groups),
+                        Err(error) => State::Failed(error),
+                    };
+                }
+            }
+            Message::Progress(..) | Message::Scanned(..) => {}
+            Message::Toggle(path, checked) => {
+                if checked {
+                    self.checked.insert(path);
+                } else {
+                    self.checked.remove(&path);
+                }
+            }
+            Message::CheckCopies => {
+                if let State::Done(groups) = &self.state {
+                    for group in groups {
+                        self.checked.extend(group.files.iter().skip(1).cloned());
+                    }
+                }
+            }
+            Message::UncheckAll => self.checked.clear(),
+            Message::Trash => {
+                let trashed: Vec<PathBuf> = self.checked.drain().collect();
+                self.remove(&trashed);
+                self.notice = None;
+                return Some(Event::Trash(trashed));
+            }
+            Message::Link => {
+                let pairs = self.links();
+                if !pairs.is_empty() {
+                    self.linking = true;
+                    self.notice = None;
+                    return Some(Event::Link(pairs));
+                }
+            }
+            Message::Linked(linked, error) => {
+                self.linking = false;
+                self.remove(&linked);
+                self.notice = Some(match error {
+                    Some(error) => error,
+                    None if linked.len() == 1 => String::from("Replaced 1 copy with a link"),
+                    None => trf("Replaced {} copies with links", &[&linked.len()]),
+                });
+            }
+            Message::Rescan => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.stop = Arc::new(AtomicBool::new(false));
+                self.scan += 1;
+                self.checked.clear();
+                self.notice = None;
+                self.state = State::Scanning(Found::Files(0));
+            }
+            Message::Cancel => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.state = State::Cancelled;
+            }
+            Message::Close => {
+                self.stop.store(true, Ordering::Relaxed);
+                return Some(Event::Closed);
+            }
+        }
+
+        None
+    }
+
+    fn groups(&self) -> &[Group] {
+        match &self.state {
+            State::Done(groups) => groups,
+            _ => &[],
+        }
+    }
+
+    /// The checked copies, each with the first unchecked file of its group.
+    /// Groups checked throughout have nothing left to link to.
+    fn links(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut pairs = Vec::new();
+        for group in self.groups() {
+            let Some(kept) = group
+                .files
+                .iter()
+                .find(|file| !self.checked.contains(*file))
+            else {
+                continue;
+            };
+            pairs.extend(
+                group
+                    .files
+                    .iter()
+                    .filter(|file| self.checked.contains(*file))
+                    .map(|copy| (kept.clone(), copy.clone())),
+            );
+        }
+
+        pairs
+    }
+
+    /// Drops `paths` from their groups, and groups left with a single file.
+    fn remove(&mut self, paths: &[PathBuf]) {
+        if let State::Done(groups) = &mut self.state {
+            for group in groups.iter_mut() {
+                group.files.retain(|file| !paths.contains(file));
+            }
+            groups.retain(|group| group.files.len() > 1);
+        }
+        for path in paths {
+            self.checked.remove(path);
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Scan;
+
+        if !matches!(self.state, State::Scanning(_)) {
+            return Subscription::none();
+        }
+
+        let (scan, root, stop) = (self.scan, self.root.clone(), self.stop.clone());
+        subscription::channel(
+            (TypeId::of::<Scan>(), root.clone(), scan),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    let Some(_turn) = devices::wait_for(&[&root], || stop.load(Ordering::Relaxed))
+                    else {
+                        return;
+                    };
+                    let mut reported = Instant::now();
+                    let mut report = |found| {
+                        if reported.elapsed() >= PROGRESS_INTERVAL {
+                            reported = Instant::now();
+                            let _ = sender.unbounded_send(Message::Progress(scan, found));
+                        }
+                    };
+                    let result = find(&root, &stop, &mut report);
+                    let _ = sender.unbounded_send(Message::Scanned(scan, result));
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let groups = self.groups();
+        let mut header = row!(text(self.root.display().to_string()).width(Length::Fill))
+            .spacing(10)
+            .align_items(Alignment::Center);
+        if let State::Done(_) = self.state {
+            let wasted: u64 = groups.iter().map(Group::wasted).sum();
+            header = header.push(text(trf(
+                "{} groups, {} in copies",
+                &[&groups.len(), &format_bytes(wasted)],
+            )));
+        }
+
+        let body: Element<Message> = match &self.state {
+            State::Scanning(Found::Files(files)) => {
+                text(trf("Listing… {} files", &[&files])).into()
+            }
+            State::Scanning(Found::Hashed(bytes, total)) => text(trf(
+                "Comparing… {} of {}",
+                &[&format_bytes(*bytes), &format_bytes(*total)],
+            ))
+            .into(),
+            State::Failed(error) => text(error).style(theme::Text::Color(style.error)).into(),
+            State::Cancelled => text(tr("Scan cancelled")).into(),
+            State::Done(groups) if groups.is_empty() => text(tr("No duplicates found")).into(),
+            State::Done(groups) => {
+                let mut col = column!().spacing(10);
+                for group in groups.iter().take(SHOWN) {
+                    let mut files = column!(text(trf(
+                        "{} copies of {}",
+                        &[&group.files.len(), &format_bytes(group.size)]
+                    ))
+                    .size(style.small_text_size))
+                    .spacing(2);
+                    for file in &group.files {
+                        let shown = file.strip_prefix(&self.root).unwrap_or(file);
+                        let path = file.clone();
+                        files = files.push(
+                            checkbox(shown.display().to_string(), self.checked.contains(file))
+                                .on_toggle(move |checked| Message::Toggle(path.clone(), checked)),
+                        );
+                    }
+                    col = col.push(files);
+                }
+                if groups.len() > SHOWN {
+                    col = col.push(
+                        text(trf("and {} more groups", &[&(groups.len() - SHOWN)]))
+                            .size(style.small_text_size),
+                    );
+                }
+                scrollable(col).height(Length::Fill).into()
+            }
+        };
+
+        let scanning = matches!(self.state, State::Scanning(_));
+        let checked = !self.checked.is_empty() && !self.linking;
+        let mut col = column!(
+            text(tr("Duplicates")).size(20),
+            header,
+            container(body).height(Length::Fill),
+        )
+        .spacing(10);
+        if self.linking {
+            col = col.push(text(tr("Linking…")).size(style.small_text_size));
+        } else if let Some(notice) = &self.notice {
+            col = col.push(text(notice).size(style.small_text_size));
+        }
+        col = col.push(
+            row!(
+                button(tr("Check all but one"))
+                    .on_press_maybe((!groups.is_empty()).then_some(Message::CheckCopies)),
+                button(tr("Uncheck all")).on_press_maybe(checked.then_some(Message::UncheckAll)),
+                text("").width(Length::Fill),
+                button(tr("Trash checked")).on_press_maybe(checked.then_some(Message::Trash)),
+                button(tr("Replace with links"))
+                    .on_press_maybe((checked && !self.links().is_empty()).then_some(Message::Link)),
+                if scanning {
+                    button(tr("Cancel")).on_press(Message::Cancel)
+                } else {
+                    button(tr("Rescan")).on_press(Message::Rescan)
+                },
+                button(tr("Close")).on_press(Message::Close),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .max_width(900)
+            .max_height(700)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+/// Groups of files below `root` with the same contents, most space wasted
+/// first. Symlinks are not followed, empty files are left out, and hard
+/// links to one file count once.
+fn find(
+    root: &Path,
+    stop: &AtomicBool,
+    report: &mut impl FnMut(Found),
+) -> Result<Vec<Group>, String> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut files = 0;
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                tracing::debug!(%error, dir = %dir.display(), "cannot list folder for duplicates");
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            if stop.load(Ordering::Relaxed) {
+                return Err(String::from("Cancelled"));
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if metadata.is_file()
+                && metadata.len() > 0
+                && seen.insert(identity(&metadata, &entry.path()))
+            {
+                files += 1;
+                report(Found::Files(files));
+                by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push(entry.path());
+            }
+        }
+    }
+
+    let total = by_size
+        .iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(size, files)| size * files.len() as u64)
+        .sum();
+    let mut hashed = 0;
+    let mut groups = Vec::new();
+    for (size, files) in by_size.into_iter().filter(|(_, files)| files.len() > 1) {
+        let mut by_start: HashMap<Vec<u8>, Vec<PathBuf>> = HashMap::new();
+        for file in files {
+            if let Ok(hash) = checksum::sha256(&file, Some(START), stop) {
+                by_start.entry(hash).or_default().push(file);
+            }
+            if stop.load(Ordering::Relaxed) {
+                return Err(String::from("Cancelled"));
+            }
+        }
+
+        for (_, files) in by_start {
+            if files.len() < 2 || size <= START {
+                hashed += size * files.len() as u64;
+                if files.len() > 1 {
+                    groups.push(Group { size, files });
+                }
+                continue;
+            }
+            let mut by_hash: HashMap<Vec<u8>, Vec<PathBuf>> = HashMap::new();
+            for file in files {
+                if let Ok(hash) = checksum::sha256(&file, None, stop) {
+                    by_hash.entry(hash).or_default().push(file);
+                }
+                if stop.load(Ordering::Relaxed) {
+                    return Err(String::from("Cancelled"));
+                }
+                hashed += size;
+                report(Found::Hashed(hashed, total));
+            }
+            groups.extend(
+                by_hash
+                    .into_values()
+                    .filter(|files| files.len() > 1)
+                    .map(|files| Group { size, files }),
+            );
+        }
+        report(Found::Hashed(hashed, total));
+    }
+
+    for group in &mut groups {
+        group.files.sort();
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.wasted()));
+
+    Ok(groups)
+}
+
+/// What tells hard links to the same file apart from copies.
+#[cfg(unix)]
+fn identity(metadata: &fs::Metadata, _path: &Path) -> (u64, u64, PathBuf) {
+    use std::os::unix::fs::MetadataExt;
+
+    (metadata.dev(), metadata.ino(), PathBuf::new())
+}
+
+#[cfg(not(unix))]
+fn identity(_metadata: &fs::Metadata, path: &Path) -> (u64, u64, PathBuf) {
+    (0, 0, path.to_path_buf())
+}
+
+/// Replaces each copy with a hard link to the file given with it, once
+/// they are found to still match. Gives the copies replaced and the first
+/// failure.
+pub fn link(pairs: Vec<(PathBuf, PathBuf)>) -> (Vec<PathBuf>, Option<String>) {
+    let mut linked = Vec::new();
+    let mut failure = None;
+    for (kept, copy) in pairs {
+        match replace_with_link(&kept, &copy) {
+            Ok(()) => linked.push(copy),
+            Err(error) => {
+                tracing::warn!(%error, copy = %copy.display(), "cannot replace copy with a link");
+                failure
+                    .get_or_insert_with(|| trf("Cannot link {}: {}", &[&copy.display(), &error]));
+            }
+        }
+    }
+
+    (linked, failure)
+}
+
+fn replace_with_link(kept: &Path, copy: &Path) -> io::Result<()> {
+    if !same_contents(kept, copy)? {
+        return Err(io::Error::other("it changed since the scan"));
+    }
+
+    // Linked beside the copy first, so the copy is only replaced whole.
+    let mut name = copy.file_name().unwrap_or_default().to_os_string();
+    name.push(".link");
+    let mut temporary = copy.with_file_name(format!(".{}", name.to_string_lossy()));
+    while fs::symlink_metadata(&temporary).is_ok() {
+        temporary.as_mut_os_string().push("~");
+    }
+    fs::hard_link(kept, &temporary)?;
+    fs::rename(&temporary, copy).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (fs::File::open(a)?, fs::File::open(b)?);
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+    let (mut left, mut right) = (vec![0; 64 * 1024], vec![0; 64 * 1024]);
+    loop {
+        let read = a.read(&mut left)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut right[..read])?;
+        if left[..read] != right[..read] {
+            return Ok(false);
+        }
+    }
+}
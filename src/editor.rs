@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use iced::widget::{button, column, container, row, text, text_editor};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::{tr, trf};
+use crate::safe_save;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Action(text_editor::Action),
+    Save,
+    /// Drops the edits for what is on disk now.
+    Reload,
+    /// Saves over what another program changed.
+    Overwrite,
+    /// Goes on editing, leaving the conflict for the next save.
+    KeepEditing,
+    Close,
+}
+
+pub enum Event {
+    Saved(PathBuf),
+    Closed,
+}
+
+/// The version of a file on disk, to tell when another program saved it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    /// `None` when the file is gone.
+    fn of(path: &Path) -> Option<Stamp> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Stamp {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conflict {
+    Changed,
+    Removed,
+}
+
+pub struct Editor {
+    path: PathBuf,
+    content: text_editor::Content,
+    modified: bool,
+    error: Option<String>,
+    /// What was opened or last saved.
+    disk: Option<Stamp>,
+    conflict: Option<Conflict>,
+}
+
+impl Editor {
+    pub fn open(path: PathBuf) -> Result<Editor, String> {
+        let disk = Stamp::of(&path);
+        let contents = read(&path)?;
+
+        Ok(Editor {
+            path,
+            content: text_editor::Content::with_text(&contents),
+            modified: false,
+            error: None,
+            disk,
+            conflict: None,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Looks at the file again after the watcher saw it change. Without
+    /// edits a changed file is simply reloaded; with them, the user is
+    /// asked before either side is lost.
+    pub fn check_disk(&mut self) {
+        let disk = Stamp::of(&self.path);
+        if disk == self.disk {
+            return;
+        }
+        match disk {
+            Some(_) if !self.modified => self.reload(),
+            Some(_) => self.conflict = Some(Conflict::Changed),
+            None => self.conflict = Some(Conflict::Removed),
+        }
+    }
+
+    fn reload(&mut self) {
+        let disk = Stamp::of(&self.path);
+        match read(&self.path) {
+            Ok(contents) => {
+                self.content = text_editor::Content::with_text(&contents);
+                self.modified = false;
+                self.error = None;
+                self.disk = disk;
+                self.conflict = None;
+            }
+            Err(error) => {
+                tracing::warn!(path = %self.path.display(), %error, "reload failed");
+                self.error = Some(trf("Reload failed: {}", &[&error]));
+            }
+        }
+    }
+
+    fn save(&mut self) -> Option<Event> {
+        match safe_save::write(&self.path, self.content.text().as_bytes()) {
+            Ok(()) => {
+                self.modified = false;
+                self.error = None;
+                self.disk = Stamp::of(&self.path);
+                self.conflict = None;
+                Some(Event::Saved(self.path.clone()))
+            }
+            Err(error) => {
+                tracing::warn!(path = %self.path.display(), %error, "save failed");
+                self.error = Some(trf("Save failed: {}", &[&error]));
+                None
+            }
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Action(action) => {
+                self.modified |= action.is_edit();
+                self.content.perform(action);
+                None
+            }
+            Message::Save => {
+                // The watcher may not have told yet, or at all.
+                let disk = Stamp::of(&self.path);
+                if disk != self.disk {
+                    self.conflict = Some(match disk {
+                        Some(_) => Conflict::Changed,
+                        None => Conflict::Removed,
+                    });
+                    return None;
+                }
+                self.save()
+            }
+            Message::Overwrite => self.save(),
+            Message::Reload => {
+                self.reload();
+                None
+            }
+            Message::KeepEditing => {
+                self.conflict = None;
+                None
+            }
+            Message::Close => Some(Event::Closed),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let title = if self.modified {
+            format!("{} *", self.path.display())
+        } else {
+            self.path.display().to_string()
+        };
+
+        let mut col = column!(
+            text(title),
+            text_editor(&self.content)
+                .on_action(Message::Action)
+                .height(Length::Fill),
+        )
+        .spacing(10);
+
+        if let Some(error) = &self.error {
+            col = col.push(text(error));
+        }
+
+        if let Some(conflict) = self.conflict {
+            let (warning, overwrite) = match conflict {
+                Conflict::Changed => ("Another program changed this file.", "Overwrite"),
+                Conflict::Removed => ("Another program removed this file.", "Save again"),
+            };
+            let mut choices = row!(text(warning).width(Length::Fill))
+                .spacing(5)
+                .align_items(Alignment::Center);
+            if conflict == Conflict::Changed {
+                choices = choices.push(button(tr("Reload")).on_press(Message::Reload));
+            }
+            choices = choices
+                .push(button(overwrite).on_press(Message::Overwrite))
+                .push(button(tr("Keep editing")).on_press(Message::KeepEditing));
+            col = col.push(container(choices).padding(5).style(theme::Container::Box));
+        }
+
+        col = col.push(
+            row!(
+                button(tr("Save")).on_press_maybe(self.modified.then_some(Message::Save)),
+                button(tr("Close")).on_press(Message::Close),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .max_width(900)
+            .max_height(700)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    let contents = fs::read(path).map_err(|error| error.to_string())?;
+    String::from_utf8(contents).map_err(|_| "not a UTF-8 text file".to_string())
+}
@@ -0,0 +1,120 @@
+//! Emblems: small marks drawn on the icons in the listing. The picker
+//! draws those for symlinks, read-only files and changes in version
+//! control itself; host applications and their plugins add their own, such
+//! as the sync status of a cloud folder, through `Emblems`.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use iced::widget::{row, text, tooltip};
+use iced::{theme, Alignment, Color, Element};
+
+use crate::style::{Icons, Style};
+
+type Source = Arc<dyn Fn(&Path) -> Vec<Emblem> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emblem {
+    /// Drawn in the icon font, like the icons.
+    pub glyph: String,
+    /// The text's color when `None`.
+    pub color: Option<Color>,
+    /// Shown on hovering the emblem.
+    pub description: String,
+}
+
+impl Emblem {
+    pub fn new(glyph: impl Into<String>, description: impl Into<String>) -> Emblem {
+        Emblem {
+            glyph: glyph.into(),
+            color: None,
+            description: description.into(),
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// Where the emblems beyond the picker's own come from; set them with
+/// `FilePicker::set_emblems`.
+#[derive(Clone, Default)]
+pub struct Emblems {
+    sources: Vec<Source>,
+}
+
+impl Emblems {
+    pub fn new() -> Emblems {
+        Emblems::default()
+    }
+
+    /// Adds the emblems `emblems` gives for each listed path, after those
+    /// of earlier sources. It is asked every time the listing is drawn, so
+    /// it should answer from what it already knows rather than wait on the
+    /// disk or the network.
+    pub fn source(
+        mut self,
+        emblems: impl Fn(&Path) -> Vec<Emblem> + Send + Sync + 'static,
+    ) -> Self {
+        self.sources.push(Arc::new(emblems));
+        self
+    }
+
+    pub(crate) fn of(&self, path: &Path) -> Vec<Emblem> {
+        self.sources
+            .iter()
+            .flat_map(|source| source(path))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for Emblems {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Emblems")
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
+
+/// A row's icon with its emblems after it, drawn small; `None` for an
+/// empty icon without any.
+pub(crate) fn icon<'a, M: 'a>(
+    icon: &str,
+    emblems: Vec<Emblem>,
+    style: &Style,
+) -> Option<Element<'a, M>> {
+    if icon.is_empty() && emblems.is_empty() {
+        return None;
+    }
+    let icons = &style.icons;
+    let mut composed = row!().align_items(Alignment::Start);
+    if !icon.is_empty() {
+        composed = composed.push(glyph(icon, icons));
+    }
+    for emblem in emblems {
+        let mut mark = glyph(&emblem.glyph, icons).size(style.small_text_size);
+        if let Some(color) = emblem.color {
+            mark = mark.style(theme::Text::Color(color));
+        }
+        composed = composed.push(
+            tooltip(
+                mark,
+                text(emblem.description),
+                tooltip::Position::FollowCursor,
+            )
+            .style(theme::Container::Box),
+        );
+    }
+
+    Some(composed.into())
+}
+
+pub(crate) fn glyph<'a>(glyph: &str, icons: &Icons) -> iced::widget::Text<'a> {
+    let glyph = text(glyph);
+    match icons.font {
+        Some(font) => glyph.font(font),
+        None => glyph,
+    }
+}
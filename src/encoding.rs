@@ -0,0 +1,254 @@
+//! Telling which encoding a text file is in from its bytes, and rewriting it
+//! as UTF-8. Byte order marks are trusted first; without one, UTF-16 shows
+//! in the zero bytes of its ASCII characters, and anything not valid UTF-8
+//! is taken for the Windows Western codepage, the most common legacy one.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::safe_save;
+
+/// How much of a file is looked at to tell its encoding.
+const SAMPLE_BYTES: u64 = 64 * 1024;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Encoding::Ascii => "ASCII",
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Windows1252 => "Windows-1252",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Detected {
+    pub encoding: Encoding,
+    /// Whether the text starts with a byte order mark.
+    pub bom: bool,
+}
+
+impl Detected {
+    /// Whether rewriting as UTF-8 with or without a mark changes nothing.
+    pub fn is_utf8(&self, bom: bool) -> bool {
+        matches!(self.encoding, Encoding::Ascii | Encoding::Utf8) && self.bom == bom
+    }
+}
+
+impl std::fmt::Display for Detected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bom {
+            write!(f, "{} with BOM", self.encoding)
+        } else {
+            write!(f, "{}", self.encoding)
+        }
+    }
+}
+
+/// What becomes of the byte order mark when converting to UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrderMark {
+    /// Written when the file had one.
+    #[default]
+    Keep,
+    Add,
+    Remove,
+}
+
+impl ByteOrderMark {
+    pub const ALL: [ByteOrderMark; 3] = [
+        ByteOrderMark::Keep,
+        ByteOrderMark::Add,
+        ByteOrderMark::Remove,
+    ];
+
+    fn wanted(self, detected: Detected) -> bool {
+        match self {
+            ByteOrderMark::Keep => detected.bom,
+            ByteOrderMark::Add => true,
+            ByteOrderMark::Remove => false,
+        }
+    }
+
+    /// Whether converting `detected` this way changes anything.
+    pub fn changes(self, detected: Detected) -> bool {
+        !detected.is_utf8(self.wanted(detected))
+    }
+}
+
+impl std::fmt::Display for ByteOrderMark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ByteOrderMark::Keep => "Keep it where there is one",
+            ByteOrderMark::Add => "Add one",
+            ByteOrderMark::Remove => "Leave it out",
+        })
+    }
+}
+
+/// The encoding of `bytes`, or `None` when they are not text. `complete`
+/// says they are the whole file rather than its start, which may end
+/// halfway through a character.
+pub fn detect(bytes: &[u8], complete: bool) -> Option<Detected> {
+    let (encoding, bom) = match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, true),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, true),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, true),
+        _ => (unmarked(bytes, complete)?, false),
+    };
+    if bom && encoding == Encoding::Utf8 && !is_utf8(&bytes[UTF8_BOM.len()..], complete) {
+        return None;
+    }
+
+    Some(Detected { encoding, bom })
+}
+
+/// Looks at the start of the file at `path`.
+pub fn detect_file(path: &Path) -> io::Result<Option<Detected>> {
+    let (bytes, complete) = sample(path)?;
+
+    Ok(detect(&bytes, complete))
+}
+
+/// The start of the file at `path`, and whether that is all of it.
+pub fn sample(path: &Path) -> io::Result<(Vec<u8>, bool)> {
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(SAMPLE_BYTES)
+        .read_to_end(&mut bytes)?;
+    let complete = (bytes.len() as u64) < SAMPLE_BYTES;
+
+    Ok((bytes, complete))
+}
+
+fn unmarked(bytes: &[u8], complete: bool) -> Option<Encoding> {
+    if let Some(encoding) = utf16(bytes) {
+        return Some(encoding);
+    }
+    let control = |&byte: &u8| byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b);
+    if bytes.is_empty() || bytes.iter().any(control) {
+        return None;
+    }
+
+    Some(if bytes.is_ascii() {
+        Encoding::Ascii
+    } else if is_utf8(bytes, complete) {
+        Encoding::Utf8
+    } else {
+        Encoding::Windows1252
+    })
+}
+
+fn is_utf8(bytes: &[u8], complete: bool) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        // Only cut short at the end of the sample.
+        Err(error) => !complete && error.error_len().is_none(),
+    }
+}
+
+/// UTF-16 without a mark, told by a third of its high bytes being zero on
+/// one side and nearly none on the other.
+fn utf16(bytes: &[u8]) -> Option<Encoding> {
+    let pairs = bytes.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros = |offset: usize| {
+        bytes
+            .chunks_exact(2)
+            .filter(|pair| pair[offset] == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    match (even * 3 >= pairs, odd * 3 >= pairs) {
+        (false, true) if even * 20 < pairs => Some(Encoding::Utf16Le),
+        (true, false) if odd * 20 < pairs => Some(Encoding::Utf16Be),
+        _ => None,
+    }
+}
+
+/// The text of `bytes`, without its byte order mark. A character cut off at
+/// the end is left out.
+pub fn decode(bytes: &[u8], detected: Detected) -> String {
+    let mark = match (detected.bom, detected.encoding) {
+        (false, _) => 0,
+        (true, Encoding::Utf16Le | Encoding::Utf16Be) => 2,
+        (true, _) => UTF8_BOM.len(),
+    };
+    let bytes = &bytes[mark.min(bytes.len())..];
+
+    match detected.encoding {
+        Encoding::Ascii | Encoding::Utf8 => match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(error) if error.error_len().is_none() => {
+                String::from_utf8_lossy(&bytes[..error.valid_up_to()]).into_owned()
+            }
+            Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+        },
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = bytes.chunks_exact(2).map(|pair| match detected.encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            char::decode_utf16(units)
+                .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        Encoding::Windows1252 => bytes.iter().map(|&byte| windows_1252(byte)).collect(),
+    }
+}
+
+/// Bytes the codepage leaves undefined stand for the same code point, as
+/// browsers read them.
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [u16; 32] = [
+        0x20AC, 0x81, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160,
+        0x2039, 0x0152, 0x8D, 0x017D, 0x8F, 0x90, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013,
+        0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x9D, 0x017E, 0x0178,
+    ];
+    match byte {
+        0x80..=0x9F => char::from_u32(HIGH[usize::from(byte - 0x80)].into()).unwrap_or('\u{FFFD}'),
+        _ => char::from(byte),
+    }
+}
+
+/// Rewrites the file at `path` as UTF-8, returning whether anything
+/// changed.
+pub fn to_utf8(path: &Path, bom: ByteOrderMark) -> io::Result<bool> {
+    let bytes = fs::read(path)?;
+    let detected = detect(&bytes, true).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not text", path.display()),
+        )
+    })?;
+    if !bom.changes(detected) {
+        return Ok(false);
+    }
+
+    let text = decode(&bytes, detected);
+    let mut contents = Vec::with_capacity(UTF8_BOM.len() + text.len());
+    if bom.wanted(detected) {
+        contents.extend_from_slice(UTF8_BOM);
+    }
+    contents.extend_from_slice(text.as_bytes());
+    safe_save::write(path, &contents)?;
+    tracing::debug!(path = %path.display(), from = %detected, "converted to UTF-8");
+
+    Ok(true)
+}
@@ -0,0 +1,132 @@
+//! What host applications and their plugins add to the picker beyond
+//! emblems: actions in the Tools menu and the command palette, run on the
+//! selected paths, such as uploading them somewhere or scanning them for
+//! viruses, and columns in the listing after the size, such as a sync state.
+//! Set them with `FilePicker::set_extensions`.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type Applies = Arc<dyn Fn(&[PathBuf]) -> bool + Send + Sync>;
+type Run = Arc<dyn Fn(&[PathBuf]) -> Result<String, String> + Send + Sync>;
+type Value = Arc<dyn Fn(&Path) -> Option<String> + Send + Sync>;
+
+/// Something to do with the selection.
+#[derive(Clone)]
+pub struct Action {
+    label: String,
+    applies: Applies,
+    run: Run,
+}
+
+impl Action {
+    /// `run` is given the selected paths off the UI thread, so it may take
+    /// its time. What it answers is shown once it is done: `Ok` with a
+    /// line for the user, which may be empty, or `Err` with why it failed.
+    pub fn new(
+        label: impl Into<String>,
+        run: impl Fn(&[PathBuf]) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Action {
+        Action {
+            label: label.into(),
+            applies: Arc::new(|_| true),
+            run: Arc::new(run),
+        }
+    }
+
+    /// Offers the action only for selections `applies` accepts, such as
+    /// files of one kind. It is asked whenever the menus are drawn.
+    pub fn applies(mut self, applies: impl Fn(&[PathBuf]) -> bool + Send + Sync + 'static) -> Self {
+        self.applies = Arc::new(applies);
+        self
+    }
+
+    pub(crate) fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub(crate) fn accepts(&self, selection: &[PathBuf]) -> bool {
+        !selection.is_empty() && (self.applies)(selection)
+    }
+
+    pub(crate) fn run(&self, selection: &[PathBuf]) -> Result<String, String> {
+        (self.run)(selection)
+    }
+}
+
+/// A column in the listing.
+#[derive(Clone)]
+pub struct Column {
+    title: String,
+    value: Value,
+}
+
+impl Column {
+    /// What `value` gives each listed path is shown under `title`, nothing
+    /// for `None`. Like the emblems, it is asked every time the listing is
+    /// drawn, so it should answer from what it already knows.
+    pub fn new(
+        title: impl Into<String>,
+        value: impl Fn(&Path) -> Option<String> + Send + Sync + 'static,
+    ) -> Column {
+        Column {
+            title: title.into(),
+            value: Arc::new(value),
+        }
+    }
+
+    pub(crate) fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub(crate) fn value(&self, path: &Path) -> String {
+        (self.value)(path).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Extensions {
+    actions: Vec<Action>,
+    columns: Vec<Column>,
+}
+
+impl Extensions {
+    pub fn new() -> Extensions {
+        Extensions::default()
+    }
+
+    /// Adds `action` after those added before.
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Adds `column` after those added before.
+    pub fn column(mut self, column: Column) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    pub(crate) fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    pub(crate) fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field(
+                "actions",
+                &self.actions.iter().map(Action::label).collect::<Vec<_>>(),
+            )
+            .field(
+                "columns",
+                &self.columns.iter().map(Column::title).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,122 @@
+//! The icons of files by their kind, for hosts whose `Icons` give glyphs
+//! for kinds: worked out in the background for the rows in view, from the
+//! MIME type, sniffed when the extension does not tell, and the icon a
+//! desktop entry names. Rows show the generic file icon until theirs is
+//! known, so the first paint of a folder never waits for them.
+
+use std::any::TypeId;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+
+use crate::mime;
+use crate::style::Icons;
+
+/// How many rows from the top of the view get their icons worked out.
+pub const AHEAD: usize = 100;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// What a file is, most telling first.
+    Resolved(PathBuf, Vec<String>),
+}
+
+#[derive(Default)]
+pub struct FileIcons {
+    kinds: HashMap<PathBuf, Vec<String>>,
+    /// Counts forgetting, to start working out again.
+    generation: usize,
+}
+
+impl FileIcons {
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Resolved(path, kinds) => {
+                self.kinds.insert(path, kinds);
+            }
+        }
+    }
+
+    pub fn forget(&mut self, path: &Path) {
+        if self.kinds.remove(path).is_some() {
+            self.generation += 1;
+        }
+    }
+
+    /// The glyph `icons` give the kind of `path`, once it is known.
+    pub fn glyph<'a>(&self, path: &Path, icons: &'a Icons) -> Option<&'a str> {
+        self.kinds
+            .get(path)?
+            .iter()
+            .find_map(|kind| icons.for_kind(kind))
+    }
+
+    /// Works out the kinds of those of `files` not known yet, one after the
+    /// other; none when `icons` have no glyphs for kinds.
+    pub fn subscription(&self, icons: &Icons, files: Vec<PathBuf>) -> Subscription<Message> {
+        struct Resolving;
+
+        if icons.kinds.is_empty() {
+            return Subscription::none();
+        }
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|path| !self.kinds.contains_key(path))
+            .collect();
+        if files.is_empty() {
+            return Subscription::none();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        files.hash(&mut hasher);
+        subscription::channel(
+            (TypeId::of::<Resolving>(), self.generation, hasher.finish()),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    for path in files {
+                        let kinds = kinds(&path);
+                        if sender
+                            .unbounded_send(Message::Resolved(path, kinds))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+}
+
+/// The icon a desktop entry names, then the MIME type.
+fn kinds(path: &Path) -> Vec<String> {
+    let mut kinds = Vec::new();
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "desktop")
+    {
+        let icon = fs::read_to_string(path).ok().and_then(|entry| {
+            entry
+                .lines()
+                .find_map(|line| line.strip_prefix("Icon="))
+                .map(|icon| icon.trim().to_string())
+        });
+        kinds.extend(icon);
+    }
+    kinds.push(mime::guess(path).to_string());
+    kinds
+}
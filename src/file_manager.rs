@@ -0,0 +1,120 @@
+//! Serves `org.freedesktop.FileManager1`, which browsers and other apps call
+//! for "Show in folder". Unless another file manager already owns the name,
+//! a running browser takes it; to be started on demand instead, install a
+//! D-Bus service file for `org.freedesktop.FileManager1` running
+//! `filepicker --hidden`.
+
+use std::path::PathBuf;
+
+use iced::Subscription;
+
+/// What an app asked to be shown.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Open these folders.
+    ShowFolders(Vec<PathBuf>),
+    /// Open the folders holding these, with them selected.
+    ShowItems(Vec<PathBuf>),
+    ShowItemProperties(Vec<PathBuf>),
+}
+
+#[cfg(target_os = "linux")]
+pub fn requests() -> Subscription<Event> {
+    use std::any::TypeId;
+    use std::thread;
+
+    use iced::futures::channel::mpsc;
+    use iced::futures::{SinkExt, StreamExt};
+    use iced::subscription;
+
+    struct FileManager;
+
+    subscription::channel(TypeId::of::<FileManager>(), 10, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        thread::spawn(move || {
+            if let Err(error) = service::serve(sender) {
+                tracing::info!(%error, "not serving org.freedesktop.FileManager1");
+            }
+        });
+
+        while let Some(event) = receiver.next().await {
+            let _ = output.send(event).await;
+        }
+
+        std::future::pending().await
+    })
+}
+
+/// FileManager1 is a Linux desktop interface.
+#[cfg(not(target_os = "linux"))]
+pub fn requests() -> Subscription<Event> {
+    Subscription::none()
+}
+
+#[cfg(target_os = "linux")]
+mod service {
+    use std::io;
+    use std::path::PathBuf;
+
+    use iced::futures::channel::mpsc;
+
+    use super::Event;
+    use crate::dbus::{Connection, Value};
+    use crate::paths;
+
+    const NAME: &str = "org.freedesktop.FileManager1";
+
+    pub fn serve(events: mpsc::UnboundedSender<Event>) -> io::Result<()> {
+        let mut connection = Connection::session()?;
+        if !connection.request_name(NAME)? {
+            return Err(io::Error::other(format!("{} is already taken", NAME)));
+        }
+        let sender = connection.sender();
+
+        loop {
+            let call = connection.receive()?;
+            if !call.is_call() {
+                continue;
+            }
+
+            // Each method takes `(as uris, s startup_id)`.
+            let uris = || -> Vec<PathBuf> {
+                call.body
+                    .first()
+                    .and_then(Value::items)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter_map(path)
+                    .collect()
+            };
+            let event = match (call.interface.as_deref(), call.member.as_deref()) {
+                (Some(NAME), Some("ShowFolders")) => Event::ShowFolders(uris()),
+                (Some(NAME), Some("ShowItems")) => Event::ShowItems(uris()),
+                (Some(NAME), Some("ShowItemProperties")) => Event::ShowItemProperties(uris()),
+                _ => {
+                    sender.reply_error(
+                        &call,
+                        "org.freedesktop.DBus.Error.UnknownMethod",
+                        "no such method on org.freedesktop.FileManager1",
+                    )?;
+                    continue;
+                }
+            };
+
+            sender.reply(&call, Vec::new())?;
+            if events.unbounded_send(event).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Callers pass `file://` URIs, though some send bare paths.
+    fn path(uri: &str) -> Option<PathBuf> {
+        if uri.starts_with('/') {
+            Some(PathBuf::from(uri))
+        } else {
+            paths::from_uri(uri)
+        }
+    }
+}
@@ -1,34 +1,182 @@
-use std::path::Path;
-use std::{env, fs};
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use std::{env, fs, io, thread};
 
-use iced::widget::{button, column, row, scrollable, text, text_input, Container};
-use iced::{executor, Length};
-use iced::{Application, Command, Element, Theme};
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::{
+    button, checkbox, column, image, mouse_area, pick_list, row, scrollable, text, text_input,
+    tooltip, Container,
+};
+use iced::{clipboard, theme, Command, Element, Point, Size, Subscription, Theme};
+use iced::{event, keyboard, mouse, subscription, window, Alignment, Color, Event, Length};
+
+use crate::access;
+use crate::accounts::{self, Accounts};
+use crate::appimage;
+use crate::archive;
+use crate::autostart;
+use crate::band::Band;
+use crate::bindings::{self, Bindings};
+use crate::breadcrumb;
+use crate::checksum::{self, Checksums};
+use crate::collation;
+use crate::columns::{self, Columns};
+use crate::compare::{self, Compare};
+use crate::completion::Completions;
+use crate::config::{Config, FolderSize, GroupBy, SortBy, Symlinks, View, ViewMode};
+use crate::conversion::{self, Convert};
+use crate::dates::{self, format_time};
+use crate::devices;
+use crate::dialog::{self, Dialog, DialogMode, Outcome, Selection};
+use crate::dirs;
+use crate::drag::Drag;
+use crate::drag_out;
+use crate::duplicates::{self, Duplicates};
+use crate::editor::{self, Editor};
+use crate::emblem::{self, Emblem, Emblems};
+use crate::extensions::Extensions;
+use crate::file_icons::{self, FileIcons};
+use crate::folder_sizes::{self, FolderSizes};
+use crate::folder_tree::{self, FolderTree};
+use crate::git::{self, Badge, Git};
+use crate::groups::{Grouping, Heading};
+use crate::highlight;
+use crate::history::{Closed, History, Step};
+use crate::hot_folders::{self, HotFolders};
+use crate::i18n::{tr, trf};
+use crate::images::{self, Conversion};
+use crate::indexer;
+use crate::jobs::{self, format_bytes, AfterAction, ConflictAction, JobKind, Jobs};
+use crate::journal::{self, Journal, Operation};
+use crate::keymap::{self, Keymap};
+use crate::launch::{self, Launch};
+use crate::login::{self, Login};
+use crate::media;
+use crate::menu::{self, Menu, MenuBar, Shortcut};
+use crate::modal::Modal;
+use crate::monitors;
+use crate::mounts;
+use crate::newlines::{self, Newlines};
+use crate::normalize;
+use crate::oci;
+use crate::package;
+use crate::palette::{self, Palette};
+use crate::paths;
+use crate::pictures::{self, Thumbnails};
+use crate::prefetch::{self, Prefetch};
+use crate::prompt::{self, Prompt, Prompts, Role, Tone};
+use crate::properties::{self, Properties};
+use crate::recent::Recent;
+use crate::recode::{self, Recode};
+use crate::remote::{self, Transfers};
+use crate::rendering;
+use crate::restrictions::Restrictions;
+use crate::sandbox;
+use crate::search::{self, Search};
+use crate::selection_size::{self, SelectionSize};
+use crate::settings::{self, Setting, Settings};
+use crate::shell::{self, Shell};
+use crate::sidebar::{self, Sidebar};
+use crate::sidecars::{self, Sidecars, Verdict};
+use crate::streams;
+use crate::style::{self, Style};
+use crate::summary;
+use crate::system;
+use crate::tags::{self, Label, Tags};
+use crate::task;
+use crate::toast::{self, Toasts};
+use crate::torrent;
+use crate::trash;
+use crate::type_ahead::{self, TypeAhead};
+use crate::udisks;
+use crate::usage::{self, Usage};
+use crate::validate;
+use crate::vfs;
+use crate::watcher;
+use crate::webdav;
+
+/// Rows actually turned into widgets; the rest of a huge listing stays in
+/// memory for filtering but is not rendered.
+const MAX_RENDERED_ENTRIES: usize = 5_000;
+const LISTING_BATCH_SIZE: usize = 1_000;
+const LOW_MEMORY_RENDERED_ENTRIES: usize = 1_000;
+const LOW_MEMORY_BATCH_SIZE: usize = 100;
+/// Metadata reads are I/O bound, so this does not follow the core count.
+const LISTING_WORKERS: usize = 8;
+const LOW_MEMORY_LISTING_WORKERS: usize = 2;
+const COMPRESSION_LEVELS: [u32; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+const RECENT_PROJECTS: usize = 8;
+/// How many of the folders visited last the sidebar lists.
+const RECENT_SHOWN: usize = 5;
+/// How soon a second click must follow for the two to open an item.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// How much a listing holds on to, tighter in low-memory mode.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    rendered: usize,
+    batch: usize,
+    workers: usize,
+    /// Keep each entry's metadata for Properties instead of reading it again.
+    keep_metadata: bool,
+}
+
+impl Limits {
+    fn new(low_memory: bool) -> Limits {
+        if low_memory {
+            Limits {
+                rendered: LOW_MEMORY_RENDERED_ENTRIES,
+                batch: LOW_MEMORY_BATCH_SIZE,
+                workers: LOW_MEMORY_LISTING_WORKERS,
+                keep_metadata: false,
+            }
+        } else {
+            Limits {
+                rendered: MAX_RENDERED_ENTRIES,
+                batch: LISTING_BATCH_SIZE,
+                workers: LISTING_WORKERS,
+                keep_metadata: true,
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum Content {
     File(ContentData),
     Directory(ContentData),
-    Corrupt,
+    Corrupt(String),
 }
 
 impl std::fmt::Display for Content {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Content::File(file_data) | Content::Directory(file_data) => {
-                write!(f, "{}", file_data.name)
+                write!(f, "{}", file_data.name.to_string_lossy())
             }
-            Content::Corrupt => write!(f, "File or directory corrupted."),
+            Content::Corrupt(error) => f.write_str(&trf("Unreadable entry: {}", &[error])),
         }
     }
 }
 
 impl Content {
+    fn data(&self) -> Option<&ContentData> {
+        match self {
+            Content::File(data) | Content::Directory(data) => Some(data),
+            Content::Corrupt(_) => None,
+        }
+    }
+
     fn size(&self) -> u64 {
         match self {
             Content::File(files) => files.size,
             Content::Directory(dirs) => dirs.size,
-            Content::Corrupt => 0,
+            Content::Corrupt(_) => 0,
         }
     }
 }
@@ -36,179 +184,5866 @@ impl Content {
 #[derive(Debug, Clone)]
 pub struct ContentData {
     pub is_parent: bool,
-    path: String,
-    name: String,
+    path: PathBuf,
+    name: OsString,
     size: u64,
+    link_target: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    read_only: bool,
+    metadata: Option<Box<fs::Metadata>>,
 }
 
 impl Default for ContentData {
     fn default() -> Self {
         Self {
             is_parent: false,
-            path: "no path".to_string(),
-            name: "unknown".to_string(),
+            path: PathBuf::from("no path"),
+            name: OsString::from("unknown"),
             size: 0,
+            link_target: None,
+            modified: None,
+            read_only: false,
+            metadata: None,
         }
     }
 }
 
 impl ContentData {
-    fn new(path: String, parent: bool) -> ContentData {
+    fn new(path: PathBuf, parent: bool, keep_metadata: bool) -> io::Result<ContentData> {
         let name = if parent {
-            "..".to_string()
+            OsString::from("..")
         } else {
-            Path::new(&path)
-                .file_name()
-                .unwrap()
-                .to_str()
-                .expect("file name")
-                .to_string()
+            path.file_name().unwrap_or(path.as_os_str()).to_os_string()
         };
 
-        let size = match Path::new(&path).metadata() {
-            Ok(meta) => meta.len() / 1024,
-            Err(_) => 0,
+        let link_target = if parent {
+            None
+        } else {
+            fs::read_link(&path).ok()
         };
+        let meta = path.metadata().or_else(|_| path.symlink_metadata())?;
 
-        ContentData {
+        Ok(ContentData {
             is_parent: parent,
             path,
             name,
-            size,
-        }
+            size: meta.len(),
+            link_target,
+            modified: meta.modified().ok(),
+            read_only: !parent && meta.permissions().readonly(),
+            metadata: keep_metadata.then(|| Box::new(meta)),
+        })
     }
 }
 
+/// The file browser as an embeddable component: keep it in the
+/// application state, map its `Message`s back to `update`, and include its
+/// `view` and `subscription` in the application's own.
 pub struct FilePicker {
     path: String,
+    /// Whether the address bar is a text field rather than breadcrumbs.
+    editing_path: bool,
+    /// Why the typed path could not be opened.
+    path_error: Option<String>,
+    completions: Completions,
+    current_dir: PathBuf,
+    view: View,
+    /// The folder browsing stays within until left on purpose. Breadcrumbs
+    /// and searches start from it.
+    project: Option<PathBuf>,
     content: Vec<Content>,
+    /// The folder `content` was listed from.
+    listed_dir: PathBuf,
+    /// The entries of a folder listed again so far, gathered until the
+    /// listing is done and then merged into `content`.
+    relisted: Option<Vec<Content>>,
+    listing: usize,
+    loading: bool,
+    filter: String,
+    visible: Vec<usize>,
+    matching: usize,
+    /// The match last jumped to, as a position in `visible`.
+    current_match: Option<usize>,
+    /// Held on the keyboard, as text fields do not tell about Shift+Enter.
+    modifiers: keyboard::Modifiers,
+    type_ahead: TypeAhead,
+    keymap: Keymap,
+    /// The listing's scroll offset and height, to keep the selection in
+    /// sight when moving it by keys.
+    listing_scroll: (f32, f32),
+    /// The heading each of `content` is listed under, when grouped.
+    headings: Vec<Option<Heading>>,
+    listed_mtime: Option<SystemTime>,
+    selected: Vec<PathBuf>,
+    clipboard: Option<(JobKind, Vec<PathBuf>)>,
+    jobs: Jobs,
+    journal: Journal,
+    folder_tree: FolderTree,
+    columns: Columns,
+    folder_sizes: FolderSizes,
+    file_icons: FileIcons,
+    thumbnails: Thumbnails,
+    git: Git,
+    sidecars: Sidecars,
+    selection_size: SelectionSize,
+    prefetch: Prefetch<Content>,
+    /// Remote machines signed in to, by `[user@]host`.
+    connected: HashSet<String>,
+    transfers: Transfers,
+    /// What is being dragged, until the mouse button goes up.
+    dragging: Option<Vec<PathBuf>>,
+    /// What was selected before the rubber band, which it adds to when
+    /// drawn with Ctrl or Shift held.
+    band: Option<Vec<PathBuf>>,
+    /// Files dropped from other applications, gathered until the drop is
+    /// over since each comes as an event of its own.
+    dropped: Option<(JobKind, Vec<PathBuf>)>,
+    editor: Option<Editor>,
+    properties: Option<Properties>,
+    checksums: Option<Checksums>,
+    usage: Option<Usage>,
+    duplicates: Option<Duplicates>,
+    compare: Option<Compare>,
+    conversion: Option<Convert>,
+    recode: Option<Recode>,
+    newlines: Option<Newlines>,
+    shell: Option<Shell>,
+    launch: Option<Launch>,
+    image_summary: Option<oci::Summary>,
+    package: Option<package::Summary>,
+    torrent: Option<torrent::Summary>,
+    bindings: Option<Bindings>,
+    palette: Option<Palette>,
+    recent: Recent,
+    tags: Tags,
+    tag_editor: Option<tags::Editor>,
+    settings: Option<Settings>,
+    /// The item last clicked and when, to tell a double click.
+    last_click: Option<(PathBuf, Instant)>,
+    accounts: Option<Accounts>,
+    login: Option<Login>,
+    search: Search,
+    sidebar: Sidebar,
+    toasts: Toasts<Action>,
+    prompts: Prompts<Answer>,
+    /// What keeps the folder shown from being listed.
+    blocked: Option<access::Blocked>,
+    menu_bar: MenuBar,
+    config: Config,
+    /// The window shown in, among the host's.
+    window: window::Id,
+    focused: bool,
+    /// Whether the host opens the windows asked for with `new_window`.
+    new_windows: bool,
+    new_window: Option<PathBuf>,
+    /// Where the window's top left is on the desktop, once it has moved.
+    window_position: Option<Point>,
+    fullscreen: bool,
+    job_options: jobs::Options,
+    show_advanced: bool,
+    new_entry: Option<NewEntry>,
+    /// Set when running as an open or save dialog rather than a browser.
+    dialog: Option<Dialog>,
+    outcome: Option<Outcome>,
+    /// Whether a login item starts us in the background.
+    autostart: bool,
+    /// Whether hot folders and the index are looked after here.
+    background: bool,
+    hot_folders: HotFolders,
+    /// The folders of the windows closed lately, as the host has them.
+    recently_closed: Vec<PathBuf>,
+    reopen: Option<usize>,
+    history: History,
+    /// How far down to scroll the listing once read, for a window opened
+    /// again.
+    restored_scroll: Option<f32>,
+    style: Style,
+    restrictions: Restrictions,
+    emblems: Emblems,
+    extensions: Extensions,
+    /// The archive being browsed, when the current folder is inside one.
+    archive: Option<PathBuf>,
+    /// For the next "Compress…".
+    compression: archive::Compression,
+    /// Archives waiting for the user to open the folder to extract them to.
+    extract_to: Option<Vec<PathBuf>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Folder,
+    Rename(PathBuf),
+    /// An archive of the selection.
+    Archive,
+}
+
+impl EntryKind {
+    fn renaming(&self) -> Option<&Path> {
+        match self {
+            EntryKind::Rename(original) => Some(original),
+            EntryKind::File | EntryKind::Folder | EntryKind::Archive => None,
+        }
+    }
+}
+
+/// The parts of the window that can be hidden from the View menu.
+#[derive(Debug, Clone, Copy)]
+enum Panel {
+    Toolbar,
+    Sidebar,
+    StatusBar,
+}
+
+/// What a toast's button does.
+#[derive(Debug, Clone)]
+pub enum Action {
+    Open(PathBuf),
+    Reveal(Vec<PathBuf>),
+    Undo,
+}
+
+/// A recent project, by its folder's name in the switcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project(PathBuf);
+
+impl std::fmt::Display for Project {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&display_name(&self.0))
+    }
+}
+
+/// What the user answered a prompt with.
+#[derive(Debug, Clone)]
+pub enum Answer {
+    Delete(Vec<PathBuf>),
+    Trash(Vec<PathBuf>),
+    /// Move system folders all the same.
+    Move(Vec<PathBuf>, PathBuf),
+    /// Save over the existing files.
+    Replace(Vec<PathBuf>),
+    /// Create the folder typed into the save dialog, then go there.
+    CreateFolder(PathBuf, String),
+    /// Close the project to go outside it.
+    LeaveProject(PathBuf),
+    /// Go to the folder pasted or dropped.
+    Go(PathBuf),
+    /// Select the file pasted or dropped, in its folder.
+    Show(PathBuf),
+    Transfer(JobKind, Vec<PathBuf>),
+    /// Settle the conflict of the job, for the rest of its own too when
+    /// `true`.
+    Resolve(usize, ConflictAction, bool),
+    CancelJob(usize),
+    /// Run the command installing a package, in the folder given.
+    Install(PathBuf, String),
+    Dismiss,
+}
+
+struct NewEntry {
+    kind: EntryKind,
+    name: String,
+    error: Option<String>,
+    /// Probed once when the entry is started rather than on every keystroke.
+    case_sensitive: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     PathInput(String),
     PathChange,
+    EditPath,
+    Navigate(PathBuf),
+    CompletionMove(isize),
+    CompletionAccept(Option<usize>),
+    /// Tab, or Shift+Tab when `true`, outside the text fields' own use.
+    Tab(bool),
+    Escape,
     ContentClicked(Content),
+    /// Opens a folder, or picks or previews a file.
+    ContentActivated(Content),
+    ShowPackageContents,
+    Opened(PathBuf, Result<(), String>),
+    ContentSelected(PathBuf, bool),
+    Listed(usize, Result<Vec<Content>, String>),
+    ListingDone(usize),
+    /// The Finder tags of what was listed.
+    FinderTags(Vec<(PathBuf, Vec<String>)>),
+    FilterInput(String),
+    FilterSubmit,
+    Find,
+    NextMatch(isize),
+    ModifiersChanged(keyboard::Modifiers),
+    TypeAhead(type_ahead::Message),
+    /// A character typed over the listing, which may be bound to an action
+    /// before it selects by name.
+    Typed(char),
+    MoveSelection(isize),
+    SelectAll,
+    InvertSelection,
+    /// The rubber band spans the rows from the one height down to the
+    /// other, in the listing's coordinates.
+    Band(f32, f32),
+    BandReleased,
+    /// By as many rows as the listing shows at once.
+    MovePage(isize),
+    SelectFirst,
+    SelectLast,
+    OpenSelected,
+    ListingScrolled(scrollable::Viewport),
+    Copy,
+    Cut,
+    Paste,
+    PasteSymlinks,
+    Trash,
+    Delete,
+    Undo,
+    Redo,
+    /// An entry started being dragged.
+    DragStart(PathBuf),
+    /// What is being dragged was let go on this folder.
+    DropOn(PathBuf),
+    DragEnd,
+    /// The mouse left the window, taking what is dragged to other
+    /// applications.
+    DragOut,
+    DraggedOut(Result<(), String>),
+    /// Dropped on the window by another application.
+    FileDropped(window::Id, PathBuf),
+    DropFinished,
+    Edit,
+    Editor(editor::Message),
+    ShowProperties,
+    Properties(properties::Message),
+    ShowChecksums,
+    AnalyzeUsage,
+    FindDuplicates,
+    CompareFolders,
+    Compare(compare::Message),
+    ConvertImages,
+    Conversion(conversion::Message),
+    ConvertText,
+    Recode(recode::Message),
+    ConvertLineEndings,
+    Newlines(newlines::Message),
+    OpenTerminal,
+    RunCommand,
+    Shell(shell::Message),
+    Launch(launch::Message),
+    RunAppImage,
+    IntegrateAppImage,
+    ExtractAppImage,
+    /// The AppImage was added to the menu, with its desktop entry, or not.
+    Integrated(PathBuf, Result<PathBuf, String>),
+    /// The AppImage was extracted, into the folder given, or not.
+    AppImageExtracted(PathBuf, Result<PathBuf, String>),
+    ShowImageSummary,
+    ImageSummary(oci::Message),
+    ShowPackage,
+    Package(package::Message),
+    ShowTorrent,
+    Torrent(torrent::Message),
+    TerminalCommand(String),
+    /// Makes the current folder the project.
+    ProjectHere,
+    OpenProject(Project),
+    CloseProject,
+    Duplicates(duplicates::Message),
+    Usage(usage::Message),
+    Checksums(checksum::Message),
+    NewEntry(EntryKind),
+    NewEntryInput(String),
+    CreateEntry,
+    CancelNewEntry,
+    CompressFormat(archive::Format),
+    CompressLevel(u32),
+    ToggleAdvanced,
+    OpenAccounts,
+    Accounts(accounts::Message),
+    /// A WebDAV entry was renamed from the path, or could not be.
+    CloudRenamed(PathBuf, Result<(), String>),
+    CloudCreated(PathBuf, Result<(), String>),
+    PreserveTimestamps(bool),
+    DefaultPreserveTimestamps(bool),
+    ResolveSymlinks(bool),
+    UseSystemIndex(bool),
+    UseNtfsIndex(bool),
+    ShowAppleDouble(bool),
+    ShowFolderTree(bool),
+    GroupBy(GroupBy),
+    ShowToolbar(bool),
+    ShowSidebar(bool),
+    ShowStatusBar(bool),
+    ToggleDistractionFree,
+    ToggleFullscreen,
+    /// How the current folder is shown, from now on there alone.
+    ViewMode(ViewMode),
+    SortBy(SortBy),
+    /// Shows the current folder as the others again.
+    DefaultView,
+    FolderSize(FolderSize),
+    FolderSizes(folder_sizes::Message),
+    FileIcons(file_icons::Message),
+    Thumbnails(pictures::Message),
+    Git(git::Message),
+    Sidecars(sidecars::Message),
+    SelectionSize(selection_size::Message),
+    Prefetch(prefetch::Message<Content>),
+    Login(login::Message),
+    Transfers(remote::Message),
+    BookmarkRemote,
+    UnbookmarkRemote,
+    Columns(columns::Message),
+    FolderTree(folder_tree::Message),
+    LowMemory(bool),
+    Autostart(bool),
+    StripAppleDouble,
+    AppleDoubleFound(Vec<PathBuf>),
+    Search(search::Message),
+    Sidebar(sidebar::Message),
+    Dialog(dialog::Message),
+    Jobs(jobs::Message),
+    Toast(toast::Message<Action>),
+    CopyPath,
+    /// The selection as a Markdown table, with the files' checksums if
+    /// `true`.
+    CopySummary(bool),
+    SummaryReady(String),
+    /// The host's action at that index, on the selection.
+    RunAction(usize),
+    ActionDone(String, Result<String, String>),
+    Prompt(prompt::Message<Answer>),
+    SystemActionDone(AfterAction, Result<(), String>),
+    /// Where a drive was mounted, or its label and why it was not.
+    Mounted(Result<PathBuf, (String, String)>),
+    /// Drives were plugged in, taken out, mounted or unmounted.
+    DevicesChanged,
+    Watcher(watcher::Event),
+    WindowFocused(window::Id),
+    WindowUnfocused(window::Id),
+    Resized(window::Id, u32, u32),
+    Moved(window::Id, i32, i32),
+    CloseRequested(window::Id),
+    Closing(Option<String>),
+    /// Asks the host for another window on this folder.
+    NewWindow,
+    /// Asks the host for another window on the selected folder.
+    OpenInNewWindow,
+    PastePrimary,
+    PrimaryPasted(Option<String>),
+    Pasted(Option<String>),
+    HotFolders(hot_folders::Message),
+    /// How many items the background index holds now.
+    Indexed(usize),
+    Extract,
+    ExtractTo,
+    ExtractInto,
+    CancelExtractTo,
+    PickFromHost,
+    PickedFromHost(Result<Vec<PathBuf>, String>),
+    /// Asks the host to open again the window closed at this index of
+    /// those it listed with `set_recently_closed`, 0 being the last.
+    ReopenClosed(usize),
+    Back,
+    Forward,
+    /// Why the folder of a listing could not be read, when permissions
+    /// are to blame, and the error it failed with.
+    Diagnosed(usize, String, Option<access::Blocked>),
+    OpenAsAdministrator,
     Sort,
+    Menu(menu::Message<Message>),
+    Shortcut(Shortcut),
+    ShowShortcuts,
+    Bindings(bindings::Message),
+    OpenPalette,
+    /// The palette, for the folders visited.
+    OpenJump,
+    Palette(palette::Message),
+    /// Gives the selection the tag, or takes it off.
+    Tag(String, bool),
+    EditTags,
+    Tags(tags::Message),
+    OpenSettings,
+    /// Switches between the light and the dark theme.
+    ToggleTheme,
+    Settings(settings::Message),
+    About,
 }
 
-impl Application for FilePicker {
-    type Executor = executor::Default;
-    type Flags = ();
-    type Message = Message;
-    type Theme = Theme;
-
-    fn new(_flags: ()) -> (FilePicker, Command<Self::Message>) {
-        let cwd = env::current_dir().expect("current working directory");
-        let path = cwd.clone().to_str().unwrap().to_owned();
-        (
-            FilePicker {
-                path: path.clone(),
-                content: get_dir_content(path),
-            },
-            Command::none(),
-        )
+impl FilePicker {
+    /// Opens in the current directory; the command lists the volumes for
+    /// the sidebar.
+    pub fn new() -> (FilePicker, Command<Message>) {
+        FilePicker::with_dialog(None)
     }
 
-    fn title(&self) -> String {
-        String::from("FilePicker - Iced")
+    /// Opens as a dialog; poll `take_outcome` after each `update` to learn
+    /// when and how it was closed.
+    pub fn dialog(dialog: Dialog) -> (FilePicker, Command<Message>) {
+        FilePicker::with_dialog(Some(dialog))
     }
 
-    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
-        match message {
-            Message::PathInput(path) => self.path = path,
-            Message::PathChange => self.content = get_dir_content(self.path.clone()),
-            Message::ContentClicked(content) => match content {
-                Content::Directory(dir) => {
-                    self.path = dir.path.clone();
-                    self.content = get_dir_content(dir.path);
-                }
-                Content::File(file) => {
-                    self.path = file.path;
+    /// Shows `path`: a folder is browsed, anything else is highlighted in
+    /// the folder holding it.
+    pub fn open(&mut self, path: &Path) {
+        match path.parent() {
+            Some(_) if !path.is_dir() => {
+                self.reveal(&[path.to_path_buf()]);
+                if let Some(dialog) = self.dialog.as_mut() {
+                    if dialog.mode() == DialogMode::SaveFile {
+                        if let Some(name) = path.file_name() {
+                            dialog.set_file_name(name.to_string_lossy().into_owned());
+                        }
+                    }
                 }
-                Content::Corrupt => {}
-            },
-            Message::Sort => {}
+            }
+            _ => self.navigate(path.to_path_buf()),
+        }
+    }
+
+    /// Opens the folder holding the first of `items`, with those of them
+    /// that are in it selected.
+    pub fn reveal(&mut self, items: &[PathBuf]) {
+        let Some(dir) = items.first().and_then(|item| item.parent()) else {
+            return;
         };
+        let dir = dir.to_path_buf();
+        self.navigate(dir.clone());
+        if self.current_dir != dir {
+            return;
+        }
+        self.selected = items
+            .iter()
+            .filter(|item| item.parent() == Some(dir.as_path()))
+            .cloned()
+            .collect();
+    }
 
-        Command::none()
+    /// Draws what `emblems` gives on the icons in the listing, after the
+    /// picker's own.
+    pub fn set_emblems(&mut self, emblems: Emblems) {
+        self.emblems = emblems;
     }
 
-    fn view(&self) -> Element<Self::Message> {
-        let mut content = column!();
-        let adress_bar = text_input("Path: ", &self.path)
-            .on_input(Message::PathInput)
-            .on_submit(Message::PathChange)
-            .padding(10);
+    /// Offers the actions `extensions` has in the Tools menu and shows its
+    /// columns in the listing.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.extensions = extensions;
+    }
 
-        content = content.push(adress_bar);
+    /// Keeps users away from what `restrictions` rules out, moving to its
+    /// start folder if the one shown is no longer allowed.
+    pub fn set_restrictions(&mut self, restrictions: Restrictions) {
+        self.restrictions = restrictions;
+        if paths::is_computer(&self.current_dir) || self.restrictions.may_enter(&self.current_dir) {
+            self.refresh();
+            return;
+        }
 
-        let row = row!(
-            text("Name").width(Length::FillPortion(2)),
-            text("Size").width(Length::FillPortion(1))
-        )
-        .height(48.);
-        let header = button(row).on_press(Message::Sort);
-        content = content.push(header);
+        match self.restrictions.start_dir().map(Path::to_path_buf) {
+            Some(start) if self.restrictions.may_enter(&start) => self.navigate(start),
+            _ => {
+                self.listing += 1;
+                self.loading = false;
+                self.content.clear();
+                self.visible.clear();
+                self.matching = 0;
+                self.selected.clear();
+            }
+        }
+    }
 
-        content = content.push(self.list_dir());
+    /// Replaces the look set by `Style::default`.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
 
-        Container::new(content).padding(20).into()
+    /// Records where the browser is for the next launch and writes the
+    /// configuration out. Done on close; call it yourself when hiding the
+    /// window instead.
+    pub fn save_session(&mut self) {
+        if !paths::is_computer(&self.current_dir) {
+            self.config.last_dir = Some(self.current_dir.clone());
+        }
+        self.config.project = self.project.clone();
+        if let Some(position) = self.window_position {
+            let size = self
+                .config
+                .window_size
+                .map_or(Size::ZERO, |(width, height)| Size::new(width, height));
+            let monitors = monitors::monitors();
+            let (monitor, position) = match monitors::locate(&monitors, position, size) {
+                Some((monitor, offset)) => (Some(monitor.name.clone()), offset),
+                None => (None, position),
+            };
+            self.config.window_position = Some((position.x, position.y));
+            self.config.window_monitor = monitor;
+        }
+        self.save_config();
+        if let Err(error) = self.recent.save() {
+            tracing::warn!(%error, "cannot save the recent folders");
+        }
     }
-}
 
-impl FilePicker {
-    fn list_dir(&self) -> Element<Message> {
-        let mut col = column!();
+    /// Keeps browsing within `root`, as a project browser does; `None`
+    /// lifts that again.
+    pub fn set_project(&mut self, root: Option<PathBuf>) {
+        match root {
+            Some(root) => self.open_project(root),
+            None => self.project = None,
+        }
+    }
+
+    fn shows(&self, panel: Panel) -> bool {
+        let hidden = match panel {
+            Panel::Toolbar => self.config.hide_toolbar,
+            Panel::Sidebar => self.config.hide_sidebar,
+            Panel::StatusBar => self.config.hide_status_bar,
+        };
+        !hidden && !self.config.distraction_free
+    }
 
-        for file in &self.content {
-            let size = text(format!("{} Kb", file.size())).width(Length::FillPortion(1));
-            let filename = text(file.to_string()).width(Length::FillPortion(2));
-            let row = row!(filename, size);
-            let item = button(row)
-                .on_press(Message::ContentClicked(file.clone()))
-                .height(48.);
-            col = col.push(item);
+    /// Turning a bar on from distraction-free shows it alone.
+    fn leave_distraction_free(&mut self) {
+        if std::mem::take(&mut self.config.distraction_free) {
+            self.config.hide_toolbar = true;
+            self.config.hide_sidebar = true;
+            self.config.hide_status_bar = true;
         }
+    }
 
-        scrollable(col)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
+    /// What the dialog ended with, handed out once.
+    pub fn take_outcome(&mut self) -> Option<Outcome> {
+        self.outcome.take()
     }
-}
 
-fn get_dir_content(cwd: String) -> Vec<Content> {
-    let mut files = Vec::new();
-    let cwd = Path::new(&cwd);
-    let parent_dir = match cwd.parent() {
-        Some(parent) => parent.to_str().unwrap().to_string(),
-        None => cwd.to_str().unwrap().to_string(),
-    };
+    /// For hosts with several windows: the picker only heeds the events of
+    /// `id`, and the keys while it has the focus.
+    pub fn set_window(&mut self, id: window::Id) {
+        self.window = id;
+    }
+
+    /// Offers "New window" and "Open in new window"; poll `take_new_window`
+    /// after each `update` for the folder to open one on.
+    pub fn offer_new_windows(&mut self) {
+        self.new_windows = true;
+    }
+
+    /// Runs the hot-folder rules and keeps the search index current, as
+    /// the instance started in the background does.
+    pub fn run_in_background(&mut self) {
+        self.background = true;
+        self.hot_folders = HotFolders::new(self.config.hot_folders.clone());
+    }
+
+    pub fn take_new_window(&mut self) -> Option<PathBuf> {
+        self.new_window.take()
+    }
+
+    /// Offers "Recently closed" with the folders of `dirs`, the last closed
+    /// first; poll `take_reopen` after each `update` for the one to open.
+    pub fn set_recently_closed(&mut self, dirs: Vec<PathBuf>) {
+        self.recently_closed = dirs;
+    }
+
+    pub fn take_reopen(&mut self) -> Option<usize> {
+        self.reopen.take()
+    }
+
+    /// What to keep of the window as it closes, for `restore`.
+    pub fn closed(&self) -> Closed {
+        Closed {
+            dir: self.current_dir.clone(),
+            history: self.history.clone(),
+            scroll: self.listing_scroll.0,
+        }
+    }
 
-    files.push(Content::Directory(ContentData::new(parent_dir, true)));
+    /// Takes up the history and the scrolling of a window closed on
+    /// `closed.dir`, which this one has been opened on.
+    pub fn restore(&mut self, closed: Closed) {
+        self.history = closed.history;
+        self.restored_scroll = Some(closed.scroll);
+    }
+
+    fn with_dialog(dialog: Option<Dialog>) -> (FilePicker, Command<Message>) {
+        let mut toasts = Toasts::default();
+        let config = Config::load();
+        webdav::configure(&config.webdav);
+        jobs::set_size_units(config.size_units);
+        collation::set_order(config.name_order);
+        dates::set(
+            config.time_format,
+            &config.time_pattern,
+            config.relative_times,
+        );
+        // A dialog's own folder first, then where the last session ended.
+        let restored = [
+            dialog.as_ref().and_then(Dialog::start_dir),
+            config.last_dir.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.is_dir())
+        .map(Path::to_path_buf);
+        let cwd = restored.unwrap_or_else(|| {
+            env::current_dir().unwrap_or_else(|error| {
+                toasts.error(tr("Cannot determine the current directory"), error);
+                env::var_os("HOME")
+                    .or_else(|| env::var_os("USERPROFILE"))
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| PathBuf::from("/"))
+            })
+        });
+        let mut picker = FilePicker {
+            path: paths::display(&cwd),
+            editing_path: false,
+            path_error: None,
+            completions: Completions::default(),
+            current_dir: cwd.clone(),
+            view: config.view(&cwd),
+            project: None,
+            content: Vec::new(),
+            listed_dir: PathBuf::new(),
+            relisted: None,
+            listing: 0,
+            loading: false,
+            filter: String::new(),
+            visible: Vec::new(),
+            matching: 0,
+            current_match: None,
+            modifiers: keyboard::Modifiers::empty(),
+            type_ahead: TypeAhead::default(),
+            keymap: Keymap::new(config.keymap, &config.keys),
+            listing_scroll: (0., 0.),
+            headings: Vec::new(),
+            listed_mtime: None,
+            selected: Vec::new(),
+            clipboard: None,
+            jobs: match dialog {
+                Some(_) => Jobs::default(),
+                None => Jobs::restore(),
+            },
+            journal: Journal::default(),
+            folder_tree: FolderTree::default(),
+            columns: Columns::default(),
+            folder_sizes: FolderSizes::default(),
+            file_icons: FileIcons::default(),
+            thumbnails: Thumbnails::default(),
+            git: Git::default(),
+            sidecars: Sidecars::default(),
+            selection_size: SelectionSize::default(),
+            prefetch: Prefetch::default(),
+            connected: HashSet::new(),
+            transfers: Transfers::default(),
+            dragging: None,
+            band: None,
+            dropped: None,
+            editor: None,
+            properties: None,
+            checksums: None,
+            usage: None,
+            duplicates: None,
+            compare: None,
+            conversion: None,
+            recode: None,
+            newlines: None,
+            shell: None,
+            launch: None,
+            image_summary: None,
+            package: None,
+            torrent: None,
+            bindings: None,
+            palette: None,
+            recent: Recent::load(),
+            tags: Tags::load(),
+            tag_editor: None,
+            settings: None,
+            last_click: None,
+            accounts: None,
+            login: None,
+            search: Search::default(),
+            sidebar: Sidebar::default(),
+            toasts,
+            prompts: Prompts::default(),
+            blocked: None,
+            menu_bar: MenuBar::default(),
+            config,
+            window: window::Id::MAIN,
+            focused: true,
+            new_windows: false,
+            new_window: None,
+            window_position: None,
+            fullscreen: false,
+            job_options: jobs::Options::default(),
+            show_advanced: false,
+            new_entry: None,
+            dialog,
+            outcome: None,
+            autostart: autostart::is_enabled(),
+            background: false,
+            hot_folders: HotFolders::default(),
+            recently_closed: Vec::new(),
+            reopen: None,
+            history: History::default(),
+            restored_scroll: None,
+            style: Style::default(),
+            restrictions: Restrictions::default(),
+            emblems: Emblems::default(),
+            extensions: Extensions::default(),
+            archive: None,
+            compression: archive::Compression::default(),
+            extract_to: None,
+        };
+        picker.job_options = picker.config.job_options();
+        picker.project = picker
+            .config
+            .project
+            .clone()
+            .filter(|project| project.is_dir() && paths::starts_with(&cwd, project));
+        picker.navigate(cwd);
 
-    match fs::read_dir(cwd) {
-        Ok(entries) => {
-            for entry in entries {
-                match entry {
-                    Ok(entry) => {
-                        let path = entry.path().to_str().unwrap().to_string();
+        (picker, list_volumes())
+    }
+
+    pub fn update(&mut self, message: Message) -> Command<Message> {
+        // Every window's events reach every picker, and the keys too.
+        if window_of(&message).is_some_and(|id| id != self.window)
+            || (!self.focused && from_keyboard(&message))
+        {
+            return Command::none();
+        }
 
-                        if entry.path().is_file() {
-                            files.push(Content::File(ContentData::new(path, false)));
-                        } else {
-                            files.push(Content::Directory(ContentData::new(path, false)));
+        match message {
+            Message::PathInput(path) => {
+                self.completions.update(&path, &self.current_dir);
+                self.path = path;
+                self.path_error = None;
+            }
+            Message::PathChange => {
+                self.completions.clear();
+                match paths::expand(&self.path, &self.current_dir) {
+                    Ok(path)
+                        if !paths::is_computer(&path) && !self.restrictions.may_enter(&path) =>
+                    {
+                        self.path_error = Some(trf("{} is not available here", &[&path.display()]))
+                    }
+                    Ok(path)
+                        if paths::is_computer(&path)
+                            || paths::is_network(&path)
+                            || path.exists() =>
+                    {
+                        self.editing_path = false;
+                        self.navigate(path);
+                    }
+                    Ok(path) => {
+                        self.path_error = Some(trf("{} does not exist", &[&path.display()]))
+                    }
+                    Err(error) => self.path_error = Some(error),
+                }
+            }
+            Message::EditPath => {
+                self.editing_path = true;
+                self.path_error = None;
+                return Command::batch([
+                    text_input::focus(address_bar()),
+                    text_input::select_all(address_bar()),
+                ]);
+            }
+            Message::Navigate(path) => self.navigate(path),
+            Message::CompletionMove(delta) if self.completions.is_empty() => {
+                return self.update(Message::MoveSelection(delta));
+            }
+            Message::CompletionMove(delta) => self.completions.move_selection(delta),
+            Message::CompletionAccept(index) => {
+                if let Some(path) = self.completions.accept(&self.path, index) {
+                    self.completions.update(&path, &self.current_dir);
+                    self.path = path;
+                    return Command::batch([
+                        text_input::focus(address_bar()),
+                        text_input::move_cursor_to_end(address_bar()),
+                    ]);
+                }
+            }
+            Message::Tab(false) if !self.completions.is_empty() => {
+                return self.update(Message::CompletionAccept(None));
+            }
+            Message::Tab(backwards) => {
+                return match backwards {
+                    true => iced::widget::focus_previous(),
+                    false => iced::widget::focus_next(),
+                };
+            }
+            Message::Escape => {
+                if self.palette.is_some() {
+                    self.palette = None;
+                } else if self.is_modal() {
+                    // Its modal closes it.
+                } else if !self.completions.is_empty() {
+                    self.completions.clear();
+                } else if self.editing_path {
+                    self.editing_path = false;
+                    self.path = paths::display(&self.current_dir);
+                    self.path_error = None;
+                } else if !self.filter.is_empty() {
+                    self.filter.clear();
+                    self.apply_filter();
+                }
+            }
+            Message::ContentClicked(content) if self.config.double_click => {
+                let path = content.data().map(|data| data.path.clone());
+                let now = Instant::now();
+                let again = match (&self.last_click, &path) {
+                    (Some((last, at)), Some(path)) => {
+                        last == path && now.duration_since(*at) < DOUBLE_CLICK
+                    }
+                    _ => false,
+                };
+                if again {
+                    self.last_click = None;
+                    return self.update(Message::ContentActivated(content));
+                }
+                self.last_click = path.map(|path| (path, now));
+                if let Some(data) = content.data().filter(|data| !data.is_parent) {
+                    self.selected = vec![data.path.clone()];
+                }
+            }
+            Message::ContentClicked(content) => {
+                return self.update(Message::ContentActivated(content));
+            }
+            Message::ContentActivated(content) => match content {
+                Content::Directory(dir) if !dir.is_parent && paths::is_app_bundle(&dir.path) => {
+                    return open(dir.path);
+                }
+                Content::Directory(dir) => self.navigate(dir.path),
+                Content::File(file) => {
+                    match self.dialog.as_mut().map(|dialog| (dialog.mode(), dialog)) {
+                        Some((DialogMode::OpenFile, _)) => self.selected = vec![file.path],
+                        Some((DialogMode::OpenFiles, _)) => {
+                            let selected = self.selected.contains(&file.path);
+                            return self.update(Message::ContentSelected(file.path, !selected));
+                        }
+                        Some((DialogMode::SaveFile, dialog)) => {
+                            dialog.set_file_name(file.name.to_string_lossy().to_string())
+                        }
+                        Some((DialogMode::OpenDirectory, _)) => {}
+                        None if self.archive.is_none() && archive::format(&file.path).is_some() => {
+                            self.navigate(file.path)
+                        }
+                        None if vfs::is_local(&file.path) => {
+                            if let Some(kind) = package::kind(&file.path) {
+                                self.package = Some(package::Summary::open(file.path, kind));
+                                return Command::none();
+                            }
+                            if let Some(kind) = torrent::kind(&file.path) {
+                                self.torrent = Some(torrent::Summary::open(file.path, kind));
+                                return Command::none();
+                            }
+                            match system::executable(&file.path) {
+                                Some(kind) => self.launch = Some(Launch::open(file.path, kind)),
+                                None => {
+                                    // The picked file is shown as its full path.
+                                    self.editing_path = true;
+                                    self.path = file.path.to_string_lossy().to_string();
+                                }
+                            }
+                        }
+                        None => {
+                            // The picked file is shown as its full path.
+                            self.editing_path = true;
+                            self.path = file.path.to_string_lossy().to_string();
+                        }
+                    }
+                }
+                Content::Corrupt(_) => {}
+            },
+            Message::ShowPackageContents => {
+                if let [path] = &self.selected[..] {
+                    self.navigate(path.clone());
+                }
+            }
+            Message::Opened(path, result) => {
+                if let Err(error) = result {
+                    self.toasts
+                        .error(trf("Cannot open {}", &[&path.display()]), error);
+                }
+            }
+            Message::ContentSelected(path, selected) => {
+                self.selected.retain(|selected| selected != &path);
+                if selected {
+                    self.selected.push(path);
+                }
+            }
+            Message::Listed(listing, batch) => {
+                if listing == self.listing {
+                    match batch {
+                        Ok(batch) => match &mut self.relisted {
+                            Some(relisted) => relisted.extend(batch),
+                            None => self.append(batch),
+                        },
+                        Err(error) => {
+                            // Signed out since, most likely, so the next
+                            // visit signs in again.
+                            if let Some(location) = remote::Location::parse(&self.current_dir) {
+                                self.connected.remove(&location.host());
+                            }
+                            self.recent.forget(&self.current_dir);
+                            if vfs::is_disk_folder(&self.current_dir) {
+                                let dir = self.current_dir.clone();
+                                return Command::perform(
+                                    task::blocking(move || access::diagnose(&dir)),
+                                    move |blocked| Message::Diagnosed(listing, error, blocked),
+                                );
+                            }
+                            self.toasts
+                                .error(trf("Cannot open {}", &[&self.current_dir.display()]), error)
+                        }
+                    }
+                }
+            }
+            Message::ListingDone(listing) => {
+                if listing == self.listing {
+                    self.loading = false;
+                    if let Some(relisted) = self.relisted.take() {
+                        self.merge(relisted);
+                    }
+                    tracing::debug!(entries = self.content.len(), "listing complete");
+                    let scroll = match self.restored_scroll.take() {
+                        Some(y) => scrollable::scroll_to(
+                            self::listing(),
+                            scrollable::AbsoluteOffset { x: 0., y },
+                        ),
+                        None => Command::none(),
+                    };
+                    if cfg!(target_os = "macos") && self.archive.is_none() {
+                        let paths: Vec<PathBuf> = self
+                            .content
+                            .iter()
+                            .filter_map(Content::data)
+                            .filter(|data| !data.is_parent)
+                            .map(|data| data.path.clone())
+                            .collect();
+                        let finder = Command::perform(
+                            task::blocking(move || {
+                                paths
+                                    .into_iter()
+                                    .map(|path| {
+                                        let tags = tags::finder(&path);
+                                        (path, tags)
+                                    })
+                                    .collect()
+                            }),
+                            Message::FinderTags,
+                        );
+                        return Command::batch([scroll, finder]);
+                    }
+                    return scroll;
+                }
+            }
+            Message::FinderTags(found) => self.tags.learn(found),
+            Message::FilterInput(filter) => {
+                self.filter = filter;
+                self.apply_filter();
+            }
+            Message::FilterSubmit => {
+                let step = if self.modifiers.shift() { -1 } else { 1 };
+                return self.update(Message::NextMatch(step));
+            }
+            Message::Find => {
+                return Command::batch([
+                    text_input::focus(filter_box()),
+                    text_input::select_all(filter_box()),
+                ]);
+            }
+            Message::NextMatch(step) => {
+                let matches = self.matches();
+                if matches.is_empty() {
+                    return Command::none();
+                }
+                let count = matches.len() as isize;
+                let next = match self
+                    .current_match
+                    .and_then(|current| matches.iter().position(|&known| known == current))
+                {
+                    Some(index) => (index as isize + step).rem_euclid(count),
+                    None if step < 0 => count - 1,
+                    None => 0,
+                };
+                let position = matches[next as usize];
+                self.current_match = Some(position);
+                return self.jump_to(position);
+            }
+            Message::ModifiersChanged(modifiers) => self.modifiers = modifiers,
+            Message::MoveSelection(step) => {
+                let position = rows::step(self.selected_position(), step, self.visible.len());
+                if let Some(position) = position {
+                    return self.step_to(position);
+                }
+            }
+            Message::MovePage(pages) => {
+                let rows = (self.listing_scroll.1 / self.style.row_height).max(1.) as isize;
+                return self.update(Message::MoveSelection(pages * rows));
+            }
+            Message::SelectFirst if !self.visible.is_empty() => return self.step_to(0),
+            Message::SelectLast if !self.visible.is_empty() => {
+                return self.step_to(self.visible.len() - 1);
+            }
+            Message::SelectFirst | Message::SelectLast => {}
+            Message::SelectAll => {
+                self.selected = self.selectable().map(|(_, path)| path.clone()).collect();
+            }
+            Message::InvertSelection => {
+                self.selected =
+                    rows::invert(self.selectable().map(|(_, path)| path), &self.selected);
+            }
+            Message::Band(top, bottom) => {
+                let adding = self.modifiers.command() || self.modifiers.shift();
+                let base = self
+                    .band
+                    .get_or_insert_with(|| match adding {
+                        true => self.selected.clone(),
+                        false => Vec::new(),
+                    })
+                    .clone();
+                self.selected =
+                    rows::band(self.selectable(), base, top, bottom, self.style.row_height);
+            }
+            Message::BandReleased => self.band = None,
+            Message::OpenSelected => {
+                if let Some(position) = self.selected_position() {
+                    let content = self.content[self.visible[position]].clone();
+                    return self.update(Message::ContentActivated(content));
+                }
+            }
+            Message::ListingScrolled(viewport) => {
+                self.listing_scroll = (viewport.absolute_offset().y, viewport.bounds().height)
+            }
+            Message::TypeAhead(message) => {
+                // Search results are not the listing it selects in.
+                let searching = self.search.is_active();
+                if let Some(prefix) = self.type_ahead.update(message).filter(|_| !searching) {
+                    let prefix = prefix.to_lowercase();
+                    if let Some(position) = self.starting_with(&prefix) {
+                        return self.jump_to(position);
+                    }
+                }
+            }
+            Message::Copy | Message::Cut => {
+                let kind = match message {
+                    Message::Cut => JobKind::Move,
+                    _ => JobKind::Copy,
+                };
+                let uris = uri_list(&self.selected);
+                self.clipboard = Some((kind, self.selected.clone()));
+                return clipboard::write(uris);
+            }
+            Message::Paste => return clipboard::read(Message::Pasted),
+            Message::Pasted(contents) => {
+                let pasted = contents
+                    .as_deref()
+                    .map_or_else(Vec::new, paths::from_uri_list);
+                match self.clipboard.clone() {
+                    // What we copied ourselves, or the clipboard could not
+                    // be read.
+                    Some((kind, sources)) if pasted.is_empty() || pasted == sources => {
+                        if kind == JobKind::Move {
+                            self.clipboard = None;
+                        }
+                        let dir = self.current_dir.clone();
+                        self.transfer(kind, sources, dir);
+                    }
+                    _ if pasted.is_empty() => self.toasts.push(
+                        toast::Kind::Info,
+                        "Nothing to paste",
+                        "The clipboard holds no files or paths.",
+                    ),
+                    _ => {
+                        // Another application copied since.
+                        self.clipboard = None;
+                        self.offer(JobKind::Copy, pasted);
+                    }
+                }
+            }
+            Message::PasteSymlinks => {
+                if let Some((_, sources)) = &self.clipboard {
+                    for source in sources {
+                        let Some(name) = source.file_name() else {
+                            continue;
                         };
+                        let link = self.current_dir.join(name);
+                        if let Err(error) = vfs::for_path(&link).symlink(source, &link) {
+                            self.toasts
+                                .error(trf("Cannot create link {}", &[&link.display()]), error);
+                        }
                     }
-                    Err(_) => files.push(Content::Corrupt),
+                    self.refresh();
                 }
             }
-        }
-        Err(_) => {
-            files.push(Content::Corrupt);
-        }
-    }
-
-    files
-}
+            Message::Trash => {
+                let title = match &self.selected[..] {
+                    [] => return Command::none(),
+                    [path] => trf("Move \"{}\" to the trash?", &[&display_name(path)]),
+                    selected => trf(
+                        "Move {} items to the trash?",
+                        &[&format_count(selected.len())],
+                    ),
+                };
+                let Some(risk) = self.risk(&self.selected) else {
+                    let sources = std::mem::take(&mut self.selected);
+                    self.jobs
+                        .push(JobKind::Trash, sources, None, self.job_options);
+                    return Command::none();
+                };
+                self.prompts.push(
+                    Prompt::new(Tone::Warning, title, risk)
+                        .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                        .button(
+                            tr("Move to trash"),
+                            Role::Destructive,
+                            Answer::Trash(self.selected.clone()),
+                        )
+                        .dismiss(Answer::Dismiss),
+                );
+            }
+            Message::DragStart(path) => {
+                // A selected entry takes the rest of the selection along.
+                self.dragging = Some(if self.selected.contains(&path) {
+                    self.selected.clone()
+                } else {
+                    vec![path]
+                });
+            }
+            Message::DropOn(dir) => {
+                if let Some(sources) = self.dragging.take() {
+                    let kind = self.drop_kind(&sources, &dir);
+                    self.drop_into(kind, sources, dir);
+                }
+            }
+            Message::DragEnd => self.dragging = None,
+            Message::DragOut => {
+                if let Some(sources) = self.dragging.take() {
+                    return window::run_with_handle(self.window, move |handle| {
+                        Message::DraggedOut(
+                            drag_out::start(handle, sources).map_err(|error| error.to_string()),
+                        )
+                    });
+                }
+            }
+            Message::DraggedOut(Ok(())) => {}
+            Message::DraggedOut(Err(error)) => self
+                .toasts
+                .error(tr("Cannot drag out of the window"), error),
+            Message::FileDropped(_, path) => {
+                if let Some((_, dropped)) = &mut self.dropped {
+                    dropped.push(path);
+                    return Command::none();
+                }
+                // Other applications' files are copied unless Shift asks
+                // for a move, since their owner may still want them there.
+                let kind = if self.modifiers.shift() {
+                    JobKind::Move
+                } else {
+                    JobKind::Copy
+                };
+                self.dropped = Some((kind, vec![path]));
+                // The rest of the drop comes in the same batch of events.
+                return Command::perform(async {}, |()| Message::DropFinished);
+            }
+            Message::DropFinished => {
+                if let Some((kind, dropped)) = self.dropped.take() {
+                    self.offer(kind, dropped);
+                }
+            }
+            Message::Undo | Message::Redo => {
+                let undone = self.journal.next_undo().map(Operation::moves);
+                let (result, moves) = match message {
+                    Message::Undo => {
+                        let moves = undone.unwrap_or_default();
+                        let back = moves.into_iter().map(|(from, to)| (to, from)).collect();
+                        (self.journal.undo(), back)
+                    }
+                    _ => {
+                        let result = self.journal.redo();
+                        let again = self.journal.next_undo().map(Operation::moves);
+                        (result, again.unwrap_or_default())
+                    }
+                };
+                match result {
+                    Some(Ok(done)) => {
+                        self.toasts.push(toast::Kind::Info, done, "");
+                        self.tags_follow(&moves);
+                    }
+                    Some(Err(error)) => self.toasts.error(error, ""),
+                    None => return Command::none(),
+                }
+                self.refresh();
+            }
+            Message::Delete => {
+                let title = match &self.selected[..] {
+                    [] => return Command::none(),
+                    [path] => trf("Delete \"{}\"?", &[&display_name(path)]),
+                    selected => trf("Delete {} items?", &[&format_count(selected.len())]),
+                };
+                let risk = self.risk(&self.selected);
+                if self.config.delete_without_asking && risk.is_none() {
+                    let sources = std::mem::take(&mut self.selected);
+                    self.jobs
+                        .push(JobKind::Delete, sources, None, self.job_options);
+                    return Command::none();
+                }
+                let mut body = tr("This cannot be undone.").to_string();
+                if let Some(risk) = risk {
+                    body = format!("{} {}", risk, body);
+                }
+                self.prompts.push(
+                    Prompt::new(Tone::Warning, title, body)
+                        .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                        .button(
+                            tr("Delete"),
+                            Role::Destructive,
+                            Answer::Delete(self.selected.clone()),
+                        )
+                        .dismiss(Answer::Dismiss),
+                );
+            }
+            Message::NewEntry(kind) => {
+                let name = match &kind {
+                    EntryKind::Rename(path) => path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    EntryKind::Archive => {
+                        let stem = match &self.selected[..] {
+                            [path] => path.file_stem().map(|stem| stem.to_string_lossy()),
+                            _ => None,
+                        };
+                        format!(
+                            "{}.{}",
+                            stem.as_deref().unwrap_or("Archive"),
+                            self.compression.format.extension()
+                        )
+                    }
+                    EntryKind::File | EntryKind::Folder => String::new(),
+                };
+                self.new_entry = Some(NewEntry {
+                    kind,
+                    name,
+                    error: None,
+                    case_sensitive: validate::case_sensitive(&self.current_dir),
+                })
+            }
+            Message::NewEntryInput(name) => {
+                if let Some(entry) = &mut self.new_entry {
+                    entry.error = validate::validate_name(
+                        &self.current_dir,
+                        &name,
+                        entry.kind.renaming(),
+                        entry.case_sensitive,
+                    )
+                    .err();
+                    entry.name = name;
+                }
+            }
+            Message::CreateEntry => {
+                if let Some(entry) = self.new_entry.take() {
+                    let path = match validate::validate_name(
+                        &self.current_dir,
+                        &entry.name,
+                        entry.kind.renaming(),
+                        entry.case_sensitive,
+                    ) {
+                        Ok(path) => path,
+                        Err(error) => {
+                            self.new_entry = Some(NewEntry {
+                                error: Some(error),
+                                ..entry
+                            });
+                            return Command::none();
+                        }
+                    };
+                    let folder = matches!(entry.kind, EntryKind::Folder);
+                    let created = match &entry.kind {
+                        // Over the network, in the background, and not
+                        // undoable.
+                        EntryKind::Folder | EntryKind::File if !vfs::is_local(&path) => {
+                            let created = path.clone();
+                            return Command::perform(
+                                task::blocking(move || {
+                                    let fs = vfs::for_path(&path);
+                                    match folder {
+                                        true => fs.create_dir(&path),
+                                        false => fs.create_file(&path),
+                                    }
+                                    .map_err(|error| error.to_string())
+                                }),
+                                move |result| Message::CloudCreated(created.clone(), result),
+                            );
+                        }
+                        // On the disk, creation goes through the regular mode
+                        // bits (0o777 for folders, 0o666 for files) so the
+                        // kernel applies our umask.
+                        EntryKind::Folder | EntryKind::File => {
+                            let fs = vfs::for_path(&path);
+                            match folder {
+                                true => fs.create_dir(&path),
+                                false => fs.create_file(&path),
+                            }
+                            .map(|()| {
+                                self.journal.record(Operation::Create {
+                                    path: path.clone(),
+                                    folder,
+                                })
+                            })
+                        }
+                        // Over the network, in the background, and not
+                        // undoable.
+                        EntryKind::Rename(original) if !vfs::is_local(original) => {
+                            let (from, to) = (original.clone(), path.clone());
+                            let original = original.clone();
+                            return Command::perform(
+                                task::blocking(move || {
+                                    vfs::for_path(&from)
+                                        .rename(&from, &to)
+                                        .map_err(|error| error.to_string())
+                                }),
+                                move |result| Message::CloudRenamed(original.clone(), result),
+                            );
+                        }
+                        EntryKind::Rename(original) => {
+                            journal::rename(original, &path, entry.case_sensitive).map(|()| {
+                                self.journal.record(Operation::Rename {
+                                    from: original.clone(),
+                                    to: path.clone(),
+                                    case_sensitive: entry.case_sensitive,
+                                });
+                                self.tags_follow(&[(original.clone(), path.clone())]);
+                            })
+                        }
+                        EntryKind::Archive => {
+                            let sources = std::mem::take(&mut self.selected);
+                            self.jobs.push(
+                                JobKind::Compress(self.compression),
+                                sources,
+                                Some(path.clone()),
+                                self.job_options,
+                            );
+                            Ok(())
+                        }
+                    };
+                    match created {
+                        Ok(()) => {
+                            if let EntryKind::Rename(original) = &entry.kind {
+                                self.selected.retain(|selected| selected != original);
+                            }
+                            self.refresh();
+                        }
+                        Err(error) => self
+                            .toasts
+                            .error(trf("Cannot create {}", &[&path.display()]), error),
+                    }
+                }
+            }
+            Message::CancelNewEntry => self.new_entry = None,
+            Message::CompressFormat(format) => {
+                let old = std::mem::replace(&mut self.compression.format, format);
+                if let Some(entry) = &self.new_entry {
+                    // Swap the extension along, unless the user typed their own.
+                    if let Some(stem) = entry.name.strip_suffix(&format!(".{}", old.extension())) {
+                        let name = format!("{}.{}", stem, format.extension());
+                        return self.update(Message::NewEntryInput(name));
+                    }
+                }
+            }
+            Message::CompressLevel(level) => self.compression.level = level,
+            Message::ToggleAdvanced => self.show_advanced = !self.show_advanced,
+            Message::OpenAccounts => self.accounts = Some(Accounts::open(&self.config.webdav)),
+            Message::Accounts(message) => {
+                if let Some(dialog) = &mut self.accounts {
+                    match dialog.update(message) {
+                        Some(accounts::Event::Changed(accounts)) => {
+                            webdav::configure(&accounts);
+                            self.config.webdav = accounts;
+                            self.save_config();
+                        }
+                        Some(accounts::Event::Closed) => self.accounts = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::CloudRenamed(original, Ok(())) => {
+                self.selected.retain(|selected| *selected != original);
+                self.refresh();
+            }
+            Message::CloudRenamed(original, Err(error)) => self
+                .toasts
+                .error(trf("Cannot rename {}", &[&original.display()]), error),
+            Message::CloudCreated(_, Ok(())) => self.refresh(),
+            Message::CloudCreated(path, Err(error)) => self
+                .toasts
+                .error(trf("Cannot create {}", &[&path.display()]), error),
+            Message::PreserveTimestamps(preserve) => {
+                self.job_options.preserve_timestamps = preserve
+            }
+            Message::DefaultPreserveTimestamps(preserve) => {
+                self.config.preserve_timestamps = preserve;
+                self.job_options.preserve_timestamps = preserve;
+                self.save_config();
+            }
+            Message::ResolveSymlinks(resolve) => {
+                self.config.symlinks = match resolve {
+                    true => Symlinks::Resolve,
+                    false => Symlinks::Keep,
+                };
+                self.save_config();
+            }
+            Message::UseSystemIndex(use_index) => {
+                self.config.use_system_index = use_index;
+                self.save_config();
+            }
+            Message::UseNtfsIndex(use_index) => {
+                self.config.use_ntfs_index = use_index;
+                self.save_config();
+            }
+            Message::ShowAppleDouble(show) => {
+                self.config.show_apple_double = show;
+                self.save_config();
+                self.apply_filter();
+            }
+            Message::ShowFolderTree(show) => {
+                self.config.hide_folder_tree = !show;
+                self.save_config();
+            }
+            Message::ShowToolbar(show) => {
+                self.leave_distraction_free();
+                self.config.hide_toolbar = !show;
+                self.save_config();
+            }
+            Message::ShowSidebar(show) => {
+                self.leave_distraction_free();
+                self.config.hide_sidebar = !show;
+                self.save_config();
+            }
+            Message::ShowStatusBar(show) => {
+                self.leave_distraction_free();
+                self.config.hide_status_bar = !show;
+                self.save_config();
+            }
+            Message::ToggleDistractionFree => {
+                self.config.distraction_free = !self.config.distraction_free;
+                self.save_config();
+            }
+            Message::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                let mode = match self.fullscreen {
+                    true => window::Mode::Fullscreen,
+                    false => window::Mode::Windowed,
+                };
+                return window::change_mode(self.window, mode);
+            }
+            Message::ViewMode(mode) => self.set_view(View { mode, ..self.view }),
+            Message::SortBy(sort) => self.set_view(View { sort, ..self.view }),
+            Message::GroupBy(group_by) => self.set_view(View {
+                group_by,
+                ..self.view
+            }),
+            Message::DefaultView => self.set_view(self.config.default_view()),
+            Message::FolderSize(what) => {
+                self.config.folder_size = what;
+                self.save_config();
+            }
+            Message::FolderSizes(message) => self.folder_sizes.update(message),
+            Message::FileIcons(message) => self.file_icons.update(message),
+            Message::Thumbnails(message) => self.thumbnails.update(message),
+            Message::Git(message) => self.git.update(message),
+            Message::SelectionSize(message) => self.selection_size.update(message),
+            Message::Prefetch(message) => self.prefetch.update(message),
+            Message::Sidecars(message) => {
+                if let Some(sidecars::Event::Verified {
+                    checked,
+                    differ,
+                    failed,
+                }) = self.sidecars.update(message)
+                {
+                    let names = |paths: &[PathBuf]| {
+                        paths
+                            .iter()
+                            .map(|path| display_name(path))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    };
+                    if !differ.is_empty() {
+                        self.toasts.error(
+                            match differ.len() {
+                                1 => String::from(tr("1 file does not match its checksum")),
+                                count => trf(
+                                    "{} files do not match their checksums",
+                                    &[&format_count(count)],
+                                ),
+                            },
+                            names(&differ),
+                        );
+                    }
+                    if !failed.is_empty() {
+                        self.toasts.error(
+                            match failed.len() {
+                                1 => String::from(tr("1 file could not be checked")),
+                                count => {
+                                    trf("{} files could not be checked", &[&format_count(count)])
+                                }
+                            },
+                            names(&failed),
+                        );
+                    }
+                    if differ.is_empty() && failed.is_empty() {
+                        self.toasts.push(
+                            toast::Kind::Info,
+                            match checked {
+                                1 => String::from(tr("The checksum matches")),
+                                checked => trf("All {} checksums match", &[&format_count(checked)]),
+                            },
+                            "",
+                        );
+                    }
+                }
+            }
+            Message::Login(message) => {
+                if let Some(login) = &mut self.login {
+                    match login.update(message) {
+                        Some(login::Event::Connected(host, target)) => {
+                            self.login = None;
+                            self.connected.insert(host);
+                            self.navigate(target);
+                        }
+                        Some(login::Event::Cancelled) => self.login = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::Transfers(message) => match self.transfers.update(message) {
+                Some(remote::Event::Done(copies)) => self.choose(copies),
+                Some(remote::Event::Failed(error)) => {
+                    self.toasts.error(tr("Cannot copy the files here"), error)
+                }
+                None => {}
+            },
+            Message::BookmarkRemote => {
+                if !self.config.remote_bookmarks.contains(&self.current_dir) {
+                    self.config.remote_bookmarks.push(self.current_dir.clone());
+                    self.save_config();
+                }
+            }
+            Message::UnbookmarkRemote => {
+                self.config
+                    .remote_bookmarks
+                    .retain(|bookmark| *bookmark != self.current_dir);
+                self.save_config();
+            }
+            Message::Columns(message) => match self.columns.update(message) {
+                Some(columns::Event::Open(path)) => self.navigate(path),
+                None => {}
+            },
+            Message::FolderTree(message) => match self.folder_tree.update(message) {
+                Some(folder_tree::Event::Open(path)) => {
+                    self.search.update(search::Message::Clear);
+                    self.navigate(path);
+                }
+                None => {}
+            },
+            Message::Autostart(enabled) => match autostart::set_enabled(enabled) {
+                Ok(()) => self.autostart = enabled,
+                Err(error) => self.toasts.error(tr("Cannot change the login item"), error),
+            },
+            Message::LowMemory(low_memory) => {
+                self.config.low_memory = low_memory;
+                self.job_options.low_memory = low_memory;
+                if low_memory {
+                    self.prefetch.clear();
+                    self.thumbnails.clear();
+                }
+                self.save_config();
+                self.refresh();
+            }
+            Message::RunAction(index) => {
+                let Some(action) = self.extensions.actions().get(index).cloned() else {
+                    return Command::none();
+                };
+                let selection = self.selected.clone();
+                let label = action.label().to_string();
+                return Command::perform(
+                    task::blocking(move || action.run(&selection)),
+                    move |result| Message::ActionDone(label.clone(), result),
+                );
+            }
+            Message::ActionDone(label, Ok(done)) => {
+                self.toasts.push(toast::Kind::Info, label, done);
+                self.refresh();
+            }
+            Message::ActionDone(label, Err(error)) => {
+                self.toasts.error(trf("{} failed", &[&label]), error);
+                self.refresh();
+            }
+            Message::StripAppleDouble => {
+                let root = self.current_dir.clone();
+                return Command::perform(
+                    task::blocking(move || streams::apple_double_files(&root)),
+                    Message::AppleDoubleFound,
+                );
+            }
+            Message::AppleDoubleFound(files) => {
+                if files.is_empty() {
+                    self.toasts.push(
+                        toast::Kind::Info,
+                        "Nothing to remove",
+                        "No ._ files below this folder",
+                    );
+                } else {
+                    self.prompts.push(
+                        Prompt::new(
+                            Tone::Question,
+                            trf("Remove {} ._ files?", &[&format_count(files.len())]),
+                            tr("They only hold Finder metadata written by macOS."),
+                        )
+                        .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                        .button(tr("Remove"), Role::Destructive, Answer::Delete(files))
+                        .dismiss(Answer::Dismiss),
+                    );
+                }
+            }
+            Message::Dialog(message) => {
+                let Some(dialog) = &mut self.dialog else {
+                    return Command::none();
+                };
+                match dialog.update(message) {
+                    Some(dialog::Event::Confirm) => {
+                        if let Some(typed) = dialog.typed_path(&self.current_dir) {
+                            match typed {
+                                Ok((dir, name)) if dir.is_dir() => self.save_in(dir, name),
+                                Ok((dir, name)) => self.prompts.push(
+                                    Prompt::new(
+                                        Tone::Question,
+                                        trf("Create \"{}\"?", &[&dir.display()]),
+                                        tr("There is no such folder yet."),
+                                    )
+                                    .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                                    .button(
+                                        tr("Create"),
+                                        Role::Primary,
+                                        Answer::CreateFolder(dir, name),
+                                    )
+                                    .dismiss(Answer::Dismiss),
+                                ),
+                                Err(error) => self.toasts.error(tr("Cannot go there"), error),
+                            }
+                            return Command::none();
+                        }
+                        match dialog.choice(&self.current_dir, &self.selected) {
+                            Some(paths)
+                                if dialog.mode() == DialogMode::SaveFile
+                                    && paths.iter().any(|path| path.exists()) =>
+                            {
+                                let title = match &paths[..] {
+                                    [path] => trf("Replace \"{}\"?", &[&display_name(path)]),
+                                    _ => "Replace the existing files?".to_string(),
+                                };
+                                self.prompts.push(
+                                    Prompt::new(
+                                        Tone::Warning,
+                                        title,
+                                        tr("It already exists here. Replacing it overwrites what it holds."),
+                                    )
+                                    .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                                    .button(tr("Replace"), Role::Destructive, Answer::Replace(paths))
+                                    .dismiss(Answer::Dismiss),
+                                );
+                            }
+                            Some(paths) => self.choose(paths),
+                            None => {}
+                        }
+                    }
+                    Some(dialog::Event::Cancel) => self.outcome = Some(Outcome::Cancelled),
+                    Some(dialog::Event::FilterChanged) => {
+                        let accepted = |path: &PathBuf| {
+                            path.is_dir()
+                                || path
+                                    .file_name()
+                                    .is_some_and(|name| dialog.accepts(&name.to_string_lossy()))
+                        };
+                        self.selected.retain(accepted);
+                    }
+                    None => {}
+                }
+            }
+            Message::Sidebar(message) => match self.sidebar.update(message) {
+                Some(sidebar::Event::VolumesChanged) if paths::is_computer(&self.current_dir) => {
+                    self.refresh()
+                }
+                Some(sidebar::Event::Open(path)) => {
+                    self.search.update(search::Message::Clear);
+                    self.navigate(path);
+                }
+                Some(sidebar::Event::Drop(dir)) => {
+                    if let Some(sources) = self.dragging.take() {
+                        let kind = self.drop_kind(&sources, &dir);
+                        self.drop_into(kind, sources, dir);
+                    }
+                }
+                Some(sidebar::Event::Mount(drive)) => {
+                    return Command::perform(
+                        task::blocking(move || {
+                            udisks::mount(&drive).map_err(|error| (drive.label, error.to_string()))
+                        }),
+                        Message::Mounted,
+                    );
+                }
+                Some(sidebar::Event::Eject(path)) => {
+                    if paths::starts_with(&self.current_dir, &path) {
+                        let home = env::var_os("HOME").map(PathBuf::from);
+                        self.navigate(home.unwrap_or_else(|| PathBuf::from("/")));
+                    }
+                    return Command::perform(
+                        task::blocking(move || {
+                            system::eject(&path).map_err(|error| error.to_string())
+                        }),
+                        |result| Message::SystemActionDone(AfterAction::EjectDrive, result),
+                    );
+                }
+                Some(sidebar::Event::VolumesChanged) | None => {}
+            },
+            Message::Search(search::Message::Found(generation, mut found)) => {
+                found.retain(|hit| self.restrictions.shows(&hit.path));
+                self.search
+                    .update(search::Message::Found(generation, found));
+            }
+            Message::Search(message) => match self.search.update(message) {
+                Some(search::Event::Start) => {
+                    let root = self.project.as_ref().unwrap_or(&self.current_dir).clone();
+                    match tags::wanted(self.search.query()) {
+                        Some(tag) => {
+                            let mut found = self.tags.tagged(&tag, &root);
+                            found.retain(|path| self.restrictions.shows(path));
+                            self.search.show(root, found);
+                        }
+                        None => self.search.start(root, self.config.search_sources()),
+                    }
+                }
+                Some(search::Event::Open(path)) => {
+                    self.search.update(search::Message::Clear);
+                    if let Some(parent) = path.parent() {
+                        self.navigate(parent.to_path_buf());
+                        if self.current_dir == parent {
+                            self.selected.push(path);
+                        }
+                    }
+                }
+                None => {}
+            },
+            Message::Edit => {
+                if let [path] = &self.selected[..] {
+                    self.edit(path.clone());
+                }
+            }
+            Message::RunAppImage => {
+                if let [path] = &self.selected[..] {
+                    if let Err(error) = appimage::run(path) {
+                        self.toasts
+                            .error(trf("Cannot run {}", &[&path.display()]), error.to_string());
+                    }
+                }
+            }
+            Message::IntegrateAppImage | Message::ExtractAppImage => {
+                if let [path] = &self.selected[..] {
+                    let path = path.clone();
+                    let integrate = matches!(message, Message::IntegrateAppImage);
+                    return Command::perform(
+                        task::blocking({
+                            let path = path.clone();
+                            move || {
+                                match integrate {
+                                    true => appimage::integrate(&path),
+                                    false => appimage::extract(&path),
+                                }
+                                .map_err(|error| error.to_string())
+                            }
+                        }),
+                        move |result| match integrate {
+                            true => Message::Integrated(path.clone(), result),
+                            false => Message::AppImageExtracted(path.clone(), result),
+                        },
+                    );
+                }
+            }
+            Message::Integrated(path, result) => match result {
+                Ok(entry) => self.toasts.push(
+                    toast::Kind::Info,
+                    trf("Added {} to the menu", &[&display_name(&path)]),
+                    entry.display().to_string(),
+                ),
+                Err(error) => self.toasts.error(
+                    trf("Cannot add {} to the menu", &[&display_name(&path)]),
+                    error,
+                ),
+            },
+            Message::AppImageExtracted(path, result) => match result {
+                Ok(dir) => self.toasts.push(
+                    toast::Kind::Info,
+                    trf("Extracted {}", &[&display_name(&path)]),
+                    dir.display().to_string(),
+                ),
+                Err(error) => self
+                    .toasts
+                    .error(trf("Cannot extract {}", &[&display_name(&path)]), error),
+            },
+            Message::ShowImageSummary => {
+                if let [path] = &self.selected[..] {
+                    self.image_summary = Some(oci::Summary::open(path.clone()));
+                }
+            }
+            Message::ShowPackage => {
+                if let [path] = &self.selected[..] {
+                    if let Some(kind) = package::kind(path) {
+                        self.package = Some(package::Summary::open(path.clone(), kind));
+                    }
+                }
+            }
+            Message::Package(message) => {
+                if let Some(dialog) = &mut self.package {
+                    match dialog.update(message) {
+                        Some(package::Event::Install(path, kind)) => {
+                            match package::install_command(&path, kind) {
+                                Ok(command) => self.prompts.push(
+                                    Prompt::new(
+                                        Tone::Warning,
+                                        trf("Install {}?", &[&display_name(&path)]),
+                                        trf("This runs, as administrator:\n{}", &[&command]),
+                                    )
+                                    .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                                    .button(
+                                        tr("Install"),
+                                        Role::Primary,
+                                        Answer::Install(
+                                            path.parent().unwrap_or(&path).to_path_buf(),
+                                            command,
+                                        ),
+                                    )
+                                    .dismiss(Answer::Dismiss),
+                                ),
+                                Err(error) => self.toasts.error(
+                                    trf("Cannot install {}", &[&display_name(&path)]),
+                                    error,
+                                ),
+                            }
+                        }
+                        Some(package::Event::Closed) => self.package = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ShowTorrent => {
+                if let [path] = &self.selected[..] {
+                    if let Some(kind) = torrent::kind(path) {
+                        self.torrent = Some(torrent::Summary::open(path.clone(), kind));
+                    }
+                }
+            }
+            Message::Torrent(message) => {
+                if let Some(dialog) = &mut self.torrent {
+                    match dialog.update(message) {
+                        Some(torrent::Event::Open(target)) => return open(target),
+                        Some(torrent::Event::CopyMagnet(link)) => {
+                            self.toasts
+                                .push(toast::Kind::Info, "Magnet link copied", "");
+                            return clipboard::write(link);
+                        }
+                        Some(torrent::Event::Closed) => self.torrent = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ImageSummary(message) => {
+                if let Some(dialog) = &mut self.image_summary {
+                    if let Some(oci::Event::Closed) = dialog.update(message) {
+                        self.image_summary = None;
+                    }
+                }
+            }
+            Message::Launch(message) => {
+                if let Some(dialog) = &mut self.launch {
+                    match dialog.update(message) {
+                        Some(launch::Event::Run(path, args)) => {
+                            self.launch = None;
+                            if let Err(error) = system::launch(&path, &args) {
+                                self.toasts.error(
+                                    trf("Cannot run {}", &[&path.display()]),
+                                    error.to_string(),
+                                );
+                            }
+                        }
+                        Some(launch::Event::Edit(path)) => {
+                            self.launch = None;
+                            self.edit(path);
+                        }
+                        Some(launch::Event::Closed) => self.launch = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::Editor(message) => {
+                if let Some(editor) = &mut self.editor {
+                    match editor.update(message) {
+                        Some(editor::Event::Closed) => self.editor = None,
+                        Some(editor::Event::Saved(path)) => {
+                            self.toasts
+                                .push(toast::Kind::Info, "Saved", path.display().to_string())
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Message::ShowProperties => {
+                let path = match &self.selected[..] {
+                    [path] => path.clone(),
+                    [] => self.current_dir.clone(),
+                    _ => return Command::none(),
+                };
+                let metadata = self
+                    .content
+                    .iter()
+                    .filter_map(Content::data)
+                    .find(|data| !data.is_parent && data.path == path)
+                    .and_then(|data| data.metadata.as_deref().cloned());
+                match Properties::open(path.clone(), metadata) {
+                    Ok(properties) => {
+                        let is_file = properties.is_file();
+                        self.properties = Some(properties);
+                        if is_file {
+                            return Command::perform(
+                                task::blocking(move || {
+                                    let media = media::read(&path).map(Box::new);
+                                    (path, media)
+                                }),
+                                |(path, media)| {
+                                    Message::Properties(properties::Message::MediaRead(path, media))
+                                },
+                            );
+                        }
+                    }
+                    Err(error) => self
+                        .toasts
+                        .error(trf("Cannot read {}", &[&path.display()]), error),
+                }
+            }
+            Message::Properties(message) => {
+                if let Some(dialog) = &mut self.properties {
+                    match dialog.update(message) {
+                        Some(properties::Event::CalculateSize(path, stop)) => {
+                            return Command::perform(
+                                task::blocking(move || {
+                                    let Some(_turn) = devices::wait_for(&[&path], || {
+                                        stop.load(Ordering::Relaxed)
+                                    }) else {
+                                        return Err(tr("Cancelled").to_string());
+                                    };
+                                    properties::measure(&path, &stop)
+                                }),
+                                |result| {
+                                    Message::Properties(properties::Message::SizeCalculated(result))
+                                },
+                            );
+                        }
+                        Some(properties::Event::ApplyMode {
+                            path,
+                            mode,
+                            recursive,
+                        }) => {
+                            return Command::perform(
+                                task::blocking(move || properties::chmod(&path, mode, recursive)),
+                                |failures| {
+                                    Message::Properties(properties::Message::ModeApplied(failures))
+                                },
+                            );
+                        }
+                        Some(properties::Event::Checksums(path)) => {
+                            self.properties = None;
+                            return self.show_checksums(vec![path]);
+                        }
+                        Some(properties::Event::Closed) => self.properties = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ShowChecksums => return self.show_checksums(self.selected.clone()),
+            Message::AnalyzeUsage => self.usage = Some(Usage::open(self.current_dir.clone())),
+            Message::Usage(message) => {
+                if let Some(usage) = &mut self.usage {
+                    match usage.update(message) {
+                        Some(usage::Event::Open(path)) => {
+                            self.usage = None;
+                            self.navigate(path);
+                        }
+                        Some(usage::Event::Closed) => self.usage = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ConvertImages => {
+                self.conversion = Some(Convert::open(&self.current_dir, self.selected.len()))
+            }
+            Message::Conversion(message) => {
+                if let Some(dialog) = &mut self.conversion {
+                    match dialog.update(message) {
+                        Some(conversion::Event::Convert(conversion, destination)) => {
+                            self.conversion = None;
+                            self.convert_images(conversion, destination);
+                        }
+                        Some(conversion::Event::Closed) => self.conversion = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::Tag(tag, on) => {
+                if let Err(error) = self.tags.set(&self.selected, &tag, on) {
+                    self.toasts
+                        .error(tr("Cannot save the tags"), error.to_string());
+                }
+            }
+            Message::EditTags => {
+                self.tag_editor = Some(tags::Editor::open(self.selected.clone(), &self.tags));
+                return text_input::focus(tags::input());
+            }
+            Message::Tags(message) => {
+                if let Some(editor) = &mut self.tag_editor {
+                    match editor.update(message) {
+                        Some(tags::Event::Save(paths, tags)) => {
+                            self.tag_editor = None;
+                            if let Err(error) = self.tags.replace(&paths, &tags) {
+                                self.toasts
+                                    .error(tr("Cannot save the tags"), error.to_string());
+                            }
+                        }
+                        Some(tags::Event::Cancelled) => self.tag_editor = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ConvertText => self.recode = Some(Recode::open(self.selected.clone())),
+            Message::Recode(message) => {
+                if let Some(dialog) = &mut self.recode {
+                    match dialog.update(message) {
+                        Some(recode::Event::Convert(sources, bom)) => {
+                            self.recode = None;
+                            self.jobs
+                                .push(JobKind::Recode(bom), sources, None, self.job_options);
+                        }
+                        Some(recode::Event::Closed) => self.recode = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::OpenTerminal => {
+                if let Err(error) = system::open_terminal(&self.current_dir, &self.config.terminal)
+                {
+                    self.toasts
+                        .error(tr("Cannot open a terminal"), error.to_string());
+                }
+            }
+            Message::RunCommand => self.shell = Some(Shell::open(self.current_dir.clone())),
+            Message::Shell(message) => {
+                if let Some(dialog) = &mut self.shell {
+                    if let Some(shell::Event::Closed) = dialog.update(message) {
+                        self.shell = None;
+                    }
+                }
+            }
+            Message::TerminalCommand(command) => {
+                self.config.terminal = command;
+                self.save_config();
+            }
+            Message::ConvertLineEndings => {
+                self.newlines = Some(Newlines::open(self.selected.clone()))
+            }
+            Message::Newlines(message) => {
+                if let Some(dialog) = &mut self.newlines {
+                    match dialog.update(message) {
+                        Some(newlines::Event::Convert(sources, to)) => {
+                            self.newlines = None;
+                            self.jobs.push(
+                                JobKind::LineEndings(to),
+                                sources,
+                                None,
+                                self.job_options,
+                            );
+                        }
+                        Some(newlines::Event::Closed) => self.newlines = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::FindDuplicates => {
+                self.duplicates = Some(Duplicates::open(self.current_dir.clone()))
+            }
+            Message::Duplicates(message) => {
+                if let Some(finder) = &mut self.duplicates {
+                    match finder.update(message) {
+                        Some(duplicates::Event::Trash(paths)) => {
+                            self.jobs
+                                .push(JobKind::Trash, paths, None, self.job_options);
+                        }
+                        Some(duplicates::Event::Link(pairs)) => {
+                            return Command::perform(
+                                task::blocking(move || duplicates::link(pairs)),
+                                |(linked, failure)| {
+                                    Message::Duplicates(duplicates::Message::Linked(
+                                        linked, failure,
+                                    ))
+                                },
+                            );
+                        }
+                        Some(duplicates::Event::Closed) => self.duplicates = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ProjectHere => self.open_project(self.current_dir.clone()),
+            Message::OpenProject(Project(root)) => self.open_project(root),
+            Message::CloseProject => self.project = None,
+            Message::CompareFolders => {
+                // A single selected folder is likely what to compare with.
+                let other = match &self.selected[..] {
+                    [dir] if dir.is_dir() => Some(dir.clone()),
+                    _ => None,
+                };
+                self.compare = Some(Compare::open(self.current_dir.clone(), other));
+            }
+            Message::Compare(message) => {
+                if let Some(compare) = &mut self.compare {
+                    match compare.update(message) {
+                        Some(compare::Event::Sync(copies)) => {
+                            // Kept times make the copies compare the same
+                            // afterwards.
+                            let options = jobs::Options {
+                                preserve_timestamps: true,
+                                overwrite: true,
+                                ..self.job_options
+                            };
+                            for (sources, into) in copies {
+                                if let Err(error) = fs::create_dir_all(&into) {
+                                    self.toasts
+                                        .error(trf("Cannot create {}", &[&into.display()]), error);
+                                    continue;
+                                }
+                                self.jobs.push(JobKind::Copy, sources, Some(into), options);
+                            }
+                        }
+                        Some(compare::Event::Closed) => self.compare = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::Checksums(message) => {
+                if let Some(checksums) = &mut self.checksums {
+                    match checksums.update(message) {
+                        Some(checksum::Event::Copy(sum)) => {
+                            self.toasts
+                                .push(toast::Kind::Info, "Checksum copied", sum.clone());
+                            return clipboard::write(sum);
+                        }
+                        Some(checksum::Event::Closed) => self.checksums = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::Jobs(message) => {
+                let event = self.jobs.update(message);
+                for (kind, mut moved) in self.jobs.take_moved() {
+                    // Undoing only knows how to move things back on disk.
+                    moved.retain(|(from, to)| vfs::is_local(from) && vfs::is_local(to));
+                    if moved.is_empty() {
+                        continue;
+                    }
+                    self.tags_follow(&moved);
+                    self.journal.record(match kind {
+                        JobKind::Trash => Operation::Trash(moved),
+                        _ => Operation::Move(moved),
+                    });
+                }
+                if let (
+                    Some(compare),
+                    Some(jobs::Event::Finished | jobs::Event::Completed { .. }),
+                ) = (&mut self.compare, &event)
+                {
+                    compare.refresh();
+                }
+                match event {
+                    // Jobs change how much room the drives have left.
+                    Some(jobs::Event::Finished) => {
+                        self.refresh();
+                        return list_volumes();
+                    }
+                    Some(jobs::Event::Completed {
+                        kind,
+                        count,
+                        destination,
+                    }) => {
+                        self.refresh();
+                        let title = match count {
+                            1 => trf("{} 1 item", &[&kind.completed()]),
+                            count => trf("{} {} items", &[&kind.completed(), &format_count(count)]),
+                        };
+                        let action = match (kind, destination) {
+                            (JobKind::Compress(_), Some(archive)) => {
+                                Some((archive.clone(), Action::Reveal(vec![archive])))
+                            }
+                            (JobKind::Trash | JobKind::Delete, _) | (_, None) => None,
+                            (_, Some(dir)) => Some((dir.clone(), Action::Open(dir))),
+                        };
+                        match action {
+                            Some((target, action)) => self.toasts.push_with_action(
+                                toast::Kind::Info,
+                                title,
+                                trf("to {}", &[&target.display()]),
+                                "Show",
+                                action,
+                            ),
+                            // Trashing is one click away from being taken back.
+                            None if kind == JobKind::Trash => self.toasts.push_with_action(
+                                toast::Kind::Info,
+                                title,
+                                "",
+                                "Undo",
+                                Action::Undo,
+                            ),
+                            None => self.toasts.push(toast::Kind::Info, title, ""),
+                        }
+                        return list_volumes();
+                    }
+                    Some(jobs::Event::FollowUp(after, target)) => match after {
+                        AfterAction::Nothing => self.refresh(),
+                        AfterAction::OpenDestination => self.navigate(target),
+                        AfterAction::EjectDrive | AfterAction::ShutDown => {
+                            self.refresh();
+                            return Command::perform(
+                                task::blocking(move || {
+                                    match after {
+                                        AfterAction::EjectDrive => system::eject(&target),
+                                        _ => system::shut_down(),
+                                    }
+                                    .map_err(|error| error.to_string())
+                                }),
+                                move |result| Message::SystemActionDone(after, result),
+                            );
+                        }
+                    },
+                    Some(jobs::Event::Conflict(id, target)) => self.ask_conflict(id, &target),
+                    Some(jobs::Event::Failed(error)) => {
+                        self.prompts.push(Prompt::error(
+                            tr("File operation failed"),
+                            error,
+                            Answer::Dismiss,
+                        ));
+                        self.refresh();
+                    }
+                    None => {}
+                }
+            }
+            Message::Toast(message) => match self.toasts.update(message) {
+                Some(Action::Open(dir)) => self.navigate(dir),
+                Some(Action::Reveal(paths)) => self.reveal(&paths),
+                Some(Action::Undo) => return self.update(Message::Undo),
+                None => {}
+            },
+            Message::CopyPath => {
+                let paths = match &self.selected[..] {
+                    [] => vec![self.current_dir.clone()],
+                    selected => selected.to_vec(),
+                };
+                let list: Vec<String> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                let title = match &paths[..] {
+                    [_] => "Path copied".to_string(),
+                    paths => format!("{} paths copied", format_count(paths.len())),
+                };
+                self.toasts.push(toast::Kind::Info, title, list[0].clone());
+                return clipboard::write(list.join("\n"));
+            }
+            Message::CopySummary(checksums) => {
+                let paths = self.selected.clone();
+                return Command::perform(
+                    task::blocking(move || summary::markdown(&paths, checksums)),
+                    Message::SummaryReady,
+                );
+            }
+            Message::SummaryReady(summary) => {
+                self.toasts.push(
+                    toast::Kind::Info,
+                    "Summary copied",
+                    "Paste it as Markdown, or as it is.",
+                );
+                return clipboard::write(summary);
+            }
+            Message::Prompt(message) => match self.prompts.update(message) {
+                Some(Answer::Delete(sources)) => {
+                    self.selected.retain(|selected| !sources.contains(selected));
+                    self.jobs
+                        .push(JobKind::Delete, sources, None, self.job_options);
+                }
+                Some(Answer::Trash(sources)) => {
+                    self.selected.retain(|selected| !sources.contains(selected));
+                    self.jobs
+                        .push(JobKind::Trash, sources, None, self.job_options);
+                }
+                Some(Answer::Move(sources, dir)) => {
+                    self.jobs
+                        .push(JobKind::Move, sources, Some(dir), self.job_options);
+                    self.job_options = self.config.job_options();
+                }
+                Some(Answer::Replace(paths)) => self.choose(paths),
+                Some(Answer::CreateFolder(dir, name)) => match fs::create_dir_all(&dir) {
+                    Ok(()) => self.save_in(dir, name),
+                    Err(error) => self
+                        .toasts
+                        .error(trf("Cannot create {}", &[&dir.display()]), error),
+                },
+                Some(Answer::LeaveProject(path)) => {
+                    self.project = None;
+                    self.navigate(path);
+                }
+                Some(Answer::Go(dir)) => self.navigate(dir),
+                Some(Answer::Show(file)) => {
+                    if let Some(dir) = file.parent() {
+                        self.navigate(dir.to_path_buf());
+                        if self.current_dir == dir {
+                            self.selected = vec![file];
+                        }
+                    }
+                }
+                Some(Answer::Resolve(id, ConflictAction::Rename(name), _))
+                    if name.is_empty() || name.contains(['/', std::path::MAIN_SEPARATOR]) =>
+                {
+                    if let Some(target) = self.jobs.conflict(id).map(Path::to_path_buf) {
+                        self.ask_conflict(id, &target);
+                    }
+                }
+                Some(Answer::Resolve(id, action, apply_to_all)) => {
+                    return self.update(Message::Jobs(jobs::Message::Resolve(
+                        id,
+                        action,
+                        apply_to_all,
+                    )));
+                }
+                Some(Answer::CancelJob(id)) => {
+                    return self.update(Message::Jobs(jobs::Message::Cancel(id)))
+                }
+                Some(Answer::Transfer(kind, sources)) => {
+                    let dir = self.current_dir.clone();
+                    self.drop_into(kind, sources, dir);
+                }
+                Some(Answer::Install(dir, command)) => {
+                    self.package = None;
+                    self.shell = Some(Shell::run(dir, command));
+                }
+                Some(Answer::Dismiss) | None => {}
+            },
+            Message::Mounted(Ok(dir)) => {
+                self.search.update(search::Message::Clear);
+                self.navigate(dir);
+                return list_volumes();
+            }
+            Message::Mounted(Err((label, error))) => {
+                self.toasts.error(trf("Cannot mount {}", &[&label]), error);
+            }
+            Message::DevicesChanged => return list_volumes(),
+            Message::SystemActionDone(after, result) => match (after, result) {
+                (AfterAction::EjectDrive, Ok(())) => {
+                    self.toasts.push(
+                        toast::Kind::Info,
+                        "Drive ejected",
+                        "It is safe to unplug it now.",
+                    );
+                    return list_volumes();
+                }
+                (_, Ok(())) => {}
+                (after, Err(error)) => self.toasts.error(trf("{} failed", &[&after]), error),
+            },
+            Message::Watcher(watcher::Event::Changed(paths)) => {
+                let touched = |path: &Path| paths.iter().any(|changed| paths::same(changed, path));
+                if let Some(editor) = &mut self.editor {
+                    if touched(editor.path()) {
+                        editor.check_disk();
+                    }
+                }
+                let renaming = self
+                    .new_entry
+                    .as_ref()
+                    .and_then(|entry| entry.kind.renaming());
+                if let Some(original) = renaming.filter(|original| touched(original)) {
+                    if fs::symlink_metadata(original).is_err() {
+                        self.toasts.error(
+                            trf("Cannot rename “{}”", &[&display_name(original)]),
+                            "Another program moved or removed it",
+                        );
+                        self.new_entry = None;
+                    }
+                }
+
+                for path in paths {
+                    self.folder_sizes.forget(&path);
+                    self.file_icons.forget(&path);
+                    self.thumbnails.forget(&path);
+                    self.sidecars.forget(&path);
+                    self.selection_size.forget();
+                    self.sync_entry(path);
+                }
+                self.sort();
+                // Another program may have taken the name being typed.
+                if let Some(entry) = &self.new_entry {
+                    return self.update(Message::NewEntryInput(entry.name.clone()));
+                }
+            }
+            Message::Watcher(watcher::Event::Unavailable) => {}
+            // Fullscreen is not the size or place to come back to.
+            Message::Resized(_, width, height) if !self.fullscreen => {
+                self.config.window_size = Some((width as f32, height as f32))
+            }
+            Message::Moved(_, x, y) if !self.fullscreen => {
+                self.window_position = Some(Point::new(x as f32, y as f32))
+            }
+            Message::Resized(..) | Message::Moved(..) => {}
+            Message::CloseRequested(_) => {
+                self.save_session();
+                if cfg!(target_os = "linux") && self.clipboard.is_some() {
+                    return clipboard::read(Message::Closing);
+                }
+                return window::close(self.window);
+            }
+            Message::Closing(contents) => {
+                // Only what we put there is ours to keep alive.
+                let ours = self
+                    .clipboard
+                    .as_ref()
+                    .map(|(_, sources)| uri_list(sources));
+                if let Some(uris) = ours.filter(|uris| contents.as_ref() == Some(uris)) {
+                    if let Err(error) = system::hand_off_clipboard(&uris) {
+                        tracing::warn!(%error, "cannot hand off the clipboard");
+                    }
+                }
+                return window::close(self.window);
+            }
+            Message::Extract => {
+                let destination = match &self.archive {
+                    Some(archive) => archive.parent().unwrap_or(Path::new("/")).to_path_buf(),
+                    None => self.current_dir.clone(),
+                };
+                let sources = self.selected.clone();
+                self.jobs.push(
+                    JobKind::Extract,
+                    sources,
+                    Some(destination),
+                    self.job_options,
+                );
+            }
+            Message::ExtractTo => self.extract_to = Some(self.selected.clone()),
+            Message::ExtractInto => {
+                if let Some(sources) = self.extract_to.take() {
+                    let destination = self.current_dir.clone();
+                    self.jobs.push(
+                        JobKind::Extract,
+                        sources,
+                        Some(destination),
+                        self.job_options,
+                    );
+                }
+            }
+            Message::CancelExtractTo => self.extract_to = None,
+            Message::Diagnosed(listing, error, blocked) => {
+                if listing == self.listing {
+                    match blocked {
+                        Some(blocked) => self.blocked = Some(blocked),
+                        None => self
+                            .toasts
+                            .error(trf("Cannot open {}", &[&self.current_dir.display()]), error),
+                    }
+                }
+            }
+            Message::OpenAsAdministrator => {
+                if let Err(error) = system::open_as_administrator(&self.current_dir) {
+                    self.toasts.error(tr("Cannot open as administrator"), error);
+                }
+            }
+            Message::PickFromHost => {
+                return Command::perform(
+                    task::blocking(|| {
+                        sandbox::pick_from_host("Share files with iced-fm")
+                            .map_err(|error| error.to_string())
+                    }),
+                    Message::PickedFromHost,
+                );
+            }
+            Message::PickedFromHost(Ok(paths)) => {
+                if let Some(dir) = paths.first().and_then(|path| path.parent()) {
+                    self.navigate(dir.to_path_buf());
+                    if self.current_dir == dir {
+                        self.selected = paths;
+                    }
+                }
+            }
+            Message::PickedFromHost(Err(error)) => self
+                .toasts
+                .error(tr("Cannot ask the host for files"), error),
+            Message::PastePrimary => return clipboard::read_primary(Message::PrimaryPasted),
+            Message::PrimaryPasted(contents) => {
+                if let Some(contents) = contents {
+                    let contents = contents.trim_end_matches(['\r', '\n']);
+                    match paths::from_uri(contents) {
+                        Some(path) => self.path = path.to_string_lossy().to_string(),
+                        None => self.path.push_str(contents),
+                    }
+                    self.completions.update(&self.path, &self.current_dir);
+                    self.path_error = None;
+                    return text_input::move_cursor_to_end(address_bar());
+                }
+            }
+            Message::WindowFocused(_) => {
+                self.focused = true;
+                if !self.loading && self.listed_mtime != dir_mtime(&self.current_dir) {
+                    tracing::debug!(dir = %self.current_dir.display(), "listing stale after focus");
+                    self.refresh();
+                }
+                return list_volumes();
+            }
+            Message::HotFolders(message) => {
+                for arrival in self.hot_folders.update(message) {
+                    if let Err(error) = fs::create_dir_all(&arrival.to) {
+                        self.toasts
+                            .error(trf("Cannot create {}", &[&arrival.to.display()]), error);
+                        continue;
+                    }
+                    let kind = match arrival.action {
+                        hot_folders::Action::Move => JobKind::Move,
+                        hot_folders::Action::Copy => JobKind::Copy,
+                    };
+                    let options = jobs::Options {
+                        keep_both: true,
+                        ..self.job_options
+                    };
+                    self.jobs
+                        .push(kind, arrival.files, Some(arrival.to), options);
+                }
+            }
+            Message::Indexed(count) => tracing::debug!(count, "index written"),
+            Message::WindowUnfocused(_) => self.focused = false,
+            Message::NewWindow => self.new_window = Some(self.current_dir.clone()),
+            Message::OpenInNewWindow => self.new_window = self.selected.first().cloned(),
+            Message::ReopenClosed(index) => self.reopen = Some(index),
+            Message::Back => {
+                if let Some(dir) = self.history.back() {
+                    self.go(dir.to_path_buf(), Step::Back);
+                }
+            }
+            Message::Forward => {
+                if let Some(dir) = self.history.forward() {
+                    self.go(dir.to_path_buf(), Step::Forward);
+                }
+            }
+            Message::Sort => {}
+            Message::Menu(message) => {
+                if let Some(message) = self.menu_bar.update(message) {
+                    return self.update(message);
+                }
+            }
+            Message::Shortcut(shortcut) => {
+                if let Some(command) = self.press(shortcut) {
+                    return command;
+                }
+            }
+            Message::Typed(c) => {
+                return match self.press(Shortcut::typed(c)) {
+                    Some(command) => command,
+                    None => self.update(Message::TypeAhead(type_ahead::Message::Typed(c))),
+                };
+            }
+            Message::OpenPalette => {
+                self.palette = Some(Palette::default());
+                return text_input::focus(palette::input());
+            }
+            Message::OpenJump => {
+                self.palette = Some(Palette::new(palette::Kind::Places));
+                return text_input::focus(palette::input());
+            }
+            Message::Palette(message) => {
+                let commands = self.palette_commands();
+                if let Some(dialog) = &mut self.palette {
+                    match dialog.update(message, &commands) {
+                        Some(palette::Event::Run(message)) => {
+                            self.palette = None;
+                            return self.update(message);
+                        }
+                        Some(palette::Event::Closed) => self.palette = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::OpenSettings => self.settings = Some(Settings),
+            Message::ToggleTheme => {
+                self.config.theme = match is_dark(&self.config.theme) {
+                    true => Theme::Light,
+                    false => Theme::Dark,
+                };
+                self.save_config();
+            }
+            Message::Settings(message) => {
+                if let Some(dialog) = &mut self.settings {
+                    match dialog.update(message) {
+                        Some(settings::Event::Changed(setting)) => return self.change(setting),
+                        Some(settings::Event::Closed) => self.settings = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::ShowShortcuts => self.bindings = Some(Bindings::open(self.config.keymap)),
+            Message::Bindings(message) => {
+                if let Some(dialog) = &mut self.bindings {
+                    match dialog.update(message) {
+                        Some(bindings::Event::Preset(preset)) => {
+                            self.config.keymap = preset;
+                            self.keymap = Keymap::new(preset, &self.config.keys);
+                            self.save_config();
+                        }
+                        Some(bindings::Event::Closed) => self.bindings = None,
+                        None => {}
+                    }
+                }
+            }
+            Message::About => self.prompts.push(
+                Prompt::new(
+                    Tone::Question,
+                    trf("iced-fm {}", &[&env!("CARGO_PKG_VERSION")]),
+                    tr("A file browser built with iced."),
+                )
+                .button(tr("Close"), Role::Primary, Answer::Dismiss)
+                .dismiss(Answer::Dismiss),
+            ),
+        };
+
+        Command::none()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            self.jobs.subscription().map(Message::Jobs),
+            self.search.subscription().map(Message::Search),
+            self.toasts.subscription().map(Message::Toast),
+            udisks::watch().map(|()| Message::DevicesChanged),
+            self.type_ahead.subscription().map(Message::TypeAhead),
+            event::listen_with(|event, status| match event {
+                Event::Window(id, window::Event::Focused) => Some(Message::WindowFocused(id)),
+                Event::Window(id, window::Event::Unfocused) => Some(Message::WindowUnfocused(id)),
+                Event::Window(id, window::Event::Resized { width, height }) => {
+                    Some(Message::Resized(id, width, height))
+                }
+                Event::Window(id, window::Event::Moved { x, y }) => Some(Message::Moved(id, x, y)),
+                Event::Window(id, window::Event::CloseRequested) => {
+                    Some(Message::CloseRequested(id))
+                }
+                Event::Window(id, window::Event::FileDropped(path)) => {
+                    Some(Message::FileDropped(id, path))
+                }
+                // Comes after any drop the widgets saw on the same release.
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    Some(Message::DragEnd)
+                }
+                Event::Mouse(mouse::Event::CursorLeft) => Some(Message::DragOut),
+                Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key,
+                    modifiers,
+                    text,
+                    ..
+                }) => {
+                    // Keys a text field took are typing, not accelerators.
+                    let shortcut = Shortcut::pressed(&key, modifiers)
+                        .filter(|_| status == iced::event::Status::Ignored);
+                    if let Some(shortcut) = shortcut {
+                        return Some(Message::Shortcut(shortcut));
+                    }
+                    let typed = text
+                        .and_then(|text| text.chars().next())
+                        .filter(|c| !c.is_control() && status == iced::event::Status::Ignored);
+                    if let Some(c) = typed {
+                        return Some(Message::Typed(c));
+                    }
+                    use keyboard::key::Named;
+
+                    let keyboard::Key::Named(named) = key else {
+                        return None;
+                    };
+                    // Up and Down are let through by the text fields, and
+                    // taken by the palette's modal for its list.
+                    let ignored = status == iced::event::Status::Ignored;
+                    match named {
+                        Named::ArrowDown | Named::ArrowUp => {
+                            let step = if named == Named::ArrowDown { 1 } else { -1 };
+                            Some(match ignored {
+                                true => Message::CompletionMove(step),
+                                false => Message::Palette(palette::Message::Move(step)),
+                            })
+                        }
+                        Named::Escape => Some(Message::Escape),
+                        _ if !ignored => None,
+                        Named::Tab => Some(Message::Tab(modifiers.shift())),
+                        Named::Enter => Some(Message::OpenSelected),
+                        Named::Home => Some(Message::SelectFirst),
+                        Named::End => Some(Message::SelectLast),
+                        Named::PageDown => Some(Message::MovePage(1)),
+                        Named::PageUp => Some(Message::MovePage(-1)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }),
+        ];
+        if !self.config.hide_folder_tree {
+            subscriptions.push(self.folder_tree.subscription().map(Message::FolderTree));
+        }
+        subscriptions.push(
+            self.folder_sizes
+                .subscription(self.config.folder_size, self.listing, self.listed_folders())
+                .map(Message::FolderSizes),
+        );
+        if !self.config.low_memory {
+            subscriptions.push(
+                self.file_icons
+                    .subscription(&self.style.icons, self.files_in_view())
+                    .map(Message::FileIcons),
+            );
+            subscriptions.push(
+                self.thumbnails
+                    .subscription(self.files_in_view())
+                    .map(Message::Thumbnails),
+            );
+        }
+        subscriptions.push(self.git.subscription(self.listing).map(Message::Git));
+        subscriptions.push(
+            self.sidecars
+                .subscription(self.listing)
+                .map(Message::Sidecars),
+        );
+        if !self.selected.is_empty() {
+            subscriptions.push(
+                self.selection_size
+                    .subscription(
+                        selection_size::fingerprint(&self.selected),
+                        self.selection_items(),
+                    )
+                    .map(Message::SelectionSize),
+            );
+        }
+        if let Some(login) = &self.login {
+            subscriptions.push(login.subscription().map(Message::Login));
+        }
+        if let Some(dialog) = &self.recode {
+            subscriptions.push(dialog.subscription().map(Message::Recode));
+        }
+        if let Some(dialog) = &self.image_summary {
+            subscriptions.push(dialog.subscription().map(Message::ImageSummary));
+        }
+        if let Some(dialog) = &self.package {
+            subscriptions.push(dialog.subscription().map(Message::Package));
+        }
+        if let Some(dialog) = &self.torrent {
+            subscriptions.push(dialog.subscription().map(Message::Torrent));
+        }
+        if let Some(dialog) = &self.shell {
+            subscriptions.push(dialog.subscription().map(Message::Shell));
+        }
+        if let Some(dialog) = &self.newlines {
+            subscriptions.push(dialog.subscription().map(Message::Newlines));
+        }
+        subscriptions.push(self.transfers.subscription().map(Message::Transfers));
+        if let Some(usage) = &self.usage {
+            subscriptions.push(usage.subscription().map(Message::Usage));
+        }
+        if let Some(finder) = &self.duplicates {
+            subscriptions.push(finder.subscription().map(Message::Duplicates));
+        }
+        if let Some(compare) = &self.compare {
+            subscriptions.push(compare.subscription().map(Message::Compare));
+        }
+        if self.shows_columns() {
+            subscriptions.push(
+                self.columns
+                    .subscription(
+                        &self.current_dir,
+                        self.selected_file(),
+                        self.config.low_memory,
+                        &highlight::theme_name(
+                            self.style.syntax_theme.as_deref(),
+                            is_dark(&self.config.theme),
+                        ),
+                    )
+                    .map(Message::Columns),
+            );
+        }
+        if !self.config.low_memory {
+            let limits = self.limits();
+            subscriptions.push(
+                self.prefetch
+                    .subscription(&self.folders_in_view(), move |dir| read_ahead(dir, limits))
+                    .map(Message::Prefetch),
+            );
+        }
+        if self.loading {
+            subscriptions.push(self.listing_subscription());
+        } else if !paths::is_computer(&self.current_dir) && !paths::is_network(&self.current_dir) {
+            subscriptions.push(watcher::watch(self.current_dir.clone()).map(Message::Watcher));
+        }
+        if self.background {
+            subscriptions.push(self.hot_folders.subscription().map(Message::HotFolders));
+            if !self.config.low_memory {
+                subscriptions
+                    .push(indexer::subscription(self.config.index_folders()).map(Message::Indexed));
+            }
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let mut content = column!(self.menu_bar.view(self.menus()).map(Message::Menu));
+        if self.editing_path {
+            let adress_bar = text_input(tr("Path: "), &self.path)
+                .id(address_bar())
+                .on_input(Message::PathInput)
+                .on_submit(Message::PathChange)
+                .padding(10);
+            // Middle-click pastes the primary selection, as X11 and Wayland
+            // text fields do.
+            content = content.push(mouse_area(adress_bar).on_middle_press(Message::PastePrimary));
+            if let Some(error) = &self.path_error {
+                content = content.push(
+                    text(error)
+                        .size(self.style.small_text_size)
+                        .style(theme::Text::Color(self.style.error)),
+                );
+            }
+        } else {
+            let crumbs = breadcrumb::view(
+                &self.current_dir,
+                self.project.as_deref(),
+                Message::Navigate,
+                Message::EditPath,
+            );
+            if self.config.recent_projects.is_empty() {
+                content = content.push(crumbs);
+            } else {
+                let projects: Vec<Project> = self
+                    .config
+                    .recent_projects
+                    .iter()
+                    .cloned()
+                    .map(Project)
+                    .collect();
+                content = content.push(
+                    row!(
+                        pick_list(
+                            projects,
+                            self.project.clone().map(Project),
+                            Message::OpenProject
+                        )
+                        .placeholder("Projects"),
+                        crumbs
+                    )
+                    .spacing(5)
+                    .align_items(Alignment::Center),
+                );
+            }
+        }
+        if let Some(warning) = self.sandbox_warning() {
+            content = content.push(warning);
+        }
+        if let Some(panel) = self.access_panel() {
+            content = content.push(panel);
+        }
+        if !self.completions.is_empty() {
+            content = content.push(
+                self.completions
+                    .view()
+                    .map(|index| Message::CompletionAccept(Some(index))),
+            );
+        }
+        if self.shows(Panel::Toolbar) {
+            content = content.push(self.toolbar());
+        }
+        content = content.push(self.find_bar());
+
+        content = content.push(self.search.bar().map(Message::Search));
+
+        if self.search.is_active() {
+            content = content.push(self.search.view().map(Message::Search));
+        } else {
+            let mut row = row!(
+                text("").width(Length::Fixed(30.)),
+                text(tr("Name")).width(Length::FillPortion(2)),
+                text(tr("Size")).width(Length::FillPortion(1)),
+                text(tr("Modified")).width(Length::FillPortion(1))
+            )
+            .height(self.style.row_height);
+            for column in self.extensions.columns() {
+                row = row.push(text(column.title()).width(Length::FillPortion(1)));
+            }
+            if self.shows_columns() {
+                let mut columns = row!().spacing(10).height(Length::Fill);
+                for column in self.columns.ancestors(&self.current_dir, &self.style) {
+                    columns = columns.push(column.map(Message::Columns));
+                }
+                columns = columns.push(column!(
+                    button(row).on_press(Message::Sort),
+                    self.list_dir()
+                ));
+                if let Some(path) = self.selected_file() {
+                    columns = columns.push(
+                        self.columns
+                            .preview(path, &self.style)
+                            .map(Message::Columns),
+                    );
+                }
+                content = content.push(columns);
+            } else {
+                let header = button(row).on_press(Message::Sort);
+                content = content.push(header);
+                content = content.push(self.list_dir());
+            }
+
+            if let Some(sources) = &self.dragging {
+                content = content.push(
+                    text(trf("Dragging {}: let go on a folder or place, holding Ctrl to copy or Shift to move", &[&match sources.as_slice() {
+                            [source] => format!("“{}”", display_name(source)),
+                            sources => format!("{} items", format_count(sources.len())),
+                        }]))
+                    .size(self.style.small_text_size),
+                );
+            } else if let Some(notice) = self.listing_notice() {
+                content = content.push(text(notice).size(self.style.small_text_size));
+            }
+        }
+
+        if let Some(dialog) = &self.dialog {
+            let ready = dialog.choice(&self.current_dir, &self.selected).is_some();
+            content = content.push(dialog.view(ready).map(Message::Dialog));
+        }
+
+        if !self.jobs.is_empty() {
+            content = content.push(self.jobs.view().map(Message::Jobs));
+        }
+        if self.shows(Panel::StatusBar) {
+            content = content.push(self.status_bar());
+        }
+
+        let mut body = row!().spacing(20);
+        if self.shows(Panel::Sidebar) {
+            let remotes = self.remotes();
+            let recent = self.recent.latest(RECENT_SHOWN);
+            let mut sidebar = column!(self
+                .sidebar
+                .view(&remotes, &recent, self.drop_highlight())
+                .map(Message::Sidebar))
+            .spacing(20);
+            if !self.config.hide_folder_tree {
+                sidebar = sidebar.push(self.folder_tree.view().map(Message::FolderTree));
+            }
+            body = body.push(sidebar);
+        }
+        let base: Element<'_, Message> = Container::new(body.push(content)).padding(20).into();
+        let base = match self.toasts.view(&self.style) {
+            Some(toasts) => Modal::new(base, toasts.map(Message::Toast))
+                .floating()
+                .into(),
+            None => base,
+        };
+
+        if let Some(prompt) = self.prompts.view(&self.style) {
+            let mut modal = Modal::new(base, prompt.map(Message::Prompt))
+                .on_key(|key, modifiers| self.prompts.on_key(key, modifiers).map(Message::Prompt));
+            if let Some(dismiss) = self.prompts.on_blur() {
+                modal = modal.on_blur(Message::Prompt(dismiss));
+            }
+            modal.into()
+        } else if let Some(viewer) = self.columns.viewer() {
+            Modal::new(base, viewer.map(Message::Columns))
+                .on_blur(Message::Columns(columns::Message::View(false)))
+                .into()
+        } else if let Some(login) = &self.login {
+            Modal::new(base, login.view(&self.style).map(Message::Login))
+                .on_blur(Message::Login(login::Message::Cancel))
+                .into()
+        } else if let Some(dialog) = &self.palette {
+            Modal::new(
+                base,
+                dialog
+                    .view(&self.palette_commands(), &self.style)
+                    .map(Message::Palette),
+            )
+            .on_blur(Message::Palette(palette::Message::Close))
+            .into()
+        } else if let Some(editor) = &self.editor {
+            Modal::new(base, editor.view().map(Message::Editor)).into()
+        } else if let Some(usage) = &self.usage {
+            Modal::new(base, usage.view(&self.style).map(Message::Usage))
+                .on_blur(Message::Usage(usage::Message::Close))
+                .into()
+        } else if let Some(finder) = &self.duplicates {
+            Modal::new(base, finder.view(&self.style).map(Message::Duplicates))
+                .on_blur(Message::Duplicates(duplicates::Message::Close))
+                .into()
+        } else if let Some(compare) = &self.compare {
+            Modal::new(base, compare.view(&self.style).map(Message::Compare))
+                .on_blur(Message::Compare(compare::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.conversion {
+            Modal::new(base, dialog.view(&self.style).map(Message::Conversion))
+                .on_blur(Message::Conversion(conversion::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.accounts {
+            Modal::new(base, dialog.view(&self.style).map(Message::Accounts))
+                .on_blur(Message::Accounts(accounts::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.settings {
+            Modal::new(
+                base,
+                dialog
+                    .view(&self.config, &self.style)
+                    .map(Message::Settings),
+            )
+            .on_blur(Message::Settings(settings::Message::Close))
+            .into()
+        } else if let Some(dialog) = &self.bindings {
+            Modal::new(
+                base,
+                dialog
+                    .view(&self.keymap, &self.style)
+                    .map(Message::Bindings),
+            )
+            .on_blur(Message::Bindings(bindings::Message::Close))
+            .into()
+        } else if let Some(dialog) = &self.package {
+            Modal::new(base, dialog.view(&self.style).map(Message::Package))
+                .on_blur(Message::Package(package::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.torrent {
+            Modal::new(base, dialog.view(&self.style).map(Message::Torrent))
+                .on_blur(Message::Torrent(torrent::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.image_summary {
+            Modal::new(base, dialog.view(&self.style).map(Message::ImageSummary))
+                .on_blur(Message::ImageSummary(oci::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.launch {
+            Modal::new(base, dialog.view(&self.style).map(Message::Launch))
+                .on_blur(Message::Launch(launch::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.shell {
+            Modal::new(base, dialog.view(&self.style).map(Message::Shell))
+                .on_blur(Message::Shell(shell::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.newlines {
+            Modal::new(base, dialog.view(&self.style).map(Message::Newlines))
+                .on_blur(Message::Newlines(newlines::Message::Close))
+                .into()
+        } else if let Some(editor) = &self.tag_editor {
+            Modal::new(base, editor.view(&self.style).map(Message::Tags))
+                .on_blur(Message::Tags(tags::Message::Cancel))
+                .into()
+        } else if let Some(dialog) = &self.recode {
+            Modal::new(base, dialog.view(&self.style).map(Message::Recode))
+                .on_blur(Message::Recode(recode::Message::Close))
+                .into()
+        } else if let Some(checksums) = &self.checksums {
+            Modal::new(base, checksums.view(&self.style).map(Message::Checksums))
+                .on_blur(Message::Checksums(checksum::Message::Close))
+                .into()
+        } else if let Some(dialog) = &self.properties {
+            Modal::new(base, dialog.view(&self.style).map(Message::Properties))
+                .on_blur(Message::Properties(properties::Message::Close))
+                .into()
+        } else {
+            base
+        }
+    }
+
+    /// The remote places in the sidebar.
+    fn remotes(&self) -> Vec<PathBuf> {
+        self.config
+            .webdav
+            .iter()
+            .filter_map(webdav::Account::root)
+            .chain(self.config.remote_bookmarks.iter().cloned())
+            .collect()
+    }
+
+    /// Asks what job `id` is to do about `target` being taken.
+    fn ask_conflict(&mut self, id: usize, target: &Path) {
+        let name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.prompts.push(
+            Prompt::new(
+                Tone::Question,
+                tr("File already exists"),
+                trf(
+                    "{} already exists in the destination.",
+                    &[&target.display()],
+                ),
+            )
+            .input(tr("New name"), name, |answer, name| match answer {
+                Answer::Resolve(id, ConflictAction::Rename(_), all) => {
+                    Answer::Resolve(id, ConflictAction::Rename(name), all)
+                }
+                answer => answer,
+            })
+            .check(
+                tr("Apply to all conflicts in this job"),
+                false,
+                |answer, all| match answer {
+                    Answer::Resolve(id, action, _) => Answer::Resolve(id, action, all),
+                    answer => answer,
+                },
+            )
+            .button(
+                tr("Rename"),
+                Role::Normal,
+                Answer::Resolve(id, ConflictAction::Rename(String::new()), false),
+            )
+            .button(
+                tr("Skip"),
+                Role::Normal,
+                Answer::Resolve(id, ConflictAction::Skip, false),
+            )
+            .button(
+                tr("Overwrite"),
+                Role::Destructive,
+                Answer::Resolve(id, ConflictAction::Overwrite, false),
+            )
+            .button(
+                tr("Keep both"),
+                Role::Primary,
+                Answer::Resolve(id, ConflictAction::KeepBoth, false),
+            )
+            .button(tr("Cancel job"), Role::Normal, Answer::CancelJob(id)),
+        );
+    }
+
+    /// Has the tags of what was moved go with it.
+    fn tags_follow(&mut self, moves: &[(PathBuf, PathBuf)]) {
+        for (from, to) in moves {
+            if let Err(error) = self.tags.moved(from, to) {
+                self.toasts
+                    .error(tr("Cannot save the tags"), error.to_string());
+                return;
+            }
+        }
+    }
+
+    /// Whether a prompt or dialog is over the picker, as `view` lays one on.
+    fn is_modal(&self) -> bool {
+        self.prompts.is_open()
+            || self.columns.is_viewing()
+            || self.login.is_some()
+            || self.palette.is_some()
+            || self.editor.is_some()
+            || self.usage.is_some()
+            || self.duplicates.is_some()
+            || self.compare.is_some()
+            || self.conversion.is_some()
+            || self.accounts.is_some()
+            || self.settings.is_some()
+            || self.bindings.is_some()
+            || self.package.is_some()
+            || self.torrent.is_some()
+            || self.image_summary.is_some()
+            || self.launch.is_some()
+            || self.shell.is_some()
+            || self.newlines.is_some()
+            || self.recode.is_some()
+            || self.tag_editor.is_some()
+            || self.checksums.is_some()
+            || self.properties.is_some()
+    }
+
+    fn show_checksums(&mut self, paths: Vec<PathBuf>) -> Command<Message> {
+        let checksums = Checksums::open(paths);
+        let commands: Vec<_> = checksums
+            .pending()
+            .map(|(index, path, stop)| {
+                Command::perform(
+                    task::blocking(move || {
+                        let Some(_turn) =
+                            devices::wait_for(&[&path], || stop.load(Ordering::Relaxed))
+                        else {
+                            return Err(tr("Cancelled").to_string());
+                        };
+                        checksum::compute(&path, &stop)
+                    }),
+                    move |result| Message::Checksums(checksum::Message::Calculated(index, result)),
+                )
+            })
+            .collect();
+        self.checksums = Some(checksums);
+
+        Command::batch(commands)
+    }
+
+    fn choose(&mut self, paths: Vec<PathBuf>) {
+        if paths.iter().any(|path| !vfs::is_local(path)) {
+            match self.dialog.as_ref().map(Dialog::mode) {
+                // Handed over once copied to this machine.
+                Some(DialogMode::OpenFile | DialogMode::OpenFiles) => self.transfers.push(paths),
+                _ => self.toasts.error(
+                    tr("Cannot choose a remote location"),
+                    "Only files on other machines can be chosen, copied here first.",
+                ),
+            }
+            return;
+        }
+        tracing::info!(?paths, "dialog confirmed");
+        let chosen = paths.into_iter().map(Selection::new).collect();
+        self.outcome = Some(Outcome::Chosen(chosen));
+    }
+
+    fn edit(&mut self, path: PathBuf) {
+        match Editor::open(path.clone()) {
+            Ok(editor) => self.editor = Some(editor),
+            Err(error) => {
+                tracing::warn!(path = %path.display(), error, "cannot edit");
+                self.toasts
+                    .error(trf("Cannot edit {}", &[&path.display()]), error);
+            }
+        }
+    }
+
+    /// Applies a setting from the Settings dialog, saving it at once.
+    fn change(&mut self, setting: Setting) -> Command<Message> {
+        match setting {
+            Setting::Theme(theme) => self.config.theme = theme,
+            Setting::StripedRows(striped) => self.config.striped_rows = striped,
+            Setting::ViewMode(mode) => self.config.view_mode = mode,
+            Setting::SizeUnits(units) => {
+                self.config.size_units = units;
+                jobs::set_size_units(units);
+            }
+            Setting::TimeFormat(format) => self.config.time_format = format,
+            Setting::TimePattern(pattern) => self.config.time_pattern = pattern,
+            Setting::RelativeTimes(relative) => self.config.relative_times = relative,
+            Setting::ShowHidden(show) => {
+                self.config.hide_hidden = !show;
+                self.apply_filter();
+            }
+            Setting::ConfirmDelete(ask) => self.config.delete_without_asking = !ask,
+            Setting::AskOver(many) => self.config.ask_over = many,
+            Setting::ConfirmOverwrite(ask) => {
+                self.config.overwrite_without_asking = !ask;
+                self.job_options = self.config.job_options();
+            }
+            Setting::ConfirmSystem(ask) => self.config.system_without_asking = !ask,
+            Setting::Sort(sort) => self.config.sort = sort,
+            Setting::GroupBy(by) => self.config.group_by = by,
+            Setting::NameOrder(order) => {
+                self.config.name_order = order;
+                collation::set_order(order);
+                self.sort();
+            }
+            Setting::DoubleClick(double) => self.config.double_click = double,
+            Setting::Terminal(command) => return self.update(Message::TerminalCommand(command)),
+            Setting::Renderer(renderer) => self.config.renderer = renderer,
+            Setting::Antialiasing(smooth) => self.config.antialiasing = smooth,
+            Setting::MaxFps(fps) => {
+                self.config.max_fps = fps;
+                rendering::set_max_fps(fps);
+            }
+        }
+        dates::set(
+            self.config.time_format,
+            &self.config.time_pattern,
+            self.config.relative_times,
+        );
+        self.show(self.config.view(&self.current_dir));
+        self.save_config();
+
+        Command::none()
+    }
+
+    /// The iced theme the picker is drawn with, as chosen in the settings.
+    pub fn theme(&self) -> Theme {
+        self.config.theme.clone()
+    }
+
+    /// Shows the current folder as `view` from now on.
+    fn set_view(&mut self, view: View) {
+        self.config.set_view(&self.current_dir, view);
+        self.save_config();
+        self.show(view);
+    }
+
+    fn show(&mut self, view: View) {
+        let order = (self.view.sort, self.view.group_by);
+        self.view = view;
+        if (view.sort, view.group_by) != order {
+            self.sort();
+        }
+    }
+
+    fn save_config(&self) {
+        if let Err(error) = self.config.save() {
+            tracing::warn!(%error, "cannot save the configuration");
+        }
+    }
+
+    fn navigate(&mut self, path: PathBuf) {
+        self.go(path, Step::Visit);
+    }
+
+    /// Shows the folder at `path`, reached by `step`.
+    fn go(&mut self, path: PathBuf, step: Step) {
+        let path = normalize::for_navigation(&path, self.config.symlinks);
+        if !paths::is_computer(&path) && !self.restrictions.may_enter(&path) {
+            tracing::info!(path = %path.display(), "navigation vetoed");
+            self.toasts.error(
+                trf("Cannot open {}", &[&path.display()]),
+                "It is not available here",
+            );
+            return;
+        }
+        if let Some(project) = self
+            .project
+            .as_ref()
+            .filter(|project| !paths::starts_with(&path, project))
+        {
+            self.prompts.push(
+                Prompt::new(
+                    Tone::Question,
+                    trf("Leave the project \"{}\"?", &[&display_name(project)]),
+                    trf("{} is outside of it.", &[&paths::display(&path)]),
+                )
+                .button(tr("Stay"), Role::Normal, Answer::Dismiss)
+                .button(
+                    tr("Leave project"),
+                    Role::Primary,
+                    Answer::LeaveProject(path),
+                )
+                .dismiss(Answer::Dismiss),
+            );
+            return;
+        }
+        if let Some(location) = remote::Location::parse(&path) {
+            if !self.connected.contains(&location.host()) {
+                self.login = Some(Login::open(location, path));
+                return;
+            }
+        }
+        tracing::info!(path = %path.display(), "navigate");
+        if !paths::is_computer(&path) {
+            self.recent.visit(&path);
+        }
+        if path != self.current_dir {
+            self.history.went(&self.current_dir, step);
+        }
+        self.path = paths::display(&path);
+        self.current_dir = path;
+        self.filter.clear();
+        self.selected.clear();
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.archive = archive::split(&self.current_dir).map(|(archive, _)| archive);
+        self.folder_tree.reveal(&self.current_dir);
+        self.columns.follow(&self.current_dir);
+        self.git.follow(&self.current_dir);
+        let local =
+            vfs::is_disk_folder(&self.current_dir) && !paths::is_computer(&self.current_dir);
+        self.sidecars
+            .follow(Some(self.current_dir.as_path()).filter(|_| local));
+        self.view = self.config.view(&self.current_dir);
+        self.blocked = None;
+        self.listing += 1;
+        self.listed_mtime = dir_mtime(&self.current_dir);
+        // The same folder again is merged into what is shown, so the rows
+        // stay put while it is read.
+        let again = self.listed_dir == self.current_dir && !paths::is_computer(&self.current_dir);
+        self.listed_dir = self.current_dir.clone();
+        self.relisted = again.then(Vec::new);
+        if !again {
+            self.content.clear();
+            self.visible.clear();
+            self.matching = 0;
+            self.current_match = None;
+        }
+
+        if paths::is_computer(&self.current_dir) {
+            self.loading = false;
+            let drives = self
+                .sidebar
+                .volumes()
+                .iter()
+                .map(|volume| {
+                    Content::Directory(ContentData {
+                        path: volume.mount_point.clone(),
+                        name: OsString::from(format!(
+                            "{} — {}, {} free of {}",
+                            volume.label,
+                            volume.kind,
+                            format_bytes(volume.free),
+                            format_bytes(volume.total)
+                        )),
+                        size: volume.total,
+                        ..ContentData::default()
+                    })
+                })
+                .collect();
+            self.append(drives);
+            return;
+        }
+
+        let remote = paths::is_network(&self.current_dir);
+        let parent = match self.current_dir.parent() {
+            Some(parent) if !vfs::is_disk_folder(parent) => Ok(ContentData {
+                is_parent: true,
+                path: parent.to_path_buf(),
+                name: OsString::from(".."),
+                ..ContentData::default()
+            }),
+            // The top of a remote machine is its own parent, as `/` is.
+            Some(_) if remote => Ok(ContentData {
+                is_parent: true,
+                path: self.current_dir.clone(),
+                name: OsString::from(".."),
+                ..ContentData::default()
+            }),
+            Some(parent) => ContentData::new(parent.to_path_buf(), true, false),
+            // Above a drive or share root is the virtual Computer location.
+            None if cfg!(windows) => Ok(ContentData {
+                is_parent: true,
+                path: PathBuf::new(),
+                name: OsString::from(".."),
+                ..ContentData::default()
+            }),
+            None => ContentData::new(self.current_dir.clone(), true, false),
+        };
+        let parent = match parent {
+            Ok(data) => Content::Directory(data),
+            Err(error) => Content::Corrupt(error.to_string()),
+        };
+        self.loading = true;
+        match &mut self.relisted {
+            Some(relisted) => relisted.push(parent),
+            None => self.append(vec![parent]),
+        }
+        if let Some(entries) = self.prefetch.take(&self.current_dir) {
+            self.loading = false;
+            match self.relisted.take() {
+                Some(mut relisted) => {
+                    relisted.extend(entries);
+                    self.merge(relisted);
+                }
+                None => self.append(entries),
+            }
+        }
+    }
+
+    /// Brings the listing in line with `relisted`, all the folder holds
+    /// now, row by row: the rows still there are updated where they are,
+    /// so the selection, the scroll and a name being typed stay as they
+    /// were.
+    fn merge(&mut self, relisted: Vec<Content>) {
+        let key = |content: &Content| {
+            content
+                .data()
+                .map(|data| (data.is_parent, data.path.clone()))
+        };
+        let mut fresh: HashMap<(bool, PathBuf), Content> = HashMap::new();
+        let mut unreadable = Vec::new();
+        for content in self.admit(relisted) {
+            match key(&content) {
+                Some(key) => {
+                    fresh.insert(key, content);
+                }
+                None => unreadable.push(content),
+            }
+        }
+
+        let before = self.content.len();
+        self.content.retain_mut(
+            |content| match key(content).and_then(|key| fresh.remove(&key)) {
+                Some(current) => {
+                    *content = current;
+                    true
+                }
+                None => false,
+            },
+        );
+        let (kept, inserted) = (self.content.len(), fresh.len() + unreadable.len());
+        self.content.extend(fresh.into_values());
+        self.content.extend(unreadable);
+        let listed: HashSet<&Path> = self
+            .content
+            .iter()
+            .filter_map(|content| content.data().map(|data| data.path.as_path()))
+            .collect();
+        self.selected.retain(|path| listed.contains(path.as_path()));
+        tracing::debug!(kept, inserted, removed = before - kept, "listing merged");
+        self.sort();
+    }
+
+    /// Brings the row for `path` in line with the filesystem after a watcher
+    /// event: inserted if new, refreshed if changed, dropped if gone.
+    fn sync_entry(&mut self, path: PathBuf) {
+        if !path
+            .parent()
+            .is_some_and(|parent| paths::same(parent, &self.current_dir))
+        {
+            return;
+        }
+
+        let index = self.content.iter().position(|content| {
+            content
+                .data()
+                .is_some_and(|data| !data.is_parent && data.path == path)
+        });
+
+        match (index, fs::symlink_metadata(&path).is_ok()) {
+            (Some(index), true) => self.content[index] = content_from_path(path, self.limits()),
+            (Some(index), false) => {
+                self.content.remove(index);
+                self.selected.retain(|selected| selected != &path);
+            }
+            (None, true) => self.content.push(content_from_path(path, self.limits())),
+            (None, false) => {}
+        }
+    }
+
+    fn append(&mut self, batch: Vec<Content>) {
+        let admitted = self.admit(batch);
+        self.content.extend(admitted);
+        self.sort();
+    }
+
+    /// What of `batch` may be listed, the entries that could not be read
+    /// told of.
+    fn admit(&mut self, batch: Vec<Content>) -> Vec<Content> {
+        let mut errors = batch.iter().filter_map(|content| match content {
+            Content::Corrupt(error) => Some(error),
+            _ => None,
+        });
+        if let Some(first) = errors.next() {
+            let count = errors.count() + 1;
+            self.toasts.error(
+                trf("{} entries could not be read", &[&format_count(count)]),
+                first,
+            );
+        }
+
+        batch
+            .into_iter()
+            .filter(|content| match content {
+                Content::Corrupt(_) => true,
+                Content::Directory(data) if data.is_parent => {
+                    self.restrictions.may_enter(&data.path)
+                }
+                Content::File(data) | Content::Directory(data) => {
+                    self.restrictions.lists(&data.path)
+                }
+            })
+            .collect()
+    }
+
+    /// Orders the listing as the settings say, folders first, and shows it
+    /// filtered again.
+    fn sort(&mut self) {
+        let grouping = Grouping::new(self.view.group_by);
+        self.headings = rows::sort(&mut self.content, self.view.sort, &grouping);
+        self.apply_filter();
+    }
+
+    fn limits(&self) -> Limits {
+        Limits::new(self.config.low_memory)
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let limits = self.limits();
+        (self.visible, self.matching) = rows::filter(
+            &self.content,
+            &filter,
+            &self.config,
+            &self.tags,
+            limits.rendered,
+        );
+        self.current_match = None;
+    }
+
+    /// Goes to `dir` with `name` waiting in the save dialog's name field.
+    fn save_in(&mut self, dir: PathBuf, name: String) {
+        self.navigate(dir.clone());
+        if let Some(dialog) = self
+            .dialog
+            .as_mut()
+            .filter(|_| paths::same(&self.current_dir, &dir))
+        {
+            dialog.set_file_name(name);
+        }
+    }
+
+    /// How many entries are here and selected, and the room left on the
+    /// drive.
+    fn status_bar(&self) -> Element<'_, Message> {
+        let entries = self
+            .content
+            .iter()
+            .filter(|content| content.data().is_some_and(|data| !data.is_parent))
+            .count();
+        let status = match entries {
+            1 => String::from(tr("1 item")),
+            entries => trf("{} items", &[&format_count(entries)]),
+        };
+
+        let mut summary = row!(text(status).size(self.style.small_text_size))
+            .spacing(5)
+            .align_items(Alignment::Center);
+        if !self.selected.is_empty() {
+            let items = self.selection_items();
+            let known = items
+                .iter()
+                .map(|(_, size)| *size)
+                .sum::<Option<u64>>()
+                .map(|bytes| selection_size::Total {
+                    bytes,
+                    partial: false,
+                });
+            summary = summary.push(text("·").size(self.style.small_text_size));
+            summary = summary.push(
+                self.selection_size
+                    .view(
+                        selection_size::fingerprint(&self.selected),
+                        &format_count(self.selected.len()),
+                        known,
+                        &self.style,
+                    )
+                    .map(Message::SelectionSize),
+            );
+        }
+
+        let mut bar = row!(Container::new(summary).width(Length::Fill)).spacing(10);
+        if self.transfers.is_busy() {
+            bar = bar.push(text(tr("Transferring…")).size(self.style.small_text_size));
+        }
+        if let Some(branch) = self.git.branch() {
+            bar = bar.push(text(trf("On {}", &[&branch])).size(self.style.small_text_size));
+        }
+        if self.sidecars.count() > 0 {
+            let label = match self.sidecars.is_checking() {
+                true => String::from(tr("Checking checksums…")),
+                false => match self.sidecars.count() {
+                    1 => String::from(tr("1 checksum")),
+                    count => trf("{} checksums", &[&format_count(count)]),
+                },
+            };
+            bar = bar.push(text(label).size(self.style.small_text_size));
+            bar = bar.push(
+                button(text(tr("Verify all")).size(self.style.small_text_size))
+                    .padding([2, 8])
+                    .on_press_maybe(
+                        self.available(Message::Sidecars(sidecars::Message::VerifyAll)),
+                    ),
+            );
+        }
+        if let Some(volume) = self
+            .volume_of(&self.current_dir)
+            .filter(|volume| volume.total > 0)
+        {
+            bar = bar.push(
+                text(trf(
+                    "{} free of {}",
+                    &[&format_bytes(volume.free), &format_bytes(volume.total)],
+                ))
+                .size(self.style.small_text_size),
+            );
+        }
+
+        bar.into()
+    }
+
+    /// The selected files with their sizes, and the folders with theirs
+    /// where known already, or `None` to add them up. Drives and folders in
+    /// archives or on the network have the size they are listed with.
+    fn selection_items(&self) -> Vec<(PathBuf, Option<u64>)> {
+        let listed: HashMap<&Path, &Content> = self
+            .content
+            .iter()
+            .filter_map(|content| Some((content.data()?.path.as_path(), content)))
+            .collect();
+        let walkable =
+            vfs::is_disk_folder(&self.current_dir) && !paths::is_computer(&self.current_dir);
+        self.selected
+            .iter()
+            .map(|path| {
+                let size = match listed.get(path.as_path()) {
+                    Some(Content::File(data)) => Some(
+                        data.metadata
+                            .as_ref()
+                            .map_or(data.size, |metadata| metadata.len()),
+                    ),
+                    Some(Content::Directory(data)) if !walkable => Some(data.size),
+                    _ => self.folder_sizes.total(path),
+                };
+                (path.clone(), size)
+            })
+            .collect()
+    }
+
+    /// The folders in the finished listing, for the Size column. Drives
+    /// and archive members have theirs already.
+    fn listed_folders(&self) -> Vec<PathBuf> {
+        if self.loading || self.archive.is_some() || paths::is_computer(&self.current_dir) {
+            return Vec::new();
+        }
+        self.content
+            .iter()
+            .filter_map(|content| match content {
+                Content::Directory(data) if !data.is_parent => Some(data.path.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The folders listed from the top of the view down, on this disk.
+    fn folders_in_view(&self) -> Vec<PathBuf> {
+        if self.loading
+            || !vfs::is_disk_folder(&self.current_dir)
+            || paths::is_computer(&self.current_dir)
+        {
+            return Vec::new();
+        }
+        let top = (self.listing_scroll.0 / self.style.row_height) as usize;
+        self.visible
+            .iter()
+            .skip(top)
+            .filter_map(|&index| match &self.content[index] {
+                Content::Directory(data) if !data.is_parent => Some(data.path.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The files listed from the top of the view down, on this disk.
+    fn files_in_view(&self) -> Vec<PathBuf> {
+        if !vfs::is_disk_folder(&self.current_dir) {
+            return Vec::new();
+        }
+        let top = (self.listing_scroll.0 / self.style.row_height) as usize;
+        self.visible
+            .iter()
+            .skip(top)
+            .filter_map(|&index| match &self.content[index] {
+                Content::File(data) => Some(data.path.clone()),
+                _ => None,
+            })
+            .take(file_icons::AHEAD)
+            .collect()
+    }
+
+    /// Archives and the list of drives have no folders above to show.
+    fn shows_columns(&self) -> bool {
+        self.view.mode == ViewMode::Columns
+            && vfs::is_disk_folder(&self.current_dir)
+            && !paths::is_computer(&self.current_dir)
+    }
+
+    /// The one file selected, for the columns view's preview.
+    fn selected_file(&self) -> Option<&Path> {
+        let [path] = &self.selected[..] else {
+            return None;
+        };
+        self.content
+            .iter()
+            .any(|content| matches!(content, Content::File(data) if &data.path == path))
+            .then_some(path.as_path())
+    }
+
+    fn drop_highlight(&self) -> Option<Color> {
+        self.dragging.as_ref().map(|_| self.style.drop_target)
+    }
+
+    /// Ctrl copies and Shift moves; otherwise items stay on their drive
+    /// by being moved, and are copied to another one.
+    fn drop_kind(&self, sources: &[PathBuf], dir: &Path) -> JobKind {
+        if self.modifiers.command() {
+            JobKind::Copy
+        } else if self.modifiers.shift() || sources.iter().all(|source| same_volume(source, dir)) {
+            JobKind::Move
+        } else {
+            JobKind::Copy
+        }
+    }
+
+    fn drop_into(&mut self, kind: JobKind, mut sources: Vec<PathBuf>, dir: PathBuf) {
+        if paths::is_computer(&dir)
+            || vfs::for_dir(&dir).is_read_only()
+            || !self.restrictions.may_enter(&dir)
+            || self.restrictions.is_read_only()
+        {
+            return;
+        }
+        // Nothing goes into itself, and moving to where it already is does
+        // nothing.
+        sources.retain(|source| {
+            !paths::starts_with(&dir, source)
+                && (kind != JobKind::Move
+                    || source
+                        .parent()
+                        .is_none_or(|parent| !paths::same(parent, &dir)))
+        });
+        if sources.is_empty() {
+            return;
+        }
+
+        self.transfer(kind, sources, dir);
+    }
+
+    /// Starts copying or moving `sources` into `dir`, first asking when
+    /// system folders would be moved.
+    fn transfer(&mut self, kind: JobKind, sources: Vec<PathBuf>, dir: PathBuf) {
+        self.warn_dropped_streams(&sources, &dir);
+        let system = sources.iter().find(|source| paths::is_system(source));
+        if let Some(system) =
+            system.filter(|_| kind == JobKind::Move && !self.config.system_without_asking)
+        {
+            self.prompts.push(
+                Prompt::new(
+                    Tone::Warning,
+                    trf("Move {}?", &[&system.display()]),
+                    tr("It belongs to the system, which may stop working without it there."),
+                )
+                .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+                .button(tr("Move"), Role::Destructive, Answer::Move(sources, dir))
+                .dismiss(Answer::Dismiss),
+            );
+            return;
+        }
+        self.jobs.push(kind, sources, Some(dir), self.job_options);
+        self.job_options = self.config.job_options();
+    }
+
+    /// Why sending `sources` to the trash or deleting them is asked about
+    /// first, beyond that, if it is.
+    fn risk(&self, sources: &[PathBuf]) -> Option<String> {
+        let system = sources.iter().find(|source| paths::is_system(source));
+        if let Some(system) = system.filter(|_| !self.config.system_without_asking) {
+            return Some(trf(
+                "{} belongs to the system, which may stop working without it.",
+                &[&system.display()],
+            ));
+        }
+        let many = self.config.ask_over.0;
+        (many > 0 && sources.len() > many)
+            .then(|| trf("That is more than {} items.", &[&format_count(many)]))
+    }
+
+    fn convert_images(&mut self, conversion: Conversion, destination: PathBuf) {
+        if !vfs::is_local(&destination) || !self.restrictions.may_enter(&destination) {
+            self.toasts.error(
+                trf("Cannot convert into {}", &[&destination.display()]),
+                "It is not available here",
+            );
+            return;
+        }
+        let sources = self.selected.clone();
+        self.jobs.push(
+            JobKind::Convert(conversion),
+            sources,
+            Some(destination),
+            self.job_options,
+        );
+    }
+
+    fn open_project(&mut self, root: PathBuf) {
+        self.config.recent_projects.retain(|recent| recent != &root);
+        if !root.is_dir() {
+            self.toasts.error(
+                trf("Cannot open {}", &[&root.display()]),
+                "It is not a folder",
+            );
+            return;
+        }
+        self.config.recent_projects.insert(0, root.clone());
+        self.config.recent_projects.truncate(RECENT_PROJECTS);
+        self.project = Some(root.clone());
+        if !paths::starts_with(&self.current_dir, &root) {
+            self.navigate(root);
+        }
+    }
+
+    /// Asks what to do with paths pasted or dropped from elsewhere: go to
+    /// a folder, show a file, or bring them all here.
+    fn offer(&mut self, kind: JobKind, mut paths: Vec<PathBuf>) {
+        paths.retain(|path| fs::symlink_metadata(path).is_ok());
+        let here = match kind {
+            JobKind::Move => "Move here",
+            _ => "Copy here",
+        };
+        let transfer = Answer::Transfer(kind, paths.clone());
+        // Read-only pickers can still go to what was brought.
+        let writable = !self.restrictions.is_read_only();
+        let bring = |prompt: Prompt<Answer>| match writable {
+            true => prompt.button(here, Role::Normal, transfer.clone()),
+            false => prompt,
+        };
+        let prompt = match &paths[..] {
+            [] => {
+                self.toasts
+                    .error(tr("Nothing to bring here"), "None of those paths exist.");
+                return;
+            }
+            [dir] if dir.is_dir() => bring(
+                Prompt::new(
+                    Tone::Question,
+                    trf("Go to \"{}\"?", &[&display_name(dir)]),
+                    dir.display().to_string(),
+                )
+                .button(tr("Cancel"), Role::Normal, Answer::Dismiss),
+            )
+            .button(tr("Go there"), Role::Primary, Answer::Go(dir.clone())),
+            [file] => bring(
+                Prompt::new(
+                    Tone::Question,
+                    trf("Show \"{}\"?", &[&display_name(file)]),
+                    file.display().to_string(),
+                )
+                .button(tr("Cancel"), Role::Normal, Answer::Dismiss),
+            )
+            .button(tr("Show"), Role::Primary, Answer::Show(file.clone())),
+            _ if !writable => {
+                self.toasts.error(
+                    tr("Nothing can be brought here"),
+                    "This picker is read-only.",
+                );
+                return;
+            }
+            _ => Prompt::new(
+                Tone::Question,
+                trf("{} {} items?", &[&here, &format_count(paths.len())]),
+                paths
+                    .iter()
+                    .take(5)
+                    .map(|path| display_name(path))
+                    .chain((paths.len() > 5).then(|| String::from("…")))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .button(tr("Cancel"), Role::Normal, Answer::Dismiss)
+            .button(here, Role::Primary, transfer),
+        };
+        self.prompts.push(prompt.dismiss(Answer::Dismiss));
+    }
+
+    fn badge_color(&self, badge: Badge) -> Color {
+        match badge {
+            Badge::Conflicted => self.style.error,
+            Badge::Modified => self.style.warning,
+            Badge::Staged => self.style.added,
+            Badge::Untracked => self.style.drop_target,
+            Badge::Ignored => self.style.dimmed,
+        }
+    }
+
+    /// The picker's own emblems for a listed item, then the host's.
+    fn emblems(&self, data: &ContentData) -> Vec<Emblem> {
+        let icons = &self.style.icons;
+        let mut emblems = Vec::new();
+        if let Some(target) = &data.link_target {
+            if !icons.link.is_empty() {
+                emblems.push(Emblem::new(
+                    &icons.link,
+                    trf("Link to {}", &[&target.display()]),
+                ));
+            }
+        }
+        if data.read_only && !icons.read_only.is_empty() {
+            emblems.push(Emblem::new(&icons.read_only, "Read-only"));
+        }
+        if let Some(badge) = self.git.badge(&data.path) {
+            emblems.push(Emblem::new("●", badge.describe()).color(self.badge_color(badge)));
+        }
+        emblems.extend(self.tags.emblems(&data.path, self.style.dimmed));
+        emblems.extend(self.emblems.of(&data.path));
+        emblems
+    }
+
+    /// The innermost mounted volume holding `path`.
+    fn volume_of(&self, path: &Path) -> Option<&mounts::Volume> {
+        self.sidebar
+            .volumes()
+            .iter()
+            .filter(|volume| paths::starts_with(path, &volume.mount_point))
+            .max_by_key(|volume| volume.mount_point.as_os_str().len())
+    }
+
+    /// Streams are copied along on Windows and macOS, but only as long as
+    /// the destination filesystem can hold them.
+    fn warn_dropped_streams(&mut self, sources: &[PathBuf], destination: &Path) {
+        let destination = self.volume_of(destination);
+        let Some(volume) = destination.filter(|volume| streams::drops_streams(&volume.fs_type))
+        else {
+            return;
+        };
+
+        let affected = sources
+            .iter()
+            .filter(|source| !streams::streams(source).is_empty())
+            .count();
+        if affected > 0 {
+            self.toasts.push(
+                toast::Kind::Info,
+                "Extra file data will be lost",
+                trf("{} is {}, which cannot store the alternate streams or resource forks of {} item(s)", &[&volume.label, &volume.fs_type, &format_count(affected)]),
+            );
+        }
+    }
+
+    /// Positions in `visible` of the entries matching the filter, leaving
+    /// out the parent folder which is always listed.
+    fn matches(&self) -> Vec<usize> {
+        if self.filter.is_empty() {
+            return Vec::new();
+        }
+        (0..self.visible.len())
+            .filter(|&position| {
+                self.content[self.visible[position]]
+                    .data()
+                    .is_none_or(|data| !data.is_parent)
+            })
+            .collect()
+    }
+
+    /// Selects the entry at `position` in `visible` and scrolls to it.
+    fn jump_to(&mut self, position: usize) -> Command<Message> {
+        if let Some(data) = self.content[self.visible[position]].data() {
+            self.selected = vec![data.path.clone()];
+        }
+        // A couple of rows above it stay in sight for context.
+        let offset = self.line(position).saturating_sub(2) as f32 * self.style.row_height;
+        scrollable::scroll_to(listing(), scrollable::AbsoluteOffset { x: 0., y: offset })
+    }
+
+    /// Selects what is listed at `position`, scrolling only as far as it
+    /// takes to show it.
+    fn step_to(&mut self, position: usize) -> Command<Message> {
+        if let Some(data) = self.content[self.visible[position]].data() {
+            self.selected = vec![data.path.clone()];
+        }
+        let (offset, height) = self.listing_scroll;
+        let top = self.line(position) as f32 * self.style.row_height;
+        let bottom = top + self.style.row_height;
+        match rows::reveal(top, bottom, offset, height) {
+            Some(offset) => {
+                scrollable::scroll_to(listing(), scrollable::AbsoluteOffset { x: 0., y: offset })
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// The heading `visible[position]` starts a group under, drawn above
+    /// it unless it is the very first row.
+    fn starts_group(&self, position: usize) -> Option<&Heading> {
+        rows::starts_group(&self.visible, &self.headings, position)
+    }
+
+    /// The line `visible[position]` is drawn on, counting the headings.
+    fn line(&self, position: usize) -> usize {
+        rows::line(&self.visible, &self.headings, position)
+    }
+
+    /// The entries that can be selected with the line each is drawn on:
+    /// not the parent folder, nor what the dialog's filter rejects.
+    fn selectable(&self) -> impl Iterator<Item = (usize, &PathBuf)> {
+        let mut line = 0;
+        let selectable = !paths::is_computer(&self.current_dir);
+        self.visible
+            .iter()
+            .enumerate()
+            .map(move |(position, &index)| {
+                if position > 0 && self.starts_group(position).is_some() {
+                    line += 1;
+                }
+                line += 1;
+                (line - 1, &self.content[index])
+            })
+            .filter(move |(_, file)| selectable && !self.dimmed(file))
+            .filter_map(|(line, file)| {
+                file.data()
+                    .filter(|data| !data.is_parent)
+                    .map(|data| (line, &data.path))
+            })
+    }
+
+    /// Files the dialog's filter rejects stay listed but cannot be picked.
+    fn dimmed(&self, file: &Content) -> bool {
+        match (file, &self.dialog) {
+            (Content::File(data), Some(dialog)) => !dialog.accepts(&data.name.to_string_lossy()),
+            _ => false,
+        }
+    }
+
+    /// Where the one item selected is listed.
+    fn selected_position(&self) -> Option<usize> {
+        self.visible.iter().position(|&index| {
+            self.content[index].data().is_some_and(
+                |data| matches!(&self.selected[..], [selected] if *selected == data.path),
+            )
+        })
+    }
+
+    /// Runs what `shortcut` is bound to, alone or after the keys before
+    /// it; `None` when it is bound to nothing.
+    fn press(&mut self, shortcut: Shortcut) -> Option<Command<Message>> {
+        let action = match self.keymap.press(shortcut) {
+            keymap::Press::Action(action) => action,
+            keymap::Press::Pending => return Some(Command::none()),
+            keymap::Press::Unbound => return None,
+        };
+        let message = match action {
+            keymap::Action::SelectNext => Some(Message::MoveSelection(1)),
+            keymap::Action::SelectPrevious => Some(Message::MoveSelection(-1)),
+            keymap::Action::SelectFirst => Some(Message::SelectFirst),
+            keymap::Action::SelectLast => Some(Message::SelectLast),
+            keymap::Action::Open => Some(Message::OpenSelected),
+            keymap::Action::MenuBar => Some(Message::Menu(menu::Message::Open(
+                (!self.menu_bar.is_open()).then_some(0),
+            ))),
+            keymap::Action::Fullscreen => Some(Message::ToggleFullscreen),
+            keymap::Action::DistractionFree => Some(Message::ToggleDistractionFree),
+            // The rest are in the menus, which know when they can run.
+            action => self
+                .keymap
+                .binding(action)
+                .and_then(|binding| menu::find(&self.menus(), &binding)),
+        };
+
+        Some(message.map_or_else(Command::none, |message| self.update(message)))
+    }
+
+    /// Where the first name starting with `prefix` is listed. The same
+    /// letter typed again and again steps through the names starting with
+    /// it instead, as in most file managers.
+    fn starting_with(&self, prefix: &str) -> Option<usize> {
+        let named = |position: &usize, prefix: &str| {
+            self.content[self.visible[*position]]
+                .data()
+                .is_some_and(|data| {
+                    !data.is_parent
+                        && data
+                            .name
+                            .to_string_lossy()
+                            .to_lowercase()
+                            .starts_with(prefix)
+                })
+        };
+        let mut positions = 0..self.visible.len();
+        if let Some(position) = positions.clone().find(|position| named(position, prefix)) {
+            return Some(position);
+        }
+
+        let mut chars = prefix.chars();
+        let first = chars.next()?;
+        if !chars.all(|c| c == first) {
+            return None;
+        }
+        let first = first.to_string();
+        let after = self.selected_position().map_or(0, |current| current + 1);
+        positions
+            .clone()
+            .skip(after)
+            .find(|position| named(position, &first))
+            .or_else(|| positions.find(|position| named(position, &first)))
+    }
+
+    fn find_bar(&self) -> Element<'_, Message> {
+        let input = text_input(tr("Filter"), &self.filter)
+            .id(filter_box())
+            .on_input(Message::FilterInput)
+            .on_submit(Message::FilterSubmit)
+            .padding(5);
+        let hint = self
+            .type_ahead
+            .prefix()
+            .map(|prefix| text(trf("Go to “{}”", &[&prefix])).size(self.style.small_text_size));
+        if self.filter.is_empty() {
+            return match hint {
+                Some(hint) => row!(input, hint)
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .into(),
+                None => input.into(),
+            };
+        }
+
+        let matches = self.matches();
+        // Counted over the whole listing, not only the rows rendered.
+        let total = self.matching - (self.visible.len() - matches.len());
+        let count = match self
+            .current_match
+            .and_then(|current| matches.iter().position(|&known| known == current))
+        {
+            Some(index) => format!(
+                "{} of {} matches",
+                format_count(index + 1),
+                format_count(total)
+            ),
+            None if total == 0 => String::from("No matches"),
+            None => format!("{} matches", format_count(total)),
+        };
+
+        row!(
+            input,
+            text(count).size(self.style.small_text_size),
+            tooltip(
+                button("▲").on_press_maybe((total > 0).then_some(Message::NextMatch(-1))),
+                text(tr("Previous match")),
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box),
+            tooltip(
+                button("▼").on_press_maybe((total > 0).then_some(Message::NextMatch(1))),
+                text(tr("Next match")),
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box),
+        )
+        .push_maybe(hint)
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    fn listing_notice(&self) -> Option<String> {
+        let loading = if self.loading { " (still loading)" } else { "" };
+
+        if self.matching > self.visible.len() {
+            Some(trf(
+                "Showing first {} of {}{} — refine with a filter",
+                &[
+                    &format_count(self.visible.len()),
+                    &format_count(self.matching),
+                    &loading,
+                ],
+            ))
+        } else if self.loading {
+            Some(format!(
+                "Loading… {} entries",
+                format_count(self.content.len())
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn listing_subscription(&self) -> Subscription<Message> {
+        struct Listing;
+
+        let listing = self.listing;
+        let cwd = self.current_dir.clone();
+        let limits = self.limits();
+
+        subscription::channel(
+            (TypeId::of::<Listing>(), listing),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+
+                thread::spawn(move || get_dir_content(cwd, limits, sender));
+
+                while let Some(batch) = receiver.next().await {
+                    let _ = output.send(Message::Listed(listing, batch)).await;
+                }
+                let _ = output.send(Message::ListingDone(listing)).await;
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    /// Shown in folders the sandbox keeps some or all of the host's files
+    /// out of, with a way to have the host share files from there.
+    fn sandbox_warning(&self) -> Option<Element<'_, Message>> {
+        let rules = sandbox::rules()?;
+        if paths::is_computer(&self.current_dir) || !rules.hides(&self.current_dir) {
+            return None;
+        }
+
+        let warning = row!(
+            text(trf(
+                "The {} sandbox hides files here, so this folder may look empty or incomplete.",
+                &[&rules.sandbox]
+            ))
+            .size(self.style.small_text_size)
+            .style(theme::Text::Color(self.style.warning))
+            .width(Length::Fill),
+            button(tr("Choose files from the host…")).on_press(Message::PickFromHost),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        Some(warning.into())
+    }
+
+    /// Shown when permissions keep the folder from being listed: which
+    /// folder on the way is to blame and whose it is, with ways around.
+    fn access_panel(&self) -> Option<Element<'_, Message>> {
+        let blocked = self.blocked.as_ref()?;
+
+        let mut ways = row!().spacing(10);
+        if let Some(parent) = blocked.dir.parent() {
+            ways = ways.push(
+                button(text(trf("Go to {}", &[&paths::display(parent)])))
+                    .on_press(Message::Navigate(parent.to_path_buf())),
+            );
+        }
+        if system::can_elevate() {
+            ways = ways
+                .push(button(tr("Open as administrator")).on_press(Message::OpenAsAdministrator));
+        }
+        let panel = column!(
+            text(trf("Cannot open {}", &[&display_name(&self.current_dir)]))
+                .size(16)
+                .style(theme::Text::Color(self.style.warning)),
+            text(blocked.explanation(&self.current_dir)),
+            ways,
+        )
+        .spacing(10);
+
+        Some(
+            Container::new(panel)
+                .width(Length::Fill)
+                .padding(15)
+                .style(theme::Container::Box)
+                .into(),
+        )
+    }
+
+    /// `message`, if it can be done in this folder with this selection.
+    fn available(&self, message: Message) -> Option<Message> {
+        let has_selection = !self.selected.is_empty();
+        // Entries inside an archive can only be extracted.
+        let in_archive = self.archive.is_some();
+        // Folders on other machines take copies, moves, deletions and
+        // renames, but not what only works on the disk.
+        let remote = !in_archive && !vfs::is_local(&self.current_dir);
+        let computer = paths::is_computer(&self.current_dir);
+        let in_dir = !computer && !in_archive && !remote;
+
+        let changes = matches!(
+            message,
+            Message::Cut
+                | Message::Delete
+                | Message::Trash
+                | Message::Undo
+                | Message::Redo
+                | Message::Paste
+                | Message::PasteSymlinks
+                | Message::NewEntry(_)
+                | Message::ExtractInto
+                | Message::Extract
+                | Message::ExtractTo
+                | Message::Edit
+                | Message::IntegrateAppImage
+                | Message::ExtractAppImage
+                | Message::StripAppleDouble
+                | Message::FindDuplicates
+                | Message::CompareFolders
+                | Message::ConvertImages
+                | Message::ConvertText
+                | Message::ConvertLineEndings
+        );
+        if changes && self.restrictions.is_read_only() {
+            return None;
+        }
+
+        let available = match &message {
+            Message::Copy => has_selection && !in_archive,
+            Message::Cut | Message::Delete => has_selection && !in_archive,
+            Message::Trash => has_selection && !in_archive && !remote && trash::is_supported(),
+            Message::Undo => self.journal.next_undo().is_some(),
+            Message::Sidecars(sidecars::Message::VerifyAll) => {
+                self.sidecars.count() > 0 && !self.sidecars.is_checking()
+            }
+            Message::Redo => self.journal.next_redo().is_some(),
+            Message::Paste => in_dir || remote,
+            Message::PasteSymlinks => in_dir && self.clipboard.is_some(),
+            Message::CopyPath => has_selection || !computer,
+            Message::CopySummary(_) => has_selection && in_dir,
+            Message::NewEntry(EntryKind::Archive) => in_dir && has_selection,
+            Message::NewEntry(EntryKind::Folder | EntryKind::File) | Message::ExtractInto => in_dir,
+            Message::NewEntry(EntryKind::Rename(_)) => !in_archive,
+            Message::Tag(..) | Message::EditTags => has_selection && in_dir,
+            Message::NewWindow => self.new_windows && self.dialog.is_none(),
+            Message::ReopenClosed(index) => {
+                self.new_windows && self.dialog.is_none() && *index < self.recently_closed.len()
+            }
+            Message::OpenInNewWindow => {
+                self.new_windows
+                    && self.dialog.is_none()
+                    && matches!(&self.selected[..], [path] if path.is_dir())
+            }
+            Message::ShowChecksums => {
+                has_selection && in_dir && self.selected.iter().all(|path| path.is_file())
+            }
+            Message::Edit => matches!(&self.selected[..], [path] if path.is_file()),
+            Message::DefaultView => self.view != self.config.default_view(),
+            Message::ShowProperties => self.selected.len() < 2 && !in_archive && !remote,
+            Message::ShowPackageContents => {
+                matches!(&self.selected[..], [path] if paths::is_app_bundle(path))
+            }
+            Message::RunAppImage | Message::IntegrateAppImage | Message::ExtractAppImage => {
+                in_dir && matches!(&self.selected[..], [path] if appimage::is_appimage(path))
+            }
+            Message::ShowImageSummary => {
+                in_dir && matches!(&self.selected[..], [path] if oci::may_be_image(path))
+            }
+            Message::ShowPackage => {
+                in_dir && matches!(&self.selected[..], [path] if package::kind(path).is_some())
+            }
+            Message::ShowTorrent => {
+                in_dir && matches!(&self.selected[..], [path] if torrent::kind(path).is_some())
+            }
+            Message::Extract | Message::ExtractTo => {
+                has_selection
+                    && (in_archive
+                        || self
+                            .selected
+                            .iter()
+                            .all(|path| archive::format(path).is_some() && path.is_file()))
+            }
+            Message::StripAppleDouble => !computer && !remote,
+            Message::BookmarkRemote => {
+                remote && !self.config.remote_bookmarks.contains(&self.current_dir)
+            }
+            Message::UnbookmarkRemote => self.config.remote_bookmarks.contains(&self.current_dir),
+            Message::AnalyzeUsage
+            | Message::FindDuplicates
+            | Message::CompareFolders
+            | Message::OpenTerminal
+            | Message::RunCommand => in_dir,
+            Message::ConvertImages => {
+                in_dir && has_selection && self.selected.iter().all(|path| images::is_image(path))
+            }
+            Message::ConvertText | Message::ConvertLineEndings => {
+                let selected: HashSet<&PathBuf> = self.selected.iter().collect();
+                in_dir
+                    && has_selection
+                    && self
+                        .content
+                        .iter()
+                        .filter(|content| {
+                            matches!(content, Content::File(data) if selected.contains(&data.path))
+                        })
+                        .count()
+                        == selected.len()
+            }
+            Message::ProjectHere => in_dir && self.project.as_ref() != Some(&self.current_dir),
+            Message::SelectAll | Message::InvertSelection => {
+                !computer && !self.search.is_active() && self.selectable().next().is_some()
+            }
+            Message::RunAction(index) => {
+                !in_archive
+                    && self
+                        .extensions
+                        .actions()
+                        .get(*index)
+                        .is_some_and(|action| action.accepts(&self.selected))
+            }
+            Message::NextMatch(_) => !self.matches().is_empty(),
+            _ => true,
+        };
+        available.then_some(message)
+    }
+
+    fn rename(&self) -> Option<Message> {
+        match &self.selected[..] {
+            [path] => self.available(Message::NewEntry(EntryKind::Rename(path.clone()))),
+            _ => None,
+        }
+    }
+
+    /// The windows closed lately, Ctrl+Shift+T opening the last again.
+    fn recently_closed_menu(&self) -> Menu<Message> {
+        let binding = self.keymap.binding(keymap::Action::ReopenClosed);
+        let mut menu = Menu::new(tr("Recently closed"));
+        for (index, dir) in self.recently_closed.iter().enumerate() {
+            menu = menu.item(
+                paths::display(dir),
+                binding.clone().filter(|_| index == 0),
+                self.available(Message::ReopenClosed(index)),
+            );
+        }
+        menu
+    }
+
+    /// Every action, with the accelerators that run it from anywhere.
+    fn menus(&self) -> Vec<Menu<Message>> {
+        let mut file = Menu::new(tr("File"))
+            .item(
+                "New window",
+                self.keymap.binding(keymap::Action::NewWindow),
+                self.available(Message::NewWindow),
+            )
+            .item(
+                "Open in new window",
+                None,
+                self.available(Message::OpenInNewWindow),
+            )
+            .submenu(self.recently_closed_menu())
+            .separator()
+            .item(
+                "New folder",
+                self.keymap.binding(keymap::Action::NewFolder),
+                self.available(Message::NewEntry(EntryKind::Folder)),
+            )
+            .item(
+                "New file",
+                self.keymap.binding(keymap::Action::NewFile),
+                self.available(Message::NewEntry(EntryKind::File)),
+            )
+            .separator()
+            .item(
+                "Compress…",
+                None,
+                self.available(Message::NewEntry(EntryKind::Archive)),
+            )
+            .item("Extract here", None, self.available(Message::Extract))
+            .item("Extract to…", None, self.available(Message::ExtractTo))
+            .separator()
+            .item(
+                "Properties",
+                self.keymap.binding(keymap::Action::Properties),
+                self.available(Message::ShowProperties),
+            )
+            .item("Checksums", None, self.available(Message::ShowChecksums))
+            .item(
+                "Verify checksums in this folder",
+                None,
+                self.available(Message::Sidecars(sidecars::Message::VerifyAll)),
+            )
+            .item(
+                "Container image summary…",
+                None,
+                self.available(Message::ShowImageSummary),
+            )
+            .item(
+                "Package contents…",
+                None,
+                self.available(Message::ShowPackage),
+            )
+            .item(
+                "Torrent details…",
+                None,
+                self.available(Message::ShowTorrent),
+            );
+        if cfg!(target_os = "linux") {
+            file = file
+                .separator()
+                .item("Run AppImage", None, self.available(Message::RunAppImage))
+                .item(
+                    "Add AppImage to the menu",
+                    None,
+                    self.available(Message::IntegrateAppImage),
+                )
+                .item(
+                    "Extract AppImage",
+                    None,
+                    self.available(Message::ExtractAppImage),
+                );
+        }
+        if cfg!(target_os = "macos") {
+            file = file.item(
+                "Show package contents",
+                None,
+                self.available(Message::ShowPackageContents),
+            );
+        }
+        if sandbox::rules().is_some() {
+            file = file.item(
+                "Choose files from the host…",
+                None,
+                Some(Message::PickFromHost),
+            );
+        }
+        let file = file.separator().item(
+            "Quit",
+            self.keymap.binding(keymap::Action::Quit),
+            Some(Message::CloseRequested(self.window)),
+        );
+
+        let undo = match self.journal.next_undo() {
+            Some(operation) => trf("Undo {}", &[&operation.title()]),
+            None => String::from("Undo"),
+        };
+        let redo = match self.journal.next_redo() {
+            Some(operation) => trf("Redo {}", &[&operation.title()]),
+            None => String::from("Redo"),
+        };
+        let mut edit = Menu::new(tr("Edit"))
+            .item(
+                undo,
+                self.keymap.binding(keymap::Action::Undo),
+                self.available(Message::Undo),
+            )
+            .item(
+                redo,
+                self.keymap.binding(keymap::Action::Redo),
+                self.available(Message::Redo),
+            )
+            .separator()
+            .item(
+                "Cut",
+                self.keymap.binding(keymap::Action::Cut),
+                self.available(Message::Cut),
+            )
+            .item(
+                "Copy",
+                self.keymap.binding(keymap::Action::Copy),
+                self.available(Message::Copy),
+            )
+            .item(
+                "Paste",
+                self.keymap.binding(keymap::Action::Paste),
+                self.available(Message::Paste),
+            )
+            .item(
+                "Create symlink here",
+                self.keymap.binding(keymap::Action::PasteSymlinks),
+                self.available(Message::PasteSymlinks),
+            )
+            .separator()
+            .item(
+                "Select all",
+                self.keymap.binding(keymap::Action::SelectAll),
+                self.available(Message::SelectAll),
+            )
+            .item(
+                "Invert selection",
+                self.keymap.binding(keymap::Action::InvertSelection),
+                self.available(Message::InvertSelection),
+            )
+            .separator()
+            .item(
+                "Rename",
+                self.keymap.binding(keymap::Action::Rename),
+                self.rename(),
+            )
+            .item(
+                "Edit",
+                self.keymap.binding(keymap::Action::Edit),
+                self.available(Message::Edit),
+            )
+            .item(
+                "Move to trash",
+                self.keymap.binding(keymap::Action::Trash),
+                self.available(Message::Trash),
+            )
+            .item(
+                "Delete permanently",
+                self.keymap.binding(keymap::Action::Delete),
+                self.available(Message::Delete),
+            )
+            .separator()
+            .item("Tags…", None, self.available(Message::EditTags));
+        if self.available(Message::EditTags).is_some() {
+            for label in Label::ALL {
+                let checked = self
+                    .selected
+                    .iter()
+                    .all(|path| self.tags.has(path, label.tag()));
+                edit = edit.check(trf("{} label", &[&label]), checked, move |on| {
+                    Message::Tag(label.tag().to_string(), on)
+                });
+            }
+        }
+        let edit = edit
+            .separator()
+            .item(
+                "Copy path",
+                self.keymap.binding(keymap::Action::CopyPath),
+                self.available(Message::CopyPath),
+            )
+            .item(
+                "Copy selection summary",
+                None,
+                self.available(Message::CopySummary(false)),
+            )
+            .item(
+                "Copy selection summary with checksums",
+                None,
+                self.available(Message::CopySummary(true)),
+            )
+            .separator()
+            .item(
+                "Find",
+                self.keymap.binding(keymap::Action::Find),
+                Some(Message::Find),
+            )
+            .item(
+                "Next match",
+                self.keymap.binding(keymap::Action::NextMatch),
+                self.available(Message::NextMatch(1)),
+            )
+            .item(
+                "Previous match",
+                self.keymap.binding(keymap::Action::PreviousMatch),
+                self.available(Message::NextMatch(-1)),
+            )
+            .separator()
+            .item(
+                "Settings…",
+                self.keymap.binding(keymap::Action::Settings),
+                Some(Message::OpenSettings),
+            );
+
+        let view = Menu::new(tr("View"))
+            .check("As list", self.view.mode == ViewMode::List, |_| {
+                Message::ViewMode(ViewMode::List)
+            })
+            .check("As columns", self.view.mode == ViewMode::Columns, |_| {
+                Message::ViewMode(ViewMode::Columns)
+            })
+            .separator()
+            .check("Ungrouped", self.view.group_by == GroupBy::None, |_| {
+                Message::GroupBy(GroupBy::None)
+            })
+            .check(
+                "Grouped by first letter",
+                self.view.group_by == GroupBy::Letter,
+                |_| Message::GroupBy(GroupBy::Letter),
+            )
+            .check(
+                "Grouped by type",
+                self.view.group_by == GroupBy::Type,
+                |_| Message::GroupBy(GroupBy::Type),
+            )
+            .check(
+                "Grouped by date modified",
+                self.view.group_by == GroupBy::Date,
+                |_| Message::GroupBy(GroupBy::Date),
+            )
+            .separator()
+            .check("Sorted by name", self.view.sort == SortBy::Name, |_| {
+                Message::SortBy(SortBy::Name)
+            })
+            .check("Sorted by size", self.view.sort == SortBy::Size, |_| {
+                Message::SortBy(SortBy::Size)
+            })
+            .check(
+                "Sorted by date modified",
+                self.view.sort == SortBy::Modified,
+                |_| Message::SortBy(SortBy::Modified),
+            )
+            .check("Sorted by type", self.view.sort == SortBy::Type, |_| {
+                Message::SortBy(SortBy::Type)
+            })
+            .item(
+                "View as elsewhere",
+                None,
+                self.available(Message::DefaultView),
+            )
+            .separator()
+            .check("Advanced options", self.show_advanced, |_| {
+                Message::ToggleAdvanced
+            })
+            .check(
+                "Show ._ files",
+                self.config.show_apple_double,
+                Message::ShowAppleDouble,
+            )
+            .check(
+                "Folder tree",
+                !self.config.hide_folder_tree,
+                Message::ShowFolderTree,
+            )
+            .check(
+                "Low-memory mode",
+                self.config.low_memory,
+                Message::LowMemory,
+            )
+            .check("Dark theme", is_dark(&self.config.theme), |_| {
+                Message::ToggleTheme
+            })
+            .separator()
+            .check("Toolbar", self.shows(Panel::Toolbar), Message::ShowToolbar)
+            .check("Sidebar", self.shows(Panel::Sidebar), Message::ShowSidebar)
+            .check(
+                "Status bar",
+                self.shows(Panel::StatusBar),
+                Message::ShowStatusBar,
+            )
+            .check("Distraction-free", self.config.distraction_free, |_| {
+                Message::ToggleDistractionFree
+            })
+            .check("Fullscreen", self.fullscreen, |_| Message::ToggleFullscreen);
+
+        let mut go = Menu::new(tr("Go"))
+            .item(
+                "Parent folder",
+                self.keymap.binding(keymap::Action::ParentFolder),
+                self.current_dir
+                    .parent()
+                    .filter(|_| self.project.as_ref() != Some(&self.current_dir))
+                    .filter(|dir| paths::is_network(dir) || !paths::is_network(&self.current_dir))
+                    .map(|dir| Message::Navigate(dir.to_path_buf())),
+            )
+            .item(
+                "Back",
+                self.keymap.binding(keymap::Action::Back),
+                self.history.back().map(|_| Message::Back),
+            )
+            .item(
+                "Forward",
+                self.keymap.binding(keymap::Action::Forward),
+                self.history.forward().map(|_| Message::Forward),
+            )
+            .item(
+                "Location…",
+                self.keymap.binding(keymap::Action::Location),
+                Some(Message::EditPath),
+            )
+            .item(
+                "Jump to a recent folder…",
+                self.keymap.binding(keymap::Action::JumpTo),
+                Some(Message::OpenJump),
+            );
+        if cfg!(windows) {
+            go = go.item(
+                paths::COMPUTER,
+                None,
+                Some(Message::Navigate(PathBuf::new())),
+            );
+        }
+        // The sidebar's places, for the keyboard.
+        let volumes = self.sidebar.volumes();
+        let remotes = self.remotes();
+        if !volumes.is_empty() || !remotes.is_empty() {
+            go = go.separator();
+        }
+        for volume in volumes {
+            go = go.item(
+                volume.label.clone(),
+                None,
+                Some(Message::Sidebar(sidebar::Message::Open(
+                    volume.mount_point.clone(),
+                ))),
+            );
+        }
+        for bookmark in remotes {
+            go = go.item(
+                sidebar::label(&bookmark),
+                None,
+                Some(Message::Sidebar(sidebar::Message::Open(bookmark))),
+            );
+        }
+        for drive in self.sidebar.drives() {
+            go = go.item(
+                trf("Mount {}", &[&drive.label]),
+                None,
+                Some(Message::Sidebar(sidebar::Message::Mount(drive.clone()))),
+            );
+        }
+        for volume in volumes
+            .iter()
+            .filter(|volume| volume.kind == mounts::VolumeKind::Removable)
+        {
+            go = go.item(
+                trf("Eject {}", &[&volume.label]),
+                None,
+                Some(Message::Sidebar(sidebar::Message::Eject(
+                    volume.mount_point.clone(),
+                ))),
+            );
+        }
+        go = go
+            .separator()
+            .item(
+                "Use this folder as project",
+                None,
+                self.available(Message::ProjectHere),
+            )
+            .item(
+                "Close project",
+                None,
+                self.project.as_ref().map(|_| Message::CloseProject),
+            )
+            .separator()
+            .item(
+                "Bookmark this remote folder",
+                None,
+                self.available(Message::BookmarkRemote),
+            )
+            .item(
+                "Remove remote bookmark",
+                None,
+                self.available(Message::UnbookmarkRemote),
+            );
+
+        let mut tools = Menu::new(tr("Tools"))
+            .item(
+                "Open terminal here",
+                self.keymap.binding(keymap::Action::OpenTerminal),
+                self.available(Message::OpenTerminal),
+            )
+            .item(
+                "Run command in this folder…",
+                self.keymap.binding(keymap::Action::RunCommand),
+                self.available(Message::RunCommand),
+            )
+            .separator()
+            .item(
+                "Analyze usage…",
+                None,
+                self.available(Message::AnalyzeUsage),
+            )
+            .item(
+                "Find duplicates…",
+                None,
+                self.available(Message::FindDuplicates),
+            )
+            .item(
+                "Compare with folder…",
+                None,
+                self.available(Message::CompareFolders),
+            )
+            .item(
+                "Convert images…",
+                None,
+                self.available(Message::ConvertImages),
+            )
+            .item(
+                "Convert to UTF-8…",
+                None,
+                self.available(Message::ConvertText),
+            )
+            .item(
+                "Convert line endings…",
+                None,
+                self.available(Message::ConvertLineEndings),
+            )
+            .item(
+                "Remove ._ files below this folder",
+                None,
+                self.available(Message::StripAppleDouble),
+            );
+        if !self.extensions.actions().is_empty() {
+            tools = tools.separator();
+            for (index, action) in self.extensions.actions().iter().enumerate() {
+                tools = tools.item(
+                    action.label(),
+                    None,
+                    self.available(Message::RunAction(index)),
+                );
+            }
+        }
+        tools = tools
+            .separator()
+            .check(
+                "Preserve timestamps for the next paste",
+                self.job_options.preserve_timestamps,
+                Message::PreserveTimestamps,
+            )
+            .check(
+                "Always preserve timestamps",
+                self.config.preserve_timestamps,
+                Message::DefaultPreserveTimestamps,
+            )
+            .check(
+                "Resolve symlinks when browsing",
+                self.config.symlinks == Symlinks::Resolve,
+                Message::ResolveSymlinks,
+            );
+        if let Some(label) = system_index_label() {
+            tools = tools.check(label, self.config.use_system_index, Message::UseSystemIndex);
+        }
+        if cfg!(windows) {
+            tools = tools.check(
+                "Search with the NTFS index (requires administrator)",
+                self.config.use_ntfs_index,
+                Message::UseNtfsIndex,
+            );
+        }
+        let tools = tools.check(
+            "Start in the background at login",
+            self.autostart,
+            Message::Autostart,
+        );
+
+        let help = Menu::new(tr("Help"))
+            .item(
+                "Command palette…",
+                self.keymap.binding(keymap::Action::CommandPalette),
+                Some(Message::OpenPalette),
+            )
+            .item(
+                "Keyboard shortcuts",
+                self.keymap.binding(keymap::Action::Shortcuts),
+                Some(Message::ShowShortcuts),
+            )
+            .item("About iced-fm", None, Some(Message::About));
+
+        vec![file, edit, view, go, tools, help]
+    }
+
+    fn palette_commands(&self) -> Vec<palette::Command<Message>> {
+        match self.palette.as_ref().map(Palette::kind) {
+            Some(palette::Kind::Places) => self.places(),
+            _ => self.commands(),
+        }
+    }
+
+    /// The folders visited but this one, to jump to.
+    fn places(&self) -> Vec<palette::Command<Message>> {
+        self.recent
+            .ranked()
+            .into_iter()
+            .filter(|&dir| dir != self.current_dir)
+            .map(|dir| palette::Command {
+                title: paths::display(dir),
+                keys: None,
+                message: Message::Navigate(dir.to_path_buf()),
+            })
+            .collect()
+    }
+
+    /// What the command palette offers: every menu item that can run.
+    fn commands(&self) -> Vec<palette::Command<Message>> {
+        menu::entries(&self.menus())
+            .filter(|entry| !matches!(entry.on_press, Message::OpenPalette | Message::OpenJump))
+            .map(|entry| palette::Command {
+                title: format!("{} › {}", entry.menu, entry.label),
+                keys: entry
+                    .shortcut
+                    .map(|binding| binding.to_string())
+                    .or_else(|| (entry.checked == Some(true)).then(|| String::from("✓"))),
+                message: entry.on_press.clone(),
+            })
+            .collect()
+    }
+
+    fn toolbar(&self) -> Element<'_, Message> {
+        let buttons = row!(
+            button(tr("Copy")).on_press_maybe(self.available(Message::Copy)),
+            button(tr("Cut")).on_press_maybe(self.available(Message::Cut)),
+            button(tr("Paste")).on_press_maybe(self.available(Message::Paste)),
+            button(tr("Create symlink here"))
+                .on_press_maybe(self.available(Message::PasteSymlinks)),
+            button(tr("Move to trash")).on_press_maybe(self.available(Message::Trash)),
+            button(tr("Delete")).on_press_maybe(self.available(Message::Delete)),
+            button(tr("Copy path")).on_press_maybe(self.available(Message::CopyPath)),
+            button(tr("Compress…"))
+                .on_press_maybe(self.available(Message::NewEntry(EntryKind::Archive))),
+            button(tr("Checksums")).on_press_maybe(self.available(Message::ShowChecksums)),
+            button(tr("Edit")).on_press_maybe(self.available(Message::Edit)),
+            button(tr("Rename")).on_press_maybe(self.rename()),
+            button(tr("Properties")).on_press_maybe(self.available(Message::ShowProperties)),
+            button(tr("New folder"))
+                .on_press_maybe(self.available(Message::NewEntry(EntryKind::Folder))),
+            button(tr("New file"))
+                .on_press_maybe(self.available(Message::NewEntry(EntryKind::File))),
+            button(if self.show_advanced {
+                "Advanced ▾"
+            } else {
+                "Advanced ▸"
+            })
+            .on_press(Message::ToggleAdvanced),
+            tooltip(
+                button(if is_dark(&self.config.theme) {
+                    "☀"
+                } else {
+                    "☾"
+                })
+                .on_press(Message::ToggleTheme),
+                text(tr("Switch between the light and the dark theme")),
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box),
+        )
+        .spacing(5);
+        let buttons = if let Some(show) = self.available(Message::ShowPackageContents) {
+            buttons.push(button(tr("Show package contents")).on_press(show))
+        } else if self.available(Message::Extract).is_some() {
+            buttons
+                .push(button(tr("Extract here")).on_press(Message::Extract))
+                .push(button(tr("Extract to…")).on_press(Message::ExtractTo))
+        } else {
+            buttons
+        };
+
+        let mut col = column!(buttons).spacing(5).padding([10, 0]);
+
+        if let Some(sources) = &self.extract_to {
+            let what = match &sources[..] {
+                [path] => display_name(path),
+                sources => format!("{} items", format_count(sources.len())),
+            };
+            col = col.push(
+                row!(
+                    text(trf("Open the folder to extract {} into", &[&what])).width(Length::Fill),
+                    button(tr("Extract into this folder"))
+                        .on_press_maybe(self.available(Message::ExtractInto)),
+                    button(tr("Cancel")).on_press(Message::CancelExtractTo),
+                )
+                .spacing(5)
+                .align_items(Alignment::Center),
+            );
+        }
+
+        if let Some(entry) = &self.new_entry {
+            let (label, action) = match entry.kind {
+                EntryKind::Folder => ("New folder name", "Create"),
+                EntryKind::File => ("New file name", "Create"),
+                EntryKind::Rename(_) => ("New name", "Rename"),
+                EntryKind::Archive => ("Archive name", "Compress"),
+            };
+            let create =
+                (!entry.name.is_empty() && entry.error.is_none()).then_some(Message::CreateEntry);
+            let mut name = text_input(label, &entry.name).on_input(Message::NewEntryInput);
+            if let Some(create) = create.clone() {
+                name = name.on_submit(create);
+            }
+
+            let mut fields = row!(name).spacing(5).align_items(Alignment::Center);
+            if entry.kind == EntryKind::Archive {
+                fields = fields.push(pick_list(
+                    &archive::Format::WRITABLE[..],
+                    Some(self.compression.format),
+                    Message::CompressFormat,
+                ));
+                fields = fields.push(text(tr("Level"))).push(pick_list(
+                    &COMPRESSION_LEVELS[..],
+                    Some(self.compression.level),
+                    Message::CompressLevel,
+                ));
+            }
+            col = col.push(
+                fields
+                    .push(button(action).on_press_maybe(create))
+                    .push(button(tr("Cancel")).on_press(Message::CancelNewEntry)),
+            );
+            if let Some(error) = &entry.error {
+                col = col.push(
+                    text(error)
+                        .size(self.style.small_text_size)
+                        .style(theme::Text::Color(self.style.error)),
+                );
+            }
+        }
+
+        if self.show_advanced {
+            let mut options = row!(
+                checkbox(
+                    tr("Preserve timestamps for the next paste"),
+                    self.job_options.preserve_timestamps
+                )
+                .on_toggle(Message::PreserveTimestamps),
+                checkbox(
+                    tr("Always preserve timestamps"),
+                    self.config.preserve_timestamps
+                )
+                .on_toggle(Message::DefaultPreserveTimestamps),
+                checkbox(
+                    tr("Resolve symlinks when browsing"),
+                    self.config.symlinks == Symlinks::Resolve
+                )
+                .on_toggle(Message::ResolveSymlinks),
+            )
+            .spacing(20);
+            if let Some(label) = system_index_label() {
+                options = options.push(
+                    checkbox(label, self.config.use_system_index)
+                        .on_toggle(Message::UseSystemIndex),
+                );
+            }
+            if cfg!(windows) {
+                options = options.push(
+                    checkbox(
+                        tr("Search with the NTFS index (requires administrator)"),
+                        self.config.use_ntfs_index,
+                    )
+                    .on_toggle(Message::UseNtfsIndex),
+                );
+            }
+            col = col.push(options).push(
+                row!(
+                    checkbox(tr("Show ._ files"), self.config.show_apple_double)
+                        .on_toggle(Message::ShowAppleDouble),
+                    checkbox(tr("Low-memory mode"), self.config.low_memory)
+                        .on_toggle(Message::LowMemory),
+                    text(tr("Size of folders:")),
+                    pick_list(
+                        FolderSize::ALL,
+                        Some(self.config.folder_size),
+                        Message::FolderSize
+                    ),
+                    checkbox(tr("Start in the background at login"), self.autostart)
+                        .on_toggle(Message::Autostart),
+                    button(tr("WebDAV accounts…")).on_press(Message::OpenAccounts),
+                    button(tr("Remove ._ files below this folder")).on_press_maybe(
+                        (!paths::is_computer(&self.current_dir))
+                            .then_some(Message::StripAppleDouble)
+                    ),
+                )
+                .spacing(20)
+                .align_items(Alignment::Center),
+            );
+            col = col.push(
+                row!(
+                    text(tr("Terminal:")),
+                    text_input(tr("Found on the system"), &self.config.terminal)
+                        .on_input(Message::TerminalCommand)
+                        .width(Length::Fixed(260.)),
+                )
+                .spacing(10)
+                .align_items(Alignment::Center),
+            );
+            if let Some(dir) = dirs::config_dir().filter(|_| dirs::is_portable()) {
+                col = col.push(
+                    text(trf(
+                        "Portable mode: settings are kept in {}",
+                        &[&dir.display()],
+                    ))
+                    .size(self.style.small_text_size),
+                );
+            }
+        }
+
+        col.into()
+    }
+
+    fn list_dir(&self) -> Element<'_, Message> {
+        let mut col = column!();
+        let heading_row = |label: String| {
+            row!(
+                text("").width(Length::Fixed(30.)),
+                text(label)
+                    .size(self.style.small_text_size)
+                    .style(theme::Text::Color(self.style.dimmed))
+            )
+            .height(self.style.row_height)
+            .align_items(Alignment::Center)
+        };
+        // The heading of the group at the top, kept in sight above the rows.
+        let top = (self.listing_scroll.0 / self.style.row_height) as usize;
+        let mut line = 0;
+        let mut sticky = None;
+        let scheme = self.config.color_scheme();
+        let (selection, stripe) = style::Row::colors(
+            &self.config.theme,
+            scheme.and_then(|scheme| scheme.selection),
+            scheme.and_then(|scheme| scheme.stripe),
+        );
+
+        for (position, file) in self
+            .visible
+            .iter()
+            .map(|&index| &self.content[index])
+            .enumerate()
+        {
+            if let Some(heading) = self.starts_group(position) {
+                if position > 0 {
+                    col = col.push(heading_row(heading.label.clone()));
+                    line += 1;
+                }
+            }
+            if line <= top {
+                sticky = self.headings[self.visible[position]].as_ref();
+            }
+            line += 1;
+            let dimmed = self.dimmed(file);
+            let select: Element<Message> = match file.data() {
+                Some(data)
+                    if !data.is_parent && !paths::is_computer(&self.current_dir) && !dimmed =>
+                {
+                    let path = data.path.clone();
+                    checkbox("", self.selected.contains(&data.path))
+                        .on_toggle(move |selected| Message::ContentSelected(path.clone(), selected))
+                        .into()
+                }
+                _ => text("").into(),
+            };
+            let size = match file {
+                Content::Directory(data)
+                    if !data.is_parent
+                        && self.archive.is_none()
+                        && !paths::is_computer(&self.current_dir) =>
+                {
+                    self.folder_sizes.label(self.config.folder_size, &data.path)
+                }
+                Content::Directory(data) if data.is_parent => String::new(),
+                file => format_bytes(file.size()),
+            };
+            let size = text(size).width(Length::FillPortion(1));
+            let modified = match file.data() {
+                Some(data) if !data.is_parent && !paths::is_computer(&self.current_dir) => data
+                    .modified
+                    .map(|time| format_time(Ok(time)))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            let modified = text(modified).width(Length::FillPortion(1));
+            let icons = &self.style.icons;
+            let mut name = text(file.to_string());
+            if let Some(font) = self.style.font {
+                name = name.font(font);
+            }
+            if dimmed {
+                name = name.style(theme::Text::Color(self.style.dimmed));
+            }
+            let icon = match file {
+                Content::Directory(data) if !data.is_parent => &icons.folder,
+                Content::File(data) => self
+                    .file_icons
+                    .glyph(&data.path, icons)
+                    .unwrap_or(&icons.file),
+                _ => "",
+            };
+            let mut label = row!().spacing(5).align_items(Alignment::Center);
+            let thumbnail = match file {
+                Content::File(data) => self.thumbnails.get(&data.path),
+                _ => None,
+            };
+            let icon = match thumbnail {
+                Some(thumbnail) => {
+                    let side = Length::Fixed(self.style.row_height - 4.);
+                    label = label.push(image(thumbnail.clone()).width(side).height(side));
+                    ""
+                }
+                None => icon,
+            };
+            let emblems = file
+                .data()
+                .filter(|data| !data.is_parent)
+                .map(|data| self.emblems(data))
+                .unwrap_or_default();
+            if let Some(icon) = emblem::icon(icon, emblems, &self.style) {
+                label = label.push(icon);
+            }
+            label = label.push(name);
+            if let Some(sidecar) = file
+                .data()
+                .filter(|data| !data.is_parent)
+                .and_then(|data| self.sidecars.get(&data.path))
+            {
+                let color = match sidecar.verdict {
+                    Verdict::Matches => self.style.added,
+                    Verdict::Differs | Verdict::Failed(_) => self.style.error,
+                    Verdict::Unchecked | Verdict::Checking => self.style.dimmed,
+                };
+                label = label.push(
+                    tooltip(
+                        text(sidecar.label())
+                            .size(self.style.small_text_size)
+                            .style(theme::Text::Color(color)),
+                        text(sidecar.describe()),
+                        tooltip::Position::FollowCursor,
+                    )
+                    .style(theme::Container::Box),
+                );
+            }
+            let mut row = row!(
+                Container::new(label).width(Length::FillPortion(2)),
+                size,
+                modified
+            );
+            for column in self.extensions.columns() {
+                let value = file
+                    .data()
+                    .filter(|data| !data.is_parent)
+                    .map(|data| column.value(&data.path))
+                    .unwrap_or_default();
+                row = row.push(text(value).width(Length::FillPortion(1)));
+            }
+            let mut item = button(row)
+                .on_press_maybe((!dimmed).then(|| Message::ContentClicked(file.clone())))
+                .width(Length::Fill)
+                .height(self.style.row_height);
+            let selected = file
+                .data()
+                .is_some_and(|data| self.selected.contains(&data.path));
+            if selected || self.config.striped_rows {
+                let background = match selected {
+                    true => Some(selection),
+                    false => (position % 2 == 1).then_some(stripe),
+                };
+                item = item.style(theme::Button::custom(style::Row { background }));
+            }
+            let movable = self.archive.is_none() && !paths::is_computer(&self.current_dir);
+            let item = Drag::new(item)
+                .on_drag(
+                    file.data()
+                        .filter(|data| movable && !data.is_parent && !dimmed)
+                        .map(|data| Message::DragStart(data.path.clone())),
+                )
+                .on_drop(match file {
+                    Content::Directory(data) if movable => Some(Message::DropOn(data.path.clone())),
+                    _ => None,
+                })
+                .highlight(self.drop_highlight());
+            let item: Element<Message> = match file {
+                Content::Directory(data) if !data.is_parent => mouse_area(item)
+                    .on_enter(Message::Prefetch(prefetch::Message::Hover(
+                        data.path.clone(),
+                    )))
+                    .into(),
+                _ => item.into(),
+            };
+            col = col.push(
+                row!(Container::new(select).width(Length::Fixed(30.)), item)
+                    .align_items(Alignment::Center),
+            );
+        }
+
+        let list = scrollable(col)
+            .id(listing())
+            .on_scroll(Message::ListingScrolled)
+            .width(Length::Fill)
+            .height(Length::Fill);
+        let list = Band::new(
+            list,
+            self.listing_scroll.0,
+            line as f32 * self.style.row_height,
+        )
+        .on_band(Message::Band)
+        .on_release(Message::BandReleased)
+        .color(selection);
+        match self.view.group_by {
+            GroupBy::None => list.into(),
+            _ => column!(
+                heading_row(
+                    sticky
+                        .map(|heading| heading.label.clone())
+                        .unwrap_or_default()
+                ),
+                list
+            )
+            .into(),
+        }
+    }
+}
+
+fn heading(grouping: &Grouping, content: &Content) -> Option<Heading> {
+    match content {
+        Content::Directory(data) if data.is_parent => None,
+        Content::Directory(data) => {
+            grouping.heading(&data.name.to_string_lossy(), true, data.modified)
+        }
+        Content::File(data) => grouping.heading(&data.name.to_string_lossy(), false, data.modified),
+        Content::Corrupt(_) => grouping.unreadable(),
+    }
+}
+
+/// What the system's own file index is called, where there is one.
+fn system_index_label() -> Option<&'static str> {
+    if cfg!(target_os = "linux") {
+        Some("Search the locate database too")
+    } else if cfg!(target_os = "macos") {
+        Some("Search with Spotlight too")
+    } else {
+        None
+    }
+}
+
+fn uri_list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| paths::to_uri(path) + "\r\n")
+        .collect()
+}
+
+fn open(path: PathBuf) -> Command<Message> {
+    Command::perform(
+        task::blocking({
+            let path = path.clone();
+            move || system::open(&path).map_err(|error| error.to_string())
+        }),
+        move |result| Message::Opened(path, result),
+    )
+}
+
+fn address_bar() -> text_input::Id {
+    text_input::Id::new("address-bar")
+}
+
+fn filter_box() -> text_input::Id {
+    text_input::Id::new("filter")
+}
+
+fn listing() -> scrollable::Id {
+    scrollable::Id::new("listing")
+}
+
+fn list_volumes() -> Command<Message> {
+    Command::perform(
+        task::blocking(|| (mounts::volumes(), udisks::unmounted())),
+        |(volumes, drives)| Message::Sidebar(sidebar::Message::Volumes(volumes, drives)),
+    )
+}
+
+fn dir_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn content_from_path(path: PathBuf, limits: Limits) -> Content {
+    match ContentData::new(path.clone(), false, limits.keep_metadata) {
+        Ok(data) if path.is_dir() => Content::Directory(data),
+        Ok(data) => Content::File(data),
+        Err(error) => Content::Corrupt(format!("{}: {}", path.display(), error)),
+    }
+}
+
+type Batches = mpsc::UnboundedSender<Result<Vec<Content>, String>>;
+
+/// All of `dir` read at once off the disk, or `None` if it cannot be.
+fn read_ahead(dir: &Path, limits: Limits) -> Option<Vec<Content>> {
+    let (sender, mut receiver) = mpsc::unbounded();
+    list_disk(dir, limits, &sender);
+    drop(sender);
+    let mut entries = Vec::new();
+    while let Ok(Some(batch)) = receiver.try_next() {
+        entries.extend(batch.ok()?);
+    }
+    Some(entries)
+}
+
+#[tracing::instrument(skip(sender))]
+fn get_dir_content(cwd: PathBuf, limits: Limits, sender: Batches) {
+    match vfs::is_disk_folder(&cwd) {
+        true => list_disk(&cwd, limits, &sender),
+        false => list(&cwd, limits, &sender),
+    }
+}
+
+/// Reads the entry names first, which is quick even on slow storage,
+/// then gathers their metadata from several threads at once. Each
+/// thread sends what it has whenever a batch fills up or the update
+/// interval passes, so cold network or spinning disks fill the listing
+/// progressively.
+fn list_disk(dir: &Path, limits: Limits, sender: &Batches) {
+    let started = Instant::now();
+    let mut unreadable = Vec::new();
+    let paths: Vec<PathBuf> = match vfs::for_path(dir).read_dir(dir) {
+        Ok(entries) => entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Ok(path) => Some(path),
+                Err(error) => {
+                    tracing::warn!(%error, "unreadable directory entry");
+                    unreadable.push(Content::Corrupt(error.to_string()));
+                    None
+                }
+            })
+            .collect(),
+        Err(error) => {
+            tracing::warn!(%error, "cannot read directory");
+            let _ = sender.unbounded_send(Err(error.to_string()));
+            return;
+        }
+    };
+    if !unreadable.is_empty() && sender.unbounded_send(Ok(unreadable)).is_err() {
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    let abandoned = AtomicBool::new(false);
+    let workers = limits.workers.min(paths.len()).max(1);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let mut files = Vec::with_capacity(limits.batch);
+                let mut sent = Instant::now();
+
+                while !abandoned.load(Ordering::Relaxed) {
+                    let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    files.push(content_from_path(path.clone(), limits));
+
+                    if files.len() == limits.batch || sent.elapsed() >= rendering::update_interval()
+                    {
+                        let batch = std::mem::replace(&mut files, Vec::with_capacity(limits.batch));
+                        if sender.unbounded_send(Ok(batch)).is_err() {
+                            abandoned.store(true, Ordering::Relaxed);
+                        }
+                        sent = Instant::now();
+                    }
+                }
+
+                if !files.is_empty() {
+                    let _ = sender.unbounded_send(Ok(files));
+                }
+            });
+        }
+    });
+
+    if abandoned.load(Ordering::Relaxed) {
+        tracing::debug!("listing abandoned");
+    } else {
+        tracing::debug!(entries = paths.len(), workers, elapsed = ?started.elapsed(), "listed directory");
+    }
+}
+
+/// A folder its backend lists in one go, saying what each entry is: an
+/// archive's, or one on another machine.
+fn list(dir: &Path, limits: Limits, sender: &Batches) {
+    match vfs::for_dir(dir).list(dir) {
+        Ok(entries) => send_all(
+            entries.into_iter().map(|entry| match entry {
+                Ok(entry) => content_from_entry(entry),
+                Err(error) => {
+                    tracing::warn!(%error, "unreadable directory entry");
+                    Content::Corrupt(error.to_string())
+                }
+            }),
+            limits,
+            sender,
+        ),
+        Err(error) => {
+            tracing::warn!(%error, dir = %dir.display(), "cannot list folder");
+            let _ = sender.unbounded_send(Err(error.to_string()));
+        }
+    }
+}
+
+fn content_from_entry(entry: vfs::Entry) -> Content {
+    let data = ContentData {
+        name: entry
+            .path
+            .file_name()
+            .unwrap_or(entry.path.as_os_str())
+            .to_os_string(),
+        size: entry.meta.len,
+        modified: entry.meta.modified,
+        link_target: entry.link_target,
+        path: entry.path,
+        ..ContentData::default()
+    };
+    match entry.meta.is_dir {
+        true => Content::Directory(data),
+        false => Content::File(data),
+    }
+}
+
+/// Sends entries read all at once in batches of the usual size.
+fn send_all(mut entries: impl Iterator<Item = Content>, limits: Limits, sender: &Batches) {
+    loop {
+        let batch: Vec<Content> = entries.by_ref().take(limits.batch).collect();
+        if batch.is_empty() || sender.unbounded_send(Ok(batch)).is_err() {
+            return;
+        }
+    }
+}
+
+fn matches_filter(content: &Content, filter: &str, config: &Config, tags: &Tags) -> bool {
+    match content {
+        Content::Directory(data) if data.is_parent => true,
+        Content::File(data)
+            if !config.show_apple_double && data.name.to_string_lossy().starts_with("._") =>
+        {
+            false
+        }
+        Content::File(data) | Content::Directory(data)
+            if config.hide_hidden && data.name.to_string_lossy().starts_with('.') =>
+        {
+            false
+        }
+        _ => match tags::wanted(filter) {
+            Some(tag) => content
+                .data()
+                .is_some_and(|data| tags.has(&data.path, &tag)),
+            None => filter.is_empty() || content.to_string().to_lowercase().contains(filter),
+        },
+    }
+}
+
+/// The window a message from its events is about.
+fn window_of(message: &Message) -> Option<window::Id> {
+    match message {
+        Message::WindowFocused(id)
+        | Message::WindowUnfocused(id)
+        | Message::Resized(id, ..)
+        | Message::Moved(id, ..)
+        | Message::CloseRequested(id)
+        | Message::FileDropped(id, _) => Some(*id),
+        _ => None,
+    }
+}
+
+/// Whether `message` comes of a key pressed, which only the window with
+/// the focus takes.
+fn from_keyboard(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::Shortcut(_)
+            | Message::Typed(_)
+            | Message::CompletionMove(_)
+            | Message::Palette(palette::Message::Move(_))
+            | Message::Escape
+            | Message::Tab(_)
+            | Message::OpenSelected
+            | Message::SelectFirst
+            | Message::SelectLast
+            | Message::MovePage(_)
+    )
+}
+
+fn is_dark(theme: &Theme) -> bool {
+    let background = theme.palette().background;
+    background.r + background.g + background.b < 1.5
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(unix)]
+fn same_volume(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::symlink_metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(windows)]
+fn same_volume(a: &Path, b: &Path) -> bool {
+    a.components().next() == b.components().next()
+}
+
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+mod rows;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,288 @@
+//! The rows of the listing worked out from what is listed alone, apart
+//! from how they are drawn: their order, which the filter lets through,
+//! the line each goes on under the group headings, and how the selection
+//! moves over them.
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{heading, matches_filter, Content};
+use crate::collation;
+use crate::config::{Config, SortBy};
+use crate::groups::{Grouping, Heading};
+use crate::tags::Tags;
+
+/// Orders `content` by `sort` within the groups of `grouping`, the parent
+/// folder first, then folders, files and what could not be read, and
+/// gives the heading of each in the new order.
+pub fn sort(content: &mut [Content], sort: SortBy, grouping: &Grouping) -> Vec<Option<Heading>> {
+    content.sort_by_cached_key(|content| {
+        let heading = heading(grouping, content);
+        let group = match content {
+            Content::Directory(data) if data.is_parent => 0,
+            Content::Directory(_) => 1,
+            Content::File(_) => 2,
+            Content::Corrupt(_) => 3,
+        };
+        let Some(data) = content.data() else {
+            return (
+                heading,
+                group,
+                Reverse(0),
+                String::new(),
+                collation::key(""),
+            );
+        };
+        let name = collation::key(&data.name);
+        match sort {
+            SortBy::Name => (heading, group, Reverse(0), String::new(), name),
+            SortBy::Size => (heading, group, Reverse(data.size), String::new(), name),
+            SortBy::Modified => {
+                let seconds = data
+                    .modified
+                    .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map_or(0, |since| since.as_secs());
+                (heading, group, Reverse(seconds), String::new(), name)
+            }
+            SortBy::Type => {
+                let extension = Path::new(&data.name)
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                (heading, group, Reverse(0), extension, name)
+            }
+        }
+    });
+    content
+        .iter()
+        .map(|content| heading(grouping, content))
+        .collect()
+}
+
+/// The entries `filter`, in lowercase, lets through, by index and no more
+/// than `rendered` of them, with how many it lets through in all.
+pub fn filter(
+    content: &[Content],
+    filter: &str,
+    config: &Config,
+    tags: &Tags,
+    rendered: usize,
+) -> (Vec<usize>, usize) {
+    let mut visible = Vec::new();
+    let mut matching = 0;
+    for (index, content) in content.iter().enumerate() {
+        if matches_filter(content, filter, config, tags) {
+            matching += 1;
+            if visible.len() < rendered {
+                visible.push(index);
+            }
+        }
+    }
+    (visible, matching)
+}
+
+/// The heading `visible[position]` starts a group under, drawn above it
+/// unless it is the very first row.
+pub fn starts_group<'a>(
+    visible: &[usize],
+    headings: &'a [Option<Heading>],
+    position: usize,
+) -> Option<&'a Heading> {
+    let heading = |position: usize| headings.get(visible[position])?.as_ref();
+    let current = heading(position)?;
+    match position.checked_sub(1) {
+        Some(previous) if heading(previous) == Some(current) => None,
+        _ => Some(current),
+    }
+}
+
+/// The line `visible[position]` is drawn on, counting the headings.
+pub fn line(visible: &[usize], headings: &[Option<Heading>], position: usize) -> usize {
+    position
+        + (1..=position)
+            .filter(|&position| starts_group(visible, headings, position).is_some())
+            .count()
+}
+
+/// Where stepping `step` rows from `current` lands among `len`, stopping
+/// at either end; with nothing selected, it starts at the end stepped
+/// away from.
+pub fn step(current: Option<usize>, step: isize, len: usize) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    Some(match current {
+        Some(current) => current.saturating_add_signed(step).min(last),
+        None if step < 0 => last,
+        None => 0,
+    })
+}
+
+/// How far to scroll for the row from `top` to `bottom` to be in sight of
+/// the viewport `height` tall at `offset`, or `None` when it is already.
+pub fn reveal(top: f32, bottom: f32, offset: f32, height: f32) -> Option<f32> {
+    if top < offset {
+        Some(top)
+    } else if bottom > offset + height {
+        Some(bottom - height)
+    } else {
+        None
+    }
+}
+
+/// What a rubber band from `top` to `bottom` selects: `base`, then the
+/// rows of `selectable` it reaches into, each a line of `height`.
+pub fn band<'a>(
+    selectable: impl Iterator<Item = (usize, &'a PathBuf)>,
+    base: Vec<PathBuf>,
+    top: f32,
+    bottom: f32,
+    height: f32,
+) -> Vec<PathBuf> {
+    let spanned: Vec<PathBuf> = selectable
+        .filter(|(line, _)| {
+            let line = *line as f32 * height;
+            line + height > top && line < bottom
+        })
+        .map(|(_, path)| path.clone())
+        .filter(|path| !base.contains(path))
+        .collect();
+    let mut selected = base;
+    selected.extend(spanned);
+    selected
+}
+
+/// Those of `selectable` not in `selected`.
+pub fn invert<'a>(
+    selectable: impl Iterator<Item = &'a PathBuf>,
+    selected: &[PathBuf],
+) -> Vec<PathBuf> {
+    selectable
+        .filter(|path| !selected.contains(path))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::super::ContentData;
+    use super::*;
+    use crate::config::GroupBy;
+
+    fn entry(name: &str, folder: bool, size: u64) -> Content {
+        let data = ContentData {
+            path: PathBuf::from("/").join(name),
+            name: OsString::from(name),
+            size,
+            ..ContentData::default()
+        };
+        match folder {
+            true => Content::Directory(data),
+            false => Content::File(data),
+        }
+    }
+
+    fn parent() -> Content {
+        Content::Directory(ContentData {
+            is_parent: true,
+            name: OsString::from(".."),
+            ..ContentData::default()
+        })
+    }
+
+    fn names(content: &[Content]) -> Vec<String> {
+        content.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn sorts_the_parent_then_folders_then_files() {
+        let mut content = vec![
+            Content::Corrupt(String::from("broken")),
+            entry("b.txt", false, 5),
+            entry("docs", true, 0),
+            parent(),
+            entry("A.txt", false, 3),
+        ];
+        let headings = sort(&mut content, SortBy::Name, &Grouping::new(GroupBy::None));
+        assert_eq!(
+            names(&content),
+            ["..", "docs", "A.txt", "b.txt", "Unreadable entry: broken"]
+        );
+        assert!(headings.iter().all(Option::is_none));
+
+        sort(&mut content, SortBy::Size, &Grouping::new(GroupBy::None));
+        assert_eq!(names(&content)[2..4], ["b.txt", "A.txt"]);
+    }
+
+    #[test]
+    fn filters_up_to_what_is_rendered_but_counts_all() {
+        let content = vec![
+            parent(),
+            entry("report.pdf", false, 1),
+            entry("notes.txt", false, 1),
+            entry("old report.txt", false, 1),
+        ];
+        let config = Config::default();
+        let tags = Tags::default();
+        assert_eq!(
+            filter(&content, "report", &config, &tags, 100),
+            (vec![0, 1, 3], 3)
+        );
+        assert_eq!(
+            filter(&content, "report", &config, &tags, 2),
+            (vec![0, 1], 3)
+        );
+        assert_eq!(filter(&content, "", &config, &tags, 100).1, 4);
+    }
+
+    #[test]
+    fn steps_stop_at_either_end() {
+        assert_eq!(step(None, 1, 3), Some(0));
+        assert_eq!(step(None, -1, 3), Some(2));
+        assert_eq!(step(Some(1), 5, 3), Some(2));
+        assert_eq!(step(Some(1), -5, 3), Some(0));
+        assert_eq!(step(Some(0), 1, 0), None);
+    }
+
+    #[test]
+    fn scrolls_only_as_far_as_the_row_needs() {
+        assert_eq!(reveal(40., 60., 0., 100.), None);
+        assert_eq!(reveal(100., 120., 0., 100.), Some(20.));
+        assert_eq!(reveal(20., 40., 50., 100.), Some(20.));
+    }
+
+    #[test]
+    fn headings_take_a_line_of_their_own() {
+        let grouping = Grouping::new(GroupBy::Letter);
+        let headings: Vec<_> = ["apple", "avocado", "banana"]
+            .into_iter()
+            .map(|name| grouping.heading(name, false, None))
+            .collect();
+        let visible = [0, 1, 2];
+        assert_eq!(starts_group(&visible, &headings, 0), headings[0].as_ref());
+        assert_eq!(starts_group(&visible, &headings, 1), None);
+        assert_eq!(line(&visible, &headings, 1), 1);
+        assert_eq!(line(&visible, &headings, 2), 3);
+    }
+
+    #[test]
+    fn the_band_adds_what_it_reaches_to_its_base() {
+        let paths: Vec<PathBuf> = ["/a", "/b", "/c"].into_iter().map(PathBuf::from).collect();
+        let rows = || {
+            paths
+                .iter()
+                .enumerate()
+                .map(|(line, path)| (line + 1, path))
+        };
+        assert_eq!(band(rows(), Vec::new(), 15., 25., 10.), &paths[..2]);
+        assert_eq!(
+            band(rows(), vec![paths[2].clone()], 15., 16., 10.),
+            [paths[2].clone(), paths[0].clone()]
+        );
+        assert_eq!(
+            invert(paths.iter(), &paths[1..2]),
+            [paths[0].clone(), paths[2].clone()]
+        );
+    }
+}
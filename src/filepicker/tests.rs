@@ -0,0 +1,514 @@
+//! The picker driven through sequences of messages against a folder held
+//! in memory, or one on disk for what jobs do to files, checking what the
+//! listing shows after each: where it is, the entries in order and which
+//! are selected.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+use iced::futures::channel::mpsc;
+use iced::futures::executor::block_on;
+use iced::futures::stream;
+
+use super::*;
+use crate::config::ManyItems;
+use crate::vfs::memory;
+
+/// Keeps the settings, sessions and tags the tests write away from the
+/// user's.
+fn isolate() -> PathBuf {
+    static ONCE: Once = Once::new();
+    let home = std::env::temp_dir().join(format!("iced-fm-tests-{}", std::process::id()));
+    ONCE.call_once(|| {
+        std::env::set_var("XDG_CONFIG_HOME", home.join("config"));
+        std::env::set_var("XDG_DATA_HOME", home.join("data"));
+        std::env::set_var("XDG_CACHE_HOME", home.join("cache"));
+    });
+    home
+}
+
+struct Harness {
+    root: PathBuf,
+    on_disk: bool,
+    picker: FilePicker,
+}
+
+impl Harness {
+    /// A picker at an empty folder of its own, named after the test.
+    fn new(name: &str) -> Harness {
+        isolate();
+        let root = Path::new(memory::ROOT).join(name);
+        memory::FS.clear(&root);
+        memory::FS.add_dir(&root);
+        Harness {
+            root,
+            on_disk: false,
+            picker: FilePicker::new().0,
+        }
+    }
+
+    /// The same in a temporary folder, for the jobs, which reach past
+    /// the filesystem the tests keep in memory.
+    fn on_disk(name: &str) -> Harness {
+        let root = isolate().join("disk").join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Harness {
+            root,
+            on_disk: true,
+            picker: FilePicker::new().0,
+        }
+    }
+
+    fn dir(self, path: &str) -> Self {
+        let path = self.root.join(path);
+        match self.on_disk {
+            true => fs::create_dir_all(path).unwrap(),
+            false => memory::FS.add_dir(&path),
+        }
+        self
+    }
+
+    /// A file of `size` bytes.
+    fn file(self, path: &str, size: usize) -> Self {
+        let path = self.root.join(path);
+        let contents = vec![b'x'; size];
+        match self.on_disk {
+            true => {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, contents).unwrap();
+            }
+            false => memory::FS.add_file(&path, &contents),
+        }
+        self
+    }
+
+    /// Whether `path` of the tree is on disk.
+    fn exists(&self, path: &str) -> bool {
+        self.root.join(path).exists()
+    }
+
+    /// Opens `path` of the tree, "" for its top.
+    fn open(mut self, path: &str) -> Self {
+        let dir = self.root.join(path);
+        self.send(Message::Navigate(dir));
+        self
+    }
+
+    /// Updates the picker with `message`, then lists the folder it is at
+    /// if that started, as its subscription would.
+    fn send(&mut self, message: Message) {
+        let _ = self.picker.update(message);
+        while self.picker.loading {
+            let listing = self.picker.listing;
+            let (sender, mut receiver) = mpsc::unbounded();
+            get_dir_content(
+                self.picker.current_dir.clone(),
+                self.picker.limits(),
+                sender,
+            );
+            while let Ok(Some(batch)) = receiver.try_next() {
+                let _ = self.picker.update(Message::Listed(listing, batch));
+            }
+            let _ = self.picker.update(Message::ListingDone(listing));
+        }
+    }
+
+    /// Runs the jobs through their subscriptions, one after the other,
+    /// until none is left; a name taken is answered with Enter.
+    fn finish_jobs(&mut self) {
+        while let Some(recipe) = self.picker.jobs.subscription().into_recipes().pop() {
+            let mut reported = recipe.stream(Box::pin(stream::empty()));
+            while let Some(message) = block_on(reported.next()) {
+                let jobs::Message::Progress(_, progress) = &message else {
+                    continue;
+                };
+                let conflict = matches!(progress, jobs::Progress::Conflict { .. });
+                let ended = matches!(
+                    progress,
+                    jobs::Progress::Finished
+                        | jobs::Progress::Cancelled
+                        | jobs::Progress::Failed(_)
+                );
+                self.send(Message::Jobs(message));
+                if conflict {
+                    self.confirm();
+                }
+                if ended {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Presses Enter on the prompt on show, which answers with the button
+    /// it starts on.
+    fn confirm(&mut self) {
+        let message = self
+            .picker
+            .prompts
+            .on_key(keyboard::key::Named::Enter, keyboard::Modifiers::empty())
+            .expect("no prompt on show");
+        self.send(Message::Prompt(message));
+    }
+
+    /// Clicks outside the prompt on show.
+    fn dismiss(&mut self) {
+        let message = self.picker.prompts.on_blur().expect("no prompt to dismiss");
+        self.send(Message::Prompt(message));
+    }
+
+    /// Ticks the entries `names` of the folder shown, as their checkboxes
+    /// do.
+    fn select(&mut self, names: &[&str]) {
+        for name in names {
+            let path = self.picker.current_dir.join(name);
+            self.send(Message::ContentSelected(path, true));
+        }
+    }
+
+    /// Activates the entry shown as `name`, ".." for the parent.
+    fn activate(&mut self, name: &str) {
+        let content = self
+            .picker
+            .visible
+            .iter()
+            .map(|&index| &self.picker.content[index])
+            .find(|content| {
+                content.data().is_some_and(|data| match data.is_parent {
+                    true => name == "..",
+                    false => data.name == name,
+                })
+            })
+            .unwrap_or_else(|| panic!("no {} in the listing", name))
+            .clone();
+        self.send(Message::ContentActivated(content));
+    }
+
+    /// The folder shown, from the top of the tree, then an entry a line in
+    /// the order shown: "> " before the selected, "/" after folders.
+    fn snapshot(&self) -> Vec<String> {
+        let at = match self.picker.current_dir.strip_prefix(&self.root) {
+            Ok(path) => format!("/{}", path.display()),
+            Err(_) => self.picker.current_dir.display().to_string(),
+        };
+        let entries = self.picker.visible.iter().map(|&index| {
+            let content = &self.picker.content[index];
+            let Some(data) = content.data() else {
+                return format!("  ! {}", content);
+            };
+            let marker = match self.picker.selected.contains(&data.path) {
+                true => "> ",
+                false => "  ",
+            };
+            let name = match data.is_parent {
+                true => "..".into(),
+                false => data.name.to_string_lossy(),
+            };
+            let slash = match content {
+                Content::Directory(_) => "/",
+                _ => "",
+            };
+            format!("{}{}{}", marker, name, slash)
+        });
+        std::iter::once(at).chain(entries).collect()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        match self.on_disk {
+            true => {
+                let _ = fs::remove_dir_all(&self.root);
+            }
+            false => memory::FS.clear(&self.root),
+        }
+    }
+}
+
+#[test]
+fn folders_come_first_then_files_by_name() {
+    let harness = Harness::new("sorted")
+        .file("b.txt", 1)
+        .dir("photos")
+        .file("a.txt", 1)
+        .dir("docs")
+        .file("C.txt", 1)
+        .open("");
+
+    assert_eq!(
+        harness.snapshot(),
+        [
+            "/",
+            "  ../",
+            "  docs/",
+            "  photos/",
+            "  a.txt",
+            "  b.txt",
+            "  C.txt"
+        ]
+    );
+}
+
+#[test]
+fn opening_a_folder_lists_it_and_its_parent_goes_back() {
+    let mut harness = Harness::new("opening")
+        .file("docs/letter.txt", 1)
+        .dir("docs/old")
+        .file("top.txt", 1)
+        .open("");
+
+    harness.activate("docs");
+    assert_eq!(
+        harness.snapshot(),
+        ["/docs", "  ../", "  old/", "  letter.txt"]
+    );
+
+    harness.activate("..");
+    assert_eq!(harness.snapshot(), ["/", "  ../", "  docs/", "  top.txt"]);
+}
+
+#[test]
+fn back_and_forward_retrace_the_folders_opened() {
+    let mut harness = Harness::new("history")
+        .dir("docs/old")
+        .file("top.txt", 1)
+        .open("");
+    harness.activate("docs");
+    harness.activate("old");
+
+    harness.send(Message::Back);
+    assert_eq!(harness.snapshot()[0], "/docs");
+    harness.send(Message::Back);
+    assert_eq!(harness.snapshot()[0], "/");
+    harness.send(Message::Forward);
+    assert_eq!(harness.snapshot()[0], "/docs");
+
+    // A window opened again on it goes back the same way.
+    let closed = harness.picker.closed();
+    let mut reopened = Harness::new("history-reopened");
+    reopened.send(Message::Navigate(closed.dir.clone()));
+    reopened.picker.restore(closed);
+    reopened.send(Message::Forward);
+    assert_eq!(reopened.picker.current_dir, harness.root.join("docs/old"));
+}
+
+#[test]
+fn sorting_by_size_puts_the_largest_first() {
+    let mut harness = Harness::new("by-size")
+        .file("small.txt", 10)
+        .file("large.txt", 300)
+        .file("medium.txt", 100)
+        .dir("folder")
+        .open("");
+
+    harness.send(Message::SortBy(SortBy::Size));
+    assert_eq!(
+        harness.snapshot(),
+        [
+            "/",
+            "  ../",
+            "  folder/",
+            "  large.txt",
+            "  medium.txt",
+            "  small.txt"
+        ]
+    );
+}
+
+#[test]
+fn the_filter_narrows_the_listing_until_it_is_cleared() {
+    let mut harness = Harness::new("filter")
+        .file("report.pdf", 1)
+        .file("notes.txt", 1)
+        .file("draft report.txt", 1)
+        .open("");
+
+    harness.send(Message::FilterInput("report".into()));
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "  draft report.txt", "  report.pdf"]
+    );
+
+    harness.send(Message::Escape);
+    assert_eq!(
+        harness.snapshot(),
+        [
+            "/",
+            "  ../",
+            "  draft report.txt",
+            "  notes.txt",
+            "  report.pdf"
+        ]
+    );
+}
+
+#[test]
+fn the_selection_moves_and_is_cleared_by_navigating() {
+    let mut harness = Harness::new("selection")
+        .dir("inner")
+        .file("one.txt", 1)
+        .file("two.txt", 1)
+        .open("");
+
+    harness.send(Message::MoveSelection(1));
+    harness.send(Message::MoveSelection(1));
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "> inner/", "  one.txt", "  two.txt"]
+    );
+
+    harness.send(Message::SelectLast);
+    assert_eq!(harness.snapshot().last().unwrap(), "> two.txt");
+
+    harness.send(Message::SelectFirst);
+    harness.send(Message::MoveSelection(1));
+    harness.send(Message::OpenSelected);
+    assert_eq!(harness.snapshot(), ["/inner", "  ../"]);
+}
+
+#[test]
+fn select_all_leaves_out_the_parent_and_inverting_flips_each() {
+    let mut harness = Harness::new("select-all")
+        .dir("inner")
+        .file("one.txt", 1)
+        .file("two.txt", 1)
+        .open("");
+
+    harness.send(Message::SelectAll);
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "> inner/", "> one.txt", "> two.txt"]
+    );
+
+    harness.send(Message::ContentSelected(
+        harness.root.join("one.txt"),
+        false,
+    ));
+    harness.send(Message::InvertSelection);
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "  inner/", "> one.txt", "  two.txt"]
+    );
+}
+
+#[test]
+fn the_rubber_band_selects_the_rows_it_spans_and_adds_with_ctrl() {
+    let mut harness = Harness::new("band")
+        .file("a.txt", 1)
+        .file("b.txt", 1)
+        .file("c.txt", 1)
+        .file("d.txt", 1)
+        .open("");
+    let row = harness.picker.style.row_height;
+
+    // From the middle of the row under `..` to that of the next.
+    harness.send(Message::Band(row * 1.5, row * 2.5));
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "> a.txt", "> b.txt", "  c.txt", "  d.txt"]
+    );
+    harness.send(Message::Band(row * 1.5, row * 1.6));
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "> a.txt", "  b.txt", "  c.txt", "  d.txt"]
+    );
+    harness.send(Message::BandReleased);
+
+    harness.send(Message::ModifiersChanged(keyboard::Modifiers::CTRL));
+    harness.send(Message::Band(row * 3.2, row * 10.));
+    assert_eq!(
+        harness.snapshot(),
+        ["/", "  ../", "> a.txt", "  b.txt", "> c.txt", "> d.txt"]
+    );
+}
+
+#[test]
+fn pasting_what_was_copied_copies_it_on_disk() {
+    let mut harness = Harness::on_disk("copy")
+        .file("letter.txt", 10)
+        .dir("into")
+        .open("");
+
+    harness.select(&["letter.txt"]);
+    harness.send(Message::Copy);
+    harness.activate("into");
+    harness.send(Message::Pasted(None));
+    harness.finish_jobs();
+
+    assert_eq!(harness.snapshot(), ["/into", "  ../", "  letter.txt"]);
+    assert!(harness.exists("letter.txt"));
+    assert!(harness.exists("into/letter.txt"));
+}
+
+#[test]
+fn a_move_pasted_is_undone_back_where_it_was() {
+    let mut harness = Harness::on_disk("move")
+        .file("photo.jpg", 10)
+        .dir("album")
+        .open("");
+
+    harness.select(&["photo.jpg"]);
+    harness.send(Message::Cut);
+    harness.activate("album");
+    harness.send(Message::Pasted(None));
+    harness.finish_jobs();
+    assert_eq!(harness.snapshot(), ["/album", "  ../", "  photo.jpg"]);
+    assert!(!harness.exists("photo.jpg"));
+
+    harness.send(Message::Undo);
+    assert_eq!(harness.snapshot(), ["/album", "  ../"]);
+    assert!(harness.exists("photo.jpg"));
+}
+
+#[test]
+fn deleting_asks_first_and_cancelling_keeps_the_files() {
+    let mut harness = Harness::on_disk("delete")
+        .file("keep.txt", 1)
+        .file("old.txt", 1)
+        .open("");
+
+    harness.select(&["old.txt"]);
+    harness.send(Message::Delete);
+    harness.dismiss();
+    harness.finish_jobs();
+    assert!(harness.exists("old.txt"));
+
+    harness.send(Message::Delete);
+    harness.confirm();
+    harness.finish_jobs();
+    assert_eq!(harness.snapshot(), ["/", "  ../", "  keep.txt"]);
+    assert!(!harness.exists("old.txt"));
+}
+
+#[test]
+fn trashing_more_than_asked_about_waits_for_an_answer() {
+    let mut harness = Harness::new("many")
+        .file("a.txt", 1)
+        .file("b.txt", 1)
+        .file("c.txt", 1)
+        .open("");
+    harness.picker.config.ask_over = ManyItems(2);
+
+    harness.send(Message::SelectAll);
+    harness.send(Message::Trash);
+    assert!(harness.picker.prompts.is_open());
+    harness.dismiss();
+    assert!(harness.picker.jobs.is_empty());
+    assert_eq!(harness.picker.selected.len(), 3);
+}
+
+#[test]
+fn a_new_folder_is_created_and_undone() {
+    let mut harness = Harness::on_disk("new-folder").open("");
+
+    harness.send(Message::NewEntry(EntryKind::Folder));
+    harness.send(Message::NewEntryInput("Projects".into()));
+    harness.send(Message::CreateEntry);
+    assert_eq!(harness.snapshot(), ["/", "  ../", "  Projects/"]);
+
+    harness.send(Message::Undo);
+    assert_eq!(harness.snapshot(), ["/", "  ../"]);
+    assert!(!harness.exists("Projects"));
+}
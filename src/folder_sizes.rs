@@ -0,0 +1,134 @@
+//! What the Size column shows for folders, worked out in the background
+//! as they come into the listing and kept until they change.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+
+use crate::config::FolderSize;
+use crate::jobs::format_bytes;
+use crate::properties;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// `None` when the folder could not be read.
+    Measured(FolderSize, PathBuf, Option<u64>),
+}
+
+#[derive(Default)]
+pub struct FolderSizes {
+    items: HashMap<PathBuf, Option<u64>>,
+    totals: HashMap<PathBuf, Option<u64>>,
+    /// Counts forgetting, to start working out again.
+    generation: usize,
+}
+
+impl FolderSizes {
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Measured(FolderSize::Items, dir, items) => {
+                self.items.insert(dir, items);
+            }
+            Message::Measured(FolderSize::Total, dir, bytes) => {
+                self.totals.insert(dir, bytes);
+            }
+            Message::Measured(FolderSize::Blank, ..) => {}
+        }
+    }
+
+    fn known(&self, what: FolderSize) -> Option<&HashMap<PathBuf, Option<u64>>> {
+        match what {
+            FolderSize::Blank => None,
+            FolderSize::Items => Some(&self.items),
+            FolderSize::Total => Some(&self.totals),
+        }
+    }
+
+    /// Drops what was worked out for `path`, and the totals of the folders
+    /// holding it.
+    pub fn forget(&mut self, path: &Path) {
+        self.items.remove(path);
+        for dir in path.ancestors() {
+            self.totals.remove(dir);
+        }
+        self.generation += 1;
+    }
+
+    /// How much `dir` holds, when worked out already.
+    pub fn total(&self, dir: &Path) -> Option<u64> {
+        self.totals.get(dir).copied().flatten()
+    }
+
+    pub fn label(&self, what: FolderSize, dir: &Path) -> String {
+        let Some(known) = self.known(what) else {
+            return String::new();
+        };
+        match (what, known.get(dir)) {
+            (_, None) => String::from("…"),
+            (_, Some(None)) => String::from("?"),
+            (FolderSize::Items, Some(Some(1))) => String::from("1 item"),
+            (FolderSize::Items, Some(Some(items))) => format!("{} items", items),
+            (_, Some(Some(bytes))) => format_bytes(*bytes),
+        }
+    }
+
+    /// Works out `what` for those of `dirs` not known yet, one after the
+    /// other. A new `listing`, or anything forgotten, starts it over.
+    pub fn subscription(
+        &self,
+        what: FolderSize,
+        listing: usize,
+        dirs: Vec<PathBuf>,
+    ) -> Subscription<Message> {
+        struct Measuring;
+
+        let Some(known) = self.known(what) else {
+            return Subscription::none();
+        };
+        let dirs: Vec<PathBuf> = dirs
+            .into_iter()
+            .filter(|dir| !known.contains_key(dir))
+            .collect();
+        if dirs.is_empty() {
+            return Subscription::none();
+        }
+
+        subscription::channel(
+            (TypeId::of::<Measuring>(), what, listing, self.generation),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    for dir in dirs {
+                        let measured = match what {
+                            FolderSize::Items => fs::read_dir(&dir)
+                                .ok()
+                                .map(|entries| entries.count() as u64),
+                            _ => properties::measure(&dir, &AtomicBool::new(false))
+                                .ok()
+                                .map(|(bytes, _)| bytes),
+                        };
+                        if sender
+                            .unbounded_send(Message::Measured(what, dir, measured))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+}
@@ -0,0 +1,286 @@
+//! Folders as a tree beside the places, each listing its subfolders the
+//! first time it is opened. It follows the listing: going somewhere opens
+//! the tree down to there.
+
+use std::any::TypeId;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, column, horizontal_space, row, scrollable, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::collation;
+use crate::i18n::tr;
+use crate::paths;
+
+const INDENT: f32 = 12.;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Opens or closes the node at this index path from the roots.
+    Toggle(Vec<usize>),
+    Open(PathBuf),
+    Listed(PathBuf, Vec<PathBuf>),
+}
+
+pub enum Event {
+    Open(PathBuf),
+}
+
+struct Node {
+    path: PathBuf,
+    label: String,
+    expanded: bool,
+    /// `None` until listed.
+    children: Option<Vec<Node>>,
+}
+
+impl Node {
+    fn new(path: PathBuf) -> Node {
+        let label = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => path.display().to_string(),
+        };
+        Node {
+            path,
+            label,
+            expanded: false,
+            children: None,
+        }
+    }
+
+    /// Nodes that are open but not listed yet.
+    fn unlisted<'a>(&'a self, found: &mut Vec<&'a Path>) {
+        if !self.expanded {
+            return;
+        }
+        match &self.children {
+            Some(children) => children.iter().for_each(|child| child.unlisted(found)),
+            None => found.push(&self.path),
+        }
+    }
+
+    fn listed(&mut self, path: &Path, subfolders: &[PathBuf]) {
+        if paths::same(&self.path, path) {
+            if self.children.is_none() {
+                self.children = Some(subfolders.iter().cloned().map(Node::new).collect());
+            }
+            return;
+        }
+        for child in self.children.iter_mut().flatten() {
+            if paths::starts_with(path, &child.path) {
+                child.listed(path, subfolders);
+            }
+        }
+    }
+}
+
+pub struct FolderTree {
+    roots: Vec<Node>,
+    /// Where the listing is, opened down to as far as it has been listed.
+    current: PathBuf,
+}
+
+impl Default for FolderTree {
+    fn default() -> FolderTree {
+        let mut roots = Vec::new();
+        if let Some(home) = env::var_os("HOME").map(PathBuf::from) {
+            let mut node = Node::new(home);
+            node.label = String::from(tr("Home"));
+            roots.push(node);
+        }
+        if cfg!(windows) {
+            roots.extend(
+                ('A'..='Z')
+                    .map(|letter| PathBuf::from(format!("{}:\\", letter)))
+                    .filter(|drive| drive.is_dir())
+                    .map(Node::new),
+            );
+        } else {
+            roots.push(Node::new(PathBuf::from("/")));
+        }
+
+        FolderTree {
+            roots,
+            current: PathBuf::new(),
+        }
+    }
+}
+
+impl FolderTree {
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Toggle(index) => {
+                if let Some(node) = self.node_mut(&index) {
+                    node.expanded = !node.expanded;
+                }
+            }
+            Message::Open(path) => return Some(Event::Open(path)),
+            Message::Listed(path, subfolders) => {
+                for root in &mut self.roots {
+                    if paths::starts_with(&path, &root.path) {
+                        root.listed(&path, &subfolders);
+                    }
+                }
+                // Going on down to where the listing is.
+                self.reveal(&self.current.clone());
+            }
+        }
+
+        None
+    }
+
+    /// Opens the folders leading to `dir`, under the innermost root
+    /// holding it.
+    pub fn reveal(&mut self, dir: &Path) {
+        self.current = dir.to_path_buf();
+        let root = self
+            .roots
+            .iter_mut()
+            .filter(|root| paths::starts_with(dir, &root.path))
+            .max_by_key(|root| root.path.components().count());
+        let Some(mut node) = root else {
+            return;
+        };
+
+        loop {
+            if paths::same(&node.path, dir) {
+                return;
+            }
+            node.expanded = true;
+            let Some(children) = &mut node.children else {
+                return;
+            };
+            match children
+                .iter_mut()
+                .find(|child| paths::starts_with(dir, &child.path))
+            {
+                Some(child) => node = child,
+                None => return,
+            }
+        }
+    }
+
+    fn node_mut(&mut self, index: &[usize]) -> Option<&mut Node> {
+        let (&first, rest) = index.split_first()?;
+        let mut node = self.roots.get_mut(first)?;
+        for &i in rest {
+            node = node.children.as_mut()?.get_mut(i)?;
+        }
+
+        Some(node)
+    }
+
+    /// Lists the folders opened since the last time.
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Listing;
+
+        let mut unlisted = Vec::new();
+        self.roots
+            .iter()
+            .for_each(|root| root.unlisted(&mut unlisted));
+        Subscription::batch(unlisted.into_iter().map(|path| {
+            let path = path.to_path_buf();
+            subscription::channel(
+                (TypeId::of::<Listing>(), path.clone()),
+                1,
+                move |mut output| async move {
+                    use iced::futures::channel::oneshot;
+                    use iced::futures::SinkExt;
+
+                    let (sender, receiver) = oneshot::channel();
+                    std::thread::spawn({
+                        let path = path.clone();
+                        move || sender.send(subfolders(&path))
+                    });
+                    if let Ok(subfolders) = receiver.await {
+                        let _ = output.send(Message::Listed(path, subfolders)).await;
+                    }
+
+                    std::future::pending().await
+                },
+            )
+        }))
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let mut col = column!(text(tr("Folders")).size(14)).spacing(2);
+        let mut index = Vec::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            index.push(i);
+            col = self.push_node(col, root, &mut index);
+            index.pop();
+        }
+
+        scrollable(col)
+            .width(Length::Fixed(220.))
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn push_node<'a>(
+        &self,
+        mut col: iced::widget::Column<'a, Message>,
+        node: &'a Node,
+        index: &mut Vec<usize>,
+    ) -> iced::widget::Column<'a, Message> {
+        let arrow: Element<Message> = match &node.children {
+            Some(children) if children.is_empty() => horizontal_space().width(20).into(),
+            _ => button(text(if node.expanded { "▾" } else { "▸" }).size(12))
+                .on_press(Message::Toggle(index.clone()))
+                .style(theme::Button::Text)
+                .padding(2)
+                .width(20)
+                .into(),
+        };
+        let style = if paths::same(&node.path, &self.current) {
+            theme::Button::Primary
+        } else {
+            theme::Button::Text
+        };
+        col = col.push(
+            row!(
+                horizontal_space().width(INDENT * (index.len() - 1) as f32),
+                arrow,
+                button(text(&node.label))
+                    .on_press(Message::Open(node.path.clone()))
+                    .style(style)
+                    .padding([2, 5])
+                    .width(Length::Fill),
+            )
+            .align_items(Alignment::Center),
+        );
+
+        if let Some(children) = node.children.as_ref().filter(|_| node.expanded) {
+            for (i, child) in children.iter().enumerate() {
+                index.push(i);
+                col = self.push_node(col, child, index);
+                index.pop();
+            }
+        }
+
+        col
+    }
+}
+
+/// The folders directly in `dir`, by name, leaving out hidden ones and
+/// links so the tree cannot loop.
+fn subfolders(dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::debug!(%error, dir = %dir.display(), "cannot list folder for the tree");
+            return Vec::new();
+        }
+    };
+    let mut folders: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .collect();
+    folders.sort_by_cached_key(|path| path.file_name().map(collation::key));
+
+    folders
+}
@@ -0,0 +1,212 @@
+//! Version control status for folders inside a git repository, asked of
+//! the `git` command in the background each time the listing is made, so
+//! a missing or slow git never holds up browsing.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use iced::{subscription, Subscription};
+
+/// What a row's badge says, the most pressing first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Badge {
+    Conflicted,
+    Modified,
+    Staged,
+    Untracked,
+    Ignored,
+}
+
+impl Badge {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Badge::Conflicted => "Conflicted",
+            Badge::Modified => "Modified",
+            Badge::Staged => "Staged",
+            Badge::Untracked => "Untracked",
+            Badge::Ignored => "Ignored",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    /// `None` on a detached head.
+    branch: Option<String>,
+    /// Files by their path, and the folders holding changes.
+    badges: HashMap<PathBuf, Badge>,
+    /// Untracked or ignored folders, whose contents all share the badge.
+    folders: Vec<(PathBuf, Badge)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// What the listing `usize` found; `None` outside a repository.
+    Status(usize, Option<Status>),
+}
+
+#[derive(Default)]
+pub struct Git {
+    /// The work tree the listing is in.
+    root: Option<PathBuf>,
+    listing: usize,
+    status: Option<Status>,
+}
+
+impl Git {
+    pub fn update(&mut self, message: Message) {
+        let Message::Status(listing, status) = message;
+        if listing >= self.listing {
+            self.listing = listing;
+            self.status = status;
+        }
+    }
+
+    /// Looks for the work tree holding `dir`, forgetting the status of
+    /// another one.
+    pub fn follow(&mut self, dir: &Path) {
+        let root = repository(dir);
+        if root != self.root {
+            self.root = root;
+            self.status = None;
+        }
+    }
+
+    pub fn branch(&self) -> Option<&str> {
+        self.status.as_ref()?.branch.as_deref()
+    }
+
+    pub fn badge(&self, path: &Path) -> Option<Badge> {
+        let status = self.status.as_ref()?;
+        status.badges.get(path).copied().or_else(|| {
+            status
+                .folders
+                .iter()
+                .find(|(folder, _)| path.starts_with(folder))
+                .map(|(_, badge)| *badge)
+        })
+    }
+
+    pub fn subscription(&self, listing: usize) -> Subscription<Message> {
+        struct Asking;
+
+        let Some(root) = self.root.clone() else {
+            return Subscription::none();
+        };
+        subscription::channel(
+            (TypeId::of::<Asking>(), listing),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || sender.send(status(&root)));
+                if let Ok(status) = receiver.await {
+                    let _ = output.send(Message::Status(listing, status)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+}
+
+/// The top of the work tree holding `dir`.
+fn repository(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+fn status(root: &Path) -> Option<Status> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--branch",
+            "--ignored=matching",
+            "--untracked-files=normal",
+        ])
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            tracing::debug!(
+                root = %root.display(),
+                stderr = %String::from_utf8_lossy(&output.stderr),
+                "git status failed"
+            );
+            return None;
+        }
+        Err(error) => {
+            tracing::debug!(%error, "cannot run git");
+            return None;
+        }
+    };
+
+    Some(parse(root, &String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads `git status --porcelain=v1 -z --branch` output for `root`.
+fn parse(root: &Path, output: &str) -> Status {
+    let mut status = Status::default();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(branch) = record.strip_prefix("## ") {
+            let branch = branch.split("...").next().unwrap_or(branch);
+            status.branch = match branch.strip_prefix("No commits yet on ") {
+                Some(branch) => Some(branch.to_string()),
+                None if branch.starts_with("HEAD (no branch)") => None,
+                None => Some(branch.to_string()),
+            };
+            continue;
+        }
+        let (Some(code), Some(path)) = (record.get(..2), record.get(3..)) else {
+            continue;
+        };
+        // Renames and copies give the original name next.
+        if code.starts_with(['R', 'C']) {
+            records.next();
+        }
+
+        let mut chars = code.chars();
+        let (index, tree) = (chars.next().unwrap_or(' '), chars.next().unwrap_or(' '));
+        let badge = match (index, tree) {
+            ('?', '?') => Badge::Untracked,
+            ('!', '!') => Badge::Ignored,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => Badge::Conflicted,
+            (_, 'M' | 'D' | 'T') => Badge::Modified,
+            _ => Badge::Staged,
+        };
+        let path = root.join(path.trim_end_matches('/'));
+        if record.ends_with('/') && matches!(badge, Badge::Untracked | Badge::Ignored) {
+            status.folders.push((path.clone(), badge));
+        }
+        if badge != Badge::Ignored {
+            // The folders down to it hold a change.
+            for folder in path
+                .ancestors()
+                .skip(1)
+                .take_while(|folder| *folder != root)
+            {
+                mark(&mut status.badges, folder, badge.min(Badge::Modified));
+            }
+        }
+        mark(&mut status.badges, &path, badge);
+    }
+
+    status
+}
+
+fn mark(badges: &mut HashMap<PathBuf, Badge>, path: &Path, badge: Badge) {
+    badges
+        .entry(path.to_path_buf())
+        .and_modify(|known| *known = (*known).min(badge))
+        .or_insert(badge);
+}
@@ -0,0 +1,214 @@
+//! The headings the listing can be split under: the first letter of the
+//! names, the kind of file, or how long ago it changed.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::collation::{self, Key};
+use crate::config::GroupBy;
+use crate::i18n::tr;
+use crate::mime;
+
+/// A heading and where it goes among the others.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Heading {
+    order: (u8, Key),
+    pub label: String,
+}
+
+impl Heading {
+    fn new(rank: u8, label: &str) -> Heading {
+        Heading {
+            order: (rank, collation::key("")),
+            label: String::from(label),
+        }
+    }
+}
+
+/// What the headings are worked out against, taken once per sort.
+pub struct Grouping {
+    by: GroupBy,
+    today: Option<Day>,
+}
+
+impl Grouping {
+    pub fn new(by: GroupBy) -> Grouping {
+        Grouping {
+            by,
+            today: (by == GroupBy::Date)
+                .then(|| Day::of(SystemTime::now()))
+                .flatten(),
+        }
+    }
+
+    /// The heading for `name`, a folder when `is_dir`; `None` when the
+    /// listing is not grouped.
+    pub fn heading(
+        &self,
+        name: &str,
+        is_dir: bool,
+        modified: Option<SystemTime>,
+    ) -> Option<Heading> {
+        match self.by {
+            GroupBy::None => None,
+            GroupBy::Letter => Some(letter(name)),
+            GroupBy::Type => Some(kind(name, is_dir)),
+            GroupBy::Date => Some(self.age(modified)),
+        }
+    }
+
+    /// Entries that could not be read.
+    pub fn unreadable(&self) -> Option<Heading> {
+        (self.by != GroupBy::None).then(|| Heading::new(u8::MAX, tr("Unreadable")))
+    }
+
+    fn age(&self, modified: Option<SystemTime>) -> Heading {
+        let (Some(today), Some(day)) = (&self.today, modified.and_then(Day::of)) else {
+            return Heading::new(10, tr("Date unknown"));
+        };
+        let monday = today.days - i64::from(today.weekday);
+        let last_month = match today.month {
+            0 => (today.year - 1, 11),
+            month => (today.year, month - 1),
+        };
+        let (rank, label) = if day.days > today.days {
+            (0, "In the future")
+        } else if day.days == today.days {
+            (1, "Today")
+        } else if day.days == today.days - 1 {
+            (2, "Yesterday")
+        } else if day.days >= monday {
+            (3, "Earlier this week")
+        } else if day.days >= monday - 7 {
+            (4, "Last week")
+        } else if (day.year, day.month) == (today.year, today.month) {
+            (5, "Earlier this month")
+        } else if (day.year, day.month) == last_month {
+            (6, "Last month")
+        } else if day.year == today.year {
+            (7, "Earlier this year")
+        } else if day.year == today.year - 1 {
+            (8, "Last year")
+        } else {
+            (9, "Older")
+        };
+        Heading::new(rank, tr(label))
+    }
+}
+
+/// Names under their first letter, accents aside, after those starting
+/// with a digit and before those with anything else.
+fn letter(name: &str) -> Heading {
+    let first = name.trim_start_matches('.').chars().next();
+    match first {
+        Some(c) if c.is_ascii_digit() => Heading::new(0, "0–9"),
+        Some(c) if c.is_alphabetic() => {
+            let letter: String = collation::fold(c).to_uppercase().collect();
+            Heading {
+                order: (1, collation::key(&letter)),
+                label: letter,
+            }
+        }
+        _ => Heading::new(2, "#"),
+    }
+}
+
+fn kind(name: &str, is_dir: bool) -> Heading {
+    if is_dir {
+        return Heading::new(0, tr("Folders"));
+    }
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let mime = extension
+        .as_deref()
+        .and_then(mime::from_extension)
+        .unwrap_or_default();
+    let (rank, label) = match mime.split_once('/').unwrap_or_default() {
+        ("image", _) => (1, "Images"),
+        ("video", _) => (2, "Videos"),
+        ("audio", _) => (3, "Audio"),
+        ("text", _) => (4, "Documents"),
+        ("application", "pdf" | "json" | "xml" | "yaml" | "toml" | "x-shellscript") => {
+            (4, "Documents")
+        }
+        ("application", subtype) if subtype.starts_with("vnd.o") => (4, "Documents"),
+        (
+            "application",
+            "zip"
+            | "gzip"
+            | "zstd"
+            | "x-tar"
+            | "x-xz"
+            | "x-7z-compressed"
+            | "x-rpm"
+            | "vnd.debian.binary-package"
+            | "x-iso9660-image",
+        ) => (5, "Archives and packages"),
+        _ => (6, "Other files"),
+    };
+    Heading::new(rank, tr(label))
+}
+
+/// A calendar day in local time.
+struct Day {
+    /// Since 1 January 1970.
+    days: i64,
+    year: i64,
+    /// From 0 for January.
+    month: u32,
+    /// From 0 for Monday.
+    weekday: u32,
+}
+
+impl Day {
+    #[cfg(unix)]
+    // `time_t` and `long` are narrower than `i64` on some systems.
+    #[allow(clippy::useless_conversion)]
+    fn of(time: SystemTime) -> Option<Day> {
+        let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as libc::time_t,
+            Err(error) => -(error.duration().as_secs() as libc::time_t),
+        };
+        // SAFETY: `localtime_r` only writes into the `tm` we own.
+        let tm = unsafe {
+            let mut tm: libc::tm = std::mem::zeroed();
+            if libc::localtime_r(&seconds, &mut tm).is_null() {
+                return None;
+            }
+            tm
+        };
+        Some(Day {
+            days: (i64::from(seconds) + i64::from(tm.tm_gmtoff)).div_euclid(86_400),
+            year: i64::from(tm.tm_year) + 1900,
+            month: tm.tm_mon as u32,
+            weekday: (tm.tm_wday as u32 + 6) % 7,
+        })
+    }
+
+    /// In UTC, without the time zone at hand.
+    #[cfg(not(unix))]
+    fn of(time: SystemTime) -> Option<Day> {
+        let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(error) => -(error.duration().as_secs() as i64),
+        };
+        let days = seconds.div_euclid(86_400);
+        // Howard Hinnant's `civil_from_days`.
+        let shifted = days + 719_468;
+        let era = shifted.div_euclid(146_097);
+        let of_era = shifted - era * 146_097;
+        let year_of_era = (of_era - of_era / 1460 + of_era / 36_524 - of_era / 146_096) / 365;
+        let of_year = of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * of_year + 2) / 153;
+        let month = if month < 10 { month + 2 } else { month - 10 };
+        let year = year_of_era + era * 400 + i64::from(month < 2);
+        Some(Day {
+            days,
+            year,
+            month: month as u32,
+            // 1 January 1970 was a Thursday.
+            weekday: (days + 3).rem_euclid(7) as u32,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+//! Colours for the source files the preview shows, from syntect's grammars
+//! and one of its themes: one for light and one for dark backgrounds unless
+//! the `Style` names another. Text the theme leaves in its own foreground
+//! gets the text colour of the iced theme instead.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use iced::Color;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// The themes picked when the `Style` names none.
+const LIGHT: &str = "InspiredGitHub";
+const DARK: &str = "base16-ocean.dark";
+
+/// A line of text as pieces of one colour each, `None` for the text colour
+/// of the iced theme.
+pub type Line = Vec<(Option<Color>, String)>;
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn themes() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// The theme called `name`, or the one for the background when there is
+/// none of that name.
+pub fn theme_name(name: Option<&str>, dark: bool) -> String {
+    match name.filter(|name| themes().themes.contains_key(*name)) {
+        Some(name) => name.to_string(),
+        None => String::from(if dark { DARK } else { LIGHT }),
+    }
+}
+
+/// The grammar of `path` by its extension, or by the first line of `text`
+/// as for scripts with a `#!`. `None` for plain text.
+fn syntax(path: &Path, text: &str) -> Option<&'static SyntaxReference> {
+    let syntaxes = syntaxes();
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| syntaxes.find_syntax_by_extension(extension))
+        .or_else(|| syntaxes.find_syntax_by_first_line(text))
+        .filter(|syntax| syntax.name != syntaxes.find_syntax_plain_text().name)
+}
+
+/// Each line of `text` in pieces, in the colours of the theme named, or
+/// `None` when `path` is not a source file known here.
+pub fn highlight(path: &Path, text: &str, theme: &str) -> Option<Vec<Line>> {
+    let syntax = syntax(path, text)?;
+    let theme = themes().themes.get(theme)?;
+    let mut lines = HighlightLines::new(syntax, theme);
+    LinesWithEndings::from(text)
+        .map(|line| {
+            let pieces = lines.highlight_line(line, syntaxes()).ok()?;
+            Some(pieces_of(&pieces, theme))
+        })
+        .collect()
+}
+
+fn pieces_of(pieces: &[(syntect::highlighting::Style, &str)], theme: &Theme) -> Line {
+    let mut line: Line = Vec::new();
+    for (style, piece) in pieces {
+        let piece = piece.trim_end_matches(['\n', '\r']);
+        if piece.is_empty() {
+            continue;
+        }
+        let color = (Some(style.foreground) != theme.settings.foreground).then(|| {
+            Color::from_rgba8(
+                style.foreground.r,
+                style.foreground.g,
+                style.foreground.b,
+                f32::from(style.foreground.a) / 255.,
+            )
+        });
+        match line.last_mut() {
+            Some((last, text)) if *last == color => text.push_str(piece),
+            _ => line.push((color, piece.to_string())),
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colours_source_files() {
+        let lines = highlight(Path::new("main.rs"), "fn main() {}\n// done", DARK).unwrap();
+        assert_eq!(lines.len(), 2);
+        let text: String = lines[0].iter().map(|(_, piece)| piece.as_str()).collect();
+        assert_eq!(text, "fn main() {}");
+        assert!(lines[0].iter().any(|(color, _)| color.is_some()));
+        assert!(lines[1].iter().all(|(color, _)| color.is_some()));
+    }
+
+    #[test]
+    fn knows_scripts_by_their_first_line() {
+        assert!(highlight(Path::new("run"), "#!/bin/sh\necho hi\n", LIGHT).is_some());
+        assert!(highlight(Path::new("notes.txt"), "fn main() {}", LIGHT).is_none());
+    }
+
+    #[test]
+    fn picks_a_theme_for_the_background() {
+        assert_eq!(theme_name(None, true), DARK);
+        assert_eq!(theme_name(Some("no such theme"), false), LIGHT);
+        assert_eq!(
+            theme_name(Some("Solarized (dark)"), false),
+            "Solarized (dark)"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! The folders a window went through, to go back and forward as in a web
+//! browser, and what is kept of a window once closed to open it again.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// The most folders kept each way.
+const DEPTH: usize = 100;
+/// The most windows kept once closed.
+const CLOSED: usize = 10;
+
+/// How the folder shown came to be shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Opened anew, which leaves nothing to go forward to.
+    Visit,
+    Back,
+    Forward,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    back: Vec<PathBuf>,
+    forward: Vec<PathBuf>,
+}
+
+impl History {
+    /// Where going back leads.
+    pub fn back(&self) -> Option<&Path> {
+        self.back.last().map(PathBuf::as_path)
+    }
+
+    pub fn forward(&self) -> Option<&Path> {
+        self.forward.last().map(PathBuf::as_path)
+    }
+
+    /// Records leaving `from` for another folder by `step`.
+    pub fn went(&mut self, from: &Path, step: Step) {
+        let (left, arrived) = match step {
+            Step::Visit => {
+                self.forward.clear();
+                (&mut self.back, None)
+            }
+            Step::Back => (&mut self.forward, self.back.pop()),
+            Step::Forward => (&mut self.back, self.forward.pop()),
+        };
+        if step != Step::Visit && arrived.is_none() {
+            return;
+        }
+        left.push(from.to_path_buf());
+        if left.len() > DEPTH {
+            left.remove(0);
+        }
+    }
+}
+
+/// A window as it was closed: its folder, its history and how far down its
+/// listing was scrolled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closed {
+    pub dir: PathBuf,
+    pub history: History,
+    pub scroll: f32,
+}
+
+/// The windows closed lately, the last first.
+#[derive(Debug, Default)]
+pub struct RecentlyClosed {
+    closed: VecDeque<Closed>,
+}
+
+impl RecentlyClosed {
+    pub fn push(&mut self, closed: Closed) {
+        self.closed.push_front(closed);
+        self.closed.truncate(CLOSED);
+    }
+
+    /// Takes the one at `index`, 0 being the last closed.
+    pub fn take(&mut self, index: usize) -> Option<Closed> {
+        self.closed.remove(index)
+    }
+
+    /// Their folders, the last closed first.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        self.closed
+            .iter()
+            .map(|closed| closed.dir.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_back_and_forward() {
+        let mut history = History::default();
+        history.went(Path::new("/a"), Step::Visit);
+        history.went(Path::new("/b"), Step::Visit);
+        // Now in /c.
+        assert_eq!(history.back(), Some(Path::new("/b")));
+        assert_eq!(history.forward(), None);
+
+        history.went(Path::new("/c"), Step::Back);
+        assert_eq!(history.back(), Some(Path::new("/a")));
+        assert_eq!(history.forward(), Some(Path::new("/c")));
+
+        history.went(Path::new("/b"), Step::Forward);
+        assert_eq!(history.back(), Some(Path::new("/b")));
+        assert_eq!(history.forward(), None);
+
+        history.went(Path::new("/c"), Step::Back);
+        history.went(Path::new("/b"), Step::Visit);
+        assert_eq!(history.forward(), None);
+        assert_eq!(history.back(), Some(Path::new("/b")));
+    }
+
+    #[test]
+    fn ignores_steps_with_nowhere_to_go() {
+        let mut history = History::default();
+        history.went(Path::new("/a"), Step::Back);
+        assert_eq!(history, History::default());
+    }
+
+    #[test]
+    fn keeps_the_last_closed_first() {
+        let mut closed = RecentlyClosed::default();
+        for index in 0..=CLOSED {
+            closed.push(Closed {
+                dir: PathBuf::from(format!("/{}", index)),
+                history: History::default(),
+                scroll: 0.,
+            });
+        }
+        let dirs = closed.dirs();
+        assert_eq!(dirs.len(), CLOSED);
+        assert_eq!(dirs[0], Path::new(&format!("/{}", CLOSED)));
+        assert_eq!(closed.take(0).unwrap().dir, dirs[0]);
+        assert_eq!(closed.dirs()[0], dirs[1]);
+    }
+}
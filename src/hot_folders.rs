@@ -0,0 +1,245 @@
+//! Hot folders: files arriving in a folder with names matching a pattern
+//! are moved or copied to another, by rules written into `config.toml` as
+//!
+//! ```toml
+//! [[hot_folder]]
+//! folder = "~/Downloads"
+//! pattern = "*.pdf"
+//! action = "move"
+//! to = "~/Documents/PDFs"
+//! ```
+//!
+//! Only the instance started with `--hidden` runs them, for what arrives
+//! while it runs. A file is taken once it has not changed for `SETTLED`,
+//! so that one still being downloaded is left alone until it is complete.
+
+use std::any::TypeId;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use iced::futures::SinkExt;
+use iced::{subscription, Subscription};
+
+use crate::dialog;
+use crate::watcher;
+
+const SETTLED: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Move,
+    Copy,
+}
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        match name {
+            "move" => Some(Action::Move),
+            "copy" => Some(Action::Copy),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub folder: PathBuf,
+    /// Matched against names as file filters are, ignoring case.
+    pub pattern: String,
+    pub action: Action,
+    pub to: PathBuf,
+}
+
+impl Rule {
+    fn takes(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase());
+        let pattern: Vec<char> = self.pattern.to_lowercase().chars().collect();
+        path.parent() == Some(self.folder.as_path())
+            && name.is_some_and(|name| dialog::glob(&pattern, &name.chars().collect::<Vec<_>>()))
+    }
+}
+
+/// Files a rule took, for a job to move or copy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arrival {
+    pub action: Action,
+    pub files: Vec<PathBuf>,
+    pub to: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Watched(PathBuf, watcher::Event),
+    /// Time to look again at the files still changing.
+    Check,
+}
+
+#[derive(Debug, Default)]
+pub struct HotFolders {
+    rules: Vec<Rule>,
+    /// Files seen arriving, by the rule taking them, until they settle.
+    arriving: HashMap<PathBuf, usize>,
+    /// When the files taken were last changed, so a copy is not made again
+    /// for a file that only reported being read.
+    taken: HashMap<PathBuf, SystemTime>,
+    /// Counts checks, to wait anew after each.
+    checks: usize,
+}
+
+impl HotFolders {
+    pub fn new(rules: Vec<Rule>) -> HotFolders {
+        HotFolders {
+            rules,
+            ..HotFolders::default()
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Vec<Arrival> {
+        match message {
+            Message::Watched(_, watcher::Event::Unavailable) => {}
+            Message::Watched(folder, watcher::Event::Changed(paths)) => {
+                for path in paths {
+                    let rule = self
+                        .rules
+                        .iter()
+                        .position(|rule| rule.folder == folder && rule.takes(&path));
+                    if let Some(rule) = rule {
+                        self.arriving.insert(path, rule);
+                    }
+                }
+            }
+            Message::Check => self.checks += 1,
+        }
+        self.settled(SystemTime::now())
+    }
+
+    /// Takes the files arriving that are done changing as of `now`.
+    fn settled(&mut self, now: SystemTime) -> Vec<Arrival> {
+        let mut arrivals: Vec<Arrival> = Vec::new();
+        let arriving = std::mem::take(&mut self.arriving);
+        for (path, rule) in arriving {
+            let modified = fs::metadata(&path)
+                .ok()
+                .filter(|meta| meta.is_file())
+                .and_then(|meta| meta.modified().ok());
+            let Some(modified) = modified else {
+                // Gone again, or moved away by this very rule.
+                continue;
+            };
+            if now.duration_since(modified).unwrap_or_default() < SETTLED {
+                self.arriving.insert(path, rule);
+                continue;
+            }
+            if self.taken.get(&path) == Some(&modified) {
+                continue;
+            }
+            self.taken.insert(path.clone(), modified);
+
+            let Rule { action, to, .. } = &self.rules[rule];
+            match arrivals
+                .iter_mut()
+                .find(|arrival| arrival.action == *action && arrival.to == *to)
+            {
+                Some(arrival) => arrival.files.push(path),
+                None => arrivals.push(Arrival {
+                    action: *action,
+                    files: vec![path],
+                    to: to.clone(),
+                }),
+            }
+        }
+        arrivals
+    }
+
+    /// Watches the folders of the rules, and waits for the files arriving
+    /// to settle.
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Settling;
+
+        let folders: BTreeSet<&PathBuf> = self.rules.iter().map(|rule| &rule.folder).collect();
+        let mut subscriptions: Vec<_> = folders
+            .into_iter()
+            .map(|folder| {
+                watcher::watch(folder.clone())
+                    .with(folder.clone())
+                    .map(|(folder, event)| Message::Watched(folder, event))
+            })
+            .collect();
+        if !self.arriving.is_empty() {
+            subscriptions.push(subscription::channel(
+                (TypeId::of::<Settling>(), self.checks),
+                1,
+                |mut output| async move {
+                    let (sender, receiver) = iced::futures::channel::oneshot::channel();
+                    std::thread::spawn(move || {
+                        std::thread::sleep(SETTLED);
+                        let _ = sender.send(());
+                    });
+                    if receiver.await.is_ok() {
+                        let _ = output.send(Message::Check).await;
+                    }
+                    std::future::pending().await
+                },
+            ));
+        }
+        Subscription::batch(subscriptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(folder: &Path, action: Action) -> Rule {
+        Rule {
+            folder: folder.to_path_buf(),
+            pattern: String::from("*.PDF"),
+            action,
+            to: PathBuf::from("/elsewhere"),
+        }
+    }
+
+    #[test]
+    fn takes_names_matching_in_the_folder_itself() {
+        let rule = rule(Path::new("/in"), Action::Move);
+        assert!(rule.takes(Path::new("/in/paper.pdf")));
+        assert!(!rule.takes(Path::new("/in/paper.pdf.part")));
+        assert!(!rule.takes(Path::new("/in/below/paper.pdf")));
+        assert!(!rule.takes(Path::new("/out/paper.pdf")));
+    }
+
+    #[test]
+    fn takes_files_once_they_settle() {
+        let dir = std::env::temp_dir().join(format!("hot-folders-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("paper.pdf");
+        fs::write(&file, "%PDF").unwrap();
+        let mut hot = HotFolders::new(vec![rule(&dir, Action::Copy)]);
+
+        let changed = watcher::Event::Changed(vec![file.clone(), dir.join("notes.txt")]);
+        assert!(hot
+            .update(Message::Watched(dir.clone(), changed))
+            .is_empty());
+        assert_eq!(hot.arriving.len(), 1);
+
+        let later = SystemTime::now() + SETTLED;
+        let arrivals = hot.settled(later);
+        assert_eq!(
+            arrivals,
+            vec![Arrival {
+                action: Action::Copy,
+                files: vec![file.clone()],
+                to: PathBuf::from("/elsewhere"),
+            }]
+        );
+
+        // Copied already, unchanged since.
+        hot.arriving.insert(file, 0);
+        assert!(hot.settled(later).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
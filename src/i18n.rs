@@ -0,0 +1,231 @@
+//! Translations of the interface, in gettext's `.po` files keyed by the
+//! English text, which is what is shown without one.
+//!
+//! The catalog for the first language the user prefers that has one is
+//! read at startup from `locale/<language>.po` in the data folder, then in
+//! `share/iced-fm/locale` beside the executable's folder and in `locale`
+//! next to it, so a language is added by dropping in its file. Languages
+//! come from `LANGUAGE`, `LC_ALL`, `LC_MESSAGES` and `LANG`, and otherwise
+//! the system's settings; `de_AT` falls back to `de`.
+//!
+//! `po/iced-fm.pot` lists the texts to translate; after changing them,
+//! refresh it with
+//!
+//! ```text
+//! xgettext -L C --from-code=UTF-8 --package-name=iced-fm -k -ktr -ktrf \
+//!     -kitem:1 -kcheck:1 -kbutton:1 -kpush:2 -kpush:3 -kpush_with_action:2 \
+//!     -kpush_with_action:3 -kpush_with_action:4 -o po/iced-fm.pot src/*.rs
+//! ```
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::dirs;
+
+/// English texts and their translations.
+fn catalog() -> &'static HashMap<String, String> {
+    static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+    CATALOG.get_or_init(|| {
+        let folders = folders();
+        for language in languages() {
+            for folder in &folders {
+                let path = folder.join(format!("{}.po", language));
+                let Ok(contents) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let texts = parse(&contents);
+                tracing::info!(%language, path = %path.display(), texts = texts.len(), "translation");
+                return texts;
+            }
+        }
+
+        HashMap::new()
+    })
+}
+
+/// `text`, translated.
+pub fn tr(text: &str) -> &str {
+    catalog().get(text).map_or(text, String::as_str)
+}
+
+/// `template`, translated, with `{}` standing for each of `args` in turn
+/// and `{0}`, `{1}`… for a given one, so translations can reorder them.
+pub fn trf(template: &str, args: &[&dyn Display]) -> String {
+    let template = tr(template);
+    let mut filled = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let index = match &after[..end] {
+            "" => {
+                next += 1;
+                Some(next - 1)
+            }
+            index => index.parse().ok(),
+        };
+        match index.and_then(|index: usize| args.get(index)) {
+            Some(arg) => filled.push_str(&arg.to_string()),
+            None => filled.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+fn folders() -> Vec<PathBuf> {
+    let mut folders = Vec::new();
+    if let Some(data) = dirs::data_dir() {
+        folders.push(data.join("locale"));
+    }
+    if let Some(exe_dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+    {
+        if let Some(prefix) = exe_dir.parent() {
+            folders.push(prefix.join("share/iced-fm/locale"));
+        }
+        folders.push(exe_dir.join("locale"));
+    }
+    folders
+}
+
+/// The catalogs to look for, most wanted first.
+fn languages() -> Vec<String> {
+    let set = |name| env::var(name).ok().filter(|value| !value.is_empty());
+    let locale = set("LC_ALL")
+        .or_else(|| set("LC_MESSAGES"))
+        .or_else(|| set("LANG"));
+    // As with gettext, `LANGUAGE` is not heeded in the C locale.
+    let mut wanted: Vec<String> = match locale.as_deref() {
+        Some("C" | "POSIX") => return Vec::new(),
+        _ => set("LANGUAGE")
+            .map(|list| list.split(':').map(String::from).collect())
+            .unwrap_or_default(),
+    };
+    match locale {
+        Some(locale) => wanted.push(locale),
+        None => wanted.extend(sys_locale::get_locales()),
+    }
+
+    let mut languages = Vec::new();
+    for locale in wanted {
+        // de_DE.UTF-8@euro, or de-DE from the system.
+        let locale = locale
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('-', "_");
+        if locale.is_empty() || locale == "C" || locale == "POSIX" {
+            continue;
+        }
+        let base = locale.split('_').next().unwrap_or_default().to_string();
+        for language in [locale, base] {
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+    }
+    languages
+}
+
+/// The translated entries of a `.po` file; the plural forms give their
+/// first, and fuzzy ones, which need a translator's look, are left out.
+fn parse(contents: &str) -> HashMap<String, String> {
+    enum Field {
+        Id,
+        Translation,
+        Other,
+    }
+
+    let mut texts = HashMap::new();
+    let mut id = String::new();
+    let mut translation = String::new();
+    let mut field = Field::Other;
+    let mut context = false;
+    let mut fuzzy = false;
+    let mut entry_fuzzy = false;
+    let mut finish = |id: &mut String, translation: &mut String, fuzzy: bool| {
+        if !id.is_empty() && !translation.is_empty() && !fuzzy {
+            texts.insert(std::mem::take(id), std::mem::take(translation));
+        }
+        id.clear();
+        translation.clear();
+    };
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with("#,") {
+            fuzzy |= line.contains("fuzzy");
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('"') {
+            match field {
+                Field::Id => id.push_str(&unquote(line)),
+                Field::Translation => translation.push_str(&unquote(line)),
+                Field::Other => {}
+            }
+            continue;
+        }
+        let (keyword, value) = line.split_once(' ').unwrap_or((line, ""));
+        field = match keyword {
+            "msgctxt" | "msgid" => {
+                // A context starts the entry its msgid belongs to.
+                if !std::mem::replace(&mut context, keyword == "msgctxt") {
+                    finish(&mut id, &mut translation, entry_fuzzy);
+                    entry_fuzzy = std::mem::take(&mut fuzzy);
+                }
+                if keyword == "msgctxt" {
+                    Field::Other
+                } else {
+                    id.push_str(&unquote(value));
+                    Field::Id
+                }
+            }
+            "msgstr" | "msgstr[0]" => {
+                translation.push_str(&unquote(value));
+                Field::Translation
+            }
+            _ => Field::Other,
+        };
+    }
+    finish(&mut id, &mut translation, entry_fuzzy);
+
+    texts
+}
+
+fn unquote(quoted: &str) -> String {
+    let inner = quoted
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .unwrap_or_default();
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
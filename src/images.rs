@@ -0,0 +1,470 @@
+//! What can be learned about images without decoding them: their size from
+//! the header, and the JPEG rendering camera RAW files carry for the
+//! camera's own screen. CR2, NEF and ARW files are TIFF underneath, so
+//! that JPEG is found by walking their image directories. Converting them
+//! is left to ImageMagick, and so is the first page of a PDF when Poppler's
+//! `pdftoppm` is missing; a frame of a video comes from `ffmpeg`, with the
+//! `ffmpeg` feature.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::dirs;
+use crate::mime;
+
+/// How far into a JPEG its start of frame is looked for. Embedded
+/// thumbnails can take tens of kilobytes before it.
+const FRAME_SEARCH: u64 = 1 << 20;
+/// Image directories walked in a RAW file, against loops.
+const MAX_DIRECTORIES: usize = 32;
+/// Embedded previews larger than this are not read.
+const MAX_PREVIEW: u64 = 64 << 20;
+/// The longest side of the previews rendered of documents and videos.
+const RENDERED_SIZE: u32 = 1024;
+
+const JPEG_OFFSET: u16 = 0x0201;
+const JPEG_LENGTH: u16 = 0x0202;
+const COMPRESSION: u16 = 0x0103;
+const STRIP_OFFSETS: u16 = 0x0111;
+const STRIP_BYTE_COUNTS: u16 = 0x0117;
+const SUB_DIRECTORIES: u16 = 0x014a;
+/// Old-style JPEG compression, as CR2 files mark their preview.
+const COMPRESSION_JPEG: u32 = 6;
+
+/// What images are converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 3] = [ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::Webp];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Webp => "webp",
+        }
+    }
+}
+
+impl std::fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageFormat::Jpeg => write!(f, "JPEG"),
+            ImageFormat::Png => write!(f, "PNG"),
+            ImageFormat::Webp => write!(f, "WebP"),
+        }
+    }
+}
+
+/// How images are converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conversion {
+    pub format: ImageFormat,
+    /// The longest side is brought down to this; smaller images keep
+    /// their size.
+    pub max_dimension: Option<u32>,
+    /// From 1 to 100, for JPEG and WebP.
+    pub quality: u8,
+}
+
+impl Default for Conversion {
+    fn default() -> Conversion {
+        Conversion {
+            format: ImageFormat::Jpeg,
+            max_dimension: None,
+            quality: 85,
+        }
+    }
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.is_file() && mime::guess(path).starts_with("image/")
+}
+
+/// Writes `source` as `conversion` says to `target`, through whichever
+/// ImageMagick command there is.
+pub fn convert(source: &Path, target: &Path, conversion: Conversion) -> io::Result<()> {
+    // Only the first frame of an animation or page of a document.
+    let mut input = source.as_os_str().to_os_string();
+    input.push("[0]");
+    let mut args = vec![input, "-auto-orient".into()];
+    if let Some(max) = conversion.max_dimension {
+        args.push("-resize".into());
+        args.push(format!("{}x{}>", max, max).into());
+    }
+    args.push("-quality".into());
+    args.push(conversion.quality.to_string().into());
+    // The extension would do, but names can hold colons that would be
+    // taken for a prefix like this one.
+    let coder = match conversion.format {
+        ImageFormat::Jpeg => "JPEG:",
+        ImageFormat::Png => "PNG:",
+        ImageFormat::Webp => "WEBP:",
+    };
+    let mut output = std::ffi::OsString::from(coder);
+    output.push(target);
+    args.push(output);
+
+    // `convert` is something else on Windows.
+    let commands: &[&str] = if cfg!(windows) {
+        &["magick"]
+    } else {
+        &["magick", "convert"]
+    };
+    for command in commands {
+        let output = match Command::new(command)
+            .args(&args)
+            .stdin(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let _ = fs::remove_file(target);
+        return Err(io::Error::other(format!(
+            "cannot convert {}: {}",
+            source.display(),
+            stderr.lines().next().unwrap_or("ImageMagick failed").trim()
+        )));
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "converting images needs ImageMagick",
+    ))
+}
+
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| matches!(extension.as_str(), "cr2" | "nef" | "arw"))
+}
+
+/// An image's size from its header. `start` holds the first bytes of
+/// `file`; JPEG markers are skipped through in `file`.
+pub fn dimensions(start: &[u8], file: &mut (impl Read + Seek)) -> Option<(u32, u32)> {
+    let u32_le = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+    if start.starts_with(b"\x89PNG\r\n\x1a\n") && start.len() >= 24 {
+        let width = u32::from_be_bytes(start[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(start[20..24].try_into().ok()?);
+        return Some((width, height));
+    }
+    if (start.starts_with(b"GIF87a") || start.starts_with(b"GIF89a")) && start.len() >= 10 {
+        let width = u16::from_le_bytes([start[6], start[7]]) as u32;
+        let height = u16::from_le_bytes([start[8], start[9]]) as u32;
+        return Some((width, height));
+    }
+    if start.starts_with(b"BM") && start.len() >= 26 {
+        // Bottom-up bitmaps give their height negative.
+        let height = (u32_le(&start[22..26]) as i32).unsigned_abs();
+        return Some((u32_le(&start[18..22]), height));
+    }
+    if !start.starts_with(b"\xff\xd8") {
+        return None;
+    }
+
+    jpeg_frame(file, 0).map(|(_, width, height)| (width, height))
+}
+
+/// The start of frame marker and size of the JPEG at `at` in `file`.
+fn jpeg_frame(file: &mut (impl Read + Seek), at: u64) -> Option<(u8, u32, u32)> {
+    let u16_be = |bytes: &[u8]| u16::from_be_bytes([bytes[0], bytes[1]]) as u32;
+
+    let mut header = [0; 9];
+    file.seek(SeekFrom::Start(at)).ok()?;
+    file.read_exact(&mut header[..2]).ok()?;
+    if header[..2] != [0xff, 0xd8] {
+        return None;
+    }
+
+    let mut position = at + 2;
+    while position < at + FRAME_SEARCH {
+        file.seek(SeekFrom::Start(position)).ok()?;
+        file.read_exact(&mut header[..4]).ok()?;
+        if header[0] != 0xff {
+            return None;
+        }
+        let marker = header[1];
+        let length = u16_be(&header[2..4]) as u64;
+        if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            file.read_exact(&mut header[4..9]).ok()?;
+            return Some((marker, u16_be(&header[7..9]), u16_be(&header[5..7])));
+        }
+        position += 2 + length;
+    }
+
+    None
+}
+
+/// The largest JPEG preview embedded in the RAW file at `path`. The raw
+/// data itself, stored as lossless JPEG in CR2 files, does not count.
+pub fn embedded_preview(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let mut file = fs::File::open(path)?;
+    let mut header = [0; 8];
+    file.read_exact(&mut header)?;
+    let big_endian = match &header[..4] {
+        b"II*\0" => false,
+        b"MM\0*" => true,
+        _ => return Ok(None),
+    };
+    let mut tiff = Tiff { file, big_endian };
+
+    let mut candidates = Vec::new();
+    let mut pending = vec![tiff.u32_at(&header[4..8]) as u64];
+    let mut seen = HashSet::new();
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || seen.len() == MAX_DIRECTORIES || !seen.insert(offset) {
+            continue;
+        }
+        let Ok(directory) = tiff.directory(offset) else {
+            continue;
+        };
+        pending.extend(directory.next);
+        pending.extend(directory.children);
+        candidates.extend(directory.jpeg);
+    }
+
+    let mut best: Option<(u64, u64)> = None;
+    for (offset, length) in candidates {
+        if length > MAX_PREVIEW || best.is_some_and(|(_, best)| best >= length) {
+            continue;
+        }
+        // Baseline, extended or progressive; lossless is the raw data.
+        if let Some((0xc0..=0xc2, ..)) = jpeg_frame(&mut tiff.file, offset) {
+            best = Some((offset, length));
+        }
+    }
+    let Some((offset, length)) = best else {
+        return Ok(None);
+    };
+
+    let mut jpeg = vec![0; length as usize];
+    tiff.file.seek(SeekFrom::Start(offset))?;
+    tiff.file.read_exact(&mut jpeg)?;
+
+    Ok(Some(jpeg))
+}
+
+pub fn is_pdf(path: &Path) -> bool {
+    mime::guess(path) == "application/pdf"
+}
+
+pub fn is_video(path: &Path) -> bool {
+    mime::guess(path).starts_with("video/")
+}
+
+/// Whether a picture can be rendered of `path`: the first page of a PDF,
+/// or a frame of a video when built with `ffmpeg`. RAW files have theirs
+/// embedded.
+pub fn renders_preview(path: &Path) -> bool {
+    is_pdf(path) || (cfg!(feature = "ffmpeg") && is_video(path))
+}
+
+/// Writes a preview of `path` to the cache, for `pictures` to decode,
+/// giving where it went: the one embedded in a RAW file, or one rendered as
+/// `renders_preview` says.
+pub fn extract_preview(path: &Path) -> io::Result<PathBuf> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache folder"))?
+        .join("previews");
+    fs::create_dir_all(&dir)?;
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(".jpg");
+    let target = dir.join(name);
+
+    if is_pdf(path) {
+        first_page(path, &target)?;
+    } else if is_video(path) {
+        poster_frame(path, &target)?;
+    } else {
+        let jpeg = embedded_preview(path)?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "it holds no preview to show")
+        })?;
+        fs::write(&target, jpeg)?;
+    }
+
+    Ok(target)
+}
+
+/// Renders the first page of the PDF `source` as the JPEG `target`.
+fn first_page(source: &Path, target: &Path) -> io::Result<()> {
+    // `pdftoppm` adds the extension itself.
+    let prefix = target.with_extension("");
+    let output = Command::new("pdftoppm")
+        .args(["-jpeg", "-singlefile", "-f", "1", "-l", "1", "-scale-to"])
+        .arg(RENDERED_SIZE.to_string())
+        .arg(source)
+        .arg(&prefix)
+        .stdin(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(io::Error::other(format!(
+            "cannot render {}: {}",
+            source.display(),
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .next()
+                .unwrap_or("pdftoppm failed")
+                .trim()
+        ))),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => convert(
+            source,
+            target,
+            Conversion {
+                max_dimension: Some(RENDERED_SIZE),
+                ..Conversion::default()
+            },
+        ),
+        Err(error) => Err(error),
+    }
+}
+
+/// Writes a frame of the video `source` as the JPEG `target`, the one
+/// `ffmpeg` finds most telling among the first few seconds, since the very
+/// first is often black.
+#[cfg(feature = "ffmpeg")]
+fn poster_frame(source: &Path, target: &Path) -> io::Result<()> {
+    let scale = format!(
+        "thumbnail,scale='min({size},iw)':'min({size},ih)':force_original_aspect_ratio=decrease",
+        size = RENDERED_SIZE
+    );
+    let _ = fs::remove_file(target);
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-y", "-i"])
+        .arg(source)
+        .args(["-vf", &scale, "-frames:v", "1"])
+        .arg(target)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(io::ErrorKind::NotFound, "previews of videos need ffmpeg")
+            }
+            _ => error,
+        })?;
+    // It may finish without a frame when it has no decoder for the video.
+    if output.status.success() && target.is_file() {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "cannot take a frame of {}: {}",
+        source.display(),
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .next()
+            .unwrap_or("ffmpeg failed")
+            .trim()
+    )))
+}
+
+#[cfg(not(feature = "ffmpeg"))]
+fn poster_frame(_: &Path, _: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "previews of videos are not built in",
+    ))
+}
+
+struct Tiff {
+    file: fs::File,
+    big_endian: bool,
+}
+
+struct Directory {
+    next: Option<u64>,
+    children: Vec<u64>,
+    /// Offset and length of a JPEG it points to.
+    jpeg: Option<(u64, u64)>,
+}
+
+impl Tiff {
+    fn u16_at(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32_at(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn directory(&mut self, offset: u64) -> io::Result<Directory> {
+        let mut count = [0; 2];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut count)?;
+        let mut entries = vec![0; self.u16_at(&count) as usize * 12 + 4];
+        self.file.read_exact(&mut entries)?;
+
+        let (mut jpeg_offset, mut jpeg_length) = (None, None);
+        let (mut strip_offset, mut strip_length, mut compression) = (None, None, None);
+        let mut children = Vec::new();
+        let (fields, next) = entries.split_at(entries.len() - 4);
+        for entry in fields.chunks_exact(12) {
+            let tag = self.u16_at(&entry[0..2]);
+            let count = self.u32_at(&entry[4..8]);
+            // Short values sit in the first half of the value field.
+            let value = match self.u16_at(&entry[2..4]) {
+                3 => self.u16_at(&entry[8..10]) as u32,
+                _ => self.u32_at(&entry[8..12]),
+            };
+            match tag {
+                JPEG_OFFSET => jpeg_offset = Some(value as u64),
+                JPEG_LENGTH => jpeg_length = Some(value as u64),
+                COMPRESSION => compression = Some(value),
+                // Only single strips are whole JPEGs.
+                STRIP_OFFSETS if count == 1 => strip_offset = Some(value as u64),
+                STRIP_BYTE_COUNTS if count == 1 => strip_length = Some(value as u64),
+                SUB_DIRECTORIES if count == 1 => children.push(value as u64),
+                SUB_DIRECTORIES => children.extend(self.offsets(value as u64, count)?),
+                _ => {}
+            }
+        }
+
+        let jpeg = jpeg_offset.zip(jpeg_length).or_else(|| {
+            strip_offset
+                .zip(strip_length)
+                .filter(|_| compression == Some(COMPRESSION_JPEG))
+        });
+        let next = self.u32_at(next) as u64;
+
+        Ok(Directory {
+            next: (next != 0).then_some(next),
+            children,
+            jpeg,
+        })
+    }
+
+    fn offsets(&mut self, at: u64, count: u32) -> io::Result<Vec<u64>> {
+        let mut bytes = vec![0; count.min(MAX_DIRECTORIES as u32) as usize * 4];
+        self.file.seek(SeekFrom::Start(at))?;
+        self.file.read_exact(&mut bytes)?;
+
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|offset| self.u32_at(offset) as u64)
+            .collect())
+    }
+}
@@ -0,0 +1,157 @@
+//! The index the instance started with `--hidden` keeps of the files in the
+//! folders `index_folders` lists in `config.toml`, the home folder unless
+//! it is there: walked again every `INTERVAL`, without following symlinks,
+//! and written to the cache as a `file://` URI a line. Searches look
+//! through it before the folders are walked.
+
+use std::any::TypeId;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+
+use crate::dirs;
+use crate::paths;
+
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+const FILE: &str = "index";
+const BATCH_SIZE: usize = 256;
+
+/// Indexes `folders` now and then every `INTERVAL`, telling how many
+/// files and folders were found each time.
+pub fn subscription(folders: Vec<PathBuf>) -> Subscription<usize> {
+    struct Indexer;
+
+    subscription::channel(
+        (TypeId::of::<Indexer>(), folders.clone()),
+        1,
+        move |mut output| async move {
+            let (sender, mut receiver) = mpsc::unbounded();
+            thread::spawn(move || loop {
+                let started = Instant::now();
+                match build(&folders) {
+                    Ok(count) => {
+                        tracing::info!(count, elapsed = ?started.elapsed(), "indexed");
+                        if sender.unbounded_send(count).is_err() {
+                            return;
+                        }
+                    }
+                    Err(error) => tracing::warn!(%error, "cannot write the index"),
+                }
+                thread::sleep(INTERVAL);
+            });
+
+            while let Some(count) = receiver.next().await {
+                let _ = output.send(count).await;
+            }
+
+            std::future::pending().await
+        },
+    )
+}
+
+/// Walks `folders` into the index file, replacing it once written whole.
+fn build(folders: &[PathBuf]) -> io::Result<usize> {
+    let file = file().ok_or_else(|| io::Error::other("no cache directory"))?;
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = file.with_extension("new");
+    let mut writer = BufWriter::new(File::create(&temp)?);
+    let written = walk(folders, &mut writer).and_then(|count| {
+        writer.flush()?;
+        Ok(count)
+    });
+    drop(writer);
+    match written.and_then(|count| fs::rename(&temp, &file).map(|()| count)) {
+        Ok(count) => Ok(count),
+        Err(error) => {
+            let _ = fs::remove_file(&temp);
+            Err(error)
+        }
+    }
+}
+
+fn walk(folders: &[PathBuf], writer: &mut impl Write) -> io::Result<usize> {
+    let mut count = 0;
+    let mut pending: VecDeque<PathBuf> = folders.iter().cloned().collect();
+    while let Some(dir) = pending.pop_front() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                pending.push_back(path.clone());
+            }
+            writeln!(writer, "{}", paths::to_uri(&path))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Sends what the index holds below `root` with names containing `query`,
+/// already lowercase, in batches while `send` takes them.
+pub fn search(root: &Path, query: &str, mut send: impl FnMut(Vec<PathBuf>) -> bool) {
+    let Some(index) = file().and_then(|file| File::open(file).ok()) else {
+        return;
+    };
+    let mut batch = Vec::new();
+    for line in BufReader::new(index).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Some(path) = paths::from_uri(&line) else {
+            continue;
+        };
+        if path != root && path.starts_with(root) && matches(&path, query) {
+            batch.push(path);
+            if batch.len() >= BATCH_SIZE && !send(std::mem::take(&mut batch)) {
+                return;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        send(batch);
+    }
+}
+
+fn matches(path: &Path, query: &str) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(query))
+}
+
+fn file() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_everything_below_the_folders() {
+        let dir = std::env::temp_dir().join(format!("indexer-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a b")).unwrap();
+        fs::write(dir.join("a b").join("Report.txt"), "").unwrap();
+        fs::write(dir.join("top.txt"), "").unwrap();
+
+        let mut written = Vec::new();
+        assert_eq!(walk(std::slice::from_ref(&dir), &mut written).unwrap(), 3);
+        let listed: Vec<PathBuf> = String::from_utf8(written)
+            .unwrap()
+            .lines()
+            .filter_map(paths::from_uri)
+            .collect();
+        assert!(listed.contains(&dir.join("a b").join("Report.txt")));
+        assert!(matches(&dir.join("a b").join("Report.txt"), "report"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+use std::any::TypeId;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use std::{fs, io, process, thread};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+
+use crate::dirs;
+
+/// Where a background instance writes the loopback port it listens on,
+/// and the token a launch sends to show it has read the file.
+const PORT_FILE: &str = "instance.port";
+const SHOW: &str = "show";
+const SHOWN: &str = "shown";
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// What this process wrote to the port file, to remove it on the way out
+/// only if no later instance has written its own since.
+static WRITTEN: OnceLock<String> = OnceLock::new();
+
+/// Asks an instance running in the background to show its window. When one
+/// answers there is nothing left for this process to do; a port taken over
+/// by some other program does not answer with the token.
+pub fn show_running() -> bool {
+    let recorded = port_file().and_then(|file| fs::read_to_string(file).ok());
+    let Some((port, token)) = recorded.as_deref().and_then(parse) else {
+        return false;
+    };
+    ask(port, token).unwrap_or(false)
+}
+
+/// Removes the port file written by `show_requests`, for the app to call as
+/// it exits.
+pub fn forget() {
+    let (Some(file), Some(written)) = (port_file(), WRITTEN.get()) else {
+        return;
+    };
+    if fs::read_to_string(&file).is_ok_and(|recorded| recorded == *written) {
+        if let Err(error) = fs::remove_file(&file) {
+            tracing::debug!(%error, "cannot remove the instance port");
+        }
+    }
+}
+
+/// Fires whenever another launch asks for the window.
+pub fn show_requests() -> Subscription<()> {
+    struct Instance;
+
+    subscription::channel(TypeId::of::<Instance>(), 10, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        thread::spawn(move || serve(sender));
+
+        while let Some(()) = receiver.next().await {
+            let _ = output.send(()).await;
+        }
+
+        std::future::pending().await
+    })
+}
+
+fn serve(sender: mpsc::UnboundedSender<()>) {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(error) => {
+            tracing::warn!(%error, "cannot listen for other launches");
+            return;
+        }
+    };
+    let (Some(file), Ok(address)) = (port_file(), listener.local_addr()) else {
+        return;
+    };
+    let token = token();
+    let recorded = format!("{} {}\n", address.port(), token);
+    if let Err(error) = record(&file, &recorded) {
+        tracing::warn!(%error, "cannot record the instance port");
+        return;
+    }
+    let _ = WRITTEN.set(recorded);
+
+    answer(&listener, &token, || sender.unbounded_send(()).is_ok());
+}
+
+/// Sends the token to `port` and waits for the answer a background
+/// instance gives.
+fn ask(port: u16, token: &str) -> io::Result<bool> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.write_all(format!("{} {}\n", SHOW, token).as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream.take(64)).read_line(&mut reply)?;
+    Ok(reply.trim_end() == SHOWN)
+}
+
+/// Answers the launches that send `token`, calling `show` for each, until
+/// it returns `false`.
+fn answer(listener: &TcpListener, token: &str, mut show: impl FnMut() -> bool) {
+    let expected = format!("{} {}", SHOW, token);
+    for mut stream in listener.incoming().flatten() {
+        let _ = stream.set_read_timeout(Some(TIMEOUT));
+        let mut request = String::new();
+        let read = stream
+            .try_clone()
+            .and_then(|reader| BufReader::new(reader.take(64)).read_line(&mut request));
+        if read.is_err() || request.trim_end() != expected {
+            continue;
+        }
+        let _ = stream.write_all(format!("{}\n", SHOWN).as_bytes());
+        if !show() {
+            return;
+        }
+    }
+}
+
+/// The port and the token of a port file.
+fn parse(recorded: &str) -> Option<(u16, &str)> {
+    let (port, token) = recorded.trim().split_once(' ')?;
+    let port = port.parse().ok()?;
+    (!token.is_empty()).then_some((port, token))
+}
+
+/// A random token: the standard library seeds each `RandomState` afresh.
+fn token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(process::id());
+    if let Ok(since) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(since.as_nanos());
+    }
+    let high = hasher.finish();
+    hasher.write_u64(RandomState::new().build_hasher().finish());
+    format!("{:016x}{:016x}", high, hasher.finish())
+}
+
+/// Writes the port file readable by this user alone, since the token in it
+/// is all that lets a launch in.
+fn record(file: &Path, recorded: &str) -> io::Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+    options.open(file)?.write_all(recorded.as_bytes())
+}
+
+fn port_file() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(PORT_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_port_files() {
+        assert_eq!(parse("4242 abc\n"), Some((4242, "abc")));
+        assert_eq!(parse("4242\n"), None);
+        assert_eq!(parse("4242 "), None);
+        assert_eq!(parse("port abc"), None);
+    }
+
+    #[test]
+    fn shows_only_for_the_token() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let served = thread::spawn(move || {
+            let mut shown = 0;
+            answer(&listener, "right", || {
+                shown += 1;
+                false
+            });
+            shown
+        });
+
+        assert!(!ask(port, "wrong").unwrap());
+        assert!(ask(port, "right").unwrap());
+        assert_eq!(served.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn makes_a_new_token_each_time() {
+        assert_ne!(token(), token());
+        assert_eq!(token().len(), 32);
+    }
+}
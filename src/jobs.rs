@@ -0,0 +1,1782 @@
+use std::any::TypeId;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::{button, column, pick_list, progress_bar, row, text};
+use iced::{subscription, Alignment, Element, Length, Subscription};
+use toml_edit::{value, Array, ArrayOfTables, Document, Item, Table};
+
+use crate::archive::{self, Compression};
+use crate::config::SizeUnits;
+use crate::devices::{self, Device};
+use crate::dirs;
+use crate::encoding::{self, ByteOrderMark};
+use crate::i18n::{tr, trf};
+use crate::images::{self, Conversion};
+use crate::line_endings::{self, LineEnding};
+use crate::paths;
+use crate::rendering;
+use crate::safe_save;
+use crate::trash;
+use crate::vfs;
+
+pub const MAX_CONCURRENT_JOBS: usize = 2;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+const LOW_MEMORY_BUFFER_SIZE: usize = 8 * 1024;
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The copies and moves not done when the picker last closed, in the data
+/// folder.
+const FILE: &str = "jobs.toml";
+
+/// Whether `format_bytes` counts in thousands.
+static DECIMAL: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Trash,
+    Delete,
+    /// Into a new archive at the destination.
+    Compress(Compression),
+    /// Archives, or paths inside them, into the destination.
+    Extract,
+    /// Images, as new files in the destination.
+    Convert(Conversion),
+    /// Text files, rewritten as UTF-8 where they are.
+    Recode(ByteOrderMark),
+    /// Text files, with their line breaks rewritten where they are.
+    LineEndings(LineEnding),
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            JobKind::Copy => tr("Copying"),
+            JobKind::Move => tr("Moving"),
+            JobKind::Trash => tr("Moving to the trash"),
+            JobKind::Delete => tr("Deleting"),
+            JobKind::Compress(_) => tr("Compressing"),
+            JobKind::Extract => tr("Extracting"),
+            JobKind::Convert(_) => tr("Converting"),
+            JobKind::Recode(_) => tr("Converting to UTF-8"),
+            JobKind::LineEndings(_) => tr("Converting line endings"),
+        })
+    }
+}
+
+impl JobKind {
+    /// For telling the user it is done.
+    pub fn completed(&self) -> &'static str {
+        match self {
+            JobKind::Copy => tr("Copied"),
+            JobKind::Move => tr("Moved"),
+            JobKind::Trash => tr("Trashed"),
+            JobKind::Delete => tr("Deleted"),
+            JobKind::Compress(_) => tr("Compressed"),
+            JobKind::Extract => tr("Extracted"),
+            JobKind::Convert(_) => tr("Converted"),
+            JobKind::Recode(_) => tr("Re-encoded"),
+            JobKind::LineEndings(_) => tr("Converted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    pub preserve_timestamps: bool,
+    pub low_memory: bool,
+    /// Replaces whatever is in the way without asking, as syncing does.
+    pub overwrite: bool,
+    /// Keeps what is in the way, numbering the name of what comes instead,
+    /// as hot folders do with no one there to ask.
+    pub keep_both: bool,
+    /// Carries on a copy interrupted before: what is in the way is taken
+    /// for what was copied then, and files already as long as theirs are
+    /// left as they are.
+    pub resume: bool,
+}
+
+impl Options {
+    fn buffer_size(&self) -> usize {
+        if self.low_memory {
+            LOW_MEMORY_BUFFER_SIZE
+        } else {
+            BUFFER_SIZE
+        }
+    }
+}
+
+/// What to do once a job has completed successfully.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AfterAction {
+    #[default]
+    Nothing,
+    OpenDestination,
+    EjectDrive,
+    ShutDown,
+}
+
+impl AfterAction {
+    const ALL: [AfterAction; 4] = [
+        AfterAction::Nothing,
+        AfterAction::OpenDestination,
+        AfterAction::EjectDrive,
+        AfterAction::ShutDown,
+    ];
+}
+
+impl std::fmt::Display for AfterAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AfterAction::Nothing => tr("Do nothing when done"),
+            AfterAction::OpenDestination => tr("Open destination when done"),
+            AfterAction::EjectDrive => tr("Eject drive when done"),
+            AfterAction::ShutDown => tr("Shut down when done"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Conflict(PathBuf),
+    Done,
+    Cancelled,
+    Failed(String),
+}
+
+impl JobStatus {
+    fn is_active(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Running | JobStatus::Paused | JobStatus::Conflict(_)
+        )
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Cancelled | JobStatus::Failed(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Progress {
+    /// Another operation has one of the job's devices.
+    Waiting,
+    Started {
+        total: u64,
+    },
+    Advanced {
+        done: u64,
+    },
+    Conflict {
+        target: PathBuf,
+    },
+    /// An item was moved, or trashed, from `from` to `to`.
+    Moved {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Finished,
+    Cancelled,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Progress(usize, Progress),
+    Pause(usize),
+    Resume(usize),
+    Cancel(usize),
+    SetAfter(usize, AfterAction),
+    ClearFinished,
+    /// What to do with the name taken, and whether for the rest of the
+    /// job's as well.
+    Resolve(usize, ConflictAction, bool),
+    ResumeInterrupted,
+    DiscardInterrupted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictAction {
+    Skip,
+    Overwrite,
+    Rename(String),
+    KeepBoth,
+}
+
+#[derive(Debug, Clone)]
+struct Resolution {
+    action: ConflictAction,
+    apply_to_all: bool,
+}
+
+pub enum Event {
+    Finished,
+    /// The job succeeded, with no `AfterAction` to run.
+    Completed {
+        kind: JobKind,
+        count: usize,
+        destination: Option<PathBuf>,
+    },
+    Failed(String),
+    /// The job found `PathBuf` taken, and waits to hear what to do.
+    Conflict(usize, PathBuf),
+    /// The job succeeded and asked for `AfterAction` to run on the given
+    /// destination (or, for deletions, the directory the items were in).
+    FollowUp(AfterAction, PathBuf),
+}
+
+/// How a running job is paused, stopped, or told what to do about a name
+/// taken, from outside the thread doing it.
+#[derive(Default)]
+pub struct Control {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    decision: Mutex<Option<Resolution>>,
+    decided: Condvar,
+}
+
+impl Control {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    fn decide(&self, resolution: Resolution) {
+        *self.decision.lock().unwrap_or_else(PoisonError::into_inner) = Some(resolution);
+        self.decided.notify_all();
+    }
+
+    fn wait_for_decision(&self) -> io::Result<Resolution> {
+        let mut decision = self.decision.lock().unwrap_or_else(PoisonError::into_inner);
+
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            if let Some(resolution) = decision.take() {
+                return Ok(resolution);
+            }
+            decision = self
+                .decided
+                .wait_timeout(decision, REPORT_INTERVAL)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    fn wait_if_paused(&self) -> io::Result<()> {
+        while self.paused.load(Ordering::Relaxed) {
+            if self.cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            thread::sleep(REPORT_INTERVAL);
+        }
+
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Job {
+    pub id: usize,
+    pub kind: JobKind,
+    pub sources: Vec<PathBuf>,
+    pub destination: Option<PathBuf>,
+    pub options: Options,
+    pub after: AfterAction,
+    pub status: JobStatus,
+    /// Those of its sources and destination, which it runs alone on.
+    devices: Vec<Device>,
+    waiting: bool,
+    /// Where items went, for undoing the job.
+    moved: Vec<(PathBuf, PathBuf)>,
+    done: u64,
+    total: u64,
+    started: Option<Instant>,
+    paused_at: Option<Instant>,
+    paused_for: Duration,
+    control: Arc<Control>,
+}
+
+impl Job {
+    fn new(
+        id: usize,
+        kind: JobKind,
+        sources: Vec<PathBuf>,
+        destination: Option<PathBuf>,
+        options: Options,
+    ) -> Self {
+        let mut paths: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+        paths.extend(destination.as_deref());
+        let devices = devices::of(&paths);
+        Job {
+            id,
+            kind,
+            sources,
+            destination,
+            options,
+            after: AfterAction::Nothing,
+            status: JobStatus::Queued,
+            devices,
+            waiting: false,
+            moved: Vec::new(),
+            done: 0,
+            total: 0,
+            started: None,
+            paused_at: None,
+            paused_for: Duration::ZERO,
+            control: Arc::new(Control::default()),
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        let Some(started) = self.started else {
+            return Duration::ZERO;
+        };
+        let paused = match self.paused_at {
+            Some(at) => self.paused_for + at.elapsed(),
+            None => self.paused_for,
+        };
+
+        started.elapsed().saturating_sub(paused)
+    }
+
+    fn speed(&self) -> f64 {
+        let secs = self.elapsed().as_secs_f64();
+        if secs > 0.0 {
+            self.done as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    fn eta(&self) -> Option<Duration> {
+        let speed = self.speed();
+        if speed > 0.0 && self.total >= self.done {
+            Some(Duration::from_secs_f64(
+                (self.total - self.done) as f64 / speed,
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn units(&self, amount: u64) -> String {
+        match self.kind {
+            JobKind::Trash
+            | JobKind::Delete
+            | JobKind::Convert(_)
+            | JobKind::Recode(_)
+            | JobKind::LineEndings(_) => match amount {
+                1 => String::from(tr("1 item")),
+                amount => trf("{} items", &[&amount]),
+            },
+            JobKind::Copy | JobKind::Move | JobKind::Compress(_) | JobKind::Extract => {
+                format_bytes(amount)
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        struct Worker;
+
+        let id = self.id;
+        let kind = self.kind;
+        let sources = self.sources.clone();
+        let destination = self.destination.clone();
+        let options = self.options;
+        let devices = self.devices.clone();
+        let control = self.control.clone();
+
+        subscription::channel(
+            (TypeId::of::<Worker>(), id),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+
+                thread::spawn(move || {
+                    let turn = devices::take(&devices).or_else(|| {
+                        let _ = sender.unbounded_send(Progress::Waiting);
+                        devices::wait(&devices, || control.is_cancelled())
+                    });
+                    let Some(_turn) = turn else {
+                        let _ = sender.unbounded_send(Progress::Cancelled);
+                        return;
+                    };
+                    let progress = match run(
+                        kind,
+                        &sources,
+                        destination.as_deref(),
+                        options,
+                        match options {
+                            Options {
+                                overwrite: true, ..
+                            } => Some(ConflictAction::Overwrite),
+                            Options {
+                                keep_both: true, ..
+                            } => Some(ConflictAction::KeepBoth),
+                            _ => None,
+                        },
+                        &control,
+                        &sender,
+                    ) {
+                        Ok(()) => Progress::Finished,
+                        Err(error) if error.kind() == io::ErrorKind::Interrupted => {
+                            Progress::Cancelled
+                        }
+                        Err(error) => Progress::Failed(error.to_string()),
+                    };
+                    let _ = sender.unbounded_send(progress);
+                });
+
+                while let Some(progress) = receiver.next().await {
+                    let _ = output.send(Message::Progress(id, progress)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let count = self.sources.len();
+        let what = match (&self.destination, count) {
+            (Some(destination), 1) => trf("{} 1 item to {}", &[&self.kind, &destination.display()]),
+            (Some(destination), count) => trf(
+                "{} {} items to {}",
+                &[&self.kind, &count, &destination.display()],
+            ),
+            (None, 1) => trf("{} 1 item", &[&self.kind]),
+            (None, count) => trf("{} {} items", &[&self.kind, &count]),
+        };
+
+        let state = match &self.status {
+            JobStatus::Queued => String::from(tr("Queued")),
+            JobStatus::Running if self.waiting => String::from(tr("Waiting for its disk")),
+            JobStatus::Running | JobStatus::Paused => {
+                let speed = match self.kind {
+                    JobKind::Trash
+                    | JobKind::Delete
+                    | JobKind::Convert(_)
+                    | JobKind::Recode(_)
+                    | JobKind::LineEndings(_) => {
+                        trf("{} items/s", &[&format!("{:.0}", self.speed())])
+                    }
+                    JobKind::Copy | JobKind::Move | JobKind::Compress(_) | JobKind::Extract => {
+                        trf("{}/s", &[&format_bytes(self.speed() as u64)])
+                    }
+                };
+                let eta = match self.eta() {
+                    Some(eta) => trf("ETA {}s", &[&eta.as_secs()]),
+                    None => String::from(tr("ETA --")),
+                };
+                let paused = if self.status == JobStatus::Paused {
+                    tr(" (paused)")
+                } else {
+                    ""
+                };
+
+                format!(
+                    "{} / {} - {} - {}{}",
+                    self.units(self.done),
+                    self.units(self.total),
+                    speed,
+                    eta,
+                    paused
+                )
+            }
+            JobStatus::Conflict(_) => String::from(tr("Waiting for a decision")),
+            JobStatus::Done => String::from(tr("Done")),
+            JobStatus::Cancelled => String::from(tr("Cancelled")),
+            JobStatus::Failed(error) => trf("Failed: {}", &[error]),
+        };
+
+        let fraction = if self.total > 0 {
+            self.done as f32 / self.total as f32
+        } else if self.status == JobStatus::Done {
+            1.0
+        } else {
+            0.0
+        };
+
+        let mut controls = row!().spacing(5);
+        match self.status {
+            JobStatus::Running => {
+                controls = controls.push(button(tr("Pause")).on_press(Message::Pause(self.id)));
+            }
+            JobStatus::Paused => {
+                controls = controls.push(button(tr("Resume")).on_press(Message::Resume(self.id)));
+            }
+            _ => {}
+        }
+        if !self.status.is_finished() {
+            let id = self.id;
+            let options: &[AfterAction] = match self.kind {
+                JobKind::Copy
+                | JobKind::Move
+                | JobKind::Compress(_)
+                | JobKind::Extract
+                | JobKind::Convert(_) => &AfterAction::ALL,
+                JobKind::Trash | JobKind::Delete | JobKind::Recode(_) | JobKind::LineEndings(_) => {
+                    &AfterAction::ALL[..1]
+                }
+            };
+            controls = controls
+                .push(
+                    pick_list(options, Some(self.after), move |after| {
+                        Message::SetAfter(id, after)
+                    })
+                    .text_size(14),
+                )
+                .push(button(tr("Cancel")).on_press(Message::Cancel(self.id)));
+        }
+
+        row!(
+            column!(
+                text(what),
+                progress_bar(0.0..=1.0, fraction).height(8.),
+                text(state).size(14)
+            )
+            .spacing(4)
+            .width(Length::Fill),
+            controls
+        )
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    }
+}
+
+/// A copy or move not done when the picker last closed.
+#[derive(Debug, Clone)]
+struct Interrupted {
+    kind: JobKind,
+    sources: Vec<PathBuf>,
+    destination: PathBuf,
+    options: Options,
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    next_id: usize,
+    /// Keeps the copies and moves not done in `FILE`.
+    persist: bool,
+    interrupted: Vec<Interrupted>,
+    /// The jobs and how many interrupted ones `FILE` was last written
+    /// with.
+    saved: Option<(Vec<usize>, usize)>,
+}
+
+impl Jobs {
+    /// Jobs that keep the copies and moves not done, to offer resuming
+    /// them the next time, and that offer those of the last.
+    pub fn restore() -> Jobs {
+        let interrupted = file().map(|file| load(&file)).unwrap_or_default();
+        if !interrupted.is_empty() {
+            tracing::info!(count = interrupted.len(), "interrupted jobs found");
+        }
+        Jobs {
+            persist: true,
+            saved: Some((Vec::new(), interrupted.len())),
+            interrupted,
+            ..Jobs::default()
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        kind: JobKind,
+        sources: Vec<PathBuf>,
+        destination: Option<PathBuf>,
+        options: Options,
+    ) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        tracing::info!(id, ?kind, ?sources, ?destination, ?options, "job queued");
+        self.jobs
+            .push(Job::new(id, kind, sources, destination, options));
+        self.schedule();
+        self.save();
+
+        id
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty() && self.interrupted.is_empty()
+    }
+
+    /// Where finished jobs moved items, each handed out once. Jobs that
+    /// failed or were cancelled count too, for the items they got to.
+    pub fn take_moved(&mut self) -> Vec<(JobKind, Vec<(PathBuf, PathBuf)>)> {
+        self.jobs
+            .iter_mut()
+            .filter(|job| job.status.is_finished() && !job.moved.is_empty())
+            .map(|job| (job.kind, std::mem::take(&mut job.moved)))
+            .collect()
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    /// Starts the jobs first queued, up to `MAX_CONCURRENT_JOBS` and one a
+    /// device, so that two copies to the same disk go one after the other.
+    fn schedule(&mut self) {
+        let mut active = 0;
+        let mut busy = Vec::new();
+        for job in self.jobs.iter().filter(|job| job.status.is_active()) {
+            active += 1;
+            busy.extend_from_slice(&job.devices);
+        }
+
+        for job in self.jobs.iter_mut() {
+            if active >= MAX_CONCURRENT_JOBS {
+                break;
+            }
+            if job.status == JobStatus::Queued
+                && !job.devices.iter().any(|device| busy.contains(device))
+            {
+                tracing::debug!(id = job.id, "job started");
+                job.status = JobStatus::Running;
+                job.started = Some(Instant::now());
+                busy.extend_from_slice(&job.devices);
+                active += 1;
+            }
+        }
+    }
+
+    /// Writes the copies and moves not done to `FILE` when they changed.
+    fn save(&mut self) {
+        if !self.persist {
+            return;
+        }
+        let unfinished: Vec<&Job> = self
+            .jobs
+            .iter()
+            .filter(|job| {
+                matches!(job.kind, JobKind::Copy | JobKind::Move) && !job.status.is_finished()
+            })
+            .collect();
+        let state = (
+            unfinished.iter().map(|job| job.id).collect(),
+            self.interrupted.len(),
+        );
+        if self.saved.as_ref() == Some(&state) {
+            return;
+        }
+        self.saved = Some(state);
+
+        let jobs = unfinished.into_iter().filter_map(|job| {
+            Some(Interrupted {
+                kind: job.kind,
+                sources: job.sources.clone(),
+                destination: job.destination.clone()?,
+                options: job.options,
+            })
+        });
+        let jobs: Vec<Interrupted> = self.interrupted.iter().cloned().chain(jobs).collect();
+        let result = match file() {
+            Some(file) => store(&file, &jobs),
+            None => Ok(()),
+        };
+        if let Err(error) = result {
+            tracing::warn!(%error, "cannot keep the unfinished copies");
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        let mut event = None;
+
+        match message {
+            Message::Progress(id, progress) => {
+                if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+                    match progress {
+                        Progress::Waiting => job.waiting = true,
+                        Progress::Started { total } => {
+                            job.waiting = false;
+                            job.total = total;
+                        }
+                        Progress::Advanced { done } => job.done = done,
+                        Progress::Conflict { target } => {
+                            event = Some(Event::Conflict(id, target.clone()));
+                            job.status = JobStatus::Conflict(target);
+                        }
+                        Progress::Moved { from, to } => job.moved.push((from, to)),
+                        Progress::Finished => {
+                            job.done = job.total;
+                            job.status = JobStatus::Done;
+                        }
+                        Progress::Cancelled => job.status = JobStatus::Cancelled,
+                        Progress::Failed(error) => job.status = JobStatus::Failed(error),
+                    }
+                    if job.status.is_finished() {
+                        event = match &job.status {
+                            JobStatus::Failed(error) => {
+                                tracing::warn!(id, error, elapsed = ?job.elapsed(), "job failed");
+                                Some(Event::Failed(error.clone()))
+                            }
+                            status => {
+                                tracing::info!(id, ?status, elapsed = ?job.elapsed(), "job finished");
+                                let target = job.destination.clone().or_else(|| {
+                                    job.sources
+                                        .first()
+                                        .and_then(|source| source.parent())
+                                        .map(Path::to_path_buf)
+                                });
+                                match (status, job.after, target) {
+                                    (JobStatus::Done, after, Some(target))
+                                        if after != AfterAction::Nothing =>
+                                    {
+                                        Some(Event::FollowUp(after, target))
+                                    }
+                                    (JobStatus::Done, _, _) => Some(Event::Completed {
+                                        kind: job.kind,
+                                        count: job.sources.len(),
+                                        destination: job.destination.clone(),
+                                    }),
+                                    _ => Some(Event::Finished),
+                                }
+                            }
+                        };
+                    }
+                }
+            }
+            Message::Pause(id) => {
+                tracing::debug!(id, "job paused");
+                if let Some(job) = self.get_mut(id) {
+                    if job.status == JobStatus::Running {
+                        job.control.paused.store(true, Ordering::Relaxed);
+                        job.status = JobStatus::Paused;
+                        job.paused_at = Some(Instant::now());
+                    }
+                }
+            }
+            Message::Resume(id) => {
+                tracing::debug!(id, "job resumed");
+                if let Some(job) = self.get_mut(id) {
+                    if job.status == JobStatus::Paused {
+                        job.control.paused.store(false, Ordering::Relaxed);
+                        job.status = JobStatus::Running;
+                        if let Some(at) = job.paused_at.take() {
+                            job.paused_for += at.elapsed();
+                        }
+                    }
+                }
+            }
+            Message::Cancel(id) => {
+                tracing::debug!(id, "job cancel requested");
+                if let Some(job) = self.get_mut(id) {
+                    job.control.cancelled.store(true, Ordering::Relaxed);
+                    if job.status == JobStatus::Queued {
+                        job.status = JobStatus::Cancelled;
+                        event = Some(Event::Finished);
+                    }
+                }
+            }
+            Message::SetAfter(id, after) => {
+                if let Some(job) = self.get_mut(id) {
+                    job.after = after;
+                }
+            }
+            Message::ClearFinished => self.jobs.retain(|job| !job.status.is_finished()),
+            Message::Resolve(id, action, apply_to_all) => {
+                if let Some(job) = self.get_mut(id) {
+                    if let JobStatus::Conflict(_) = job.status {
+                        tracing::debug!(id, ?action, apply_to_all, "conflict resolved");
+                        job.control.decide(Resolution {
+                            action,
+                            apply_to_all,
+                        });
+                        job.status = if job.control.paused.load(Ordering::Relaxed) {
+                            JobStatus::Paused
+                        } else {
+                            JobStatus::Running
+                        };
+                    }
+                }
+            }
+            Message::ResumeInterrupted => {
+                for interrupted in std::mem::take(&mut self.interrupted) {
+                    // What a move got to is no longer there to move.
+                    let sources: Vec<PathBuf> = interrupted
+                        .sources
+                        .into_iter()
+                        .filter(|source| vfs::exists(source))
+                        .collect();
+                    if sources.is_empty() {
+                        continue;
+                    }
+                    let options = Options {
+                        resume: true,
+                        ..interrupted.options
+                    };
+                    self.push(
+                        interrupted.kind,
+                        sources,
+                        Some(interrupted.destination),
+                        options,
+                    );
+                }
+            }
+            Message::DiscardInterrupted => self.interrupted.clear(),
+        }
+
+        self.schedule();
+        self.save();
+
+        event
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(
+            self.jobs
+                .iter()
+                .filter(|job| job.status.is_active())
+                .map(Job::subscription),
+        )
+    }
+
+    /// What job `id` found taken, while it waits to hear what to do.
+    pub fn conflict(&self, id: usize) -> Option<&Path> {
+        match &self.jobs.iter().find(|job| job.id == id)?.status {
+            JobStatus::Conflict(target) => Some(target),
+            _ => None,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let mut col = column!(row!(
+            text(tr("Transfers")).width(Length::Fill),
+            button(tr("Clear finished")).on_press(Message::ClearFinished)
+        )
+        .align_items(Alignment::Center))
+        .spacing(10);
+
+        if !self.interrupted.is_empty() {
+            col = col.push(
+                row!(
+                    text(match self.interrupted.len() {
+                        1 =>
+                            String::from(tr("A copy or move was not done when the picker closed.")),
+                        count => trf(
+                            "{} copies or moves were not done when the picker closed.",
+                            &[&count],
+                        ),
+                    })
+                    .width(Length::Fill),
+                    button(tr("Discard")).on_press(Message::DiscardInterrupted),
+                    button(tr("Resume")).on_press(Message::ResumeInterrupted),
+                )
+                .spacing(5)
+                .align_items(Alignment::Center),
+            );
+        }
+        for job in &self.jobs {
+            col = col.push(job.view());
+        }
+
+        col.into()
+    }
+}
+
+fn file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(FILE))
+}
+
+/// What `file` holds, or nothing if it cannot be read.
+fn load(file: &Path) -> Vec<Interrupted> {
+    let document = match fs::read_to_string(file) {
+        Ok(text) => text.parse::<Document>().map_err(io::Error::other),
+        Err(error) => Err(error),
+    };
+    let document = match document {
+        Ok(document) => document,
+        Err(error) => {
+            if error.kind() != io::ErrorKind::NotFound {
+                tracing::warn!(%error, file = %file.display(), "cannot read the unfinished copies");
+            }
+            return Vec::new();
+        }
+    };
+
+    let Some(jobs) = document.get("job").and_then(Item::as_array_of_tables) else {
+        return Vec::new();
+    };
+    jobs.iter()
+        .filter_map(|job| {
+            let flag = |key| job.get(key).and_then(Item::as_bool).unwrap_or(false);
+            Some(Interrupted {
+                kind: match job.get("kind")?.as_str()? {
+                    "copy" => JobKind::Copy,
+                    "move" => JobKind::Move,
+                    _ => return None,
+                },
+                sources: job
+                    .get("sources")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(|source| source.as_str().map(PathBuf::from))
+                    .collect(),
+                destination: PathBuf::from(job.get("destination")?.as_str()?),
+                options: Options {
+                    preserve_timestamps: flag("preserve_timestamps"),
+                    low_memory: flag("low_memory"),
+                    overwrite: flag("overwrite"),
+                    keep_both: flag("keep_both"),
+                    resume: false,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Writes `jobs` to `file`, or removes it when there are none.
+fn store(file: &Path, jobs: &[Interrupted]) -> io::Result<()> {
+    if jobs.is_empty() {
+        return match fs::remove_file(file) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        };
+    }
+
+    let mut tables = ArrayOfTables::new();
+    for job in jobs {
+        let mut table = Table::new();
+        table["kind"] = value(match job.kind {
+            JobKind::Move => "move",
+            _ => "copy",
+        });
+        let mut sources = Array::new();
+        for source in &job.sources {
+            sources.push(source.to_string_lossy().as_ref());
+        }
+        table["sources"] = value(sources);
+        table["destination"] = value(job.destination.to_string_lossy().as_ref());
+        table["preserve_timestamps"] = value(job.options.preserve_timestamps);
+        table["low_memory"] = value(job.options.low_memory);
+        table["overwrite"] = value(job.options.overwrite);
+        table["keep_both"] = value(job.options.keep_both);
+        tables.push(table);
+    }
+    let mut document = Document::new();
+    document["job"] = Item::ArrayOfTables(tables);
+
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    safe_save::write(file, document.to_string().as_bytes())
+}
+
+/// The units sizes are written in from now on.
+pub fn set_size_units(units: SizeUnits) {
+    DECIMAL.store(units == SizeUnits::Decimal, Ordering::Relaxed);
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    let (step, units) = match DECIMAL.load(Ordering::Relaxed) {
+        true => (1000.0, ["B", "kB", "MB", "GB", "TB"]),
+        false => (1024.0, ["B", "KB", "MB", "GB", "TB"]),
+    };
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= step && unit < units.len() - 1 {
+        size /= step;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+struct Reporter<'a> {
+    sender: &'a mpsc::UnboundedSender<Progress>,
+    done: u64,
+    last: Instant,
+}
+
+impl Reporter<'_> {
+    fn advance(&mut self, amount: u64) {
+        self.done += amount;
+        if self.last.elapsed() >= rendering::update_interval() {
+            self.last = Instant::now();
+            let _ = self
+                .sender
+                .unbounded_send(Progress::Advanced { done: self.done });
+        }
+    }
+}
+
+/// Does the job on this thread, reporting to `sender`. A name taken is
+/// dealt with as `conflicts` says, or asked about when it says nothing.
+#[tracing::instrument(skip(control, sender))]
+pub fn run(
+    kind: JobKind,
+    sources: &[PathBuf],
+    destination: Option<&Path>,
+    options: Options,
+    conflicts: Option<ConflictAction>,
+    control: &Control,
+    sender: &mpsc::UnboundedSender<Progress>,
+) -> io::Result<()> {
+    let mut total = 0;
+    for source in sources {
+        total += match kind {
+            JobKind::Trash | JobKind::Convert(_) | JobKind::Recode(_) | JobKind::LineEndings(_) => {
+                1
+            }
+            JobKind::Delete => count_entries(source)?,
+            JobKind::Copy | JobKind::Move | JobKind::Compress(_) => tree_size(source)?,
+            JobKind::Extract => {
+                let (archive, inner) = in_archive(source);
+                archive::size(&archive, &[inner])?
+            }
+        };
+    }
+    tracing::debug!(total, "job measured");
+    let _ = sender.unbounded_send(Progress::Started { total });
+
+    let mut reporter = Reporter {
+        sender,
+        done: 0,
+        last: Instant::now(),
+    };
+    let mut remembered = conflicts;
+
+    if let JobKind::Compress(compression) = kind {
+        let destination = destination
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no destination"))?;
+        return compress(sources, destination, compression, control, &mut reporter);
+    }
+
+    for source in sources {
+        match kind {
+            JobKind::Trash => {
+                control.wait_if_paused()?;
+                let to = trash::trash(source)?;
+                let _ = sender.unbounded_send(Progress::Moved {
+                    from: source.clone(),
+                    to,
+                });
+                reporter.advance(1);
+            }
+            JobKind::Delete => delete_tree(source, control, &mut reporter)?,
+            // Written as a whole above.
+            JobKind::Compress(_) => {}
+            JobKind::Convert(conversion) => {
+                control.wait_if_paused()?;
+                let destination = destination
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no destination"))?;
+                vfs::create_dir_all(destination)?;
+                let mut name = source.file_stem().unwrap_or_default().to_os_string();
+                name.push(".");
+                name.push(conversion.format.extension());
+                let target = resolve_conflicts(
+                    destination.join(name),
+                    Some(source),
+                    control,
+                    sender,
+                    &mut remembered,
+                )?;
+                if let Some(target) = target {
+                    images::convert(source, &target, conversion)?;
+                }
+                reporter.advance(1);
+            }
+            JobKind::Recode(bom) => {
+                control.wait_if_paused()?;
+                encoding::to_utf8(source, bom)?;
+                reporter.advance(1);
+            }
+            JobKind::LineEndings(to) => {
+                control.wait_if_paused()?;
+                line_endings::convert(source, to)?;
+                reporter.advance(1);
+            }
+            JobKind::Extract => {
+                let destination = destination
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no destination"))?;
+                extract(
+                    source,
+                    destination,
+                    control,
+                    sender,
+                    &mut reporter,
+                    &mut remembered,
+                )?
+            }
+            JobKind::Copy | JobKind::Move => {
+                let destination = destination
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no destination"))?;
+                if vfs::is_dir(source) && paths::starts_with(destination, source) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "cannot copy a directory into itself",
+                    ));
+                }
+
+                // What is in the way of a resumed copy is what it got to.
+                let resumed = source
+                    .file_name()
+                    .filter(|_| options.resume)
+                    .map(|name| destination.join(name))
+                    .filter(|target| vfs::exists(target));
+                let target = match resumed {
+                    Some(target) => target,
+                    None => {
+                        match resolve_target(source, destination, control, sender, &mut remembered)?
+                        {
+                            Some(target) => target,
+                            None => {
+                                reporter.advance(tree_size(source)?);
+                                continue;
+                            }
+                        }
+                    }
+                };
+
+                if kind == JobKind::Move && vfs::for_path(source).rename(source, &target).is_ok() {
+                    reporter.advance(tree_size(&target)?);
+                    let _ = sender.unbounded_send(Progress::Moved {
+                        from: source.clone(),
+                        to: target,
+                    });
+                    continue;
+                }
+
+                let moving = kind == JobKind::Move;
+                copy_tree(source, &target, moving, options, control, &mut reporter)?;
+
+                if kind == JobKind::Move {
+                    // Across drives the original only goes once the copy is
+                    // known to be whole.
+                    verify_copy(source, &target)?;
+                    vfs::remove_tree(source)?;
+                    let _ = sender.unbounded_send(Progress::Moved {
+                        from: source.clone(),
+                        to: target,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Picks where `source` ends up inside `destination`, asking the user through
+/// the conflict dialog when the name is taken. `None` means skip this item.
+fn resolve_target(
+    source: &Path,
+    destination: &Path,
+    control: &Control,
+    sender: &mpsc::UnboundedSender<Progress>,
+    remembered: &mut Option<ConflictAction>,
+) -> io::Result<Option<PathBuf>> {
+    let name = source
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid source"))?;
+
+    resolve_conflicts(
+        destination.join(name),
+        Some(source),
+        control,
+        sender,
+        remembered,
+    )
+}
+
+/// Asks through the conflict dialog what to do for as long as `target` is
+/// taken, clearing it out for an overwrite unless it is `source` itself.
+fn resolve_conflicts(
+    mut target: PathBuf,
+    source: Option<&Path>,
+    control: &Control,
+    sender: &mpsc::UnboundedSender<Progress>,
+    remembered: &mut Option<ConflictAction>,
+) -> io::Result<Option<PathBuf>> {
+    while vfs::exists(&target) {
+        let action = match remembered {
+            Some(action) => action.clone(),
+            None => {
+                let _ = sender.unbounded_send(Progress::Conflict {
+                    target: target.clone(),
+                });
+                let resolution = control.wait_for_decision()?;
+                if resolution.apply_to_all
+                    && !matches!(resolution.action, ConflictAction::Rename(_))
+                {
+                    *remembered = Some(resolution.action.clone());
+                }
+                resolution.action
+            }
+        };
+
+        match action {
+            ConflictAction::Skip => return Ok(None),
+            ConflictAction::Overwrite => {
+                if source.is_some_and(|source| paths::same(&target, source)) {
+                    return Ok(None);
+                }
+                vfs::remove_tree(&target)?;
+            }
+            ConflictAction::Rename(name) => target.set_file_name(name),
+            ConflictAction::KeepBoth => target = unique_name(&target),
+        }
+    }
+
+    Ok(Some(target))
+}
+
+/// `path` with a number added, the first not taken.
+pub fn unique_name(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (2..)
+        .map(|n| parent.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !vfs::exists(candidate))
+        .expect("unused file name")
+}
+
+fn tree_size(path: &Path) -> io::Result<u64> {
+    let fs = vfs::for_path(path);
+    let meta = fs.metadata(path)?;
+    if !meta.is_dir {
+        return Ok(meta.len);
+    }
+
+    let mut size = 0;
+    for path in fs.read_dir(path)? {
+        size += tree_size(&path?)?;
+    }
+
+    Ok(size)
+}
+
+/// Fails unless `target` holds as many entries and bytes as `source`.
+fn verify_copy(source: &Path, target: &Path) -> io::Result<()> {
+    if count_entries(source)? == count_entries(target)? && tree_size(source)? == tree_size(target)?
+    {
+        return Ok(());
+    }
+
+    Err(io::Error::other(format!(
+        "the copy of {} does not match it, so it was not removed",
+        source.display()
+    )))
+}
+
+fn count_entries(path: &Path) -> io::Result<u64> {
+    let fs = vfs::for_path(path);
+    if !fs.metadata(path)?.is_dir {
+        return Ok(1);
+    }
+
+    let mut count = 1;
+    for path in fs.read_dir(path)? {
+        count += count_entries(&path?)?;
+    }
+
+    Ok(count)
+}
+
+/// Copies `source` to `target`, as it is when `moving` and as a new copy
+/// otherwise.
+fn copy_tree(
+    source: &Path,
+    target: &Path,
+    moving: bool,
+    options: Options,
+    control: &Control,
+    reporter: &mut Reporter,
+) -> io::Result<()> {
+    control.wait_if_paused()?;
+
+    let (from, to) = (vfs::for_path(source), vfs::for_path(target));
+    let meta = from.metadata(source)?;
+    if meta.is_symlink {
+        reporter.advance(meta.len);
+        if options.resume && to.metadata(target).is_ok() {
+            return Ok(());
+        }
+        return to.symlink(&from.read_link(source)?, target);
+    }
+    // What a resumed copy got to.
+    let resumed = options.resume.then(|| to.metadata(target).ok()).flatten();
+    if meta.is_dir {
+        if !resumed.as_ref().is_some_and(|resumed| resumed.is_dir) {
+            to.create_dir(target)?;
+        }
+        for path in from.read_dir(source)? {
+            let path = path?;
+            let name = path.file_name().unwrap_or_default();
+            copy_tree(
+                &path,
+                &target.join(name),
+                moving,
+                options,
+                control,
+                reporter,
+            )?;
+        }
+        return copy_attributes(source, target, moving, options);
+    }
+
+    if resumed.is_some_and(|resumed| !resumed.is_dir && resumed.len == meta.len) {
+        reporter.advance(meta.len);
+        return Ok(());
+    }
+
+    // What fits in one buffer goes in one call, letting the backend copy it
+    // its own way.
+    if meta.len < options.buffer_size() as u64 && vfs::same_backend(source, target) {
+        let copied = from.copy(source, target)?;
+        reporter.advance(copied);
+        return copy_attributes(source, target, moving, options);
+    }
+
+    let mut reader = from.open_read(source)?;
+    let mut writer = to.open_write(target)?;
+    let mut buffer = vec![0; options.buffer_size()];
+
+    loop {
+        if let Err(error) = control.wait_if_paused() {
+            drop(writer);
+            let _ = to.delete(target);
+            return Err(error);
+        }
+
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        reporter.advance(read as u64);
+    }
+    writer.flush()?;
+    drop(writer);
+
+    copy_attributes(source, target, moving, options)
+}
+
+/// Permissions and timestamps, which only the local disk keeps. What is
+/// moved keeps both as they were; a copy gets its permissions through the
+/// umask, like a new file, and its timestamps if asked for.
+fn copy_attributes(source: &Path, target: &Path, moving: bool, options: Options) -> io::Result<()> {
+    if !vfs::is_local(source) || !vfs::is_local(target) {
+        return Ok(());
+    }
+    let meta = fs::symlink_metadata(source)?;
+    fs::set_permissions(
+        target,
+        match moving {
+            true => meta.permissions(),
+            false => masked_permissions(&meta),
+        },
+    )?;
+    if moving || options.preserve_timestamps {
+        copy_timestamps(&meta, &open_for_times(target, meta.is_dir())?)?;
+    }
+
+    Ok(())
+}
+
+/// `path` opened only to set its times. Folders cannot be opened for
+/// writing.
+#[cfg(not(windows))]
+fn open_for_times(path: &Path, is_dir: bool) -> io::Result<fs::File> {
+    match is_dir {
+        true => fs::File::open(path),
+        false => fs::File::options().write(true).open(path),
+    }
+}
+
+/// Windows opens folders only with backup semantics, and setting times
+/// needs no more than the right to write attributes.
+#[cfg(windows)]
+fn open_for_times(path: &Path, _is_dir: bool) -> io::Result<fs::File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_WRITE_ATTRIBUTES,
+    };
+
+    fs::File::options()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+/// The source's permissions with the process umask applied, so copies come
+/// out the same way freshly created files would.
+#[cfg(unix)]
+fn masked_permissions(meta: &fs::Metadata) -> fs::Permissions {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::Permissions::from_mode(meta.permissions().mode() & !umask())
+}
+
+#[cfg(not(unix))]
+fn masked_permissions(meta: &fs::Metadata) -> fs::Permissions {
+    meta.permissions()
+}
+
+#[cfg(unix)]
+fn umask() -> u32 {
+    static UMASK: std::sync::OnceLock<u32> = std::sync::OnceLock::new();
+
+    *UMASK.get_or_init(|| {
+        let from_proc = fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Umask:"))
+                    .and_then(|mask| u32::from_str_radix(mask.trim(), 8).ok())
+            });
+
+        // SAFETY: umask cannot fail; the original mask is restored at once.
+        from_proc.unwrap_or_else(|| unsafe {
+            let mask = libc::umask(0o022);
+            libc::umask(mask);
+            mask as u32
+        })
+    })
+}
+
+fn copy_timestamps(meta: &fs::Metadata, file: &fs::File) -> io::Result<()> {
+    let mut times = fs::FileTimes::new();
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = meta.modified() {
+        times = times.set_modified(modified);
+    }
+
+    file.set_times(times)
+}
+
+/// Writes `sources` into a new archive at `destination`, which is removed
+/// again when the job fails or is cancelled. Archives are written on the
+/// disk, going back over their headers, though what goes in may come from
+/// anywhere.
+fn compress(
+    sources: &[PathBuf],
+    destination: &Path,
+    compression: Compression,
+    control: &Control,
+    reporter: &mut Reporter,
+) -> io::Result<()> {
+    if !vfs::is_local(destination) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "archives can only be written on this computer",
+        ));
+    }
+    let mut writer = archive::create(destination, compression)?;
+    let written = sources
+        .iter()
+        .try_for_each(|source| {
+            let name = source
+                .file_name()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid source"))?;
+            compress_tree(
+                source,
+                &name.to_string_lossy(),
+                destination,
+                &mut writer,
+                control,
+                reporter,
+            )
+        })
+        .and_then(|()| writer.finish());
+    if written.is_err() {
+        let _ = vfs::for_path(destination).delete(destination);
+    }
+
+    written
+}
+
+fn compress_tree(
+    path: &Path,
+    name: &str,
+    destination: &Path,
+    writer: &mut archive::Writer,
+    control: &Control,
+    reporter: &mut Reporter,
+) -> io::Result<()> {
+    control.wait_if_paused()?;
+    // The archive may be written inside a folder being compressed.
+    if path == destination {
+        return Ok(());
+    }
+
+    let fs = vfs::for_path(path);
+    let meta = fs.metadata(path)?;
+    if meta.is_symlink {
+        writer.add_link(name, &meta, &fs.read_link(path)?)?;
+        reporter.advance(meta.len);
+        return Ok(());
+    }
+    if meta.is_dir {
+        writer.add_dir(name, &meta)?;
+        for child in fs.read_dir(path)? {
+            let child = child?;
+            let name = format!(
+                "{}/{}",
+                name,
+                child.file_name().unwrap_or_default().to_string_lossy()
+            );
+            compress_tree(&child, &name, destination, writer, control, reporter)?;
+        }
+        return Ok(());
+    }
+
+    let mut reader = Tracked {
+        inner: fs.open_read(path)?,
+        control,
+        reporter,
+    };
+    writer.add_file(name, &meta, &mut reader)
+}
+
+/// An archive file as a whole, or the archive a path is inside of.
+fn in_archive(source: &Path) -> (PathBuf, PathBuf) {
+    archive::split(source).unwrap_or_else(|| (source.to_path_buf(), PathBuf::new()))
+}
+
+/// Unpacks `source` into `destination`, asking about each file already
+/// there. A whole archive holding more than one item at the top gets a
+/// folder of its own, so it does not scatter across the destination.
+fn extract(
+    source: &Path,
+    destination: &Path,
+    control: &Control,
+    sender: &mpsc::UnboundedSender<Progress>,
+    reporter: &mut Reporter,
+    remembered: &mut Option<ConflictAction>,
+) -> io::Result<()> {
+    let (archive, inner) = in_archive(source);
+    let mut destination = destination.to_path_buf();
+    if inner.as_os_str().is_empty() && archive::children(&archive, &inner)?.len() > 1 {
+        let name = archive::stem(&archive).unwrap_or_else(|| "Extracted".to_string());
+        destination.push(name);
+        if vfs::exists(&destination) {
+            destination = unique_name(&destination);
+        }
+        vfs::for_path(&destination).create_dir(&destination)?;
+    }
+
+    archive::extract(&archive, &[inner], &destination, |target, is_dir, data| {
+        control.wait_if_paused()?;
+        let mut data = Tracked {
+            inner: data,
+            control,
+            reporter: &mut *reporter,
+        };
+        if is_dir {
+            return vfs::create_dir_all(&target);
+        }
+        let Some(target) = resolve_conflicts(target, None, control, sender, remembered)? else {
+            io::copy(&mut data, &mut io::sink())?;
+            return Ok(());
+        };
+
+        if let Some(parent) = target.parent() {
+            vfs::create_dir_all(parent)?;
+        }
+        let fs = vfs::for_path(&target);
+        let mut file = fs.open_write(&target)?;
+        if let Err(error) = io::copy(&mut data, &mut file).and_then(|_| file.flush()) {
+            drop(file);
+            let _ = fs.delete(&target);
+            return Err(error);
+        }
+        Ok(())
+    })
+}
+
+/// Reports what is read from `inner`, stopping when paused or cancelled.
+struct Tracked<'a, 'b, R> {
+    inner: R,
+    control: &'a Control,
+    reporter: &'a mut Reporter<'b>,
+}
+
+impl<R: Read> Read for Tracked<'_, '_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.control.wait_if_paused()?;
+        let read = self.inner.read(buffer)?;
+        self.reporter.advance(read as u64);
+        Ok(read)
+    }
+}
+
+fn delete_tree(path: &Path, control: &Control, reporter: &mut Reporter) -> io::Result<()> {
+    control.wait_if_paused()?;
+
+    let fs = vfs::for_path(path);
+    if fs.metadata(path)?.is_dir {
+        for path in fs.read_dir(path)? {
+            delete_tree(&path?, control, reporter)?;
+        }
+    }
+    fs.delete(path)?;
+    reporter.advance(1);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::memory;
+
+    /// Jobs over paths in memory, which are on no device of their own.
+    fn queued(name: &str, count: usize) -> Jobs {
+        let mut jobs = Jobs::default();
+        for index in 0..count {
+            let source = Path::new(memory::ROOT).join(name).join(index.to_string());
+            jobs.push(JobKind::Delete, vec![source], None, Options::default());
+        }
+        jobs
+    }
+
+    fn statuses(jobs: &Jobs) -> Vec<JobStatus> {
+        jobs.jobs.iter().map(|job| job.status.clone()).collect()
+    }
+
+    #[test]
+    fn runs_two_at_a_time_and_starts_the_next_when_one_ends() {
+        let mut jobs = queued("jobs-queue", 3);
+        assert_eq!(
+            statuses(&jobs),
+            [JobStatus::Running, JobStatus::Running, JobStatus::Queued]
+        );
+
+        let event = jobs.update(Message::Progress(0, Progress::Finished));
+        assert!(matches!(event, Some(Event::Completed { count: 1, .. })));
+        assert_eq!(
+            statuses(&jobs),
+            [JobStatus::Done, JobStatus::Running, JobStatus::Running]
+        );
+
+        let event = jobs.update(Message::Progress(1, Progress::Failed("gone".into())));
+        assert!(matches!(event, Some(Event::Failed(error)) if error == "gone"));
+        jobs.update(Message::ClearFinished);
+        assert_eq!(statuses(&jobs), [JobStatus::Running]);
+    }
+
+    #[test]
+    fn a_queued_job_cancelled_ends_at_once() {
+        let mut jobs = queued("jobs-cancel", 3);
+        let event = jobs.update(Message::Cancel(2));
+        assert!(matches!(event, Some(Event::Finished)));
+        assert_eq!(jobs.jobs[2].status, JobStatus::Cancelled);
+
+        // A running one only hears of it, and ends when it stops.
+        assert!(jobs.update(Message::Cancel(0)).is_none());
+        assert!(jobs.jobs[0].control.is_cancelled());
+        assert_eq!(jobs.jobs[0].status, JobStatus::Running);
+    }
+
+    #[test]
+    fn a_conflict_waits_for_an_answer_and_keeps_the_job_paused() {
+        let mut jobs = queued("jobs-conflict", 1);
+        jobs.update(Message::Pause(0));
+        assert_eq!(statuses(&jobs), [JobStatus::Paused]);
+
+        let target = PathBuf::from("/taken");
+        let event = jobs.update(Message::Progress(
+            0,
+            Progress::Conflict {
+                target: target.clone(),
+            },
+        ));
+        assert!(matches!(event, Some(Event::Conflict(0, ref taken)) if *taken == target));
+        assert_eq!(jobs.conflict(0), Some(target.as_path()));
+
+        jobs.update(Message::Resolve(0, ConflictAction::Skip, true));
+        assert_eq!(statuses(&jobs), [JobStatus::Paused]);
+        let resolution = jobs.jobs[0]
+            .control
+            .decision
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap();
+        assert_eq!(resolution.action, ConflictAction::Skip);
+        assert!(resolution.apply_to_all);
+
+        jobs.update(Message::Resume(0));
+        assert_eq!(statuses(&jobs), [JobStatus::Running]);
+    }
+
+    #[test]
+    fn copies_keeping_both_when_the_name_is_taken() {
+        let root = Path::new(memory::ROOT).join("jobs-copy");
+        memory::FS.clear(&root);
+        memory::FS.add_dir(&root.join("to"));
+        memory::FS.add_file(&root.join("a.txt"), b"new");
+        memory::FS.add_file(&root.join("to").join("a.txt"), b"old");
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        run(
+            JobKind::Copy,
+            &[root.join("a.txt")],
+            Some(&root.join("to")),
+            Options::default(),
+            Some(ConflictAction::KeepBoth),
+            &Control::default(),
+            &sender,
+        )
+        .unwrap();
+        assert!(vfs::exists(&root.join("to").join("a.txt")));
+        assert!(vfs::exists(&root.join("to").join("a (2).txt")));
+        assert!(vfs::exists(&root.join("a.txt")));
+
+        let mut reported = Vec::new();
+        while let Ok(Some(progress)) = receiver.try_next() {
+            reported.push(progress);
+        }
+        assert!(matches!(reported.first(), Some(Progress::Started { .. })));
+        memory::FS.clear(&root);
+    }
+}
@@ -0,0 +1,309 @@
+//! What was done to files, newest last, so it can be undone and redone.
+//! Each operation keeps where things were and where they went; undoing
+//! only moves them back while nothing has taken their old place since.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::trf;
+use crate::paths;
+use crate::trash;
+
+/// Operations kept for undoing; older ones are forgotten.
+const LIMIT: usize = 100;
+
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        case_sensitive: bool,
+    },
+    /// Each item as (where it was, where it went).
+    Move(Vec<(PathBuf, PathBuf)>),
+    /// An empty folder or file.
+    Create { path: PathBuf, folder: bool },
+    /// Each item as (where it was, where it is in the trash).
+    Trash(Vec<(PathBuf, PathBuf)>),
+}
+
+impl Operation {
+    /// For "Undo …" and "Redo …".
+    pub fn title(&self) -> &'static str {
+        match self {
+            Operation::Rename { .. } => "rename",
+            Operation::Move(_) => "move",
+            Operation::Create { folder: true, .. } => "new folder",
+            Operation::Create { folder: false, .. } => "new file",
+            Operation::Trash(_) => "move to trash",
+        }
+    }
+
+    /// What the operation took where, as done.
+    pub fn moves(&self) -> Vec<(PathBuf, PathBuf)> {
+        match self {
+            Operation::Rename { from, to, .. } => vec![(from.clone(), to.clone())],
+            Operation::Move(moves) | Operation::Trash(moves) => moves.clone(),
+            Operation::Create { .. } => Vec::new(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let name = |path: &Path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string())
+        };
+        let items = |moves: &[(PathBuf, PathBuf)]| match moves {
+            [(from, _)] => format!("“{}”", name(from)),
+            moves => format!("{} items", moves.len()),
+        };
+        match self {
+            Operation::Rename { from, .. } => format!("“{}”", name(from)),
+            Operation::Move(moves) | Operation::Trash(moves) => items(moves),
+            Operation::Create { path, .. } => format!("“{}”", name(path)),
+        }
+    }
+
+    /// Reverses the operation, giving the one that would redo it.
+    fn undo(self) -> io::Result<Operation> {
+        match self {
+            Operation::Rename {
+                from,
+                to,
+                case_sensitive,
+            } => {
+                vacant(&from, &to)?;
+                rename(&to, &from, case_sensitive)?;
+                Ok(Operation::Rename {
+                    from,
+                    to,
+                    case_sensitive,
+                })
+            }
+            Operation::Move(moves) => {
+                for (from, to) in &moves {
+                    vacant(from, to)?;
+                }
+                for (from, to) in &moves {
+                    move_back(to, from)?;
+                }
+                Ok(Operation::Move(moves))
+            }
+            Operation::Create { path, folder } => {
+                if folder {
+                    // Only ever removed while empty, so nothing is lost.
+                    fs::remove_dir(&path).map_err(|error| match error.kind() {
+                        io::ErrorKind::NotFound => error,
+                        _ => io::Error::other(format!("{} is no longer empty", path.display())),
+                    })?;
+                } else {
+                    if fs::symlink_metadata(&path)?.len() > 0 {
+                        return Err(io::Error::other(format!(
+                            "{} has been written to since",
+                            path.display()
+                        )));
+                    }
+                    fs::remove_file(&path)?;
+                }
+                Ok(Operation::Create { path, folder })
+            }
+            Operation::Trash(moves) => {
+                for (from, _) in &moves {
+                    if fs::symlink_metadata(from).is_ok() {
+                        return Err(taken(from));
+                    }
+                }
+                for (from, trashed) in &moves {
+                    trash::restore(trashed, from)?;
+                }
+                Ok(Operation::Trash(moves))
+            }
+        }
+    }
+
+    /// Does the operation again after `undo`, giving the one that would
+    /// undo it once more.
+    fn redo(self) -> io::Result<Operation> {
+        match self {
+            Operation::Rename {
+                from,
+                to,
+                case_sensitive,
+            } => {
+                vacant(&to, &from)?;
+                rename(&from, &to, case_sensitive)?;
+                Ok(Operation::Rename {
+                    from,
+                    to,
+                    case_sensitive,
+                })
+            }
+            Operation::Move(moves) => {
+                for (from, to) in &moves {
+                    vacant(to, from)?;
+                }
+                for (from, to) in &moves {
+                    move_back(from, to)?;
+                }
+                Ok(Operation::Move(moves))
+            }
+            Operation::Create { path, folder } => {
+                if folder {
+                    fs::create_dir(&path)?;
+                } else {
+                    fs::File::options()
+                        .write(true)
+                        .create_new(true)
+                        .open(&path)?;
+                }
+                Ok(Operation::Create { path, folder })
+            }
+            Operation::Trash(moves) => {
+                // The trash picks new names, so the new places are kept.
+                let mut trashed = Vec::with_capacity(moves.len());
+                for (from, _) in moves {
+                    let place = trash::trash(&from)?;
+                    trashed.push((from, place));
+                }
+                Ok(Operation::Trash(trashed))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Journal {
+    done: Vec<Operation>,
+    undone: Vec<Operation>,
+}
+
+impl Journal {
+    pub fn record(&mut self, operation: Operation) {
+        tracing::debug!(?operation, "operation recorded");
+        self.done.push(operation);
+        if self.done.len() > LIMIT {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    pub fn next_undo(&self) -> Option<&Operation> {
+        self.done.last()
+    }
+
+    pub fn next_redo(&self) -> Option<&Operation> {
+        self.undone.last()
+    }
+
+    /// What was undone, or why it could not be. An operation that cannot
+    /// be undone is dropped rather than tried again.
+    pub fn undo(&mut self) -> Option<Result<String, String>> {
+        let operation = self.done.pop()?;
+        let title = operation.title();
+        let description = operation.describe();
+        Some(match operation.undo() {
+            Ok(redo) => {
+                self.undone.push(redo);
+                Ok(trf("Undid {}: {}", &[&title, &description]))
+            }
+            Err(error) => {
+                tracing::warn!(%error, title, "cannot undo");
+                Err(format!(
+                    "Cannot undo {} of {}: {}",
+                    title, description, error
+                ))
+            }
+        })
+    }
+
+    pub fn redo(&mut self) -> Option<Result<String, String>> {
+        let operation = self.undone.pop()?;
+        let title = operation.title();
+        let description = operation.describe();
+        Some(match operation.redo() {
+            Ok(undo) => {
+                self.done.push(undo);
+                Ok(trf("Redid {}: {}", &[&title, &description]))
+            }
+            Err(error) => {
+                tracing::warn!(%error, title, "cannot redo");
+                self.undone.clear();
+                Err(format!(
+                    "Cannot redo {} of {}: {}",
+                    title, description, error
+                ))
+            }
+        })
+    }
+}
+
+/// Checks that `path` can be moved back to `place`: it is still there,
+/// and nothing else has taken `place` meanwhile.
+fn vacant(place: &Path, path: &Path) -> io::Result<()> {
+    fs::symlink_metadata(path).map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound => {
+            io::Error::new(error.kind(), format!("{} is gone", path.display()))
+        }
+        _ => error,
+    })?;
+    if fs::symlink_metadata(place).is_ok() && !paths::same(place, path) {
+        return Err(taken(place));
+    }
+
+    Ok(())
+}
+
+fn taken(place: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} exists again", place.display()),
+    )
+}
+
+/// Items moved across drives would have to be copied back, which is left
+/// to a new move.
+fn move_back(path: &Path, place: &Path) -> io::Result<()> {
+    fs::rename(path, place).map_err(|error| {
+        if is_cross_device(&error) {
+            io::Error::other(format!(
+                "{} is on another drive now; move it back instead",
+                path.display()
+            ))
+        } else {
+            error
+        }
+    })
+}
+
+#[cfg(unix)]
+fn is_cross_device(error: &io::Error) -> bool {
+    error.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(windows)]
+fn is_cross_device(error: &io::Error) -> bool {
+    error.raw_os_error() == Some(windows_sys::Win32::Foundation::ERROR_NOT_SAME_DEVICE as i32)
+}
+
+/// A rename that only changes case goes through a temporary name on
+/// case-insensitive filesystems, where some refuse it or keep the old case.
+pub fn rename(original: &Path, path: &Path, case_sensitive: bool) -> io::Result<()> {
+    let case_only = original.file_name().is_some_and(|name| {
+        path.file_name().is_some_and(|new| {
+            new != name
+                && new.to_string_lossy().to_lowercase() == name.to_string_lossy().to_lowercase()
+        })
+    });
+    if case_sensitive || !case_only {
+        return fs::rename(original, path);
+    }
+
+    let mut temporary = original.as_os_str().to_owned();
+    temporary.push(format!(".rename-{}", std::process::id()));
+    fs::rename(original, &temporary)?;
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::rename(&temporary, original);
+    })
+}
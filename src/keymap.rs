@@ -0,0 +1,439 @@
+//! Which keys run which actions: those of a preset, with the `[keys]` table
+//! of the settings on top, such as `copy = "Ctrl+C"` or, for several,
+//! `copy = ["Ctrl+C", "Ctrl+Insert"]`. A binding is one shortcut or a
+//! sequence of them, such as `g g`; `""` leaves an action unbound.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::i18n::tr;
+use crate::menu::Shortcut;
+
+/// How many shortcuts a binding may take in a row.
+const MAX_SEQUENCE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preset {
+    #[default]
+    Standard,
+    /// The standard bindings, with `j`/`k` to move, `gg`/`G` to go to the
+    /// first and last items, `h`/`l` to go up and in and `/` to filter.
+    Vim,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 2] = [Preset::Standard, Preset::Vim];
+
+    /// As kept in the settings.
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Standard => "standard",
+            Preset::Vim => "vim",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Preset::Standard => tr("Standard"),
+            Preset::Vim => tr("Vim-style"),
+        })
+    }
+}
+
+/// What keys can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NewWindow,
+    NewFolder,
+    NewFile,
+    Properties,
+    Quit,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    PasteSymlinks,
+    Rename,
+    Edit,
+    Trash,
+    Delete,
+    CopyPath,
+    Find,
+    NextMatch,
+    PreviousMatch,
+    ParentFolder,
+    Back,
+    Forward,
+    ReopenClosed,
+    Location,
+    JumpTo,
+    OpenTerminal,
+    RunCommand,
+    Shortcuts,
+    CommandPalette,
+    Settings,
+    MenuBar,
+    Fullscreen,
+    DistractionFree,
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    SelectAll,
+    InvertSelection,
+    /// What clicking the selected item does.
+    Open,
+}
+
+impl Action {
+    pub const ALL: [Action; 40] = [
+        Action::NewWindow,
+        Action::NewFolder,
+        Action::NewFile,
+        Action::Properties,
+        Action::Quit,
+        Action::Undo,
+        Action::Redo,
+        Action::Cut,
+        Action::Copy,
+        Action::Paste,
+        Action::PasteSymlinks,
+        Action::Rename,
+        Action::Edit,
+        Action::Trash,
+        Action::Delete,
+        Action::CopyPath,
+        Action::Find,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::ParentFolder,
+        Action::Back,
+        Action::Forward,
+        Action::ReopenClosed,
+        Action::Location,
+        Action::JumpTo,
+        Action::OpenTerminal,
+        Action::RunCommand,
+        Action::Shortcuts,
+        Action::CommandPalette,
+        Action::Settings,
+        Action::MenuBar,
+        Action::Fullscreen,
+        Action::DistractionFree,
+        Action::SelectNext,
+        Action::SelectPrevious,
+        Action::SelectFirst,
+        Action::SelectLast,
+        Action::SelectAll,
+        Action::InvertSelection,
+        Action::Open,
+    ];
+
+    /// As written in the `[keys]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::NewWindow => "new-window",
+            Action::NewFolder => "new-folder",
+            Action::NewFile => "new-file",
+            Action::Properties => "properties",
+            Action::Quit => "quit",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Cut => "cut",
+            Action::Copy => "copy",
+            Action::Paste => "paste",
+            Action::PasteSymlinks => "paste-symlinks",
+            Action::Rename => "rename",
+            Action::Edit => "edit",
+            Action::Trash => "trash",
+            Action::Delete => "delete",
+            Action::CopyPath => "copy-path",
+            Action::Find => "find",
+            Action::NextMatch => "next-match",
+            Action::PreviousMatch => "previous-match",
+            Action::ParentFolder => "parent-folder",
+            Action::Back => "back",
+            Action::Forward => "forward",
+            Action::ReopenClosed => "reopen-closed",
+            Action::Location => "location",
+            Action::JumpTo => "jump-to",
+            Action::OpenTerminal => "open-terminal",
+            Action::RunCommand => "run-command",
+            Action::Shortcuts => "shortcuts",
+            Action::CommandPalette => "command-palette",
+            Action::Settings => "settings",
+            Action::MenuBar => "menu-bar",
+            Action::Fullscreen => "fullscreen",
+            Action::DistractionFree => "distraction-free",
+            Action::SelectNext => "select-next",
+            Action::SelectPrevious => "select-previous",
+            Action::SelectFirst => "select-first",
+            Action::SelectLast => "select-last",
+            Action::SelectAll => "select-all",
+            Action::InvertSelection => "invert-selection",
+            Action::Open => "open",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::NewWindow => tr("New window"),
+            Action::NewFolder => tr("New folder"),
+            Action::NewFile => tr("New file"),
+            Action::Properties => tr("Properties"),
+            Action::Quit => tr("Quit"),
+            Action::Undo => tr("Undo"),
+            Action::Redo => tr("Redo"),
+            Action::Cut => tr("Cut"),
+            Action::Copy => tr("Copy"),
+            Action::Paste => tr("Paste"),
+            Action::PasteSymlinks => tr("Create symlink here"),
+            Action::Rename => tr("Rename"),
+            Action::Edit => tr("Edit"),
+            Action::Trash => tr("Move to trash"),
+            Action::Delete => tr("Delete permanently"),
+            Action::CopyPath => tr("Copy path"),
+            Action::Find => tr("Find"),
+            Action::NextMatch => tr("Next match"),
+            Action::PreviousMatch => tr("Previous match"),
+            Action::ParentFolder => tr("Parent folder"),
+            Action::Back => tr("Back"),
+            Action::Forward => tr("Forward"),
+            Action::ReopenClosed => tr("Reopen closed window"),
+            Action::Location => tr("Location…"),
+            Action::JumpTo => tr("Jump to a recent folder…"),
+            Action::OpenTerminal => tr("Open terminal here"),
+            Action::RunCommand => tr("Run command in this folder…"),
+            Action::Shortcuts => tr("Keyboard shortcuts"),
+            Action::CommandPalette => tr("Command palette"),
+            Action::Settings => tr("Settings"),
+            Action::MenuBar => tr("Open the menu bar"),
+            Action::Fullscreen => tr("Fullscreen"),
+            Action::DistractionFree => tr("Distraction-free"),
+            Action::SelectNext => tr("Select the next item"),
+            Action::SelectPrevious => tr("Select the previous item"),
+            Action::SelectFirst => tr("Select the first item"),
+            Action::SelectLast => tr("Select the last item"),
+            Action::SelectAll => tr("Select all"),
+            Action::InvertSelection => tr("Invert selection"),
+            Action::Open => tr("Open the selected item"),
+        }
+    }
+}
+
+/// One shortcut, or several pressed one after the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding(Vec<Shortcut>);
+
+impl From<Shortcut> for Binding {
+    fn from(shortcut: Shortcut) -> Binding {
+        Binding(vec![shortcut])
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, shortcut) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", shortcut)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Binding, String> {
+        let shortcuts = text
+            .split_whitespace()
+            .map(Shortcut::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if shortcuts.is_empty() || shortcuts.len() > MAX_SEQUENCE {
+            return Err(format!("{:?} is not 1 to {} keys", text, MAX_SEQUENCE));
+        }
+        if let Some(shortcut) = shortcuts.iter().find(|shortcut| !shortcut.can_press()) {
+            return Err(format!(
+                "{} types or moves around by itself; hold Ctrl or Alt with it",
+                shortcut
+            ));
+        }
+
+        Ok(Binding(shortcuts))
+    }
+}
+
+fn standard() -> Vec<(Binding, Action)> {
+    use iced::keyboard::key::Named;
+
+    [
+        (Shortcut::ctrl('n'), Action::NewWindow),
+        (Shortcut::ctrl('n').shift(), Action::NewFolder),
+        (Shortcut::alt('n'), Action::NewFile),
+        (Shortcut::alt(Named::Enter), Action::Properties),
+        (Shortcut::ctrl('q'), Action::Quit),
+        (Shortcut::ctrl('z'), Action::Undo),
+        (Shortcut::ctrl('z').shift(), Action::Redo),
+        (Shortcut::ctrl('x'), Action::Cut),
+        (Shortcut::ctrl('c'), Action::Copy),
+        (Shortcut::ctrl('v'), Action::Paste),
+        (Shortcut::ctrl('v').shift(), Action::PasteSymlinks),
+        (Shortcut::ctrl('a'), Action::SelectAll),
+        (Shortcut::ctrl('i').shift(), Action::InvertSelection),
+        (Shortcut::new(Named::F2), Action::Rename),
+        (Shortcut::ctrl('e'), Action::Edit),
+        (Shortcut::new(Named::Delete), Action::Trash),
+        (Shortcut::new(Named::Delete).shift(), Action::Delete),
+        (Shortcut::ctrl('c').shift(), Action::CopyPath),
+        (Shortcut::ctrl('f'), Action::Find),
+        (Shortcut::new(Named::F3), Action::NextMatch),
+        (Shortcut::new(Named::F3).shift(), Action::PreviousMatch),
+        (Shortcut::alt(Named::ArrowUp), Action::ParentFolder),
+        (Shortcut::alt(Named::ArrowLeft), Action::Back),
+        (Shortcut::alt(Named::ArrowRight), Action::Forward),
+        (Shortcut::ctrl('t').shift(), Action::ReopenClosed),
+        (Shortcut::ctrl('l'), Action::Location),
+        (Shortcut::ctrl('o'), Action::JumpTo),
+        (Shortcut::new(Named::F4).shift(), Action::OpenTerminal),
+        (Shortcut::new(Named::F1), Action::Shortcuts),
+        (Shortcut::ctrl('p').shift(), Action::CommandPalette),
+        (Shortcut::ctrl(','), Action::Settings),
+        (Shortcut::new(Named::F10), Action::MenuBar),
+        (Shortcut::new(Named::F11), Action::Fullscreen),
+        (Shortcut::new(Named::F11).shift(), Action::DistractionFree),
+    ]
+    .into_iter()
+    .map(|(shortcut, action)| (Binding::from(shortcut), action))
+    .collect()
+}
+
+fn vim() -> Vec<(Binding, Action)> {
+    let mut bindings = standard();
+    bindings.extend(
+        [
+            (vec![Shortcut::new('j')], Action::SelectNext),
+            (vec![Shortcut::new('k')], Action::SelectPrevious),
+            (
+                vec![Shortcut::new('g'), Shortcut::new('g')],
+                Action::SelectFirst,
+            ),
+            (vec![Shortcut::typed('G')], Action::SelectLast),
+            (vec![Shortcut::new('h')], Action::ParentFolder),
+            (vec![Shortcut::new('l')], Action::Open),
+            (vec![Shortcut::new('/')], Action::Find),
+        ]
+        .into_iter()
+        .map(|(shortcuts, action)| (Binding(shortcuts), action)),
+    );
+
+    bindings
+}
+
+/// What a key did.
+pub enum Press {
+    Action(Action),
+    /// Started a binding of several keys, which the next may finish.
+    Pending,
+    Unbound,
+}
+
+pub struct Keymap {
+    /// In the order they are tried; the first action a binding is found
+    /// for is the one shown.
+    bindings: Vec<(Binding, Action)>,
+    pending: Vec<Shortcut>,
+    errors: Vec<String>,
+}
+
+impl Keymap {
+    /// The preset's bindings, with those of `keys`, by action name,
+    /// replacing what it has for an action and taking the keys from any
+    /// other.
+    pub fn new(preset: Preset, keys: &[(String, Vec<String>)]) -> Keymap {
+        let mut bindings = match preset {
+            Preset::Standard => standard(),
+            Preset::Vim => vim(),
+        };
+        let mut errors = Vec::new();
+        for (name, texts) in keys {
+            let Some(action) = Action::ALL.into_iter().find(|action| action.name() == name) else {
+                errors.push(format!("{}: no such action", name));
+                continue;
+            };
+            bindings.retain(|(_, bound)| *bound != action);
+            for text in texts.iter().filter(|text| !text.trim().is_empty()) {
+                match text.parse::<Binding>() {
+                    Ok(binding) => {
+                        bindings.retain(|(known, _)| *known != binding);
+                        bindings.push((binding, action));
+                    }
+                    Err(error) => errors.push(format!("{}: {}", name, error)),
+                }
+            }
+        }
+        for error in &errors {
+            tracing::warn!(%error, "ignoring a key binding");
+        }
+
+        Keymap {
+            bindings,
+            pending: Vec::new(),
+            errors,
+        }
+    }
+
+    /// The first of the keys bound to `action`.
+    pub fn binding(&self, action: Action) -> Option<Binding> {
+        self.bindings(action).next().cloned()
+    }
+
+    pub fn bindings(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(binding, _)| binding)
+    }
+
+    /// What in the settings could not be bound.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Takes a key, after those of a binding it may finish. A key that
+    /// finishes none is taken by itself instead.
+    pub fn press(&mut self, shortcut: Shortcut) -> Press {
+        self.pending.push(shortcut);
+        loop {
+            let found = self
+                .bindings
+                .iter()
+                .find(|(binding, _)| binding.0 == self.pending);
+            if let Some((_, action)) = found {
+                self.pending.clear();
+                return Press::Action(*action);
+            }
+            if self
+                .bindings
+                .iter()
+                .any(|(binding, _)| binding.0.starts_with(&self.pending))
+            {
+                return Press::Pending;
+            }
+            if self.pending.len() == 1 {
+                self.pending.clear();
+                return Press::Unbound;
+            }
+            self.pending.drain(..self.pending.len() - 1);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Asking what to do with an executable file that is opened: run it, with
+//! arguments if any are typed, or open a script to edit it.
+
+use std::path::PathBuf;
+
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{theme, Alignment, Element, Font, Length};
+
+use crate::i18n::{tr, trf};
+use crate::style::Style;
+use crate::system::Executable;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Arguments(String),
+    Run,
+    Edit,
+    Close,
+}
+
+pub enum Event {
+    Run(PathBuf, Vec<String>),
+    Edit(PathBuf),
+    Closed,
+}
+
+pub struct Launch {
+    path: PathBuf,
+    kind: Executable,
+    arguments: String,
+}
+
+impl Launch {
+    pub fn open(path: PathBuf, kind: Executable) -> Launch {
+        Launch {
+            path,
+            kind,
+            arguments: String::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Arguments(arguments) => self.arguments = arguments,
+            Message::Run => return Some(Event::Run(self.path.clone(), split(&self.arguments))),
+            Message::Edit => return Some(Event::Edit(self.path.clone())),
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let body = match self.kind {
+            Executable::Program => format!("{} is a program.", name),
+            Executable::Script => {
+                format!("{} is a script. Run it, or open it to edit?", name)
+            }
+        };
+        let arguments = text_input(tr("Arguments, if any"), &self.arguments)
+            .on_input(Message::Arguments)
+            .on_submit(Message::Run)
+            .font(Font::MONOSPACE)
+            .padding(5);
+
+        let mut buttons = row!(
+            text("").width(Length::Fill),
+            button(tr("Cancel")).on_press(Message::Close),
+        )
+        .spacing(5)
+        .align_items(Alignment::Center);
+        if self.kind == Executable::Script {
+            buttons = buttons.push(button(tr("Edit")).on_press(Message::Edit));
+        }
+        buttons = buttons.push(button(tr("Run")).on_press(Message::Run));
+
+        container(
+            column!(
+                text(trf("Run {}?", &[&name])).size(20),
+                text(body),
+                arguments,
+                text(tr("It starts in its own folder.")).size(style.small_text_size),
+                buttons,
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .max_width(480)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+/// Words as a shell splits them: at spaces outside quotes, with a
+/// backslash taking the next character as it is.
+fn split(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None | Some('"'), '\\') => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.next());
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    words
+}
@@ -0,0 +1,111 @@
+//! A file browser for iced, usable as a component inside other applications
+//! or as the standalone `filepicker` binary.
+
+mod access;
+mod accounts;
+mod appimage;
+mod archive;
+mod autostart;
+mod band;
+mod binary;
+mod bindings;
+mod breadcrumb;
+mod checksum;
+mod collation;
+mod columns;
+mod compare;
+mod completion;
+mod config;
+mod conversion;
+mod dates;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod devices;
+mod dialog;
+pub mod dirs;
+mod drag;
+mod drag_out;
+mod duplicates;
+mod editor;
+mod emblem;
+mod encoding;
+mod extensions;
+mod file_icons;
+pub mod file_manager;
+mod filepicker;
+mod folder_sizes;
+mod folder_tree;
+mod git;
+mod groups;
+mod highlight;
+pub mod history;
+mod hot_folders;
+mod i18n;
+mod images;
+mod indexer;
+pub mod instance;
+mod jobs;
+mod journal;
+mod json;
+mod keymap;
+mod launch;
+mod line_endings;
+pub mod logging;
+mod login;
+mod media;
+mod menu;
+mod mime;
+mod modal;
+mod monitors;
+mod mounts;
+mod newlines;
+mod normalize;
+#[cfg(windows)]
+mod ntfs;
+mod oci;
+pub mod operations;
+mod package;
+mod palette;
+mod paths;
+mod pictures;
+pub mod portal;
+mod prefetch;
+mod prompt;
+mod properties;
+mod recent;
+mod recode;
+mod remote;
+pub mod rendering;
+mod restrictions;
+mod safe_save;
+mod sandbox;
+mod search;
+mod selection_size;
+mod settings;
+mod shell;
+mod sidebar;
+mod sidecars;
+mod streams;
+mod style;
+mod summary;
+mod system;
+mod tags;
+mod task;
+mod toast;
+mod torrent;
+mod trash;
+pub mod tray;
+mod type_ahead;
+mod udisks;
+mod usage;
+mod validate;
+mod vfs;
+mod watcher;
+mod webdav;
+
+pub use dialog::{Dialog, DialogMode, FileFilter, Outcome, Selection};
+pub use emblem::{Emblem, Emblems};
+pub use extensions::{Action, Column, Extensions};
+pub use filepicker::{FilePicker, Message};
+pub use restrictions::Restrictions;
+pub use style::{Icons, Style};
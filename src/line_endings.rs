@@ -0,0 +1,160 @@
+//! Which line breaks a text file uses, and rewriting them all one way.
+//! Breaks are found in the file's own encoding, so UTF-16 keeps its byte
+//! order and nothing else in the file changes.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::encoding::{self, Encoding};
+use crate::safe_save;
+
+/// The breaks found in a file with at least one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEndings {
+    Lf,
+    Crlf,
+    /// Carriage returns alone, as classic Mac OS wrote them.
+    Cr,
+    Mixed,
+}
+
+impl std::fmt::Display for LineEndings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LineEndings::Lf => "LF",
+            LineEndings::Crlf => "CRLF",
+            LineEndings::Cr => "CR",
+            LineEndings::Mixed => "Mixed",
+        })
+    }
+}
+
+/// What breaks are converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::Crlf];
+
+    /// Whether converting a file with `endings` this way changes anything.
+    pub fn changes(self, endings: LineEndings) -> bool {
+        !matches!(
+            (self, endings),
+            (LineEnding::Lf, LineEndings::Lf) | (LineEnding::Crlf, LineEndings::Crlf)
+        )
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LineEnding::Lf => "LF (Linux, macOS)",
+            LineEnding::Crlf => "CRLF (Windows)",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Break {
+    Lf,
+    Crlf,
+    Cr,
+}
+
+/// The carriage return and line feed as a code unit of `encoding`.
+fn units(encoding: Encoding) -> (&'static [u8], &'static [u8]) {
+    match encoding {
+        Encoding::Utf16Le => (&[0x0D, 0], &[0x0A, 0]),
+        Encoding::Utf16Be => (&[0, 0x0D], &[0, 0x0A]),
+        Encoding::Ascii | Encoding::Utf8 | Encoding::Windows1252 => (b"\r", b"\n"),
+    }
+}
+
+/// Calls `found` with the byte range of each break in `bytes`.
+fn breaks(bytes: &[u8], encoding: Encoding, mut found: impl FnMut(usize, usize, Break)) {
+    let (cr, lf) = units(encoding);
+    let width = cr.len();
+    let mut i = 0;
+    while i + width <= bytes.len() {
+        let unit = &bytes[i..i + width];
+        if unit == lf {
+            found(i, i + width, Break::Lf);
+        } else if unit == cr {
+            if bytes.get(i + width..i + 2 * width) == Some(lf) {
+                found(i, i + 2 * width, Break::Crlf);
+                i += width;
+            } else {
+                found(i, i + width, Break::Cr);
+            }
+        }
+        i += width;
+    }
+}
+
+/// The breaks in `bytes` of text in `encoding`, or `None` when there are
+/// none. `complete` says they are the whole file, rather than a start
+/// which may end between the two halves of a CRLF.
+pub fn detect(bytes: &[u8], encoding: Encoding, complete: bool) -> Option<LineEndings> {
+    let (mut lf, mut crlf, mut cr) = (false, false, false);
+    breaks(bytes, encoding, |_, end, kind| match kind {
+        Break::Lf => lf = true,
+        Break::Crlf => crlf = true,
+        Break::Cr if complete || end < bytes.len() => cr = true,
+        Break::Cr => {}
+    });
+
+    match (lf, crlf, cr) {
+        (false, false, false) => None,
+        (true, false, false) => Some(LineEndings::Lf),
+        (false, true, false) => Some(LineEndings::Crlf),
+        (false, false, true) => Some(LineEndings::Cr),
+        _ => Some(LineEndings::Mixed),
+    }
+}
+
+/// Looks at the start of the file at `path`; `None` for a file that is not
+/// text or has no breaks.
+pub fn detect_file(path: &Path) -> io::Result<Option<LineEndings>> {
+    let (bytes, complete) = encoding::sample(path)?;
+
+    Ok(encoding::detect(&bytes, complete)
+        .and_then(|detected| detect(&bytes, detected.encoding, complete)))
+}
+
+/// Rewrites every break in the file at `path` as `to`, returning whether
+/// anything changed.
+pub fn convert(path: &Path, to: LineEnding) -> io::Result<bool> {
+    let bytes = fs::read(path)?;
+    let detected = encoding::detect(&bytes, true).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not text", path.display()),
+        )
+    })?;
+    match detect(&bytes, detected.encoding, true) {
+        Some(endings) if to.changes(endings) => {}
+        _ => return Ok(false),
+    }
+
+    let (cr, lf) = units(detected.encoding);
+    let mut contents = Vec::with_capacity(bytes.len() + bytes.len() / 32);
+    let mut start = 0;
+    breaks(&bytes, detected.encoding, |from, end, _| {
+        contents.extend_from_slice(&bytes[start..from]);
+        if to == LineEnding::Crlf {
+            contents.extend_from_slice(cr);
+        }
+        contents.extend_from_slice(lf);
+        start = end;
+    });
+    contents.extend_from_slice(&bytes[start..]);
+    safe_save::write(path, &contents)?;
+    tracing::debug!(path = %path.display(), ?to, "converted line endings");
+
+    Ok(true)
+}
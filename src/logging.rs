@@ -0,0 +1,48 @@
+use std::fs::OpenOptions;
+use std::sync::Mutex;
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+/// Environment variable holding the filter directives, e.g.
+/// `ICED_FM_LOG=filepicker::jobs=trace,info`. Falls back to `RUST_LOG`.
+pub const FILTER_ENV: &str = "ICED_FM_LOG";
+
+/// Environment variable naming a file that receives a copy of every log line.
+pub const FILE_ENV: &str = "ICED_FM_LOG_FILE";
+
+const DEFAULT_FILTER: &str = "warn,filepicker=info";
+
+pub fn init() {
+    let directives = std::env::var(FILTER_ENV)
+        .or_else(|_| std::env::var(EnvFilter::DEFAULT_ENV))
+        .unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+
+    let filter =
+        || EnvFilter::try_new(&directives).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(filter());
+
+    let file = std::env::var(FILE_ENV).ok().and_then(|path| {
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .with_filter(filter()),
+            ),
+            Err(error) => {
+                eprintln!("cannot open log file {}: {}", path, error);
+                None
+            }
+        }
+    });
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .init();
+}
@@ -0,0 +1,184 @@
+//! Signing in to a remote machine before its first folder is listed. Keys
+//! and the SSH agent are tried first; a password is only asked for if the
+//! server turns them down.
+
+use std::any::TypeId;
+use std::io;
+use std::path::PathBuf;
+
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::i18n::{tr, trf};
+use crate::remote::{self, Location};
+use crate::style::Style;
+
+#[derive(Debug, Clone)]
+pub enum Attempt {
+    Connected,
+    Denied,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Password(String),
+    Submit,
+    Attempted(usize, Attempt),
+    Cancel,
+}
+
+pub enum Event {
+    /// Signed in; the folder it was opened for can be listed.
+    Connected(String, PathBuf),
+    Cancelled,
+}
+
+enum State {
+    /// With the password being tried, if any.
+    Connecting(Option<String>),
+    /// With why the last one did not do.
+    Password(Option<String>),
+    Failed(String),
+}
+
+pub struct Login {
+    location: Location,
+    /// The folder to go to once signed in.
+    target: PathBuf,
+    password: String,
+    /// Counts attempts so a new one ignores what an earlier one sends.
+    attempt: usize,
+    state: State,
+}
+
+impl Login {
+    pub fn open(location: Location, target: PathBuf) -> Login {
+        Login {
+            location,
+            target,
+            password: String::new(),
+            attempt: 0,
+            state: State::Connecting(None),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Password(password) => self.password = password,
+            Message::Submit => match self.state {
+                State::Password(_) if !self.password.is_empty() => {
+                    self.attempt += 1;
+                    self.state = State::Connecting(Some(std::mem::take(&mut self.password)));
+                }
+                // Tried again from the start, the server perhaps being up by
+                // now.
+                State::Failed(_) => {
+                    self.attempt += 1;
+                    self.state = State::Connecting(None);
+                }
+                _ => {}
+            },
+            Message::Attempted(attempt, _) if attempt != self.attempt => {}
+            Message::Attempted(_, Attempt::Connected) => {
+                return Some(Event::Connected(self.location.host(), self.target.clone()))
+            }
+            Message::Attempted(_, Attempt::Denied) => {
+                let tried = matches!(self.state, State::Connecting(Some(_)));
+                self.state =
+                    State::Password(tried.then(|| String::from("That password was not accepted.")));
+            }
+            Message::Attempted(_, Attempt::Failed(error)) => self.state = State::Failed(error),
+            Message::Cancel => return Some(Event::Cancelled),
+        }
+
+        None
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Connecting;
+
+        let State::Connecting(password) = &self.state else {
+            return Subscription::none();
+        };
+        let (attempt, location, password) = (self.attempt, self.location.clone(), password.clone());
+        subscription::channel(
+            (TypeId::of::<Connecting>(), attempt),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let outcome = match remote::connect(&location, password.as_deref()) {
+                        Ok(()) => Attempt::Connected,
+                        Err(error) if error.kind() == io::ErrorKind::PermissionDenied => {
+                            Attempt::Denied
+                        }
+                        Err(error) => Attempt::Failed(error.to_string()),
+                    };
+                    sender.send(outcome)
+                });
+                if let Ok(outcome) = receiver.await {
+                    let _ = output.send(Message::Attempted(attempt, outcome)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let ready = match self.state {
+            State::Connecting(_) => false,
+            State::Password(_) => !self.password.is_empty(),
+            State::Failed(_) => true,
+        };
+        let body: Element<Message> = match &self.state {
+            State::Connecting(_) => text(tr("Connecting…")).into(),
+            State::Password(error) => {
+                let input = text_input(tr("Password"), &self.password)
+                    .on_input(Message::Password)
+                    .on_submit(Message::Submit)
+                    .secure(true)
+                    .padding(5);
+                let mut col =
+                    column!(text(tr("The server asks for a password.")), input).spacing(5);
+                if let Some(error) = error {
+                    col = col.push(
+                        text(error)
+                            .size(style.small_text_size)
+                            .style(theme::Text::Color(style.error)),
+                    );
+                }
+                col.into()
+            }
+            State::Failed(error) => text(trf("Cannot connect: {}", &[&error]))
+                .style(theme::Text::Color(style.error))
+                .into(),
+        };
+
+        let buttons = row!(
+            text("").width(Length::Fill),
+            button(tr("Cancel")).on_press(Message::Cancel),
+            button(tr("Connect")).on_press_maybe(ready.then_some(Message::Submit)),
+        )
+        .spacing(5)
+        .align_items(Alignment::Center);
+
+        container(
+            column!(
+                text(trf("Connect to {}", &[&self.location.host()])).size(20),
+                body,
+                buttons,
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .max_width(420)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
@@ -1,7 +1,450 @@
-use iced::{Application, Settings};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{env, process};
 
-mod filepicker;
+use iced::multi_window::Application;
+use iced::widget::horizontal_space;
+use iced::{executor, window, Command, Element, Subscription, Theme};
+
+use filepicker::history::{Closed, RecentlyClosed};
+use filepicker::{dirs, file_manager, instance, logging, portal, rendering, tray};
+use filepicker::{Dialog, DialogMode, FilePicker, Outcome};
+
+const USAGE: &str = "\
+Usage: filepicker [OPTIONS] [PATH]
+
+Opens PATH, or the folder last browsed. A file is shown highlighted in its
+folder.
+
+Options:
+  --select FILE   Open the folder holding FILE with FILE highlighted
+  --pick-file     Choose a file and print its path
+  --pick-files    Choose one or more files and print their paths
+  --pick-dir      Choose a folder and print its path
+  --save          Choose where to save and print that path; a PATH that
+                  does not exist yet gives the suggested name
+  --hidden        Start in the background, with an icon in the tray, running
+                  hot-folder rules and indexing; later launches show it
+  --portal        Serve the XDG desktop portal file chooser
+  --portable      Keep configuration next to the executable
+  -h, --help      Show this help";
+
+#[derive(Debug, Default)]
+struct Args {
+    path: Option<PathBuf>,
+    select: Option<PathBuf>,
+    mode: Option<DialogMode>,
+    hidden: bool,
+    portal: bool,
+    portable: bool,
+}
+
+impl Args {
+    fn parse() -> Result<Args, String> {
+        let mut args = Args::default();
+        let mut argv = env::args_os().skip(1);
+        let absolute = |path: PathBuf| {
+            env::current_dir()
+                .map(|cwd| cwd.join(&path))
+                .unwrap_or(path)
+        };
+
+        while let Some(arg) = argv.next() {
+            match arg.to_str() {
+                Some("-h" | "--help") => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                Some("--select") => {
+                    let file = argv.next().ok_or("--select needs a file")?;
+                    args.select = Some(absolute(PathBuf::from(file)));
+                }
+                Some("--pick-file") => args.mode = Some(DialogMode::OpenFile),
+                Some("--pick-files") => args.mode = Some(DialogMode::OpenFiles),
+                Some("--pick-dir") => args.mode = Some(DialogMode::OpenDirectory),
+                Some("--save") => args.mode = Some(DialogMode::SaveFile),
+                Some("--hidden") => args.hidden = true,
+                Some("--portal") if !cfg!(target_os = "linux") => {
+                    return Err(String::from("--portal is only available on Linux"));
+                }
+                Some("--portal") => args.portal = true,
+                Some("--portable") => args.portable = true,
+                Some(flag) if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("unknown option {}", flag));
+                }
+                _ if args.path.is_none() => args.path = Some(absolute(PathBuf::from(arg))),
+                _ => return Err(String::from("only one PATH can be given")),
+            }
+        }
+
+        let may_be_new = args.mode == Some(DialogMode::SaveFile);
+        for path in args.path.iter().filter(|_| !may_be_new).chain(&args.select) {
+            if !path.exists() {
+                return Err(format!("{} does not exist", path.display()));
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// Whether this launch asks for something a running instance would not
+    /// do when merely shown.
+    fn is_specific(&self) -> bool {
+        self.path.is_some() || self.select.is_some() || self.mode.is_some()
+    }
+}
+
+struct App {
+    /// By window; the main window's picks files and serves requests, the
+    /// others browse.
+    pickers: BTreeMap<window::Id, FilePicker>,
+    /// Started with `--hidden`: closing the window only hides it, and other
+    /// launches bring it back.
+    background: bool,
+    /// Started with `--portal`: stays hidden until an app asks for a file
+    /// through xdg-desktop-portal.
+    portal: bool,
+    request: Option<portal::Request>,
+    /// Browsing rather than picking, so other apps may ask to be shown
+    /// files through FileManager1.
+    file_manager: bool,
+    /// The windows closed lately, for any to open again.
+    closed: RecentlyClosed,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Picker(window::Id, filepicker::Message),
+    Show,
+    Tray(tray::Event),
+    Portal(portal::Event),
+    FileManager(file_manager::Event),
+}
+
+impl Application for App {
+    type Executor = executor::Default;
+    type Flags = Args;
+    type Message = Message;
+    type Theme = Theme;
+
+    fn new(args: Args) -> (App, Command<Message>) {
+        let (mut picker, command) = match args.mode {
+            Some(mode) => {
+                let mut dialog = Dialog::new(mode);
+                match &args.path {
+                    Some(path) if path.is_dir() => dialog = dialog.directory(path),
+                    Some(path) if mode == DialogMode::SaveFile && !path.exists() => {
+                        if let Some(dir) = path.parent() {
+                            dialog = dialog.directory(dir);
+                        }
+                        if let Some(name) = path.file_name() {
+                            dialog = dialog.file_name(name.to_string_lossy());
+                        }
+                    }
+                    _ => {}
+                }
+                FilePicker::dialog(dialog)
+            }
+            None => {
+                let (mut picker, command) = FilePicker::new();
+                picker.offer_new_windows();
+                if args.hidden && !args.portal {
+                    picker.run_in_background();
+                }
+                (picker, command)
+            }
+        };
+        let shown = match (&args.mode, &args.path) {
+            (None, Some(path)) => Some(path),
+            (Some(_), Some(path)) if path.is_file() => Some(path),
+            _ => None,
+        };
+        if let Some(path) = args.select.as_ref().or(shown) {
+            picker.open(path);
+        }
+
+        let app = App {
+            pickers: BTreeMap::from([(window::Id::MAIN, picker)]),
+            background: args.hidden || args.portal,
+            portal: args.portal,
+            request: None,
+            file_manager: !args.portal && args.mode.is_none(),
+            closed: RecentlyClosed::default(),
+        };
+        (app, command.map(main_window))
+    }
+
+    fn title(&self, id: window::Id) -> String {
+        match &self.request {
+            Some(request) if !request.title.is_empty() && id == window::Id::MAIN => {
+                request.title.clone()
+            }
+            _ => String::from("FilePicker - Iced"),
+        }
+    }
+
+    /// Chosen paths go to stdout, one per line, and end the program.
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let (id, message) = match message {
+            Message::Tray(tray::Event::Quit) => {
+                // Closing the last window ends the program, the main one
+                // through its picker like any other close.
+                self.background = false;
+                let others: Vec<window::Id> = self
+                    .pickers
+                    .keys()
+                    .copied()
+                    .filter(|&id| id != window::Id::MAIN)
+                    .collect();
+                let mut commands: Vec<_> = others
+                    .into_iter()
+                    .map(|id| {
+                        self.pickers.remove(&id);
+                        window::close(id)
+                    })
+                    .collect();
+                commands.push(self.update(main_window(filepicker::Message::CloseRequested(
+                    window::Id::MAIN,
+                ))));
+                return Command::batch(commands);
+            }
+            Message::Show | Message::Tray(tray::Event::Show) => {
+                return Command::batch([
+                    window::change_mode(window::Id::MAIN, window::Mode::Windowed),
+                    window::gain_focus(window::Id::MAIN),
+                ]);
+            }
+            Message::Portal(portal::Event::Open(request)) => {
+                if let Some(earlier) = self.request.replace(request.clone()) {
+                    earlier.respond(&Outcome::Cancelled);
+                }
+                let (picker, command) = FilePicker::dialog(request.dialog);
+                self.pickers.insert(window::Id::MAIN, picker);
+                return Command::batch([
+                    command.map(main_window),
+                    window::change_mode(window::Id::MAIN, window::Mode::Windowed),
+                    window::gain_focus(window::Id::MAIN),
+                ]);
+            }
+            Message::FileManager(event) => {
+                // Into the main window, or the first left once it is closed.
+                let Some((&id, picker)) = self.pickers.iter_mut().next() else {
+                    return Command::none();
+                };
+                let command = match event {
+                    file_manager::Event::ShowFolders(folders) => {
+                        if let Some(folder) = folders.first() {
+                            picker.open(folder);
+                        }
+                        Command::none()
+                    }
+                    file_manager::Event::ShowItems(items) => {
+                        picker.reveal(&items);
+                        Command::none()
+                    }
+                    file_manager::Event::ShowItemProperties(items) => {
+                        picker.reveal(&items[..items.len().min(1)]);
+                        picker
+                            .update(filepicker::Message::ShowProperties)
+                            .map(move |message| Message::Picker(id, message))
+                    }
+                };
+                return Command::batch([
+                    command,
+                    window::change_mode(id, window::Mode::Windowed),
+                    window::gain_focus(id),
+                ]);
+            }
+            Message::Portal(portal::Event::Closed(handle)) => {
+                if self
+                    .request
+                    .as_ref()
+                    .is_some_and(|request| request.handle == handle)
+                {
+                    self.request = None;
+                    return window::change_mode(window::Id::MAIN, window::Mode::Hidden);
+                }
+                return Command::none();
+            }
+            Message::Picker(window::Id::MAIN, filepicker::Message::CloseRequested(_))
+                if self.portal =>
+            {
+                self.main().save_session();
+                if let Some(request) = self.request.take() {
+                    request.respond(&Outcome::Cancelled);
+                }
+                return window::change_mode(window::Id::MAIN, window::Mode::Hidden);
+            }
+            Message::Picker(window::Id::MAIN, filepicker::Message::CloseRequested(_))
+                if self.background =>
+            {
+                self.main().save_session();
+                return window::change_mode(window::Id::MAIN, window::Mode::Hidden);
+            }
+            // The session is the last window's, which closes itself.
+            Message::Picker(id, filepicker::Message::CloseRequested(_))
+                if self.pickers.len() > 1 =>
+            {
+                if let Some(picker) = self.pickers.remove(&id) {
+                    self.closed.push(picker.closed());
+                    self.share_closed();
+                }
+                return window::close(id);
+            }
+            Message::Picker(id, message) => (id, message),
+        };
+
+        let Some(picker) = self.pickers.get_mut(&id) else {
+            return Command::none();
+        };
+        let command = picker
+            .update(message)
+            .map(move |message| Message::Picker(id, message));
+        let new_window = picker.take_new_window();
+        let reopen = picker.take_reopen();
+        // Only the main window picks.
+        let outcome = match id {
+            window::Id::MAIN => picker.take_outcome(),
+            _ => None,
+        };
+        if outcome.is_some() {
+            picker.save_session();
+        }
+        if let Some(dir) = new_window {
+            return Command::batch([command, self.open_window(&dir, None)]);
+        }
+        if let Some(closed) = reopen.and_then(|index| self.closed.take(index)) {
+            let dir = closed.dir.clone();
+            let opened = self.open_window(&dir, Some(closed));
+            self.share_closed();
+            return Command::batch([command, opened]);
+        }
+        if self.portal {
+            return match outcome {
+                Some(outcome) => {
+                    if let Some(request) = self.request.take() {
+                        request.respond(&outcome);
+                    }
+                    window::change_mode(window::Id::MAIN, window::Mode::Hidden)
+                }
+                None => command,
+            };
+        }
+        match outcome {
+            Some(Outcome::Chosen(chosen)) => {
+                for item in chosen {
+                    println!("{}", item.path.display());
+                }
+                window::close(window::Id::MAIN)
+            }
+            Some(Outcome::Cancelled) => window::close(window::Id::MAIN),
+            None => command,
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions: Vec<_> = self
+            .pickers
+            .iter()
+            .map(|(&id, picker)| {
+                picker
+                    .subscription()
+                    .with(id)
+                    .map(|(id, message)| Message::Picker(id, message))
+            })
+            .collect();
+        if self.portal {
+            subscriptions.push(portal::requests().map(Message::Portal));
+        } else if self.background {
+            subscriptions.push(instance::show_requests().map(|()| Message::Show));
+            subscriptions.push(tray::events().map(Message::Tray));
+        }
+        if self.file_manager {
+            subscriptions.push(file_manager::requests().map(Message::FileManager));
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    fn theme(&self, id: window::Id) -> Theme {
+        self.pickers
+            .get(&id)
+            .map_or_else(Theme::default, FilePicker::theme)
+    }
+
+    fn view(&self, id: window::Id) -> Element<'_, Message> {
+        match self.pickers.get(&id) {
+            Some(picker) => picker
+                .view()
+                .map(move |message| Message::Picker(id, message)),
+            None => horizontal_space().into(),
+        }
+    }
+}
+
+impl App {
+    /// Tells every window which were closed lately.
+    fn share_closed(&mut self) {
+        let dirs = self.closed.dirs();
+        for picker in self.pickers.values_mut() {
+            picker.set_recently_closed(dirs.clone());
+        }
+    }
+
+    fn main(&mut self) -> &mut FilePicker {
+        self.pickers
+            .get_mut(&window::Id::MAIN)
+            .expect("the main window is only ever hidden")
+    }
+
+    /// Another browser, on `dir`, in a window of its own; as it was when
+    /// `closed` is given.
+    fn open_window(&mut self, dir: &Path, closed: Option<Closed>) -> Command<Message> {
+        let (id, spawn) = window::spawn(window::Settings {
+            exit_on_close_request: false,
+            ..window::Settings::default()
+        });
+        let (mut picker, command) = FilePicker::new();
+        picker.set_window(id);
+        picker.offer_new_windows();
+        picker.open(dir);
+        if let Some(closed) = closed {
+            picker.restore(closed);
+        }
+        picker.set_recently_closed(self.closed.dirs());
+        self.pickers.insert(id, picker);
+        tracing::info!(dir = %dir.display(), ?id, "window opened");
+
+        Command::batch([
+            spawn,
+            command.map(move |message| Message::Picker(id, message)),
+        ])
+    }
+}
+
+fn main_window(message: filepicker::Message) -> Message {
+    Message::Picker(window::Id::MAIN, message)
+}
 
 fn main() -> iced::Result {
-    filepicker::FilePicker::run(Settings::default())
+    let args = Args::parse().unwrap_or_else(|error| {
+        eprintln!("filepicker: {}\n\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    logging::init();
+    dirs::init(args.portable);
+
+    if !args.portal && !args.is_specific() && instance::show_running() {
+        return Ok(());
+    }
+
+    let visible = !args.hidden && !args.portal;
+    let mut settings = rendering::settings(args);
+    settings.window.visible = visible;
+    // The picker closes the window itself, after handing off the clipboard.
+    settings.window.exit_on_close_request = false;
+
+    let result = App::run(settings);
+    instance::forget();
+    result
 }
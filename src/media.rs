@@ -0,0 +1,577 @@
+//! What photos and recordings say about themselves: the EXIF block of JPEG
+//! and TIFF-based images, the ID3 tags of MP3s and the Vorbis comments of
+//! FLAC, Ogg Vorbis and Opus files. All of it is read by hand from the
+//! first and last bytes of the file, every offset checked against them.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::images;
+use crate::mime;
+use crate::vfs::{self, Source};
+
+/// How much of the start of a file is read. Tags with cover art can be
+/// larger; what comes after the art is not found.
+const HEAD: u64 = 256 * 1024;
+/// How much of its end: enough for an ID3v1 tag or the last Ogg page.
+const TAIL: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Media {
+    pub camera: Option<String>,
+    pub resolution: Option<(u32, u32)>,
+    /// As the camera wrote it, which says nothing of the time zone.
+    pub taken: Option<String>,
+    /// Latitude and longitude in degrees, north and east positive.
+    pub location: Option<(f64, f64)>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl Media {
+    /// A label and a value for each thing known, in the order shown.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        let mut push = |label, value: Option<String>| {
+            if let Some(value) = value {
+                fields.push((label, value));
+            }
+        };
+        push("Camera", self.camera.clone());
+        push(
+            "Resolution",
+            self.resolution
+                .map(|(width, height)| format!("{} × {}", width, height)),
+        );
+        push("Taken", self.taken.clone());
+        push(
+            "GPS",
+            self.location
+                .map(|(latitude, longitude)| format!("{:.5}, {:.5}", latitude, longitude)),
+        );
+        push("Title", self.title.clone());
+        push("Artist", self.artist.clone());
+        push("Album", self.album.clone());
+        push("Duration", self.duration.map(format_duration));
+        fields
+    }
+
+    /// Takes what `other` knows and this does not.
+    fn fill(&mut self, other: Media) {
+        self.camera = self.camera.take().or(other.camera);
+        self.resolution = self.resolution.or(other.resolution);
+        self.taken = self.taken.take().or(other.taken);
+        self.location = self.location.or(other.location);
+        self.title = self.title.take().or(other.title);
+        self.artist = self.artist.take().or(other.artist);
+        self.album = self.album.take().or(other.album);
+        self.duration = self.duration.or(other.duration);
+    }
+}
+
+/// "4:05", or "1:02:03" past the hour.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
+
+/// What the image or recording at `path` says of itself, `None` when it
+/// is neither or says nothing. Reads from the disk, so best off the UI
+/// thread.
+pub fn read(path: &Path) -> Option<Media> {
+    let mut file = vfs::for_path(path).open_read(path).ok()?;
+    let size = file.seek(SeekFrom::End(0)).ok()?;
+    file.seek(SeekFrom::Start(0)).ok()?;
+    let mut head = Vec::new();
+    file.by_ref().take(HEAD).read_to_end(&mut head).ok()?;
+
+    let media = if head.starts_with(b"\xff\xd8") {
+        let mut media = jpeg_exif(&head).and_then(exif).unwrap_or_default();
+        if media.resolution.is_none() {
+            media.resolution = images::dimensions(&head, &mut file);
+        }
+        media
+    } else if head.starts_with(b"II*\0") || head.starts_with(b"MM\0*") {
+        exif(&head).unwrap_or_default()
+    } else if head.starts_with(b"fLaC") {
+        flac(&head)
+    } else if head.starts_with(b"OggS") {
+        ogg(&head, &tail(&mut file, size))
+    } else if head.starts_with(b"ID3") || mime::guess(path) == "audio/mpeg" {
+        mp3(&head, &tail(&mut file, size), size)
+    } else {
+        return None;
+    };
+
+    (media != Media::default()).then_some(media)
+}
+
+/// The last `TAIL` bytes of `file`, of `size` bytes; none if they cannot be
+/// read.
+fn tail(file: &mut Box<dyn Source>, size: u64) -> Vec<u8> {
+    let mut tail = Vec::new();
+    if file
+        .seek(SeekFrom::Start(size.saturating_sub(TAIL)))
+        .is_err()
+        || file.read_to_end(&mut tail).is_err()
+    {
+        tail.clear();
+    }
+    tail
+}
+
+/// The TIFF structure in the APP1 segment of a JPEG.
+fn jpeg_exif(jpeg: &[u8]) -> Option<&[u8]> {
+    let mut position = 2;
+    loop {
+        let header = jpeg.get(position..position + 4)?;
+        // Past the start of scan is the image itself.
+        if header[0] != 0xff || header[1] == 0xda {
+            return None;
+        }
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let segment = jpeg.get(position + 4..position + 2 + length)?;
+        if header[1] == 0xe1 {
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return Some(tiff);
+            }
+        }
+        position += 2 + length;
+    }
+}
+
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+/// An entry of an image directory, `value` where its value starts.
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    value: usize,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, at: usize) -> Option<u16> {
+        let bytes = self.bytes.get(at..at + 2)?.try_into().ok()?;
+        Some(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let bytes = self.bytes.get(at..at + 4)?.try_into().ok()?;
+        Some(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    fn entries(&self, offset: usize) -> Vec<Entry> {
+        let Some(count) = self.u16(offset) else {
+            return Vec::new();
+        };
+        (0..count as usize)
+            .map_while(|index| {
+                let at = offset + 2 + index * 12;
+                let kind = self.u16(at + 2)?;
+                let count = self.u32(at + 4)? as usize;
+                let width = match kind {
+                    3 => 2,
+                    4 | 9 => 4,
+                    5 | 10 => 8,
+                    _ => 1,
+                };
+                let value = match count.checked_mul(width)? <= 4 {
+                    true => at + 8,
+                    false => self.u32(at + 8)? as usize,
+                };
+                Some(Entry {
+                    tag: self.u16(at)?,
+                    kind,
+                    count,
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    fn text(&self, entry: &Entry) -> Option<String> {
+        let bytes = self
+            .bytes
+            .get(entry.value..entry.value.checked_add(entry.count)?)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    fn number(&self, entry: &Entry) -> Option<u32> {
+        match entry.kind {
+            3 => self.u16(entry.value).map(u32::from),
+            4 => self.u32(entry.value),
+            _ => None,
+        }
+    }
+
+    fn rationals(&self, entry: &Entry) -> Option<Vec<f64>> {
+        (0..entry.count)
+            .map(|index| {
+                let at = entry.value + index * 8;
+                let (numerator, denominator) = (self.u32(at)?, self.u32(at + 4)?);
+                Some(match denominator {
+                    0 => 0.,
+                    _ => numerator as f64 / denominator as f64,
+                })
+            })
+            .collect()
+    }
+}
+
+fn exif(bytes: &[u8]) -> Option<Media> {
+    let tiff = Tiff {
+        bytes,
+        big_endian: bytes.starts_with(b"MM"),
+    };
+    let mut media = Media::default();
+    let (mut make, mut model) = (None, None);
+    let (mut exif, mut gps) = (None, None);
+    for entry in tiff.entries(tiff.u32(4)? as usize) {
+        match entry.tag {
+            0x010f => make = tiff.text(&entry),
+            0x0110 => model = tiff.text(&entry),
+            0x0132 => media.taken = tiff.text(&entry),
+            0x8769 => exif = tiff.number(&entry),
+            0x8825 => gps = tiff.number(&entry),
+            _ => {}
+        }
+    }
+    // Most models are named with their make already.
+    media.camera = match (make, model) {
+        (Some(make), Some(model)) if !model.starts_with(&make) => {
+            Some(format!("{} {}", make, model))
+        }
+        (make, model) => model.or(make),
+    };
+
+    let (mut width, mut height) = (None, None);
+    for entry in exif.map_or_else(Vec::new, |offset| tiff.entries(offset as usize)) {
+        match entry.tag {
+            0x9003 => media.taken = tiff.text(&entry).or(media.taken.take()),
+            0xa002 => width = tiff.number(&entry),
+            0xa003 => height = tiff.number(&entry),
+            _ => {}
+        }
+    }
+    media.resolution = width.zip(height);
+    // "2024:05:01 12:00:00", as EXIF writes dates.
+    media.taken = media
+        .taken
+        .map(|taken| taken.replacen(':', "-", 2))
+        .filter(|taken| !taken.starts_with("0000"));
+
+    let (mut latitude, mut longitude) = (None, None);
+    let (mut south, mut west) = (false, false);
+    for entry in gps.map_or_else(Vec::new, |offset| tiff.entries(offset as usize)) {
+        let degrees = |parts: Vec<f64>| match parts[..] {
+            [degrees, minutes, seconds] => Some(degrees + minutes / 60. + seconds / 3600.),
+            _ => None,
+        };
+        match entry.tag {
+            1 => south = tiff.text(&entry).as_deref() == Some("S"),
+            2 => latitude = tiff.rationals(&entry).and_then(degrees),
+            3 => west = tiff.text(&entry).as_deref() == Some("W"),
+            4 => longitude = tiff.rationals(&entry).and_then(degrees),
+            _ => {}
+        }
+    }
+    media.location = latitude.zip(longitude).map(|(latitude, longitude)| {
+        (
+            if south { -latitude } else { latitude },
+            if west { -longitude } else { longitude },
+        )
+    });
+
+    Some(media)
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The title, artist and album among the Vorbis comments in `bytes`, as
+/// FLAC and Ogg files carry them.
+fn vorbis_comments(bytes: &[u8]) -> Media {
+    let mut media = Media::default();
+    let Some(vendor) = u32_le(bytes, 0) else {
+        return media;
+    };
+    let mut at = 4 + vendor as usize;
+    let count = u32_le(bytes, at).unwrap_or(0);
+    at += 4;
+    for _ in 0..count {
+        let Some(length) = u32_le(bytes, at) else {
+            break;
+        };
+        let Some(comment) = bytes.get(at + 4..at + 4 + length as usize) else {
+            break;
+        };
+        at += 4 + length as usize;
+        let comment = String::from_utf8_lossy(comment);
+        let Some((key, value)) = comment.split_once('=') else {
+            continue;
+        };
+        let field = match key.to_ascii_uppercase().as_str() {
+            "TITLE" => &mut media.title,
+            "ARTIST" => &mut media.artist,
+            "ALBUM" => &mut media.album,
+            _ => continue,
+        };
+        if field.is_none() && !value.trim().is_empty() {
+            *field = Some(value.trim().to_string());
+        }
+    }
+    media
+}
+
+fn flac(bytes: &[u8]) -> Media {
+    let mut media = Media::default();
+    let mut at = 4;
+    while let Some(header) = bytes.get(at..at + 4) {
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let Some(block) = bytes.get(at + 4..at + 4 + length) else {
+            break;
+        };
+        match header[0] & 0x7f {
+            0 if block.len() >= 18 => {
+                let rate =
+                    (block[10] as u64) << 12 | (block[11] as u64) << 4 | (block[12] >> 4) as u64;
+                let samples = ((block[13] & 0x0f) as u64) << 32
+                    | u32::from_be_bytes([block[14], block[15], block[16], block[17]]) as u64;
+                if rate > 0 && samples > 0 {
+                    media.duration = Some(Duration::from_secs_f64(samples as f64 / rate as f64));
+                }
+            }
+            4 => media.fill(vorbis_comments(block)),
+            _ => {}
+        }
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+        at += 4 + length;
+    }
+    media
+}
+
+/// Vorbis or Opus in Ogg. A comment packet running over into the next page
+/// has that page's header amid it, which only garbles a comment or two.
+fn ogg(head: &[u8], tail: &[u8]) -> Media {
+    let find = |bytes: &[u8], what: &[u8]| bytes.windows(what.len()).position(|at| at == what);
+
+    let (rate, skip, comments) = if let Some(at) = find(head, b"\x01vorbis") {
+        let comments = find(head, b"\x03vorbis").map(|at| at + 7);
+        (u32_le(head, at + 12).unwrap_or(0), 0, comments)
+    } else if let Some(at) = find(head, b"OpusHead") {
+        let skip = head
+            .get(at + 10..at + 12)
+            .map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+        let comments = find(head, b"OpusTags").map(|at| at + 8);
+        // Opus always counts at 48 kHz, whatever it was recorded at.
+        (48_000, skip as u64, comments)
+    } else {
+        return Media::default();
+    };
+
+    let mut media = comments.map_or_else(Media::default, |at| vorbis_comments(&head[at..]));
+    let last = tail.windows(4).rposition(|at| at == b"OggS");
+    let granule = last
+        .and_then(|at| tail.get(at + 6..at + 14))
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()));
+    if let Some(samples) = granule.filter(|&samples| rate > 0 && samples > skip) {
+        media.duration = Some(Duration::from_secs_f64(
+            (samples - skip) as f64 / rate as f64,
+        ));
+    }
+    media
+}
+
+fn mp3(head: &[u8], tail: &[u8], size: u64) -> Media {
+    let (mut media, start) = id3v2(head).unwrap_or_default();
+    let v1 = tail.len() >= 128 && tail[tail.len() - 128..].starts_with(b"TAG");
+    if v1 {
+        media.fill(id3v1(&tail[tail.len() - 128..]));
+    }
+    if media.duration.is_none() {
+        let audio = size.saturating_sub(start as u64 + if v1 { 128 } else { 0 });
+        media.duration = mpeg_duration(head.get(start..).unwrap_or_default(), audio);
+    }
+    media
+}
+
+/// The ID3v2 tag at the start of `bytes` and how long it is.
+fn id3v2(bytes: &[u8]) -> Option<(Media, usize)> {
+    let header = bytes
+        .get(..10)
+        .filter(|header| header.starts_with(b"ID3"))?;
+    let syncsafe = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(0usize, |size, byte| size << 7 | (byte & 0x7f) as usize)
+    };
+    let version = header[3];
+    let flags = header[5];
+    let mut end = 10 + syncsafe(&header[6..10]);
+    let tag = bytes.get(10..end.min(bytes.len()))?;
+    if flags & 0x10 != 0 {
+        end += 10;
+    }
+
+    let mut at = 0;
+    if flags & 0x40 != 0 {
+        let extended = tag.get(..4)?;
+        at = match version {
+            4 => syncsafe(extended),
+            _ => 4 + u32::from_be_bytes(extended.try_into().ok()?) as usize,
+        };
+    }
+
+    let mut media = Media::default();
+    let (id_length, header_length) = match version {
+        2 => (3, 6),
+        _ => (4, 10),
+    };
+    while let Some(frame) = tag.get(at..at + header_length) {
+        let id = &frame[..id_length];
+        if id[0] == 0 {
+            break;
+        }
+        let length = match version {
+            2 => u32::from_be_bytes([0, frame[3], frame[4], frame[5]]) as usize,
+            3 => u32::from_be_bytes(frame[4..8].try_into().ok()?) as usize,
+            _ => syncsafe(&frame[4..8]),
+        };
+        let Some(body) = tag.get(at + header_length..at + header_length + length) else {
+            break;
+        };
+        at += header_length + length;
+        let field = match id {
+            b"TIT2" | b"TT2" => &mut media.title,
+            b"TPE1" | b"TP1" => &mut media.artist,
+            b"TALB" | b"TAL" => &mut media.album,
+            b"TLEN" | b"TLE" => {
+                media.duration = id3_text(body)
+                    .and_then(|text| text.parse().ok())
+                    .map(Duration::from_millis);
+                continue;
+            }
+            _ => continue,
+        };
+        *field = id3_text(body);
+    }
+
+    Some((media, end))
+}
+
+/// A text frame's value; several in ID3v2.4 are joined by commas.
+fn id3_text(body: &[u8]) -> Option<String> {
+    let (&encoding, text) = body.split_first()?;
+    let utf16 = |text: &[u8], big_endian: bool| {
+        let units: Vec<u16> = text
+            .chunks_exact(2)
+            .map(|pair| match big_endian {
+                true => u16::from_be_bytes([pair[0], pair[1]]),
+                false => u16::from_le_bytes([pair[0], pair[1]]),
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    let text = match encoding {
+        1 => match text {
+            [0xfe, 0xff, rest @ ..] => utf16(rest, true),
+            [0xff, 0xfe, rest @ ..] => utf16(rest, false),
+            _ => utf16(text, false),
+        },
+        2 => utf16(text, true),
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => text.iter().map(|&byte| byte as char).collect(),
+    };
+    // A byte order mark on each of several values.
+    let values: Vec<&str> = text
+        .split('\0')
+        .map(|value| value.trim_start_matches('\u{feff}').trim())
+        .filter(|value| !value.is_empty())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// The 128-byte tag at the end of older MP3s.
+fn id3v1(tag: &[u8]) -> Media {
+    let field = |range: std::ops::Range<usize>| {
+        let text: String = tag[range].iter().map(|&byte| byte as char).collect();
+        let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        (!text.is_empty()).then(|| text.to_string())
+    };
+    Media {
+        title: field(3..33),
+        artist: field(33..63),
+        album: field(63..93),
+        ..Media::default()
+    }
+}
+
+/// How long the MPEG audio starting in `bytes` plays, `audio` bytes of it
+/// in all: from its Xing header when it has one, as files of varying
+/// bitrate do, else from the bitrate of its first frame. Layer III only.
+fn mpeg_duration(bytes: &[u8], audio: u64) -> Option<Duration> {
+    const MPEG1: [u32; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+    const MPEG2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+    let at = bytes
+        .windows(4)
+        .take(4096)
+        .position(|header| header[0] == 0xff && header[1] & 0xe0 == 0xe0)?;
+    let header = &bytes[at..at + 4];
+    let version = (header[1] >> 3) & 3;
+    let layer = (header[1] >> 1) & 3;
+    if layer != 1 || version == 1 {
+        return None;
+    }
+    let mpeg1 = version == 3;
+    let bitrate = *match mpeg1 {
+        true => MPEG1.get((header[2] >> 4) as usize)?,
+        false => MPEG2.get((header[2] >> 4) as usize)?,
+    };
+    // MPEG 2 halves the rates of MPEG 1, and 2.5 halves them again.
+    let rate =
+        [44_100_u32, 48_000, 32_000].get(((header[2] >> 2) & 3) as usize)? >> (3 - version).min(2);
+    let mono = header[3] >> 6 == 3;
+    let samples_per_frame = if mpeg1 { 1152 } else { 576 };
+
+    let side_info = match (mpeg1, mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = at + 4 + side_info;
+    if let Some(b"Xing" | b"Info") = bytes.get(xing..xing + 4) {
+        let flags = u32::from_be_bytes(bytes.get(xing + 4..xing + 8)?.try_into().ok()?);
+        if flags & 1 != 0 {
+            let frames = u32::from_be_bytes(bytes.get(xing + 8..xing + 12)?.try_into().ok()?);
+            return Some(Duration::from_secs_f64(
+                frames as f64 * samples_per_frame as f64 / rate as f64,
+            ));
+        }
+    }
+
+    (bitrate > 0).then(|| Duration::from_secs_f64(audio as f64 * 8. / (bitrate as f64 * 1000.)))
+}
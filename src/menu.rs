@@ -0,0 +1,897 @@
+//! A menu bar, as iced has none: titles in a row, each dropping its items
+//! down when clicked, or opened from the keyboard and walked with the
+//! arrows. Items may show the keys bound to them, which the host turns back
+//! into the item's message with `find`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{self, Widget};
+use iced::advanced::{overlay, renderer, Clipboard, Shell};
+use iced::keyboard::{self, key::Named, Modifiers};
+use iced::widget::{button, column, container, horizontal_rule, row, text};
+use iced::{event, mouse, theme};
+use iced::{Element, Event, Length, Point, Rectangle, Size, Vector};
+
+use crate::i18n::tr;
+use crate::keymap::Binding;
+use crate::style::Focused;
+
+const WIDTH: f32 = 300.;
+
+/// The keys named in bindings other than by their character, with how they
+/// are written.
+const NAMED: [(&str, Named); 27] = [
+    ("Enter", Named::Enter),
+    ("Tab", Named::Tab),
+    ("Space", Named::Space),
+    ("Backspace", Named::Backspace),
+    ("Delete", Named::Delete),
+    ("Insert", Named::Insert),
+    ("Escape", Named::Escape),
+    ("Home", Named::Home),
+    ("End", Named::End),
+    ("PageUp", Named::PageUp),
+    ("PageDown", Named::PageDown),
+    ("Up", Named::ArrowUp),
+    ("Down", Named::ArrowDown),
+    ("Left", Named::ArrowLeft),
+    ("Right", Named::ArrowRight),
+    ("F1", Named::F1),
+    ("F2", Named::F2),
+    ("F3", Named::F3),
+    ("F4", Named::F4),
+    ("F5", Named::F5),
+    ("F6", Named::F6),
+    ("F7", Named::F7),
+    ("F8", Named::F8),
+    ("F9", Named::F9),
+    ("F10", Named::F10),
+    ("F11", Named::F11),
+    ("F12", Named::F12),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Named(Named),
+}
+
+impl From<char> for Key {
+    fn from(c: char) -> Key {
+        Key::Char(c.to_ascii_lowercase())
+    }
+}
+
+impl From<Named> for Key {
+    fn from(named: Named) -> Key {
+        Key::Named(named)
+    }
+}
+
+/// A key and the modifiers held with it. Ctrl stands for Command on macOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shortcut {
+    key: Key,
+    modifiers: Modifiers,
+}
+
+impl Shortcut {
+    pub fn new(key: impl Into<Key>) -> Shortcut {
+        Shortcut {
+            key: key.into(),
+            modifiers: Modifiers::empty(),
+        }
+    }
+
+    pub fn ctrl(key: impl Into<Key>) -> Shortcut {
+        Shortcut::new(key).with(Modifiers::COMMAND)
+    }
+
+    pub fn alt(key: impl Into<Key>) -> Shortcut {
+        Shortcut::new(key).with(Modifiers::ALT)
+    }
+
+    pub fn shift(self) -> Shortcut {
+        self.with(Modifiers::SHIFT)
+    }
+
+    fn with(self, modifiers: Modifiers) -> Shortcut {
+        Shortcut {
+            modifiers: self.modifiers | modifiers,
+            ..self
+        }
+    }
+
+    /// The one pressed, unless it is a key that types or moves around on
+    /// its own and so is no accelerator.
+    pub fn pressed(key: &keyboard::Key, modifiers: Modifiers) -> Option<Shortcut> {
+        let key = match key {
+            keyboard::Key::Character(c) => Key::from(c.chars().next()?),
+            keyboard::Key::Named(named) => Key::Named(*named),
+            keyboard::Key::Unidentified => return None,
+        };
+        let shortcut = Shortcut {
+            key,
+            modifiers: modifiers & (Modifiers::COMMAND | Modifiers::ALT | Modifiers::SHIFT),
+        };
+
+        shortcut.is_accelerator().then_some(shortcut)
+    }
+
+    /// What typing `c` over the listing comes to, with Shift only for
+    /// capitals, whatever it took to type the others.
+    pub fn typed(c: char) -> Shortcut {
+        match c.is_uppercase() {
+            true => Shortcut::new(c).shift(),
+            false => Shortcut::new(c),
+        }
+    }
+
+    fn is_accelerator(&self) -> bool {
+        let alone = matches!(
+            self.key,
+            Key::Named(
+                Named::Delete
+                    | Named::F1
+                    | Named::F2
+                    | Named::F3
+                    | Named::F4
+                    | Named::F5
+                    | Named::F6
+                    | Named::F7
+                    | Named::F8
+                    | Named::F9
+                    | Named::F10
+                    | Named::F11
+                    | Named::F12
+            )
+        );
+        alone || self.modifiers.command() || self.modifiers.alt()
+    }
+
+    /// Whether the shortcut is ever seen: as an accelerator, or as a
+    /// character typed.
+    pub fn can_press(&self) -> bool {
+        let typed = match self.key {
+            Key::Char(c) => {
+                self.modifiers.is_empty()
+                    || (self.modifiers == Modifiers::SHIFT && c.is_alphabetic())
+            }
+            Key::Named(_) => false,
+        };
+        typed || self.is_accelerator()
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = String;
+
+    /// Reads what `Display` writes, such as `Ctrl+Shift+N` or `F2`, in any
+    /// case.
+    fn from_str(text: &str) -> Result<Shortcut, String> {
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (Some(modifiers), "+"),
+            None => match text.rsplit_once('+') {
+                Some((modifiers, key)) => (Some(modifiers), key),
+                None => (None, text),
+            },
+        };
+        let named = NAMED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, named)| Key::Named(*named));
+        let mut chars = key.chars();
+        let key = match (named, chars.next(), chars.next()) {
+            (Some(named), _, _) => named,
+            (None, Some(c), None) => Key::from(c),
+            _ => return Err(format!("unknown key {:?}", key)),
+        };
+
+        let mut shortcut = Shortcut::new(key);
+        for modifier in modifiers
+            .into_iter()
+            .flat_map(|modifiers| modifiers.split('+'))
+        {
+            shortcut = shortcut.with(match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" => Modifiers::COMMAND,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {:?}", modifier)),
+            });
+        }
+
+        Ok(shortcut)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.command() {
+            f.write_str(if cfg!(target_os = "macos") {
+                "Cmd+"
+            } else {
+                "Ctrl+"
+            })?;
+        }
+        if self.modifiers.alt() {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.shift() {
+            f.write_str("Shift+")?;
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            Key::Named(named) => match NAMED.iter().find(|(_, known)| *known == named) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{:?}", named),
+            },
+        }
+    }
+}
+
+enum Item<M> {
+    Entry {
+        label: String,
+        shortcut: Option<Binding>,
+        checked: Option<bool>,
+        on_press: Option<M>,
+    },
+    /// Items of their own, shown below it once it is picked.
+    Submenu {
+        label: String,
+        items: Vec<Item<M>>,
+    },
+    Separator,
+}
+
+impl<M> Item<M> {
+    /// This item, or what it holds for a submenu.
+    fn flatten(&self) -> Box<dyn Iterator<Item = &Item<M>> + '_> {
+        match self {
+            Item::Submenu { items, .. } => Box::new(items.iter().flat_map(Item::flatten)),
+            item => Box::new(std::iter::once(item)),
+        }
+    }
+}
+
+/// An item as shown in the open menu, or a separator when `None`.
+struct Row<M> {
+    label: String,
+    shortcut: Option<Binding>,
+    mark: &'static str,
+    /// Within an expanded submenu.
+    nested: bool,
+    on_press: Option<Message<M>>,
+}
+
+pub struct Menu<M> {
+    title: String,
+    items: Vec<Item<M>>,
+}
+
+impl<M: Clone> Menu<M> {
+    pub fn new(title: impl Into<String>) -> Menu<M> {
+        Menu {
+            title: title.into(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Greyed out, and left alone by its shortcut, without `on_press`.
+    pub fn item(
+        mut self,
+        label: impl Into<String>,
+        shortcut: Option<Binding>,
+        on_press: Option<M>,
+    ) -> Menu<M> {
+        self.items.push(Item::Entry {
+            label: tr(&label.into()).to_string(),
+            shortcut,
+            checked: None,
+            on_press,
+        });
+        self
+    }
+
+    /// Ticked when `checked`; `on_toggle` is given the state to switch to.
+    pub fn check(
+        mut self,
+        label: impl Into<String>,
+        checked: bool,
+        on_toggle: impl FnOnce(bool) -> M,
+    ) -> Menu<M> {
+        self.items.push(Item::Entry {
+            label: tr(&label.into()).to_string(),
+            shortcut: None,
+            checked: Some(checked),
+            on_press: Some(on_toggle(!checked)),
+        });
+        self
+    }
+
+    pub fn separator(mut self) -> Menu<M> {
+        self.items.push(Item::Separator);
+        self
+    }
+
+    /// The items of `menu` behind one titled as it is, greyed out when
+    /// none of them can run.
+    pub fn submenu(mut self, menu: Menu<M>) -> Menu<M> {
+        self.items.push(Item::Submenu {
+            label: menu.title,
+            items: menu.items,
+        });
+        self
+    }
+
+    /// The rows shown, the items of the submenu at `expanded` below it.
+    fn rows(self, expanded: Option<usize>) -> Vec<Option<Row<M>>> {
+        let entry = |item, nested| match item {
+            Item::Entry {
+                label,
+                shortcut,
+                checked,
+                on_press,
+            } => Some(Row {
+                label,
+                shortcut,
+                mark: if checked == Some(true) { "✓" } else { "" },
+                nested,
+                on_press: on_press.map(|message| Message::Select(Box::new(message))),
+            }),
+            _ => None,
+        };
+        let mut rows = Vec::new();
+        for (index, item) in self.items.into_iter().enumerate() {
+            match item {
+                Item::Submenu { label, items } => {
+                    let open = expanded == Some(index);
+                    let enabled = items.iter().flat_map(Item::flatten).any(|item| {
+                        matches!(
+                            item,
+                            Item::Entry {
+                                on_press: Some(_),
+                                ..
+                            }
+                        )
+                    });
+                    rows.push(Some(Row {
+                        label,
+                        shortcut: None,
+                        mark: if open { "▾" } else { "▸" },
+                        nested: false,
+                        on_press: enabled.then(|| Message::Expand((!open).then_some(index))),
+                    }));
+                    if open {
+                        rows.extend(
+                            items
+                                .into_iter()
+                                .filter_map(|item| entry(item, true))
+                                .map(Some),
+                        );
+                    }
+                }
+                Item::Separator => rows.push(None),
+                item => rows.push(entry(item, false)),
+            }
+        }
+        rows
+    }
+}
+
+/// The rows that can be picked, in order.
+fn enabled<M>(rows: &[Option<Row<M>>]) -> Vec<usize> {
+    (0..rows.len())
+        .filter(|index| {
+            rows[*index]
+                .as_ref()
+                .is_some_and(|row| row.on_press.is_some())
+        })
+        .collect()
+}
+
+fn view<'a, M: Clone + 'a>(
+    rows: Vec<Option<Row<M>>>,
+    highlighted: Option<usize>,
+) -> Element<'a, Message<M>> {
+    let mut col = column!();
+    for (index, shown) in rows.into_iter().enumerate() {
+        col = match shown {
+            Some(Row {
+                label,
+                shortcut,
+                mark,
+                nested,
+                on_press,
+            }) => {
+                let shortcut = shortcut.map(|shortcut| shortcut.to_string());
+                col.push(
+                    button(row!(
+                        text(mark).width(if nested { 40 } else { 20 }),
+                        text(label).width(Length::Fill),
+                        text(shortcut.unwrap_or_default()).size(14),
+                    ))
+                    .width(Length::Fill)
+                    .padding([4, 10])
+                    .style(match highlighted == Some(index) {
+                        true => theme::Button::custom(Focused(theme::Button::Text)),
+                        false => theme::Button::Text,
+                    })
+                    .on_press_maybe(on_press),
+                )
+            }
+            None => col.push(horizontal_rule(9)),
+        };
+    }
+
+    container(col)
+        .width(WIDTH)
+        .padding(5)
+        .style(theme::Container::Box)
+        .into()
+}
+
+/// The message of the enabled item bound to `binding`.
+pub fn find<M: Clone>(menus: &[Menu<M>], binding: &Binding) -> Option<M> {
+    menus
+        .iter()
+        .flat_map(|menu| &menu.items)
+        .flat_map(Item::flatten)
+        .find_map(|item| match item {
+            Item::Entry {
+                shortcut: Some(known),
+                on_press,
+                ..
+            } if known == binding => on_press.clone(),
+            _ => None,
+        })
+}
+
+/// An enabled item, as listed by [`entries`].
+pub struct Entry<'a, M> {
+    pub menu: &'a str,
+    pub label: &'a str,
+    pub shortcut: Option<&'a Binding>,
+    pub checked: Option<bool>,
+    pub on_press: &'a M,
+}
+
+/// Every enabled item, in menu order.
+pub fn entries<M>(menus: &[Menu<M>]) -> impl Iterator<Item = Entry<'_, M>> {
+    menus.iter().flat_map(|menu| {
+        let items = menu.items.iter().flat_map(Item::flatten);
+        items.filter_map(move |item| match item {
+            Item::Entry {
+                label,
+                shortcut,
+                checked,
+                on_press: Some(on_press),
+            } => Some(Entry {
+                menu: &menu.title,
+                label,
+                shortcut: shortcut.as_ref(),
+                checked: *checked,
+                on_press,
+            }),
+            _ => None,
+        })
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum Message<M> {
+    Open(Option<usize>),
+    /// The keyboard moved to the item at this index of the open menu.
+    Highlight(usize),
+    /// A submenu of the open menu was opened or closed, by its index.
+    Expand(Option<usize>),
+    /// An item was picked; its message is handed back by `update`.
+    Select(Box<M>),
+}
+
+/// Which menu is dropped down, if any, and the item the keyboard is on.
+#[derive(Default)]
+pub struct MenuBar {
+    open: Option<usize>,
+    highlighted: Option<usize>,
+    expanded: Option<usize>,
+}
+
+impl MenuBar {
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    pub fn update<M>(&mut self, message: Message<M>) -> Option<M> {
+        match message {
+            Message::Open(open) => {
+                self.open = open;
+                self.highlighted = None;
+                self.expanded = None;
+                None
+            }
+            Message::Highlight(index) => {
+                self.highlighted = Some(index);
+                None
+            }
+            Message::Expand(expanded) => {
+                self.expanded = expanded;
+                None
+            }
+            Message::Select(message) => {
+                self.open = None;
+                self.highlighted = None;
+                self.expanded = None;
+                Some(*message)
+            }
+        }
+    }
+
+    pub fn view<'a, M: Clone + 'a>(&self, menus: Vec<Menu<M>>) -> Element<'a, Message<M>> {
+        let count = menus.len();
+        let mut titles = row!();
+        let mut list = None;
+        let mut keys = Vec::new();
+        for (index, menu) in menus.into_iter().enumerate() {
+            let open = self.open == Some(index);
+            titles = titles.push(
+                button(text(&menu.title))
+                    .padding([4, 10])
+                    .style(if open {
+                        theme::Button::Secondary
+                    } else {
+                        theme::Button::Text
+                    })
+                    .on_press(Message::Open((!open).then_some(index))),
+            );
+            if open {
+                let rows = menu.rows(self.expanded);
+                keys = self.keys(&rows, index, count);
+                list = Some(view(rows, self.highlighted));
+            }
+        }
+
+        Element::new(Bar {
+            titles: titles.into(),
+            list,
+            open: self.open.unwrap_or_default(),
+            keys,
+        })
+    }
+
+    /// What each key does while the menu of `rows`, the one at `index` of
+    /// `count`, is open.
+    fn keys<M: Clone>(
+        &self,
+        rows: &[Option<Row<M>>],
+        index: usize,
+        count: usize,
+    ) -> Vec<(Named, Message<M>)> {
+        let mut keys = vec![
+            (Named::ArrowRight, Message::Open(Some((index + 1) % count))),
+            (
+                Named::ArrowLeft,
+                Message::Open(Some((index + count - 1) % count)),
+            ),
+        ];
+        let enabled = enabled(rows);
+        let (Some(&first), Some(&last)) = (enabled.first(), enabled.last()) else {
+            return keys;
+        };
+        let next = self
+            .highlighted
+            .and_then(|current| enabled.iter().find(|item| **item > current))
+            .map_or(first, |item| *item);
+        let previous = self
+            .highlighted
+            .and_then(|current| enabled.iter().rev().find(|item| **item < current))
+            .map_or(last, |item| *item);
+        keys.extend([
+            (Named::ArrowDown, Message::Highlight(next)),
+            (Named::ArrowUp, Message::Highlight(previous)),
+            (Named::Home, Message::Highlight(first)),
+            (Named::End, Message::Highlight(last)),
+        ]);
+
+        let picked = self
+            .highlighted
+            .and_then(|row| rows.get(row)?.as_ref()?.on_press.clone());
+        if let Some(message) = picked {
+            keys.push((Named::Enter, message.clone()));
+            keys.push((Named::Space, message));
+        }
+        keys
+    }
+}
+
+/// The row of titles, with the open menu's items as an overlay below its
+/// title.
+struct Bar<'a, M, Theme, Renderer> {
+    titles: Element<'a, Message<M>, Theme, Renderer>,
+    list: Option<Element<'a, Message<M>, Theme, Renderer>>,
+    open: usize,
+    keys: Vec<(Named, Message<M>)>,
+}
+
+impl<'a, M, Theme, Renderer> Widget<Message<M>, Theme, Renderer> for Bar<'a, M, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+    M: Clone,
+{
+    fn children(&self) -> Vec<widget::Tree> {
+        std::iter::once(&self.titles)
+            .chain(&self.list)
+            .map(widget::Tree::new)
+            .collect()
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        let children: Vec<_> = std::iter::once(&self.titles).chain(&self.list).collect();
+        tree.diff_children(&children);
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.titles.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut widget::Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.titles
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        state: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message<M>>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.titles.as_widget_mut().on_event(
+            &mut state.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn draw(
+        &self,
+        state: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.titles.as_widget().draw(
+            &state.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        state: &'b mut widget::Tree,
+        layout: Layout<'_>,
+        _renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message<M>, Theme, Renderer>> {
+        let list = self.list.as_mut()?;
+        let titles: Vec<Rectangle> = layout
+            .children()
+            .map(|title| title.bounds() + translation)
+            .collect();
+        let anchor = *titles.get(self.open)?;
+
+        Some(overlay::Element::new(Box::new(Dropdown {
+            position: Point::new(anchor.x, anchor.y + anchor.height),
+            titles,
+            open: self.open,
+            keys: &self.keys,
+            content: list,
+            tree: &mut state.children[1],
+        })))
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.titles.as_widget().mouse_interaction(
+            &state.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn operate(
+        &self,
+        state: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message<M>>,
+    ) {
+        self.titles
+            .as_widget()
+            .operate(&mut state.children[0], layout, renderer, operation);
+    }
+}
+
+struct Dropdown<'a, 'b, M, Theme, Renderer> {
+    position: Point,
+    /// Where each title is, to switch menus by moving over them.
+    titles: Vec<Rectangle>,
+    open: usize,
+    keys: &'b [(Named, Message<M>)],
+    content: &'b mut Element<'a, Message<M>, Theme, Renderer>,
+    tree: &'b mut widget::Tree,
+}
+
+impl<'a, 'b, M, Theme, Renderer> overlay::Overlay<Message<M>, Theme, Renderer>
+    for Dropdown<'a, 'b, M, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+    M: Clone,
+{
+    fn layout(&mut self, renderer: &Renderer, bounds: Size) -> layout::Node {
+        let limits = layout::Limits::new(
+            Size::ZERO,
+            Size::new(bounds.width, (bounds.height - self.position.y).max(0.)),
+        );
+        let node = self
+            .content
+            .as_widget()
+            .layout(self.tree, renderer, &limits);
+
+        // Kept inside the window when the title is near its right edge.
+        let x = self
+            .position
+            .x
+            .min(bounds.width - node.size().width)
+            .max(0.);
+        node.move_to(Point::new(x, self.position.y))
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message<M>>,
+    ) -> event::Status {
+        match &event {
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                let hovered = self
+                    .titles
+                    .iter()
+                    .position(|title| title.contains(*position));
+                if let Some(hovered) = hovered.filter(|hovered| *hovered != self.open) {
+                    shell.publish(Message::Open(Some(hovered)));
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(_)) if !cursor.is_over(layout.bounds()) => {
+                // The titles open and close menus themselves; any other click
+                // only closes this one.
+                if self.titles.iter().any(|title| cursor.is_over(*title)) {
+                    return event::Status::Ignored;
+                }
+                shell.publish(Message::Open(None));
+                return event::Status::Captured;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(Named::Escape),
+                ..
+            }) => {
+                shell.publish(Message::Open(None));
+                return event::Status::Captured;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(named),
+                ..
+            }) => {
+                if let Some((_, message)) = self.keys.iter().find(|(key, _)| key == named) {
+                    shell.publish(message.clone());
+                    return event::Status::Captured;
+                }
+                // Does nothing here, rather than in the listing underneath.
+                if matches!(
+                    named,
+                    Named::Enter
+                        | Named::Space
+                        | Named::Home
+                        | Named::End
+                        | Named::PageUp
+                        | Named::PageDown
+                        | Named::ArrowUp
+                        | Named::ArrowDown
+                ) {
+                    return event::Status::Captured;
+                }
+            }
+            _ => {}
+        }
+
+        self.content.as_widget_mut().on_event(
+            self.tree,
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            &layout.bounds(),
+        )
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        self.content.as_widget().draw(
+            self.tree,
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            &layout.bounds(),
+        );
+    }
+
+    fn operate(
+        &mut self,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message<M>>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(self.tree, layout, renderer, operation);
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content
+            .as_widget()
+            .mouse_interaction(self.tree, layout, cursor, viewport, renderer)
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// Best-effort MIME type: the extension decides when it is a known one,
+/// otherwise the first bytes of the file are sniffed. Paths that do not
+/// exist yet go by their extension alone.
+pub fn guess(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let by_extension = extension.as_deref().and_then(from_extension);
+
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => "inode/directory",
+        Ok(_) => by_extension.unwrap_or_else(|| sniff(path)),
+        Err(_) if fs::symlink_metadata(path).is_ok() => "inode/symlink",
+        Err(_) => by_extension.unwrap_or("application/octet-stream"),
+    }
+}
+
+pub fn from_extension(extension: &str) -> Option<&'static str> {
+    let mime = match extension {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "rs" => "text/rust",
+        "toml" => "application/toml",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "yml" | "yaml" => "application/yaml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "csv" => "text/csv",
+        "sh" => "application/x-shellscript",
+        "py" => "text/x-python",
+        "c" | "h" => "text/x-c",
+        "cpp" | "hpp" | "cc" => "text/x-c++",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/vnd.microsoft.icon",
+        "cr2" => "image/x-canon-cr2",
+        "nef" => "image/x-nikon-nef",
+        "arw" => "image/x-sony-arw",
+        "mp3" => "audio/mpeg",
+        "ogg" | "oga" => "audio/ogg",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "gz" => "application/gzip",
+        "xz" => "application/x-xz",
+        "zst" => "application/zstd",
+        "7z" => "application/x-7z-compressed",
+        "deb" => "application/vnd.debian.binary-package",
+        "rpm" => "application/x-rpm",
+        "iso" => "application/x-iso9660-image",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => return None,
+    };
+
+    Some(mime)
+}
+
+fn sniff(path: &Path) -> &'static str {
+    let mut head = [0u8; 512];
+    let Ok(len) = File::open(path).and_then(|mut file| file.read(&mut head)) else {
+        return "application/octet-stream";
+    };
+    let head = &head[..len];
+
+    const MAGIC: [(&[u8], &str); 7] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x7fELF", "application/x-executable"),
+    ];
+
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+
+    if len == 0 {
+        return "application/x-zerosize";
+    }
+
+    // A multi-byte character may be cut off at the end of the sample.
+    let text = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(error) => error.error_len().is_none(),
+    };
+    if text && !head.contains(&0) {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
@@ -0,0 +1,404 @@
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{self, Widget};
+use iced::advanced::{overlay, renderer, Clipboard, Shell};
+use iced::alignment::Alignment;
+use iced::keyboard::{self, key::Named, Modifiers};
+use iced::{event, mouse};
+use iced::{Color, Element, Event, Length, Point, Rectangle, Size, Vector};
+
+type OnKey<'a, Message> = Box<dyn Fn(Named, Modifiers) -> Option<Message> + 'a>;
+
+/// Displays `modal` centered on top of `base`, dimming and blocking
+/// everything underneath it, keys included: Escape answers as clicking
+/// the backdrop does.
+pub struct Modal<'a, Message, Theme, Renderer> {
+    base: Element<'a, Message, Theme, Renderer>,
+    modal: Element<'a, Message, Theme, Renderer>,
+    on_blur: Option<Message>,
+    on_key: Option<OnKey<'a, Message>>,
+    floating: bool,
+}
+
+impl<'a, Message, Theme, Renderer> Modal<'a, Message, Theme, Renderer> {
+    pub fn new(
+        base: impl Into<Element<'a, Message, Theme, Renderer>>,
+        modal: impl Into<Element<'a, Message, Theme, Renderer>>,
+    ) -> Self {
+        Self {
+            base: base.into(),
+            modal: modal.into(),
+            on_blur: None,
+            on_key: None,
+            floating: false,
+        }
+    }
+
+    /// Puts `modal` in the bottom-right corner instead, leaving `base`
+    /// visible and usable around it, as for notifications.
+    pub fn floating(self) -> Self {
+        Self {
+            floating: true,
+            ..self
+        }
+    }
+
+    /// Message produced when the backdrop outside the modal is clicked.
+    pub fn on_blur(self, on_blur: Message) -> Self {
+        Self {
+            on_blur: Some(on_blur),
+            ..self
+        }
+    }
+
+    /// What the named keys the modal's widgets leave alone do.
+    pub fn on_key(self, on_key: impl Fn(Named, Modifiers) -> Option<Message> + 'a) -> Self {
+        Self {
+            on_key: Some(Box::new(on_key)),
+            ..self
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Modal<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+    Message: Clone,
+{
+    fn children(&self) -> Vec<widget::Tree> {
+        vec![
+            widget::Tree::new(&self.base),
+            widget::Tree::new(&self.modal),
+        ]
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        tree.diff_children(&[&self.base, &self.modal]);
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.base.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut widget::Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.base
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        state: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.base.as_widget_mut().on_event(
+            &mut state.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn draw(
+        &self,
+        state: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.base.as_widget().draw(
+            &state.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        state: &'b mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        let (base_tree, modal_tree) = state.children.split_at_mut(1);
+        let overlay = overlay::Element::new(Box::new(Overlay {
+            position: layout.position() + translation,
+            content: &mut self.modal,
+            tree: &mut modal_tree[0],
+            size: layout.bounds().size(),
+            on_blur: self.on_blur.clone().filter(|_| !self.floating),
+            on_key: self.on_key.as_deref().filter(|_| !self.floating),
+            floating: self.floating,
+        }));
+        if !self.floating {
+            return Some(overlay);
+        }
+
+        // Menus opened in `base` stay usable underneath.
+        let base =
+            self.base
+                .as_widget_mut()
+                .overlay(&mut base_tree[0], layout, renderer, translation);
+        Some(overlay::Group::with_children(base.into_iter().chain([overlay]).collect()).overlay())
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.base.as_widget().mouse_interaction(
+            &state.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn operate(
+        &self,
+        state: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message>,
+    ) {
+        self.base
+            .as_widget()
+            .operate(&mut state.children[0], layout, renderer, operation);
+    }
+}
+
+struct Overlay<'a, 'b, Message, Theme, Renderer> {
+    position: Point,
+    content: &'b mut Element<'a, Message, Theme, Renderer>,
+    tree: &'b mut widget::Tree,
+    size: Size,
+    on_blur: Option<Message>,
+    on_key: Option<&'b dyn Fn(Named, Modifiers) -> Option<Message>>,
+    floating: bool,
+}
+
+impl<'a, 'b, Message, Theme, Renderer> overlay::Overlay<Message, Theme, Renderer>
+    for Overlay<'a, 'b, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+    Message: Clone,
+{
+    fn layout(&mut self, renderer: &Renderer, _bounds: Size) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, self.size)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        let child = self
+            .content
+            .as_widget()
+            .layout(self.tree, renderer, &limits);
+        let child = if self.floating {
+            const MARGIN: f32 = 20.;
+            let size = child.size();
+            child.move_to(Point::new(
+                (self.size.width - size.width - MARGIN).max(0.),
+                (self.size.height - size.height - MARGIN).max(0.),
+            ))
+        } else {
+            child.align(Alignment::Center, Alignment::Center, limits.max())
+        };
+
+        layout::Node::with_children(self.size, vec![child]).move_to(self.position)
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let content = layout.children().next().unwrap();
+
+        if let Some(message) = self.on_blur.as_ref() {
+            if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = &event {
+                if !cursor.is_over(content.bounds()) {
+                    shell.publish(message.clone());
+                    return event::Status::Captured;
+                }
+            }
+        }
+
+        let key = match &event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(named),
+                modifiers,
+                ..
+            }) => Some((*named, *modifiers)),
+            _ => None,
+        };
+        let status = self.content.as_widget_mut().on_event(
+            self.tree,
+            event,
+            content,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            &layout.bounds(),
+        );
+        let Some((named, modifiers)) = key.filter(|_| !self.floating) else {
+            return status;
+        };
+        if status == event::Status::Captured && named != Named::Escape {
+            return status;
+        }
+
+        let message = self
+            .on_key
+            .and_then(|on_key| on_key(named, modifiers))
+            .or_else(|| self.on_blur.clone().filter(|_| named == Named::Escape));
+        if let Some(message) = message {
+            shell.publish(message);
+            return event::Status::Captured;
+        }
+        // Kept from moving around the listing underneath.
+        match named {
+            Named::Enter
+            | Named::Space
+            | Named::Home
+            | Named::End
+            | Named::PageUp
+            | Named::PageDown
+            | Named::ArrowUp
+            | Named::ArrowDown
+            | Named::ArrowLeft
+            | Named::ArrowRight => event::Status::Captured,
+            _ => status,
+        }
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        if !self.floating {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: layout.bounds(),
+                    ..renderer::Quad::default()
+                },
+                Color {
+                    a: 0.6,
+                    ..Color::BLACK
+                },
+            );
+        }
+
+        self.content.as_widget().draw(
+            self.tree,
+            renderer,
+            theme,
+            style,
+            layout.children().next().unwrap(),
+            cursor,
+            &layout.bounds(),
+        );
+    }
+
+    fn operate(
+        &mut self,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message>,
+    ) {
+        self.content.as_widget().operate(
+            self.tree,
+            layout.children().next().unwrap(),
+            renderer,
+            operation,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            self.tree,
+            layout.children().next().unwrap(),
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    /// A floating one only covers its content, so the cursor still reaches
+    /// `base` everywhere else.
+    fn is_over(&self, layout: Layout<'_>, _renderer: &Renderer, cursor_position: Point) -> bool {
+        if self.floating {
+            layout
+                .children()
+                .next()
+                .is_some_and(|content| content.bounds().contains(cursor_position))
+        } else {
+            layout.bounds().contains(cursor_position)
+        }
+    }
+
+    fn overlay<'c>(
+        &'c mut self,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'c, Message, Theme, Renderer>> {
+        self.content.as_widget_mut().overlay(
+            self.tree,
+            layout.children().next().unwrap(),
+            renderer,
+            Vector::ZERO,
+        )
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Modal<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Theme: 'a,
+    Message: 'a + Clone,
+    Renderer: 'a + iced::advanced::Renderer,
+{
+    fn from(modal: Modal<'a, Message, Theme, Renderer>) -> Self {
+        Element::new(modal)
+    }
+}
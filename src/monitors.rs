@@ -0,0 +1,235 @@
+//! The displays and how they are arranged, to open the window on the one
+//! it was closed on. iced does not tell, so they are asked of `xrandr` on
+//! X11 and of the system on Windows; elsewhere, and on Wayland where
+//! windows do not place themselves, none are known.
+
+use iced::{Point, Size};
+
+/// A display, in logical pixels like the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    pub name: String,
+    pub origin: Point,
+    pub size: Size,
+    pub primary: bool,
+}
+
+impl Monitor {
+    fn contains(&self, point: Point) -> bool {
+        (self.origin.x..self.origin.x + self.size.width).contains(&point.x)
+            && (self.origin.y..self.origin.y + self.size.height).contains(&point.y)
+    }
+
+    /// Where a window of `size` wanted at `position` goes to be wholly on
+    /// this display, shrunk if it is larger.
+    fn fit(&self, position: Point, size: Size) -> (Point, Size) {
+        let size = Size::new(
+            size.width.min(self.size.width),
+            size.height.min(self.size.height),
+        );
+        let position = Point::new(
+            position
+                .x
+                .clamp(self.origin.x, self.origin.x + self.size.width - size.width),
+            position.y.clamp(
+                self.origin.y,
+                self.origin.y + self.size.height - size.height,
+            ),
+        );
+        (position, size)
+    }
+
+    fn center(&self, size: Size) -> Point {
+        Point::new(
+            self.origin.x + (self.size.width - size.width) / 2.,
+            self.origin.y + (self.size.height - size.height) / 2.,
+        )
+    }
+}
+
+/// The display the window of `size` at `position` is mostly on, judged by
+/// its middle, and where it is from that display's top left.
+pub fn locate(monitors: &[Monitor], position: Point, size: Size) -> Option<(&Monitor, Point)> {
+    let middle = Point::new(position.x + size.width / 2., position.y + size.height / 2.);
+    let monitor = monitors.iter().find(|monitor| monitor.contains(middle))?;
+    Some((
+        monitor,
+        Point::new(position.x - monitor.origin.x, position.y - monitor.origin.y),
+    ))
+}
+
+/// Where to open a window of `size` that was last `position` from the top
+/// left of display `name`, or at `position` itself without one: there
+/// again while that display is connected, and otherwise centred on the
+/// primary one. `None` leaves it to the window manager.
+pub fn place(
+    monitors: &[Monitor],
+    name: Option<&str>,
+    position: Point,
+    size: Size,
+) -> Option<(Point, Size)> {
+    let found = match name {
+        Some(name) => monitors
+            .iter()
+            .find(|monitor| monitor.name == name)
+            .map(|monitor| {
+                let position =
+                    Point::new(monitor.origin.x + position.x, monitor.origin.y + position.y);
+                (monitor, position)
+            }),
+        None => locate(monitors, position, size).map(|(monitor, _)| (monitor, position)),
+    };
+    if let Some((monitor, position)) = found {
+        return Some(monitor.fit(position, size));
+    }
+
+    match monitors
+        .iter()
+        .find(|monitor| monitor.primary)
+        .or(monitors.first())
+    {
+        Some(primary) => {
+            let (_, size) = primary.fit(primary.origin, size);
+            Some((primary.center(size), size))
+        }
+        // Nothing to check against: trust a position not tied to a display.
+        None => name.is_none().then_some((position, size)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn monitors() -> Vec<Monitor> {
+    use std::env;
+    use std::process::{Command, Stdio};
+
+    if env::var_os("DISPLAY").is_none() {
+        return Vec::new();
+    }
+    let run = |args: &[&str]| {
+        Command::new(args[0])
+            .args(&args[1..])
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let Some(listed) = run(&["xrandr", "--listactivemonitors"]) else {
+        return Vec::new();
+    };
+
+    // As winit scales on X11: its own variable, then the Xft DPI.
+    let scale = env::var("WINIT_X11_SCALE_FACTOR")
+        .ok()
+        .and_then(|scale| scale.parse::<f32>().ok())
+        .or_else(|| {
+            run(&["xrdb", "-query"])?
+                .lines()
+                .find_map(|line| line.strip_prefix("Xft.dpi:"))
+                .and_then(|dpi| dpi.trim().parse::<f32>().ok())
+                .map(|dpi| dpi / 96.)
+        })
+        .filter(|&scale| scale > 0.)
+        .unwrap_or(1.);
+
+    listed
+        .lines()
+        .skip(1)
+        .filter_map(|line| xrandr_monitor(line, scale))
+        .collect()
+}
+
+/// A line of `xrandr --listactivemonitors`, as in
+/// ` 0: +*eDP-1 1920/344x1080/193+0+0  eDP-1`, `*` marking the primary.
+#[cfg(target_os = "linux")]
+fn xrandr_monitor(line: &str, scale: f32) -> Option<Monitor> {
+    let mut words = line.split_whitespace().skip(1);
+    let name = words.next()?.trim_start_matches('+');
+    let (primary, name) = match name.strip_prefix('*') {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+    let mut numbers = words.next()?.split(['x', '+']).map(|number| {
+        number
+            .split('/')
+            .next()
+            .and_then(|pixels| pixels.parse::<f32>().ok())
+            .map(|pixels| pixels / scale)
+    });
+    let (width, height) = (numbers.next()??, numbers.next()??);
+    let (x, y) = (numbers.next()??, numbers.next()??);
+
+    Some(Monitor {
+        name: name.to_string(),
+        origin: Point::new(x, y),
+        size: Size::new(width, height),
+        primary,
+    })
+}
+
+#[cfg(windows)]
+pub fn monitors() -> Vec<Monitor> {
+    use windows_sys::Win32::Foundation::{BOOL, LPARAM, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+    };
+    use windows_sys::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    // From `MONITORINFO`; the named constant lives behind another feature.
+    const MONITORINFOF_PRIMARY: u32 = 1;
+
+    unsafe extern "system" fn found(
+        monitor: HMONITOR,
+        _: HDC,
+        _: *mut RECT,
+        monitors: LPARAM,
+    ) -> BOOL {
+        // SAFETY: `monitors` is the vector handed to `EnumDisplayMonitors`.
+        let monitors = unsafe { &mut *(monitors as *mut Vec<Monitor>) };
+        // SAFETY: an all-zero `MONITORINFOEXW` is valid, and it is passed
+        // with its size.
+        let mut info: MONITORINFOEXW = unsafe { std::mem::zeroed() };
+        info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if unsafe { GetMonitorInfoW(monitor, &mut info as *mut _ as *mut MONITORINFO) } == 0 {
+            return 1;
+        }
+        let (mut dpi, mut _dpi) = (96, 96);
+        // SAFETY: both outputs are valid for writes.
+        unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi, &mut _dpi) };
+        let scale = dpi.max(1) as f32 / 96.;
+
+        let end = info
+            .szDevice
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(info.szDevice.len());
+        let rect = info.monitorInfo.rcMonitor;
+        monitors.push(Monitor {
+            name: String::from_utf16_lossy(&info.szDevice[..end]),
+            origin: Point::new(rect.left as f32 / scale, rect.top as f32 / scale),
+            size: Size::new(
+                (rect.right - rect.left) as f32 / scale,
+                (rect.bottom - rect.top) as f32 / scale,
+            ),
+            primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+        });
+        1
+    }
+
+    let mut monitors: Vec<Monitor> = Vec::new();
+    // SAFETY: `found` only uses the vector for the duration of the call.
+    unsafe {
+        EnumDisplayMonitors(
+            0,
+            std::ptr::null(),
+            Some(found),
+            &mut monitors as *mut Vec<Monitor> as LPARAM,
+        )
+    };
+    monitors
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn monitors() -> Vec<Monitor> {
+    Vec::new()
+}
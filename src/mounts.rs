@@ -0,0 +1,331 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+}
+
+/// Mounted filesystems as reported by `/proc/mounts`.
+pub fn mounts() -> Vec<Mount> {
+    let Ok(table) = fs::read_to_string("/proc/mounts") else {
+        return Vec::new();
+    };
+
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(Mount {
+                device: unescape(fields.next()?),
+                mount_point: PathBuf::from(unescape(fields.next()?)),
+                fs_type: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The mount that contains `path`, i.e. the one with the longest matching
+/// mount point.
+pub fn mount_for(path: &Path) -> Option<Mount> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    mounts()
+        .into_iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.as_os_str().len())
+}
+
+/// `/proc/mounts` escapes whitespace and backslashes as octal sequences.
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => unescaped.push(byte as char),
+                Err(_) => {
+                    unescaped.push(c);
+                    unescaped.push_str(&code);
+                }
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+
+    unescaped
+}
+
+/// A mounted filesystem worth showing to the user, with its space usage.
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub label: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub total: u64,
+    pub free: u64,
+    pub kind: VolumeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeKind {
+    Fixed,
+    Removable,
+    Network,
+    Optical,
+}
+
+impl std::fmt::Display for VolumeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VolumeKind::Fixed => write!(f, "Local disk"),
+            VolumeKind::Removable => write!(f, "Removable"),
+            VolumeKind::Network => write!(f, "Network"),
+            VolumeKind::Optical => write!(f, "CD/DVD"),
+        }
+    }
+}
+
+#[cfg(unix)]
+const NETWORK_FS_TYPES: [&str; 7] = ["nfs", "nfs4", "cifs", "smb3", "smbfs", "fuse.sshfs", "9p"];
+
+/// Pseudo and virtual filesystems that never hold user files.
+#[cfg(unix)]
+const HIDDEN_FS_TYPES: [&str; 22] = [
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay",
+    "proc",
+    "pstore",
+    "securityfs",
+    "squashfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+/// The volumes to offer for navigation: real filesystems from
+/// `/proc/mounts` on Linux, drive letters on Windows.
+#[cfg(unix)]
+pub fn volumes() -> Vec<Volume> {
+    let labels = labels();
+
+    mounts()
+        .into_iter()
+        .filter(|mount| !HIDDEN_FS_TYPES.contains(&mount.fs_type.as_str()))
+        .filter(|mount| !mount.mount_point.starts_with("/snap"))
+        .filter_map(|mount| {
+            let (total, free) = space(&mount.mount_point)?;
+            if total == 0 {
+                return None;
+            }
+
+            let device = fs::canonicalize(&mount.device).ok();
+            let label = device
+                .as_ref()
+                .and_then(|device| labels.iter().find(|(target, _)| target == device))
+                .map(|(_, label)| label.clone())
+                .unwrap_or_else(|| match mount.mount_point.file_name() {
+                    Some(name) => name.to_string_lossy().to_string(),
+                    None => String::from("File System"),
+                });
+            let kind = if NETWORK_FS_TYPES.contains(&mount.fs_type.as_str()) {
+                VolumeKind::Network
+            } else if mount.fs_type == "iso9660" || mount.fs_type == "udf" {
+                VolumeKind::Optical
+            } else if device.as_deref().is_some_and(is_removable)
+                || mount.mount_point.starts_with("/media")
+                || mount.mount_point.starts_with("/run/media")
+            {
+                VolumeKind::Removable
+            } else {
+                VolumeKind::Fixed
+            };
+
+            Some(Volume {
+                label,
+                mount_point: mount.mount_point,
+                fs_type: mount.fs_type,
+                total,
+                free,
+                kind,
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+pub fn volumes() -> Vec<Volume> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDrives, GetVolumeInformationW,
+    };
+
+    // From `GetDriveTypeW`; the named constants live behind another feature.
+    const DRIVE_REMOVABLE: u32 = 2;
+    const DRIVE_REMOTE: u32 = 4;
+    const DRIVE_CDROM: u32 = 5;
+
+    // SAFETY: no preconditions.
+    let drives = unsafe { GetLogicalDrives() };
+
+    (0..26u8)
+        .filter(|bit| drives & (1 << bit) != 0)
+        .map(|bit| {
+            let letter = (b'A' + bit) as char;
+            let mount_point = PathBuf::from(format!("{}:\\", letter));
+            let root: Vec<u16> = mount_point
+                .as_os_str()
+                .encode_wide()
+                .chain(std::iter::once(0))
+                .collect();
+
+            let mut name = [0u16; 261];
+            let mut fs_name = [0u16; 32];
+            let (mut free, mut total) = (0, 0);
+            // SAFETY: `root` is NUL-terminated and every output buffer is
+            // passed with its length.
+            let (kind, has_info) = unsafe {
+                let kind = GetDriveTypeW(root.as_ptr());
+                let has_info = GetVolumeInformationW(
+                    root.as_ptr(),
+                    name.as_mut_ptr(),
+                    name.len() as u32,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    fs_name.as_mut_ptr(),
+                    fs_name.len() as u32,
+                ) != 0;
+                GetDiskFreeSpaceExW(root.as_ptr(), &mut free, &mut total, std::ptr::null_mut());
+                (kind, has_info)
+            };
+
+            let text = |buffer: &[u16]| {
+                let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+                String::from_utf16_lossy(&buffer[..end])
+            };
+            let kind = match kind {
+                DRIVE_REMOVABLE => VolumeKind::Removable,
+                DRIVE_REMOTE => VolumeKind::Network,
+                DRIVE_CDROM => VolumeKind::Optical,
+                _ => VolumeKind::Fixed,
+            };
+            let label = match text(&name) {
+                label if has_info && !label.is_empty() => format!("{} ({}:)", label, letter),
+                _ => format!("{} ({}:)", kind, letter),
+            };
+
+            Volume {
+                label,
+                mount_point,
+                fs_type: if has_info {
+                    text(&fs_name)
+                } else {
+                    String::new()
+                },
+                total,
+                free,
+                kind,
+            }
+        })
+        .collect()
+}
+
+/// Resolved device nodes and their filesystem labels, from
+/// `/dev/disk/by-label` where udev escapes special characters as `\xNN`.
+#[cfg(unix)]
+fn labels() -> Vec<(PathBuf, String)> {
+    let Ok(entries) = fs::read_dir("/dev/disk/by-label") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let target = fs::canonicalize(entry.path()).ok()?;
+            Some((target, unescape_label(&entry.file_name().to_string_lossy())))
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn unescape_label(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut label = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 2..i + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], bytes.get(i + 1), hex) {
+            (b'\\', Some(b'x'), Some(byte)) => {
+                label.push(byte);
+                i += 4;
+            }
+            (byte, _, _) => {
+                label.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&label).into_owned()
+}
+
+/// Whether the kernel flags the disk behind `device` (or the device itself)
+/// as removable.
+#[cfg(unix)]
+fn is_removable(device: &Path) -> bool {
+    let Some(name) = device.file_name() else {
+        return false;
+    };
+    let Ok(sys) = fs::canonicalize(Path::new("/sys/class/block").join(name)) else {
+        return false;
+    };
+
+    let flagged =
+        |dir: &Path| fs::read_to_string(dir.join("removable")).is_ok_and(|flag| flag.trim() == "1");
+
+    flagged(&sys) || sys.parent().is_some_and(flagged)
+}
+
+/// Total and available bytes on the filesystem holding `path`.
+#[cfg(unix)]
+fn space(path: &Path) -> Option<(u64, u64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+
+    // SAFETY: `statvfs` only writes into the struct we own.
+    let stat = unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(path.as_ptr(), &mut stat) != 0 {
+            return None;
+        }
+        stat
+    };
+
+    let block = stat.f_frsize as u64;
+    Some((stat.f_blocks as u64 * block, stat.f_bavail as u64 * block))
+}
@@ -0,0 +1,173 @@
+//! "Convert line endings…": the line breaks the selected files use, and
+//! which kind to rewrite them all as. Files already using only that kind
+//! are left alone; the rest are rewritten by a job.
+
+use std::any::TypeId;
+use std::path::PathBuf;
+
+use iced::widget::{button, column, container, pick_list, row, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::i18n::{tr, trf};
+use crate::line_endings::{self, LineEnding, LineEndings};
+use crate::style::Style;
+
+/// Files named in the dialog; the rest are counted.
+const MAX_LISTED: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Detected(Vec<Option<LineEndings>>),
+    Target(LineEnding),
+    Convert,
+    Close,
+}
+
+pub enum Event {
+    Convert(Vec<PathBuf>, LineEnding),
+    Closed,
+}
+
+pub struct Newlines {
+    sources: Vec<PathBuf>,
+    /// Each source's breaks, `None` for what is not text or has none, once
+    /// read.
+    detected: Option<Vec<Option<LineEndings>>>,
+    target: LineEnding,
+}
+
+impl Newlines {
+    pub fn open(sources: Vec<PathBuf>) -> Newlines {
+        Newlines {
+            sources,
+            detected: None,
+            target: LineEnding::default(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Detected(detected) => self.detected = Some(detected),
+            Message::Target(target) => self.target = target,
+            Message::Convert => {
+                let sources = self.to_convert();
+                if !sources.is_empty() {
+                    return Some(Event::Convert(sources, self.target));
+                }
+            }
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    /// The text files converting changes.
+    fn to_convert(&self) -> Vec<PathBuf> {
+        let Some(detected) = &self.detected else {
+            return Vec::new();
+        };
+        self.sources
+            .iter()
+            .zip(detected)
+            .filter(|(_, endings)| endings.is_some_and(|endings| self.target.changes(endings)))
+            .map(|(source, _)| source.clone())
+            .collect()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Detecting;
+
+        if self.detected.is_some() {
+            return Subscription::none();
+        }
+        let sources = self.sources.clone();
+        subscription::channel(
+            (TypeId::of::<Detecting>(), sources.clone()),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let detected = sources
+                        .iter()
+                        .map(|source| line_endings::detect_file(source).ok().flatten())
+                        .collect();
+                    sender.send(detected)
+                });
+                if let Ok(detected) = receiver.await {
+                    let _ = output.send(Message::Detected(detected)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let mut col = column!(text(tr("Convert line endings")).size(20)).spacing(10);
+
+        match &self.detected {
+            None => col = col.push(text(tr("Reading the files…"))),
+            Some(detected) => {
+                let mut files = column!().spacing(2);
+                for (source, endings) in self.sources.iter().zip(detected).take(MAX_LISTED) {
+                    let name = source.file_name().unwrap_or_default().to_string_lossy();
+                    let endings = match endings {
+                        Some(endings) => endings.to_string(),
+                        None => String::from("No lines to convert, left out"),
+                    };
+                    files = files.push(
+                        row!(
+                            text(name).width(Length::Fill),
+                            text(endings).size(style.small_text_size),
+                        )
+                        .spacing(10)
+                        .align_items(Alignment::Center),
+                    );
+                }
+                if self.sources.len() > MAX_LISTED {
+                    files = files.push(
+                        text(trf("and {} more", &[&(self.sources.len() - MAX_LISTED)]))
+                            .size(style.small_text_size),
+                    );
+                }
+                col = col.push(files);
+            }
+        }
+
+        col = col.push(
+            row!(
+                text(tr("Convert to")).width(Length::Fixed(140.)),
+                pick_list(&LineEnding::ALL[..], Some(self.target), Message::Target),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        let count = self.to_convert().len();
+        if self.detected.is_some() && count == 0 {
+            col = col.push(text(tr("Nothing needs converting")).size(style.small_text_size));
+        }
+        let label = match count {
+            0 | 1 => String::from("Convert"),
+            count => trf("Convert {}", &[&count]),
+        };
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Cancel")).on_press(Message::Close),
+                button(text(label)).on_press_maybe((count > 0).then_some(Message::Convert)),
+            )
+            .spacing(5),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(520)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
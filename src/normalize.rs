@@ -0,0 +1,184 @@
+//! How paths are tidied before they are browsed to, remembered or handed
+//! to a backend: `.` and `..` worked out from the text alone, trailing
+//! separators dropped, and symlinks resolved only when asked to.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Symlinks;
+use crate::paths;
+
+/// `path` without `.`, with each `..` taking off the name before it, as
+/// `cd` does by default: a `..` after a symlink goes back where the link
+/// is, not above where it points. `..` at the root stays there.
+pub fn lexical(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    // The names at the end of `normal` a `..` may take off.
+    let mut names = 0;
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if names > 0 => {
+                normal.pop();
+                names -= 1;
+            }
+            Component::ParentDir if normal.has_root() => {}
+            Component::Normal(name) => {
+                normal.push(name);
+                names += 1;
+            }
+            component => normal.push(component),
+        }
+    }
+    if normal.as_os_str().is_empty() && !path.as_os_str().is_empty() {
+        normal.push(Component::CurDir);
+    }
+
+    normal
+}
+
+/// The absolute path on a server from what follows the authority in its
+/// URL, `/`-separated and never above the top.
+pub fn remote_path(path: &str) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for name in path.split(['/', '\\']) {
+        match name {
+            "" | "." => {}
+            ".." => {
+                names.pop();
+            }
+            name => names.push(name),
+        }
+    }
+
+    format!("/{}", names.join("/"))
+}
+
+/// An `sftp://` or WebDAV URL with its path tidied, its top written
+/// without a slash.
+fn url(text: &str) -> String {
+    let Some((scheme, rest)) = text.split_once("://") else {
+        return text.to_string();
+    };
+    let (authority, path) = rest.split_once(['/', '\\']).unwrap_or((rest, ""));
+    match remote_path(path).as_str() {
+        "/" => format!("{}://{}", scheme, authority),
+        path => format!("{}://{}{}", scheme, authority, path),
+    }
+}
+
+/// Where browsing to `path` goes: tidied, and through its symlinks when
+/// `symlinks` says to resolve them and they can be.
+pub fn for_navigation(path: &Path, symlinks: Symlinks) -> PathBuf {
+    if paths::is_computer(path) {
+        return path.to_path_buf();
+    }
+    if paths::is_network(path) {
+        return PathBuf::from(url(&path.to_string_lossy()));
+    }
+
+    match symlinks {
+        Symlinks::Keep => lexical(path),
+        Symlinks::Resolve => fs::canonicalize(path)
+            .map(plain)
+            .unwrap_or_else(|_| lexical(path)),
+    }
+}
+
+/// `canonicalize` gives `\\?\C:\…` and `\\?\UNC\server\share\…` on
+/// Windows, which few programs take; this is the usual way to write them.
+#[cfg(windows)]
+fn plain(path: PathBuf) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path;
+    };
+    if let Some(share) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", share));
+    }
+    match text.strip_prefix(r"\\?\") {
+        Some(drive) if drive.as_bytes().get(1) == Some(&b':') => PathBuf::from(drive),
+        _ => path,
+    }
+}
+
+#[cfg(not(windows))]
+fn plain(path: PathBuf) -> PathBuf {
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lexical_drops_dots_and_trailing_separators() {
+        assert_eq!(lexical(Path::new("/a/./b/../c/")), Path::new("/a/c"));
+        assert_eq!(lexical(Path::new("/a//b")), Path::new("/a/b"));
+    }
+
+    #[test]
+    fn lexical_stays_at_the_root() {
+        assert_eq!(lexical(Path::new("/../a")), Path::new("/a"));
+        assert_eq!(lexical(Path::new("/a/../..")), Path::new("/"));
+    }
+
+    #[test]
+    fn lexical_keeps_the_parents_of_a_relative_path() {
+        assert_eq!(lexical(Path::new("../a/../../b")), Path::new("../../b"));
+        assert_eq!(lexical(Path::new("a/..")), Path::new("."));
+        assert_eq!(lexical(Path::new("")), Path::new(""));
+    }
+
+    #[test]
+    fn remote_path_never_climbs_above_the_top() {
+        assert_eq!(remote_path("a//b/./c/../d/"), "/a/b/d");
+        assert_eq!(remote_path("../.."), "/");
+        assert_eq!(remote_path(""), "/");
+    }
+
+    #[test]
+    fn urls_keep_their_authority() {
+        assert_eq!(url("sftp://me@host:22/a/b/../c/"), "sftp://me@host:22/a/c");
+        assert_eq!(url("sftp://host/"), "sftp://host");
+        assert_eq!(url("sftp://host/.."), "sftp://host");
+        assert_eq!(url("sftp://host"), "sftp://host");
+    }
+
+    #[test]
+    fn network_paths_are_tidied_whatever_the_policy() {
+        let path = Path::new("sftp://host/a/./b/..");
+        assert_eq!(
+            for_navigation(path, Symlinks::Resolve),
+            Path::new("sftp://host/a")
+        );
+    }
+
+    #[test]
+    fn missing_paths_are_tidied_when_they_cannot_be_resolved() {
+        let path = Path::new("/no/such/./folder/../here");
+        assert_eq!(
+            for_navigation(path, Symlinks::Resolve),
+            Path::new("/no/such/here")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_resolved_only_when_asked_to() {
+        let root = std::env::temp_dir().join(format!("iced-fm-normalize-{}", std::process::id()));
+        let target = root.join("target");
+        let link = root.join("link");
+        fs::create_dir_all(&target).unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let through = link.join(".");
+        let kept = for_navigation(&through, Symlinks::Keep);
+        let resolved = for_navigation(&through, Symlinks::Resolve);
+        let expected = fs::canonicalize(&target).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(kept, link);
+        assert_eq!(resolved, expected);
+    }
+}
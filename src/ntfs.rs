@@ -0,0 +1,409 @@
+//! Filename index built from the NTFS master file table and kept current
+//! through the USN change journal, for near-instant search on local volumes.
+//! Opening a volume handle requires administrator rights.
+
+use std::collections::HashMap;
+use std::ffi::{c_void, OsString};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use windows_sys::Win32::Foundation::{
+    CloseHandle, GetLastError, ERROR_ACCESS_DENIED, ERROR_HANDLE_EOF, GENERIC_READ, HANDLE,
+    INVALID_HANDLE_VALUE,
+};
+use windows_sys::Win32::Storage::FileSystem::{
+    CreateFileW, GetVolumeInformationW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+};
+use windows_sys::Win32::System::Ioctl::{
+    FSCTL_ENUM_USN_DATA, FSCTL_QUERY_USN_JOURNAL, FSCTL_READ_USN_JOURNAL, MFT_ENUM_DATA_V0,
+    READ_USN_JOURNAL_DATA_V0, USN_JOURNAL_DATA_V0, USN_REASON_FILE_CREATE, USN_REASON_FILE_DELETE,
+    USN_REASON_RENAME_NEW_NAME,
+};
+use windows_sys::Win32::System::IO::DeviceIoControl;
+
+use crate::paths;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+const BATCH_SIZE: usize = 256;
+
+/// Indexes are kept for the whole session, unless a search asks otherwise,
+/// and updated from the journal on every search.
+static INDEXES: Mutex<Vec<Index>> = Mutex::new(Vec::new());
+
+struct Entry {
+    parent: u64,
+    name: OsString,
+}
+
+struct Index {
+    drive: u8,
+    journal: u64,
+    next_usn: i64,
+    entries: HashMap<u64, Entry>,
+}
+
+struct Record {
+    reference: u64,
+    parent: u64,
+    reason: u32,
+    name: OsString,
+}
+
+/// Finds entries below `root` whose name contains `query` (already
+/// lowercased), handing them to `send` in batches until it returns `false`.
+/// Fails if `root` is not on a local NTFS drive or the volume cannot be
+/// opened. Unless `keep`, the index is dropped again afterwards.
+pub fn search(
+    root: &Path,
+    query: &str,
+    keep: bool,
+    send: impl FnMut(Vec<PathBuf>) -> bool,
+) -> Result<(), String> {
+    let drive = match root.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(drive) | Prefix::VerbatimDisk(drive) => drive.to_ascii_uppercase(),
+            _ => return Err(String::from("not a local drive")),
+        },
+        _ => return Err(String::from("not a local drive")),
+    };
+
+    let mut indexes = INDEXES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let position = match indexes.iter().position(|index| index.drive == drive) {
+        Some(position) => {
+            let index = &mut indexes[position];
+            if let Err(error) = index.update() {
+                tracing::info!(%error, "rebuilding NTFS index");
+                *index = Index::build(drive)?;
+            }
+            position
+        }
+        None => {
+            indexes.push(Index::build(drive)?);
+            indexes.len() - 1
+        }
+    };
+
+    indexes[position].send_matches(root, query, send);
+    if !keep {
+        indexes.remove(position);
+    }
+
+    Ok(())
+}
+
+impl Index {
+    fn send_matches(&self, root: &Path, query: &str, mut send: impl FnMut(Vec<PathBuf>) -> bool) {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        for (&reference, entry) in &self.entries {
+            if !entry.name.to_string_lossy().to_lowercase().contains(query) {
+                continue;
+            }
+            let Some(path) = self.path(reference) else {
+                continue;
+            };
+            if paths::starts_with(&path, root) && !paths::same(&path, root) {
+                batch.push(path);
+            }
+            if batch.len() == BATCH_SIZE && !send(std::mem::take(&mut batch)) {
+                return;
+            }
+        }
+        if !batch.is_empty() {
+            send(batch);
+        }
+    }
+
+    fn build(drive: u8) -> Result<Index, String> {
+        let started = Instant::now();
+        let volume = Volume::open(drive)?;
+        let journal = volume.journal()?;
+
+        let mut entries = HashMap::new();
+        let mut input = MFT_ENUM_DATA_V0 {
+            StartFileReferenceNumber: 0,
+            LowUsn: 0,
+            HighUsn: journal.NextUsn,
+        };
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+
+        loop {
+            let returned = match volume.control(FSCTL_ENUM_USN_DATA, &input, &mut buffer) {
+                Ok(returned) => returned,
+                Err(ERROR_HANDLE_EOF) => break,
+                Err(code) => return Err(format!("cannot read the MFT (error {})", code)),
+            };
+            let Some(next) = read_u64(&buffer, 0) else {
+                break;
+            };
+            for record in records(buffer.get(8..returned).unwrap_or_default()) {
+                entries.insert(
+                    record.reference,
+                    Entry {
+                        parent: record.parent,
+                        name: record.name,
+                    },
+                );
+            }
+            input.StartFileReferenceNumber = next;
+        }
+
+        tracing::info!(
+            drive = %(drive as char),
+            entries = entries.len(),
+            elapsed = ?started.elapsed(),
+            "built NTFS index"
+        );
+
+        Ok(Index {
+            drive,
+            journal: journal.UsnJournalID,
+            next_usn: journal.NextUsn,
+            entries,
+        })
+    }
+
+    /// Applies what the change journal recorded since the last look. Fails
+    /// when the journal was recreated or has wrapped past our position, in
+    /// which case the index has to be rebuilt.
+    fn update(&mut self) -> Result<(), String> {
+        let volume = Volume::open(self.drive)?;
+        let journal = volume.journal()?;
+        if journal.UsnJournalID != self.journal || journal.FirstUsn > self.next_usn {
+            return Err(String::from("change journal was reset"));
+        }
+
+        let mut input = READ_USN_JOURNAL_DATA_V0 {
+            StartUsn: self.next_usn,
+            ReasonMask: USN_REASON_FILE_CREATE
+                | USN_REASON_FILE_DELETE
+                | USN_REASON_RENAME_NEW_NAME,
+            ReturnOnlyOnClose: 0,
+            Timeout: 0,
+            BytesToWaitFor: 0,
+            UsnJournalID: self.journal,
+        };
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+
+        loop {
+            let returned = volume
+                .control(FSCTL_READ_USN_JOURNAL, &input, &mut buffer)
+                .map_err(|code| format!("cannot read the change journal (error {})", code))?;
+            let Some(next) = read_u64(&buffer, 0) else {
+                break;
+            };
+
+            for record in records(buffer.get(8..returned).unwrap_or_default()) {
+                if record.reason & USN_REASON_FILE_DELETE != 0 {
+                    self.entries.remove(&record.reference);
+                } else if record.reason & (USN_REASON_FILE_CREATE | USN_REASON_RENAME_NEW_NAME) != 0
+                {
+                    self.entries.insert(
+                        record.reference,
+                        Entry {
+                            parent: record.parent,
+                            name: record.name,
+                        },
+                    );
+                }
+            }
+            input.StartUsn = next as i64;
+            if returned <= 8 {
+                break;
+            }
+        }
+
+        self.next_usn = input.StartUsn;
+        Ok(())
+    }
+
+    /// Joins names up the parent chain, which ends at the root directory
+    /// (its own parent). Entries whose chain is broken have no path.
+    fn path(&self, reference: u64) -> Option<PathBuf> {
+        let mut names = Vec::new();
+        let mut current = reference;
+
+        loop {
+            let entry = self.entries.get(&current)?;
+            if entry.parent == current {
+                break;
+            }
+            if names.len() > 512 {
+                return None;
+            }
+            names.push(&entry.name);
+            current = entry.parent;
+        }
+        if names.is_empty() {
+            return None;
+        }
+
+        let mut path = PathBuf::from(format!("{}:\\", self.drive as char));
+        path.extend(names.into_iter().rev());
+        Some(path)
+    }
+}
+
+struct Volume(HANDLE);
+
+impl Volume {
+    fn open(drive: u8) -> Result<Volume, String> {
+        let root = wide(&format!("{}:\\", drive as char));
+        let mut fs_name = [0u16; 32];
+        // SAFETY: every buffer is passed with its length and outlives the call.
+        let ok = unsafe {
+            GetVolumeInformationW(
+                root.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                fs_name.as_mut_ptr(),
+                fs_name.len() as u32,
+            )
+        };
+        let end = fs_name
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(fs_name.len());
+        if ok == 0 || String::from_utf16_lossy(&fs_name[..end]) != "NTFS" {
+            return Err(format!("{}: is not an NTFS volume", drive as char));
+        }
+
+        let device = wide(&format!("\\\\.\\{}:", drive as char));
+        // SAFETY: `device` is a NUL-terminated UTF-16 string.
+        let handle = unsafe {
+            CreateFileW(
+                device.as_ptr(),
+                GENERIC_READ,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                0,
+                0,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            // SAFETY: no preconditions.
+            return Err(match unsafe { GetLastError() } {
+                ERROR_ACCESS_DENIED => {
+                    String::from("reading the NTFS index requires administrator rights")
+                }
+                code => format!("cannot open volume {}: error {}", drive as char, code),
+            });
+        }
+
+        Ok(Volume(handle))
+    }
+
+    fn journal(&self) -> Result<USN_JOURNAL_DATA_V0, String> {
+        // SAFETY: an all-zero value is valid for this plain C struct.
+        let mut journal: USN_JOURNAL_DATA_V0 = unsafe { std::mem::zeroed() };
+        let mut returned = 0;
+        // SAFETY: the output buffer is `journal`, passed with its size.
+        let ok = unsafe {
+            DeviceIoControl(
+                self.0,
+                FSCTL_QUERY_USN_JOURNAL,
+                std::ptr::null(),
+                0,
+                (&mut journal as *mut USN_JOURNAL_DATA_V0).cast(),
+                std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(String::from("the volume has no active change journal"));
+        }
+
+        Ok(journal)
+    }
+
+    /// Issues `code` with `input`, returning the number of bytes written to
+    /// `output` or the Win32 error.
+    fn control<T>(&self, code: u32, input: &T, output: &mut [u8]) -> Result<usize, u32> {
+        let mut returned = 0;
+        // SAFETY: both buffers are passed with their sizes and outlive the call.
+        let ok = unsafe {
+            DeviceIoControl(
+                self.0,
+                code,
+                (input as *const T).cast::<c_void>(),
+                std::mem::size_of::<T>() as u32,
+                output.as_mut_ptr().cast(),
+                output.len() as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            // SAFETY: no preconditions.
+            return Err(unsafe { GetLastError() });
+        }
+
+        Ok(returned as usize)
+    }
+}
+
+impl Drop for Volume {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from `CreateFileW` and is closed only here.
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// Parses a run of `USN_RECORD_V2` structures, skipping other versions.
+fn records(mut buffer: &[u8]) -> impl Iterator<Item = Record> + '_ {
+    std::iter::from_fn(move || loop {
+        let length = read_u32(buffer, 0)? as usize;
+        if length == 0 || length > buffer.len() {
+            return None;
+        }
+        let (record, rest) = buffer.split_at(length);
+        buffer = rest;
+
+        if read_u16(record, 4) != Some(2) {
+            continue;
+        }
+        let name_length = read_u16(record, 56)? as usize;
+        let name_offset = read_u16(record, 58)? as usize;
+        let name: Vec<u16> = record
+            .get(name_offset..name_offset + name_length)?
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        return Some(Record {
+            reference: read_u64(record, 8)?,
+            parent: read_u64(record, 16)?,
+            reason: read_u32(record, 40)?,
+            name: OsString::from_wide(&name),
+        });
+    })
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn wide(text: &str) -> Vec<u16> {
+    std::ffi::OsStr::new(text)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
@@ -0,0 +1,365 @@
+//! Container images saved as files: an OCI image layout, as a folder or a
+//! tarball, or the tarball `docker save` writes. The summary lists the
+//! images inside by name, platform and layers, read from their manifests
+//! without unpacking any layer.
+
+use std::any::TypeId;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::archive::{self, Format};
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::json::{self, Value};
+use crate::style::Style;
+
+/// Manifests and configs are small; anything larger is a layer.
+const MAX_METADATA: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub names: Vec<String>,
+    pub digest: Option<String>,
+    /// `os/architecture`, with the variant if any.
+    pub platform: Option<String>,
+    pub created: Option<String>,
+    pub layers: usize,
+    pub size: Option<u64>,
+}
+
+/// Whether `path` may hold images: a folder with an `oci-layout` file, or a
+/// tar archive, which is only known to once read.
+pub fn may_be_image(path: &Path) -> bool {
+    path.join("oci-layout").is_file()
+        || matches!(
+            archive::format(path),
+            Some(Format::Tar | Format::TarGz | Format::TarXz)
+        )
+}
+
+/// Where the files of an image layout are read from.
+enum Store {
+    Folder(PathBuf),
+    Tarball(archive::TarFiles),
+}
+
+impl Store {
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let missing =
+            || io::Error::new(io::ErrorKind::NotFound, format!("no {} in the image", name));
+        match self {
+            Store::Folder(dir) => {
+                let path = dir.join(name);
+                if fs::metadata(&path)?.len() > MAX_METADATA {
+                    return Err(missing());
+                }
+                fs::read(path)
+            }
+            Store::Tarball(files) => files
+                .get(name)
+                .and_then(|(_, contents)| contents.clone())
+                .ok_or_else(missing),
+        }
+    }
+
+    fn size(&self, name: &str) -> Option<u64> {
+        match self {
+            Store::Folder(dir) => fs::metadata(dir.join(name)).ok().map(|meta| meta.len()),
+            Store::Tarball(files) => files.get(name).map(|(size, _)| *size),
+        }
+    }
+
+    fn json(&self, name: &str) -> io::Result<Value> {
+        let bytes = self.read(name)?;
+        json::parse(&String::from_utf8_lossy(&bytes)).map_err(|error| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", name, error))
+        })
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.size(name).is_some()
+    }
+}
+
+/// The images in the layout or tarball at `path`.
+pub fn summarize(path: &Path) -> io::Result<Vec<Image>> {
+    let store = match path.is_dir() {
+        true => Store::Folder(path.to_path_buf()),
+        false => Store::Tarball(archive::tar_files(path, MAX_METADATA)?),
+    };
+
+    if store.has("index.json") {
+        let index = store.json("index.json")?;
+        let mut images = Vec::new();
+        for descriptor in index
+            .get("manifests")
+            .map(Value::as_array)
+            .unwrap_or_default()
+        {
+            oci_images(&store, descriptor, &[], 0, &mut images)?;
+        }
+        Ok(images)
+    } else if store.has("manifest.json") {
+        docker_images(&store)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a container image",
+        ))
+    }
+}
+
+/// Where a blob is kept, by its digest such as `sha256:…`.
+fn blob(digest: &str) -> Option<String> {
+    let (algorithm, hex) = digest.split_once(':')?;
+    let safe = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric());
+    (safe(algorithm) && safe(hex)).then(|| format!("blobs/{}/{}", algorithm, hex))
+}
+
+/// The image a manifest descriptor points at, or those of an index it
+/// points at, as multi-platform images have; these take the name of the
+/// index when they have none of their own.
+fn oci_images(
+    store: &Store,
+    descriptor: &Value,
+    inherited: &[String],
+    depth: usize,
+    images: &mut Vec<Image>,
+) -> io::Result<()> {
+    let text = |value: &Value, key| value.get(key).and_then(Value::as_str).map(String::from);
+    let Some(digest) = text(descriptor, "digest") else {
+        return Ok(());
+    };
+    let Some(name) = blob(&digest) else {
+        return Ok(());
+    };
+    let manifest = store.json(&name)?;
+    let annotations = descriptor.get("annotations");
+    let mut names: Vec<String> = [
+        "org.opencontainers.image.ref.name",
+        "io.containerd.image.name",
+    ]
+    .iter()
+    .filter_map(|key| annotations.and_then(|annotations| text(annotations, key)))
+    .take(1)
+    .collect();
+    if names.is_empty() {
+        names = inherited.to_vec();
+    }
+    let media_type = text(descriptor, "mediaType")
+        .or_else(|| text(&manifest, "mediaType"))
+        .unwrap_or_default();
+    if media_type.contains("index") || media_type.contains("manifest.list") {
+        if depth < 2 {
+            for nested in manifest
+                .get("manifests")
+                .map(Value::as_array)
+                .unwrap_or_default()
+            {
+                oci_images(store, nested, &names, depth + 1, images)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let config = manifest
+        .get("config")
+        .and_then(|config| text(config, "digest"))
+        .and_then(|digest| blob(&digest))
+        .and_then(|name| store.json(&name).ok());
+    let layers = manifest
+        .get("layers")
+        .map(Value::as_array)
+        .unwrap_or_default();
+    let size = layers
+        .iter()
+        .map(|layer| layer.get("size").and_then(Value::as_u64))
+        .sum();
+
+    images.push(Image {
+        names,
+        digest: Some(digest),
+        platform: descriptor
+            .get("platform")
+            .and_then(platform)
+            .or_else(|| config.as_ref().and_then(platform)),
+        created: config.as_ref().and_then(|config| text(config, "created")),
+        layers: layers.len(),
+        size,
+    });
+
+    Ok(())
+}
+
+fn docker_images(store: &Store) -> io::Result<Vec<Image>> {
+    let manifest = store.json("manifest.json")?;
+    let mut images = Vec::new();
+    for entry in manifest.as_array() {
+        let config_name = entry.get("Config").and_then(Value::as_str);
+        let config = config_name.and_then(|name| store.json(name).ok());
+        let layers = entry.get("Layers").map(Value::as_array).unwrap_or_default();
+        let size = layers.iter().map(|layer| store.size(layer.as_str()?)).sum();
+
+        images.push(Image {
+            names: entry
+                .get("RepoTags")
+                .map(Value::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+            // Named after the digest of its config.
+            digest: config_name.map(|name| {
+                let name = name.trim_start_matches("blobs/").trim_end_matches(".json");
+                format!("sha256:{}", name.trim_start_matches("sha256/"))
+            }),
+            platform: config.as_ref().and_then(platform),
+            created: config
+                .as_ref()
+                .and_then(|config| config.get("created"))
+                .and_then(Value::as_str)
+                .map(String::from),
+            layers: layers.len(),
+            size,
+        });
+    }
+
+    Ok(images)
+}
+
+fn platform(value: &Value) -> Option<String> {
+    let field = |key| value.get(key).and_then(Value::as_str);
+    let mut platform = format!("{}/{}", field("os")?, field("architecture")?);
+    if let Some(variant) = field("variant") {
+        platform = format!("{}/{}", platform, variant);
+    }
+
+    Some(platform)
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Summarized(Result<Vec<Image>, String>),
+    Close,
+}
+
+pub enum Event {
+    Closed,
+}
+
+/// "Container image summary".
+pub struct Summary {
+    path: PathBuf,
+    images: Option<Result<Vec<Image>, String>>,
+}
+
+impl Summary {
+    pub fn open(path: PathBuf) -> Summary {
+        Summary { path, images: None }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Summarized(images) => self.images = Some(images),
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Reading;
+
+        if self.images.is_some() {
+            return Subscription::none();
+        }
+        let path = self.path.clone();
+        subscription::channel(
+            (TypeId::of::<Reading>(), path.clone()),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    sender.send(summarize(&path).map_err(|error| error.to_string()))
+                });
+                if let Ok(images) = receiver.await {
+                    let _ = output.send(Message::Summarized(images)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let mut col = column!(text(trf("Images in {}", &[&name])).size(20)).spacing(10);
+
+        match &self.images {
+            None => col = col.push(text(tr("Reading the manifests…"))),
+            Some(Err(error)) => col = col.push(text(error).style(theme::Text::Color(style.error))),
+            Some(Ok(images)) if images.is_empty() => col = col.push(text(tr("No images"))),
+            Some(Ok(images)) => {
+                let mut list = column!().spacing(12);
+                for image in images {
+                    let title = match &image.names[..] {
+                        [] => String::from("Untagged"),
+                        names => names.join(", "),
+                    };
+                    let mut details = vec![format!(
+                        "{} layers{}",
+                        image.layers,
+                        image
+                            .size
+                            .map(|size| format!(", {}", format_bytes(size)))
+                            .unwrap_or_default()
+                    )];
+                    details.extend(image.platform.clone());
+                    details.extend(
+                        image
+                            .created
+                            .clone()
+                            .map(|created| format!("created {}", created)),
+                    );
+                    let mut entry = column!(
+                        text(title),
+                        text(details.join(" · ")).size(style.small_text_size),
+                    )
+                    .spacing(2);
+                    if let Some(digest) = &image.digest {
+                        entry = entry.push(
+                            text(digest)
+                                .size(style.small_text_size)
+                                .style(theme::Text::Color(style.dimmed)),
+                        );
+                    }
+                    list = list.push(entry);
+                }
+                col = col.push(scrollable(list).height(Length::Shrink));
+            }
+        }
+
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Close")).on_press(Message::Close),
+            )
+            .align_items(Alignment::Center),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(620)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
@@ -0,0 +1,205 @@
+//! The engine behind the picker's transfers, for hosts that copy, move,
+//! trash or delete without showing it. A `Batch` runs its steps one after
+//! another on the calling thread, so it belongs on a thread of its own;
+//! progress comes back through a callback, and a `Token` handed to another
+//! thread pauses or stops it. Moves within a drive are renames, and across
+//! drives the originals only go once their copies check out, as in the
+//! picker.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use iced::futures::channel::mpsc;
+use iced::futures::executor::block_on_stream;
+
+use crate::jobs::{self, ConflictAction, Control, JobKind, Progress};
+
+/// What to do when a name is taken in the destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Conflicts {
+    /// Leaves what is there, and the source where it is.
+    #[default]
+    Skip,
+    Overwrite,
+    /// Adds a number to the new name, as "report (2).pdf".
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    pub conflicts: Conflicts,
+    pub preserve_timestamps: bool,
+    /// Copies through a smaller buffer.
+    pub low_memory: bool,
+}
+
+/// Stops or pauses a batch from another thread; its clones all act on the
+/// same one.
+#[derive(Clone, Default)]
+pub struct Token(Arc<Control>);
+
+impl Token {
+    pub fn new() -> Token {
+        Token::default()
+    }
+
+    /// Stops at the next file, or within the one being copied; the batch
+    /// gives an `Interrupted` error.
+    pub fn cancel(&self) {
+        self.0.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled()
+    }
+
+    pub fn pause(&self) {
+        self.0.set_paused(true);
+    }
+
+    pub fn resume(&self) {
+        self.0.set_paused(false);
+    }
+}
+
+/// How a step of the batch is getting on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    /// `done` of `total`: bytes for copies and moves, items for the rest.
+    Progress { step: usize, done: u64, total: u64 },
+    /// An item was moved, or trashed, from `from` to `to`.
+    Moved { from: PathBuf, to: PathBuf },
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    kind: JobKind,
+    sources: Vec<PathBuf>,
+    destination: Option<PathBuf>,
+}
+
+/// Operations to run in order, the first failure ending the batch.
+#[derive(Debug, Clone, Default)]
+pub struct Batch {
+    steps: Vec<Step>,
+    options: Options,
+}
+
+impl Batch {
+    pub fn new() -> Batch {
+        Batch::default()
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Copies `sources` into the folder `destination`.
+    pub fn copy(self, sources: Vec<PathBuf>, destination: impl Into<PathBuf>) -> Self {
+        self.step(JobKind::Copy, sources, Some(destination.into()))
+    }
+
+    /// Moves `sources` into the folder `destination`.
+    pub fn move_to(self, sources: Vec<PathBuf>, destination: impl Into<PathBuf>) -> Self {
+        self.step(JobKind::Move, sources, Some(destination.into()))
+    }
+
+    pub fn trash(self, sources: Vec<PathBuf>) -> Self {
+        self.step(JobKind::Trash, sources, None)
+    }
+
+    /// Deletes `sources` for good, folders with all they hold.
+    pub fn delete(self, sources: Vec<PathBuf>) -> Self {
+        self.step(JobKind::Delete, sources, None)
+    }
+
+    fn step(mut self, kind: JobKind, sources: Vec<PathBuf>, destination: Option<PathBuf>) -> Self {
+        self.steps.push(Step {
+            kind,
+            sources,
+            destination,
+        });
+        self
+    }
+
+    /// Runs every step, handing `progress` what happens as it does.
+    pub fn run(self, token: &Token, mut progress: impl FnMut(Update)) -> io::Result<()> {
+        let options = jobs::Options {
+            preserve_timestamps: self.options.preserve_timestamps,
+            low_memory: self.options.low_memory,
+            overwrite: false,
+            keep_both: false,
+            resume: false,
+        };
+        let conflicts = match self.options.conflicts {
+            Conflicts::Skip => ConflictAction::Skip,
+            Conflicts::Overwrite => ConflictAction::Overwrite,
+            Conflicts::KeepBoth => ConflictAction::KeepBoth,
+        };
+
+        for (index, step) in self.steps.iter().enumerate() {
+            if token.is_cancelled() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+
+            let (sender, receiver) = mpsc::unbounded();
+            let control = &token.0;
+            let conflicts = conflicts.clone();
+            let mut total = 0;
+            let result = thread::scope(|scope| {
+                let worker = scope.spawn(move || {
+                    jobs::run(
+                        step.kind,
+                        &step.sources,
+                        step.destination.as_deref(),
+                        options,
+                        Some(conflicts),
+                        control,
+                        &sender,
+                    )
+                });
+
+                for report in block_on_stream(receiver) {
+                    match report {
+                        Progress::Started { total: size } => {
+                            total = size;
+                            progress(Update::Progress {
+                                step: index,
+                                done: 0,
+                                total,
+                            });
+                        }
+                        Progress::Advanced { done } => progress(Update::Progress {
+                            step: index,
+                            done,
+                            total,
+                        }),
+                        Progress::Moved { from, to } => progress(Update::Moved { from, to }),
+                        // Never asked with a policy for conflicts; the rest
+                        // are only sent by the picker's jobs.
+                        Progress::Conflict { .. }
+                        | Progress::Waiting
+                        | Progress::Finished
+                        | Progress::Cancelled
+                        | Progress::Failed(_) => {}
+                    }
+                }
+
+                worker
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("the operation panicked")))
+            });
+            result?;
+            progress(Update::Progress {
+                step: index,
+                done: total,
+                total,
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,572 @@
+//! Software packages, read without installing or unpacking them: Debian
+//! `.deb` files, an `ar` archive of a control tarball and a data tarball,
+//! and `.rpm` files, whose header holds both the metadata and the list of
+//! files. Installing one goes through the system's package manager.
+
+use std::any::TypeId;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::archive;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::style::Style;
+use crate::system;
+
+/// Files listed by the summary; the others are only counted.
+const MAX_LISTED: usize = 1000;
+/// More than any real package header; anything larger is corrupt.
+const MAX_HEADER: u64 = 64 * 1024 * 1024;
+const MAX_CONTROL: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Deb,
+    Rpm,
+}
+
+pub fn kind(path: &Path) -> Option<Kind> {
+    let extension = path.extension()?.to_str()?;
+    let kind = if extension.eq_ignore_ascii_case("deb") {
+        Kind::Deb
+    } else if extension.eq_ignore_ascii_case("rpm") {
+        Kind::Rpm
+    } else {
+        return None;
+    };
+
+    path.is_file().then_some(kind)
+}
+
+#[derive(Debug, Clone)]
+pub struct Packaged {
+    /// Absolute, as installed.
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Package {
+    /// Name, version and the like, as labelled for showing.
+    pub fields: Vec<(String, String)>,
+    /// The first of the files, up to [`MAX_LISTED`].
+    pub files: Vec<Packaged>,
+    pub file_count: usize,
+}
+
+impl Package {
+    fn add(&mut self, file: Packaged) {
+        if self.files.len() < MAX_LISTED {
+            self.files.push(file);
+        }
+        self.file_count += 1;
+    }
+}
+
+pub fn read(path: &Path) -> io::Result<Package> {
+    match kind(path) {
+        Some(Kind::Deb) => deb(path),
+        Some(Kind::Rpm) => rpm(path),
+        None => Err(io::Error::other("not a package")),
+    }
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, what.to_string())
+}
+
+fn deb(path: &Path) -> io::Result<Package> {
+    let mut file = File::open(path)?;
+    let mut magic = [0; 8];
+    file.read_exact(&mut magic)?;
+    if &magic != b"!<arch>\n" {
+        return Err(invalid("not a Debian package"));
+    }
+
+    let mut package = Package {
+        fields: Vec::new(),
+        files: Vec::new(),
+        file_count: 0,
+    };
+    let mut control = None;
+    let mut offset = 8;
+    loop {
+        let mut header = [0; 60];
+        file.seek(SeekFrom::Start(offset))?;
+        match file.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        }
+        let name = String::from_utf8_lossy(&header[..16])
+            .trim_end()
+            .trim_end_matches('/')
+            .to_string();
+        let size: u64 = String::from_utf8_lossy(&header[48..58])
+            .trim()
+            .parse()
+            .map_err(|_| invalid("corrupt ar header"))?;
+        let start = offset + 60;
+        // Members start at even offsets.
+        offset = start + size + size % 2;
+
+        let Some(compression) = name
+            .strip_prefix("control.tar")
+            .or_else(|| name.strip_prefix("data.tar"))
+        else {
+            continue;
+        };
+        let mut member = file.try_clone()?;
+        member.seek(SeekFrom::Start(start))?;
+        let reader = archive::decompress(
+            Box::new(member.take(size)),
+            compression.trim_start_matches('.'),
+        )?;
+        if name.starts_with("control") {
+            archive::walk_tar(reader, |header, data| {
+                if header.path.trim_start_matches("./") == "control" {
+                    let mut text = Vec::new();
+                    data.take(MAX_CONTROL).read_to_end(&mut text)?;
+                    control = Some(String::from_utf8_lossy(&text).into_owned());
+                }
+                Ok(())
+            })?;
+        } else {
+            archive::walk_tar(reader, |header, _| {
+                let path = header.path.trim_start_matches('.').trim_end_matches('/');
+                if !path.is_empty() {
+                    package.add(Packaged {
+                        path: match path.starts_with('/') {
+                            true => path.to_string(),
+                            false => format!("/{}", path),
+                        },
+                        size: header.size,
+                        is_dir: header.is_dir(),
+                    });
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    let control = control.ok_or_else(|| invalid("no control file in the package"))?;
+    package.fields = control_fields(&control);
+    Ok(package)
+}
+
+/// The fields of a Debian control file worth showing, in a set order.
+fn control_fields(control: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in control.lines() {
+        match (line.strip_prefix([' ', '\t']), fields.last_mut()) {
+            // Continues the field before; a lone dot is an empty line.
+            (Some(rest), Some((_, value))) => {
+                value.push('\n');
+                value.push_str(if rest.trim() == "." { "" } else { rest.trim() });
+            }
+            _ => {
+                if let Some((name, value)) = line.split_once(':') {
+                    fields.push((name.trim().to_string(), value.trim().to_string()));
+                }
+            }
+        }
+    }
+
+    let shown = [
+        ("Package", "Name"),
+        ("Version", "Version"),
+        ("Architecture", "Architecture"),
+        ("Maintainer", "Maintainer"),
+        ("Section", "Section"),
+        ("Installed-Size", "Installed size"),
+        ("Depends", "Depends on"),
+        ("Homepage", "Homepage"),
+        ("Description", "Description"),
+    ];
+    shown
+        .iter()
+        .filter_map(|(field, label)| {
+            let (_, value) = fields.iter().find(|(name, _)| name == field)?;
+            let value = match *field {
+                // In KiB.
+                "Installed-Size" => format_bytes(value.parse::<u64>().ok()? * 1024),
+                _ => value.clone(),
+            };
+            Some((label.to_string(), value))
+        })
+        .collect()
+}
+
+mod tag {
+    pub const NAME: u32 = 1000;
+    pub const VERSION: u32 = 1001;
+    pub const RELEASE: u32 = 1002;
+    pub const SUMMARY: u32 = 1004;
+    pub const DESCRIPTION: u32 = 1005;
+    pub const SIZE: u32 = 1009;
+    pub const VENDOR: u32 = 1011;
+    pub const LICENSE: u32 = 1014;
+    pub const URL: u32 = 1020;
+    pub const ARCH: u32 = 1022;
+    pub const OLDFILENAMES: u32 = 1027;
+    pub const FILESIZES: u32 = 1028;
+    pub const FILEMODES: u32 = 1030;
+    pub const REQUIRENAME: u32 = 1049;
+    pub const DIRINDEXES: u32 = 1116;
+    pub const BASENAMES: u32 = 1117;
+    pub const DIRNAMES: u32 = 1118;
+    pub const LONGFILESIZES: u32 = 5008;
+    pub const LONGSIZE: u32 = 5009;
+}
+
+/// An RPM header: its index of tags, and the store the index points into.
+struct Header {
+    /// Tag, type, offset and count.
+    index: Vec<(u32, u32, usize, usize)>,
+    store: Vec<u8>,
+}
+
+impl Header {
+    /// Reads the header at the reader's position, leaving it after it.
+    fn read(file: &mut File) -> io::Result<Header> {
+        let mut intro = [0; 16];
+        file.read_exact(&mut intro)?;
+        if intro[..4] != [0x8e, 0xad, 0xe8, 0x01] {
+            return Err(invalid("corrupt RPM header"));
+        }
+        let number = |at: usize| {
+            u32::from_be_bytes([intro[at], intro[at + 1], intro[at + 2], intro[at + 3]])
+        };
+        let (entries, size) = (number(8) as u64, number(12) as u64);
+        if entries * 16 + size > MAX_HEADER {
+            return Err(invalid("corrupt RPM header"));
+        }
+
+        let mut index = vec![0; entries as usize * 16];
+        file.read_exact(&mut index)?;
+        let mut store = vec![0; size as usize];
+        file.read_exact(&mut store)?;
+        let index = index
+            .chunks_exact(16)
+            .map(|entry| {
+                let field = |at: usize| {
+                    u32::from_be_bytes([entry[at], entry[at + 1], entry[at + 2], entry[at + 3]])
+                };
+                (field(0), field(4), field(8) as usize, field(12) as usize)
+            })
+            .collect();
+
+        Ok(Header { index, store })
+    }
+
+    fn entry(&self, tag: u32) -> Option<(u32, &[u8], usize)> {
+        let (_, kind, offset, count) = self.index.iter().find(|entry| entry.0 == tag)?;
+        Some((*kind, self.store.get(*offset..)?, *count))
+    }
+
+    /// The strings of a string, string array or translated string tag;
+    /// only the first translation of the last.
+    fn strings(&self, tag: u32) -> Vec<String> {
+        let Some((kind, data, count)) = self.entry(tag) else {
+            return Vec::new();
+        };
+        let count = match kind {
+            6 | 9 => 1,
+            8 => count,
+            _ => return Vec::new(),
+        };
+        data.split(|&byte| byte == 0)
+            .take(count)
+            .map(|text| String::from_utf8_lossy(text).into_owned())
+            .collect()
+    }
+
+    fn string(&self, tag: u32) -> Option<String> {
+        self.strings(tag).into_iter().next()
+    }
+
+    /// The numbers of a 16-, 32- or 64-bit integer tag.
+    fn numbers(&self, tag: u32) -> Vec<u64> {
+        let Some((kind, data, count)) = self.entry(tag) else {
+            return Vec::new();
+        };
+        let width = match kind {
+            3 => 2,
+            4 => 4,
+            5 => 8,
+            _ => return Vec::new(),
+        };
+        data.chunks_exact(width)
+            .take(count)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .fold(0u64, |number, &byte| (number << 8) | byte as u64)
+            })
+            .collect()
+    }
+}
+
+fn rpm(path: &Path) -> io::Result<Package> {
+    let mut file = File::open(path)?;
+    let mut lead = [0; 96];
+    file.read_exact(&mut lead)?;
+    if lead[..4] != [0xed, 0xab, 0xee, 0xdb] {
+        return Err(invalid("not an RPM package"));
+    }
+    Header::read(&mut file)?;
+    // The signature is padded to a multiple of eight bytes.
+    let position = file.stream_position()?;
+    file.seek(SeekFrom::Start(position.div_ceil(8) * 8))?;
+    let header = Header::read(&mut file)?;
+
+    let paths = match header.strings(tag::BASENAMES) {
+        names if !names.is_empty() => {
+            let dirs = header.strings(tag::DIRNAMES);
+            let indexes = header.numbers(tag::DIRINDEXES);
+            names
+                .into_iter()
+                .zip(indexes)
+                .map(|(name, index)| {
+                    let dir = dirs.get(index as usize).map_or("", String::as_str);
+                    format!("{}{}", dir, name)
+                })
+                .collect()
+        }
+        _ => header.strings(tag::OLDFILENAMES),
+    };
+    let sizes = match header.numbers(tag::LONGFILESIZES) {
+        sizes if !sizes.is_empty() => sizes,
+        _ => header.numbers(tag::FILESIZES),
+    };
+    let modes = header.numbers(tag::FILEMODES);
+    let mut package = Package {
+        fields: Vec::new(),
+        files: Vec::new(),
+        file_count: 0,
+    };
+    for (index, path) in paths.into_iter().enumerate() {
+        package.add(Packaged {
+            path,
+            size: sizes.get(index).copied().unwrap_or(0),
+            is_dir: modes
+                .get(index)
+                .is_some_and(|mode| mode & 0o170000 == 0o040000),
+        });
+    }
+
+    let mut fields = Vec::new();
+    let mut field = |label: &str, value: Option<String>| {
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            fields.push((label.to_string(), value));
+        }
+    };
+    field("Name", header.string(tag::NAME));
+    field(
+        "Version",
+        header
+            .string(tag::VERSION)
+            .map(|version| match header.string(tag::RELEASE) {
+                Some(release) => format!("{}-{}", version, release),
+                None => version,
+            }),
+    );
+    field("Architecture", header.string(tag::ARCH));
+    field("Summary", header.string(tag::SUMMARY));
+    field("Vendor", header.string(tag::VENDOR));
+    field("License", header.string(tag::LICENSE));
+    let size = match header.numbers(tag::LONGSIZE).first() {
+        Some(size) => Some(*size),
+        None => header.numbers(tag::SIZE).first().copied(),
+    };
+    field("Installed size", size.map(format_bytes));
+    let mut requires: Vec<String> = header
+        .strings(tag::REQUIRENAME)
+        .into_iter()
+        .filter(|name| !name.starts_with("rpmlib(") && !name.starts_with('/'))
+        .collect();
+    requires.dedup();
+    field("Depends on", Some(requires.join(", ")));
+    field("Homepage", header.string(tag::URL));
+    field("Description", header.string(tag::DESCRIPTION));
+    package.fields = fields;
+
+    Ok(package)
+}
+
+/// The command installing the package at `path`, asking for the password
+/// through polkit.
+pub fn install_command(path: &Path, kind: Kind) -> Result<String, String> {
+    if !cfg!(target_os = "linux") {
+        return Err(String::from("Packages are only installed on Linux"));
+    }
+    if !system::on_path("pkexec") {
+        return Err(String::from(
+            "Installing needs pkexec, which is not installed",
+        ));
+    }
+    let candidates: &[(&str, &str)] = match kind {
+        Kind::Deb => &[
+            ("apt-get", "apt-get install --yes"),
+            ("dpkg", "dpkg --install"),
+        ],
+        Kind::Rpm => &[
+            ("dnf", "dnf install --assumeyes"),
+            ("zypper", "zypper --non-interactive install"),
+            ("rpm", "rpm --install"),
+        ],
+    };
+    let (_, command) = candidates
+        .iter()
+        .find(|(program, _)| system::on_path(program))
+        .ok_or_else(|| String::from("No package manager for this package is installed"))?;
+
+    Ok(format!(
+        "pkexec {} {}",
+        command,
+        system::quote(&path.to_string_lossy())
+    ))
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Read(Result<Package, String>),
+    Install,
+    Close,
+}
+
+pub enum Event {
+    Install(PathBuf, Kind),
+    Closed,
+}
+
+/// "Package contents".
+pub struct Summary {
+    path: PathBuf,
+    kind: Kind,
+    package: Option<Result<Package, String>>,
+}
+
+impl Summary {
+    pub fn open(path: PathBuf, kind: Kind) -> Summary {
+        Summary {
+            path,
+            kind,
+            package: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Read(package) => self.package = Some(package),
+            Message::Install => return Some(Event::Install(self.path.clone(), self.kind)),
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Reading;
+
+        if self.package.is_some() {
+            return Subscription::none();
+        }
+        let path = self.path.clone();
+        subscription::channel(
+            (TypeId::of::<Reading>(), path.clone()),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    sender.send(read(&path).map_err(|error| error.to_string()))
+                });
+                if let Ok(package) = receiver.await {
+                    let _ = output.send(Message::Read(package)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let mut col = column!(text(name).size(20)).spacing(10);
+
+        match &self.package {
+            None => col = col.push(text(tr("Reading the package…"))),
+            Some(Err(error)) => col = col.push(text(error).style(theme::Text::Color(style.error))),
+            Some(Ok(package)) => {
+                let mut details = column!().spacing(8);
+                for (label, value) in &package.fields {
+                    details = details.push(
+                        row!(
+                            text(label)
+                                .size(style.small_text_size)
+                                .width(Length::Fixed(110.)),
+                            text(value).size(style.small_text_size).width(Length::Fill),
+                        )
+                        .spacing(10),
+                    );
+                }
+                details = details.push(text(trf("Files ({})", &[&package.file_count])));
+                let mut files = column!().spacing(2);
+                for file in &package.files {
+                    let size = match file.is_dir {
+                        true => String::new(),
+                        false => format_bytes(file.size),
+                    };
+                    files = files.push(
+                        row!(
+                            text(&file.path)
+                                .size(style.small_text_size)
+                                .width(Length::Fill),
+                            text(size).size(style.small_text_size),
+                        )
+                        .spacing(10),
+                    );
+                }
+                if package.file_count > package.files.len() {
+                    files = files.push(
+                        text(trf(
+                            "and {} more",
+                            &[&(package.file_count - package.files.len())],
+                        ))
+                        .size(style.small_text_size)
+                        .style(theme::Text::Color(style.dimmed)),
+                    );
+                }
+                details = details.push(files);
+                col = col.push(scrollable(details).height(Length::Fixed(420.)));
+            }
+        }
+
+        let installable = cfg!(target_os = "linux") && matches!(self.package, Some(Ok(_)));
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Close")).on_press(Message::Close),
+                button(tr("Install with the package manager…"))
+                    .on_press_maybe(installable.then_some(Message::Install)),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(680)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
@@ -0,0 +1,193 @@
+//! The command palette: every menu item that can run now, found by typing
+//! some of its letters in order, such as "nfo" for "New folder". The same
+//! finds one of the folders visited to jump to.
+
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::i18n::tr;
+use crate::style::Style;
+
+/// How many commands are listed at once.
+const MAX_SHOWN: usize = 12;
+
+/// Something the palette can run.
+pub struct Command<M> {
+    /// With the menu it is in, as in "Edit › Copy".
+    pub title: String,
+    pub keys: Option<String>,
+    pub message: M,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Query(String),
+    Move(isize),
+    /// Runs the command listed at the position given, or the highlighted
+    /// one.
+    Run(Option<usize>),
+    Close,
+}
+
+pub enum Event<M> {
+    Run(M),
+    Closed,
+}
+
+/// What the palette lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Kind {
+    #[default]
+    Commands,
+    /// Folders, the most used first, which the query does not reorder.
+    Places,
+}
+
+#[derive(Default)]
+pub struct Palette {
+    kind: Kind,
+    query: String,
+    /// The highlighted command, as a position in those listed.
+    highlighted: usize,
+}
+
+pub fn input() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+impl Palette {
+    pub fn new(kind: Kind) -> Palette {
+        Palette {
+            kind,
+            ..Palette::default()
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn update<M: Clone>(
+        &mut self,
+        message: Message,
+        commands: &[Command<M>],
+    ) -> Option<Event<M>> {
+        match message {
+            Message::Query(query) => {
+                self.query = query;
+                self.highlighted = 0;
+            }
+            Message::Move(step) => {
+                let last = self.matches(commands).len().saturating_sub(1);
+                self.highlighted = self.highlighted.saturating_add_signed(step).min(last);
+            }
+            Message::Run(position) => {
+                let matches = self.matches(commands);
+                let index = matches.get(position.unwrap_or(self.highlighted))?;
+                return Some(Event::Run(commands[*index].message.clone()));
+            }
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    /// Where the commands matching the query are in `commands`, best
+    /// first.
+    fn matches<M>(&self, commands: &[Command<M>]) -> Vec<usize> {
+        let query = self.query.trim().to_lowercase();
+        let mut scored: Vec<(usize, usize)> = commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| Some((score(&query, &command.title)?, index)))
+            .collect();
+        // Ties keep menu order.
+        if self.kind == Kind::Commands {
+            scored.sort_by_key(|&(score, index)| (std::cmp::Reverse(score), index));
+        }
+
+        scored
+            .into_iter()
+            .take(MAX_SHOWN)
+            .map(|(_, index)| index)
+            .collect()
+    }
+
+    pub fn view<M>(&self, commands: &[Command<M>], style: &Style) -> Element<'_, Message> {
+        let mut list = column!().spacing(2);
+        let matches = self.matches(commands);
+        if matches.is_empty() {
+            let empty = match self.kind {
+                Kind::Commands => tr("No matching command"),
+                Kind::Places => tr("No matching folder visited"),
+            };
+            list = list.push(text(empty).size(style.small_text_size));
+        }
+        for (position, index) in matches.into_iter().enumerate() {
+            let command = &commands[index];
+            list = list.push(
+                button(
+                    row!(
+                        text(&command.title).width(Length::Fill),
+                        text(command.keys.clone().unwrap_or_default()).size(style.small_text_size),
+                    )
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                )
+                .width(Length::Fill)
+                .padding([4, 10])
+                .style(match position == self.highlighted {
+                    true => theme::Button::Primary,
+                    false => theme::Button::Text,
+                })
+                .on_press(Message::Run(Some(position))),
+            );
+        }
+
+        container(
+            column!(
+                text_input(
+                    match self.kind {
+                        Kind::Commands => tr("Type a command"),
+                        Kind::Places => tr("Type part of a folder's path"),
+                    },
+                    &self.query
+                )
+                .id(input())
+                .on_input(Message::Query)
+                .on_submit(Message::Run(None))
+                .padding(8),
+                list,
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .max_width(560)
+        .padding(10)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+/// How well `text` matches `query`, a lowercase string whose characters
+/// must all be found in it in order, or `None`. Characters starting a word
+/// or following the last one matched count for more.
+fn score(query: &str, text: &str) -> Option<usize> {
+    let mut wanted = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut adjacent = false;
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let starts_word = previous.is_none_or(|previous| !previous.is_alphanumeric());
+        previous = Some(c);
+        if wanted.peek() != Some(&c) {
+            adjacent = false;
+            continue;
+        }
+        wanted.next();
+        score += 1 + if starts_word { 3 } else { 0 } + if adjacent { 2 } else { 0 };
+        adjacent = true;
+    }
+
+    wanted.peek().is_none().then_some(score)
+}
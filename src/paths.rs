@@ -0,0 +1,352 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::trf;
+use crate::remote;
+use crate::webdav;
+
+/// Name of the virtual location above the drive roots on Windows, which is
+/// represented by an empty path.
+pub const COMPUTER: &str = "Computer";
+
+pub fn is_computer(path: &Path) -> bool {
+    cfg!(windows) && path.as_os_str().is_empty()
+}
+
+/// On another machine, over SSH or WebDAV.
+pub fn is_network(path: &Path) -> bool {
+    path.to_str().is_some_and(is_network_url)
+}
+
+fn is_network_url(text: &str) -> bool {
+    text.starts_with(remote::SCHEME) || webdav::is_url(text)
+}
+
+/// Whether `path` is a macOS application bundle, which opens as an app
+/// rather than as a folder.
+pub fn is_app_bundle(path: &Path) -> bool {
+    cfg!(target_os = "macos")
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("app"))
+        && path.is_dir()
+}
+
+/// Whether `path` is the root, a folder the system keeps its own files in
+/// or inside one, or where users' homes are: worth asking about before it
+/// is trashed, deleted or moved.
+pub fn is_system(path: &Path) -> bool {
+    #[cfg(windows)]
+    const ROOTS: &[&str] = &[r"C:\Users"];
+    #[cfg(windows)]
+    const WITHIN: &[&str] = &[
+        r"C:\Windows",
+        r"C:\Program Files",
+        r"C:\Program Files (x86)",
+        r"C:\ProgramData",
+    ];
+    #[cfg(not(windows))]
+    const ROOTS: &[&str] = &[
+        "/home", "/Users", "/root", "/var", "/opt", "/srv", "/mnt", "/media",
+    ];
+    #[cfg(not(windows))]
+    const WITHIN: &[&str] = &[
+        "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/sbin", "/sys",
+        "/usr", "/System",
+    ];
+
+    // A drive's root has no parent, `/` included.
+    path.parent().is_none() && !path.as_os_str().is_empty()
+        || ROOTS.iter().any(|root| same(path, Path::new(root)))
+        || WITHIN.iter().any(|base| starts_with(path, Path::new(base)))
+}
+
+/// What to show for `path` in the address bar.
+pub fn display(path: &Path) -> String {
+    if is_computer(path) {
+        String::from(COMPUTER)
+    } else {
+        path.to_string_lossy().to_string()
+    }
+}
+
+/// Component-wise prefix test that, like the filesystem, ignores case on
+/// Windows.
+pub fn starts_with(path: &Path, base: &Path) -> bool {
+    if !cfg!(windows) {
+        return path.starts_with(base);
+    }
+
+    let mut components = path.components();
+    base.components().all(|base| {
+        components
+            .next()
+            .is_some_and(|component| fold(component.as_os_str()) == fold(base.as_os_str()))
+    })
+}
+
+/// Path equality with the same case rules as `starts_with`.
+pub fn same(a: &Path, b: &Path) -> bool {
+    starts_with(a, b) && a.components().count() == b.components().count()
+}
+
+fn fold(component: &OsStr) -> String {
+    component.to_string_lossy().to_lowercase()
+}
+
+/// Turns what the user typed into the address bar into a path. On Windows a
+/// bare drive (`C:`) means its root rather than its current directory, a
+/// share (`\\server\share`) gets the separator it needs to be listed, and
+/// forward slashes are accepted.
+pub fn from_input(input: &str) -> PathBuf {
+    let input = input.trim();
+    if !cfg!(windows) {
+        return PathBuf::from(input);
+    }
+
+    if input.eq_ignore_ascii_case(COMPUTER) {
+        return PathBuf::new();
+    }
+
+    let mut input = input.replace('/', "\\");
+    let bytes = input.as_bytes();
+    let bare_drive = bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    let bare_share = input.starts_with("\\\\")
+        && !input.starts_with("\\\\?\\")
+        && !input.ends_with('\\')
+        && input[2..].split('\\').count() == 2;
+    if bare_drive || bare_share {
+        input.push('\\');
+    }
+
+    PathBuf::from(input)
+}
+
+/// `from_input` after expanding `~`, `~user` and environment variables
+/// (`$NAME`, `${NAME}`, and `%NAME%` on Windows), with relative paths taken
+/// against `base`. Unset variables stay as typed, since names like
+/// `$Recycle.Bin` are real; an unknown `~user` is an error.
+pub fn expand(input: &str, base: &Path) -> Result<PathBuf, String> {
+    if is_network_url(input.trim()) {
+        return Ok(PathBuf::from(input.trim()));
+    }
+    let input = expand_variables(&expand_tilde(input.trim())?);
+    let path = from_input(&input);
+
+    if is_computer(&path) || path.is_absolute() {
+        Ok(path)
+    } else {
+        Ok(base.join(path))
+    }
+}
+
+fn expand_tilde(input: &str) -> Result<String, String> {
+    let Some(rest) = input.strip_prefix('~') else {
+        return Ok(input.to_string());
+    };
+
+    let end = rest.find(['/', '\\']).unwrap_or(rest.len());
+    let (user, rest) = rest.split_at(end);
+    let home = if user.is_empty() {
+        home()
+    } else {
+        home_of(user).ok_or_else(|| trf("There is no user named {}", &[&user]))?
+    };
+
+    Ok(format!("{}{}", home.to_string_lossy(), rest))
+}
+
+fn expand_variables(input: &str) -> String {
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..start]);
+        let marker = rest.as_bytes()[start];
+        let after = &rest[start + 1..];
+
+        let (name, consumed) = match marker {
+            b'$' if after.starts_with('{') => match after.find('}') {
+                Some(end) => (&after[1..end], end + 1),
+                None => ("", 0),
+            },
+            b'$' => {
+                let end = after
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+            _ if cfg!(windows) => match after.find('%') {
+                Some(end) => (&after[..end], end + 1),
+                None => ("", 0),
+            },
+            _ => ("", 0),
+        };
+
+        // A lone `$` or `%`, as in `\\server\c$`, stays as typed.
+        match std::env::var_os(name).filter(|_| !name.is_empty()) {
+            Some(value) => {
+                expanded.push_str(&value.to_string_lossy());
+                rest = &after[consumed..];
+            }
+            None => {
+                expanded.push(marker as char);
+                rest = after;
+            }
+        }
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+/// The user's home folder, empty when the environment does not say.
+pub fn home() -> PathBuf {
+    let variable = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(variable)
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn home_of(user: &str) -> Option<PathBuf> {
+    use std::ffi::{CStr, CString, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    let user = CString::new(user).ok()?;
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut result = std::ptr::null_mut();
+
+    // SAFETY: `getpwnam_r` fills `passwd` with pointers into `buffer`, which
+    // outlives the `CStr` we copy the directory out of.
+    unsafe {
+        let mut passwd: libc::passwd = std::mem::zeroed();
+        let status = libc::getpwnam_r(
+            user.as_ptr(),
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        let dir = CStr::from_ptr(passwd.pw_dir).to_bytes();
+        Some(PathBuf::from(OsStr::from_bytes(dir)))
+    }
+}
+
+/// Profiles share a parent folder, so another user's sits next to ours.
+#[cfg(not(unix))]
+fn home_of(user: &str) -> Option<PathBuf> {
+    let profile = home().parent()?.join(user);
+    profile.is_dir().then_some(profile)
+}
+
+/// `file://` URI for `path`, as used in `text/uri-list` clipboard contents.
+/// Remote paths are URIs already.
+pub fn to_uri(path: &Path) -> String {
+    if is_network(path) {
+        return path.to_string_lossy().into_owned();
+    }
+    // Names that are not UTF-8 keep their bytes, percent-encoded.
+    #[cfg(unix)]
+    let path = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let path = format!("/{}", path.to_string_lossy().replace('\\', "/")).into_bytes();
+
+    let mut uri = String::from("file://");
+    for byte in path {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+/// The paths named in pasted or dropped `text`: a `text/uri-list` of
+/// `file://`, `sftp://` or WebDAV URIs, or absolute paths, one per line. Comments,
+/// other URIs and anything else are left out.
+pub fn from_uri_list(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(|line| {
+            line.trim()
+                .trim_matches(|quote| quote == '"' || quote == '\'')
+        })
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match from_uri(line) {
+            Some(path) => Some(path),
+            None if is_network_url(line) => Some(PathBuf::from(line)),
+            None if line.contains("://") => None,
+            None if line.starts_with('~') || Path::new(line).is_absolute() => {
+                expand(line, Path::new("/")).ok()
+            }
+            None => None,
+        })
+        .collect()
+}
+
+/// The path named by a `file://` URI, or `None` for anything else.
+pub fn from_uri(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = path
+        .strip_prefix("localhost")
+        .filter(|rest| rest.starts_with('/'))
+        .unwrap_or(path);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_vec(decoded)))
+    }
+    #[cfg(not(unix))]
+    {
+        let path = String::from_utf8_lossy(&decoded);
+        Some(PathBuf::from(
+            path.trim_start_matches('/').replace('/', "\\"),
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uris_keep_names_that_are_not_utf8() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let path = Path::new("/tmp").join(OsString::from_vec(b"caf\xe9 menu".to_vec()));
+        let uri = to_uri(&path);
+        assert_eq!(uri, "file:///tmp/caf%E9%20menu");
+        assert_eq!(from_uri(&uri), Some(path));
+    }
+}
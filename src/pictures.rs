@@ -0,0 +1,294 @@
+//! Images decoded to be shown, in the background and never larger than
+//! they are shown at: JPEGs are decoded at an eighth, a quarter or half
+//! their size when that is enough, so a 50-megapixel photo costs what its
+//! preview does. The previews of RAW files, PDFs and videos that `images`
+//! finds or renders are decoded the same way. Row thumbnails are kept in
+//! memory for the folders visited, and on disk in the cache.
+
+use std::any::TypeId;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::image::Handle;
+use iced::{subscription, Subscription};
+use image::codecs::jpeg::JpegDecoder;
+use image::{DynamicImage, ImageFormat};
+
+use crate::dirs;
+use crate::images;
+use crate::mime;
+use crate::vfs;
+
+/// The longest side of a row's thumbnail.
+pub const THUMBNAIL: u32 = 64;
+/// That of the first, quick picture in the preview.
+pub const PREVIEW: u32 = 256;
+/// That of the picture the preview sharpens into, and the viewer shows.
+pub const SCREEN: u32 = 1600;
+/// Thumbnails kept in memory, the oldest dropped first.
+const KEPT: usize = 1_000;
+
+/// Where a picture of a file comes from.
+enum Source {
+    File(PathBuf),
+    /// A JPEG held by the file, as RAW files hold one.
+    Bytes(Vec<u8>),
+}
+
+/// Whether `path` has a picture to show, decoded or rendered.
+pub fn shows(path: &Path) -> bool {
+    mime::guess(path).starts_with("image/") || images::is_raw(path) || images::renders_preview(path)
+}
+
+/// What the picture of `path` is decoded from: the file itself for an
+/// image, the JPEG a RAW file holds, or the one `images` renders of a PDF
+/// or video. `None` when there is none.
+fn source(path: &Path) -> io::Result<Option<Source>> {
+    if images::is_raw(path) {
+        return Ok(images::embedded_preview(path)?.map(Source::Bytes));
+    }
+    if images::renders_preview(path) {
+        return images::extract_preview(path).map(|preview| Some(Source::File(preview)));
+    }
+    if mime::guess(path).starts_with("image/") {
+        return Ok(Some(Source::File(path.to_path_buf())));
+    }
+    Ok(None)
+}
+
+/// The picture of `path` with its longest side at most `longest`.
+pub fn picture(path: &Path, longest: u32) -> io::Result<Handle> {
+    Ok(handle(decoded(path, longest)?, longest))
+}
+
+/// The picture of `path`, decoded at `longest` or not much more.
+fn decoded(path: &Path, longest: u32) -> io::Result<DynamicImage> {
+    let source = source(path)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "it has no picture"))?;
+    match &source {
+        Source::File(path) => {
+            let file = vfs::for_path(path).open_read(path)?;
+            decode(BufReader::new(file), longest)
+        }
+        Source::Bytes(bytes) => decode(Cursor::new(bytes), longest),
+    }
+}
+
+/// Decodes what `reader` holds, JPEGs scaled down in the decoder itself.
+fn decode(reader: impl io::BufRead + io::Seek, longest: u32) -> io::Result<DynamicImage> {
+    let reader = image::io::Reader::new(reader).with_guessed_format()?;
+    let decoded = match reader.format() {
+        Some(ImageFormat::Jpeg) => {
+            let mut decoder = JpegDecoder::new(reader.into_inner()).map_err(io::Error::other)?;
+            let side = longest.min(u16::MAX.into()) as u16;
+            decoder.scale(side, side).map_err(io::Error::other)?;
+            DynamicImage::from_decoder(decoder)
+        }
+        _ => reader.decode(),
+    };
+    decoded.map_err(io::Error::other)
+}
+
+/// `image` made to fit in `longest`, for the renderer.
+fn handle(image: DynamicImage, longest: u32) -> Handle {
+    let image = match image.width().max(image.height()) > longest {
+        true => image.thumbnail(longest, longest),
+        false => image,
+    };
+    let image = image.into_rgba8();
+    Handle::from_pixels(image.width(), image.height(), image.into_raw())
+}
+
+/// The thumbnail of `path`, from the cache on disk when it was made for
+/// the file as it is, or else made and stored there.
+fn thumbnail(path: &Path) -> io::Result<Handle> {
+    let cached = cached(path);
+    if let Some(image) = cached
+        .as_deref()
+        .and_then(|cached| image::open(cached).ok())
+    {
+        return Ok(handle(image, THUMBNAIL));
+    }
+
+    let image = decoded(path, THUMBNAIL)?.thumbnail(THUMBNAIL, THUMBNAIL);
+    if let Some(cached) = cached {
+        let stored = cached
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                image
+                    .save_with_format(&cached, ImageFormat::Png)
+                    .map_err(io::Error::other)
+            });
+        if let Err(error) = stored {
+            tracing::debug!(%error, file = %cached.display(), "cannot store the thumbnail");
+        }
+    }
+    Ok(handle(image, THUMBNAIL))
+}
+
+/// Where the thumbnail of `path` is kept, named for the file's path, size
+/// and modification time so that changing it makes another.
+fn cached(path: &Path) -> Option<PathBuf> {
+    let meta = fs::metadata(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    meta.modified().ok()?.hash(&mut hasher);
+    let name = format!("{:016x}.png", hasher.finish());
+    Some(dirs::cache_dir()?.join("thumbnails").join(name))
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// The thumbnail of a file, or `None` when it has none.
+    Made(PathBuf, Option<Handle>),
+}
+
+/// The thumbnails of the rows in view.
+#[derive(Default)]
+pub struct Thumbnails {
+    made: HashMap<PathBuf, Handle>,
+    /// What `made` holds, the oldest first.
+    order: VecDeque<PathBuf>,
+    /// Files that have none, not to try again.
+    none: HashSet<PathBuf>,
+    /// Counts forgetting, to start making again.
+    generation: usize,
+}
+
+impl Thumbnails {
+    pub fn update(&mut self, message: Message) {
+        let Message::Made(path, made) = message;
+        match made {
+            Some(handle) => {
+                if self.made.insert(path.clone(), handle).is_none() {
+                    self.order.push_back(path);
+                }
+                while self.order.len() > KEPT {
+                    if let Some(oldest) = self.order.pop_front() {
+                        self.made.remove(&oldest);
+                    }
+                }
+            }
+            None => {
+                self.none.insert(path);
+            }
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Handle> {
+        self.made.get(path)
+    }
+
+    /// Has the thumbnail of `path` made again, as after it changed.
+    pub fn forget(&mut self, path: &Path) {
+        let made = self.made.remove(path).is_some();
+        if made || self.none.remove(path) {
+            self.order.retain(|kept| kept != path);
+            self.generation += 1;
+        }
+    }
+
+    /// Drops them all, as low-memory mode wants.
+    pub fn clear(&mut self) {
+        self.made.clear();
+        self.order.clear();
+        self.none.clear();
+        self.generation += 1;
+    }
+
+    /// Makes the thumbnails of those of `files` that have pictures and
+    /// none yet, one after the other.
+    pub fn subscription(&self, files: Vec<PathBuf>) -> Subscription<Message> {
+        struct Making;
+
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|path| !self.made.contains_key(path) && !self.none.contains(path))
+            .filter(|path| shows(path) && vfs::is_local(path))
+            .collect();
+        if files.is_empty() {
+            return Subscription::none();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        files.hash(&mut hasher);
+        subscription::channel(
+            (TypeId::of::<Making>(), self.generation, hasher.finish()),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    for path in files {
+                        let made = thumbnail(&path)
+                            .map_err(|error| {
+                                tracing::debug!(%error, path = %path.display(), "no thumbnail");
+                            })
+                            .ok();
+                        if sender.unbounded_send(Message::Made(path, made)).is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::new_rgb8(width, height);
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Jpeg).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn decodes_jpegs_scaled_down() {
+        let decoded = decode(Cursor::new(jpeg(1600, 800)), 200).unwrap();
+        // An eighth is the most the decoder takes off.
+        assert_eq!((decoded.width(), decoded.height()), (200, 100));
+        let decoded = decode(Cursor::new(jpeg(1600, 800)), 500).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (800, 400));
+    }
+
+    #[test]
+    fn fits_pictures_in_the_size_asked() {
+        let size = |image, longest| match handle(image, longest).data() {
+            iced::advanced::image::Data::Rgba { width, height, .. } => (*width, *height),
+            _ => panic!("not pixels"),
+        };
+        assert_eq!(size(DynamicImage::new_rgba8(300, 100), 60), (60, 20));
+        assert_eq!(size(DynamicImage::new_rgba8(30, 10), 60), (30, 10));
+    }
+
+    #[test]
+    fn drops_the_oldest_thumbnails() {
+        let mut thumbnails = Thumbnails::default();
+        let pixels = || Some(Handle::from_pixels(1, 1, vec![0; 4]));
+        for index in 0..=KEPT {
+            thumbnails.update(Message::Made(PathBuf::from(index.to_string()), pixels()));
+        }
+        assert!(thumbnails.get(Path::new("0")).is_none());
+        assert!(thumbnails.get(Path::new("1")).is_some());
+        thumbnails.update(Message::Made(PathBuf::from("none"), None));
+        thumbnails.forget(Path::new("none"));
+        assert!(!thumbnails.none.contains(Path::new("none")));
+    }
+}
@@ -0,0 +1,288 @@
+//! Serves `org.freedesktop.impl.portal.FileChooser`, so that sandboxed apps
+//! asking xdg-desktop-portal for a file get this picker. The portal finds
+//! the backend through `/usr/share/xdg-desktop-portal/portals/iced-fm.portal`:
+//!
+//! ```ini
+//! [portal]
+//! DBusName=org.freedesktop.impl.portal.desktop.iced-fm
+//! Interfaces=org.freedesktop.impl.portal.FileChooser
+//! ```
+//!
+//! and starts it through a D-Bus service file running `filepicker --portal`,
+//! once `iced-fm` is preferred for the FileChooser in `portals.conf`.
+
+use std::fmt;
+use std::sync::Arc;
+
+use iced::Subscription;
+
+use crate::dialog::{Dialog, Outcome};
+
+/// Something the portal asked of the window.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Open(Request),
+    /// The app gave up on the request with this handle.
+    Closed(String),
+}
+
+/// One OpenFile or SaveFile call waiting for the user.
+#[derive(Clone)]
+pub struct Request {
+    pub title: String,
+    pub dialog: Dialog,
+    pub handle: String,
+    respond: Arc<dyn Fn(&Outcome) + Send + Sync>,
+}
+
+impl Request {
+    /// Answers the calling app; later answers are ignored.
+    pub fn respond(&self, outcome: &Outcome) {
+        (self.respond)(outcome)
+    }
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("title", &self.title)
+            .field("dialog", &self.dialog)
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn requests() -> Subscription<Event> {
+    use std::any::TypeId;
+    use std::thread;
+
+    use iced::futures::channel::mpsc;
+    use iced::futures::{SinkExt, StreamExt};
+    use iced::subscription;
+
+    struct Portal;
+
+    subscription::channel(TypeId::of::<Portal>(), 10, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        thread::spawn(move || {
+            if let Err(error) = service::serve(sender) {
+                tracing::warn!(%error, "cannot serve the file chooser portal");
+            }
+        });
+
+        while let Some(event) = receiver.next().await {
+            let _ = output.send(event).await;
+        }
+
+        std::future::pending().await
+    })
+}
+
+/// The portal is a Linux desktop service.
+#[cfg(not(target_os = "linux"))]
+pub fn requests() -> Subscription<Event> {
+    Subscription::none()
+}
+
+#[cfg(target_os = "linux")]
+mod service {
+    use std::ffi::OsStr;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use iced::futures::channel::mpsc;
+
+    use super::{Event, Request};
+    use crate::dbus::{self, Connection, Value};
+    use crate::dialog::{Dialog, DialogMode, FileFilter, Outcome};
+    use crate::paths;
+
+    const BUS_NAME: &str = "org.freedesktop.impl.portal.desktop.iced-fm";
+    const FILE_CHOOSER: &str = "org.freedesktop.impl.portal.FileChooser";
+    const REQUEST: &str = "org.freedesktop.impl.portal.Request";
+
+    const SUCCESS: u32 = 0;
+    const CANCELLED: u32 = 1;
+    const ENDED: u32 = 2;
+
+    /// How a call gets answered, at most once.
+    #[derive(Clone)]
+    struct Reply {
+        sender: dbus::Sender,
+        call: Arc<dbus::Message>,
+        answered: Arc<AtomicBool>,
+    }
+
+    impl Reply {
+        fn send(&self, response: u32, uris: Vec<String>) {
+            if self.answered.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            let uris = Value::Array(
+                String::from("s"),
+                uris.into_iter().map(Value::String).collect(),
+            );
+            let body = vec![Value::Uint32(response), Value::dict(vec![("uris", uris)])];
+            if let Err(error) = self.sender.reply(&self.call, body) {
+                tracing::warn!(%error, "cannot answer a file chooser request");
+            }
+        }
+
+        fn respond(&self, outcome: &Outcome) {
+            match outcome {
+                Outcome::Chosen(chosen) => {
+                    let uris = chosen
+                        .iter()
+                        .map(|item| paths::to_uri(&item.path))
+                        .collect();
+                    self.send(SUCCESS, uris)
+                }
+                Outcome::Cancelled => self.send(CANCELLED, Vec::new()),
+            }
+        }
+    }
+
+    pub fn serve(events: mpsc::UnboundedSender<Event>) -> io::Result<()> {
+        let mut connection = Connection::session()?;
+        if !connection.request_name(BUS_NAME)? {
+            return Err(io::Error::other(format!("{} is already taken", BUS_NAME)));
+        }
+        let sender = connection.sender();
+        // Requests still open, by handle, so that Close can end them.
+        let mut pending: Vec<(String, Reply)> = Vec::new();
+
+        loop {
+            let call = connection.receive()?;
+            if !call.is_call() {
+                continue;
+            }
+            pending.retain(|(_, reply)| !reply.answered.load(Ordering::SeqCst));
+
+            match (call.interface.as_deref(), call.member.as_deref()) {
+                (Some(FILE_CHOOSER), Some(member @ ("OpenFile" | "SaveFile"))) => {
+                    let Some((handle, title, dialog)) = parse(member, &call.body) else {
+                        sender.reply_error(
+                            &call,
+                            "org.freedesktop.DBus.Error.InvalidArgs",
+                            "unexpected arguments",
+                        )?;
+                        continue;
+                    };
+                    let reply = Reply {
+                        sender: sender.clone(),
+                        call: Arc::new(call),
+                        answered: Arc::default(),
+                    };
+                    pending.push((handle.clone(), reply.clone()));
+
+                    let respond = Arc::new(move |outcome: &Outcome| reply.respond(outcome));
+                    let request = Request {
+                        title,
+                        dialog,
+                        handle,
+                        respond,
+                    };
+                    if events.unbounded_send(Event::Open(request)).is_err() {
+                        return Ok(());
+                    }
+                }
+                (Some(REQUEST), Some("Close")) => {
+                    let handle = call.path.clone().unwrap_or_default();
+                    let closed = pending
+                        .iter()
+                        .find(|(known, _)| known == &handle)
+                        .map(|(_, reply)| reply.clone());
+                    if let Some(reply) = closed {
+                        reply.send(ENDED, Vec::new());
+                        let _ = events.unbounded_send(Event::Closed(handle));
+                    }
+                    sender.reply(&call, Vec::new())?;
+                }
+                _ => sender.reply_error(
+                    &call,
+                    "org.freedesktop.DBus.Error.UnknownMethod",
+                    "only OpenFile and SaveFile are served",
+                )?,
+            }
+        }
+    }
+
+    /// Both calls take `(o handle, s app_id, s parent_window, s title,
+    /// a{sv} options)`; gives the handle, the title and the dialog.
+    fn parse(member: &str, body: &[Value]) -> Option<(String, String, Dialog)> {
+        let [handle, _app_id, _parent, title, options] = body else {
+            return None;
+        };
+        let flag = |key| options.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+        let mode = match member {
+            "SaveFile" => DialogMode::SaveFile,
+            _ if flag("directory") => DialogMode::OpenDirectory,
+            _ if flag("multiple") => DialogMode::OpenFiles,
+            _ => DialogMode::OpenFile,
+        };
+        let mut dialog = Dialog::new(mode);
+
+        // The current filter goes first, since that is the one that starts
+        // active.
+        let current = options.get("current_filter").and_then(filter);
+        let mut filters: Vec<FileFilter> = options
+            .get("filters")
+            .and_then(Value::items)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(filter)
+            .filter(|filter| Some(filter) != current.as_ref())
+            .collect();
+        if let Some(current) = current {
+            filters.insert(0, current);
+        }
+        for filter in filters {
+            dialog = dialog.filter(filter);
+        }
+
+        if let Some(folder) = options.get("current_folder").and_then(Value::as_bytes) {
+            dialog = dialog.directory(Path::new(OsStr::from_bytes(&folder)));
+        }
+        if let Some(name) = options.get("current_name").and_then(Value::as_str) {
+            dialog = dialog.file_name(name);
+        }
+        if let Some(file) = options.get("current_file").and_then(Value::as_bytes) {
+            let file = Path::new(OsStr::from_bytes(&file));
+            if let Some(dir) = file.parent() {
+                dialog = dialog.directory(dir);
+            }
+            if let Some(name) = file.file_name() {
+                dialog = dialog.file_name(name.to_string_lossy());
+            }
+        }
+
+        Some((
+            handle.as_str()?.to_string(),
+            title.as_str()?.to_string(),
+            dialog,
+        ))
+    }
+
+    /// `(s name, a(us) patterns)`, where kind 0 is a glob and kind 1 a
+    /// MIME type. Only globs can be matched against names here.
+    fn filter(value: &Value) -> Option<FileFilter> {
+        let [name, patterns] = value.items()? else {
+            return None;
+        };
+        let globs: Vec<&str> = patterns
+            .items()?
+            .iter()
+            .filter_map(|pattern| match pattern.items()? {
+                [kind, glob] if kind.as_u32() == Some(0) => glob.as_str(),
+                _ => None,
+            })
+            .collect();
+
+        (!globs.is_empty()).then(|| FileFilter::new(name.as_str().unwrap_or_default(), &globs))
+    }
+}
@@ -0,0 +1,170 @@
+//! Listings read ahead of need: the folder the pointer rests on and the
+//! first few in view, so opening one shows it at once. They are read one
+//! after another in the background, given up as soon as what is in view
+//! changes, and kept a little while and only as long as the folder is
+//! unchanged, the oldest dropped past a handful.
+
+use std::any::TypeId;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+
+/// How many listings are kept.
+const KEPT: usize = 16;
+
+/// How many of the folders in view are read ahead, top first.
+pub const AHEAD: usize = 4;
+
+/// How long the pointer rests on a folder before it is read, so sweeping
+/// across the listing reads nothing.
+const HOVER_DELAY: Duration = Duration::from_millis(250);
+
+/// How long a listing read ahead is trusted: the folder's time only moves
+/// when entries come and go, not when the files in it are written.
+const FRESH_FOR: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub enum Message<T> {
+    /// The pointer came onto this folder.
+    Hover(PathBuf),
+    /// What a folder held, and its modification time before reading it.
+    Read(PathBuf, Option<SystemTime>, Vec<T>),
+}
+
+struct Kept<T> {
+    dir: PathBuf,
+    modified: Option<SystemTime>,
+    read: Instant,
+    entries: Vec<T>,
+}
+
+pub struct Prefetch<T> {
+    /// Latest last.
+    kept: VecDeque<Kept<T>>,
+    hovered: Option<PathBuf>,
+}
+
+impl<T> Default for Prefetch<T> {
+    fn default() -> Self {
+        Prefetch {
+            kept: VecDeque::new(),
+            hovered: None,
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Prefetch<T> {
+    pub fn update(&mut self, message: Message<T>) {
+        match message {
+            Message::Hover(dir) => self.hovered = Some(dir),
+            Message::Read(dir, modified, entries) => {
+                self.kept.retain(|kept| kept.dir != dir);
+                if self.kept.len() == KEPT {
+                    self.kept.pop_front();
+                }
+                self.kept.push_back(Kept {
+                    dir,
+                    modified,
+                    read: Instant::now(),
+                    entries,
+                });
+            }
+        }
+    }
+
+    /// What `dir` held when read ahead, handed out once, unless it changed
+    /// since or was read too long ago.
+    pub fn take(&mut self, dir: &Path) -> Option<Vec<T>> {
+        let index = self.kept.iter().position(|kept| kept.dir == dir)?;
+        let kept = self.kept.remove(index)?;
+        let modified = fs::metadata(dir).and_then(|meta| meta.modified()).ok();
+        (kept.read.elapsed() < FRESH_FOR && kept.modified.is_some() && kept.modified == modified)
+            .then_some(kept.entries)
+    }
+
+    pub fn clear(&mut self) {
+        self.kept.clear();
+        self.hovered = None;
+    }
+
+    /// Reads the hovered folder, if it is among `in_view`, then the first
+    /// of those, with `list`; `None` from it for a folder that cannot be
+    /// read.
+    pub fn subscription(
+        &self,
+        in_view: &[PathBuf],
+        list: impl Fn(&Path) -> Option<Vec<T>> + Send + Sync + 'static,
+    ) -> Subscription<Message<T>> {
+        struct Reading;
+
+        let is_kept = |dir: &PathBuf| {
+            self.kept
+                .iter()
+                .any(|kept| &kept.dir == dir && kept.read.elapsed() < FRESH_FOR)
+        };
+        let hovered = self.hovered.as_ref().filter(|dir| in_view.contains(dir));
+        let candidates: Vec<&PathBuf> = hovered
+            .into_iter()
+            .chain(in_view.iter().take(AHEAD))
+            .collect();
+        // Told apart by what could be read rather than what is left to, so
+        // finding one does not start over on the rest.
+        let mut hasher = DefaultHasher::new();
+        candidates.hash(&mut hasher);
+        let mut wanted: Vec<PathBuf> = Vec::new();
+        for dir in candidates {
+            if !is_kept(dir) && !wanted.contains(dir) {
+                wanted.push(dir.clone());
+            }
+        }
+        if wanted.is_empty() {
+            return Subscription::none();
+        }
+
+        let delay = hovered.is_some_and(|dir| !is_kept(dir));
+        let list = Arc::new(list);
+        subscription::channel(
+            (TypeId::of::<Reading>(), hasher.finish()),
+            10,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    if delay {
+                        std::thread::sleep(HOVER_DELAY);
+                    }
+                    for dir in wanted {
+                        // Nobody waits for these any more.
+                        if sender.is_closed() {
+                            return;
+                        }
+                        let modified = fs::metadata(&dir).and_then(|meta| meta.modified()).ok();
+                        let Some(entries) = list(&dir) else {
+                            continue;
+                        };
+                        tracing::debug!(dir = %dir.display(), entries = entries.len(), "read ahead");
+                        if sender
+                            .unbounded_send(Message::Read(dir, modified, entries))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+}
@@ -0,0 +1,380 @@
+//! Questions and errors put to the user in a modal over the picker, as iced
+//! has no native message boxes. Each button carries a typed answer, handed
+//! back by `Prompts::update` once it is pressed, with what was typed into
+//! the prompt's field or ticked in its box worked into it.
+
+use std::collections::VecDeque;
+
+use iced::keyboard::{key::Named, Modifiers};
+use iced::widget::{button, checkbox, column, container, row, text, text_input, Space};
+use iced::{theme, Element, Length};
+
+use crate::i18n::tr;
+use crate::style::{Focused, Style};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Question,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Normal,
+    /// The expected answer.
+    Primary,
+    /// One that loses data, such as deleting or overwriting.
+    Destructive,
+}
+
+#[derive(Debug, Clone)]
+pub struct Prompt<T> {
+    tone: Tone,
+    title: String,
+    body: String,
+    buttons: Vec<(String, Role, T)>,
+    dismiss: Option<T>,
+    input: Option<Input<T>>,
+    check: Option<Check<T>>,
+}
+
+/// A line to type into above the buttons.
+#[derive(Debug, Clone)]
+struct Input<T> {
+    placeholder: String,
+    value: String,
+    /// Puts the text into the answer pressed.
+    with: fn(T, String) -> T,
+}
+
+/// A box to tick above the buttons.
+#[derive(Debug, Clone)]
+struct Check<T> {
+    label: String,
+    checked: bool,
+    /// Puts whether it is ticked into the answer pressed.
+    with: fn(T, bool) -> T,
+}
+
+impl<T: Clone> Prompt<T> {
+    pub fn new(tone: Tone, title: impl Into<String>, body: impl Into<String>) -> Prompt<T> {
+        Prompt {
+            tone,
+            title: title.into(),
+            body: body.into(),
+            buttons: Vec::new(),
+            dismiss: None,
+            input: None,
+            check: None,
+        }
+    }
+
+    /// A report with a single button.
+    pub fn error(title: impl Into<String>, body: impl ToString, ok: T) -> Prompt<T> {
+        Prompt::new(Tone::Error, title, body.to_string())
+            .button(tr("OK"), Role::Primary, ok.clone())
+            .dismiss(ok)
+    }
+
+    /// Buttons are laid out in the order added, at the right.
+    pub fn button(mut self, label: impl Into<String>, role: Role, answer: T) -> Prompt<T> {
+        self.buttons
+            .push((tr(&label.into()).to_string(), role, answer));
+        self
+    }
+
+    /// The answer when the backdrop is clicked; without one, only the
+    /// buttons close the prompt.
+    pub fn dismiss(mut self, answer: T) -> Prompt<T> {
+        self.dismiss = Some(answer);
+        self
+    }
+
+    /// A field starting out as `value`, its text handed to `with` along
+    /// with the answer.
+    pub fn input(
+        mut self,
+        placeholder: impl Into<String>,
+        value: impl Into<String>,
+        with: fn(T, String) -> T,
+    ) -> Prompt<T> {
+        self.input = Some(Input {
+            placeholder: placeholder.into(),
+            value: value.into(),
+            with,
+        });
+        self
+    }
+
+    /// A box starting out as `checked`, handed to `with` along with the
+    /// answer.
+    pub fn check(
+        mut self,
+        label: impl Into<String>,
+        checked: bool,
+        with: fn(T, bool) -> T,
+    ) -> Prompt<T> {
+        self.check = Some(Check {
+            label: label.into(),
+            checked,
+            with,
+        });
+        self
+    }
+
+    /// The answer with the field and the box worked in.
+    fn complete(self, answer: T) -> T {
+        let answer = match self.input {
+            Some(input) => (input.with)(answer, input.value),
+            None => answer,
+        };
+        match self.check {
+            Some(check) => (check.with)(answer, check.checked),
+            None => answer,
+        }
+    }
+
+    /// Where the keyboard starts: on the expected answer, or else the last.
+    fn default_focus(&self) -> usize {
+        self.buttons
+            .iter()
+            .position(|(_, role, _)| *role == Role::Primary)
+            .unwrap_or(self.buttons.len().saturating_sub(1))
+    }
+
+    fn view(&self, id: usize, focus: usize, style: &Style) -> Element<'_, Message<T>> {
+        let title = text(&self.title).size(20);
+        let title = match self.tone {
+            Tone::Question => title,
+            Tone::Warning => title.style(theme::Text::Color(style.warning)),
+            Tone::Error => title.style(theme::Text::Color(style.error)),
+        };
+
+        let mut buttons = row!(Space::with_width(Length::Fill)).spacing(5);
+        for (index, (label, role, answer)) in self.buttons.iter().enumerate() {
+            let look = match role {
+                Role::Normal => theme::Button::Secondary,
+                Role::Primary => theme::Button::Primary,
+                Role::Destructive => theme::Button::Destructive,
+            };
+            let look = match index == focus {
+                true => theme::Button::custom(Focused(look)),
+                false => look,
+            };
+            buttons = buttons.push(
+                button(text(label))
+                    .style(look)
+                    .on_press(Message::Answer(id, answer.clone())),
+            );
+        }
+
+        let mut col = column!(title).spacing(10);
+        if !self.body.is_empty() {
+            col = col.push(text(&self.body));
+        }
+        if let Some(input) = &self.input {
+            col = col.push(
+                text_input(&input.placeholder, &input.value)
+                    .on_input(move |value| Message::Input(id, value)),
+            );
+        }
+        if let Some(check) = &self.check {
+            col = col.push(
+                checkbox(&check.label, check.checked)
+                    .on_toggle(move |checked| Message::Check(id, checked)),
+            );
+        }
+
+        container(col.push(buttons))
+            .width(480)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message<T> {
+    /// The prompt with this id was answered.
+    Answer(usize, T),
+    /// The keyboard moved this many buttons on.
+    Focus(isize),
+    /// The field of the prompt with this id was typed into.
+    Input(usize, String),
+    /// The box of the prompt with this id was ticked or cleared.
+    Check(usize, bool),
+}
+
+/// Prompts waiting to be answered, shown one at a time in the order pushed.
+pub struct Prompts<T> {
+    queue: VecDeque<(usize, Prompt<T>)>,
+    next_id: usize,
+    /// The button of the prompt on show that Enter presses.
+    focus: usize,
+}
+
+impl<T> Default for Prompts<T> {
+    fn default() -> Prompts<T> {
+        Prompts {
+            queue: VecDeque::new(),
+            next_id: 0,
+            focus: 0,
+        }
+    }
+}
+
+impl<T: Clone> Prompts<T> {
+    pub fn push(&mut self, prompt: Prompt<T>) {
+        if self.queue.is_empty() {
+            self.focus = prompt.default_focus();
+        }
+        self.queue.push_back((self.next_id, prompt));
+        self.next_id += 1;
+    }
+
+    /// Closes the prompt answered and gives its answer; `None` when it was
+    /// already gone, as with a second click.
+    pub fn update(&mut self, message: Message<T>) -> Option<T> {
+        match message {
+            Message::Answer(id, answer) => {
+                let index = self.queue.iter().position(|(known, _)| *known == id)?;
+                let (_, prompt) = self.queue.remove(index)?;
+                if index == 0 {
+                    self.focus = self
+                        .queue
+                        .front()
+                        .map_or(0, |(_, next)| next.default_focus());
+                }
+                Some(prompt.complete(answer))
+            }
+            Message::Input(id, value) => {
+                if let Some(input) = self.get_mut(id).and_then(|prompt| prompt.input.as_mut()) {
+                    input.value = value;
+                }
+                None
+            }
+            Message::Check(id, checked) => {
+                if let Some(check) = self.get_mut(id).and_then(|prompt| prompt.check.as_mut()) {
+                    check.checked = checked;
+                }
+                None
+            }
+            Message::Focus(step) => {
+                let count = self.queue.front()?.1.buttons.len() as isize;
+                if count > 0 {
+                    self.focus = (self.focus as isize + step).rem_euclid(count) as usize;
+                }
+                None
+            }
+        }
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut Prompt<T>> {
+        self.queue
+            .iter_mut()
+            .find(|(known, _)| *known == id)
+            .map(|(_, prompt)| prompt)
+    }
+
+    pub fn view(&self, style: &Style) -> Option<Element<'_, Message<T>>> {
+        let (id, prompt) = self.queue.front()?;
+        Some(prompt.view(*id, self.focus, style))
+    }
+
+    /// What a key does to the prompt on show: the arrows and Tab move
+    /// between its buttons, and Enter or Space press the one focused.
+    pub fn on_key(&self, key: Named, modifiers: Modifiers) -> Option<Message<T>> {
+        let (id, prompt) = self.queue.front()?;
+        match key {
+            Named::ArrowLeft => Some(Message::Focus(-1)),
+            Named::Tab if modifiers.shift() => Some(Message::Focus(-1)),
+            Named::ArrowRight | Named::Tab => Some(Message::Focus(1)),
+            Named::Enter | Named::Space => {
+                let (_, _, answer) = prompt.buttons.get(self.focus)?;
+                Some(Message::Answer(*id, answer.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// What clicking outside the prompt on show answers, if anything.
+    pub fn on_blur(&self) -> Option<Message<T>> {
+        let (id, prompt) = self.queue.front()?;
+        let answer = prompt.dismiss.clone()?;
+        Some(Message::Answer(*id, answer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Answer {
+        Rename(String, bool),
+        Cancel,
+    }
+
+    fn typed(answer: Answer, name: String) -> Answer {
+        match answer {
+            Answer::Rename(_, all) => Answer::Rename(name, all),
+            answer => answer,
+        }
+    }
+
+    fn ticked(answer: Answer, all: bool) -> Answer {
+        match answer {
+            Answer::Rename(name, _) => Answer::Rename(name, all),
+            answer => answer,
+        }
+    }
+
+    #[test]
+    fn works_the_field_and_the_box_into_the_answer() {
+        let mut prompts = Prompts::default();
+        prompts.push(
+            Prompt::new(Tone::Question, "Taken", "")
+                .input("New name", "a.txt", typed)
+                .check("Apply to all", false, ticked)
+                .button(
+                    "Rename",
+                    Role::Primary,
+                    Answer::Rename(String::new(), false),
+                )
+                .button("Cancel", Role::Normal, Answer::Cancel),
+        );
+        assert_eq!(
+            prompts.update(Message::Input(0, String::from("b.txt"))),
+            None
+        );
+        assert_eq!(prompts.update(Message::Check(0, true)), None);
+        assert_eq!(
+            prompts.update(Message::Answer(0, Answer::Rename(String::new(), false))),
+            Some(Answer::Rename(String::from("b.txt"), true))
+        );
+        assert!(!prompts.is_open());
+    }
+
+    #[test]
+    fn answers_once_and_moves_on_to_the_next() {
+        let mut prompts = Prompts::default();
+        prompts.push(Prompt::error("First", "", Answer::Cancel));
+        prompts.push(Prompt::error("Second", "", Answer::Cancel));
+        assert_eq!(
+            prompts.update(Message::Answer(0, Answer::Cancel)),
+            Some(Answer::Cancel)
+        );
+        assert_eq!(prompts.update(Message::Answer(0, Answer::Cancel)), None);
+        assert!(prompts.is_open());
+        assert_eq!(
+            prompts.update(Message::Answer(1, Answer::Cancel)),
+            Some(Answer::Cancel)
+        );
+        assert!(!prompts.is_open());
+    }
+}
@@ -0,0 +1,591 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use iced::widget::{button, checkbox, column, container, row, text};
+use iced::{theme, Alignment, Element, Length};
+
+use crate::binary::{self, Binary};
+use crate::dates::format_time;
+use crate::encoding::{self, Detected};
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::line_endings::{self, LineEndings};
+use crate::media::Media;
+use crate::mime;
+use crate::streams::{self, Stream};
+use crate::style::Style;
+use crate::tags;
+
+const MAX_LISTED_FAILURES: usize = 5;
+const MAX_LISTED_LIBRARIES: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    CalculateSize,
+    SizeCalculated(Result<(u64, u64), String>),
+    ToggleMode(u32, bool),
+    Recursive(bool),
+    ApplyMode,
+    ModeApplied(Vec<(PathBuf, String)>),
+    /// What the file says of itself, read after the dialog opens.
+    MediaRead(PathBuf, Option<Box<Media>>),
+    Checksums,
+    Close,
+}
+
+pub enum Event {
+    /// The recursive size of this directory was requested; the result comes
+    /// back as `Message::SizeCalculated`, unless the flag is set first.
+    CalculateSize(PathBuf, Arc<AtomicBool>),
+    /// Permission bits to set on `path`, and on everything below it when
+    /// `recursive`; the failures come back as `Message::ModeApplied`.
+    ApplyMode {
+        path: PathBuf,
+        mode: u32,
+        recursive: bool,
+    },
+    /// Show the checksums of `path` instead.
+    Checksums(PathBuf),
+    Closed,
+}
+
+enum Size {
+    File(u64),
+    Unknown,
+    Calculating,
+    Calculated { bytes: u64, entries: u64 },
+    Failed(String),
+}
+
+pub struct Properties {
+    path: PathBuf,
+    metadata: fs::Metadata,
+    link_target: Option<PathBuf>,
+    mime: &'static str,
+    /// For text files.
+    encoding: Option<Detected>,
+    line_endings: Option<LineEndings>,
+    /// For executables and libraries.
+    binary: Option<Binary>,
+    /// For photos and recordings.
+    media: Option<Box<Media>>,
+    /// Finder tags (macOS).
+    tags: Vec<String>,
+    /// Alternate data streams (Windows) or extended attributes (macOS).
+    streams: Vec<Stream>,
+    size: Size,
+    /// Permission bits as edited in the dialog; `None` where the platform
+    /// has no Unix modes.
+    mode: Option<u32>,
+    recursive: bool,
+    applying: bool,
+    failures: Vec<(PathBuf, String)>,
+    /// Set once the dialog is closed, to stop measuring.
+    stop: Arc<AtomicBool>,
+}
+
+impl Properties {
+    /// `metadata` is what the listing already gathered for this entry, if
+    /// anything; it is only looked up again when missing.
+    pub fn open(path: PathBuf, metadata: Option<fs::Metadata>) -> Result<Properties, String> {
+        let metadata = match metadata {
+            Some(metadata) => metadata,
+            None => fs::metadata(&path)
+                .or_else(|_| fs::symlink_metadata(&path))
+                .map_err(|error| error.to_string())?,
+        };
+
+        let (encoding, line_endings) = match metadata.is_file() {
+            true => text_format(&path),
+            false => (None, None),
+        };
+
+        Ok(Properties {
+            link_target: fs::read_link(&path).ok(),
+            mime: mime::guess(&path),
+            encoding,
+            line_endings,
+            binary: metadata.is_file().then(|| binary::inspect(&path)).flatten(),
+            media: None,
+            tags: tags::finder(&path),
+            streams: streams::streams(&path),
+            size: if metadata.is_dir() {
+                Size::Unknown
+            } else {
+                Size::File(metadata.len())
+            },
+            mode: unix_mode(&metadata),
+            recursive: false,
+            applying: false,
+            failures: Vec::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            path,
+            metadata,
+        })
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.metadata.is_file()
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::CalculateSize => {
+                self.size = Size::Calculating;
+                Some(Event::CalculateSize(self.path.clone(), self.stop.clone()))
+            }
+            Message::SizeCalculated(result) => {
+                self.size = match result {
+                    Ok((bytes, entries)) => Size::Calculated { bytes, entries },
+                    Err(error) => Size::Failed(error),
+                };
+                None
+            }
+            Message::ToggleMode(bit, set) => {
+                if let Some(mode) = &mut self.mode {
+                    if set {
+                        *mode |= bit;
+                    } else {
+                        *mode &= !bit;
+                    }
+                }
+                None
+            }
+            Message::Recursive(recursive) => {
+                self.recursive = recursive;
+                None
+            }
+            Message::ApplyMode => {
+                let mode = self.mode?;
+                self.applying = true;
+                self.failures.clear();
+                Some(Event::ApplyMode {
+                    path: self.path.clone(),
+                    mode,
+                    recursive: self.recursive,
+                })
+            }
+            Message::ModeApplied(failures) => {
+                self.applying = false;
+                self.failures = failures;
+                if let Ok(metadata) = fs::metadata(&self.path) {
+                    self.mode = unix_mode(&metadata);
+                    self.metadata = metadata;
+                }
+                None
+            }
+            Message::MediaRead(path, media) => {
+                if path == self.path {
+                    self.media = media;
+                }
+                None
+            }
+            Message::Checksums => Some(Event::Checksums(self.path.clone())),
+            Message::Close => Some(Event::Closed),
+        }
+    }
+
+    fn permissions(&self) -> String {
+        match self.mode {
+            Some(mode) => format!("{} ({:04o})", mode_string(mode), mode),
+            None if self.metadata.permissions().readonly() => String::from("Read-only"),
+            None => String::from("Read and write"),
+        }
+    }
+
+    fn mode_editor(&self, mode: u32, style: &Style) -> Element<'_, Message> {
+        let bit = |label, bit| {
+            checkbox(label, mode & bit != 0).on_toggle(move |set| Message::ToggleMode(bit, set))
+        };
+        let class = |label, read, write, execute| {
+            row!(
+                text(label).width(Length::Fixed(120.)),
+                bit("Read", read),
+                bit("Write", write),
+                bit("Execute", execute),
+            )
+            .spacing(10)
+        };
+
+        let mut col = column!(
+            class("Owner", 0o400, 0o200, 0o100),
+            class("Group", 0o040, 0o020, 0o010),
+            class("Others", 0o004, 0o002, 0o001),
+            row!(
+                text(tr("Special")).width(Length::Fixed(120.)),
+                bit("Set user ID", 0o4000),
+                bit("Set group ID", 0o2000),
+                bit("Sticky", 0o1000),
+            )
+            .spacing(10),
+        )
+        .spacing(5);
+
+        if self.metadata.is_dir() {
+            col = col.push(
+                checkbox(tr("Apply to everything inside"), self.recursive)
+                    .on_toggle(Message::Recursive),
+            );
+        }
+
+        let changed = unix_mode(&self.metadata) != Some(mode) || self.recursive;
+        col = col.push(
+            row!(
+                button(tr("Apply permissions"))
+                    .on_press_maybe((changed && !self.applying).then_some(Message::ApplyMode)),
+                text(if self.applying { "Applying…" } else { "" }),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        let error = |message: String| {
+            text(message)
+                .size(style.small_text_size)
+                .style(theme::Text::Color(style.error))
+        };
+        for (path, failure) in self.failures.iter().take(MAX_LISTED_FAILURES) {
+            col = col.push(error(format!("{}: {}", path.display(), failure)));
+        }
+        if self.failures.len() > MAX_LISTED_FAILURES {
+            col = col.push(error(format!(
+                "…and {} more",
+                self.failures.len() - MAX_LISTED_FAILURES
+            )));
+        }
+
+        col.into()
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let name = self
+            .path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy();
+
+        let size: Element<Message> = match &self.size {
+            Size::File(bytes) => text(format_size(*bytes)).into(),
+            Size::Unknown => button(tr("Calculate"))
+                .on_press(Message::CalculateSize)
+                .into(),
+            Size::Calculating => text(tr("Calculating…")).into(),
+            Size::Calculated { bytes, entries } => {
+                text(trf("{} in {} items", &[&format_size(*bytes), &entries])).into()
+            }
+            Size::Failed(error) => text(trf("Unknown ({})", &[&error])).into(),
+        };
+
+        let mut col = column!(
+            text(name.to_string()).size(20),
+            field("Location", text(self.path.display().to_string())),
+        )
+        .spacing(8);
+
+        if let Some(target) = &self.link_target {
+            col = col.push(field("Link to", text(target.display().to_string())));
+        }
+
+        col = col.push(field("Type", text(self.mime)));
+        if let Some(encoding) = self.encoding {
+            col = col.push(field("Encoding", text(encoding)));
+        }
+        if let Some(line_endings) = self.line_endings {
+            col = col.push(field("Line endings", text(line_endings)));
+        }
+        if let Some(binary) = &self.binary {
+            col = col.push(field(
+                "Format",
+                text(format!(
+                    "{}, {}",
+                    binary.format,
+                    binary.architectures.join(", ")
+                )),
+            ));
+            let symbols = if binary.stripped {
+                "Stripped"
+            } else {
+                "Not stripped"
+            };
+            col = col.push(field("Symbols", text(symbols)));
+            if !binary.libraries.is_empty() {
+                let mut libraries = binary
+                    .libraries
+                    .iter()
+                    .take(MAX_LISTED_LIBRARIES)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if binary.libraries.len() > MAX_LISTED_LIBRARIES {
+                    libraries += &format!(
+                        "\nand {} more",
+                        binary.libraries.len() - MAX_LISTED_LIBRARIES
+                    );
+                }
+                col = col.push(field("Libraries", text(libraries)));
+            }
+        }
+        for (label, value) in self.media.iter().flat_map(|media| media.fields()) {
+            col = col.push(field(label, text(value)));
+        }
+        if !self.tags.is_empty() {
+            col = col.push(field("Tags", text(self.tags.join(", "))));
+        }
+        if !self.streams.is_empty() {
+            let streams: Vec<String> = self
+                .streams
+                .iter()
+                .map(|stream| format!("{} ({})", stream.name, format_size(stream.size)))
+                .collect();
+            col = col.push(field("Streams", text(streams.join(", "))));
+        }
+        col = col
+            .push(field("Size", size))
+            .push(field("Created", text(format_time(self.metadata.created()))))
+            .push(field(
+                "Modified",
+                text(format_time(self.metadata.modified())),
+            ))
+            .push(field(
+                "Accessed",
+                text(format_time(self.metadata.accessed())),
+            ))
+            .push(field("Owner", text(owner(&self.metadata))))
+            .push(field("Permissions", text(self.permissions())));
+
+        if let Some(mode) = self.mode {
+            col = col.push(self.mode_editor(mode, style));
+        }
+
+        let mut buttons = row!().spacing(10);
+        if self.metadata.is_file() {
+            buttons = buttons.push(button(tr("Checksums")).on_press(Message::Checksums));
+        }
+        col = col.push(buttons.push(button(tr("Close")).on_press(Message::Close)));
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(600)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+fn field<'a>(label: &'a str, value: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    row!(text(label).width(Length::Fixed(120.)), value.into())
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+}
+
+/// The encoding and line breaks of a text file, from its start.
+fn text_format(path: &Path) -> (Option<Detected>, Option<LineEndings>) {
+    let Ok((bytes, complete)) = encoding::sample(path) else {
+        return (None, None);
+    };
+    let detected = encoding::detect(&bytes, complete);
+    let line_endings =
+        detected.and_then(|detected| line_endings::detect(&bytes, detected.encoding, complete));
+
+    (detected, line_endings)
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format_bytes(bytes)
+    } else {
+        format!("{} ({} bytes)", format_bytes(bytes), bytes)
+    }
+}
+
+/// Total size and number of entries below `path`, not following symlinks,
+/// giving up when `stop` is set.
+pub fn measure(path: &Path, stop: &AtomicBool) -> Result<(u64, u64), String> {
+    fn walk(path: &Path, bytes: &mut u64, entries: &mut u64, stop: &AtomicBool) -> io::Result<()> {
+        if stop.load(Ordering::Relaxed) {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let meta = entry.metadata()?;
+            *entries += 1;
+            if meta.is_dir() {
+                walk(&entry.path(), bytes, entries, stop)?;
+            } else {
+                *bytes += meta.len();
+            }
+        }
+        Ok(())
+    }
+
+    let (mut bytes, mut entries) = (0, 0);
+    walk(path, &mut bytes, &mut entries, stop).map_err(|error| match error.kind() {
+        io::ErrorKind::Interrupted => tr("Cancelled").to_string(),
+        _ => error.to_string(),
+    })?;
+
+    Ok((bytes, entries))
+}
+
+#[cfg(unix)]
+fn owner(metadata: &fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    let user = user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string());
+    let group = group_name(metadata.gid()).unwrap_or_else(|| metadata.gid().to_string());
+
+    format!("{} / {}", user, group)
+}
+
+#[cfg(not(unix))]
+fn owner(_metadata: &fs::Metadata) -> String {
+    String::from("Unknown")
+}
+
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut result = std::ptr::null_mut();
+
+    // SAFETY: `getpwuid_r` fills `passwd` with pointers into `buffer`, which
+    // outlives the `CStr` we copy the name out of.
+    unsafe {
+        let mut passwd: libc::passwd = std::mem::zeroed();
+        let status = libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        Some(
+            std::ffi::CStr::from_ptr(passwd.pw_name)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+#[cfg(unix)]
+pub fn group_name(gid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut result = std::ptr::null_mut();
+
+    // SAFETY: as in `user_name`, the strings point into `buffer`.
+    unsafe {
+        let mut group: libc::group = std::mem::zeroed();
+        let status = libc::getgrgid_r(
+            gid,
+            &mut group,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        Some(
+            std::ffi::CStr::from_ptr(group.gr_name)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Sets `mode` on `path` and, when `recursive`, on everything below it.
+/// Symlinks are left alone since chmod would change their targets. Returns
+/// every path that could not be changed.
+#[cfg(unix)]
+pub fn chmod(path: &Path, mode: u32, recursive: bool) -> Vec<(PathBuf, String)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fn apply(path: &Path, mode: u32, recursive: bool, failures: &mut Vec<(PathBuf, String)>) {
+        if let Err(error) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
+            failures.push((path.to_path_buf(), error.to_string()));
+        }
+        if !recursive || !path.is_dir() {
+            return;
+        }
+
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                failures.push((path.to_path_buf(), error.to_string()));
+                return;
+            }
+        };
+        for entry in entries {
+            match entry {
+                Ok(entry) if entry.file_type().is_ok_and(|kind| kind.is_symlink()) => {}
+                Ok(entry) => apply(&entry.path(), mode, recursive, failures),
+                Err(error) => failures.push((path.to_path_buf(), error.to_string())),
+            }
+        }
+    }
+
+    let mut failures = Vec::new();
+    apply(path, mode, recursive, &mut failures);
+    tracing::info!(path = %path.display(), mode = format!("{:04o}", mode), recursive, failures = failures.len(), "changed permissions");
+
+    failures
+}
+
+#[cfg(not(unix))]
+pub fn chmod(path: &Path, _mode: u32, _recursive: bool) -> Vec<(PathBuf, String)> {
+    vec![(
+        path.to_path_buf(),
+        String::from("not supported on this platform"),
+    )]
+}
+
+/// `ls -l` style rendering of the permission bits, e.g. `rwxr-sr-t`.
+pub fn mode_string(mode: u32) -> String {
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    let special = |exec: u32, special: u32, set: char| match (mode & exec != 0, mode & special != 0)
+    {
+        (true, true) => set,
+        (false, true) => set.to_ascii_uppercase(),
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+
+    [
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        special(0o100, 0o4000, 's'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        special(0o010, 0o2000, 's'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        special(0o001, 0o1000, 't'),
+    ]
+    .into_iter()
+    .collect()
+}
+
+impl Drop for Properties {
+    /// Closing the dialog stops measuring the folder.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,178 @@
+//! The folders visited, for the recent places in the sidebar and for
+//! jumping to those most used: ranked by how often and how lately each was
+//! opened, as zoxide does, and kept in `recent.toml` in the data folder.
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+use toml_edit::{value, ArrayOfTables, Document, Item, Table};
+
+use crate::dirs;
+use crate::safe_save;
+
+const FILE: &str = "recent.toml";
+
+/// What the visits may add up to before they are all thinned, so that old
+/// habits fade and the folders no longer visited drop out.
+const MAX_TOTAL: f64 = 1000.;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+struct Visit {
+    path: PathBuf,
+    count: f64,
+    last: SystemTime,
+}
+
+impl Visit {
+    /// How often, weighed by how lately.
+    fn frecency(&self, now: SystemTime) -> f64 {
+        let age = now.duration_since(self.last).unwrap_or_default();
+        let weight = if age < HOUR {
+            4.
+        } else if age < 24 * HOUR {
+            2.
+        } else if age < 7 * 24 * HOUR {
+            0.5
+        } else {
+            0.25
+        };
+        self.count * weight
+    }
+}
+
+#[derive(Default)]
+pub struct Recent {
+    visits: Vec<Visit>,
+}
+
+impl Recent {
+    /// What the file holds, or nothing if it cannot be read.
+    pub fn load() -> Recent {
+        let Some(file) = file() else {
+            return Recent::default();
+        };
+        let document = match fs::read_to_string(&file) {
+            Ok(text) => text.parse::<Document>().map_err(io::Error::other),
+            Err(error) => Err(error),
+        };
+        let document = match document {
+            Ok(document) => document,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    tracing::warn!(%error, file = %file.display(), "cannot read the recent folders");
+                }
+                return Recent::default();
+            }
+        };
+
+        let visits = document
+            .get("visit")
+            .and_then(Item::as_array_of_tables)
+            .map(|visits| {
+                visits
+                    .iter()
+                    .filter_map(|visit| {
+                        let count = visit.get("count")?;
+                        Some(Visit {
+                            path: PathBuf::from(visit.get("path")?.as_str()?),
+                            count: count
+                                .as_float()
+                                .or_else(|| count.as_integer().map(|count| count as f64))?,
+                            last: SystemTime::UNIX_EPOCH
+                                + Duration::from_secs(
+                                    visit.get("last")?.as_integer()?.try_into().ok()?,
+                                ),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Recent { visits }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let file =
+            file().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        let mut visits = ArrayOfTables::new();
+        for visit in &self.visits {
+            let mut entry = Table::new();
+            entry["path"] = value(visit.path.to_string_lossy().as_ref());
+            entry["count"] = value(visit.count);
+            let last = visit
+                .last
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            entry["last"] = value(i64::try_from(last).unwrap_or(i64::MAX));
+            visits.push(entry);
+        }
+        let mut document = Document::new();
+        document["visit"] = Item::ArrayOfTables(visits);
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        safe_save::write(&file, document.to_string().as_bytes())
+    }
+
+    pub fn visit(&mut self, dir: &Path) {
+        let now = SystemTime::now();
+        match self.visits.iter_mut().find(|visit| visit.path == dir) {
+            Some(visit) => {
+                visit.count += 1.;
+                visit.last = now;
+            }
+            None => self.visits.push(Visit {
+                path: dir.to_path_buf(),
+                count: 1.,
+                last: now,
+            }),
+        }
+
+        if self.visits.iter().map(|visit| visit.count).sum::<f64>() > MAX_TOTAL {
+            for visit in &mut self.visits {
+                visit.count *= 0.9;
+            }
+            self.visits
+                .retain(|visit| visit.count >= 1. || visit.last == now);
+        }
+    }
+
+    /// Drops `dir`, as it could not be opened.
+    pub fn forget(&mut self, dir: &Path) {
+        self.visits.retain(|visit| visit.path != dir);
+    }
+
+    /// The `count` folders visited last, latest first.
+    pub fn latest(&self, count: usize) -> Vec<&Path> {
+        let mut visits: Vec<&Visit> = self.visits.iter().collect();
+        visits.sort_by_key(|visit| Reverse(visit.last));
+        visits
+            .into_iter()
+            .take(count)
+            .map(|visit| visit.path.as_path())
+            .collect()
+    }
+
+    /// Every folder visited, the most used first.
+    pub fn ranked(&self) -> Vec<&Path> {
+        let now = SystemTime::now();
+        let mut visits: Vec<(f64, &Visit)> = self
+            .visits
+            .iter()
+            .map(|visit| (visit.frecency(now), visit))
+            .collect();
+        visits.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        visits
+            .into_iter()
+            .map(|(_, visit)| visit.path.as_path())
+            .collect()
+    }
+}
+
+fn file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(FILE))
+}
@@ -0,0 +1,172 @@
+//! "Convert to UTF-8…": the encodings the selected files are in, and what
+//! becomes of their byte order marks. Files already as asked for are left
+//! alone; the rest are rewritten by a job.
+
+use std::any::TypeId;
+use std::path::PathBuf;
+
+use iced::widget::{button, column, container, pick_list, row, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::encoding::{self, ByteOrderMark, Detected};
+use crate::i18n::{tr, trf};
+use crate::style::Style;
+
+/// Files named in the dialog; the rest are counted.
+const MAX_LISTED: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Detected(Vec<Option<Detected>>),
+    Bom(ByteOrderMark),
+    Convert,
+    Close,
+}
+
+pub enum Event {
+    Convert(Vec<PathBuf>, ByteOrderMark),
+    Closed,
+}
+
+pub struct Recode {
+    sources: Vec<PathBuf>,
+    /// Each source's encoding, `None` for what is not text, once read.
+    detected: Option<Vec<Option<Detected>>>,
+    bom: ByteOrderMark,
+}
+
+impl Recode {
+    pub fn open(sources: Vec<PathBuf>) -> Recode {
+        Recode {
+            sources,
+            detected: None,
+            bom: ByteOrderMark::default(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Detected(detected) => self.detected = Some(detected),
+            Message::Bom(bom) => self.bom = bom,
+            Message::Convert => {
+                let sources = self.to_convert();
+                if !sources.is_empty() {
+                    return Some(Event::Convert(sources, self.bom));
+                }
+            }
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    /// The text files converting changes.
+    fn to_convert(&self) -> Vec<PathBuf> {
+        let Some(detected) = &self.detected else {
+            return Vec::new();
+        };
+        self.sources
+            .iter()
+            .zip(detected)
+            .filter(|(_, detected)| detected.is_some_and(|detected| self.bom.changes(detected)))
+            .map(|(source, _)| source.clone())
+            .collect()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Detecting;
+
+        if self.detected.is_some() {
+            return Subscription::none();
+        }
+        let sources = self.sources.clone();
+        subscription::channel(
+            (TypeId::of::<Detecting>(), sources.clone()),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let detected = sources
+                        .iter()
+                        .map(|source| encoding::detect_file(source).ok().flatten())
+                        .collect();
+                    sender.send(detected)
+                });
+                if let Ok(detected) = receiver.await {
+                    let _ = output.send(Message::Detected(detected)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let mut col = column!(text(tr("Convert to UTF-8")).size(20)).spacing(10);
+
+        match &self.detected {
+            None => col = col.push(text(tr("Reading the files…"))),
+            Some(detected) => {
+                let mut files = column!().spacing(2);
+                for (source, detected) in self.sources.iter().zip(detected).take(MAX_LISTED) {
+                    let name = source.file_name().unwrap_or_default().to_string_lossy();
+                    let encoding = match detected {
+                        Some(detected) => detected.to_string(),
+                        None => String::from("Not text, left out"),
+                    };
+                    files = files.push(
+                        row!(
+                            text(name).width(Length::Fill),
+                            text(encoding).size(style.small_text_size),
+                        )
+                        .spacing(10)
+                        .align_items(Alignment::Center),
+                    );
+                }
+                if self.sources.len() > MAX_LISTED {
+                    files = files.push(
+                        text(trf("and {} more", &[&(self.sources.len() - MAX_LISTED)]))
+                            .size(style.small_text_size),
+                    );
+                }
+                col = col.push(files);
+            }
+        }
+
+        col = col.push(
+            row!(
+                text(tr("Byte order mark")).width(Length::Fixed(140.)),
+                pick_list(&ByteOrderMark::ALL[..], Some(self.bom), Message::Bom),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        let count = self.to_convert().len();
+        if self.detected.is_some() && count == 0 {
+            col = col.push(text(tr("Nothing needs converting")).size(style.small_text_size));
+        }
+        let label = match count {
+            0 | 1 => String::from("Convert"),
+            count => trf("Convert {}", &[&count]),
+        };
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Cancel")).on_press(Message::Close),
+                button(text(label)).on_press_maybe((count > 0).then_some(Message::Convert)),
+            )
+            .spacing(5),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(520)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
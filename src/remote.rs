@@ -0,0 +1,752 @@
+//! Folders on other machines, browsed over SSH as
+//! `sftp://[user@]host[:port]/path`. The system's `ssh` and `sftp` do the
+//! work: signing in leaves a master connection open in the background for
+//! a while, and listings and transfers go through it, so a password is
+//! asked for once rather than on every folder. [`RemoteFs`] is what the
+//! rest of the picker sees of it.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use iced::{subscription, Subscription};
+
+use crate::dirs;
+use crate::normalize;
+use crate::vfs::{self, Metadata, Source, VirtualFs};
+
+pub const SCHEME: &str = "sftp://";
+/// How long a master connection outlives its last use.
+const PERSIST: &str = "10m";
+/// Hands the password to `ssh`, which reads it through `SSH_ASKPASS`.
+const PASSWORD_VARIABLE: &str = "ICED_FM_SSH_PASSWORD";
+
+pub fn is_remote(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| path.starts_with(SCHEME))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+    /// Absolute, on the remote machine.
+    path: String,
+}
+
+impl Location {
+    pub fn parse(path: &Path) -> Option<Location> {
+        // Joined on Windows with backslashes.
+        let text = path.to_str()?.replace('\\', "/");
+        let rest = text.strip_prefix(SCHEME)?;
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, authority),
+        };
+        // An IPv6 address keeps its colons inside brackets.
+        let (host, port) = match host
+            .rsplit_once(':')
+            .filter(|(name, _)| !name.contains(':') || name.ends_with(']'))
+        {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (host, None),
+        };
+        // Both go to ssh as arguments, where a leading `-` reads as an option.
+        let unsafe_name = |name: &str| {
+            name.starts_with('-') || name.chars().any(|c| c.is_whitespace() || c.is_control())
+        };
+        if host.is_empty() || unsafe_name(host) || user.as_deref().is_some_and(unsafe_name) {
+            return None;
+        }
+
+        Some(Location {
+            user,
+            host: host.to_string(),
+            port,
+            path: normalize::remote_path(path),
+        })
+    }
+
+    /// `[user@]host`, which connections are kept by.
+    pub fn host(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The top of the remote machine, as a path to browse.
+    pub fn root(&self) -> PathBuf {
+        match self.port {
+            Some(port) => PathBuf::from(format!("{}{}:{}", SCHEME, self.host(), port)),
+            None => PathBuf::from(format!("{}{}", SCHEME, self.host())),
+        }
+    }
+
+    /// Shared by `ssh` and `sftp`, which take ports differently on the
+    /// command line.
+    fn options(&self) -> Vec<OsString> {
+        let mut control = OsString::from("ControlPath=");
+        control.push(control_dir().join("%C"));
+        let mut options = vec![
+            OsString::from("-o"),
+            control,
+            OsString::from("-o"),
+            OsString::from("StrictHostKeyChecking=accept-new"),
+            OsString::from("-o"),
+            OsString::from("ConnectTimeout=15"),
+        ];
+        if let Some(port) = self.port {
+            options.push(OsString::from("-o"));
+            options.push(OsString::from(format!("Port={}", port)));
+        }
+
+        options
+    }
+}
+
+/// An entry of a remote folder.
+#[derive(Debug, Clone)]
+struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub link_target: Option<String>,
+}
+
+/// Signs in to the machine, with keys or the SSH agent if `password` is
+/// `None`, leaving a master connection for what follows. A server turning
+/// the sign-in down gives `PermissionDenied`.
+pub fn connect(location: &Location, password: Option<&str>) -> io::Result<()> {
+    let dir = control_dir();
+    dirs::make_private(&dir)?;
+    // The master lingers in the background holding whatever it was given
+    // for output, so what it says goes to a file rather than a pipe.
+    let log = dir.join(format!("connect-{}.log", std::process::id()));
+    let mut command = Command::new("ssh");
+    command
+        .args(location.options())
+        .args(["-o", "ControlMaster=auto"])
+        .arg("-o")
+        .arg(format!("ControlPersist={}", PERSIST))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(fs::File::create(&log)?);
+    match password {
+        None => {
+            command.args(["-o", "BatchMode=yes"]);
+        }
+        Some(password) => {
+            command
+                .args(["-o", "NumberOfPasswordPrompts=1"])
+                .env("SSH_ASKPASS", askpass(&dir)?)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                .env(PASSWORD_VARIABLE, password);
+        }
+    }
+    let status = command.arg("--").arg(location.host()).arg("true").status();
+    let stderr = fs::read(&log).unwrap_or_default();
+    let _ = fs::remove_file(&log);
+
+    check(status?, &stderr)
+}
+
+/// What is directly inside the remote folder, `.` and `..` left out.
+fn children(location: &Location) -> io::Result<Vec<Entry>> {
+    let listing = sftp(location, &format!("ls -la {}\n", quote(&location.path)?))?;
+
+    Ok(listing.lines().filter_map(parse_long).collect())
+}
+
+/// Copies the files elsewhere among `paths` into the cache, giving where
+/// each went; those on the disk are given back as they are.
+pub fn fetch(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let cache = dirs::cache_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache folder"))?
+        .join("remote");
+    let mut fetched = Vec::with_capacity(paths.len());
+    for path in paths {
+        if vfs::is_local(path) {
+            fetched.push(path.clone());
+            continue;
+        }
+        let target = cache.join(cached(path));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut reader = vfs::for_path(path).open_read(path)?;
+        io::copy(&mut reader, &mut fs::File::create(&target)?)?;
+        fetched.push(target);
+    }
+
+    Ok(fetched)
+}
+
+/// Where in the cache `path` goes, keeping the server's layout below a
+/// folder for the server.
+fn cached(path: &Path) -> PathBuf {
+    let text = path.to_string_lossy();
+    let rest = text
+        .split_once("://")
+        .map_or(text.as_ref(), |(_, rest)| rest);
+    rest.split(['/', '\\'])
+        .filter(|name| !matches!(*name, "" | "." | ".."))
+        .map(|name| name.replace(':', "_"))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Fetch `usize` is over, giving the copies it made.
+    Done(usize, Result<Vec<PathBuf>, String>),
+}
+
+pub enum Event {
+    Done(Vec<PathBuf>),
+    Failed(String),
+}
+
+/// Files being fetched for a dialog to hand over, each batch on its own
+/// thread.
+#[derive(Default)]
+pub struct Transfers {
+    next: usize,
+    running: Vec<(usize, Vec<PathBuf>)>,
+}
+
+impl Transfers {
+    pub fn push(&mut self, paths: Vec<PathBuf>) {
+        tracing::info!(?paths, "fetching");
+        self.running.push((self.next, paths));
+        self.next += 1;
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        let Message::Done(id, result) = message;
+        let index = self
+            .running
+            .iter()
+            .position(|(running, _)| *running == id)?;
+        self.running.remove(index);
+
+        Some(match result {
+            Ok(copies) => Event::Done(copies),
+            Err(error) => Event::Failed(error),
+        })
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Transferring;
+
+        Subscription::batch(self.running.iter().map(|(id, paths)| {
+            let (id, paths) = (*id, paths.clone());
+            subscription::channel(
+                (TypeId::of::<Transferring>(), id),
+                1,
+                move |mut output| async move {
+                    use iced::futures::channel::oneshot;
+                    use iced::futures::SinkExt;
+
+                    let (sender, receiver) = oneshot::channel();
+                    std::thread::spawn(move || {
+                        sender.send(fetch(&paths).map_err(|error| error.to_string()))
+                    });
+                    if let Ok(result) = receiver.await {
+                        let _ = output.send(Message::Done(id, result)).await;
+                    }
+
+                    std::future::pending().await
+                },
+            )
+        }))
+    }
+}
+
+static BACKEND: OnceLock<RemoteFs> = OnceLock::new();
+
+/// The backend `vfs::for_path` hands out for `sftp://` paths. Each call
+/// runs a batch of its own through the master connection.
+pub fn backend() -> &'static RemoteFs {
+    BACKEND.get_or_init(|| RemoteFs {
+        known: Mutex::new(HashMap::new()),
+    })
+}
+
+pub struct RemoteFs {
+    /// What the last listings said of each entry, with where links point,
+    /// so walking a tree asks once per folder rather than once per file.
+    known: Mutex<HashMap<PathBuf, (Metadata, Option<PathBuf>)>>,
+}
+
+impl RemoteFs {
+    /// Drops what is known of `path` and everything below it.
+    fn forget(&self, path: &Path) {
+        self.known
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|known, _| !known.starts_with(path));
+    }
+
+    /// What is known of `path`, listing its folder if nothing is yet.
+    fn known(&self, path: &Path) -> io::Result<(Metadata, Option<PathBuf>)> {
+        let known = |fs: &RemoteFs| {
+            fs.known
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(path)
+                .cloned()
+        };
+        if let Some(known) = known(self) {
+            return Ok(known);
+        }
+        let top = location(path)?.path == "/";
+        if let Some(parent) = path.parent().filter(|_| !top) {
+            self.list(parent)?;
+        }
+        known(self).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not on the server", path.display()),
+            )
+        })
+    }
+
+    /// Runs `command` with the remote paths given quoted after it.
+    fn run(&self, command: &str, paths: &[&Path]) -> io::Result<()> {
+        let locations = paths
+            .iter()
+            .map(|path| location(path))
+            .collect::<io::Result<Vec<_>>>()?;
+        if locations
+            .windows(2)
+            .any(|pair| pair[0].root() != pair[1].root())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "they are on different machines",
+            ));
+        }
+        let mut script = String::from(command);
+        for location in &locations {
+            script += " ";
+            script += &quote(&location.path)?;
+        }
+        script += "\n";
+        sftp(&locations[0], &script).map(drop)
+    }
+}
+
+impl VirtualFs for RemoteFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<io::Result<PathBuf>>> {
+        Ok(self
+            .list(dir)?
+            .into_iter()
+            .map(|entry| entry.map(|entry| entry.path))
+            .collect())
+    }
+
+    /// One `ls -l` says what each entry is.
+    fn list(&self, dir: &Path) -> io::Result<Vec<io::Result<vfs::Entry>>> {
+        let entries = children(&location(dir)?)?;
+        let mut known = self.known.lock().unwrap_or_else(PoisonError::into_inner);
+        known.retain(|known, _| known.parent() != Some(dir));
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let path = dir.join(&entry.name);
+                let meta = Metadata {
+                    is_dir: entry.is_dir,
+                    is_symlink: entry.link_target.is_some(),
+                    len: entry.size,
+                    modified: None,
+                    mode: None,
+                };
+                let link_target = entry.link_target.map(PathBuf::from);
+                known.insert(path.clone(), (meta, link_target.clone()));
+                Ok(vfs::Entry {
+                    path,
+                    meta,
+                    link_target,
+                })
+            })
+            .collect())
+    }
+
+    /// The top of the machine is taken to be a folder.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if location(path)?.path == "/" {
+            return Ok(Metadata {
+                is_dir: true,
+                is_symlink: false,
+                len: 0,
+                modified: None,
+                mode: None,
+            });
+        }
+        self.known(path).map(|(meta, _)| meta)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.known(path)?.1.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a symlink", path.display()),
+            )
+        })
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let location = location(link)?;
+        let script = format!(
+            "symlink {} {}\n",
+            quote(&target.to_string_lossy().replace('\\', "/"))?,
+            quote(&location.path)?
+        );
+        sftp(&location, &script)?;
+        self.forget(link);
+
+        Ok(())
+    }
+
+    /// `sftp` cannot copy on the server, so the file comes here and goes
+    /// back.
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let len = self.metadata(from)?.len;
+        let (source, target) = (location(from)?, location(to)?);
+        if source.root() != target.root() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "they are on different machines",
+            ));
+        }
+        let temp = Temp::new();
+        let temp_name = quote(&temp.0.to_string_lossy())?;
+        let script = format!(
+            "get -p {} {}\nput -p {} {}\n",
+            quote(&source.path)?,
+            temp_name,
+            temp_name,
+            quote(&target.path)?
+        );
+        sftp(&source, &script)?;
+        self.forget(to);
+
+        Ok(len)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run("rename", &[from, to])?;
+        self.forget(from);
+        self.forget(to);
+
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> io::Result<()> {
+        match self.metadata(path)?.is_dir {
+            true => self.run("rmdir", &[path])?,
+            false => self.run("rm", &[path])?,
+        }
+        self.forget(path);
+
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.run("mkdir", &[path])?;
+        self.forget(path);
+
+        Ok(())
+    }
+
+    /// Read from a copy fetched whole first, which goes once read.
+    fn open_read(&self, path: &Path) -> io::Result<Box<dyn Source>> {
+        let location = location(path)?;
+        let temp = Temp::new();
+        let script = format!(
+            "get {} {}\n",
+            quote(&location.path)?,
+            quote(&temp.0.to_string_lossy())?
+        );
+        sftp(&location, &script)?;
+        let file = fs::File::open(&temp.0)?;
+
+        Ok(Box::new(Download { file, _temp: temp }))
+    }
+
+    fn open_write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let location = location(path)?;
+        let temp = Temp::new();
+        let file = Some(fs::File::create(&temp.0)?);
+        self.forget(path);
+
+        Ok(Box::new(Upload {
+            file,
+            temp,
+            location,
+        }))
+    }
+}
+
+fn location(path: &Path) -> io::Result<Location> {
+    Location::parse(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a remote folder", path.display()),
+        )
+    })
+}
+
+/// A file of this machine's temporary folder for a transfer, removed when
+/// dropped.
+struct Temp(PathBuf);
+
+impl Temp {
+    fn new() -> Temp {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        Temp(std::env::temp_dir().join(format!(
+            "iced-fm-sftp-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        )))
+    }
+}
+
+impl Drop for Temp {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+struct Download {
+    file: fs::File,
+    _temp: Temp,
+}
+
+impl Read for Download {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buffer)
+    }
+}
+
+impl Seek for Download {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.file.seek(position)
+    }
+}
+
+/// A file written here first, then sent to the server when flushed; nothing
+/// can be written after.
+struct Upload {
+    /// Until flushed.
+    file: Option<fs::File>,
+    temp: Temp,
+    location: Location,
+}
+
+impl Write for Upload {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match &mut self.file {
+            Some(file) => file.write(buffer),
+            None => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the upload is finished",
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Some(mut file) = self.file.take() else {
+            return Ok(());
+        };
+        file.flush()?;
+        drop(file);
+        let script = format!(
+            "put {} {}\n",
+            quote(&self.temp.0.to_string_lossy())?,
+            quote(&self.location.path)?
+        );
+        sftp(&self.location, &script).map(drop)
+    }
+}
+
+/// Runs `script` through `sftp` in batch mode, giving what it printed.
+/// It goes through the master connection when there is one, and never
+/// leaves one behind itself.
+fn sftp(location: &Location, script: &str) -> io::Result<String> {
+    let mut child = Command::new("sftp")
+        .args(location.options())
+        .args(["-o", "ControlMaster=no", "-q", "-b", "-"])
+        .arg("--")
+        .arg(location.host())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    check(output.status, &output.stderr)?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn check(status: ExitStatus, stderr: &[u8]) -> io::Result<()> {
+    if status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(stderr);
+    let message = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("ssh failed")
+        .trim()
+        .to_string();
+    tracing::debug!(%status, %stderr, "ssh failed");
+    let kind = if stderr.contains("Permission denied") {
+        io::ErrorKind::PermissionDenied
+    } else {
+        io::ErrorKind::Other
+    };
+
+    Err(io::Error::new(kind, message))
+}
+
+/// A line of `ls -l`: mode, links, owner, group, size, three fields of
+/// date, then the name.
+fn parse_long(line: &str) -> Option<Entry> {
+    let kind = line.chars().next()?;
+    if !matches!(kind, '-' | 'd' | 'l' | 'b' | 'c' | 'p' | 's') {
+        return None;
+    }
+    let mut rest = line;
+    let mut fields = Vec::with_capacity(8);
+    for _ in 0..8 {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace)?;
+        fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let name = rest.strip_prefix(' ').unwrap_or(rest);
+    let (name, link_target) = match name.split_once(" -> ") {
+        Some((name, target)) if kind == 'l' => (name, Some(target.to_string())),
+        _ => (name, None),
+    };
+    if matches!(name, "" | "." | "..") {
+        return None;
+    }
+
+    Some(Entry {
+        name: name.to_string(),
+        is_dir: kind == 'd',
+        size: fields[4].parse().unwrap_or(0),
+        link_target,
+    })
+}
+
+/// Quoted for `sftp`, which would otherwise split on spaces and expand
+/// wildcards. Names with control characters are refused: a line break
+/// would end the command, and the batch runs a line starting with `!` in
+/// the local shell.
+fn quote(path: &str) -> io::Result<String> {
+    if path.chars().any(char::is_control) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} has control characters in its name", path),
+        ));
+    }
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '\\' | '"' | '\'' | '*' | '?' | '[' | ']') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+
+    Ok(quoted)
+}
+
+/// Where the master connections' sockets go.
+fn control_dir() -> PathBuf {
+    dirs::runtime_dir("iced-fm-ssh")
+}
+
+/// A program that prints the password `ssh` asks for, from the
+/// environment it was started with.
+#[cfg(unix)]
+fn askpass(dir: &Path) -> io::Result<PathBuf> {
+    let script = dir.join("askpass");
+    fs::write(
+        &script,
+        format!("#!/bin/sh\nprintf '%s\\n' \"${}\"\n", PASSWORD_VARIABLE),
+    )?;
+    dirs::restrict(&script, 0o700)?;
+
+    Ok(script)
+}
+
+#[cfg(windows)]
+fn askpass(_dir: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "passwords cannot be handed to ssh here; use a key or the SSH agent",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_files_by_server_and_path() {
+        assert_eq!(
+            cached(Path::new("sftp://me@host:2222/home/me/a.txt")),
+            Path::new("me@host_2222/home/me/a.txt")
+        );
+        assert_eq!(cached(Path::new("davs://host/../x")), Path::new("host/x"));
+    }
+
+    #[test]
+    fn refuses_hosts_and_users_ssh_would_take_for_options() {
+        assert!(Location::parse(Path::new("sftp://-oProxyCommand=touch%20x/home")).is_none());
+        assert!(Location::parse(Path::new("sftp://-oProxyCommand=x@host/home")).is_none());
+        assert!(Location::parse(Path::new("sftp://me@host name/home")).is_none());
+        assert!(Location::parse(Path::new("sftp://me\u{1b}@host/home")).is_none());
+        assert!(Location::parse(Path::new("sftp://me@host-1:2222/home")).is_some());
+    }
+
+    #[test]
+    fn quote_escapes_what_sftp_would_split_or_expand() {
+        assert_eq!(quote("a b.txt").unwrap(), "\"a b.txt\"");
+        assert_eq!(
+            quote(r#"it's "*?[x]\"#).unwrap(),
+            r#""it\'s \"\*\?\[x\]\\""#
+        );
+    }
+
+    #[test]
+    fn quote_refuses_names_that_would_break_the_batch() {
+        assert!(quote("a\n!rm -rf ~").is_err());
+        assert!(quote("a\rb").is_err());
+        assert!(quote("a\0b").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::env;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use iced::window::Position;
+use iced::{Point, Settings, Size};
+
+use crate::config::{Config, MaxFps, Renderer};
+use crate::monitors;
+
+/// Environment variable choosing the renderer over the configuration:
+/// `gpu`, `low-power` or `software`.
+pub const RENDERER_ENV: &str = "ICED_FM_RENDERER";
+
+/// Environment variable turning antialiasing on with `1` or off with `0`,
+/// whatever the configuration says.
+pub const ANTIALIASING_ENV: &str = "ICED_FM_ANTIALIASING";
+
+/// Environment variable capping how many times per second background work
+/// (listings, searches, job progress) may wake the UI for a redraw, over
+/// the configuration.
+pub const MAX_FPS_ENV: &str = "ICED_FM_MAX_FPS";
+
+static MAX_FPS: AtomicU32 = AtomicU32::new(0);
+
+/// Application settings with the renderer picked from the configuration,
+/// or the environment when set, and the window sized and placed as it was
+/// last closed, on the same display while it is connected. Must run before
+/// the window is created, while the process is still single-threaded.
+pub fn settings<Flags>(flags: Flags) -> Settings<Flags> {
+    let config = Config::load();
+
+    let renderer = match env::var(RENDERER_ENV) {
+        Ok(name) => Renderer::from_name(&name).unwrap_or_else(|| {
+            tracing::warn!(
+                renderer = name,
+                "unknown renderer, using the configured one"
+            );
+            config.renderer
+        }),
+        Err(_) => config.renderer,
+    };
+    match renderer {
+        Renderer::Gpu => {}
+        Renderer::LowPower => {
+            if env::var_os("WGPU_POWER_PREF").is_none() {
+                env::set_var("WGPU_POWER_PREF", "low");
+            }
+        }
+        Renderer::Software => env::set_var("ICED_BACKEND", "tiny-skia"),
+    }
+
+    let antialiasing = match env::var(ANTIALIASING_ENV).as_deref() {
+        Ok("1") => true,
+        Ok("0") => false,
+        _ => config.antialiasing,
+    };
+    set_max_fps(config.max_fps);
+    tracing::info!(
+        renderer = renderer.name(),
+        antialiasing,
+        max_fps = max_fps(),
+        "rendering"
+    );
+
+    let mut settings = Settings {
+        antialiasing,
+        ..Settings::with_flags(flags)
+    };
+    if let Some((width, height)) = config.window_size {
+        settings.window.size = Size::new(width, height);
+    }
+    if let Some((x, y)) = config.window_position {
+        let placed = monitors::place(
+            &monitors::monitors(),
+            config.window_monitor.as_deref(),
+            Point::new(x, y),
+            settings.window.size,
+        );
+        if let Some((position, size)) = placed {
+            settings.window.position = Position::Specific(position);
+            settings.window.size = size;
+        }
+    }
+    settings
+}
+
+/// Caps redraws from background work at `fps`, unless the environment
+/// sets the cap.
+pub fn set_max_fps(fps: MaxFps) {
+    MAX_FPS.store(fps.0, Ordering::Relaxed);
+}
+
+/// The shortest time between two updates sent by background work.
+pub fn update_interval() -> Duration {
+    Duration::from_secs(1) / max_fps()
+}
+
+fn max_fps() -> u32 {
+    env::var(MAX_FPS_ENV)
+        .ok()
+        .and_then(|fps| fps.parse().ok())
+        .filter(|&fps| fps > 0)
+        .unwrap_or(match MAX_FPS.load(Ordering::Relaxed) {
+            0 => MaxFps::default().0,
+            fps => fps,
+        })
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type Check = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Limits a host application puts on what its users can reach through the
+/// picker, such as staying inside a project folder. Folders that fail
+/// `navigation` cannot be opened by any means, and entries that fail
+/// `entries` are left out of listings and search results. A `read_only`
+/// picker changes nothing on disk.
+#[derive(Clone, Default)]
+pub struct Restrictions {
+    navigation: Option<Check>,
+    entries: Option<Check>,
+    start: Option<PathBuf>,
+    read_only: bool,
+}
+
+impl Restrictions {
+    pub fn new() -> Restrictions {
+        Restrictions::default()
+    }
+
+    /// Only `root` and the folders below it can be opened, and the picker
+    /// moves there if it is elsewhere. Paths are compared once resolved, so
+    /// neither `..` nor symlinks lead out.
+    pub fn within(root: impl Into<PathBuf>) -> Restrictions {
+        let root = root.into();
+        let inside = fs::canonicalize(&root).unwrap_or_else(|_| root.clone());
+        Restrictions::new()
+            .navigation(move |path| {
+                // Folders inside archives only exist below the archive file.
+                path.ancestors()
+                    .find_map(|path| fs::canonicalize(path).ok())
+                    .is_some_and(|path| path.starts_with(&inside))
+            })
+            .start(root)
+    }
+
+    /// Folders are only opened when `allow` returns `true` for them.
+    pub fn navigation(mut self, allow: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.navigation = Some(Arc::new(allow));
+        self
+    }
+
+    /// Entries are only listed when `show` returns `true` for them.
+    pub fn entries(mut self, show: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.entries = Some(Arc::new(show));
+        self
+    }
+
+    /// Where to go when the folder being shown is not allowed.
+    pub fn start(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.start = Some(dir.into());
+        self
+    }
+
+    /// Nothing can be made, renamed, edited, moved, trashed or deleted,
+    /// and nothing dropped or pasted in; browsing and picking are as ever.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub(crate) fn start_dir(&self) -> Option<&Path> {
+        self.start.as_deref()
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub(crate) fn may_enter(&self, dir: &Path) -> bool {
+        self.navigation.as_ref().is_none_or(|allow| allow(dir))
+    }
+
+    /// Whether an entry of a folder already allowed may be listed.
+    pub(crate) fn lists(&self, path: &Path) -> bool {
+        self.entries.as_ref().is_none_or(|show| show(path))
+    }
+
+    /// Whether `path`, wherever it is, may be listed: shown, and in a
+    /// folder that may be opened.
+    pub(crate) fn shows(&self, path: &Path) -> bool {
+        self.lists(path) && path.parent().is_none_or(|dir| self.may_enter(dir))
+    }
+}
+
+impl fmt::Debug for Restrictions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Restrictions")
+            .field("navigation", &self.navigation.is_some())
+            .field("entries", &self.entries.is_some())
+            .field("start", &self.start)
+            .field("read_only", &self.read_only)
+            .finish()
+    }
+}
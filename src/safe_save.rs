@@ -0,0 +1,174 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replaces the contents of `path` without ever leaving it truncated: the
+/// data goes to a temporary sibling which is flushed to disk and then renamed
+/// over the original. Permissions, ownership and extended attributes of an
+/// existing file are carried over to the replacement.
+pub fn write(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    save(path.as_ref(), contents, None)
+}
+
+/// As [`write`], but the replacement is created with `mode` rather than
+/// taking the original's permissions, so its contents are never readable
+/// with looser ones.
+pub fn write_with_mode(path: impl AsRef<Path>, contents: &[u8], mode: u32) -> io::Result<()> {
+    save(path.as_ref(), contents, Some(mode))
+}
+
+fn save(path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+    // Saving through a symlink must replace the file it points to, not the link.
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let path = path.as_path();
+    let temp = temp_path(path)?;
+
+    let result = write_temp(path, &temp, contents, mode).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+
+    sync_parent(path);
+    tracing::debug!(path = %path.display(), bytes = contents.len(), "saved atomically");
+
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let parent = path.parent().unwrap_or(Path::new("."));
+
+    (0..)
+        .map(|n| {
+            let mut temp = std::ffi::OsString::from(".");
+            temp.push(name);
+            temp.push(format!(".{}.{}.tmp", std::process::id(), n));
+            parent.join(temp)
+        })
+        .find(|temp| fs::symlink_metadata(temp).is_err())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::AlreadyExists, "no temporary name"))
+}
+
+fn write_temp(path: &Path, temp: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+    let mut options = File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(mode);
+    }
+    let mut file = options.open(temp)?;
+    file.write_all(contents)?;
+
+    if let Ok(meta) = fs::metadata(path) {
+        if mode.is_none() {
+            file.set_permissions(meta.permissions())?;
+        }
+        copy_owner(&meta, temp);
+        copy_xattrs(path, temp);
+    }
+
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn copy_owner(meta: &fs::Metadata, temp: &Path) {
+    use std::os::unix::fs::MetadataExt;
+
+    // Only root (or the owner keeping its own group) may do this; a failure
+    // just leaves the new file owned by us.
+    if let Err(error) = std::os::unix::fs::lchown(temp, Some(meta.uid()), Some(meta.gid())) {
+        tracing::debug!(%error, "ownership not preserved");
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_meta: &fs::Metadata, _temp: &Path) {}
+
+#[cfg(target_os = "linux")]
+fn copy_xattrs(path: &Path, temp: &Path) {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let (Ok(source), Ok(target)) = (
+        CString::new(path.as_os_str().as_bytes()),
+        CString::new(temp.as_os_str().as_bytes()),
+    ) else {
+        return;
+    };
+
+    // SAFETY: every buffer handed to the xattr calls is sized by the length
+    // passed alongside it, and the C strings outlive the calls.
+    unsafe {
+        let size = libc::llistxattr(source.as_ptr(), std::ptr::null_mut(), 0);
+        if size <= 0 {
+            return;
+        }
+        let mut names = vec![0u8; size as usize];
+        let size = libc::llistxattr(source.as_ptr(), names.as_mut_ptr().cast(), names.len());
+        if size <= 0 {
+            return;
+        }
+        names.truncate(size as usize);
+
+        for name in names
+            .split(|&byte| byte == 0)
+            .filter(|name| !name.is_empty())
+        {
+            let Ok(name) = CString::new(name) else {
+                continue;
+            };
+            let name: &CStr = &name;
+
+            let size = libc::lgetxattr(source.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0);
+            if size < 0 {
+                continue;
+            }
+            let mut value = vec![0u8; size as usize];
+            let size = libc::lgetxattr(
+                source.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            );
+            if size < 0 {
+                continue;
+            }
+
+            if libc::lsetxattr(
+                target.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                size as usize,
+                0,
+            ) != 0
+            {
+                tracing::debug!(name = ?name, "extended attribute not preserved");
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_xattrs(_path: &Path, _temp: &Path) {}
+
+#[cfg(unix)]
+fn sync_parent(path: &Path) {
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) {}
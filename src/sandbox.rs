@@ -0,0 +1,324 @@
+//! Flatpak and Snap confinement: which host folders the app cannot see, and
+//! the document portal as the way to reach single files outside them.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const FLATPAK_INFO: &str = "/.flatpak-info";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+impl std::fmt::Display for Sandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sandbox::Flatpak => write!(f, "Flatpak"),
+            Sandbox::Snap => write!(f, "Snap"),
+        }
+    }
+}
+
+/// What the sandbox lets through, going by its declared permissions.
+#[derive(Debug)]
+pub struct Rules {
+    pub sandbox: Sandbox,
+    /// Everything of the host is visible.
+    host: bool,
+    granted: Vec<PathBuf>,
+    /// Snaps cannot see the dot-files directly in the home folder.
+    hidden_home: Option<PathBuf>,
+}
+
+impl Rules {
+    /// Whether the sandbox keeps `path`, or some of what is below it, out of
+    /// sight.
+    pub fn hides(&self, path: &Path) -> bool {
+        if self.host {
+            return false;
+        }
+        if let Some(home) = &self.hidden_home {
+            let dot_file = path
+                .strip_prefix(home)
+                .ok()
+                .and_then(|rest| rest.components().next())
+                .is_some_and(|first| first.as_os_str().to_string_lossy().starts_with('.'));
+            if dot_file {
+                return true;
+            }
+        }
+        !self.granted.iter().any(|root| path.starts_with(root))
+    }
+}
+
+/// The confinement this process runs under, if any.
+pub fn rules() -> Option<&'static Rules> {
+    static RULES: OnceLock<Option<Rules>> = OnceLock::new();
+
+    RULES
+        .get_or_init(|| {
+            let rules = if Path::new(FLATPAK_INFO).exists() {
+                Some(flatpak_rules())
+            } else if env::var_os("SNAP").is_some() {
+                Some(snap_rules())
+            } else {
+                None
+            };
+            if let Some(rules) = &rules {
+                tracing::info!(sandbox = %rules.sandbox, granted = ?rules.granted, host = rules.host, "sandboxed");
+            }
+            rules
+        })
+        .as_ref()
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Folders the document portal exposes files in, always reachable.
+fn documents() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join("doc"))
+}
+
+/// Reads `filesystems=` from the `[Context]` of `/.flatpak-info`, as in
+/// `home;xdg-download:ro;/mnt;!~/.ssh`.
+fn flatpak_rules() -> Rules {
+    let info = fs::read_to_string(FLATPAK_INFO).unwrap_or_default();
+    let mut section = "";
+    let mut filesystems = "";
+    for line in info.lines().map(str::trim) {
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name;
+        } else if let Some(value) = line.strip_prefix("filesystems=") {
+            if section == "Context" {
+                filesystems = value;
+            }
+        }
+    }
+
+    let mut rules = Rules {
+        sandbox: Sandbox::Flatpak,
+        host: false,
+        granted: documents().into_iter().collect(),
+        hidden_home: None,
+    };
+    if let (Some(home), Ok(id)) = (home(), env::var("FLATPAK_ID")) {
+        rules.granted.push(home.join(".var/app").join(id));
+    }
+
+    grant(&mut rules, filesystems);
+
+    rules
+}
+
+/// Adds the folders of a `filesystems=` value to `rules`.
+fn grant(rules: &mut Rules, filesystems: &str) {
+    for entry in filesystems
+        .split(';')
+        .filter(|entry| !entry.starts_with('!'))
+    {
+        let entry = entry.split(':').next().unwrap_or_default();
+        let (name, rest) = entry.split_once('/').unwrap_or((entry, ""));
+        let root = match name {
+            // An absolute path, granted as it is.
+            "" if !entry.is_empty() => {
+                rules.granted.push(PathBuf::from(entry));
+                continue;
+            }
+            "host" => {
+                rules.host = true;
+                None
+            }
+            "home" | "~" => home(),
+            _ if name.starts_with("xdg-") => xdg_dir(name),
+            _ => None,
+        };
+        if let Some(root) = root {
+            rules.granted.push(if rest.is_empty() {
+                root
+            } else {
+                root.join(rest)
+            });
+        }
+    }
+}
+
+fn xdg_dir(name: &str) -> Option<PathBuf> {
+    let home = home()?;
+    let (key, default) = match name {
+        "xdg-desktop" => ("XDG_DESKTOP_DIR", "Desktop"),
+        "xdg-documents" => ("XDG_DOCUMENTS_DIR", "Documents"),
+        "xdg-download" => ("XDG_DOWNLOAD_DIR", "Downloads"),
+        "xdg-music" => ("XDG_MUSIC_DIR", "Music"),
+        "xdg-pictures" => ("XDG_PICTURES_DIR", "Pictures"),
+        "xdg-public-share" => ("XDG_PUBLICSHARE_DIR", "Public"),
+        "xdg-templates" => ("XDG_TEMPLATES_DIR", "Templates"),
+        "xdg-videos" => ("XDG_VIDEOS_DIR", "Videos"),
+        "xdg-config" => ("XDG_CONFIG_HOME", ".config"),
+        "xdg-cache" => ("XDG_CACHE_HOME", ".cache"),
+        "xdg-data" => ("XDG_DATA_HOME", ".local/share"),
+        "xdg-run" => return env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from),
+        _ => return None,
+    };
+
+    Some(env::var_os(key).map_or_else(|| home.join(default), PathBuf::from))
+}
+
+/// Strict snaps with the usual `home` and `removable-media` plugs.
+fn snap_rules() -> Rules {
+    let mut granted: Vec<PathBuf> = ["SNAP_USER_DATA", "SNAP_USER_COMMON"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .map(PathBuf::from)
+        .chain(documents())
+        .collect();
+    granted.extend(home());
+    granted.extend(["/media", "/mnt", "/run/media"].map(PathBuf::from));
+
+    Rules {
+        sandbox: Sandbox::Snap,
+        host: false,
+        granted,
+        hidden_home: home(),
+    }
+}
+
+/// Asks the host, through the FileChooser portal, for files outside the
+/// sandbox. What the user picks is shared with the app by the document
+/// portal and comes back as paths under `$XDG_RUNTIME_DIR/doc`; nothing if
+/// they cancel.
+#[cfg(target_os = "linux")]
+pub fn pick_from_host(title: &str) -> io::Result<Vec<PathBuf>> {
+    use crate::dbus::{Connection, Value};
+    use crate::paths;
+
+    let mut connection = Connection::session()?;
+    // The request object's path is known up front from the token, so the
+    // Response signal cannot slip past before the match is in place.
+    let token = format!("iced_fm_{}", std::process::id());
+    let sender = connection
+        .unique_name()
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let handle = format!(
+        "/org/freedesktop/portal/desktop/request/{}/{}",
+        sender, token
+    );
+    connection.add_match(&format!(
+        "type='signal',interface='org.freedesktop.portal.Request',member='Response',path='{}'",
+        handle
+    ))?;
+
+    let options = Value::dict(vec![
+        ("handle_token", Value::String(token)),
+        ("multiple", Value::Bool(true)),
+    ]);
+    let reply = connection.call(
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.FileChooser",
+        "OpenFile",
+        vec![
+            Value::String(String::new()),
+            Value::String(title.to_string()),
+            options,
+        ],
+    )?;
+    // Portals older than 0.9 pick the path themselves.
+    let returned = reply
+        .body
+        .first()
+        .and_then(Value::as_str)
+        .unwrap_or(&handle);
+    if returned != handle {
+        connection.add_match(&format!(
+            "type='signal',interface='org.freedesktop.portal.Request',member='Response',path='{}'",
+            returned
+        ))?;
+    }
+    let returned = returned.to_string();
+
+    loop {
+        let message = connection.receive()?;
+        let path = message.path.as_deref();
+        if !message.is_signal()
+            || (path != Some(handle.as_str()) && path != Some(returned.as_str()))
+        {
+            continue;
+        }
+        let [response, results] = &message.body[..] else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed response",
+            ));
+        };
+        return Ok(match response.as_u32() {
+            Some(0) => results
+                .get("uris")
+                .and_then(Value::items)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(paths::from_uri)
+                .collect(),
+            _ => Vec::new(),
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pick_from_host(_title: &str) -> io::Result<Vec<PathBuf>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the document portal is only available on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn granted(filesystems: &str) -> Rules {
+        let mut rules = Rules {
+            sandbox: Sandbox::Flatpak,
+            host: false,
+            granted: Vec::new(),
+            hidden_home: None,
+        };
+        grant(&mut rules, filesystems);
+        rules
+    }
+
+    #[test]
+    fn absolute_grants_are_taken_as_they_are() {
+        let rules = granted("/mnt;/srv/media:ro;!/opt");
+        assert_eq!(rules.granted, [Path::new("/mnt"), Path::new("/srv/media")]);
+        assert!(!rules.hides(Path::new("/mnt/disk")));
+        assert!(rules.hides(Path::new("/opt")));
+    }
+
+    #[test]
+    fn home_grants_are_below_the_home_folder() {
+        let Some(home) = home() else {
+            return;
+        };
+        let rules = granted("~/Music;home/Videos:ro");
+        assert_eq!(rules.granted, [home.join("Music"), home.join("Videos")]);
+    }
+
+    #[test]
+    fn host_shows_everything() {
+        let rules = granted("host");
+        assert!(rules.granted.is_empty());
+        assert!(!rules.hides(Path::new("/etc")));
+    }
+}
@@ -0,0 +1,439 @@
+use std::any::TypeId;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Instant, SystemTime};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::{button, column, row, scrollable, text, text_input};
+use iced::{subscription, Alignment, Element, Length, Subscription};
+
+use crate::dates::format_time;
+use crate::devices;
+use crate::i18n::{tr, trf};
+use crate::indexer;
+use crate::rendering;
+use crate::vfs;
+
+/// Results beyond this are dropped; the query needs refining at that point.
+const MAX_RESULTS: usize = 5_000;
+const BATCH_SIZE: usize = 256;
+
+/// Indexes consulted next to the directory walker.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sources {
+    /// The locate database on Linux, Spotlight on macOS.
+    pub system_index: bool,
+    /// Replaces the walker when it works; the walker takes over otherwise.
+    pub ntfs_index: bool,
+    /// Keep the NTFS index in memory for the next search instead of
+    /// rebuilding it every time.
+    pub keep_ntfs_index: bool,
+    /// The index the background instance keeps, when it has written one.
+    pub own_index: bool,
+}
+
+/// A result, with when it was last changed.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+}
+
+impl Hit {
+    fn of(path: PathBuf) -> Hit {
+        let modified = vfs::for_path(&path)
+            .metadata(&path)
+            .ok()
+            .and_then(|meta| meta.modified);
+        Hit { path, modified }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Query(String),
+    Start,
+    Stop,
+    Clear,
+    Found(usize, Vec<Hit>),
+    Notice(usize, String),
+    Done(usize),
+    Open(PathBuf),
+}
+
+pub enum Event {
+    /// The user asked to search; the caller picks the root and the sources,
+    /// then calls `start`.
+    Start,
+    Open(PathBuf),
+}
+
+#[derive(Default)]
+pub struct Search {
+    query: String,
+    root: PathBuf,
+    sources: Sources,
+    generation: usize,
+    running: bool,
+    /// Whether results are shown instead of the directory listing.
+    active: bool,
+    results: Vec<Hit>,
+    seen: HashSet<PathBuf>,
+    notice: Option<String>,
+}
+
+enum Found {
+    Paths(Vec<Hit>),
+    Notice(String),
+}
+
+impl Search {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn start(&mut self, root: PathBuf, sources: Sources) {
+        if self.query.is_empty() {
+            return;
+        }
+
+        tracing::info!(root = %root.display(), query = self.query, ?sources, "search");
+        self.generation += 1;
+        self.root = root;
+        self.sources = sources;
+        self.notice = None;
+        self.running = true;
+        self.active = true;
+        self.results.clear();
+        self.seen.clear();
+    }
+
+    /// `found` as the results, for a query the caller answered itself.
+    pub fn show(&mut self, root: PathBuf, mut found: Vec<PathBuf>) {
+        found.truncate(MAX_RESULTS);
+        self.generation += 1;
+        self.root = root;
+        self.notice = None;
+        self.running = false;
+        self.active = true;
+        self.seen = found.iter().cloned().collect();
+        self.results = found.into_iter().map(Hit::of).collect();
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Query(query) => self.query = query,
+            Message::Start => return Some(Event::Start),
+            Message::Stop => self.running = false,
+            Message::Clear => {
+                self.running = false;
+                self.active = false;
+                self.results.clear();
+                self.seen.clear();
+            }
+            Message::Found(generation, hits) => {
+                if generation == self.generation {
+                    for hit in hits {
+                        if self.results.len() == MAX_RESULTS {
+                            self.running = false;
+                            break;
+                        }
+                        if self.seen.insert(hit.path.clone()) {
+                            self.results.push(hit);
+                        }
+                    }
+                }
+            }
+            Message::Notice(generation, notice) => {
+                if generation == self.generation {
+                    self.notice = Some(notice);
+                }
+            }
+            Message::Done(generation) => {
+                if generation == self.generation {
+                    self.running = false;
+                    tracing::debug!(results = self.results.len(), "search complete");
+                }
+            }
+            Message::Open(path) => return Some(Event::Open(path)),
+        }
+
+        None
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Searcher;
+
+        if !self.running {
+            return Subscription::none();
+        }
+
+        let generation = self.generation;
+        let root = self.root.clone();
+        let query = self.query.to_lowercase();
+        let sources = self.sources;
+
+        subscription::channel(
+            (TypeId::of::<Searcher>(), generation),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+
+                if sources.system_index {
+                    let sender = sender.clone();
+                    let root = root.clone();
+                    let query = query.clone();
+                    thread::spawn(move || system_index(&root, &query, sender));
+                }
+                if sources.own_index {
+                    let sender = sender.clone();
+                    let root = root.clone();
+                    let query = query.clone();
+                    thread::spawn(move || {
+                        indexer::search(&root, &query, |paths| {
+                            let hits = paths.into_iter().filter_map(existing).collect();
+                            sender.unbounded_send(Found::Paths(hits)).is_ok()
+                        });
+                    });
+                }
+                thread::spawn(move || {
+                    if sources.ntfs_index {
+                        match indexed(&root, &query, sources.keep_ntfs_index, &sender) {
+                            Ok(()) => return,
+                            Err(error) => {
+                                tracing::warn!(%error, "NTFS index unavailable");
+                                let _ = sender.unbounded_send(Found::Notice(format!(
+                                    "NTFS index unavailable ({}), walking the folders instead",
+                                    error
+                                )));
+                            }
+                        }
+                    }
+                    if let Some(_turn) = devices::wait_for(&[&root], || sender.is_closed()) {
+                        walk(&root, &query, sender)
+                    }
+                });
+
+                // Ends once every source dropped its sender.
+                while let Some(found) = receiver.next().await {
+                    let message = match found {
+                        Found::Paths(paths) => Message::Found(generation, paths),
+                        Found::Notice(notice) => Message::Notice(generation, notice),
+                    };
+                    let _ = output.send(message).await;
+                }
+                let _ = output.send(Message::Done(generation)).await;
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn bar(&self) -> Element<'_, Message> {
+        let mut query = text_input(tr("Search in this folder and below"), &self.query)
+            .on_input(Message::Query)
+            .padding(5);
+        if !self.query.is_empty() {
+            query = query.on_submit(Message::Start);
+        }
+
+        let mut bar = row!(query).spacing(5).align_items(Alignment::Center);
+        bar = if self.running {
+            bar.push(button(tr("Stop")).on_press(Message::Stop))
+        } else {
+            bar.push(
+                button(tr("Search"))
+                    .on_press_maybe((!self.query.is_empty()).then_some(Message::Start)),
+            )
+        };
+        if self.active {
+            bar = bar.push(button(tr("Close search")).on_press(Message::Clear));
+        }
+
+        bar.into()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let status = match (self.running, self.results.len()) {
+            (true, found) => trf("Searching {}… {} found", &[&self.root.display(), &found]),
+            (false, MAX_RESULTS) => trf("First {} results — refine the query", &[&MAX_RESULTS]),
+            (false, found) => format!("{} found in {}", found, self.root.display()),
+        };
+
+        let mut col = column!();
+        if let Some(notice) = &self.notice {
+            col = col.push(text(notice).size(14));
+        }
+        for hit in &self.results {
+            let shown = hit.path.strip_prefix(&self.root).unwrap_or(&hit.path);
+            let modified = hit.modified.map(|time| format_time(Ok(time)));
+            col = col.push(
+                button(row!(
+                    text(shown.display().to_string()).width(Length::FillPortion(3)),
+                    text(modified.unwrap_or_default()).width(Length::FillPortion(1)),
+                ))
+                .on_press(Message::Open(hit.path.clone()))
+                .width(Length::Fill),
+            );
+        }
+
+        column!(
+            text(status).size(14),
+            scrollable(col.spacing(2))
+                .width(Length::Fill)
+                .height(Length::Fill),
+        )
+        .spacing(5)
+        .into()
+    }
+}
+
+fn matches(path: &Path, query: &str) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(query))
+}
+
+/// Walks `root` breadth-first without following symlinks, sending matches in
+/// batches until the receiver goes away.
+fn walk(root: &Path, query: &str, sender: mpsc::UnboundedSender<Found>) {
+    let started = Instant::now();
+    let mut pending = VecDeque::from([root.to_path_buf()]);
+    let mut batch = Vec::new();
+    let mut sent = Instant::now();
+
+    while let Some(dir) = pending.pop_front() {
+        let fs = vfs::for_path(&dir);
+        let Ok(entries) = fs.read_dir(&dir) else {
+            continue;
+        };
+
+        for path in entries.into_iter().flatten() {
+            let meta = fs.metadata(&path).ok();
+            if meta.is_some_and(|meta| meta.is_dir) {
+                pending.push_back(path.clone());
+            }
+            if matches(&path, query) {
+                let modified = meta.and_then(|meta| meta.modified);
+                batch.push(Hit { path, modified });
+            }
+
+            if batch.len() >= BATCH_SIZE
+                || (!batch.is_empty() && sent.elapsed() >= rendering::update_interval())
+            {
+                if sender
+                    .unbounded_send(Found::Paths(std::mem::take(&mut batch)))
+                    .is_err()
+                {
+                    return;
+                }
+                sent = Instant::now();
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        let _ = sender.unbounded_send(Found::Paths(batch));
+    }
+    tracing::debug!(elapsed = ?started.elapsed(), "search walk finished");
+}
+
+/// Asks the platform's file index, the locate database on Linux or
+/// Spotlight on macOS, for names containing `query`, keeping what still
+/// exists below `root`.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn system_index(root: &Path, query: &str, sender: mpsc::UnboundedSender<Found>) {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let started = Instant::now();
+    let Some(output) = query_index(root, query) else {
+        tracing::debug!("no system file index available");
+        return;
+    };
+
+    let hits: Vec<Hit> = output
+        .split(|&byte| byte == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| PathBuf::from(OsStr::from_bytes(path)))
+        .filter(|path| path.starts_with(root) && path != root)
+        .filter_map(existing)
+        .collect();
+
+    tracing::debug!(results = hits.len(), elapsed = ?started.elapsed(), "system index queried");
+    for chunk in hits.chunks(BATCH_SIZE) {
+        if sender.unbounded_send(Found::Paths(chunk.to_vec())).is_err() {
+            return;
+        }
+    }
+}
+
+/// What an index found, unless it is gone since.
+fn existing(path: PathBuf) -> Option<Hit> {
+    let meta = vfs::for_path(&path).metadata(&path).ok()?;
+    let modified = meta.modified;
+    Some(Hit { path, modified })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn system_index(_root: &Path, _query: &str, _sender: mpsc::UnboundedSender<Found>) {}
+
+/// plocate, or any compatible `locate`, matching basenames.
+#[cfg(target_os = "linux")]
+fn query_index(_root: &Path, query: &str) -> Option<Vec<u8>> {
+    use std::process::{Command, Stdio};
+
+    ["plocate", "locate"].iter().find_map(|program| {
+        Command::new(program)
+            .args(["--ignore-case", "--basename", "--null", "--", query])
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .map(|output| output.stdout)
+    })
+}
+
+/// Spotlight, restricted to `root` so it does the prefix filtering itself.
+#[cfg(target_os = "macos")]
+fn query_index(root: &Path, query: &str) -> Option<Vec<u8>> {
+    use std::process::{Command, Stdio};
+
+    Command::new("mdfind")
+        .arg("-0")
+        .arg("-onlyin")
+        .arg(root)
+        .arg("-name")
+        .arg(query)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .map(|output| output.stdout)
+}
+
+#[cfg(windows)]
+fn indexed(
+    root: &Path,
+    query: &str,
+    keep: bool,
+    sender: &mpsc::UnboundedSender<Found>,
+) -> Result<(), String> {
+    crate::ntfs::search(root, query, keep, |paths| {
+        let hits = paths.into_iter().map(Hit::of).collect();
+        sender.unbounded_send(Found::Paths(hits)).is_ok()
+    })
+}
+
+#[cfg(not(windows))]
+fn indexed(
+    _root: &Path,
+    _query: &str,
+    _keep: bool,
+    _sender: &mpsc::UnboundedSender<Found>,
+) -> Result<(), String> {
+    Err(String::from("only available on Windows"))
+}
@@ -0,0 +1,235 @@
+//! What the selected folders add up to, worked out in the background: a
+//! moment after the selection last changed, so sweeping across the listing
+//! does not start a walk per step, and given up as soon as it changes.
+
+use std::any::TypeId;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::{button, row, text};
+use iced::{subscription, Alignment, Element, Subscription};
+
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::rendering;
+use crate::style::Style;
+
+/// How long the selection has to stay put before its folders are walked.
+const DELAY: Duration = Duration::from_millis(300);
+
+const SPINNER: [&str; 4] = ["◐", "◓", "◑", "◒"];
+
+/// Tells selections apart without keeping them.
+pub fn fingerprint(paths: &[PathBuf]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    paths.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Total {
+    pub bytes: u64,
+    /// Whether some of it could not be read, making `bytes` a lower bound.
+    pub partial: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// How far adding up the selection with that fingerprint has got.
+    Counting(u64, Total),
+    Counted(u64, Total),
+    Cancel(u64),
+    Restart,
+}
+
+#[derive(Default)]
+pub struct SelectionSize {
+    /// The selection being added up, how far it got and the spinner's turn.
+    counting: Option<(u64, Total, usize)>,
+    counted: Option<(u64, Total)>,
+    cancelled: Option<u64>,
+}
+
+impl SelectionSize {
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Counting(selection, total) => {
+                let turn = match self.counting {
+                    Some((counting, _, turn)) if counting == selection => turn + 1,
+                    _ => 0,
+                };
+                self.counting = Some((selection, total, turn));
+            }
+            Message::Counted(selection, total) => {
+                self.counting = None;
+                self.counted = Some((selection, total));
+            }
+            Message::Cancel(selection) => {
+                self.counting = None;
+                self.cancelled = Some(selection);
+            }
+            Message::Restart => self.cancelled = None,
+        }
+    }
+
+    /// Drops the last total, as something in the listing changed.
+    pub fn forget(&mut self) {
+        self.counted = None;
+    }
+
+    /// Adds up `items`, the selection with fingerprint `selection`: the
+    /// files' sizes as given and the folders, given `None`, by walking them.
+    pub fn subscription(
+        &self,
+        selection: u64,
+        items: Vec<(PathBuf, Option<u64>)>,
+    ) -> Subscription<Message> {
+        struct Counting;
+
+        let done = self
+            .counted
+            .is_some_and(|(counted, _)| counted == selection);
+        if done || self.cancelled == Some(selection) || items.iter().all(|(_, size)| size.is_some())
+        {
+            return Subscription::none();
+        }
+
+        subscription::channel(
+            (TypeId::of::<Counting>(), selection),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    std::thread::sleep(DELAY);
+                    let mut total = Total::default();
+                    let mut sent = Instant::now();
+                    // False once the selection moved on and no one listens.
+                    let mut report = |total: Total| {
+                        if sent.elapsed() < rendering::update_interval() {
+                            return !sender.is_closed();
+                        }
+                        sent = Instant::now();
+                        sender
+                            .unbounded_send(Message::Counting(selection, total))
+                            .is_ok()
+                    };
+                    if !report(total) {
+                        return;
+                    }
+                    for (path, size) in items {
+                        match size {
+                            Some(size) => total.bytes += size,
+                            None => match fs::symlink_metadata(&path) {
+                                Ok(metadata) if !metadata.is_dir() => total.bytes += metadata.len(),
+                                _ => {
+                                    if !walk(&path, &mut total, &mut report) {
+                                        return;
+                                    }
+                                }
+                            },
+                        }
+                    }
+                    let _ = sender.unbounded_send(Message::Counted(selection, total));
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    /// How many are selected and what they add up to, or how far that has
+    /// got; `known` is the total when nothing needs walking.
+    pub fn view<'a>(
+        &self,
+        selection: u64,
+        count: &str,
+        known: Option<Total>,
+        style: &Style,
+    ) -> Element<'a, Message> {
+        let label = |content: String| text(content).size(style.small_text_size);
+        let total = known.or_else(|| {
+            self.counted
+                .filter(|&(counted, _)| counted == selection)
+                .map(|(_, total)| total)
+        });
+        if let Some(total) = total {
+            let bytes = format_bytes(total.bytes);
+            return label(match total.partial {
+                true => trf("{} selected, at least {}", &[&count, &bytes]),
+                false => trf("{} selected, {}", &[&count, &bytes]),
+            })
+            .into();
+        }
+
+        let small_button = |content: &'static str, message| {
+            button(text(content).size(style.small_text_size))
+                .padding([0, 6])
+                .style(iced::theme::Button::Text)
+                .on_press(message)
+        };
+        if self.cancelled == Some(selection) {
+            return row!(
+                label(trf("{} selected", &[&count])),
+                small_button(tr("Add up sizes"), Message::Restart)
+            )
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into();
+        }
+        let (so_far, turn) = match self.counting {
+            Some((counting, so_far, turn)) if counting == selection => (so_far.bytes, turn),
+            _ => (0, 0),
+        };
+        row!(
+            label(trf(
+                "{} {} selected, adding up… {}",
+                &[
+                    &SPINNER[turn % SPINNER.len()],
+                    &count,
+                    &format_bytes(so_far)
+                ],
+            )),
+            small_button(tr("Cancel"), Message::Cancel(selection))
+        )
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+    }
+}
+
+/// Adds up what is below `dir`, not following symlinks; false when told to
+/// stop by `report`.
+fn walk(dir: &Path, total: &mut Total, report: &mut impl FnMut(Total) -> bool) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        total.partial = true;
+        return true;
+    };
+    for entry in entries {
+        let Ok(entry) = entry else {
+            total.partial = true;
+            continue;
+        };
+        match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => {
+                if !walk(&entry.path(), total, report) {
+                    return false;
+                }
+            }
+            Ok(metadata) => total.bytes += metadata.len(),
+            Err(_) => total.partial = true,
+        }
+        if !report(*total) {
+            return false;
+        }
+    }
+    true
+}
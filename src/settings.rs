@@ -0,0 +1,208 @@
+//! "Settings…": the everyday preferences, each kept in the configuration
+//! file as soon as it is changed. The rarer ones stay under the advanced
+//! options.
+
+use iced::widget::{button, checkbox, column, container, pick_list, row, text, text_input};
+use iced::{theme, Alignment, Element, Length, Theme};
+
+use crate::config::{
+    Config, GroupBy, ManyItems, MaxFps, NameOrder, Renderer, SizeUnits, SortBy, TimeFormat,
+    ViewMode,
+};
+use crate::i18n::tr;
+use crate::style::Style;
+
+#[derive(Debug, Clone)]
+pub enum Setting {
+    Theme(Theme),
+    StripedRows(bool),
+    ViewMode(ViewMode),
+    SizeUnits(SizeUnits),
+    TimeFormat(TimeFormat),
+    TimePattern(String),
+    RelativeTimes(bool),
+    ShowHidden(bool),
+    ConfirmDelete(bool),
+    AskOver(ManyItems),
+    ConfirmOverwrite(bool),
+    ConfirmSystem(bool),
+    Sort(SortBy),
+    NameOrder(NameOrder),
+    GroupBy(GroupBy),
+    DoubleClick(bool),
+    Terminal(String),
+    Renderer(Renderer),
+    Antialiasing(bool),
+    MaxFps(MaxFps),
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Change(Setting),
+    Close,
+}
+
+pub enum Event {
+    Changed(Setting),
+    Closed,
+}
+
+#[derive(Default)]
+pub struct Settings;
+
+impl Settings {
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Change(setting) => Some(Event::Changed(setting)),
+            Message::Close => Some(Event::Closed),
+        }
+    }
+
+    pub fn view<'a>(&self, config: &'a Config, style: &Style) -> Element<'a, Message> {
+        let labelled = |label, control: Element<'a, Message>| {
+            row!(text(label).width(Length::Fill), control)
+                .spacing(10)
+                .align_items(Alignment::Center)
+        };
+
+        let col = column!(
+            text(tr("Settings")).size(20),
+            labelled(
+                tr("Theme"),
+                pick_list(config.themes(), Some(config.theme.clone()), move |theme| {
+                    Message::Change(Setting::Theme(theme))
+                })
+                .into(),
+            ),
+            labelled(
+                tr("View"),
+                pick_list(&ViewMode::ALL[..], Some(config.view_mode), move |mode| {
+                    Message::Change(Setting::ViewMode(mode))
+                })
+                .into(),
+            ),
+            labelled(
+                tr("Sort by"),
+                pick_list(&SortBy::ALL[..], Some(config.sort), move |sort| {
+                    Message::Change(Setting::Sort(sort))
+                })
+                .into(),
+            ),
+            labelled(
+                tr("Group by"),
+                pick_list(&GroupBy::ALL[..], Some(config.group_by), move |by| {
+                    Message::Change(Setting::GroupBy(by))
+                })
+                .into(),
+            ),
+            text(tr(
+                "The view, sort and grouping of folders not changed from the View menu."
+            ))
+            .size(style.small_text_size),
+            labelled(
+                tr("Names"),
+                pick_list(&NameOrder::ALL[..], Some(config.name_order), move |order| {
+                    Message::Change(Setting::NameOrder(order))
+                })
+                .into(),
+            ),
+            labelled(
+                tr("Sizes"),
+                pick_list(&SizeUnits::ALL[..], Some(config.size_units), move |units| {
+                    Message::Change(Setting::SizeUnits(units))
+                })
+                .into(),
+            ),
+            labelled(
+                tr("Times"),
+                pick_list(
+                    &TimeFormat::ALL[..],
+                    Some(config.time_format),
+                    move |format| { Message::Change(Setting::TimeFormat(format)) }
+                )
+                .into(),
+            ),
+            labelled(
+                tr("Custom pattern"),
+                text_input("%d/%m/%Y %H:%M", &config.time_pattern)
+                    .on_input(move |pattern| Message::Change(Setting::TimePattern(pattern)))
+                    .width(Length::Fixed(260.))
+                    .into(),
+            ),
+            checkbox(
+                tr("Write times of the last week as how long ago"),
+                config.relative_times
+            )
+            .on_toggle(move |relative| Message::Change(Setting::RelativeTimes(relative))),
+            checkbox(tr("Alternate row backgrounds"), config.striped_rows)
+                .on_toggle(move |striped| Message::Change(Setting::StripedRows(striped))),
+            checkbox(tr("Show hidden files"), !config.hide_hidden)
+                .on_toggle(move |show| Message::Change(Setting::ShowHidden(show))),
+            checkbox(
+                tr("Ask before deleting permanently"),
+                !config.delete_without_asking
+            )
+            .on_toggle(move |ask| Message::Change(Setting::ConfirmDelete(ask))),
+            checkbox(
+                tr("Ask before replacing what is in the way of a copy or move"),
+                !config.overwrite_without_asking
+            )
+            .on_toggle(move |ask| Message::Change(Setting::ConfirmOverwrite(ask))),
+            checkbox(
+                tr("Ask before trashing, deleting or moving system folders"),
+                !config.system_without_asking
+            )
+            .on_toggle(move |ask| Message::Change(Setting::ConfirmSystem(ask))),
+            labelled(
+                tr("Ask before trashing or deleting"),
+                pick_list(&ManyItems::ALL[..], Some(config.ask_over), move |many| {
+                    Message::Change(Setting::AskOver(many))
+                })
+                .into(),
+            ),
+            checkbox(
+                tr("Open items with a double click, selecting them with one"),
+                config.double_click
+            )
+            .on_toggle(move |double| Message::Change(Setting::DoubleClick(double))),
+            labelled(
+                tr("Terminal"),
+                text_input(tr("Found on the system"), &config.terminal)
+                    .on_input(move |command| Message::Change(Setting::Terminal(command)))
+                    .width(Length::Fixed(260.))
+                    .into(),
+            ),
+            labelled(
+                tr("Draw with"),
+                pick_list(&Renderer::ALL[..], Some(config.renderer), move |renderer| {
+                    Message::Change(Setting::Renderer(renderer))
+                })
+                .into(),
+            ),
+            checkbox(tr("Smooth edges"), config.antialiasing)
+                .on_toggle(move |smooth| Message::Change(Setting::Antialiasing(smooth))),
+            text(tr("These two take effect the next time the app starts."))
+                .size(style.small_text_size),
+            labelled(
+                tr("Redraw while working at most"),
+                pick_list(&MaxFps::ALL[..], Some(config.max_fps), move |fps| {
+                    Message::Change(Setting::MaxFps(fps))
+                })
+                .into(),
+            ),
+            text(tr("Saved as soon as changed.")).size(style.small_text_size),
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Close")).on_press(Message::Close),
+            ),
+        )
+        .spacing(12);
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(520)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
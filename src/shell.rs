@@ -0,0 +1,171 @@
+//! "Run command in this folder…": a shell command run with the folder as
+//! its working directory, and what it printed once it is done.
+
+use std::any::TypeId;
+use std::path::PathBuf;
+
+use iced::widget::{button, column, container, row, scrollable, text, text_input};
+use iced::{subscription, theme, Alignment, Element, Font, Length, Subscription};
+
+use crate::i18n::{tr, trf};
+use crate::style::Style;
+use crate::system::{self, Ran};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Input(String),
+    Run,
+    Finished(usize, Result<Ran, String>),
+    Close,
+}
+
+pub enum Event {
+    Closed,
+}
+
+pub struct Shell {
+    dir: PathBuf,
+    input: String,
+    /// The command running, if one is.
+    running: Option<String>,
+    /// Counts runs so a new one ignores what an earlier one sends.
+    run: usize,
+    last: Option<Result<Ran, String>>,
+}
+
+impl Shell {
+    pub fn open(dir: PathBuf) -> Shell {
+        Shell {
+            dir,
+            input: String::new(),
+            running: None,
+            run: 0,
+            last: None,
+        }
+    }
+
+    /// Opened with `command` already running.
+    pub fn run(dir: PathBuf, command: String) -> Shell {
+        let mut shell = Shell::open(dir);
+        shell.input = command;
+        shell.update(Message::Run);
+
+        shell
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Input(input) => self.input = input,
+            Message::Run => {
+                if self.running.is_none() && !self.input.trim().is_empty() {
+                    self.run += 1;
+                    self.running = Some(self.input.trim().to_string());
+                }
+            }
+            Message::Finished(run, _) if run != self.run => {}
+            Message::Finished(_, result) => {
+                self.running = None;
+                self.last = Some(result);
+            }
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Running;
+
+        let Some(command) = self.running.clone() else {
+            return Subscription::none();
+        };
+        let (run, dir) = (self.run, self.dir.clone());
+        subscription::channel(
+            (TypeId::of::<Running>(), run),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let result = system::run_in(&dir, &command).map_err(|error| error.to_string());
+                    sender.send(result)
+                });
+                if let Ok(result) = receiver.await {
+                    let _ = output.send(Message::Finished(run, result)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let name = self
+            .dir
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_else(|| self.dir.to_string_lossy());
+        let mut input = text_input(tr("Command"), &self.input)
+            .on_input(Message::Input)
+            .font(Font::MONOSPACE)
+            .padding(5);
+        let run = (self.running.is_none() && !self.input.trim().is_empty()).then_some(Message::Run);
+        if let Some(run) = run.clone() {
+            input = input.on_submit(run);
+        }
+        let mut col = column!(text(trf("Run in {}", &[&name])).size(20), input).spacing(10);
+
+        if let Some(command) = &self.running {
+            col = col.push(text(trf("Running {}…", &[&command])).size(style.small_text_size));
+        }
+        match &self.last {
+            None => {}
+            Some(Err(error)) => {
+                col = col.push(
+                    text(trf("Cannot run it: {}", &[&error]))
+                        .style(theme::Text::Color(style.error)),
+                )
+            }
+            Some(Ok(ran)) => {
+                if !ran.output.is_empty() {
+                    col = col.push(
+                        container(
+                            scrollable(text(&ran.output).font(Font::MONOSPACE).size(13))
+                                .width(Length::Fill),
+                        )
+                        .max_height(320)
+                        .padding(5)
+                        .style(theme::Container::Box),
+                    );
+                }
+                let status = match ran.code {
+                    _ if ran.truncated => text(tr("Stopped for printing too much")),
+                    Some(0) => text(tr("Done")),
+                    Some(code) => text(trf("Exited with code {}", &[&code]))
+                        .style(theme::Text::Color(style.error)),
+                    None => text(tr("Stopped by a signal")).style(theme::Text::Color(style.error)),
+                };
+                col = col.push(status.size(style.small_text_size));
+            }
+        }
+
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Close")).on_press(Message::Close),
+                button(tr("Run")).on_press_maybe(run),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(720)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::{Path, PathBuf};
+
+use iced::widget::{button, column, progress_bar, row, scrollable, text, tooltip};
+use iced::{theme, Alignment, Color, Element, Length};
+
+use crate::drag::Drag;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::mounts::{Volume, VolumeKind};
+use crate::paths;
+use crate::remote;
+use crate::udisks::Drive;
+use crate::webdav;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Volumes(Vec<Volume>, Vec<Drive>),
+    Open(PathBuf),
+    Mount(Drive),
+    Eject(PathBuf),
+    Drop(PathBuf),
+}
+
+pub enum Event {
+    VolumesChanged,
+    Open(PathBuf),
+    Mount(Drive),
+    Eject(PathBuf),
+    /// Something dragged was let go on this place.
+    Drop(PathBuf),
+}
+
+#[derive(Default)]
+pub struct Sidebar {
+    volumes: Vec<Volume>,
+    /// Plugged in, but not mounted.
+    drives: Vec<Drive>,
+}
+
+impl Sidebar {
+    pub fn volumes(&self) -> &[Volume] {
+        &self.volumes
+    }
+
+    pub fn drives(&self) -> &[Drive] {
+        &self.drives
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Volumes(volumes, drives) => {
+                tracing::debug!(
+                    volumes = volumes.len(),
+                    drives = drives.len(),
+                    "volumes listed"
+                );
+                self.volumes = volumes;
+                self.drives = drives;
+                Some(Event::VolumesChanged)
+            }
+            Message::Open(path) => Some(Event::Open(path)),
+            Message::Mount(drive) => Some(Event::Mount(drive)),
+            Message::Eject(path) => Some(Event::Eject(path)),
+            Message::Drop(path) => Some(Event::Drop(path)),
+        }
+    }
+
+    /// `remotes` are the bookmarked folders on other machines and `recent`
+    /// those visited last. `highlight` outlines the place under the mouse
+    /// while something is being dragged.
+    pub fn view(
+        &self,
+        remotes: &[PathBuf],
+        recent: &[&Path],
+        highlight: Option<Color>,
+    ) -> Element<'_, Message> {
+        let mut col = column!(text(tr("Devices")).size(14)).spacing(5);
+
+        for volume in &self.volumes {
+            let used = volume.total.saturating_sub(volume.free);
+            let mut details = column!(
+                text(&volume.label),
+                text(trf(
+                    "{} free of {}",
+                    &[&format_bytes(volume.free), &format_bytes(volume.total)]
+                ))
+                .size(12),
+            )
+            .spacing(2)
+            .width(Length::Fill);
+            if volume.total > 0 {
+                details = details.push(
+                    progress_bar(0.0..=volume.total as f32, used as f32).height(Length::Fixed(4.)),
+                );
+            }
+
+            let place = Drag::new(
+                button(details)
+                    .on_press(Message::Open(volume.mount_point.clone()))
+                    .width(Length::Fill),
+            )
+            .on_drop(Some(Message::Drop(volume.mount_point.clone())))
+            .highlight(highlight);
+            let entry = tooltip(
+                place,
+                text(format!(
+                    "{} ({})",
+                    volume.mount_point.display(),
+                    volume.fs_type
+                )),
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box);
+
+            let mut item = row!(entry).spacing(2).align_items(Alignment::Center);
+            if volume.kind == VolumeKind::Removable {
+                item = item.push(
+                    tooltip(
+                        button("⏏").on_press(Message::Eject(volume.mount_point.clone())),
+                        text(tr("Eject")),
+                        tooltip::Position::Bottom,
+                    )
+                    .style(theme::Container::Box),
+                );
+            }
+            col = col.push(item);
+        }
+        for drive in &self.drives {
+            let details = column!(
+                text(&drive.label),
+                text(trf("{}, not mounted", &[&format_bytes(drive.size)])).size(12),
+            )
+            .spacing(2)
+            .width(Length::Fill);
+            col = col.push(
+                tooltip(
+                    button(details)
+                        .on_press(Message::Mount(drive.clone()))
+                        .style(theme::Button::Secondary)
+                        .width(Length::Fill),
+                    text(trf("Mount {}", &[&drive.device])),
+                    tooltip::Position::Bottom,
+                )
+                .style(theme::Container::Box),
+            );
+        }
+
+        if !remotes.is_empty() {
+            col = col.push(text(tr("Remote")).size(14));
+        }
+        for bookmark in remotes {
+            let place = Drag::new(
+                button(text(label(bookmark)))
+                    .on_press(Message::Open(bookmark.clone()))
+                    .width(Length::Fill),
+            )
+            .on_drop(Some(Message::Drop(bookmark.clone())))
+            .highlight(highlight);
+            col = col.push(place);
+        }
+
+        if !recent.is_empty() {
+            col = col.push(text(tr("Recent")).size(14));
+        }
+        for &dir in recent {
+            let name = match dir.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => paths::display(dir),
+            };
+            let place = Drag::new(
+                button(text(name))
+                    .on_press(Message::Open(dir.to_path_buf()))
+                    .width(Length::Fill),
+            )
+            .on_drop(Some(Message::Drop(dir.to_path_buf())))
+            .highlight(highlight);
+            col = col.push(
+                tooltip(place, text(paths::display(dir)), tooltip::Position::Bottom)
+                    .style(theme::Container::Box),
+            );
+        }
+
+        scrollable(col)
+            .width(Length::Fixed(220.))
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// A remote place as listed, without its scheme.
+pub fn label(bookmark: &Path) -> String {
+    let label = bookmark.to_string_lossy();
+    [remote::SCHEME, webdav::SCHEME, webdav::SECURE_SCHEME]
+        .iter()
+        .fold(label.as_ref(), |label, scheme| {
+            label.trim_start_matches(scheme)
+        })
+        .to_string()
+}
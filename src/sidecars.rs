@@ -0,0 +1,247 @@
+//! Checksums published next to files, in `name.sha256`-style files or a
+//! folder's `SHA256SUMS`: the listing badges the files they cover and
+//! checks them all on request.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+
+use crate::checksum;
+use crate::devices;
+use crate::i18n::trf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Unchecked,
+    Checking,
+    Matches,
+    Differs,
+    Failed(String),
+}
+
+/// A checksum for one file and what checking it gave.
+#[derive(Debug, Clone)]
+pub struct Sidecar {
+    pub sum: String,
+    /// The checksum file it is in.
+    pub list: PathBuf,
+    pub verdict: Verdict,
+}
+
+impl Sidecar {
+    pub fn label(&self) -> &'static str {
+        match self.verdict {
+            Verdict::Unchecked | Verdict::Checking => "#",
+            Verdict::Matches => "✓",
+            Verdict::Differs | Verdict::Failed(_) => "✗",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let list = self.list.file_name().unwrap_or_default().to_string_lossy();
+        match &self.verdict {
+            Verdict::Unchecked => trf("Has a checksum in {}", &[&list]),
+            Verdict::Checking => trf("Checking against {}…", &[&list]),
+            Verdict::Matches => trf("Matches the checksum in {}", &[&list]),
+            Verdict::Differs => trf("Does not match the checksum in {}", &[&list]),
+            Verdict::Failed(error) => trf("Cannot check against {}: {}", &[&list, &error]),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// What the listing `usize` found.
+    Found(usize, HashMap<PathBuf, (String, PathBuf)>),
+    VerifyAll,
+    Verified(usize, PathBuf, Result<bool, String>),
+}
+
+pub enum Event {
+    /// Every file was checked; those that do not match, then those that
+    /// could not be read.
+    Verified {
+        checked: usize,
+        differ: Vec<PathBuf>,
+        failed: Vec<PathBuf>,
+    },
+}
+
+#[derive(Default)]
+pub struct Sidecars {
+    /// The folder looked in; `None` where files are not local.
+    dir: Option<PathBuf>,
+    listing: usize,
+    found: HashMap<PathBuf, Sidecar>,
+    /// Set to abandon the files being checked.
+    stop: Arc<AtomicBool>,
+}
+
+impl Sidecars {
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Found(listing, found) => {
+                if listing < self.listing {
+                    return None;
+                }
+                self.listing = listing;
+                let known = std::mem::take(&mut self.found);
+                self.found = found
+                    .into_iter()
+                    .map(|(path, (sum, list))| {
+                        // Checked already, unless the checksum changed.
+                        let verdict = known
+                            .get(&path)
+                            .filter(|known| known.sum == sum)
+                            .map_or(Verdict::Unchecked, |known| known.verdict.clone());
+                        (path, Sidecar { sum, list, verdict })
+                    })
+                    .collect();
+            }
+            Message::VerifyAll => {
+                for sidecar in self.found.values_mut() {
+                    sidecar.verdict = Verdict::Checking;
+                }
+            }
+            Message::Verified(listing, path, result) => {
+                let sidecar = self
+                    .found
+                    .get_mut(&path)
+                    .filter(|_| listing == self.listing)?;
+                sidecar.verdict = match result {
+                    Ok(true) => Verdict::Matches,
+                    Ok(false) => Verdict::Differs,
+                    Err(error) => Verdict::Failed(error),
+                };
+                if self.is_checking() {
+                    return None;
+                }
+                let mut differ = Vec::new();
+                let mut failed = Vec::new();
+                for (path, sidecar) in &self.found {
+                    match sidecar.verdict {
+                        Verdict::Differs => differ.push(path.clone()),
+                        Verdict::Failed(_) => failed.push(path.clone()),
+                        _ => {}
+                    }
+                }
+                differ.sort();
+                failed.sort();
+                return Some(Event::Verified {
+                    checked: self.found.len(),
+                    differ,
+                    failed,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Starts over for the folder `dir`, stopping any checks elsewhere.
+    pub fn follow(&mut self, dir: Option<&Path>) {
+        if self.dir.as_deref() != dir {
+            self.stop.store(true, Ordering::Relaxed);
+            self.stop = Arc::new(AtomicBool::new(false));
+            self.dir = dir.map(Path::to_path_buf);
+            self.found.clear();
+        }
+    }
+
+    /// Takes back what checking `path` gave, as it changed since.
+    pub fn forget(&mut self, path: &Path) {
+        if let Some(sidecar) = self.found.get_mut(path) {
+            if sidecar.verdict != Verdict::Checking {
+                sidecar.verdict = Verdict::Unchecked;
+            }
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Sidecar> {
+        self.found.get(path)
+    }
+
+    pub fn count(&self) -> usize {
+        self.found.len()
+    }
+
+    pub fn is_checking(&self) -> bool {
+        self.found
+            .values()
+            .any(|sidecar| sidecar.verdict == Verdict::Checking)
+    }
+
+    pub fn subscription(&self, listing: usize) -> Subscription<Message> {
+        struct Finding;
+        struct Checking;
+
+        let Some(dir) = self.dir.clone() else {
+            return Subscription::none();
+        };
+        let finding = subscription::channel(
+            (TypeId::of::<Finding>(), listing),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || sender.send(checksum::sidecars(&dir)));
+                if let Ok(found) = receiver.await {
+                    let _ = output.send(Message::Found(listing, found)).await;
+                }
+
+                std::future::pending().await
+            },
+        );
+
+        let pending: Vec<(PathBuf, String)> = self
+            .found
+            .iter()
+            .filter(|(_, sidecar)| sidecar.verdict == Verdict::Checking)
+            .map(|(path, sidecar)| (path.clone(), sidecar.sum.clone()))
+            .collect();
+        if pending.is_empty() {
+            return finding;
+        }
+        let listing = self.listing;
+        let stop = self.stop.clone();
+        let checking = subscription::channel(
+            (TypeId::of::<Checking>(), listing),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    // One at a time, as reading is what takes long.
+                    for (path, sum) in pending {
+                        let Some(_turn) =
+                            devices::wait_for(&[&path], || stop.load(Ordering::Relaxed))
+                        else {
+                            break;
+                        };
+                        let result = checksum::verify(&path, &sum, &stop);
+                        if sender
+                            .unbounded_send(Message::Verified(listing, path, result))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        );
+
+        Subscription::batch([finding, checking])
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Filesystems that store neither alternate data streams nor resource forks.
+const PLAIN_FS_TYPES: [&str; 6] = ["vfat", "msdos", "exfat", "FAT", "FAT32", "exFAT"];
+
+/// Data kept next to a file's main contents: NTFS alternate data streams on
+/// Windows, extended attributes (resource forks among them) on macOS.
+#[derive(Debug, Clone)]
+pub struct Stream {
+    pub name: String,
+    pub size: u64,
+}
+
+/// A `._name` file macOS writes beside `name` on filesystems that cannot
+/// hold its metadata.
+pub fn is_apple_double(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("._"))
+        && path.is_file()
+}
+
+/// Whether copying to a filesystem of this type loses the streams.
+pub fn drops_streams(fs_type: &str) -> bool {
+    PLAIN_FS_TYPES.contains(&fs_type)
+}
+
+/// Every AppleDouble file at or below `root`, symlinks not followed.
+pub fn apple_double_files(root: &Path) -> Vec<PathBuf> {
+    let mut pending = VecDeque::from([root.to_path_buf()]);
+    let mut found = Vec::new();
+
+    while let Some(dir) = pending.pop_front() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => pending.push_back(path),
+                Ok(kind) if kind.is_file() && is_apple_double(&path) => found.push(path),
+                _ => {}
+            }
+        }
+    }
+
+    found
+}
+
+#[cfg(windows)]
+pub fn streams(path: &Path) -> Vec<Stream> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+        WIN32_FIND_STREAM_DATA,
+    };
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut found = Vec::new();
+
+    // SAFETY: `wide` is NUL-terminated, `data` is the struct the standard
+    // info level fills in, and the handle is closed before returning.
+    unsafe {
+        let mut data: WIN32_FIND_STREAM_DATA = std::mem::zeroed();
+        let handle = FindFirstStreamW(
+            wide.as_ptr(),
+            FindStreamInfoStandard,
+            &mut data as *mut _ as *mut _,
+            0,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return found;
+        }
+
+        loop {
+            let end = data
+                .cStreamName
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(data.cStreamName.len());
+            let name = String::from_utf16_lossy(&data.cStreamName[..end]);
+            // Names look like ":name:$DATA"; "::$DATA" is the main contents.
+            let name = name.trim_start_matches(':').trim_end_matches(":$DATA");
+            if !name.is_empty() {
+                found.push(Stream {
+                    name: name.to_string(),
+                    size: data.StreamSize as u64,
+                });
+            }
+            if FindNextStreamW(handle, &mut data as *mut _ as *mut _) == 0 {
+                break;
+            }
+        }
+        FindClose(handle);
+    }
+
+    found
+}
+
+#[cfg(target_os = "macos")]
+pub fn streams(path: &Path) -> Vec<Stream> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return Vec::new();
+    };
+
+    // SAFETY: `path` is NUL-terminated, the buffers are passed with their
+    // lengths, and the names are NUL-separated within what was written.
+    unsafe {
+        let len = libc::listxattr(path.as_ptr(), std::ptr::null_mut(), 0, libc::XATTR_NOFOLLOW);
+        if len <= 0 {
+            return Vec::new();
+        }
+        let mut names = vec![0 as libc::c_char; len as usize];
+        let len = libc::listxattr(
+            path.as_ptr(),
+            names.as_mut_ptr(),
+            names.len(),
+            libc::XATTR_NOFOLLOW,
+        );
+        if len <= 0 {
+            return Vec::new();
+        }
+
+        names[..len as usize]
+            .split(|&c| c == 0)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| {
+                let name = CString::new(name.iter().map(|&c| c as u8).collect::<Vec<_>>()).ok()?;
+                let size = libc::getxattr(
+                    path.as_ptr(),
+                    name.as_ptr(),
+                    std::ptr::null_mut(),
+                    0,
+                    0,
+                    libc::XATTR_NOFOLLOW,
+                );
+                Some(Stream {
+                    name: CStr::to_string_lossy(&name).into_owned(),
+                    size: size.max(0) as u64,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Other platforms neither copy nor expose such data.
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn streams(_path: &Path) -> Vec<Stream> {
+    Vec::new()
+}
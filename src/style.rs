@@ -0,0 +1,179 @@
+use iced::widget::button;
+use iced::{Background, Border, Color, Font, Theme};
+
+/// The parts of the picker's look that do not come from the iced `Theme`
+/// it is drawn with. Hosts embedding the picker set their own through
+/// `FilePicker::set_style` to match the rest of the application.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub error: Color,
+    pub warning: Color,
+    /// Files a dialog's filter rejects.
+    pub dimmed: Color,
+    /// Outline of the folder or place something dragged would land in.
+    pub drop_target: Color,
+    /// Badges of changes staged in version control.
+    pub added: Color,
+    /// Height of each row in the listing and of its header.
+    pub row_height: f32,
+    /// For file names; `None` keeps the application's default font.
+    pub font: Option<Font>,
+    /// For notices, errors and other secondary text.
+    pub small_text_size: f32,
+    pub icons: Icons,
+    /// The syntect theme source files are previewed in; `None`, or one
+    /// there is not, picks one for the light or the dark iced theme.
+    pub syntax_theme: Option<String>,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            error: Color::from_rgb(0.8, 0.2, 0.2),
+            warning: Color::from_rgb(0.8, 0.6, 0.1),
+            dimmed: Color::from_rgb(0.6, 0.6, 0.6),
+            drop_target: Color::from_rgb(0.2, 0.5, 0.9),
+            added: Color::from_rgb(0.2, 0.6, 0.3),
+            row_height: 48.,
+            font: None,
+            small_text_size: 14.,
+            icons: Icons::default(),
+            syntax_theme: None,
+        }
+    }
+}
+
+/// Glyphs put before and after names in the listing, such as code points
+/// of an icon font given as `font`. Empty strings show nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Icons {
+    pub folder: String,
+    pub file: String,
+    /// On the icon of a symlink.
+    pub link: String,
+    /// On the icon of what cannot be written to.
+    pub read_only: String,
+    /// Glyphs for kinds of files instead of `file`, the first that fits:
+    /// for a MIME type such as "application/pdf", the start of one such as
+    /// "image/", or the icon a desktop entry names.
+    pub kinds: Vec<(String, String)>,
+    /// For the glyphs; `None` draws them in the name's font.
+    pub font: Option<Font>,
+}
+
+impl Default for Icons {
+    fn default() -> Icons {
+        Icons {
+            folder: String::new(),
+            file: String::new(),
+            link: String::from("⤷"),
+            read_only: String::from("🔒"),
+            kinds: Vec::new(),
+            font: None,
+        }
+    }
+}
+
+impl Icons {
+    /// The glyph for `kind`, as `kinds` has it.
+    pub fn for_kind(&self, kind: &str) -> Option<&str> {
+        self.kinds
+            .iter()
+            .find(|(pattern, _)| match pattern.strip_suffix('/') {
+                Some(top) => kind
+                    .strip_prefix(top)
+                    .is_some_and(|rest| rest.starts_with('/')),
+                None => pattern == kind,
+            })
+            .map(|(_, glyph)| glyph.as_str())
+    }
+}
+
+/// A listing row drawn flat on `background`, for striped and selected
+/// rows; the others keep the theme's button look.
+pub struct Row {
+    pub background: Option<Color>,
+}
+
+impl Row {
+    /// Behind selected rows and every other row, from the theme's palette
+    /// unless a color scheme gives its own.
+    pub fn colors(
+        theme: &Theme,
+        selection: Option<Color>,
+        stripe: Option<Color>,
+    ) -> (Color, Color) {
+        let palette = theme.palette();
+        let mix = |amount: f32| Color {
+            r: palette.background.r + (palette.text.r - palette.background.r) * amount,
+            g: palette.background.g + (palette.text.g - palette.background.g) * amount,
+            b: palette.background.b + (palette.text.b - palette.background.b) * amount,
+            a: 1.,
+        };
+        let faded = Color {
+            a: 0.35,
+            ..palette.primary
+        };
+        (selection.unwrap_or(faded), stripe.unwrap_or(mix(0.05)))
+    }
+}
+
+impl button::StyleSheet for Row {
+    type Style = Theme;
+
+    fn active(&self, theme: &Theme) -> button::Appearance {
+        button::Appearance {
+            background: self.background.map(Background::Color),
+            text_color: theme.palette().text,
+            border: Border::with_radius(2),
+            ..button::Appearance::default()
+        }
+    }
+
+    fn hovered(&self, theme: &Theme) -> button::Appearance {
+        let hover = Color {
+            a: 0.15,
+            ..theme.palette().primary
+        };
+        button::Appearance {
+            background: Some(Background::Color(match self.background {
+                Some(background) if background.a >= 0.3 => background,
+                _ => hover,
+            })),
+            ..self.active(theme)
+        }
+    }
+}
+
+/// A button drawn in the look it wraps with a ring around it, where the
+/// keyboard is.
+pub struct Focused(pub iced::theme::Button);
+
+impl Focused {
+    fn ring(theme: &Theme, appearance: button::Appearance) -> button::Appearance {
+        button::Appearance {
+            border: Border {
+                color: theme.palette().primary,
+                width: 2.,
+                radius: appearance.border.radius,
+            },
+            ..appearance
+        }
+    }
+}
+
+impl button::StyleSheet for Focused {
+    type Style = Theme;
+
+    fn active(&self, theme: &Theme) -> button::Appearance {
+        Focused::ring(theme, theme.active(&self.0))
+    }
+
+    fn hovered(&self, theme: &Theme) -> button::Appearance {
+        Focused::ring(theme, theme.hovered(&self.0))
+    }
+
+    fn pressed(&self, theme: &Theme) -> button::Appearance {
+        Focused::ring(theme, theme.pressed(&self.0))
+    }
+}
@@ -0,0 +1,84 @@
+//! "Copy selection summary": the selected entries as a Markdown table, with
+//! their sizes, folders' counted through, when they were modified and, if
+//! asked for, the SHA-256 of files, to paste into tickets and emails.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use crate::checksum;
+use crate::dates::format_time;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::properties;
+
+/// Reads every one of `paths`, so belongs off the UI thread.
+pub fn markdown(paths: &[PathBuf], checksums: bool) -> String {
+    let mut header = vec![tr("Name"), tr("Size"), tr("Modified")];
+    if checksums {
+        header.push("SHA-256");
+    }
+    let mut lines = vec![
+        row(header.iter().copied()),
+        row(header.iter().map(|_| "---")),
+    ];
+
+    let mut total = 0;
+    for path in paths {
+        let meta = fs::symlink_metadata(path);
+        let is_dir = meta.as_ref().is_ok_and(|meta| meta.is_dir());
+        let (size, bytes) = match &meta {
+            Ok(_) if is_dir => match properties::measure(path, &AtomicBool::new(false)) {
+                Ok((bytes, entries)) => (
+                    trf("{} in {} items", &[&format_bytes(bytes), &entries]),
+                    bytes,
+                ),
+                Err(_) => (String::from("?"), 0),
+            },
+            Ok(meta) => (format_bytes(meta.len()), meta.len()),
+            Err(_) => (String::from("?"), 0),
+        };
+        total += bytes;
+
+        let mut name = display_name(path);
+        if is_dir {
+            name.push('/');
+        }
+        let modified = format_time(meta.and_then(|meta| meta.modified()));
+        let mut cells = vec![name, size, modified];
+        if checksums {
+            cells.push(match is_dir {
+                true => String::new(),
+                false => sha256(path),
+            });
+        }
+        lines.push(row(cells.iter().map(String::as_str)));
+    }
+
+    lines.push(String::new());
+    lines.push(trf(
+        "{} items, {} in all",
+        &[&paths.len(), &format_bytes(total)],
+    ));
+    lines.join("\n")
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A table row, with the bars of its cells escaped.
+fn row<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells: Vec<String> = cells.map(|cell| cell.replace('|', "\\|")).collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+fn sha256(path: &Path) -> String {
+    match checksum::sha256(path, None, &AtomicBool::new(false)) {
+        Ok(digest) => digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        Err(_) => String::from("?"),
+    }
+}
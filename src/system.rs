@@ -0,0 +1,387 @@
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::{env, thread};
+
+use crate::mounts;
+
+/// Unmounts the volume holding `path` and, where the platform supports it,
+/// powers the device off so it can be unplugged.
+pub fn eject(path: &Path) -> io::Result<()> {
+    if cfg!(target_os = "macos") {
+        return run("diskutil", &["eject".as_ref(), path.as_os_str()]);
+    }
+
+    let mount = mounts::mount_for(path)
+        .filter(|mount| mount.mount_point != Path::new("/"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not on a removable volume"))?;
+
+    match run(
+        "udisksctl",
+        &["unmount".as_ref(), "-b".as_ref(), mount.device.as_ref()],
+    ) {
+        Ok(()) => {
+            // Not every device can be powered off; unmounting is what matters.
+            let _ = run(
+                "udisksctl",
+                &["power-off".as_ref(), "-b".as_ref(), mount.device.as_ref()],
+            );
+            Ok(())
+        }
+        Err(_) => run("umount", &[mount.mount_point.as_os_str()]),
+    }
+}
+
+/// Opens `path` with whatever the desktop associates with it; for macOS
+/// application bundles that means launching them.
+#[cfg(windows)]
+pub fn open(path: &Path) -> io::Result<()> {
+    shell_execute(path.as_os_str(), None)
+}
+
+#[cfg(not(windows))]
+pub fn open(path: &Path) -> io::Result<()> {
+    if cfg!(target_os = "macos") {
+        run("open", &[path.as_os_str()])
+    } else {
+        run("xdg-open", &[path.as_os_str()])
+    }
+}
+
+/// Has the shell open `file`, in `dir` when given. No command line is
+/// built, so nothing in the name is read as `cmd` syntax.
+#[cfg(windows)]
+fn shell_execute(file: &std::ffi::OsStr, dir: Option<&Path>) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::UI::Shell::ShellExecuteW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let wide = |text: &std::ffi::OsStr| -> Vec<u16> {
+        text.encode_wide().chain(std::iter::once(0)).collect()
+    };
+    let operation = wide("open".as_ref());
+    let file = wide(file);
+    let dir = dir.map(|dir| wide(dir.as_os_str()));
+    tracing::info!(file = ?String::from_utf16_lossy(&file), "opening through the shell");
+
+    // SAFETY: the strings are NUL-terminated and outlive the call.
+    let instance = unsafe {
+        ShellExecuteW(
+            0,
+            operation.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            dir.as_ref().map_or(std::ptr::null(), |dir| dir.as_ptr()),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Anything up to 32 is an error code.
+    match instance > 32 {
+        true => Ok(()),
+        false => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Terminal emulators looked for, in order, when none is set.
+#[cfg(all(unix, not(target_os = "macos")))]
+const TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "alacritty",
+    "foot",
+    "wezterm",
+    "xterm",
+];
+
+/// How much of a command's output is kept; it is stopped past that.
+const MAX_OUTPUT: u64 = 1024 * 1024;
+
+/// Opens a terminal in `dir`. `command` is the terminal to run, its words
+/// split at spaces; when it is empty the system's own is used.
+pub fn open_terminal(dir: &Path, command: &str) -> io::Result<()> {
+    let mut words: Vec<String> = command.split_whitespace().map(String::from).collect();
+    #[cfg(windows)]
+    if words.is_empty() {
+        return shell_execute("cmd.exe".as_ref(), Some(dir));
+    }
+    if words.is_empty() {
+        words = default_terminal()?;
+    }
+    let program = words.remove(0);
+    tracing::info!(program, args = ?words, dir = %dir.display(), "opening a terminal");
+
+    let mut child = Command::new(&program)
+        .args(&words)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reaped once closed, which may be long after.
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// The terminal the system offers, as words to run.
+fn default_terminal() -> io::Result<Vec<String>> {
+    let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+    if cfg!(target_os = "macos") {
+        return Ok(words(&["open", "-a", "Terminal", "."]));
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if let Some(terminal) = env::var("TERMINAL")
+            .ok()
+            .filter(|terminal| !terminal.is_empty())
+        {
+            return Ok(terminal.split_whitespace().map(String::from).collect());
+        }
+        if let Some(terminal) = TERMINALS.iter().find(|terminal| on_path(terminal)) {
+            return Ok(words(&[terminal]));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no terminal found; set one in the advanced options",
+    ))
+}
+
+pub fn on_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// `text` as one word for the shell [`run_in`] runs on Unix.
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// What a command run through [`run_in`] printed, stdout and stderr as
+/// they came.
+#[derive(Debug, Clone)]
+pub struct Ran {
+    pub output: String,
+    /// `None` when it was stopped by a signal, or for printing too much.
+    pub code: Option<i32>,
+    pub truncated: bool,
+}
+
+/// Runs `command` through the shell with `dir` as its working directory,
+/// waiting for it to finish. It reads nothing, so one waiting for input
+/// gets end-of-file.
+pub fn run_in(dir: &Path, command: &str) -> io::Result<Ran> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    tracing::info!(command, dir = %dir.display(), "running a command");
+
+    let (mut reader, writer) = io::pipe()?;
+    let mut child = shell
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(writer.try_clone()?)
+        .stderr(writer)
+        .spawn()?;
+    // Otherwise the pipe stays open after the command exits.
+    drop(shell);
+
+    let mut output = Vec::new();
+    (&mut reader).take(MAX_OUTPUT).read_to_end(&mut output)?;
+    let truncated = reader.read(&mut [0])? > 0;
+    if truncated {
+        let _ = child.kill();
+    }
+    let status = child.wait()?;
+
+    Ok(Ran {
+        output: String::from_utf8_lossy(&output).into_owned(),
+        code: status.code().filter(|_| !truncated),
+        truncated,
+    })
+}
+
+/// What an executable file is, to tell whether editing it makes sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Executable {
+    Program,
+    /// Text run by an interpreter, which can be edited.
+    Script,
+}
+
+/// Whether the file at `path` runs when launched: marked executable on
+/// Unix, by its extension on Windows.
+pub fn executable(path: &Path) -> Option<Executable> {
+    if !path.is_file() {
+        return None;
+    }
+    if cfg!(windows) {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        return match extension.as_str() {
+            "exe" | "com" => Some(Executable::Program),
+            "bat" | "cmd" => Some(Executable::Script),
+            _ => None,
+        };
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path).ok()?.permissions().mode();
+        if mode & 0o111 == 0 {
+            return None;
+        }
+    }
+    let mut start = [0; 2];
+    let script = std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut start))
+        .is_ok()
+        && &start == b"#!";
+
+    Some(match script {
+        true => Executable::Script,
+        false => Executable::Program,
+    })
+}
+
+/// Starts the program at `path` with `args`, in its own folder and
+/// without waiting for it.
+pub fn launch(path: &Path, args: &[String]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    tracing::info!(path = %path.display(), ?args, "launching");
+    let mut child = Command::new(path)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Whether [`open_as_administrator`] can ask for the password.
+pub fn can_elevate() -> bool {
+    cfg!(target_os = "linux") && on_path("pkexec")
+}
+
+/// Opens another picker at `dir` as root, asking for the password through
+/// polkit. `pkexec` clears the environment, so what reaches the display is
+/// handed on.
+pub fn open_as_administrator(dir: &Path) -> io::Result<()> {
+    if !can_elevate() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "opening as administrator needs pkexec",
+        ));
+    }
+    let mut variables = Vec::new();
+    for name in [
+        "DISPLAY",
+        "WAYLAND_DISPLAY",
+        "XAUTHORITY",
+        "XDG_RUNTIME_DIR",
+    ] {
+        if let Some(value) = env::var_os(name) {
+            let mut variable = std::ffi::OsString::from(format!("{}=", name));
+            variable.push(value);
+            variables.push(variable);
+        }
+    }
+    tracing::info!(dir = %dir.display(), "opening as administrator");
+    let mut child = Command::new("pkexec")
+        .arg("env")
+        .args(variables)
+        .arg(env::current_exe()?)
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+pub fn shut_down() -> io::Result<()> {
+    if cfg!(windows) {
+        run("shutdown", &["/s".as_ref(), "/t".as_ref(), "0".as_ref()])
+    } else if cfg!(target_os = "macos") {
+        run(
+            "osascript",
+            &[
+                "-e".as_ref(),
+                "tell app \"System Events\" to shut down".as_ref(),
+            ],
+        )
+    } else {
+        run("systemctl", &["poweroff".as_ref()])
+            .or_else(|_| run("shutdown", &["-h".as_ref(), "now".as_ref()]))
+    }
+}
+
+/// On Wayland and X11 the clipboard is served by the application that set
+/// it, so it empties when we exit. Handing `uris` to `wl-copy` or `xclip`,
+/// which stay in the background serving them, keeps them pasteable.
+#[cfg(target_os = "linux")]
+pub fn hand_off_clipboard(uris: &str) -> io::Result<()> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let (program, args): (&str, &[&str]) = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("wl-copy", &["--type", "text/uri-list"])
+    } else {
+        (
+            "xclip",
+            &["-selection", "clipboard", "-target", "text/uri-list", "-in"],
+        )
+    };
+    tracing::info!(program, "handing off the clipboard");
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(uris.as_bytes())?;
+    }
+    // Both fork into the background once they have read their input.
+    child.wait()?;
+
+    Ok(())
+}
+
+/// Other platforms keep clipboard contents after the owner exits.
+#[cfg(not(target_os = "linux"))]
+pub fn hand_off_clipboard(_uris: &str) -> io::Result<()> {
+    Ok(())
+}
+
+fn run(program: &str, args: &[&std::ffi::OsStr]) -> io::Result<()> {
+    tracing::info!(program, ?args, "running system command");
+    let output = Command::new(program).args(args).output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(io::Error::other(format!(
+            "{} failed: {}",
+            program,
+            stderr.trim()
+        )))
+    }
+}
@@ -0,0 +1,613 @@
+//! Tags on files and folders, the colour labels among them, kept by path
+//! in `tags.toml` in the data folder so that they work on any filesystem.
+//! Filtering the listing or searching for `tag:name` finds what has one.
+//! On macOS the Finder tags of what is listed count as well, read but
+//! never written.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{theme, Alignment, Color, Element, Length};
+use toml_edit::{value, Array, ArrayOfTables, Document, Item, Table};
+
+use crate::dirs;
+use crate::emblem::Emblem;
+use crate::i18n::{tr, trf};
+use crate::paths;
+use crate::safe_save;
+use crate::style::Style;
+
+const FILE: &str = "tags.toml";
+
+/// What a filter or search query starts with to look for a tag.
+pub const PREFIX: &str = "tag:";
+
+/// The tags drawn in a colour of their own, as Finder has them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
+}
+
+impl Label {
+    pub const ALL: [Label; 7] = [
+        Label::Red,
+        Label::Orange,
+        Label::Yellow,
+        Label::Green,
+        Label::Blue,
+        Label::Purple,
+        Label::Gray,
+    ];
+
+    /// The tag it is, as kept and as typed after `tag:`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Label::Red => "red",
+            Label::Orange => "orange",
+            Label::Yellow => "yellow",
+            Label::Green => "green",
+            Label::Blue => "blue",
+            Label::Purple => "purple",
+            Label::Gray => "gray",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Label::Red => Color::from_rgb8(0xee, 0x4b, 0x45),
+            Label::Orange => Color::from_rgb8(0xf5, 0x9a, 0x33),
+            Label::Yellow => Color::from_rgb8(0xf3, 0xcc, 0x33),
+            Label::Green => Color::from_rgb8(0x5a, 0xbf, 0x59),
+            Label::Blue => Color::from_rgb8(0x3f, 0x8c, 0xf2),
+            Label::Purple => Color::from_rgb8(0xa6, 0x66, 0xd9),
+            Label::Gray => Color::from_rgb8(0x99, 0x99, 0x99),
+        }
+    }
+
+    fn of(tag: &str) -> Option<Label> {
+        Label::ALL.into_iter().find(|label| label.tag() == tag)
+    }
+
+    /// The label Finder numbers `color`, none for 0.
+    #[cfg(any(target_os = "macos", test))]
+    fn finder(color: u32) -> Option<Label> {
+        match color {
+            1 => Some(Label::Gray),
+            2 => Some(Label::Green),
+            3 => Some(Label::Purple),
+            4 => Some(Label::Blue),
+            5 => Some(Label::Yellow),
+            6 => Some(Label::Red),
+            7 => Some(Label::Orange),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Label::Red => tr("Red"),
+            Label::Orange => tr("Orange"),
+            Label::Yellow => tr("Yellow"),
+            Label::Green => tr("Green"),
+            Label::Blue => tr("Blue"),
+            Label::Purple => tr("Purple"),
+            Label::Gray => tr("Gray"),
+        })
+    }
+}
+
+/// A tag as kept: trimmed and lowercase, so "Work" and "work " are one.
+fn tidy(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// What `tag:name` at the start of `query` asks for, if it does.
+pub fn wanted(query: &str) -> Option<String> {
+    let tag = query.trim().strip_prefix(PREFIX)?;
+    Some(tidy(tag)).filter(|tag| !tag.is_empty())
+}
+
+#[derive(Default)]
+pub struct Tags {
+    /// Never empty lists.
+    by_path: HashMap<PathBuf, Vec<String>>,
+    /// The Finder tags of what has been listed, not saved.
+    finder: HashMap<PathBuf, Vec<String>>,
+}
+
+impl Tags {
+    /// What the file holds, or nothing if it cannot be read.
+    pub fn load() -> Tags {
+        let Some(file) = file() else {
+            return Tags::default();
+        };
+        let document = match fs::read_to_string(&file) {
+            Ok(text) => text.parse::<Document>().map_err(io::Error::other),
+            Err(error) => Err(error),
+        };
+        let document = match document {
+            Ok(document) => document,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    tracing::warn!(%error, file = %file.display(), "cannot read the tags");
+                }
+                return Tags::default();
+            }
+        };
+
+        let by_path = document
+            .get("file")
+            .and_then(Item::as_array_of_tables)
+            .map(|files| {
+                files
+                    .iter()
+                    .filter_map(|file| {
+                        let path = PathBuf::from(file.get("path")?.as_str()?);
+                        let tags: Vec<String> = file
+                            .get("tags")?
+                            .as_array()?
+                            .iter()
+                            .filter_map(|tag| tag.as_str())
+                            .map(tidy)
+                            .filter(|tag| !tag.is_empty())
+                            .collect();
+                        (!tags.is_empty()).then_some((path, tags))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Tags {
+            by_path,
+            finder: HashMap::new(),
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let file =
+            file().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        let mut paths: Vec<&PathBuf> = self.by_path.keys().collect();
+        paths.sort();
+        let mut files = ArrayOfTables::new();
+        for path in paths {
+            let mut entry = Table::new();
+            entry["path"] = value(path.to_string_lossy().as_ref());
+            let tags: Array = self.by_path[path].iter().map(String::as_str).collect();
+            entry["tags"] = value(tags);
+            files.push(entry);
+        }
+        let mut document = Document::new();
+        document["file"] = Item::ArrayOfTables(files);
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        safe_save::write(&file, document.to_string().as_bytes())
+    }
+
+    /// The tags kept for `path`, which this app can change.
+    fn kept(&self, path: &Path) -> &[String] {
+        self.by_path.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// The tags of `path`, its Finder ones after those kept, once each.
+    pub fn of(&self, path: &Path) -> Vec<&String> {
+        let mut tags: Vec<&String> = self.kept(path).iter().collect();
+        for tag in self.finder.get(path).into_iter().flatten() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    pub fn has(&self, path: &Path, tag: &str) -> bool {
+        self.of(path).iter().any(|has| *has == tag)
+    }
+
+    /// Takes `found` as the Finder tags of its paths, from `finder`.
+    pub fn learn(&mut self, found: Vec<(PathBuf, Vec<String>)>) {
+        for (path, tags) in found {
+            match tags.is_empty() {
+                true => self.finder.remove(&path),
+                false => self.finder.insert(path, tags),
+            };
+        }
+    }
+
+    /// Gives each of `paths` the tag, or takes it off.
+    pub fn set(&mut self, paths: &[PathBuf], tag: &str, on: bool) -> io::Result<()> {
+        let tag = tidy(tag);
+        for path in paths {
+            let tags = self.by_path.entry(path.clone()).or_default();
+            tags.retain(|has| *has != tag);
+            if on {
+                tags.push(tag.clone());
+            }
+            if tags.is_empty() {
+                self.by_path.remove(path);
+            }
+        }
+        self.save()
+    }
+
+    /// Has `paths` tagged with `tags` alone but for their labels, which
+    /// only `set` changes.
+    pub fn replace(&mut self, paths: &[PathBuf], tags: &[String]) -> io::Result<()> {
+        for path in paths {
+            let mut kept: Vec<String> = self
+                .kept(path)
+                .iter()
+                .filter(|tag| Label::of(tag).is_some())
+                .cloned()
+                .collect();
+            for tag in tags.iter().map(|tag| tidy(tag)) {
+                if !tag.is_empty() && !kept.contains(&tag) {
+                    kept.push(tag);
+                }
+            }
+            match kept.is_empty() {
+                true => self.by_path.remove(path),
+                false => self.by_path.insert(path.clone(), kept),
+            };
+        }
+        self.save()
+    }
+
+    /// Keeps the tags of what was at `from`, and of all it held, now that it
+    /// is at `to`.
+    pub fn moved(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        let moved: Vec<PathBuf> = self
+            .by_path
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+        if moved.is_empty() {
+            return Ok(());
+        }
+        for path in moved {
+            if let (Some(tags), Ok(rest)) = (self.by_path.remove(&path), path.strip_prefix(from)) {
+                self.by_path.insert(to.join(rest), tags);
+            }
+        }
+        self.save()
+    }
+
+    /// What has `tag` in `root` and below.
+    pub fn tagged(&self, tag: &str, root: &Path) -> Vec<PathBuf> {
+        let mut tagged: Vec<PathBuf> = self
+            .by_path
+            .iter()
+            .chain(&self.finder)
+            .filter(|(path, tags)| {
+                paths::starts_with(path, root) && tags.iter().any(|has| has == tag)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        tagged.sort();
+        tagged.dedup();
+        tagged
+    }
+
+    /// A dot for each tag of `path`: in its colour for a label, `dimmed`
+    /// for the others.
+    pub fn emblems(&self, path: &Path, dimmed: Color) -> Vec<Emblem> {
+        self.of(path)
+            .into_iter()
+            .map(|tag| match Label::of(tag) {
+                Some(label) => Emblem::new("●", label.to_string()).color(label.color()),
+                None => Emblem::new("●", tag.clone()).color(dimmed),
+            })
+            .collect()
+    }
+
+    /// The tags all of `paths` have but labels, as the editor starts from.
+    fn shared(&self, paths: &[PathBuf]) -> Vec<String> {
+        let Some((first, rest)) = paths.split_first() else {
+            return Vec::new();
+        };
+        self.kept(first)
+            .iter()
+            .filter(|tag| Label::of(tag).is_none())
+            .filter(|tag| rest.iter().all(|path| self.kept(path).contains(tag)))
+            .cloned()
+            .collect()
+    }
+}
+
+fn file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(FILE))
+}
+
+/// The Finder tags of `path` as tags here: each name, and the label of
+/// its colour when that is another.
+#[cfg(target_os = "macos")]
+pub fn finder(path: &Path) -> Vec<String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    const NAME: &[u8] = b"com.apple.metadata:_kMDItemUserTags\0";
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return Vec::new();
+    };
+    let name = NAME.as_ptr().cast();
+    // SAFETY: both strings end in a nul, and the buffers below are as
+    // long as the sizes given.
+    let size = unsafe { libc::getxattr(path.as_ptr(), name, std::ptr::null_mut(), 0, 0, 0) };
+    let Ok(size) = usize::try_from(size) else {
+        return Vec::new();
+    };
+    let mut plist = vec![0u8; size];
+    let read =
+        unsafe { libc::getxattr(path.as_ptr(), name, plist.as_mut_ptr().cast(), size, 0, 0) };
+    let Ok(read) = usize::try_from(read) else {
+        return Vec::new();
+    };
+    plist.truncate(read);
+
+    from_finder(&strings(&plist).unwrap_or_default())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn finder(_path: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+/// Finder's "Name\ncolour" entries as tags.
+#[cfg(any(target_os = "macos", test))]
+fn from_finder(entries: &[String]) -> Vec<String> {
+    let mut tags = Vec::new();
+    for entry in entries {
+        let (name, color) = entry.split_once('\n').unwrap_or((entry, ""));
+        let label = color.trim().parse().ok().and_then(Label::finder);
+        for tag in [tidy(name)]
+            .into_iter()
+            .chain(label.map(|label| label.tag().to_string()))
+        {
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// The strings in the array a binary property list holds at its top,
+/// which is how the tags attribute is kept.
+#[cfg(any(target_os = "macos", test))]
+fn strings(plist: &[u8]) -> Option<Vec<String>> {
+    if !plist.starts_with(b"bplist00") {
+        return None;
+    }
+    let trailer = plist.get(plist.len().checked_sub(32)?..)?;
+    let offset_size = usize::from(trailer[6]);
+    let ref_size = usize::from(trailer[7]);
+    if !(1..=8).contains(&offset_size) || !(1..=8).contains(&ref_size) {
+        return None;
+    }
+    let count = number(&trailer[8..16]);
+    let top = number(&trailer[16..24]);
+    let table = usize::try_from(number(&trailer[24..32])).ok()?;
+
+    let slice = |at: usize, len: usize| plist.get(at..at.checked_add(len)?);
+    let offset = |object: u64| -> Option<usize> {
+        if object >= count {
+            return None;
+        }
+        let start = usize::try_from(object).ok()?.checked_mul(offset_size)?;
+        usize::try_from(number(slice(table.checked_add(start)?, offset_size)?)).ok()
+    };
+    // The length in a marker's low half, or in the integer after it.
+    let length = |at: usize| -> Option<(usize, usize)> {
+        let marker = *plist.get(at)?;
+        if marker & 0x0f != 0x0f {
+            return Some((usize::from(marker & 0x0f), at + 1));
+        }
+        let int = *plist.get(at + 1)?;
+        if int & 0xf0 != 0x10 {
+            return None;
+        }
+        let size = 1usize << (int & 0x0f).min(3);
+        let bytes = slice(at + 2, size)?;
+        Some((usize::try_from(number(bytes)).ok()?, at + 2 + size))
+    };
+
+    let array = offset(top)?;
+    if plist.get(array)? & 0xf0 != 0xa0 {
+        return None;
+    }
+    let (items, refs) = length(array)?;
+    let mut strings = Vec::new();
+    for item in 0..items {
+        let at = offset(number(slice(refs + item * ref_size, ref_size)?))?;
+        let (len, data) = length(at)?;
+        let string = match plist[at] & 0xf0 {
+            0x50 => String::from_utf8_lossy(slice(data, len)?).into_owned(),
+            0x60 => {
+                let units: Vec<u16> = slice(data, len.checked_mul(2)?)?
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            _ => return None,
+        };
+        strings.push(string);
+    }
+    Some(strings)
+}
+
+/// A big-endian unsigned number of up to eight bytes.
+#[cfg(any(target_os = "macos", test))]
+fn number(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |number, &byte| number << 8 | u64::from(byte))
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Input(String),
+    Save,
+    Cancel,
+}
+
+pub enum Event {
+    /// The tags for the paths, comma-separated as typed.
+    Save(Vec<PathBuf>, Vec<String>),
+    Cancelled,
+}
+
+/// "Tags…": the tags of the selection other than its labels, typed out.
+pub struct Editor {
+    paths: Vec<PathBuf>,
+    text: String,
+}
+
+pub fn input() -> text_input::Id {
+    text_input::Id::new("tags")
+}
+
+impl Editor {
+    pub fn open(paths: Vec<PathBuf>, tags: &Tags) -> Editor {
+        let text = tags.shared(&paths).join(", ");
+        Editor { paths, text }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Input(text) => self.text = text,
+            Message::Save => {
+                let tags = self.text.split(',').map(String::from).collect();
+                return Some(Event::Save(std::mem::take(&mut self.paths), tags));
+            }
+            Message::Cancel => return Some(Event::Cancelled),
+        }
+
+        None
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let title = match &self.paths[..] {
+            [path] => trf(
+                "Tags of {}",
+                &[&path
+                    .file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()],
+            ),
+            paths => trf("Tags of {} items", &[&paths.len()]),
+        };
+        let buttons = row!(
+            text("").width(Length::Fill),
+            button(tr("Cancel")).on_press(Message::Cancel),
+            button(tr("Save")).on_press(Message::Save),
+        )
+        .spacing(5)
+        .align_items(Alignment::Center);
+
+        container(
+            column!(
+                text(title).size(20),
+                text_input(tr("work, to read, 2024"), &self.text)
+                    .id(input())
+                    .on_input(Message::Input)
+                    .on_submit(Message::Save)
+                    .padding(5),
+                text(trf(
+                    "Separate tags with commas. Filter or search for “{}name” to find them.",
+                    &[&PREFIX]
+                ))
+                .size(style.small_text_size),
+                buttons,
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .max_width(420)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A binary property list of `["Red\\n6", "Work\\n4"]`.
+    const RED_AND_WORK: &[u8] = &[
+        b'b', b'p', b'l', b'i', b's', b't', b'0', b'0', // header
+        0xa2, 1, 2, // the array
+        0x55, b'R', b'e', b'd', b'\n', b'6', // "Red\n6"
+        0x56, b'W', b'o', b'r', b'k', b'\n', b'4', // "Work\n4"
+        8, 11, 17, // offsets
+        0, 0, 0, 0, 0, 0, 1, 1, // trailer
+        0, 0, 0, 0, 0, 0, 0, 3, // objects
+        0, 0, 0, 0, 0, 0, 0, 0, // top
+        0, 0, 0, 0, 0, 0, 0, 24, // offset table
+    ];
+
+    #[test]
+    fn reads_the_strings_of_a_binary_plist() {
+        assert_eq!(
+            strings(RED_AND_WORK),
+            Some(vec![String::from("Red\n6"), String::from("Work\n4")])
+        );
+    }
+
+    #[test]
+    fn reads_utf16_strings() {
+        let plist = [
+            b'b', b'p', b'l', b'i', b's', b't', b'0', b'0', // header
+            0xa1, 1, // the array
+            0x63, 0, 0xc9, 0, b't', 0, 0xe9, // "Été"
+            8, 10, // offsets
+            0, 0, 0, 0, 0, 0, 1, 1, // trailer
+            0, 0, 0, 0, 0, 0, 0, 2, // objects
+            0, 0, 0, 0, 0, 0, 0, 0, // top
+            0, 0, 0, 0, 0, 0, 0, 17, // offset table
+        ];
+        assert_eq!(strings(&plist), Some(vec![String::from("Été")]));
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_binary_plist() {
+        assert_eq!(strings(b"<?xml version=\"1.0\"?>"), None);
+        assert_eq!(strings(&RED_AND_WORK[..40]), None);
+    }
+
+    #[test]
+    fn gives_finder_tags_their_labels() {
+        let entries = [
+            String::from("Red\n6"),
+            String::from("Work\n4"),
+            String::from("To Read"),
+        ];
+        assert_eq!(from_finder(&entries), ["red", "work", "blue", "to read"]);
+    }
+
+    #[test]
+    fn has_finder_tags_filter_and_colour() {
+        let mut tags = Tags::default();
+        let path = PathBuf::from("/a/report.pdf");
+        tags.learn(vec![(path.clone(), vec![String::from("red")])]);
+        assert!(tags.has(&path, "red"));
+        assert_eq!(tags.tagged("red", Path::new("/a")), vec![path.clone()]);
+        assert_eq!(tags.emblems(&path, Color::BLACK).len(), 1);
+        tags.learn(vec![(path.clone(), Vec::new())]);
+        assert!(!tags.has(&path, "red"));
+    }
+}
@@ -0,0 +1,20 @@
+use std::future::Future;
+use std::thread;
+
+use iced::futures::channel::oneshot;
+
+/// Runs blocking work (filesystem walks, external commands) on its own
+/// thread so it never stalls the executor that drives `Command`s.
+pub fn blocking<T, F>(work: F) -> impl Future<Output = T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+
+    async move { receiver.await.expect("blocking task panicked") }
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use iced::widget::{button, column, container, row, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::i18n::{tr, trf};
+use crate::style::Style;
+
+/// At most this many are on screen; the rest wait their turn.
+const MAX_VISIBLE: usize = 3;
+const INFO_TIMEOUT: Duration = Duration::from_secs(4);
+/// Errors, and toasts offering an action, stay longer to be read.
+const LONG_TIMEOUT: Duration = Duration::from_secs(8);
+const TICK: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Info,
+    Error,
+}
+
+/// `A` is what the host runs when a toast's action button is pressed.
+#[derive(Debug, Clone)]
+pub enum Message<A> {
+    Dismiss(usize),
+    Action(usize, A),
+    Tick,
+}
+
+struct Toast<A> {
+    id: usize,
+    kind: Kind,
+    title: String,
+    body: String,
+    action: Option<(String, A)>,
+    /// When it came on screen, which is when its timeout starts.
+    shown: Option<Instant>,
+}
+
+impl<A> Toast<A> {
+    fn timeout(&self) -> Duration {
+        match (self.kind, &self.action) {
+            (Kind::Info, None) => INFO_TIMEOUT,
+            _ => LONG_TIMEOUT,
+        }
+    }
+}
+
+/// Notifications stacked in the bottom-right corner, going away by
+/// themselves after a while.
+pub struct Toasts<A> {
+    toasts: VecDeque<Toast<A>>,
+    next_id: usize,
+}
+
+impl<A> Default for Toasts<A> {
+    fn default() -> Toasts<A> {
+        Toasts {
+            toasts: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<A: Clone + Send + 'static> Toasts<A> {
+    pub fn push(&mut self, kind: Kind, title: impl Into<String>, body: impl Into<String>) {
+        self.add(kind, title.into(), body.into(), None);
+    }
+
+    pub fn error(&mut self, title: impl Into<String>, body: impl ToString) {
+        self.push(Kind::Error, title, body.to_string());
+    }
+
+    /// With a button labelled `label` that hands `action` back from `update`.
+    pub fn push_with_action(
+        &mut self,
+        kind: Kind,
+        title: impl Into<String>,
+        body: impl Into<String>,
+        label: impl Into<String>,
+        action: A,
+    ) {
+        self.add(
+            kind,
+            title.into(),
+            body.into(),
+            Some((label.into(), action)),
+        );
+    }
+
+    fn add(&mut self, kind: Kind, title: String, body: String, action: Option<(String, A)>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.toasts.push_back(Toast {
+            id,
+            kind,
+            title: tr(&title).to_string(),
+            body: tr(&body).to_string(),
+            action: action.map(|(label, action)| (tr(&label).to_string(), action)),
+            shown: None,
+        });
+        self.show_waiting();
+    }
+
+    fn show_waiting(&mut self) {
+        let now = Instant::now();
+        for toast in self.toasts.iter_mut().take(MAX_VISIBLE) {
+            toast.shown.get_or_insert(now);
+        }
+    }
+
+    /// Gives the action of a toast whose button was pressed, closing it.
+    pub fn update(&mut self, message: Message<A>) -> Option<A> {
+        let mut action = None;
+        match message {
+            Message::Dismiss(id) => self.toasts.retain(|toast| toast.id != id),
+            Message::Action(id, pressed) => {
+                if self.toasts.iter().any(|toast| toast.id == id) {
+                    self.toasts.retain(|toast| toast.id != id);
+                    action = Some(pressed);
+                }
+            }
+            Message::Tick => self.toasts.retain(|toast| {
+                toast
+                    .shown
+                    .is_none_or(|shown| shown.elapsed() < toast.timeout())
+            }),
+        }
+        self.show_waiting();
+
+        action
+    }
+
+    /// Ticks while there is something to time out.
+    pub fn subscription(&self) -> Subscription<Message<A>> {
+        if self.toasts.is_empty() {
+            return Subscription::none();
+        }
+
+        struct Ticker;
+
+        subscription::channel(
+            std::any::TypeId::of::<Ticker>(),
+            1,
+            |mut output| async move {
+                use iced::futures::channel::mpsc;
+                use iced::futures::{SinkExt, StreamExt};
+
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    while sender.unbounded_send(()).is_ok() {
+                        std::thread::sleep(TICK);
+                    }
+                });
+
+                while receiver.next().await.is_some() {
+                    let _ = output.send(Message::Tick).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    /// The ones on screen, newest at the bottom, to be laid over the
+    /// picker with `Modal::floating`.
+    pub fn view(&self, style: &Style) -> Option<Element<'_, Message<A>>> {
+        if self.toasts.is_empty() {
+            return None;
+        }
+
+        let mut col = column!().spacing(5).width(360);
+        for toast in self.toasts.iter().take(MAX_VISIBLE) {
+            let title = match toast.kind {
+                Kind::Info => text(&toast.title),
+                Kind::Error => text(&toast.title).style(theme::Text::Color(style.error)),
+            };
+
+            let mut buttons = row!().spacing(5);
+            if let Some((label, action)) = &toast.action {
+                buttons = buttons
+                    .push(button(text(label)).on_press(Message::Action(toast.id, action.clone())));
+            }
+            buttons = buttons.push(button("×").on_press(Message::Dismiss(toast.id)));
+
+            col = col.push(
+                container(
+                    row!(
+                        column!(title, text(&toast.body).size(style.small_text_size))
+                            .width(Length::Fill),
+                        buttons,
+                    )
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                )
+                .padding(10)
+                .style(theme::Container::Box),
+            );
+        }
+        let waiting = self.toasts.len().saturating_sub(MAX_VISIBLE);
+        if waiting > 0 {
+            col = col.push(text(trf("{} more", &[&waiting])).size(style.small_text_size));
+        }
+
+        Some(col.into())
+    }
+}
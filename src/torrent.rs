@@ -0,0 +1,623 @@
+//! BitTorrent metainfo: what a `.torrent` file or a magnet link would
+//! download, read from its bencoded info dictionary. The info hash, of that
+//! dictionary as written, is what clients and trackers know the torrent by.
+
+use std::any::TypeId;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::checksum;
+use crate::dates::format_time;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::style::Style;
+use crate::webdav;
+
+/// Larger than the metainfo of any real torrent.
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+/// Files listed by the summary; the others are only counted.
+const MAX_LISTED: usize = 1000;
+/// How far lists and dictionaries may nest before the file is refused.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Torrent,
+    /// A text file holding a `magnet:` link.
+    Magnet,
+}
+
+pub fn kind(path: &Path) -> Option<Kind> {
+    let extension = path.extension()?.to_str()?;
+    if extension.eq_ignore_ascii_case("torrent") {
+        Some(Kind::Torrent)
+    } else if extension.eq_ignore_ascii_case("magnet") {
+        Some(Kind::Magnet)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Torrent {
+    pub name: String,
+    /// Of all the files; magnet links may not say.
+    pub size: Option<u64>,
+    /// Paths inside the torrent's folder, with their sizes.
+    pub files: Vec<(String, u64)>,
+    pub file_count: usize,
+    pub trackers: Vec<String>,
+    pub piece_length: Option<u64>,
+    pub pieces: Option<usize>,
+    /// Of the info dictionary, in hex: SHA-1 for version 1 torrents,
+    /// SHA-256 for version 2 ones; hybrids have both.
+    pub info_hash: Option<String>,
+    pub info_hash_v2: Option<String>,
+    /// Such as the comment and the program that made it.
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl Torrent {
+    /// A magnet link asking for the same download, with its trackers.
+    pub fn magnet(&self) -> Option<String> {
+        let mut topics = Vec::new();
+        if let Some(hash) = &self.info_hash {
+            topics.push(format!("xt=urn:btih:{}", hash));
+        }
+        if let Some(hash) = &self.info_hash_v2 {
+            topics.push(format!("xt=urn:btmh:1220{}", hash));
+        }
+        if topics.is_empty() {
+            return None;
+        }
+        let mut link = format!("magnet:?{}", topics.join("&"));
+        if !self.name.is_empty() {
+            link.push_str(&format!("&dn={}", webdav::encode(&self.name)));
+        }
+        if let Some(size) = self.size {
+            link.push_str(&format!("&xl={}", size));
+        }
+        for tracker in &self.trackers {
+            link.push_str(&format!("&tr={}", webdav::encode(tracker)));
+        }
+
+        Some(link)
+    }
+}
+
+pub fn read(path: &Path, kind: Kind) -> Result<Torrent, String> {
+    let size = fs::metadata(path).map_err(|error| error.to_string())?.len();
+    if size > MAX_SIZE {
+        return Err(format!("Larger than {}", format_bytes(MAX_SIZE)));
+    }
+    let data = fs::read(path).map_err(|error| error.to_string())?;
+    match kind {
+        Kind::Torrent => parse(&data),
+        Kind::Magnet => {
+            let text = String::from_utf8_lossy(&data);
+            let link = text
+                .split_whitespace()
+                .find(|word| word.starts_with("magnet:?"))
+                .ok_or("No magnet link in the file")?;
+            parse_magnet(link)
+        }
+    }
+}
+
+/// A bencoded value, borrowing from the file.
+enum Value<'a> {
+    Integer(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    /// With the dictionary as written, which the info hash is taken of.
+    Dictionary(Vec<(&'a [u8], Value<'a>)>, &'a [u8]),
+}
+
+impl<'a> Value<'a> {
+    fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Dictionary(entries, _) => entries
+                .iter()
+                .find(|(name, _)| *name == key.as_bytes())
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> Option<String> {
+        match self {
+            Value::Bytes(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> &[Value<'a>] {
+        match self {
+            Value::List(values) => values,
+            _ => &[],
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, what: &str) -> String {
+        trf("Not a torrent file: {} at byte {}", &[&what, &self.at])
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        let start = self.at;
+        match self.data.get(self.at) {
+            Some(b'i') => {
+                self.at += 1;
+                let digits = self.until(b'e')?;
+                std::str::from_utf8(digits)
+                    .ok()
+                    .and_then(|digits| digits.parse().ok())
+                    .map(Value::Integer)
+                    .ok_or_else(|| self.error("bad integer"))
+            }
+            Some(b'l') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                while self.data.get(self.at) != Some(&b'e') {
+                    values.push(self.value(depth + 1)?);
+                }
+                self.at += 1;
+                Ok(Value::List(values))
+            }
+            Some(b'd') => {
+                self.at += 1;
+                let mut entries = Vec::new();
+                while self.data.get(self.at) != Some(&b'e') {
+                    let key = self.bytes()?;
+                    entries.push((key, self.value(depth + 1)?));
+                }
+                self.at += 1;
+                Ok(Value::Dictionary(entries, &self.data[start..self.at]))
+            }
+            Some(b'0'..=b'9') => self.bytes().map(Value::Bytes),
+            Some(_) => Err(self.error("unexpected byte")),
+            None => Err(self.error("cut short")),
+        }
+    }
+
+    /// A length-prefixed string, as in `4:spam`.
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let digits = self.until(b':')?;
+        let length: usize = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(|| self.error("bad length"))?;
+        let bytes = self
+            .data
+            .get(self.at..self.at.saturating_add(length))
+            .ok_or_else(|| self.error("cut short"))?;
+        self.at += length;
+
+        Ok(bytes)
+    }
+
+    /// What comes before `end`, leaving `at` after it.
+    fn until(&mut self, end: u8) -> Result<&'a [u8], String> {
+        let length = self.data[self.at..]
+            .iter()
+            .position(|&byte| byte == end)
+            .ok_or_else(|| self.error("cut short"))?;
+        let found = &self.data[self.at..self.at + length];
+        self.at += length + 1;
+
+        Ok(found)
+    }
+}
+
+fn parse(data: &[u8]) -> Result<Torrent, String> {
+    let mut decoder = Decoder { data, at: 0 };
+    let root = decoder.value(0)?;
+    let Some(info @ Value::Dictionary(_, raw)) = root.get("info") else {
+        return Err(String::from("Not a torrent file: no info dictionary"));
+    };
+
+    let version = info.get("meta version").and_then(Value::integer);
+    let name = info
+        .get("name.utf-8")
+        .or_else(|| info.get("name"))
+        .and_then(Value::text)
+        .unwrap_or_default();
+    let mut files = Vec::new();
+    let mut file_count = 0;
+    let mut size = 0;
+    let mut add = |path: String, length: u64| {
+        file_count += 1;
+        size += length;
+        if files.len() < MAX_LISTED {
+            files.push((path, length));
+        }
+    };
+    match (info.get("files"), info.get("file tree")) {
+        (Some(list), _) => {
+            for file in list.list() {
+                let parts = file
+                    .get("path.utf-8")
+                    .or_else(|| file.get("path"))
+                    .map(Value::list)
+                    .unwrap_or_default();
+                let path: Vec<String> = parts.iter().filter_map(Value::text).collect();
+                // Padding files only line the next file up with a piece.
+                let padding = file
+                    .get("attr")
+                    .and_then(Value::bytes)
+                    .is_some_and(|attr| attr.contains(&b'p'));
+                let length = file.get("length").and_then(Value::integer).unwrap_or(0);
+                if !padding {
+                    add(path.join("/"), length.max(0) as u64);
+                }
+            }
+        }
+        (None, Some(tree)) => walk_tree(tree, &mut String::new(), &mut add),
+        (None, None) => {
+            let length = info.get("length").and_then(Value::integer).unwrap_or(0);
+            add(name.clone(), length.max(0) as u64);
+        }
+    }
+
+    let mut trackers: Vec<String> = Vec::new();
+    let tiers = root
+        .get("announce-list")
+        .map(Value::list)
+        .unwrap_or_default();
+    let listed = root
+        .get("announce")
+        .into_iter()
+        .chain(tiers.iter().flat_map(Value::list));
+    for tracker in listed.filter_map(Value::text) {
+        if !tracker.is_empty() && !trackers.contains(&tracker) {
+            trackers.push(tracker);
+        }
+    }
+
+    let pieces = info
+        .get("pieces")
+        .and_then(Value::bytes)
+        .map(|pieces| pieces.len() / 20)
+        .or_else(|| {
+            let length = info.get("piece length")?.integer()?;
+            (length > 0).then(|| size.div_ceil(length as u64) as usize)
+        });
+    let mut fields = Vec::new();
+    for (label, key) in [
+        ("Comment", "comment"),
+        ("Created by", "created by"),
+        ("Source", "source"),
+    ] {
+        if let Some(value) = root
+            .get(key)
+            .or_else(|| info.get(key))
+            .and_then(Value::text)
+        {
+            fields.push((label, value));
+        }
+    }
+    if let Some(seconds) = root.get("creation date").and_then(Value::integer) {
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64);
+        fields.push(("Created", format_time(Ok(created))));
+    }
+    if info.get("private").and_then(Value::integer) == Some(1) {
+        fields.push(("Private", String::from("Only shared through its trackers")));
+    }
+    let hybrid = info.get("pieces").is_some();
+
+    Ok(Torrent {
+        name,
+        size: Some(size),
+        files,
+        file_count,
+        trackers,
+        piece_length: info
+            .get("piece length")
+            .and_then(Value::integer)
+            .map(|length| length.max(0) as u64),
+        pieces,
+        info_hash: (version != Some(2) || hybrid).then(|| checksum::sha1_hex(raw)),
+        info_hash_v2: (version == Some(2)).then(|| checksum::sha256_hex(raw)),
+        fields,
+    })
+}
+
+/// The files of a version 2 `file tree`, where each name maps to the
+/// names inside it and a file's to an entry under the empty name.
+fn walk_tree(tree: &Value, path: &mut String, add: &mut impl FnMut(String, u64)) {
+    let Value::Dictionary(entries, _) = tree else {
+        return;
+    };
+    for (name, value) in entries {
+        if name.is_empty() {
+            let length = value.get("length").and_then(Value::integer).unwrap_or(0);
+            add(path.clone(), length.max(0) as u64);
+            continue;
+        }
+        let length = path.len();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(&String::from_utf8_lossy(name));
+        walk_tree(value, path, add);
+        path.truncate(length);
+    }
+}
+
+fn parse_magnet(link: &str) -> Result<Torrent, String> {
+    let query = link.strip_prefix("magnet:?").ok_or("Not a magnet link")?;
+    let mut torrent = Torrent {
+        name: String::new(),
+        size: None,
+        files: Vec::new(),
+        file_count: 0,
+        trackers: Vec::new(),
+        piece_length: None,
+        pieces: None,
+        info_hash: None,
+        info_hash_v2: None,
+        fields: Vec::new(),
+    };
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = webdav::decode(&value.replace('+', " "));
+        match key {
+            "xt" => {
+                if let Some(hash) = value.strip_prefix("urn:btih:") {
+                    torrent.info_hash = Some(btih(hash).ok_or("Bad info hash")?);
+                } else if let Some(hash) = value.strip_prefix("urn:btmh:1220") {
+                    torrent.info_hash_v2 = Some(hash.to_lowercase());
+                }
+            }
+            "dn" => torrent.name = value,
+            "xl" => torrent.size = value.parse().ok(),
+            "tr" if !torrent.trackers.contains(&value) => torrent.trackers.push(value),
+            "ws" => torrent.fields.push(("Web seed", value)),
+            _ => {}
+        }
+    }
+    if torrent.info_hash.is_none() && torrent.info_hash_v2.is_none() {
+        return Err(String::from("The magnet link has no BitTorrent info hash"));
+    }
+
+    Ok(torrent)
+}
+
+/// A version 1 info hash in hex, from the hex or base32 of a magnet link.
+fn btih(hash: &str) -> Option<String> {
+    if hash.len() == 40 && hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Some(hash.to_lowercase());
+    }
+    if hash.len() != 32 {
+        return None;
+    }
+    let mut bits: u64 = 0;
+    let mut count = 0;
+    let mut hex = String::with_capacity(40);
+    for byte in hash.bytes() {
+        let value = match byte.to_ascii_uppercase() {
+            upper @ b'A'..=b'Z' => upper - b'A',
+            digit @ b'2'..=b'7' => digit - b'2' + 26,
+            _ => return None,
+        };
+        bits = bits << 5 | value as u64;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            hex.push_str(&format!("{:02x}", (bits >> count) & 0xff));
+        }
+    }
+
+    Some(hex)
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Read(Result<Torrent, String>),
+    OpenInClient,
+    CopyMagnet,
+    Close,
+}
+
+pub enum Event {
+    /// Hands the file, or the magnet link, to the torrent client.
+    Open(PathBuf),
+    CopyMagnet(String),
+    Closed,
+}
+
+pub struct Summary {
+    path: PathBuf,
+    kind: Kind,
+    torrent: Option<Result<Torrent, String>>,
+}
+
+impl Summary {
+    pub fn open(path: PathBuf, kind: Kind) -> Summary {
+        Summary {
+            path,
+            kind,
+            torrent: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        let magnet = || self.torrent.as_ref()?.as_ref().ok()?.magnet();
+        match message {
+            Message::Read(torrent) => self.torrent = Some(torrent),
+            Message::OpenInClient => {
+                return match self.kind {
+                    Kind::Torrent => Some(Event::Open(self.path.clone())),
+                    Kind::Magnet => magnet().map(|link| Event::Open(PathBuf::from(link))),
+                }
+            }
+            Message::CopyMagnet => return magnet().map(Event::CopyMagnet),
+            Message::Close => return Some(Event::Closed),
+        }
+
+        None
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Reading;
+
+        if self.torrent.is_some() {
+            return Subscription::none();
+        }
+        let path = self.path.clone();
+        let kind = self.kind;
+        subscription::channel(
+            (TypeId::of::<Reading>(), path.clone()),
+            1,
+            move |mut output| async move {
+                use iced::futures::channel::oneshot;
+                use iced::futures::SinkExt;
+
+                let (sender, receiver) = oneshot::channel();
+                std::thread::spawn(move || {
+                    let _ = sender.send(read(&path, kind));
+                });
+                if let Ok(torrent) = receiver.await {
+                    let _ = output.send(Message::Read(torrent)).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let file_name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let mut col = column!().spacing(10);
+
+        match &self.torrent {
+            None => {
+                col = col
+                    .push(text(file_name).size(20))
+                    .push(text(tr("Reading the torrent…")))
+            }
+            Some(Err(error)) => {
+                col = col
+                    .push(text(file_name).size(20))
+                    .push(text(error).style(theme::Text::Color(style.error)))
+            }
+            Some(Ok(torrent)) => {
+                let title = match torrent.name.is_empty() {
+                    true => file_name.to_string(),
+                    false => torrent.name.clone(),
+                };
+                col = col.push(text(title).size(20));
+                let field = |label: &str, value: String| {
+                    row!(
+                        text(label)
+                            .size(style.small_text_size)
+                            .width(Length::Fixed(110.)),
+                        text(value).size(style.small_text_size).width(Length::Fill),
+                    )
+                    .spacing(10)
+                };
+                let mut details = column!().spacing(8);
+                if let Some(size) = torrent.size {
+                    details = details.push(field("Size", format_bytes(size)));
+                }
+                if let Some(length) = torrent.piece_length {
+                    let pieces = torrent
+                        .pieces
+                        .map(|pieces| format!(", {} pieces", pieces))
+                        .unwrap_or_default();
+                    details = details.push(field(
+                        "Pieces",
+                        format!("{}{}", format_bytes(length), pieces),
+                    ));
+                }
+                if let Some(hash) = &torrent.info_hash {
+                    details = details.push(field("Info hash", hash.clone()));
+                }
+                if let Some(hash) = &torrent.info_hash_v2 {
+                    details = details.push(field("Info hash (v2)", hash.clone()));
+                }
+                for (label, value) in &torrent.fields {
+                    details = details.push(field(label, value.clone()));
+                }
+                if !torrent.trackers.is_empty() {
+                    details = details.push(text(trf("Trackers ({})", &[&torrent.trackers.len()])));
+                    for tracker in &torrent.trackers {
+                        details = details.push(text(tracker).size(style.small_text_size));
+                    }
+                }
+                if torrent.file_count > 0 {
+                    details = details.push(text(trf("Files ({})", &[&torrent.file_count])));
+                    let mut files = column!().spacing(2);
+                    for (path, size) in &torrent.files {
+                        files = files.push(
+                            row!(
+                                text(path).size(style.small_text_size).width(Length::Fill),
+                                text(format_bytes(*size)).size(style.small_text_size),
+                            )
+                            .spacing(10),
+                        );
+                    }
+                    if torrent.file_count > torrent.files.len() {
+                        files = files.push(
+                            text(trf(
+                                "and {} more",
+                                &[&(torrent.file_count - torrent.files.len())],
+                            ))
+                            .size(style.small_text_size)
+                            .style(theme::Text::Color(style.dimmed)),
+                        );
+                    }
+                    details = details.push(files);
+                }
+                col = col.push(scrollable(details).height(Length::Fixed(420.)));
+            }
+        }
+
+        let read = matches!(self.torrent, Some(Ok(_)));
+        col = col.push(
+            row!(
+                text("").width(Length::Fill),
+                button(tr("Close")).on_press(Message::Close),
+                button(tr("Copy magnet link")).on_press_maybe(read.then_some(Message::CopyMagnet)),
+                button(tr("Open with torrent client"))
+                    .on_press_maybe(read.then_some(Message::OpenInClient)),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center),
+        );
+
+        container(col)
+            .width(Length::Fill)
+            .max_width(680)
+            .padding(20)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
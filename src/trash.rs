@@ -0,0 +1,245 @@
+//! The desktop's trash, where deleted items wait to be restored. On
+//! freedesktop systems this is the trash of the Trash specification, with
+//! an info file per item: the home trash, or for items on other drives the
+//! trash at the top of that drive. macOS keeps `~/.Trash`. The Windows
+//! Recycle Bin is not reached yet.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub fn is_supported() -> bool {
+    cfg!(unix)
+}
+
+/// Moves `path` into the trash, giving where it went.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    use std::io::Write;
+    use std::os::unix::fs::DirBuilderExt;
+
+    let original = absolute(path)?;
+    let (trash, top) = trash_for(&original)?;
+    let (files, info) = (trash.join("files"), trash.join("info"));
+    for dir in [&files, &info] {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+
+    // Creating the info file exclusively claims the name, even against
+    // another program trashing at the same time.
+    for name in candidates(&original) {
+        let mut info_name = name.clone();
+        info_name.push(".trashinfo");
+        let info_path = info.join(info_name);
+        let mut file = match fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        };
+        let target = files.join(&name);
+        if fs::symlink_metadata(&target).is_ok() {
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+
+        let recorded = match &top {
+            Some(top) => original.strip_prefix(top).unwrap_or(&original),
+            None => &original,
+        };
+        let uri = crate::paths::to_uri(recorded);
+        let moved = writeln!(
+            file,
+            "[Trash Info]\nPath={}\nDeletionDate={}",
+            uri.trim_start_matches("file://"),
+            deletion_date()
+        )
+        .and_then(|()| fs::rename(&original, &target));
+        return match moved {
+            Ok(()) => Ok(target),
+            Err(error) => {
+                let _ = fs::remove_file(&info_path);
+                Err(error)
+            }
+        };
+    }
+
+    names_exhausted()
+}
+
+#[cfg(target_os = "macos")]
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    let original = absolute(path)?;
+    let home = std::env::var_os("HOME")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home folder"))?;
+    let trash = PathBuf::from(home).join(".Trash");
+    fs::create_dir_all(&trash)?;
+
+    for name in candidates(&original) {
+        let target = trash.join(name);
+        if fs::symlink_metadata(&target).is_err() {
+            fs::rename(&original, &target)?;
+            return Ok(target);
+        }
+    }
+
+    names_exhausted()
+}
+
+#[cfg(not(unix))]
+pub fn trash(_path: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "there is no trash to move to on this system",
+    ))
+}
+
+/// Puts back what `trash` moved to `trashed`, refusing to replace anything
+/// at `original` since.
+pub fn restore(trashed: &Path, original: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(original).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists again", original.display()),
+        ));
+    }
+    fs::rename(trashed, original)?;
+    if let Some(info) = info_file(trashed) {
+        let _ = fs::remove_file(info);
+    }
+
+    Ok(())
+}
+
+/// The item's own name, then the name with `.2`, `.3` and so on after it.
+#[cfg(unix)]
+fn candidates(path: &Path) -> impl Iterator<Item = std::ffi::OsString> + '_ {
+    let name = path.file_name().unwrap_or(path.as_os_str());
+    (1..10_000).map(move |n| {
+        let mut name = name.to_os_string();
+        if n > 1 {
+            name.push(format!(".{}", n));
+        }
+        name
+    })
+}
+
+#[cfg(unix)]
+fn names_exhausted() -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "the trash holds too many items of that name",
+    ))
+}
+
+/// Where `path` is, without following it if it is a symlink.
+#[cfg(unix)]
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash a root"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::canonicalize(parent)?,
+        _ => std::env::current_dir()?,
+    };
+
+    Ok(parent.join(name))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn home_trash() -> io::Result<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map(|dir| dir.join("Trash"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home folder for the trash"))
+}
+
+/// The trash `original` goes to: the home trash when it is on the same
+/// drive, otherwise the trash at the top of its own drive, given with that
+/// top, which the info file's path is then relative to.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_for(original: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    let home = home_trash()?;
+    let device = fs::symlink_metadata(original)?.dev();
+    // The home trash need not exist yet; where it would be created counts.
+    let home_device = home
+        .ancestors()
+        .find_map(|dir| fs::metadata(dir).ok())
+        .map(|meta| meta.dev());
+    if home_device == Some(device) {
+        return Ok((home, None));
+    }
+
+    let mut top = original.parent().unwrap_or(original);
+    while let Some(parent) = top.parent() {
+        if !fs::metadata(parent).is_ok_and(|meta| meta.dev() == device) {
+            break;
+        }
+        top = parent;
+    }
+    // SAFETY: getuid cannot fail.
+    let uid = unsafe { libc::getuid() };
+
+    // An administrator's `.Trash` counts only when it is a real folder with
+    // the sticky bit, so nobody can take over another user's part of it.
+    let shared = top.join(".Trash");
+    if fs::symlink_metadata(&shared)
+        .is_ok_and(|meta| meta.is_dir() && meta.permissions().mode() & 0o1000 != 0)
+    {
+        let own = shared.join(uid.to_string());
+        let _ = fs::DirBuilder::new().mode(0o700).create(&own);
+        if fs::symlink_metadata(&own).is_ok_and(|meta| meta.is_dir()) {
+            return Ok((own, Some(top.to_path_buf())));
+        }
+    }
+
+    Ok((top.join(format!(".Trash-{}", uid)), Some(top.to_path_buf())))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn info_file(trashed: &Path) -> Option<PathBuf> {
+    let trash = trashed.parent()?.parent()?;
+    let mut name = trashed.file_name()?.to_os_string();
+    name.push(".trashinfo");
+    Some(trash.join("info").join(name))
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn info_file(_trashed: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// Local time, as the specification asks for.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn deletion_date() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as libc::time_t);
+
+    // SAFETY: `localtime_r` only writes into the `tm` we own.
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&seconds, &mut tm);
+        tm
+    };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
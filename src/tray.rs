@@ -0,0 +1,463 @@
+//! The icon the instance started with `--hidden` puts in the system tray,
+//! to show the window or quit from. On Linux it is a StatusNotifierItem
+//! with a menu over D-Bus, which the KDE, GNOME (with the AppIndicator
+//! extension), Xfce and most other panels show; on Windows a notification
+//! area icon. macOS has none: a status item needs AppKit, which nothing
+//! here links, so launching the app again is what shows the window there.
+
+use iced::Subscription;
+
+#[cfg(any(target_os = "linux", windows))]
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Show,
+    Quit,
+}
+
+/// The menu's entries, by their ids on D-Bus and on Windows.
+#[cfg(any(target_os = "linux", windows))]
+const SHOW: i32 = 1;
+#[cfg(any(target_os = "linux", windows))]
+const QUIT: i32 = 2;
+
+#[cfg(any(target_os = "linux", windows))]
+fn entries() -> [(i32, &'static str); 2] {
+    [(SHOW, tr("Show")), (QUIT, tr("Quit"))]
+}
+
+#[cfg(any(target_os = "linux", windows))]
+fn event(id: i32) -> Option<Event> {
+    match id {
+        SHOW => Some(Event::Show),
+        QUIT => Some(Event::Quit),
+        _ => None,
+    }
+}
+
+#[cfg(any(target_os = "linux", windows))]
+pub fn events() -> Subscription<Event> {
+    use std::any::TypeId;
+    use std::thread;
+
+    use iced::futures::channel::mpsc;
+    use iced::futures::{SinkExt, StreamExt};
+    use iced::subscription;
+
+    struct Tray;
+
+    subscription::channel(TypeId::of::<Tray>(), 10, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        thread::spawn(move || {
+            if let Err(error) = icon::serve(sender) {
+                tracing::info!(%error, "no tray icon");
+            }
+        });
+
+        while let Some(event) = receiver.next().await {
+            let _ = output.send(event).await;
+        }
+
+        std::future::pending().await
+    })
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn events() -> Subscription<Event> {
+    Subscription::none()
+}
+
+#[cfg(target_os = "linux")]
+mod icon {
+    use std::io;
+
+    use iced::futures::channel::mpsc;
+
+    use super::{entries, event, Event};
+    use crate::dbus::{Connection, Message, Sender, Value};
+
+    const ITEM: &str = "org.kde.StatusNotifierItem";
+    const ITEM_PATH: &str = "/StatusNotifierItem";
+    const MENU: &str = "com.canonical.dbusmenu";
+    const MENU_PATH: &str = "/MenuBar";
+    const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+    const INTROSPECTABLE: &str = "org.freedesktop.DBus.Introspectable";
+    const UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+
+    /// Registers the item with the panel's watcher, then answers the panel
+    /// until the app goes away.
+    pub fn serve(events: mpsc::UnboundedSender<Event>) -> io::Result<()> {
+        let mut connection = Connection::session()?;
+        let name = format!("{}-{}-1", ITEM, std::process::id());
+        if !connection.request_name(&name)? {
+            return Err(io::Error::other(format!("{} is already taken", name)));
+        }
+        connection.call(
+            "org.kde.StatusNotifierWatcher",
+            "/StatusNotifierWatcher",
+            "org.kde.StatusNotifierWatcher",
+            "RegisterStatusNotifierItem",
+            vec![Value::String(name)],
+        )?;
+        let sender = connection.sender();
+
+        loop {
+            let call = connection.receive()?;
+            if !call.is_call() {
+                continue;
+            }
+            let event = answer(&sender, &call)?;
+            if let Some(event) = event {
+                if events.unbounded_send(event).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn answer(sender: &Sender, call: &Message) -> io::Result<Option<Event>> {
+        let string = |at: usize| call.body.get(at).and_then(Value::as_str).unwrap_or("");
+        let object = match call.path.as_deref() {
+            Some(ITEM_PATH) => ITEM,
+            Some(MENU_PATH) => MENU,
+            _ => "",
+        };
+        match (object, call.interface.as_deref(), call.member.as_deref()) {
+            (ITEM, Some(ITEM), Some("Activate" | "SecondaryActivate")) => {
+                sender.reply(call, Vec::new())?;
+                return Ok(Some(Event::Show));
+            }
+            (ITEM, Some(ITEM), Some("ContextMenu" | "Scroll")) => sender.reply(call, Vec::new())?,
+            (_, Some(PROPERTIES), Some("GetAll")) if string(0) == object => {
+                sender.reply(call, vec![Value::dict(properties(object))])?
+            }
+            (_, Some(PROPERTIES), Some("Get")) if string(0) == object => {
+                let property = properties(object)
+                    .into_iter()
+                    .find(|(name, _)| *name == string(1));
+                match property {
+                    Some((_, value)) => {
+                        sender.reply(call, vec![Value::Variant(Box::new(value))])?
+                    }
+                    None => sender.reply_error(
+                        call,
+                        "org.freedesktop.DBus.Error.UnknownProperty",
+                        "no such property",
+                    )?,
+                }
+            }
+            (_, Some(INTROSPECTABLE), Some("Introspect")) if !object.is_empty() => {
+                sender.reply(call, vec![Value::String(introspection(object))])?
+            }
+            (MENU, Some(MENU), Some("GetLayout")) => {
+                let children = entries()
+                    .into_iter()
+                    .map(|(id, label)| Value::Variant(Box::new(entry(id, label))))
+                    .collect();
+                let root = Value::Struct(vec![
+                    Value::Int32(0),
+                    Value::dict(vec![(
+                        "children-display",
+                        Value::String(String::from("submenu")),
+                    )]),
+                    Value::Array(String::from("v"), children),
+                ]);
+                sender.reply(call, vec![Value::Uint32(1), root])?
+            }
+            (MENU, Some(MENU), Some("GetGroupProperties")) => {
+                let ids: Vec<i32> = call
+                    .body
+                    .first()
+                    .and_then(Value::items)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|id| match id {
+                        Value::Int32(id) => Some(*id),
+                        _ => None,
+                    })
+                    .collect();
+                let entries = entries()
+                    .into_iter()
+                    .filter(|(id, _)| ids.is_empty() || ids.contains(id))
+                    .map(|(id, label)| {
+                        Value::Struct(vec![Value::Int32(id), entry_properties(label)])
+                    })
+                    .collect();
+                sender.reply(call, vec![Value::Array(String::from("(ia{sv})"), entries)])?
+            }
+            (MENU, Some(MENU), Some("Event")) => {
+                sender.reply(call, Vec::new())?;
+                return Ok(clicked(&call.body));
+            }
+            (MENU, Some(MENU), Some("EventGroup")) => {
+                sender.reply(call, vec![Value::Array(String::from("i"), Vec::new())])?;
+                let events = call.body.first().and_then(Value::items).unwrap_or_default();
+                return Ok(events
+                    .iter()
+                    .find_map(|event| clicked(event.items().unwrap_or_default())));
+            }
+            (MENU, Some(MENU), Some("AboutToShow")) => {
+                sender.reply(call, vec![Value::Bool(false)])?
+            }
+            (MENU, Some(MENU), Some("AboutToShowGroup")) => sender.reply(
+                call,
+                vec![
+                    Value::Array(String::from("i"), Vec::new()),
+                    Value::Array(String::from("i"), Vec::new()),
+                ],
+            )?,
+            _ => sender.reply_error(call, UNKNOWN_METHOD, "no such method here")?,
+        }
+        Ok(None)
+    }
+
+    /// The entry an `Event`'s `(i id, s kind, v data, u time)` clicked.
+    fn clicked(body: &[Value]) -> Option<Event> {
+        match body {
+            [Value::Int32(id), kind, ..] if kind.as_str() == Some("clicked") => event(*id),
+            _ => None,
+        }
+    }
+
+    fn properties(object: &str) -> Vec<(&'static str, Value)> {
+        let string = |text: &str| Value::String(text.to_string());
+        match object {
+            ITEM => vec![
+                ("Category", string("ApplicationStatus")),
+                ("Id", string("iced-fm")),
+                ("Title", string("FilePicker")),
+                ("Status", string("Active")),
+                ("IconName", string("system-file-manager")),
+                ("ItemIsMenu", Value::Bool(false)),
+                ("Menu", Value::ObjectPath(String::from(MENU_PATH))),
+                (
+                    "ToolTip",
+                    Value::Struct(vec![
+                        string(""),
+                        Value::Array(String::from("(iiay)"), Vec::new()),
+                        string("FilePicker"),
+                        string(""),
+                    ]),
+                ),
+            ],
+            MENU => vec![
+                ("Version", Value::Uint32(3)),
+                ("TextDirection", string("ltr")),
+                ("Status", string("normal")),
+                ("IconThemePath", Value::Array(String::from("s"), Vec::new())),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    fn entry(id: i32, label: &str) -> Value {
+        Value::Struct(vec![
+            Value::Int32(id),
+            entry_properties(label),
+            Value::Array(String::from("v"), Vec::new()),
+        ])
+    }
+
+    fn entry_properties(label: &str) -> Value {
+        Value::dict(vec![("label", Value::String(label.to_string()))])
+    }
+
+    fn introspection(object: &str) -> String {
+        let methods = match object {
+            ITEM => {
+                r#"<method name="Activate"><arg type="i" direction="in"/><arg type="i" direction="in"/></method>
+<method name="SecondaryActivate"><arg type="i" direction="in"/><arg type="i" direction="in"/></method>
+<method name="ContextMenu"><arg type="i" direction="in"/><arg type="i" direction="in"/></method>
+<method name="Scroll"><arg type="i" direction="in"/><arg type="s" direction="in"/></method>"#
+            }
+            _ => {
+                r#"<method name="GetLayout"><arg type="i" direction="in"/><arg type="i" direction="in"/><arg type="as" direction="in"/><arg type="u" direction="out"/><arg type="(ia{sv}av)" direction="out"/></method>
+<method name="GetGroupProperties"><arg type="ai" direction="in"/><arg type="as" direction="in"/><arg type="a(ia{sv})" direction="out"/></method>
+<method name="Event"><arg type="i" direction="in"/><arg type="s" direction="in"/><arg type="v" direction="in"/><arg type="u" direction="in"/></method>
+<method name="EventGroup"><arg type="a(isvu)" direction="in"/><arg type="ai" direction="out"/></method>
+<method name="AboutToShow"><arg type="i" direction="in"/><arg type="b" direction="out"/></method>
+<method name="AboutToShowGroup"><arg type="ai" direction="in"/><arg type="ai" direction="out"/><arg type="ai" direction="out"/></method>"#
+            }
+        };
+        format!(
+            "<node><interface name=\"{}\">\n{}\n</interface></node>",
+            object, methods
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::tray::{QUIT, SHOW};
+
+        #[test]
+        fn knows_the_entries_clicked() {
+            let click = |id, kind: &str| {
+                vec![
+                    Value::Int32(id),
+                    Value::String(kind.to_string()),
+                    Value::Variant(Box::new(Value::Int32(0))),
+                    Value::Uint32(0),
+                ]
+            };
+            assert_eq!(clicked(&click(SHOW, "clicked")), Some(Event::Show));
+            assert_eq!(clicked(&click(QUIT, "clicked")), Some(Event::Quit));
+            assert_eq!(clicked(&click(QUIT, "hovered")), None);
+            assert_eq!(clicked(&click(7, "clicked")), None);
+        }
+
+        #[test]
+        fn lays_out_the_menu_as_dbusmenu_asks() {
+            assert_eq!(entry(SHOW, "Show").signature(), "(ia{sv}av)");
+        }
+    }
+}
+
+#[cfg(windows)]
+mod icon {
+    use std::io;
+    use std::sync::{Mutex, PoisonError};
+
+    use iced::futures::channel::mpsc;
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM};
+    use windows_sys::Win32::UI::Shell::{
+        Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        AppendMenuW, CreatePopupMenu, CreateWindowExW, DefWindowProcW, DestroyMenu,
+        DispatchMessageW, GetCursorPos, GetMessageW, LoadIconW, PostQuitMessage, RegisterClassW,
+        SetForegroundWindow, TrackPopupMenu, TranslateMessage, HWND_MESSAGE, IDI_APPLICATION,
+        MF_STRING, MSG, TPM_NONOTIFY, TPM_RETURNCMD, WM_APP, WM_LBUTTONUP, WM_RBUTTONUP, WNDCLASSW,
+    };
+
+    use super::{entries, event, Event};
+
+    /// What the icon sends the window it belongs to when clicked.
+    const CALLBACK: u32 = WM_APP + 1;
+
+    /// Where the window procedure sends what was picked; one icon per
+    /// process.
+    static EVENTS: Mutex<Option<mpsc::UnboundedSender<Event>>> = Mutex::new(None);
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(Some(0)).collect()
+    }
+
+    /// Adds the icon to the notification area, on a message-only window of
+    /// its own, and runs that window's message loop.
+    pub fn serve(events: mpsc::UnboundedSender<Event>) -> io::Result<()> {
+        *EVENTS.lock().unwrap_or_else(PoisonError::into_inner) = Some(events);
+
+        let class = wide("iced-fm tray");
+        unsafe {
+            let mut window_class: WNDCLASSW = std::mem::zeroed();
+            window_class.lpfnWndProc = Some(procedure);
+            window_class.lpszClassName = class.as_ptr();
+            if RegisterClassW(&window_class) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let window = CreateWindowExW(
+                0,
+                class.as_ptr(),
+                class.as_ptr(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                0,
+                0,
+                std::ptr::null(),
+            );
+            if window == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut data = icon_data(window);
+            data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+            data.uCallbackMessage = CALLBACK;
+            data.hIcon = LoadIconW(0, IDI_APPLICATION);
+            for (at, unit) in "FilePicker".encode_utf16().take(127).enumerate() {
+                data.szTip[at] = unit;
+            }
+            if Shell_NotifyIconW(NIM_ADD, &data) == 0 {
+                return Err(io::Error::other("the notification area refused the icon"));
+            }
+
+            let mut message: MSG = std::mem::zeroed();
+            while GetMessageW(&mut message, 0, 0, 0) > 0 {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+            Shell_NotifyIconW(NIM_DELETE, &icon_data(window));
+        }
+        Ok(())
+    }
+
+    unsafe fn icon_data(window: HWND) -> NOTIFYICONDATAW {
+        let mut data: NOTIFYICONDATAW = std::mem::zeroed();
+        data.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+        data.hWnd = window;
+        data.uID = 1;
+        data
+    }
+
+    unsafe extern "system" fn procedure(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message != CALLBACK {
+            return DefWindowProcW(window, message, wparam, lparam);
+        }
+        let picked = match lparam as u32 {
+            WM_LBUTTONUP => Some(Event::Show),
+            WM_RBUTTONUP => menu(window),
+            _ => None,
+        };
+        if let Some(picked) = picked {
+            if picked == Event::Quit {
+                Shell_NotifyIconW(NIM_DELETE, &icon_data(window));
+            }
+            let events = EVENTS.lock().unwrap_or_else(PoisonError::into_inner);
+            let sent = events
+                .as_ref()
+                .is_some_and(|events| events.unbounded_send(picked).is_ok());
+            if !sent || picked == Event::Quit {
+                PostQuitMessage(0);
+            }
+        }
+        0
+    }
+
+    /// Pops up Show and Quit where the pointer is, and returns the pick.
+    unsafe fn menu(window: HWND) -> Option<Event> {
+        let menu = CreatePopupMenu();
+        if menu == 0 {
+            return None;
+        }
+        for (id, label) in entries() {
+            let label = wide(label);
+            AppendMenuW(menu, MF_STRING, id as usize, label.as_ptr());
+        }
+
+        let mut point = POINT { x: 0, y: 0 };
+        GetCursorPos(&mut point);
+        // Without this the menu stays open after clicking elsewhere.
+        SetForegroundWindow(window);
+        let picked = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_NONOTIFY,
+            point.x,
+            point.y,
+            0,
+            window,
+            std::ptr::null(),
+        );
+        DestroyMenu(menu);
+        event(picked)
+    }
+}
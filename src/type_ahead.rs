@@ -0,0 +1,81 @@
+//! Select-on-type: what is typed over the listing, each key within a
+//! second of the last, adds up to the start of the name to select.
+
+use std::time::{Duration, Instant};
+
+use iced::{subscription, Subscription};
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+const TICK: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Typed(char),
+    Tick,
+}
+
+#[derive(Default)]
+pub struct TypeAhead {
+    prefix: String,
+    typed: Option<Instant>,
+}
+
+impl TypeAhead {
+    /// The prefix with the key just typed; `None` for ticks.
+    pub fn update(&mut self, message: Message) -> Option<&str> {
+        let expired = self.typed.is_none_or(|typed| typed.elapsed() >= TIMEOUT);
+        match message {
+            Message::Typed(c) => {
+                if expired {
+                    self.prefix.clear();
+                }
+                self.prefix.push(c);
+                self.typed = Some(Instant::now());
+                Some(&self.prefix)
+            }
+            Message::Tick => {
+                if expired {
+                    self.prefix.clear();
+                    self.typed = None;
+                }
+                None
+            }
+        }
+    }
+
+    /// What has been typed, while it still counts.
+    pub fn prefix(&self) -> Option<&str> {
+        self.typed.map(|_| self.prefix.as_str())
+    }
+
+    /// Ticks until the prefix runs out, to take its hint away.
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.typed.is_none() {
+            return Subscription::none();
+        }
+
+        struct Ticker;
+
+        subscription::channel(
+            std::any::TypeId::of::<Ticker>(),
+            1,
+            |mut output| async move {
+                use iced::futures::channel::mpsc;
+                use iced::futures::{SinkExt, StreamExt};
+
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    while sender.unbounded_send(()).is_ok() {
+                        std::thread::sleep(TICK);
+                    }
+                });
+
+                while receiver.next().await.is_some() {
+                    let _ = output.send(Message::Tick).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+}
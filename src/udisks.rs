@@ -0,0 +1,238 @@
+//! Removable drives through UDisks2 on the system bus: the USB sticks and
+//! SD cards plugged in but not mounted, for the sidebar to offer, mounting
+//! them when picked, and word of drives coming and going so the sidebar
+//! keeps up. Elsewhere there are none, and no word.
+
+use std::io;
+use std::path::PathBuf;
+
+use iced::Subscription;
+
+/// A filesystem on a removable drive that is not mounted.
+#[derive(Debug, Clone)]
+pub struct Drive {
+    /// The block device's object on the bus.
+    pub object: String,
+    pub label: String,
+    pub device: String,
+    pub size: u64,
+}
+
+/// The removable drives plugged in with filesystems not mounted.
+#[cfg(target_os = "linux")]
+pub fn unmounted() -> Vec<Drive> {
+    bus::drives().unwrap_or_else(|error| {
+        tracing::debug!(%error, "cannot list drives through UDisks2");
+        Vec::new()
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn unmounted() -> Vec<Drive> {
+    Vec::new()
+}
+
+/// Mounts `drive` where UDisks2 puts such things, giving the folder.
+#[cfg(target_os = "linux")]
+pub fn mount(drive: &Drive) -> io::Result<PathBuf> {
+    bus::mount(drive)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn mount(_drive: &Drive) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "mounting drives needs UDisks2",
+    ))
+}
+
+/// Fires when drives were plugged in, taken out, mounted or unmounted.
+#[cfg(target_os = "linux")]
+pub fn watch() -> Subscription<()> {
+    use std::any::TypeId;
+    use std::thread;
+
+    use iced::futures::channel::mpsc;
+    use iced::futures::{SinkExt, StreamExt};
+    use iced::subscription;
+
+    struct Devices;
+
+    subscription::channel(TypeId::of::<Devices>(), 10, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        thread::spawn(move || {
+            if let Err(error) = bus::listen(sender) {
+                tracing::debug!(%error, "not watching drives through UDisks2");
+            }
+        });
+
+        while let Some(()) = receiver.next().await {
+            let _ = output.send(()).await;
+        }
+
+        std::future::pending().await
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch() -> Subscription<()> {
+    Subscription::none()
+}
+
+#[cfg(target_os = "linux")]
+mod bus {
+    use std::io;
+    use std::path::PathBuf;
+    use std::sync::mpsc::{self as std_mpsc, RecvTimeoutError};
+    use std::time::{Duration, Instant};
+
+    use iced::futures::channel::mpsc;
+
+    use super::Drive;
+    use crate::dbus::{Connection, Value};
+    use crate::i18n::trf;
+    use crate::jobs::format_bytes;
+
+    const UDISKS: &str = "org.freedesktop.UDisks2";
+    const BLOCK: &str = "org.freedesktop.UDisks2.Block";
+    const DRIVE: &str = "org.freedesktop.UDisks2.Drive";
+    const FILESYSTEM: &str = "org.freedesktop.UDisks2.Filesystem";
+
+    /// Plugging a drive in sends a signal for each of its partitions and
+    /// then some; they are told as one after this.
+    const SETTLE: Duration = Duration::from_millis(300);
+
+    pub fn drives() -> io::Result<Vec<Drive>> {
+        let reply = Connection::system()?.call(
+            UDISKS,
+            "/org/freedesktop/UDisks2",
+            "org.freedesktop.DBus.ObjectManager",
+            "GetManagedObjects",
+            Vec::new(),
+        )?;
+        let objects: Vec<(&str, &Value)> = reply
+            .body
+            .first()
+            .and_then(Value::items)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|entry| match entry {
+                Value::DictEntry(path, interfaces) => Some((path.as_str()?, &**interfaces)),
+                _ => None,
+            })
+            .collect();
+        let drive_of = |block: &Value| {
+            let path = block.get("Drive")?.as_str()?;
+            objects
+                .iter()
+                .find(|(object, _)| *object == path)
+                .and_then(|(_, interfaces)| interfaces.get(DRIVE))
+        };
+
+        let drives = objects
+            .iter()
+            .filter_map(|(object, interfaces)| {
+                let filesystem = interfaces.get(FILESYSTEM)?;
+                let block = interfaces.get(BLOCK)?;
+                let flag = |key| block.get(key).and_then(Value::as_bool).unwrap_or(false);
+                let mounted = filesystem
+                    .get("MountPoints")
+                    .and_then(Value::items)
+                    .is_some_and(|points| !points.is_empty());
+                if mounted || flag("HintIgnore") || flag("HintSystem") {
+                    return None;
+                }
+                let drive = drive_of(block)?;
+                let removable = ["Removable", "MediaRemovable"]
+                    .iter()
+                    .any(|key| drive.get(key).and_then(Value::as_bool) == Some(true))
+                    || matches!(
+                        drive.get("ConnectionBus").and_then(Value::as_str),
+                        Some("usb" | "sdio")
+                    );
+                if !removable {
+                    return None;
+                }
+
+                let text = |value: Option<&Value>| {
+                    value
+                        .and_then(Value::as_str)
+                        .filter(|text| !text.is_empty())
+                        .map(String::from)
+                };
+                let size = block.get("Size").and_then(Value::as_u64).unwrap_or(0);
+                let label = text(block.get("IdLabel"))
+                    .or_else(|| text(block.get("HintName")))
+                    .or_else(|| text(drive.get("Model")))
+                    .unwrap_or_else(|| trf("{} drive", &[&format_bytes(size)]));
+                let device = block
+                    .get("PreferredDevice")
+                    .and_then(Value::as_bytes)
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .unwrap_or_default();
+                Some(Drive {
+                    object: object.to_string(),
+                    label,
+                    device,
+                    size,
+                })
+            })
+            .collect();
+
+        Ok(drives)
+    }
+
+    pub fn mount(drive: &Drive) -> io::Result<PathBuf> {
+        let reply = Connection::system()?.call(
+            UDISKS,
+            &drive.object,
+            FILESYSTEM,
+            "Mount",
+            vec![Value::dict(Vec::new())],
+        )?;
+        reply
+            .body
+            .first()
+            .and_then(Value::as_str)
+            .map(PathBuf::from)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed reply"))
+    }
+
+    /// Tells `sender` once drives came, went or were mounted elsewhere and
+    /// the signals about it have stopped for a while.
+    pub fn listen(sender: mpsc::UnboundedSender<()>) -> io::Result<()> {
+        let mut connection = Connection::system()?;
+        connection.add_match(&format!(
+            "type='signal',sender='{}',interface='org.freedesktop.DBus.ObjectManager'",
+            UDISKS
+        ))?;
+        connection.add_match(&format!(
+            "type='signal',sender='{}',interface='org.freedesktop.DBus.Properties',\
+             member='PropertiesChanged',arg0='{}'",
+            UDISKS, FILESYSTEM
+        ))?;
+
+        let (signals, signalled) = std_mpsc::channel();
+        std::thread::spawn(move || {
+            while signalled.recv().is_ok() {
+                let deadline = Instant::now() + SETTLE;
+                while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                    match signalled.recv_timeout(left) {
+                        Ok(()) => {}
+                        Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                tracing::debug!("drives changed");
+                if sender.unbounded_send(()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        loop {
+            if connection.receive()?.is_signal() && signals.send(()).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
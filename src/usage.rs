@@ -0,0 +1,456 @@
+//! "Analyze usage": everything below a folder is scanned in the background,
+//! then shown as bars or as a treemap, largest first, to find what takes
+//! the space. Folders can be entered to look closer.
+
+use std::any::TypeId;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::widget::{button, column, container, progress_bar, row, scrollable, text, Column, Row};
+use iced::{subscription, theme, Alignment, Element, Length, Subscription};
+
+use crate::devices;
+use crate::i18n::{tr, trf};
+use crate::jobs::format_bytes;
+use crate::style::Style;
+
+/// Files kept by name in each folder; smaller ones are summed up together.
+const KEPT_FILES: usize = 50;
+/// Entries shown in a folder, as bars or tiles.
+const SHOWN: usize = 100;
+const TILES: usize = 20;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chart {
+    Bars,
+    Treemap,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Entries and bytes seen so far by scan `usize`.
+    Progress(usize, u64, u64),
+    Scanned(usize, Result<Node, String>),
+    /// Looks into the entry at this index of the folder being shown.
+    Enter(usize),
+    Up,
+    Chart(Chart),
+    Rescan,
+    Cancel,
+    /// Shows the folder being looked at in the listing.
+    Open,
+    Close,
+}
+
+pub enum Event {
+    Open(PathBuf),
+    Closed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    name: String,
+    path: PathBuf,
+    size: u64,
+    is_dir: bool,
+    /// Largest first.
+    children: Vec<Node>,
+}
+
+enum State {
+    Scanning { entries: u64, bytes: u64 },
+    Done(Node),
+    Failed(String),
+    Cancelled,
+}
+
+pub struct Usage {
+    root: PathBuf,
+    /// Counts scans so a rescan ignores what an earlier one still sends.
+    scan: usize,
+    stop: Arc<AtomicBool>,
+    state: State,
+    /// Indices from the root to the folder being looked at.
+    at: Vec<usize>,
+    chart: Chart,
+}
+
+impl Usage {
+    pub fn open(root: PathBuf) -> Usage {
+        Usage {
+            root,
+            scan: 0,
+            stop: Arc::new(AtomicBool::new(false)),
+            state: State::Scanning {
+                entries: 0,
+                bytes: 0,
+            },
+            at: Vec::new(),
+            chart: Chart::Bars,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Event> {
+        match message {
+            Message::Progress(scan, entries, bytes) if scan == self.scan => {
+                if let State::Scanning { .. } = self.state {
+                    self.state = State::Scanning { entries, bytes };
+                }
+            }
+            Message::Scanned(scan, result) if scan == self.scan => {
+                if let State::Scanning { .. } = self.state {
+                    self.state = match result {
+                        Ok(node) => State::Done(node),
+                        Err(error) => State::Failed(error),
+                    };
+                }
+            }
+            Message::Progress(..) | Message::Scanned(..) => {}
+            Message::Enter(index) => {
+                if self
+                    .current()
+                    .and_then(|node| node.children.get(index))
+                    .is_some_and(|child| child.is_dir)
+                {
+                    self.at.push(index);
+                }
+            }
+            Message::Up => {
+                self.at.pop();
+            }
+            Message::Chart(chart) => self.chart = chart,
+            Message::Rescan => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.stop = Arc::new(AtomicBool::new(false));
+                self.scan += 1;
+                self.at.clear();
+                self.state = State::Scanning {
+                    entries: 0,
+                    bytes: 0,
+                };
+            }
+            Message::Cancel => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.state = State::Cancelled;
+            }
+            Message::Open => {
+                let path = self.current().map_or(&self.root, |node| &node.path);
+                return Some(Event::Open(path.clone()));
+            }
+            Message::Close => {
+                self.stop.store(true, Ordering::Relaxed);
+                return Some(Event::Closed);
+            }
+        }
+
+        None
+    }
+
+    fn current(&self) -> Option<&Node> {
+        let State::Done(root) = &self.state else {
+            return None;
+        };
+        let mut node = root;
+        for &index in &self.at {
+            node = node.children.get(index)?;
+        }
+
+        Some(node)
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        struct Scan;
+
+        if !matches!(self.state, State::Scanning { .. }) {
+            return Subscription::none();
+        }
+
+        let (scan, root, stop) = (self.scan, self.root.clone(), self.stop.clone());
+        subscription::channel(
+            (TypeId::of::<Scan>(), root.clone(), scan),
+            100,
+            move |mut output| async move {
+                let (sender, mut receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    let Some(_turn) = devices::wait_for(&[&root], || stop.load(Ordering::Relaxed))
+                    else {
+                        return;
+                    };
+                    let mut progress = Progress {
+                        entries: 0,
+                        bytes: 0,
+                        reported: Instant::now(),
+                        report: |entries, bytes| {
+                            let _ = sender.unbounded_send(Message::Progress(scan, entries, bytes));
+                        },
+                    };
+                    let result = walk(&root, &stop, &mut progress);
+                    let _ = sender.unbounded_send(Message::Scanned(scan, result));
+                });
+
+                while let Some(message) = receiver.next().await {
+                    let _ = output.send(message).await;
+                }
+
+                std::future::pending().await
+            },
+        )
+    }
+
+    pub fn view(&self, style: &Style) -> Element<'_, Message> {
+        let current = self.current();
+        let path = current.map_or(&self.root, |node| &node.path);
+        let mut header = row!(
+            button(tr("Up")).on_press_maybe((!self.at.is_empty()).then_some(Message::Up)),
+            text(path.display().to_string()).width(Length::Fill),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center);
+        if let Some(node) = current {
+            header = header.push(text(format_bytes(node.size)));
+        }
+
+        let body: Element<Message> = match &self.state {
+            State::Scanning { entries, bytes } => text(trf(
+                "Scanning… {} entries, {}",
+                &[&entries, &format_bytes(*bytes)],
+            ))
+            .into(),
+            State::Failed(error) => text(error).style(theme::Text::Color(style.error)).into(),
+            State::Cancelled => text(tr("Scan cancelled")).into(),
+            State::Done(_) => match (current, self.chart) {
+                (Some(node), Chart::Bars) => bars(node, style),
+                (Some(node), Chart::Treemap) => treemap(node, 0, style),
+                (None, _) => text("").into(),
+            },
+        };
+
+        let scanning = matches!(self.state, State::Scanning { .. });
+        let chart = |label, chart| {
+            button(label)
+                .style(if self.chart == chart {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Secondary
+                })
+                .on_press(Message::Chart(chart))
+        };
+        let buttons = row!(
+            chart("Bars", Chart::Bars),
+            chart("Treemap", Chart::Treemap),
+            text("").width(Length::Fill),
+            button(tr("Show in list")).on_press_maybe(current.map(|_| Message::Open)),
+            if scanning {
+                button(tr("Cancel")).on_press(Message::Cancel)
+            } else {
+                button(tr("Rescan")).on_press(Message::Rescan)
+            },
+            button(tr("Close")).on_press(Message::Close),
+        )
+        .spacing(5)
+        .align_items(Alignment::Center);
+
+        container(
+            column!(
+                text(tr("Disk usage")).size(20),
+                header,
+                container(body).height(Length::Fill),
+                buttons
+            )
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .max_width(900)
+        .max_height(700)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+fn bars<'a>(node: &'a Node, style: &Style) -> Element<'a, Message> {
+    let mut col = column!().spacing(4);
+    for (index, child) in node.children.iter().enumerate().take(SHOWN) {
+        let name = button(text(label(child)))
+            .style(theme::Button::Text)
+            .on_press_maybe(child.is_dir.then_some(Message::Enter(index)))
+            .width(Length::FillPortion(2));
+        col = col.push(
+            row!(
+                name,
+                progress_bar(0.0..=node.size.max(1) as f32, child.size as f32)
+                    .height(Length::Fixed(8.))
+                    .width(Length::FillPortion(3)),
+                text(format_bytes(child.size))
+                    .size(style.small_text_size)
+                    .width(Length::Fixed(90.)),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+    }
+    if node.children.is_empty() {
+        col = col.push(text(tr("Empty")));
+    }
+
+    scrollable(col).height(Length::Fill).into()
+}
+
+/// Slice-and-dice: the largest entries side by side in proportion to their
+/// size, their own entries across them one level further down.
+fn treemap<'a>(node: &'a Node, depth: usize, style: &Style) -> Element<'a, Message> {
+    let total = node.size.max(1);
+    let portion = |size: u64| ((size * 1000 / total) as u16).max(1);
+    let tiles = node
+        .children
+        .iter()
+        .enumerate()
+        .take(TILES)
+        .filter(|(_, child)| child.size > 0);
+
+    let tile = |index: usize, child: &'a Node| -> Element<'a, Message> {
+        let inner: Element<Message> = if depth == 0 && child.is_dir && !child.children.is_empty() {
+            column!(
+                text(label(child)).size(style.small_text_size),
+                treemap(child, depth + 1, style)
+            )
+            .into()
+        } else {
+            column!(
+                text(label(child)).size(style.small_text_size),
+                text(format_bytes(child.size)).size(style.small_text_size)
+            )
+            .into()
+        };
+        // Only the tiles of the folder shown lead anywhere; clicks on those
+        // nested in them go to the tile around.
+        let enter = (depth == 0 && child.is_dir).then_some(Message::Enter(index));
+        let tile = button(inner)
+            .style(if child.is_dir {
+                theme::Button::Secondary
+            } else {
+                theme::Button::Text
+            })
+            .padding(2)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press_maybe(enter);
+        container(tile)
+            .style(theme::Container::Box)
+            .width(Length::FillPortion(portion(child.size)))
+            .height(Length::FillPortion(portion(child.size)))
+            .into()
+    };
+
+    if depth == 0 {
+        let mut tiles_row = Row::new().spacing(2).height(Length::Fill);
+        for (index, child) in tiles {
+            tiles_row = tiles_row.push(tile(index, child));
+        }
+        tiles_row.into()
+    } else {
+        let mut tiles_col = Column::new().spacing(2).width(Length::Fill);
+        for (index, child) in tiles {
+            tiles_col = tiles_col.push(tile(index, child));
+        }
+        tiles_col.into()
+    }
+}
+
+fn label(node: &Node) -> String {
+    if node.is_dir {
+        format!("{} /", node.name)
+    } else {
+        node.name.clone()
+    }
+}
+
+struct Progress<F> {
+    entries: u64,
+    bytes: u64,
+    reported: Instant,
+    report: F,
+}
+
+impl<F: FnMut(u64, u64)> Progress<F> {
+    fn add(&mut self, bytes: u64) {
+        self.entries += 1;
+        self.bytes += bytes;
+        if self.reported.elapsed() >= PROGRESS_INTERVAL {
+            self.reported = Instant::now();
+            (self.report)(self.entries, self.bytes);
+        }
+    }
+}
+
+/// Sizes everything below `dir`, not following symlinks. Unreadable
+/// folders count as empty rather than failing the scan.
+fn walk<F: FnMut(u64, u64)>(
+    dir: &Path,
+    stop: &AtomicBool,
+    progress: &mut Progress<F>,
+) -> Result<Node, String> {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.display().to_string());
+    let mut folders = Vec::new();
+    let mut files = Vec::new();
+
+    match fs::read_dir(dir) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                if stop.load(Ordering::Relaxed) {
+                    return Err(String::from("Cancelled"));
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    progress.add(0);
+                    folders.push(walk(&entry.path(), stop, progress)?);
+                } else {
+                    progress.add(metadata.len());
+                    files.push(Node {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        path: entry.path(),
+                        size: metadata.len(),
+                        is_dir: false,
+                        children: Vec::new(),
+                    });
+                }
+            }
+        }
+        Err(error) => tracing::debug!(%error, dir = %dir.display(), "cannot scan folder"),
+    }
+
+    files.sort_by_key(|file| std::cmp::Reverse(file.size));
+    if files.len() > KEPT_FILES {
+        let rest = files.split_off(KEPT_FILES);
+        files.push(Node {
+            name: format!("{} smaller files", rest.len()),
+            path: dir.to_path_buf(),
+            size: rest.iter().map(|file| file.size).sum(),
+            is_dir: false,
+            children: Vec::new(),
+        });
+    }
+    let mut children = folders;
+    children.append(&mut files);
+    children.sort_by_key(|child| std::cmp::Reverse(child.size));
+
+    Ok(Node {
+        name,
+        path: dir.to_path_buf(),
+        size: children.iter().map(|child| child.size).sum(),
+        is_dir: true,
+        children,
+    })
+}
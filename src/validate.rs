@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::{tr, trf};
+use crate::mounts;
+
+/// Characters and names that FAT, exFAT and NTFS refuse, whatever the host OS.
+const WINDOWS_ILLEGAL: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+const WINDOWS_FILESYSTEMS: &[&str] = &["vfat", "msdos", "exfat", "ntfs", "ntfs3", "fuseblk"];
+
+/// Checks that `name` can be created inside `dir`, returning a message fit
+/// to show next to the input when it cannot. When `renaming`, the entry may
+/// take a name that differs from its own only by case, which on a
+/// case-insensitive `dir` is the entry itself rather than a collision.
+pub fn validate_name(
+    dir: &Path,
+    name: &str,
+    renaming: Option<&Path>,
+    case_sensitive: bool,
+) -> Result<PathBuf, String> {
+    if name.is_empty() {
+        return Err(String::from(tr("Name cannot be empty")));
+    }
+    if name == "." || name == ".." {
+        return Err(trf("\"{}\" is reserved", &[&name]));
+    }
+    if name.contains('\0') {
+        return Err(String::from(tr("Name cannot contain NUL characters")));
+    }
+    if name.contains('/') || name.contains(std::path::MAIN_SEPARATOR) {
+        return Err(String::from(tr("Name cannot contain path separators")));
+    }
+
+    let max_name = max_name_length(dir);
+    if name.len() > max_name {
+        return Err(trf(
+            "Name is {} bytes long, the limit here is {}",
+            &[&name.len(), &max_name],
+        ));
+    }
+
+    if windows_rules(dir) {
+        if let Some(c) = name
+            .chars()
+            .find(|c| WINDOWS_ILLEGAL.contains(c) || c.is_control())
+        {
+            return Err(trf(
+                "\"{}\" is not allowed on this filesystem",
+                &[&c.escape_default()],
+            ));
+        }
+        if name.ends_with('.') || name.ends_with(' ') {
+            return Err(String::from(tr(
+                "Name cannot end with a dot or a space on this filesystem",
+            )));
+        }
+        let stem = name.split('.').next().unwrap_or(name).trim_end();
+        if WINDOWS_RESERVED
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+        {
+            return Err(trf("\"{}\" is a reserved device name", &[&stem]));
+        }
+    }
+
+    let path = dir.join(name);
+    let max_path = max_path_length(dir);
+    if path.as_os_str().len() > max_path {
+        return Err(trf(
+            "Full path would be {} bytes long, the limit here is {}",
+            &[&path.as_os_str().len(), &max_path],
+        ));
+    }
+    let same_entry = !case_sensitive
+        && renaming.and_then(Path::file_name).is_some_and(|original| {
+            original.to_string_lossy().to_lowercase() == name.to_lowercase()
+        });
+    if path.symlink_metadata().is_ok() && !same_entry {
+        return Err(trf("\"{}\" already exists", &[&name]));
+    }
+
+    Ok(path)
+}
+
+/// Whether names in `dir` that differ only by case are different entries.
+/// An existing entry is looked up under its flipped-case name first; only
+/// when there is none to try is a probe file created. Unwritable folders
+/// fall back to the platform's usual behaviour.
+pub fn case_sensitive(dir: &Path) -> bool {
+    let candidate = fs::read_dir(dir).ok().and_then(|entries| {
+        entries.flatten().find_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let flipped = flip_case(&name);
+            (flipped != name).then(|| (entry.path(), dir.join(flipped)))
+        })
+    });
+    if let Some((path, flipped)) = candidate {
+        return !same_file(&path, &flipped);
+    }
+
+    let probe = dir.join(format!(".iced-fm-case-probe-{}", std::process::id()));
+    match fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            let sensitive = !dir
+                .join(flip_case(
+                    &probe.file_name().unwrap_or_default().to_string_lossy(),
+                ))
+                .exists();
+            let _ = fs::remove_file(&probe);
+            sensitive
+        }
+        Err(_) => !(cfg!(windows) || cfg!(target_os = "macos")),
+    }
+}
+
+fn flip_case(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (a.symlink_metadata(), b.symlink_metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn windows_rules(dir: &Path) -> bool {
+    cfg!(windows)
+        || mounts::mount_for(dir)
+            .is_some_and(|mount| WINDOWS_FILESYSTEMS.contains(&mount.fs_type.as_str()))
+}
+
+#[cfg(unix)]
+fn path_conf(dir: &Path, name: libc::c_int) -> Option<usize> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `dir` is a valid NUL-terminated string for the duration of the call.
+    let value = unsafe { libc::pathconf(dir.as_ptr(), name) };
+
+    (value > 0).then_some(value as usize)
+}
+
+#[cfg(unix)]
+fn max_name_length(dir: &Path) -> usize {
+    path_conf(dir, libc::_PC_NAME_MAX).unwrap_or(255)
+}
+
+#[cfg(unix)]
+fn max_path_length(dir: &Path) -> usize {
+    path_conf(dir, libc::_PC_PATH_MAX).unwrap_or(4096)
+}
+
+#[cfg(not(unix))]
+fn max_name_length(_dir: &Path) -> usize {
+    255
+}
+
+#[cfg(not(unix))]
+fn max_path_length(_dir: &Path) -> usize {
+    260
+}
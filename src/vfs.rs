@@ -0,0 +1,328 @@
+//! The file operations listing, jobs, search and previews go through, so a
+//! backend other than the local disk can stand in for any of them.
+//! [`for_path`] is where backends plug in.
+
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::archive;
+use crate::remote;
+use crate::webdav;
+
+#[cfg(test)]
+pub mod memory;
+
+/// What is known of an entry, without following a symlink.
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub len: u64,
+    /// `None` when the backend does not say.
+    pub modified: Option<SystemTime>,
+    /// The Unix mode, the kind of entry included, when the backend keeps one.
+    pub mode: Option<u32>,
+}
+
+/// An entry of a folder as a listing gives it.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub meta: Metadata,
+    /// Where a symlink points, as it was written.
+    pub link_target: Option<PathBuf>,
+}
+
+/// File contents being read, which image headers are looked for in.
+pub trait Source: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> Source for T {}
+
+pub trait VirtualFs: Send + Sync {
+    /// The paths in `dir`, with an error in place of any unreadable entry.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<io::Result<PathBuf>>>;
+
+    /// The entries of `dir` with what is known of each. Backends that learn
+    /// it all from one request say so here rather than once per entry.
+    fn list(&self, dir: &Path) -> io::Result<Vec<io::Result<Entry>>> {
+        Ok(self
+            .read_dir(dir)?
+            .into_iter()
+            .map(|path| {
+                let path = path?;
+                let meta = self.metadata(&path)?;
+                let link_target = match meta.is_symlink {
+                    true => self.read_link(&path).ok(),
+                    false => None,
+                };
+                Ok(Entry {
+                    path,
+                    meta,
+                    link_target,
+                })
+            })
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Where the symlink at `path` points.
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(unsupported("symlinks"))
+    }
+
+    /// Creates `link` pointing at `target`, which is taken from the folder
+    /// of `link` when relative.
+    fn symlink(&self, _target: &Path, _link: &Path) -> io::Result<()> {
+        Err(unsupported("symlinks"))
+    }
+
+    /// Copies a file's contents, returning how many bytes went.
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Removes a file, symlink or empty folder.
+    fn delete(&self, path: &Path) -> io::Result<()>;
+
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Creates an empty file, failing if the name is taken.
+    fn create_file(&self, path: &Path) -> io::Result<()> {
+        if self.metadata(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+        self.open_write(path)?.flush()
+    }
+
+    fn open_read(&self, path: &Path) -> io::Result<Box<dyn Source>>;
+
+    /// Creates or truncates `path`. Flushing may finish the file, after
+    /// which nothing more can be written.
+    fn open_write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// Whether nothing here can be changed, as inside an archive.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+}
+
+fn unsupported(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} are not supported here", what),
+    )
+}
+
+pub struct LocalFs;
+
+impl VirtualFs for LocalFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<io::Result<PathBuf>>> {
+        Ok(fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let meta = fs::symlink_metadata(path)?;
+        Ok(Metadata {
+            is_dir: meta.is_dir(),
+            is_symlink: meta.is_symlink(),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+            mode: mode(&meta),
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    /// Windows tells links to folders from links to files, so `target` is
+    /// looked at first.
+    #[cfg(windows)]
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let resolved = link.parent().unwrap_or(Path::new("")).join(target);
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        fs::copy(from, to)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn delete(&self, path: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(path)?.is_dir() {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<()> {
+        fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(drop)
+    }
+
+    fn open_read(&self, path: &Path) -> io::Result<Box<dyn Source>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn open_write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(fs::File::create(path)?))
+    }
+}
+
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(meta.mode())
+}
+
+#[cfg(not(unix))]
+fn mode(_meta: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// The backend `path` lives on. An archive itself is a file on the disk;
+/// what is inside it is the archive's.
+pub fn for_path(path: &Path) -> &'static dyn VirtualFs {
+    #[cfg(test)]
+    if memory::owns(path) {
+        return &memory::FS;
+    }
+    if webdav::is_webdav(path) {
+        return webdav::backend();
+    }
+    if remote::is_remote(path) {
+        return remote::backend();
+    }
+    if archive::split(path).is_some_and(|(_, inner)| !inner.as_os_str().is_empty()) {
+        return archive::backend();
+    }
+    &LocalFs
+}
+
+/// The backend listing `dir`, which for an archive is the archive's.
+pub fn for_dir(dir: &Path) -> &'static dyn VirtualFs {
+    match archive::split(dir) {
+        Some(_) => archive::backend(),
+        None => for_path(dir),
+    }
+}
+
+/// Whether `dir` is a folder on the disk, listed as such, rather than an
+/// archive or a folder elsewhere.
+pub fn is_disk_folder(dir: &Path) -> bool {
+    std::ptr::addr_eq(for_dir(dir), &LocalFs)
+}
+
+pub fn is_local(path: &Path) -> bool {
+    same_backend(path, Path::new("/"))
+}
+
+/// Whether one backend can go between `a` and `b` by itself.
+pub fn same_backend(a: &Path, b: &Path) -> bool {
+    std::ptr::addr_eq(for_path(a), for_path(b))
+}
+
+pub fn exists(path: &Path) -> bool {
+    for_path(path).metadata(path).is_ok()
+}
+
+pub fn is_dir(path: &Path) -> bool {
+    for_path(path).metadata(path).is_ok_and(|meta| meta.is_dir)
+}
+
+/// Creates `path` and whatever folders above it are missing.
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    let fs = for_path(path);
+    match fs.metadata(path) {
+        Ok(meta) if meta.is_dir => return Ok(()),
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is not a folder", path.display()),
+            ))
+        }
+        Err(_) => {}
+    }
+    if let Some(parent) = path.parent().filter(|parent| fs.metadata(parent).is_err()) {
+        create_dir_all(parent)?;
+    }
+    match fs.create_dir(path) {
+        // Made meanwhile by someone else.
+        Err(_) if is_dir(path) => Ok(()),
+        created => created,
+    }
+}
+
+/// Deletes `path` and, for a folder, everything inside.
+pub fn remove_tree(path: &Path) -> io::Result<()> {
+    let fs = for_path(path);
+    if fs.metadata(path)?.is_dir {
+        for path in fs.read_dir(path)? {
+            remove_tree(&path?)?;
+        }
+    }
+    fs.delete(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creates_the_folders_missing_above() {
+        let top = Path::new(memory::ROOT).join("create-dir-all");
+        memory::FS.add_dir(&top);
+        let deep = top.join("a").join("b");
+        create_dir_all(&deep).unwrap();
+        assert!(is_dir(&top.join("a")) && is_dir(&deep));
+        // Already there.
+        create_dir_all(&deep).unwrap();
+
+        memory::FS.add_file(&top.join("file"), b"");
+        assert!(create_dir_all(&top.join("file")).is_err());
+        memory::FS.clear(&top);
+    }
+
+    #[test]
+    fn archives_are_files_listed_by_their_own_backend() {
+        let dir = std::env::temp_dir().join(format!("iced-fm-vfs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("a.zip");
+        fs::write(&archive, "").unwrap();
+
+        assert!(is_local(&archive) && !is_disk_folder(&archive));
+        assert!(!is_local(&archive.join("inside.txt")));
+        assert!(for_dir(&archive).is_read_only());
+        assert!(is_disk_folder(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! A filesystem held in memory, for tests that drive the picker without
+//! touching the disk. Everything under [`ROOT`] lives here.
+
+use std::collections::BTreeMap;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{Metadata, Source, VirtualFs};
+
+pub const ROOT: &str = "/iced-fm-memory";
+
+pub static FS: MemoryFs = MemoryFs {
+    nodes: Mutex::new(BTreeMap::new()),
+};
+
+enum Node {
+    Dir,
+    File(Vec<u8>),
+}
+
+pub struct MemoryFs {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+}
+
+pub fn owns(path: &Path) -> bool {
+    path.starts_with(ROOT)
+}
+
+impl MemoryFs {
+    /// Makes `path` a folder, with the folders above it.
+    pub fn add_dir(&self, path: &Path) {
+        let mut nodes = self.nodes.lock().unwrap();
+        for dir in path.ancestors().take_while(|dir| owns(dir)) {
+            nodes.entry(dir.to_path_buf()).or_insert(Node::Dir);
+        }
+    }
+
+    pub fn add_file(&self, path: &Path, contents: &[u8]) {
+        if let Some(dir) = path.parent() {
+            self.add_dir(dir);
+        }
+        self.put(path, contents.to_vec());
+    }
+
+    /// Forgets `path` and all it holds.
+    pub fn clear(&self, path: &Path) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .retain(|node, _| !node.starts_with(path));
+    }
+
+    fn put(&self, path: &Path, contents: Vec<u8>) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), Node::File(contents));
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+fn taken(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+impl VirtualFs for MemoryFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<io::Result<PathBuf>>> {
+        let nodes = self.nodes.lock().unwrap();
+        match nodes.get(dir) {
+            Some(Node::Dir) => Ok(nodes
+                .keys()
+                .filter(|path| path.parent() == Some(dir))
+                .map(|path| Ok(path.clone()))
+                .collect()),
+            Some(Node::File(_)) => Err(io::Error::other(format!(
+                "{} is not a folder",
+                dir.display()
+            ))),
+            None => Err(not_found(dir)),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        match self.nodes.lock().unwrap().get(path) {
+            Some(Node::Dir) => Ok(Metadata {
+                is_dir: true,
+                is_symlink: false,
+                len: 0,
+                modified: None,
+                mode: None,
+            }),
+            Some(Node::File(contents)) => Ok(Metadata {
+                is_dir: false,
+                is_symlink: false,
+                len: contents.len() as u64,
+                modified: None,
+                mode: None,
+            }),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let contents = match self.nodes.lock().unwrap().get(from) {
+            Some(Node::File(contents)) => contents.clone(),
+            Some(Node::Dir) => return Err(io::Error::other("cannot copy a folder as a file")),
+            None => return Err(not_found(from)),
+        };
+        let len = contents.len() as u64;
+        self.put(to, contents);
+        Ok(len)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if !nodes.contains_key(from) {
+            return Err(not_found(from));
+        }
+        if nodes.contains_key(to) {
+            return Err(taken(to));
+        }
+        let moved: Vec<PathBuf> = nodes
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let (Some(node), Ok(rest)) = (nodes.remove(&path), path.strip_prefix(from)) {
+                nodes.insert(to.join(rest), node);
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if nodes.keys().any(|node| node.parent() == Some(path)) {
+            return Err(io::Error::other(format!("{} is not empty", path.display())));
+        }
+        nodes
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if nodes.contains_key(path) {
+            return Err(taken(path));
+        }
+        nodes.insert(path.to_path_buf(), Node::Dir);
+        Ok(())
+    }
+
+    fn open_read(&self, path: &Path) -> io::Result<Box<dyn Source>> {
+        match self.nodes.lock().unwrap().get(path) {
+            Some(Node::File(contents)) => Ok(Box::new(Cursor::new(contents.clone()))),
+            Some(Node::Dir) => Err(io::Error::other(format!("{} is a folder", path.display()))),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn open_write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.put(path, Vec::new());
+        Ok(Box::new(Writer {
+            path: path.to_path_buf(),
+            written: Vec::new(),
+        }))
+    }
+}
+
+/// Keeps what it is given as the file's contents when flushed or dropped.
+struct Writer {
+    path: PathBuf,
+    written: Vec<u8>,
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        FS.put(&self.path, self.written.clone());
+        Ok(())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
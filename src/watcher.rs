@@ -0,0 +1,117 @@
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self as std_mpsc, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::{subscription, Subscription};
+use notify::{RecursiveMode, Watcher};
+
+/// How long changes are gathered before they are reported together, so a
+/// busy folder, a build writing its output say, is gone through a few times
+/// a second rather than at each write.
+const INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Each path once, however many times it changed: both ends of a
+    /// rename, and what was made and removed again in between.
+    Changed(Vec<PathBuf>),
+    Unavailable,
+}
+
+/// Watches the entries of `dir` (not recursively) and reports the paths of
+/// anything that was created, removed, renamed or modified, at most once
+/// an `INTERVAL`.
+pub fn watch(dir: PathBuf) -> Subscription<Event> {
+    struct DirWatcher;
+
+    subscription::channel(
+        (TypeId::of::<DirWatcher>(), dir.clone()),
+        100,
+        move |mut output| async move {
+            let (sender, mut receiver) = mpsc::unbounded();
+            let (changes, changed) = std_mpsc::channel();
+            let watched = dir.clone();
+            std::thread::spawn(move || coalesce(watched, changed, sender));
+
+            let watcher = notify::recommended_watcher(
+                move |event: notify::Result<notify::Event>| match event {
+                    Ok(event) => {
+                        let _ = changes.send(event.paths);
+                    }
+                    Err(error) => tracing::warn!(%error, "watcher error"),
+                },
+            )
+            .and_then(|mut watcher| {
+                watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+                Ok(watcher)
+            });
+
+            match watcher {
+                Ok(_watcher) => {
+                    tracing::debug!(dir = %dir.display(), "watching directory");
+                    while let Some(event) = receiver.next().await {
+                        let _ = output.send(event).await;
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(dir = %dir.display(), %error, "cannot watch directory");
+                    let _ = output.send(Event::Unavailable).await;
+                }
+            }
+
+            std::future::pending().await
+        },
+    )
+}
+
+/// Gathers what `changed` reports for an `INTERVAL` from the first change,
+/// then sends it as one event; until the watcher is gone.
+fn coalesce(
+    dir: PathBuf,
+    changed: std_mpsc::Receiver<Vec<PathBuf>>,
+    sender: mpsc::UnboundedSender<Event>,
+) {
+    let mut total = 0;
+    let mut batches = 0;
+    while let Ok(first) = changed.recv() {
+        let deadline = Instant::now() + INTERVAL;
+        let mut events = 1;
+        let mut seen = HashSet::new();
+        let mut paths = Vec::new();
+        let mut merge = |changes: Vec<PathBuf>| {
+            for path in changes {
+                if seen.insert(path.clone()) {
+                    paths.push(path);
+                }
+            }
+        };
+        merge(first);
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match changed.recv_timeout(left) {
+                Ok(changes) => {
+                    events += 1;
+                    merge(changes);
+                }
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        total += events;
+        batches += 1;
+        tracing::debug!(
+            dir = %dir.display(),
+            events,
+            paths = paths.len(),
+            total,
+            batches,
+            "watcher changes coalesced"
+        );
+        if sender.unbounded_send(Event::Changed(paths)).is_err() {
+            return;
+        }
+    }
+}
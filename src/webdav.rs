@@ -0,0 +1,642 @@
+//! Folders on WebDAV servers such as Nextcloud and ownCloud, browsed as
+//! `davs://host/path`, or `dav://` without TLS. The system's `curl` makes
+//! the requests, signing in with the account from the settings whose
+//! address the folder is under. Listings and transfers go through
+//! [`VirtualFs`], so jobs copy, move and delete here as on a disk.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use crate::dirs;
+use crate::normalize;
+use crate::vfs::{Metadata, Source, VirtualFs};
+
+pub const SCHEME: &str = "dav://";
+pub const SECURE_SCHEME: &str = "davs://";
+
+/// Asks for what a listing shows.
+const PROPFIND: &str = concat!(
+    r#"<?xml version="1.0" encoding="utf-8"?>"#,
+    r#"<d:propfind xmlns:d="DAV:"><d:prop>"#,
+    r#"<d:resourcetype/><d:getcontentlength/><d:getlastmodified/>"#,
+    r#"</d:prop></d:propfind>"#,
+);
+
+static ACCOUNTS: Mutex<Vec<Account>> = Mutex::new(Vec::new());
+static BACKEND: OnceLock<WebDav> = OnceLock::new();
+
+pub fn is_webdav(path: &Path) -> bool {
+    path.to_str().is_some_and(is_url)
+}
+
+pub fn is_url(text: &str) -> bool {
+    text.starts_with(SCHEME) || text.starts_with(SECURE_SCHEME)
+}
+
+/// Where to sign in, as set in the settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// The `http://` or `https://` address of the top folder, such as
+    /// Nextcloud's `https://host/remote.php/dav/files/name/`.
+    pub url: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl Account {
+    /// The top folder, as a path to browse; `None` for an address that is
+    /// not HTTP.
+    pub fn root(&self) -> Option<PathBuf> {
+        let url = self.url.trim().trim_end_matches('/');
+        let (scheme, rest) = match (url.strip_prefix("https://"), url.strip_prefix("http://")) {
+            (Some(rest), _) => (SECURE_SCHEME, rest),
+            (_, Some(rest)) => (SCHEME, rest),
+            _ => return None,
+        };
+        (!rest.is_empty()).then(|| PathBuf::from(format!("{}{}", scheme, decode(rest))))
+    }
+}
+
+/// The accounts requests sign in with from now on.
+pub fn configure(accounts: &[Account]) {
+    *ACCOUNTS.lock().unwrap_or_else(PoisonError::into_inner) = accounts.to_vec();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    secure: bool,
+    /// `host[:port]`.
+    authority: String,
+    /// Absolute on the server, not percent-encoded.
+    path: String,
+}
+
+impl Location {
+    pub fn parse(path: &Path) -> Option<Location> {
+        // Joined on Windows with backslashes.
+        let text = path.to_str()?.replace('\\', "/");
+        let (secure, rest) = match (text.strip_prefix(SECURE_SCHEME), text.strip_prefix(SCHEME)) {
+            (Some(rest), _) => (true, rest),
+            (_, Some(rest)) => (false, rest),
+            _ => return None,
+        };
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        if authority.is_empty() {
+            return None;
+        }
+
+        Some(Location {
+            secure,
+            authority: authority.to_string(),
+            path: normalize::remote_path(path),
+        })
+    }
+
+    pub fn authority(&self) -> &str {
+        &self.authority
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The top of the server, as a path to browse.
+    pub fn root(&self) -> PathBuf {
+        let scheme = if self.secure { SECURE_SCHEME } else { SCHEME };
+        PathBuf::from(format!("{}{}", scheme, self.authority))
+    }
+
+    fn browsed(&self) -> String {
+        format!("{}{}", self.root().to_string_lossy(), self.path)
+    }
+
+    fn url(&self) -> String {
+        let scheme = if self.secure { "https://" } else { "http://" };
+        format!("{}{}{}", scheme, self.authority, encode(&self.path))
+    }
+
+    /// The account with the longest address this is under.
+    fn account(&self) -> Option<Account> {
+        let browsed = self.browsed();
+        ACCOUNTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter_map(|account| Some((account.root()?, account)))
+            .filter(|(root, _)| {
+                let root = root.to_string_lossy();
+                browsed == root || browsed.starts_with(&format!("{}/", root))
+            })
+            .max_by_key(|(root, _)| root.as_os_str().len())
+            .map(|(_, account)| account.clone())
+    }
+}
+
+/// The backend `vfs::for_path` hands out for WebDAV paths.
+pub fn backend() -> &'static WebDav {
+    BACKEND.get_or_init(|| WebDav {
+        known: Mutex::new(HashMap::new()),
+    })
+}
+
+pub struct WebDav {
+    /// What the last listings said of each entry, so walking a tree asks
+    /// the server once per folder rather than once per file.
+    known: Mutex<HashMap<PathBuf, Metadata>>,
+}
+
+impl WebDav {
+    /// Drops what is known of `path` and everything below it.
+    fn forget(&self, path: &Path) {
+        self.known
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|known, _| !known.starts_with(path));
+    }
+
+    /// Sends `method` to `to`'s address for `from`, as COPY and MOVE do.
+    fn transfer(&self, method: &str, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (location(from)?, location(to)?);
+        if from.root() != to.root() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "they are on different servers",
+            ));
+        }
+        let mut command = curl(&from)?;
+        command
+            .args(["-X", method])
+            .arg("-H")
+            .arg(format!("Destination: {}", to.url()))
+            .args(["-H", "Overwrite: F"])
+            .arg(from.url());
+        run(command).map(drop)
+    }
+}
+
+impl VirtualFs for WebDav {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<io::Result<PathBuf>>> {
+        let location = location(dir)?;
+        let responses = propfind(&location, "1")?;
+        let mut known = self.known.lock().unwrap_or_else(PoisonError::into_inner);
+        known.retain(|known, _| known.parent() != Some(dir));
+        let mut paths = Vec::with_capacity(responses.len());
+        for (path, metadata) in responses {
+            // The folder itself comes first.
+            let Some(name) = path
+                .strip_prefix(location.path.trim_end_matches('/'))
+                .and_then(|name| name.strip_prefix('/'))
+                .filter(|name| !name.is_empty() && !name.contains('/'))
+            else {
+                continue;
+            };
+            let path = dir.join(name);
+            known.insert(path.clone(), metadata);
+            paths.push(Ok(path));
+        }
+
+        Ok(paths)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if let Some(metadata) = self
+            .known
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(path)
+        {
+            return Ok(*metadata);
+        }
+        let (_, metadata) = propfind(&location(path)?, "0")?
+            .into_iter()
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not on the server"))?;
+        self.known
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(path.to_path_buf(), metadata);
+
+        Ok(metadata)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let len = self.metadata(from)?.len;
+        self.transfer("COPY", from, to)?;
+        self.forget(to);
+
+        Ok(len)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.transfer("MOVE", from, to)?;
+        self.forget(from);
+        self.forget(to);
+
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> io::Result<()> {
+        let location = location(path)?;
+        let mut command = curl(&location)?;
+        command.args(["-X", "DELETE"]).arg(location.url());
+        run(command)?;
+        self.forget(path);
+
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let location = location(path)?;
+        let mut command = curl(&location)?;
+        command.args(["-X", "MKCOL"]).arg(location.url());
+        run(command)?;
+        self.forget(path);
+
+        Ok(())
+    }
+
+    fn open_read(&self, path: &Path) -> io::Result<Box<dyn Source>> {
+        let location = location(path)?;
+        let mut command = curl(&location)?;
+        let mut child = command.arg(location.url()).stdout(Stdio::piped()).spawn()?;
+        let stdout = child.stdout.take().expect("piped");
+
+        Ok(Box::new(Download {
+            child: Some(child),
+            stdout,
+            position: 0,
+        }))
+    }
+
+    fn open_write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let location = location(path)?;
+        self.forget(path);
+        let mut command = curl(&location)?;
+        let mut child = command
+            .args(["-T", "-"])
+            .arg(location.url())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+
+        Ok(Box::new(Upload {
+            child: Some(child),
+            stdin,
+        }))
+    }
+}
+
+/// A file coming from the server as it is read. It only seeks forward, by
+/// reading past what is skipped.
+struct Download {
+    /// Until it has all been read.
+    child: Option<Child>,
+    stdout: ChildStdout,
+    position: u64,
+}
+
+impl Read for Download {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.stdout.read(buffer)?;
+        self.position += read as u64;
+        // Only a finished download says whether it all came.
+        if read == 0 && !buffer.is_empty() {
+            if let Some(child) = self.child.take() {
+                let output = child.wait_with_output()?;
+                check(output.status, &output.stderr)?;
+            }
+        }
+
+        Ok(read)
+    }
+}
+
+impl Seek for Download {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let target = match position {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        };
+        match target {
+            Some(target) if target >= self.position => {
+                let skip = target - self.position;
+                io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+                Ok(self.position)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "downloads cannot seek back",
+            )),
+        }
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// A file going to the server as it is written. Flushing finishes the
+/// upload, telling whether it went through; nothing can be written after.
+struct Upload {
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+}
+
+impl Write for Upload {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.write(buffer),
+            None => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the upload is finished",
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        drop(self.stdin.take());
+        match self.child.take() {
+            Some(child) => {
+                let output = child.wait_with_output()?;
+                check(output.status, &output.stderr)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Upload {
+    /// Left unflushed, the upload was given up on.
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+fn location(path: &Path) -> io::Result<Location> {
+    Location::parse(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a WebDAV folder", path.display()),
+        )
+    })
+}
+
+/// `curl` set up for `location`, signed in with its account if it has one.
+fn curl(location: &Location) -> io::Result<Command> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--fail", "--globoff"])
+        .args(["--connect-timeout", "15"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    if let Some(account) = location.account() {
+        command.arg("--config").arg(credentials(&account)?);
+    }
+
+    Ok(command)
+}
+
+/// A `curl` config file naming the account's user and password, so they
+/// stay off the command line where other users could see them.
+fn credentials(account: &Account) -> io::Result<PathBuf> {
+    let dir = dirs::runtime_dir("iced-fm-dav");
+    dirs::make_private(&dir)?;
+    let mut hasher = DefaultHasher::new();
+    (&account.url, &account.user).hash(&mut hasher);
+    let file = dir.join(format!("{:016x}.conf", hasher.finish()));
+    let quoted: String = format!("{}:{}", account.user, account.password)
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '\\' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect();
+    fs::write(&file, format!("user = \"{}\"\n", quoted))?;
+    dirs::restrict(&file, 0o600)?;
+
+    Ok(file)
+}
+
+/// Runs `command`, giving what it printed.
+fn run(mut command: Command) -> io::Result<Vec<u8>> {
+    let output = command.stdout(Stdio::piped()).output()?;
+    check(output.status, &output.stderr)?;
+
+    Ok(output.stdout)
+}
+
+fn check(status: ExitStatus, stderr: &[u8]) -> io::Result<()> {
+    if status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(stderr);
+    let line = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("curl failed")
+        .trim();
+    tracing::debug!(%status, %stderr, "WebDAV request failed");
+    // "curl: (22) The requested URL returned error: 404"
+    let message = line.split_once(") ").map_or(line, |(_, message)| message);
+    let code = message
+        .rsplit_once("error: ")
+        .and_then(|(_, code)| code.get(..3)?.parse::<u16>().ok());
+    let (kind, message) = match code {
+        Some(401 | 403) => (
+            io::ErrorKind::PermissionDenied,
+            "the server turned down the sign-in; check the account in the settings",
+        ),
+        Some(404) => (io::ErrorKind::NotFound, "it is not on the server"),
+        Some(405 | 412) => (io::ErrorKind::AlreadyExists, "it is already there"),
+        _ => (io::ErrorKind::Other, message),
+    };
+
+    Err(io::Error::new(kind, message))
+}
+
+/// The entries a PROPFIND of `location` at `depth` gives, by their decoded
+/// paths on the server.
+fn propfind(location: &Location, depth: &str) -> io::Result<Vec<(String, Metadata)>> {
+    let mut command = curl(location)?;
+    command
+        .args(["-X", "PROPFIND"])
+        .arg("-H")
+        .arg(format!("Depth: {}", depth))
+        .args(["-H", "Content-Type: application/xml; charset=utf-8"])
+        .args(["--data-binary", PROPFIND])
+        .arg(location.url());
+    let body = run(command)?;
+
+    parse_multistatus(&String::from_utf8_lossy(&body))
+}
+
+fn parse_multistatus(xml: &str) -> io::Result<Vec<(String, Metadata)>> {
+    let document = roxmltree::Document::parse(xml)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    let dav = |node: &roxmltree::Node, name: &str| {
+        node.is_element()
+            && node.tag_name().name() == name
+            && node.tag_name().namespace() == Some("DAV:")
+    };
+    fn child<'a, 'input>(
+        node: roxmltree::Node<'a, 'input>,
+        name: &str,
+    ) -> Option<roxmltree::Node<'a, 'input>> {
+        node.children().find(|child| {
+            child.is_element()
+                && child.tag_name().name() == name
+                && child.tag_name().namespace() == Some("DAV:")
+        })
+    }
+
+    let mut entries = Vec::new();
+    for response in document.descendants().filter(|node| dav(node, "response")) {
+        let Some(href) = child(response, "href").and_then(|href| href.text()) else {
+            continue;
+        };
+        let mut metadata = Metadata {
+            is_dir: false,
+            is_symlink: false,
+            len: 0,
+            modified: None,
+            mode: None,
+        };
+        // Properties the server lacks come back under a status of their own.
+        let found = response
+            .children()
+            .filter(|node| dav(node, "propstat"))
+            .filter(|propstat| {
+                child(*propstat, "status")
+                    .and_then(|status| status.text())
+                    .is_some_and(|status| status.contains(" 200 "))
+            });
+        for prop in found.filter_map(|propstat| child(propstat, "prop")) {
+            if let Some(kind) = child(prop, "resourcetype") {
+                metadata.is_dir = child(kind, "collection").is_some();
+            }
+            if let Some(len) = child(prop, "getcontentlength").and_then(|len| len.text()) {
+                metadata.len = len.trim().parse().unwrap_or(0);
+            }
+            if let Some(date) = child(prop, "getlastmodified").and_then(|date| date.text()) {
+                metadata.modified = http_date(date);
+            }
+        }
+        // Either a whole URL or a path on the server.
+        let href = href.trim();
+        let path = match href.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+            None => href,
+        };
+        entries.push((decode(path).trim_end_matches('/').to_string(), metadata));
+    }
+
+    Ok(entries)
+}
+
+/// Percent-encoded for a URL, leaving the slashes between names.
+pub fn encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+pub fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// An HTTP date such as `Sun, 06 Nov 1994 08:49:37 GMT`, which servers
+/// give the last modification in.
+fn http_date(date: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let mut parts = date.split_whitespace().skip(1);
+    let day: i64 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|known| *known == month)? as i64 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut clock = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (clock.next()??, clock.next()??, clock.next()??);
+
+    // Days since 1970 in the proleptic Gregorian calendar.
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let of_era = year - era * 400;
+    let of_year = (153 * month + 2) / 5 + day - 1;
+    let days = era * 146_097 + of_era * 365 + of_era / 4 - of_era / 100 + of_year - 719_468;
+
+    let seconds = u64::try_from(days).ok()? * 86_400 + hours * 3_600 + minutes * 60 + seconds;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_http_dates() {
+        let at = |seconds| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(http_date("Sun, 06 Nov 1994 08:49:37 GMT"), at(784_111_777));
+        assert_eq!(http_date("Thu, 01 Jan 1970 00:00:00 GMT"), at(0));
+        assert_eq!(
+            http_date("Thu, 29 Feb 2024 12:00:00 GMT"),
+            at(1_709_208_000)
+        );
+        assert_eq!(http_date("yesterday"), None);
+        assert_eq!(http_date("Sun, 06 Nov 1994 08:49 GMT"), None);
+    }
+}